@@ -35,6 +35,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         #[cfg(feature = "websocket")]
         websocket: Default::default(),
         auth_method: AuthMethod::Token, // Uses RSA + JWT token authentication
+        credential_id: None,
+        command_encryption: Default::default(),
+        reuse_token: false,
     };
 
     match create_client(&config_token, &credentials).await {
@@ -52,6 +55,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n2️⃣  Creating client with Basic Authentication (legacy mode)");
     let config_basic = LoxoneConfig {
         auth_method: AuthMethod::Basic, // Uses HTTP Basic Auth
+        credential_id: None,
         ..config_token
     };
 
@@ -246,6 +246,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         #[cfg(feature = "websocket")]
         websocket: Default::default(),
         auth_method: AuthMethod::Token,
+        credential_id: None,
+        command_encryption: Default::default(),
+        reuse_token: false,
     };
 
     let credentials = LoxoneCredentials {
@@ -86,6 +86,7 @@ impl LoxoneClient for MockLoxoneClient {
             controls,
             cats: HashMap::new(),
             global_states: HashMap::new(),
+            operating_modes: HashMap::new(),
         })
     }
 
@@ -32,6 +32,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         #[cfg(feature = "websocket")]
         websocket: Default::default(),
         auth_method: AuthMethod::Basic, // For demo compatibility
+        credential_id: None,
+        command_encryption: Default::default(),
+        reuse_token: false,
     };
 
     let credentials = LoxoneCredentials {
@@ -0,0 +1,138 @@
+//! JSON lines audit backend
+//!
+//! One [`AuditEntry`] per line, appended in order. Queries scan the file;
+//! retention rewrites it without the expired entries.
+
+use super::{AuditBackend, AuditEntry, AuditFilter};
+use crate::error::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Audit entries in a JSON lines file
+#[derive(Debug)]
+pub struct JsonlAuditBackend {
+    path: PathBuf,
+    /// Serializes appends with the rewrite during purges
+    write_lock: Mutex<()>,
+}
+
+impl JsonlAuditBackend {
+    /// Use the file at `path`, creating its directory if needed
+    pub async fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            fs::create_dir_all(dir).await?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    /// All readable entries in file order
+    async fn read_entries(&self) -> Result<Vec<AuditEntry>> {
+        let contents = match fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Skipping unreadable audit line: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl AuditBackend for JsonlAuditBackend {
+    async fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().await;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        let mut entries = self.read_entries().await?;
+        entries.reverse();
+        entries.retain(|entry| filter.matches(entry));
+        entries.truncate(filter.limit());
+        Ok(entries)
+    }
+
+    async fn purge_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let _guard = self.write_lock.lock().await;
+        let entries = self.read_entries().await?;
+        let (kept, expired): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|entry| entry.timestamp >= cutoff);
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        let mut contents = String::new();
+        for entry in &kept {
+            contents.push_str(&serde_json::to_string(entry)?);
+            contents.push('\n');
+        }
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, contents).await?;
+        fs::rename(&tmp, &self.path).await?;
+        Ok(expired.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_jsonl_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("audit.jsonl");
+        let backend = JsonlAuditBackend::open(&path).await.unwrap();
+        assert!(
+            backend
+                .query(&AuditFilter::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        super::super::tests::check_backend(&backend).await;
+
+        // Damaged lines do not hide the rest of the log
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await
+            .unwrap();
+        file.write_all(b"{not json\n").await.unwrap();
+        assert_eq!(
+            backend.query(&AuditFilter::default()).await.unwrap().len(),
+            2
+        );
+    }
+}
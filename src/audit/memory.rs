@@ -0,0 +1,60 @@
+//! In-memory audit backend
+//!
+//! Entries are lost on restart; used for tests and offline servers.
+
+use super::{AuditBackend, AuditEntry, AuditFilter};
+use crate::error::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+
+/// Audit entries kept in memory
+#[derive(Debug, Default)]
+pub struct MemoryAuditBackend {
+    entries: RwLock<Vec<AuditEntry>>,
+}
+
+impl MemoryAuditBackend {
+    /// Empty backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AuditBackend for MemoryAuditBackend {
+    async fn append(&self, entry: &AuditEntry) -> Result<()> {
+        self.entries.write().await.push(entry.clone());
+        Ok(())
+    }
+
+    async fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        Ok(self
+            .entries
+            .read()
+            .await
+            .iter()
+            .rev()
+            .filter(|entry| filter.matches(entry))
+            .take(filter.limit())
+            .cloned()
+            .collect())
+    }
+
+    async fn purge_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|entry| entry.timestamp >= cutoff);
+        Ok(before - entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_backend() {
+        super::super::tests::check_backend(&MemoryAuditBackend::new()).await;
+    }
+}
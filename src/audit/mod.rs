@@ -0,0 +1,431 @@
+//! Append-only audit log of device commands
+//!
+//! Every command the server sends to the Miniserver is recorded with the tool
+//! and MCP client that triggered it, the target control, the Miniserver's
+//! response code and the round trip latency. Commands refused before sending,
+//! by validation or because the user did not approve them, are recorded with
+//! their error. The [`AuditPolicy`] decides which of these are recorded and
+//! how long they are kept. Entries go to a pluggable [`AuditBackend`]:
+//!
+//! - JSON lines file (default)
+//! - SQLite database (with "turso" feature)
+//! - In-memory (tests, offline servers)
+//!
+//! Entries older than the configured retention are purged periodically.
+
+pub mod jsonl;
+pub mod memory;
+#[cfg(feature = "turso")]
+pub mod sqlite;
+
+pub use jsonl::JsonlAuditBackend;
+pub use memory::MemoryAuditBackend;
+#[cfg(feature = "turso")]
+pub use sqlite::SqliteAuditBackend;
+
+use crate::config::{AuditBackendKind, AuditConfig};
+use crate::error::{LoxoneError, Result};
+use crate::security::policy::AuditPolicy;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How often retention is enforced while recording
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Default number of entries returned by a query
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// One command sent to the Miniserver, or refused before sending
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the command was sent or refused
+    pub timestamp: DateTime<Utc>,
+    /// MCP tool that sent the command
+    pub tool: Option<String>,
+    /// MCP client that called the tool (`clientInfo` name, transport and session)
+    ///
+    /// Self-reported by the client: the server does not authenticate MCP
+    /// clients, so there is no API key identity to record.
+    pub client: Option<String>,
+    /// Target control UUID
+    pub device_uuid: String,
+    /// Target control name at the time of the command
+    pub device_name: Option<String>,
+    /// Command as sent or attempted, e.g. `on` or `ManualPosition/40`
+    pub command: String,
+    /// Miniserver response code; `None` if no response arrived
+    pub response_code: Option<i32>,
+    /// Error if the command failed or was refused before sending
+    pub error: Option<String>,
+    /// Round trip time in milliseconds
+    pub latency_ms: u64,
+}
+
+/// Selection of audit entries; empty fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditFilter {
+    /// Only entries at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only commands sent by this tool
+    pub tool: Option<String>,
+    /// Control UUID, or part of the control name (case-insensitive)
+    pub device: Option<String>,
+    /// Only commands triggered by this client
+    pub client: Option<String>,
+    /// Maximum number of entries, newest first
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    /// Whether an entry is selected by this filter
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self
+                .tool
+                .as_ref()
+                .is_none_or(|tool| entry.tool.as_ref() == Some(tool))
+            && self
+                .client
+                .as_ref()
+                .is_none_or(|client| entry.client.as_ref() == Some(client))
+            && self.device.as_ref().is_none_or(|device| {
+                entry.device_uuid == *device
+                    || entry
+                        .device_name
+                        .as_ref()
+                        .is_some_and(|name| name.to_lowercase().contains(&device.to_lowercase()))
+            })
+    }
+
+    /// Maximum number of entries to return
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_QUERY_LIMIT)
+    }
+}
+
+/// Storage for audit entries
+#[async_trait]
+pub trait AuditBackend: Send + Sync {
+    /// Append an entry
+    async fn append(&self, entry: &AuditEntry) -> Result<()>;
+
+    /// Entries selected by the filter, newest first
+    async fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>>;
+
+    /// Remove entries older than `cutoff`; returns how many were removed
+    async fn purge_before(&self, cutoff: DateTime<Utc>) -> Result<usize>;
+}
+
+/// What happened to an audited command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOutcome {
+    /// Sent and accepted by the Miniserver
+    Sent,
+    /// Sent but failed or rejected by the Miniserver
+    Failed,
+    /// Refused before sending, by validation or consent
+    Refused,
+}
+
+/// Who triggered the commands of the current tool call
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditCaller {
+    /// MCP tool being executed
+    pub tool: Option<String>,
+    /// MCP client identity, see [`AuditEntry::client`]
+    pub client: Option<String>,
+}
+
+tokio::task_local! {
+    static CALLER: AuditCaller;
+}
+
+impl AuditCaller {
+    /// Run a future with this caller attached to the commands it sends
+    pub async fn scope<F: Future>(self, f: F) -> F::Output {
+        CALLER.scope(self, f).await
+    }
+
+    /// Caller of the current task, empty outside a tool call
+    pub fn current() -> Self {
+        CALLER.try_with(Clone::clone).unwrap_or_default()
+    }
+}
+
+/// Audit log on top of a backend, driven by an [`AuditPolicy`]
+#[derive(Clone)]
+pub struct AuditLog {
+    backend: Arc<dyn AuditBackend>,
+    policy: AuditPolicy,
+    last_purge: Arc<Mutex<Option<Instant>>>,
+}
+
+impl AuditLog {
+    /// Audit log on a backend, recording and purging as the policy says
+    pub fn new(backend: Arc<dyn AuditBackend>, policy: AuditPolicy) -> Self {
+        Self {
+            backend,
+            policy,
+            last_purge: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// In-memory audit log recording everything, without retention
+    pub fn memory() -> Self {
+        let policy = AuditPolicy {
+            retention_days: 0,
+            ..AuditPolicy::default()
+        };
+        Self::new(Arc::new(MemoryAuditBackend::new()), policy)
+    }
+
+    /// Open the configured audit log; `None` if the policy disables auditing
+    pub async fn open(config: &AuditConfig) -> Result<Option<Self>> {
+        if !config.policy.enabled {
+            return Ok(None);
+        }
+
+        let path = match &config.path {
+            Some(path) => path.clone(),
+            None => default_path(config.backend)?,
+        };
+        let backend: Arc<dyn AuditBackend> = match config.backend {
+            AuditBackendKind::Jsonl => Arc::new(JsonlAuditBackend::open(&path).await?),
+            #[cfg(feature = "turso")]
+            AuditBackendKind::Sqlite => Arc::new(SqliteAuditBackend::open(&path).await?),
+            #[cfg(not(feature = "turso"))]
+            AuditBackendKind::Sqlite => {
+                return Err(LoxoneError::config(
+                    "SQLite audit log requires the 'turso' feature",
+                ));
+            }
+        };
+        info!("Audit log: {:?} at {}", config.backend, path.display());
+
+        let log = Self::new(backend, config.policy.clone());
+        log.enforce_retention().await?;
+        Ok(Some(log))
+    }
+
+    /// Whether the policy records a command with this outcome
+    ///
+    /// `sensitive` marks commands to security devices, which
+    /// `log_sensitive_ops` records whatever their outcome.
+    pub fn records(&self, outcome: AuditOutcome, sensitive: bool) -> bool {
+        let by_outcome = match outcome {
+            AuditOutcome::Sent => self.policy.log_success,
+            AuditOutcome::Failed => self.policy.log_failures,
+            AuditOutcome::Refused => self.policy.log_denials,
+        };
+        self.policy.enabled && (by_outcome || (sensitive && self.policy.log_sensitive_ops))
+    }
+
+    /// Record an entry if the policy asks for it
+    ///
+    /// Write failures are logged and never fail the command.
+    pub async fn record(&self, entry: AuditEntry, outcome: AuditOutcome, sensitive: bool) {
+        if !self.records(outcome, sensitive) {
+            return;
+        }
+        if let Err(e) = self.backend.append(&entry).await {
+            warn!("Failed to write audit entry: {}", e);
+        }
+
+        let purge_due = {
+            let last_purge = self
+                .last_purge
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            last_purge.is_none_or(|at| at.elapsed() >= PURGE_INTERVAL)
+        };
+        if purge_due && let Err(e) = self.enforce_retention().await {
+            warn!("Failed to purge audit log: {}", e);
+        }
+    }
+
+    /// Entries selected by the filter, newest first
+    pub async fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        self.backend.query(filter).await
+    }
+
+    /// Remove entries older than the retention period
+    pub async fn enforce_retention(&self) -> Result<usize> {
+        *self
+            .last_purge
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now());
+
+        if self.policy.retention_days == 0 {
+            return Ok(0);
+        }
+        let retention = chrono::Duration::days(i64::from(self.policy.retention_days));
+        let removed = self.backend.purge_before(Utc::now() - retention).await?;
+        if removed > 0 {
+            info!("Purged {} audit entries past retention", removed);
+        }
+        Ok(removed)
+    }
+}
+
+/// `audit.jsonl` or `audit.db` in the user config directory
+fn default_path(backend: AuditBackendKind) -> Result<PathBuf> {
+    let file = match backend {
+        AuditBackendKind::Jsonl => "audit.jsonl",
+        AuditBackendKind::Sqlite => "audit.db",
+    };
+    Ok(crate::config::master_key::config_dir()
+        .map_err(|e| LoxoneError::config(e.to_string()))?
+        .join(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn entry(minutes_ago: i64, tool: &str, name: &str, command: &str) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            tool: Some(tool.to_string()),
+            client: Some("stdio".to_string()),
+            device_uuid: format!("uuid-{}", name.to_lowercase().replace(' ', "-")),
+            device_name: Some(name.to_string()),
+            command: command.to_string(),
+            response_code: Some(200),
+            error: None,
+            latency_ms: 12,
+        }
+    }
+
+    /// Shared contract of all backends
+    pub(super) async fn check_backend(backend: &dyn AuditBackend) {
+        backend
+            .append(&entry(90, "control_lights", "Ceiling Light", "on"))
+            .await
+            .unwrap();
+        backend
+            .append(&entry(30, "control_blinds", "Kitchen Blinds", "FullDown"))
+            .await
+            .unwrap();
+        backend
+            .append(&entry(5, "control_lights", "Kitchen Light", "off"))
+            .await
+            .unwrap();
+
+        let all = backend.query(&AuditFilter::default()).await.unwrap();
+        let commands: Vec<_> = all.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["off", "FullDown", "on"]);
+
+        let lights = AuditFilter {
+            tool: Some("control_lights".to_string()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let latest = backend.query(&lights).await.unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].device_name.as_deref(), Some("Kitchen Light"));
+
+        let kitchen = AuditFilter {
+            device: Some("kitchen".to_string()),
+            since: Some(Utc::now() - chrono::Duration::minutes(60)),
+            ..Default::default()
+        };
+        assert_eq!(backend.query(&kitchen).await.unwrap().len(), 2);
+
+        let by_uuid = AuditFilter {
+            device: Some("uuid-ceiling-light".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(backend.query(&by_uuid).await.unwrap()[0], all[2]);
+
+        let removed = backend
+            .purge_before(Utc::now() - chrono::Duration::minutes(60))
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert_eq!(
+            backend.query(&AuditFilter::default()).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_caller_scope() {
+        assert_eq!(AuditCaller::current(), AuditCaller::default());
+
+        let caller = AuditCaller {
+            tool: Some("control_lights".to_string()),
+            client: Some("http".to_string()),
+        };
+        let seen = caller.clone().scope(async { AuditCaller::current() }).await;
+        assert_eq!(seen, caller);
+    }
+
+    #[tokio::test]
+    async fn test_retention_is_enforced() {
+        let backend = Arc::new(MemoryAuditBackend::new());
+        let policy = AuditPolicy {
+            retention_days: 1,
+            ..AuditPolicy::default()
+        };
+        let log = AuditLog::new(backend, policy);
+        let two_days = 2 * 24 * 60;
+
+        // The first record purges; later ones wait for the purge interval
+        for minutes_ago in [1, two_days, 1] {
+            let command = if minutes_ago == 1 { "on" } else { "dim" };
+            log.record(
+                entry(minutes_ago, "control_lights", "Ceiling Light", command),
+                AuditOutcome::Sent,
+                false,
+            )
+            .await;
+        }
+        assert_eq!(log.query(&AuditFilter::default()).await.unwrap().len(), 3);
+
+        assert_eq!(log.enforce_retention().await.unwrap(), 1);
+        let remaining = log.query(&AuditFilter::default()).await.unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().all(|entry| entry.command == "on"));
+    }
+
+    #[tokio::test]
+    async fn test_policy_selects_entries() {
+        let policy = AuditPolicy {
+            log_success: false,
+            log_denials: false,
+            ..AuditPolicy::default()
+        };
+        let log = AuditLog::new(Arc::new(MemoryAuditBackend::new()), policy);
+
+        let record = |command: &str, outcome, sensitive| {
+            log.record(
+                entry(1, "control_lights", "Ceiling Light", command),
+                outcome,
+                sensitive,
+            )
+        };
+        record("on", AuditOutcome::Sent, false).await;
+        record("off", AuditOutcome::Failed, false).await;
+        record("dim", AuditOutcome::Refused, false).await;
+        record("unlock", AuditOutcome::Sent, true).await;
+
+        let entries = log.query(&AuditFilter::default()).await.unwrap();
+        let commands: Vec<_> = entries.iter().map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["unlock", "off"]);
+
+        let disabled = AuditLog::new(
+            Arc::new(MemoryAuditBackend::new()),
+            AuditPolicy {
+                enabled: false,
+                ..AuditPolicy::default()
+            },
+        );
+        assert!(!disabled.records(AuditOutcome::Failed, true));
+    }
+}
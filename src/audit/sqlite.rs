@@ -0,0 +1,206 @@
+//! SQLite audit backend
+//!
+//! Entries live in an `audit_log` table indexed by time. Timestamps are stored
+//! as fixed-width RFC 3339 text so they compare in chronological order.
+
+use super::{AuditBackend, AuditEntry, AuditFilter};
+use crate::error::{LoxoneError, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::{QueryBuilder, Row, Sqlite};
+use std::path::Path;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp TEXT NOT NULL,
+        tool TEXT,
+        client TEXT,
+        device_uuid TEXT NOT NULL,
+        device_name TEXT,
+        command TEXT NOT NULL,
+        response_code INTEGER,
+        error TEXT,
+        latency_ms INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS audit_log_timestamp ON audit_log (timestamp);
+";
+
+const COLUMNS: &str = "timestamp, tool, client, device_uuid, device_name, command, \
+                       response_code, error, latency_ms";
+
+/// Audit entries in a SQLite database
+#[derive(Debug)]
+pub struct SqliteAuditBackend {
+    pool: SqlitePool,
+}
+
+impl SqliteAuditBackend {
+    /// Open or create the database at `path`
+    pub async fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        Self::connect(SqlitePoolOptions::new(), options).await
+    }
+
+    /// Database that lives as long as the backend
+    pub async fn in_memory() -> Result<Self> {
+        // Every connection to `:memory:` is its own database, so keep exactly one
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None);
+        Self::connect(pool, SqliteConnectOptions::new().in_memory(true)).await
+    }
+
+    async fn connect(pool: SqlitePoolOptions, options: SqliteConnectOptions) -> Result<Self> {
+        // sqlx shares SQLite with libsql, which must configure it before first use
+        libsql::Builder::new_local(":memory:")
+            .build()
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to initialize SQLite: {e}")))?;
+
+        let pool = pool
+            .connect_with(options)
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to open audit database: {e}")))?;
+        sqlx::raw_sql(SCHEMA)
+            .execute(&pool)
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to create audit table: {e}")))?;
+        Ok(Self { pool })
+    }
+}
+
+/// Sortable timestamp text
+fn timestamp_text(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn entry_from_row(row: &SqliteRow) -> std::result::Result<AuditEntry, String> {
+    let timestamp: String = row.try_get(0).map_err(|e| e.to_string())?;
+    let latency_ms: i64 = row.try_get(8).map_err(|e| e.to_string())?;
+    let text = |idx| {
+        row.try_get::<Option<String>, _>(idx)
+            .map_err(|e| e.to_string())
+    };
+    Ok(AuditEntry {
+        timestamp: DateTime::parse_from_rfc3339(&timestamp)
+            .map_err(|e| e.to_string())?
+            .with_timezone(&Utc),
+        tool: text(1)?,
+        client: text(2)?,
+        device_uuid: row.try_get(3).map_err(|e| e.to_string())?,
+        device_name: text(4)?,
+        command: row.try_get(5).map_err(|e| e.to_string())?,
+        response_code: row.try_get(6).map_err(|e| e.to_string())?,
+        error: text(7)?,
+        latency_ms: latency_ms.max(0) as u64,
+    })
+}
+
+#[async_trait]
+impl AuditBackend for SqliteAuditBackend {
+    async fn append(&self, entry: &AuditEntry) -> Result<()> {
+        sqlx::query(&format!(
+            "INSERT INTO audit_log ({COLUMNS}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        ))
+        .bind(timestamp_text(entry.timestamp))
+        .bind(&entry.tool)
+        .bind(&entry.client)
+        .bind(&entry.device_uuid)
+        .bind(&entry.device_name)
+        .bind(&entry.command)
+        .bind(entry.response_code)
+        .bind(&entry.error)
+        .bind(i64::try_from(entry.latency_ms).unwrap_or(i64::MAX))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| LoxoneError::database(format!("Failed to write audit entry: {e}")))?;
+        Ok(())
+    }
+
+    async fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        let mut sql: QueryBuilder<Sqlite> =
+            QueryBuilder::new(format!("SELECT {COLUMNS} FROM audit_log WHERE 1 = 1"));
+        if let Some(since) = filter.since {
+            sql.push(" AND timestamp >= ")
+                .push_bind(timestamp_text(since));
+        }
+        if let Some(tool) = &filter.tool {
+            sql.push(" AND tool = ").push_bind(tool.clone());
+        }
+        if let Some(client) = &filter.client {
+            sql.push(" AND client = ").push_bind(client.clone());
+        }
+        if let Some(device) = &filter.device {
+            sql.push(" AND (device_uuid = ")
+                .push_bind(device.clone())
+                .push(" OR instr(lower(device_name), ")
+                .push_bind(device.to_lowercase())
+                .push(") > 0)");
+        }
+        sql.push(" ORDER BY id DESC LIMIT ")
+            .push_bind(i64::try_from(filter.limit()).unwrap_or(i64::MAX));
+
+        let rows = sql
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to query audit log: {e}")))?;
+        rows.iter()
+            .map(|row| {
+                entry_from_row(row)
+                    .map_err(|e| LoxoneError::database(format!("Invalid audit entry: {e}")))
+            })
+            .collect()
+    }
+
+    async fn purge_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let result = sqlx::query("DELETE FROM audit_log WHERE timestamp < ?")
+            .bind(timestamp_text(cutoff))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to purge audit log: {e}")))?;
+        Ok(result.rows_affected() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sqlite_backend() {
+        let backend = SqliteAuditBackend::in_memory().await.unwrap();
+        super::super::tests::check_backend(&backend).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_file_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.db");
+        let entry = super::super::tests::entry(1, "control_lights", "Ceiling Light", "on");
+
+        SqliteAuditBackend::open(&path)
+            .await
+            .unwrap()
+            .append(&entry)
+            .await
+            .unwrap();
+
+        let reopened = SqliteAuditBackend::open(&path).await.unwrap();
+        let entries = reopened.query(&AuditFilter::default()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].command, "on");
+        assert_eq!(entries[0].device_name.as_deref(), Some("Ceiling Light"));
+    }
+}
@@ -10,6 +10,9 @@ use std::time::Duration;
 
 const DEFAULT_URL: &str = "http://localhost:3001";
 const MCP_ENDPOINT: &str = "/mcp";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// `ask` waits for an LLM, which can take far longer than a device command
+const ASK_TIMEOUT: Duration = Duration::from_secs(180);
 
 #[derive(Parser)]
 #[command(name = "loxone-cli")]
@@ -162,6 +165,44 @@ enum Command {
         room: Option<String>,
     },
 
+    /// Send a raw command to a device (checked against its command catalog)
+    Send {
+        /// Device name or UUID
+        device: String,
+        /// Command, e.g. on, ManualPosition/40, changeTo/2
+        command: String,
+    },
+
+    /// Show the audit log of device commands
+    Audit {
+        /// Device name or UUID
+        #[arg(long)]
+        device: Option<String>,
+        /// Only commands sent by this tool
+        #[arg(long)]
+        tool: Option<String>,
+        /// RFC 3339 time or duration like 24h
+        #[arg(long)]
+        since: Option<String>,
+        /// Maximum number of entries
+        #[arg(long)]
+        limit: Option<u32>,
+    },
+
+    /// Ask in plain language, e.g. "dim the kitchen lights to 30%"
+    ///
+    /// Shows the planned commands; nothing is sent without --execute.
+    Ask {
+        /// What to do
+        request: String,
+        /// Send the planned commands instead of a dry run
+        #[arg(long)]
+        execute: bool,
+        /// LLM provider: mcp, ollama, openai or anthropic
+        #[arg(long)]
+        provider: Option<String>,
+    },
+
     // --- Low-level ---
     /// List all MCP tools
     Tools,
@@ -182,10 +223,10 @@ struct McpClient {
 }
 
 impl McpClient {
-    fn new(base_url: &str) -> Self {
+    fn new(base_url: &str, timeout: Duration) -> Self {
         Self {
             http: reqwest::Client::builder()
-                .timeout(Duration::from_secs(30))
+                .timeout(timeout)
                 .build()
                 .expect("Failed to create HTTP client"),
             base_url: base_url.trim_end_matches('/').to_string(),
@@ -432,6 +473,18 @@ fn format_output(value: &Value, raw_json: bool) {
                 return;
             }
 
+            if let Some(plan) = map.get("plan") {
+                format_plan(map, plan);
+                return;
+            }
+
+            if let Some(entries) = map.get("entries").and_then(Value::as_array) {
+                for entry in entries {
+                    format_audit_entry(entry);
+                }
+                return;
+            }
+
             // Check for common list patterns
             if let Some(items) = map
                 .get("rooms")
@@ -471,6 +524,97 @@ fn format_output(value: &Value, raw_json: bool) {
     }
 }
 
+/// Natural-language plan: the analysis, then one line per command with its outcome
+fn format_plan(map: &serde_json::Map<String, Value>, plan: &Value) {
+    let dry_run = map.get("dry_run").and_then(Value::as_bool).unwrap_or(true);
+    if let Some(analysis) = plan.get("analysis").and_then(Value::as_str) {
+        println!("{analysis}");
+    }
+    let confidence = plan
+        .get("confidence")
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0);
+    println!(
+        "{} (confidence {:.0}%):",
+        if dry_run { "Plan" } else { "Executed" },
+        confidence * 100.0
+    );
+
+    let results = map
+        .get("results")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let commands = plan
+        .get("commands")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    // Low-confidence plans come back without results, all awaiting approval
+    let unrun = if results.is_empty()
+        && map["approval_required"]
+            .as_array()
+            .is_some_and(|a| !a.is_empty())
+    {
+        "needs approval"
+    } else {
+        "not run"
+    };
+    if commands.is_empty() {
+        println!("  (no device commands)");
+    }
+    for (index, command) in commands.iter().enumerate() {
+        let field = |key: &str| command.get(key).and_then(Value::as_str).unwrap_or("");
+        let result = results.get(index);
+        let outcome = match result {
+            Some(result) if result["required_approval"].as_bool() == Some(true) => {
+                "needs approval".to_string()
+            }
+            Some(result) => result["message"].as_str().unwrap_or("-").to_string(),
+            None => unrun.to_string(),
+        };
+        println!(
+            "  {} {} {} -> {outcome}",
+            field("device"),
+            field("action"),
+            field("value")
+        );
+    }
+
+    if let Some(recommendations) = plan.get("recommendations").and_then(Value::as_array) {
+        for recommendation in recommendations {
+            if let Some(text) = recommendation.get("text").and_then(Value::as_str) {
+                println!("  tip: {text}");
+            }
+        }
+    }
+    if dry_run && !commands.is_empty() {
+        println!("Dry run - rerun with --execute to send the commands.");
+    }
+}
+
+fn format_audit_entry(entry: &Value) {
+    let field = |key: &str| entry.get(key).and_then(Value::as_str).unwrap_or("-");
+    let device = entry
+        .get("device_name")
+        .and_then(Value::as_str)
+        .unwrap_or_else(|| field("device_uuid"));
+    let outcome = match (entry.get("response_code"), entry.get("error")) {
+        (_, Some(Value::String(error))) => format!("error: {error}"),
+        (Some(Value::Number(code)), _) => code.to_string(),
+        _ => "-".to_string(),
+    };
+    let latency = entry.get("latency_ms").and_then(Value::as_u64).unwrap_or(0);
+    println!(
+        "  {} {} {} {} {} -> {outcome} ({latency} ms)",
+        field("timestamp"),
+        field("client"),
+        field("tool"),
+        device,
+        field("command")
+    );
+}
+
 fn format_item(item: &Value) {
     if let Some(obj) = item.as_object() {
         let name = obj
@@ -505,10 +649,20 @@ fn format_item(item: &Value) {
     }
 }
 
-fn parse_kv_args(args: &[String]) -> Value {
+fn parse_kv_args(args: &[String], schema: Option<&Value>) -> Value {
+    let properties = schema.and_then(|schema| schema.get("properties"));
     let mut map = serde_json::Map::new();
     for arg in args {
         if let Some((key, val)) = arg.split_once('=') {
+            // String parameters stay strings (e.g. a command like "50")
+            let declared = properties
+                .and_then(|props| props.get(key))
+                .and_then(|prop| prop.get("type"))
+                .and_then(Value::as_str);
+            if declared == Some("string") {
+                map.insert(key.to_string(), json!(val));
+                continue;
+            }
             // Try to parse as number or bool, fallback to string
             if let Ok(n) = val.parse::<f64>() {
                 map.insert(key.to_string(), json!(n));
@@ -524,6 +678,16 @@ fn parse_kv_args(args: &[String]) -> Value {
     Value::Object(map)
 }
 
+/// Input schema of a tool from a `tools/list` result
+fn tool_input_schema<'a>(tools: &'a Value, name: &str) -> Option<&'a Value> {
+    tools
+        .get("tools")?
+        .as_array()?
+        .iter()
+        .find(|tool| tool.get("name").and_then(Value::as_str) == Some(name))?
+        .get("inputSchema")
+}
+
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
@@ -537,7 +701,11 @@ async fn main() {
 async fn run(cli: Cli) -> Result<(), String> {
     ensure_server_running(&cli).await?;
 
-    let mut client = McpClient::new(&cli.url);
+    let timeout = match cli.command {
+        Command::Ask { .. } => ASK_TIMEOUT,
+        _ => REQUEST_TIMEOUT,
+    };
+    let mut client = McpClient::new(&cli.url, timeout);
     client.initialize().await?;
 
     let result = match &cli.command {
@@ -691,6 +859,33 @@ async fn run(cli: Cli) -> Result<(), String> {
                 .await?
         }
 
+        Command::Audit {
+            device,
+            tool,
+            since,
+            limit,
+        } => {
+            client
+                .call_tool(
+                    "get_audit_log",
+                    json!({ "device": device, "tool": tool, "since": since, "limit": limit }),
+                )
+                .await?
+        }
+
+        Command::Ask {
+            request,
+            execute,
+            provider,
+        } => {
+            client
+                .call_tool(
+                    "execute_natural_language",
+                    json!({ "request": request, "dry_run": !execute, "provider": provider }),
+                )
+                .await?
+        }
+
         Command::Tools => {
             let result = client.list_tools().await?;
             if cli.json {
@@ -712,8 +907,22 @@ async fn run(cli: Cli) -> Result<(), String> {
             return Ok(());
         }
 
+        Command::Send { device, command } => {
+            client
+                .call_tool(
+                    "send_device_command",
+                    json!({ "device": device, "command": command }),
+                )
+                .await?
+        }
+
         Command::Call { tool, args } => {
-            let arguments = parse_kv_args(args);
+            // Without a schema every numeric-looking value becomes a number
+            let tools = client.list_tools().await.ok();
+            let schema = tools
+                .as_ref()
+                .and_then(|tools| tool_input_schema(tools, tool));
+            let arguments = parse_kv_args(args, schema);
             client.call_tool(tool, arguments).await?
         }
     };
@@ -453,6 +453,9 @@ async fn test_connection(host: &str, port: u16, username: &str, password: &str)
         #[cfg(feature = "websocket")]
         websocket: Default::default(),
         auth_method: AuthMethod::Basic,
+        credential_id: None,
+        command_encryption: Default::default(),
+        reuse_token: false,
     };
 
     let credentials = LoxoneCredentials {
@@ -505,6 +508,9 @@ async fn test_connection_verbose(
             #[cfg(feature = "websocket")]
             websocket: Default::default(),
             auth_method: AuthMethod::Basic,
+            credential_id: None,
+            command_encryption: Default::default(),
+            reuse_token: false,
         };
 
         let credentials = LoxoneCredentials {
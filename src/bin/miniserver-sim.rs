@@ -0,0 +1,105 @@
+//! loxone-miniserver-sim — Local Miniserver simulator
+//!
+//! Serves a structure fixture over HTTP and WebSocket so the MCP server and
+//! the CLI can be developed and tested without real hardware:
+//!
+//! ```text
+//! loxone-miniserver-sim --bind 127.0.0.1:8080 --time-scale 10
+//! LOXONE_HOST=127.0.0.1:8080 LOXONE_USER=admin LOXONE_PASS=admin loxone-mcp-server http
+//! ```
+
+use clap::Parser;
+use loxone_mcp_rust::{
+    Result,
+    simulator::{MiniserverSimulator, PhysicsSettings, SimulatorConfig},
+};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::info;
+
+/// Local Loxone Miniserver simulator
+#[derive(Parser, Debug)]
+#[command(name = "loxone-miniserver-sim")]
+#[command(about = "Simulate a Loxone Miniserver for offline development and CI")]
+#[command(version = env!("CARGO_PKG_VERSION"))]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080", env = "LOXONE_SIM_BIND")]
+    bind: SocketAddr,
+
+    /// LoxAPP3.json structure fixture (defaults to the bundled demo home)
+    #[arg(long, env = "LOXONE_SIM_STRUCTURE")]
+    structure: Option<PathBuf>,
+
+    /// Username accepted by the simulator
+    #[arg(long, default_value = "admin", env = "LOXONE_SIM_USER")]
+    username: String,
+
+    /// Password accepted by the simulator
+    #[arg(long, default_value = "admin", env = "LOXONE_SIM_PASS")]
+    password: String,
+
+    /// Simulated seconds per real second (speeds up blinds and temperatures)
+    #[arg(long, default_value = "1.0")]
+    time_scale: f64,
+
+    /// Physics tick interval in milliseconds
+    #[arg(long, default_value = "250")]
+    tick_ms: u64,
+
+    /// Seconds a blind needs for a full travel
+    #[arg(long, default_value = "20")]
+    blind_travel_secs: u64,
+
+    /// Room temperature drift in °C per minute
+    #[arg(long, default_value = "0.1")]
+    temperature_rate: f64,
+
+    /// Enable debug logging
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let level = if cli.verbose { "debug" } else { "info" };
+    tracing_subscriber::fmt().with_env_filter(level).init();
+
+    let config = SimulatorConfig {
+        username: cli.username,
+        password: cli.password,
+        tick_interval: Duration::from_millis(cli.tick_ms.max(10)),
+        time_scale: cli.time_scale.max(0.0),
+        physics: PhysicsSettings {
+            blind_travel_time: Duration::from_secs(cli.blind_travel_secs.max(1)),
+            temperature_rate: cli.temperature_rate,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let simulator = match &cli.structure {
+        Some(path) => MiniserverSimulator::from_structure_file(config, path)?,
+        None => MiniserverSimulator::with_default_structure(config)?,
+    };
+
+    let listener = TcpListener::bind(cli.bind).await?;
+    info!(
+        "🏠 Simulating {} controls on http://{} (time scale {}x)",
+        simulator.control_count().await,
+        listener.local_addr()?,
+        cli.time_scale
+    );
+
+    tokio::select! {
+        result = simulator.serve(listener) => result,
+        _ = tokio::signal::ctrl_c() => {
+            info!("Shutting down simulator");
+            Ok(())
+        }
+    }
+}
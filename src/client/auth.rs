@@ -36,6 +36,19 @@ pub struct AuthToken {
     pub unsecure_pass: bool,
 }
 
+impl AuthToken {
+    /// Expiry as a Unix timestamp (`valid_until` counts from the Loxone epoch)
+    pub fn expires_at_unix(&self) -> i64 {
+        crate::client::binary_protocol::LOXONE_EPOCH_UNIX + self.valid_until
+    }
+
+    /// Time left until the token expires (zero once expired)
+    pub fn remaining(&self) -> std::time::Duration {
+        let seconds = self.expires_at_unix() - chrono::Utc::now().timestamp();
+        std::time::Duration::from_secs(seconds.max(0) as u64)
+    }
+}
+
 // OpenSSL implementation (modern, battle-tested, Send + Sync)
 #[cfg(feature = "crypto-openssl")]
 use base64::{Engine as _, engine::general_purpose};
@@ -177,10 +190,7 @@ impl LoxoneAuth {
     /// Check if current token is expired
     pub fn is_token_expired(&self) -> bool {
         match &self.token {
-            Some(token) => {
-                let now = chrono::Utc::now().timestamp();
-                now >= token.valid_until
-            }
+            Some(token) => token.remaining().is_zero(),
             None => true,
         }
     }
@@ -315,6 +325,93 @@ pub fn encrypt_credentials(public_key: &LoxonePublicKey, credentials: &str) -> R
     Ok(general_purpose::STANDARD.encode(&encrypted))
 }
 
+/// Client identifier sent with `getjwt`
+pub(crate) const JWT_CLIENT_UUID: &str = "loxone-mcp-rust";
+
+/// Client description sent with `getjwt`
+pub(crate) const JWT_CLIENT_INFO: &str = "loxone-mcp";
+
+/// Permission requested with `getjwt` (4 = long-lived app token)
+pub(crate) const JWT_PERMISSION: &str = "4";
+
+#[cfg(feature = "crypto-openssl")]
+fn message_digest(hash_alg: &str) -> openssl::hash::MessageDigest {
+    if hash_alg == "SHA256" {
+        openssl::hash::MessageDigest::sha256()
+    } else {
+        openssl::hash::MessageDigest::sha1()
+    }
+}
+
+/// Hex HMAC of `data`, keyed with a hex key from `getkey` or `getkey2`
+#[cfg(feature = "crypto-openssl")]
+pub fn hmac_hex(key_hex: &str, data: &str, hash_alg: &str) -> Result<String> {
+    use openssl::sign::Signer;
+
+    let key_bytes = hex::decode(key_hex)
+        .map_err(|e| LoxoneError::crypto(format!("Failed to decode key: {e}")))?;
+    let pkey = PKey::hmac(&key_bytes)
+        .map_err(|e| LoxoneError::crypto(format!("Failed to create HMAC key: {e}")))?;
+    let mut signer = Signer::new(message_digest(hash_alg), &pkey)
+        .map_err(|e| LoxoneError::crypto(format!("Failed to create signer: {e}")))?;
+    signer
+        .update(data.as_bytes())
+        .map_err(|e| LoxoneError::crypto(format!("Failed to update signer: {e}")))?;
+    let mac = signer
+        .sign_to_vec()
+        .map_err(|e| LoxoneError::crypto(format!("Failed to sign: {e}")))?;
+    Ok(hex::encode(mac))
+}
+
+/// Uppercase hex hash of `{password}:{salt}`, as `getjwt` expects it
+#[cfg(feature = "crypto-openssl")]
+pub fn password_hash(password: &str, salt: &str, hash_alg: &str) -> Result<String> {
+    let digest = openssl::hash::hash(
+        message_digest(hash_alg),
+        format!("{password}:{salt}").as_bytes(),
+    )
+    .map_err(|e| LoxoneError::crypto(format!("Failed to hash password: {e}")))?;
+    Ok(hex::encode_upper(digest))
+}
+
+#[cfg(feature = "crypto-openssl")]
+impl AuthToken {
+    /// Parse the `value` object of a `getjwt` response
+    pub fn from_response(value: &serde_json::Value) -> Result<Self> {
+        let token_obj = value
+            .as_object()
+            .ok_or_else(|| LoxoneError::authentication("Invalid token response format"))?;
+
+        Ok(Self {
+            token: token_obj["token"]
+                .as_str()
+                .ok_or_else(|| LoxoneError::authentication("No token in response"))?
+                .to_string(),
+            key: token_obj
+                .get("key")
+                .and_then(|k| k.as_str())
+                .unwrap_or("")
+                .to_string(),
+            salt: token_obj
+                .get("salt")
+                .and_then(|s| s.as_str())
+                .unwrap_or("")
+                .to_string(),
+            valid_until: token_obj["validUntil"]
+                .as_i64()
+                .ok_or_else(|| LoxoneError::authentication("No validUntil in response"))?,
+            token_rights: token_obj["tokenRights"]
+                .as_i64()
+                .ok_or_else(|| LoxoneError::authentication("No tokenRights in response"))?
+                as i32,
+            unsecure_pass: token_obj
+                .get("unsecurePass")
+                .and_then(|u| u.as_bool())
+                .unwrap_or(false),
+        })
+    }
+}
+
 /// Token-based HTTP client for authenticated Loxone communication
 #[cfg(feature = "crypto-openssl")]
 pub struct TokenAuthClient {
@@ -326,6 +423,8 @@ pub struct TokenAuthClient {
     auth: LoxoneAuth,
     /// Username for authentication
     username: String,
+    /// Hash algorithm announced by `getkey2` (SHA1 or SHA256)
+    hash_alg: String,
 }
 
 #[cfg(feature = "crypto-openssl")]
@@ -333,20 +432,34 @@ impl TokenAuthClient {
     /// Create a new token authentication client
     pub fn new(base_url: String, client: reqwest::Client) -> Self {
         Self {
-            base_url,
+            base_url: base_url.trim_end_matches('/').to_string(),
             client,
             auth: LoxoneAuth::new(),
             username: String::new(),
+            hash_alg: "SHA1".to_string(),
         }
     }
 
+    /// Restore a previously issued token (e.g. from the token cache)
+    pub fn restore(&mut self, cached: CachedToken) {
+        self.username = cached.username;
+        self.hash_alg = cached.hash_alg;
+        self.auth.set_token(cached.token);
+    }
+
+    /// Current token together with what is needed to refresh or kill it
+    pub fn snapshot(&self) -> Option<CachedToken> {
+        self.auth.get_token().map(|token| CachedToken {
+            username: self.username.clone(),
+            hash_alg: self.hash_alg.clone(),
+            token: token.clone(),
+        })
+    }
+
     /// Authenticate with username and password using proper Loxone token flow
     pub async fn authenticate(&mut self, username: &str, password: &str) -> Result<()> {
         // Store username for later use
         self.username = username.to_string();
-        use openssl::hash::{MessageDigest, hash};
-        use openssl::pkey::PKey;
-        use openssl::sign::Signer;
 
         // Step 1: Get server public key
         let cert_url = format!("{}/jdev/sys/getPublicKey", self.base_url);
@@ -394,59 +507,30 @@ impl TokenAuthClient {
             .as_str()
             .ok_or_else(|| LoxoneError::authentication("No key in response".to_string()))?;
         let hash_alg = salt_obj["hashAlg"].as_str().unwrap_or("SHA1");
+        self.hash_alg = hash_alg.to_string();
 
         // Step 3: Create password hash (using the algorithm specified by server)
-        let pwd_salt = format!("{password}:{salt}");
-        let pwd_hash = if hash_alg == "SHA256" {
-            hash(MessageDigest::sha256(), pwd_salt.as_bytes())
-        } else {
-            hash(MessageDigest::sha1(), pwd_salt.as_bytes())
-        }
-        .map_err(|e| LoxoneError::crypto(format!("Failed to hash password: {e}")))?;
-        let pwd_hash_hex = hex::encode(pwd_hash).to_uppercase();
+        let pwd_hash_hex = password_hash(password, salt, hash_alg)?;
 
         // Note: Unlike the original documentation, the Python implementation
         // shows that we don't need to generate and encrypt a session key for JWT.
         // The HMAC is sufficient for authentication.
 
         // Step 6: Create HMAC hash using server-specified algorithm
-
         // HMAC: key from server is the key, username:password_hash is the data
-        let hmac_key_bytes = hex::decode(key)
-            .map_err(|e| LoxoneError::crypto(format!("Failed to decode key: {e}")))?;
-        let hmac_data = format!("{username}:{pwd_hash_hex}");
-
-        let pkey = PKey::hmac(&hmac_key_bytes)
-            .map_err(|e| LoxoneError::crypto(format!("Failed to create HMAC key: {e}")))?;
-        let digest = if hash_alg == "SHA256" {
-            MessageDigest::sha256()
-        } else {
-            MessageDigest::sha1()
-        };
-        let mut signer = Signer::new(digest, &pkey)
-            .map_err(|e| LoxoneError::crypto(format!("Failed to create signer: {e}")))?;
-        signer
-            .update(hmac_data.as_bytes())
-            .map_err(|e| LoxoneError::crypto(format!("Failed to update signer: {e}")))?;
-        let hmac_result = signer
-            .sign_to_vec()
-            .map_err(|e| LoxoneError::crypto(format!("Failed to sign: {e}")))?;
-        let hmac_hex = hex::encode(hmac_result).to_uppercase();
+        let hmac_hex =
+            hmac_hex(key, &format!("{username}:{pwd_hash_hex}"), hash_alg)?.to_uppercase();
 
         // Step 7: Request JWT token (not gettoken!)
-        let uuid = "loxone-mcp-rust"; // Client identifier
-        let permission = "4"; // Standard permission level
-        let client_info = "loxone-mcp"; // Client info string
-
         // Use getjwt endpoint, not gettoken
         let token_url = format!(
             "{}/jdev/sys/getjwt/{}/{}/{}/{}/{}",
             self.base_url,
             hmac_hex,
             urlencoding::encode(username),
-            permission,
-            uuid,
-            urlencoding::encode(client_info)
+            JWT_PERMISSION,
+            JWT_CLIENT_UUID,
+            urlencoding::encode(JWT_CLIENT_INFO)
         );
 
         let token_response = self
@@ -468,37 +552,7 @@ impl TokenAuthClient {
         })?;
 
         // JWT response has the token info in an object
-        let token_obj = token_data["LL"]["value"]
-            .as_object()
-            .ok_or_else(|| LoxoneError::authentication("Invalid token response format"))?;
-
-        let auth_token = AuthToken {
-            token: token_obj["token"]
-                .as_str()
-                .ok_or_else(|| LoxoneError::authentication("No token in response"))?
-                .to_string(),
-            key: token_obj
-                .get("key")
-                .and_then(|k| k.as_str())
-                .unwrap_or("")
-                .to_string(),
-            salt: token_obj
-                .get("salt")
-                .and_then(|s| s.as_str())
-                .unwrap_or("")
-                .to_string(),
-            valid_until: token_obj["validUntil"]
-                .as_i64()
-                .ok_or_else(|| LoxoneError::authentication("No validUntil in response"))?,
-            token_rights: token_obj["tokenRights"]
-                .as_i64()
-                .ok_or_else(|| LoxoneError::authentication("No tokenRights in response"))?
-                as i32,
-            unsecure_pass: token_obj
-                .get("unsecurePass")
-                .and_then(|u| u.as_bool())
-                .unwrap_or(false),
-        };
+        let auth_token = AuthToken::from_response(&token_data["LL"]["value"])?;
 
         self.auth.set_token(auth_token);
         Ok(())
@@ -561,18 +615,19 @@ impl TokenAuthClient {
         ))
     }
 
-    /// Refresh the authentication token
+    /// Refresh the authentication token via `refreshjwt`
     pub async fn refresh_token(&mut self) -> Result<()> {
-        let token = self
+        let current = self
             .auth
-            .get_token_string()
+            .get_token()
+            .cloned()
             .ok_or_else(|| LoxoneError::authentication("No token to refresh"))?;
+        let token_hash = self.token_hash(&current.token).await?;
 
-        // Call refresh endpoint
         let refresh_url = format!(
             "{}/jdev/sys/refreshjwt/{}/{}",
             self.base_url,
-            token,
+            token_hash,
             urlencoding::encode(&self.username)
         );
 
@@ -584,18 +639,22 @@ impl TokenAuthClient {
 
         // Check if refresh was successful
         if let Some(value) = data["LL"]["value"].as_object() {
-            // Update token info
+            // Firmware 10.2+ issues a new JWT; older versions extend the current one
             let auth_token = AuthToken {
-                token: self.auth.get_token().unwrap().token.clone(), // Keep same token
+                token: value
+                    .get("token")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or(&current.token)
+                    .to_string(),
                 key: value
                     .get("key")
                     .and_then(|k| k.as_str())
-                    .unwrap_or("")
+                    .unwrap_or(&current.key)
                     .to_string(),
                 salt: value
                     .get("salt")
                     .and_then(|s| s.as_str())
-                    .unwrap_or("")
+                    .unwrap_or(&current.salt)
                     .to_string(),
                 valid_until: value["validUntil"].as_i64().ok_or_else(|| {
                     LoxoneError::authentication("No validUntil in refresh response")
@@ -603,8 +662,7 @@ impl TokenAuthClient {
                 token_rights: value
                     .get("tokenRights")
                     .and_then(|t| t.as_i64())
-                    .unwrap_or(self.auth.get_token().unwrap().token_rights as i64)
-                    as i32,
+                    .unwrap_or(current.token_rights as i64) as i32,
                 unsecure_pass: value
                     .get("unsecurePass")
                     .and_then(|u| u.as_bool())
@@ -617,6 +675,62 @@ impl TokenAuthClient {
             Err(LoxoneError::authentication("Failed to refresh token"))
         }
     }
+
+    /// Revoke the token on the Miniserver via `killtoken`, freeing its slot
+    pub async fn kill_token(&mut self) -> Result<()> {
+        let token = self
+            .auth
+            .get_token_string()
+            .ok_or_else(|| LoxoneError::authentication("No token to kill"))?;
+        let token_hash = self.token_hash(&token).await?;
+
+        let kill_url = format!(
+            "{}/jdev/sys/killtoken/{}/{}",
+            self.base_url,
+            token_hash,
+            urlencoding::encode(&self.username)
+        );
+        let text = self.client.get(&kill_url).send().await?.text().await?;
+        let data: serde_json::Value = serde_json::from_str(&text)?;
+
+        self.auth.clear();
+        match loxone_code(&data) {
+            Some(200) | None => Ok(()),
+            Some(code) => Err(LoxoneError::authentication(format!(
+                "killtoken rejected with code {code}"
+            ))),
+        }
+    }
+
+    /// Hash a token with a one-time key from `getkey`, as required by
+    /// `refreshjwt` and `killtoken`
+    async fn token_hash(&self, token: &str) -> Result<String> {
+        let key_url = format!("{}/jdev/sys/getkey", self.base_url);
+        let text = self.client.get(&key_url).send().await?.text().await?;
+        let data: serde_json::Value = serde_json::from_str(&text)?;
+        let key = data["LL"]["value"]
+            .as_str()
+            .ok_or_else(|| LoxoneError::authentication("No key in getkey response"))?;
+        hmac_hex(key, token, &self.hash_alg)
+    }
+}
+
+/// Token state persisted between runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedToken {
+    /// User the token was issued to
+    pub username: String,
+    /// Hash algorithm used for token hashing
+    pub hash_alg: String,
+    /// The issued token
+    pub token: AuthToken,
+}
+
+/// Extract the numeric `Code` from an `LL` response (sent as string or number)
+fn loxone_code(data: &serde_json::Value) -> Option<i64> {
+    let code = data["LL"].get("Code").or_else(|| data["LL"].get("code"))?;
+    code.as_i64()
+        .or_else(|| code.as_str().and_then(|c| c.parse().ok()))
 }
 
 /// Fallback authentication manager for non-crypto builds
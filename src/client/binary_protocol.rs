@@ -0,0 +1,950 @@
+//! Loxone WebSocket binary message framing and event-table decoding
+//!
+//! The Miniserver announces every WebSocket payload with an 8-byte message
+//! header frame, followed by the payload itself in a separate frame:
+//!
+//! ```text
+//! +--------+------------+------+----------+---------------------+
+//! | 0x03   | identifier | info | reserved | payload length (LE) |
+//! | 1 byte | 1 byte     | 1 b. | 1 byte   | 4 bytes             |
+//! +--------+------------+------+----------+---------------------+
+//! ```
+//!
+//! When the most significant bit of the info byte ("estimated") is set, the
+//! length is only an estimate and a second header with the exact length is
+//! sent before the payload. Keep-alive responses and out-of-service
+//! indicators consist of the header alone.
+//!
+//! Event tables are sequences of fixed or length-prefixed entries keyed by
+//! 16-byte Loxone UUIDs (little-endian `Data1`/`Data2`/`Data3` followed by
+//! 8 raw bytes):
+//!
+//! - value events: `UUID` + `f64`
+//! - text events: `UUID` + icon `UUID` + `u32` length + text padded to 4 bytes
+//! - daytimer events: `UUID` + default `f64` + `i32` count + 24-byte entries
+//! - weather events: `UUID` + `u32` last update + `i32` count + 68-byte entries
+
+use crate::error::{LoxoneError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Size of a Loxone binary message header
+pub const MESSAGE_HEADER_LEN: usize = 8;
+
+/// First byte of every Loxone binary message header
+pub const MESSAGE_HEADER_BIN_TYPE: u8 = 0x03;
+
+/// Size of a binary Loxone UUID
+pub const UUID_LEN: usize = 16;
+
+/// Info-byte flag marking an estimated payload length
+const INFO_ESTIMATED: u8 = 0x80;
+
+/// Unix timestamp of the Loxone epoch (2009-01-01T00:00:00Z)
+pub const LOXONE_EPOCH_UNIX: i64 = 1_230_768_000;
+
+const VALUE_EVENT_LEN: usize = UUID_LEN + 8;
+const DAYTIMER_ENTRY_LEN: usize = 4 * 4 + 8;
+const WEATHER_ENTRY_LEN: usize = 5 * 4 + 6 * 8;
+
+/// Message identifier carried in the second header byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageIdentifier {
+    /// Text message (JSON response), delivered as a text frame
+    Text,
+    /// Binary file (e.g. structure file or image)
+    BinaryFile,
+    /// Event table of value states
+    ValueStates,
+    /// Event table of text states
+    TextStates,
+    /// Event table of daytimer states
+    DaytimerStates,
+    /// Miniserver is going out of service (e.g. firmware update)
+    OutOfService,
+    /// Response to a `keepalive` command
+    Keepalive,
+    /// Event table of weather states
+    WeatherStates,
+    /// Identifier not defined by the protocol version we know
+    Unknown(u8),
+}
+
+impl From<u8> for MessageIdentifier {
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => MessageIdentifier::Text,
+            1 => MessageIdentifier::BinaryFile,
+            2 => MessageIdentifier::ValueStates,
+            3 => MessageIdentifier::TextStates,
+            4 => MessageIdentifier::DaytimerStates,
+            5 => MessageIdentifier::OutOfService,
+            6 => MessageIdentifier::Keepalive,
+            7 => MessageIdentifier::WeatherStates,
+            other => MessageIdentifier::Unknown(other),
+        }
+    }
+}
+
+impl From<MessageIdentifier> for u8 {
+    fn from(identifier: MessageIdentifier) -> Self {
+        match identifier {
+            MessageIdentifier::Text => 0,
+            MessageIdentifier::BinaryFile => 1,
+            MessageIdentifier::ValueStates => 2,
+            MessageIdentifier::TextStates => 3,
+            MessageIdentifier::DaytimerStates => 4,
+            MessageIdentifier::OutOfService => 5,
+            MessageIdentifier::Keepalive => 6,
+            MessageIdentifier::WeatherStates => 7,
+            MessageIdentifier::Unknown(other) => other,
+        }
+    }
+}
+
+impl MessageIdentifier {
+    /// Whether the header is followed by a payload frame
+    pub fn has_payload(&self) -> bool {
+        !matches!(
+            self,
+            MessageIdentifier::OutOfService | MessageIdentifier::Keepalive
+        )
+    }
+}
+
+/// Parsed 8-byte message header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageHeader {
+    /// Kind of payload that follows
+    pub identifier: MessageIdentifier,
+    /// Length is an estimate; an exact header follows
+    pub estimated: bool,
+    /// Payload length in bytes
+    pub length: u32,
+}
+
+impl MessageHeader {
+    /// Parse a message header frame
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() != MESSAGE_HEADER_LEN {
+            return Err(LoxoneError::parsing_error(format!(
+                "Binary message header must be {MESSAGE_HEADER_LEN} bytes, got {}",
+                data.len()
+            )));
+        }
+        if data[0] != MESSAGE_HEADER_BIN_TYPE {
+            return Err(LoxoneError::parsing_error(format!(
+                "Invalid binary message header type 0x{:02X}",
+                data[0]
+            )));
+        }
+
+        Ok(Self {
+            identifier: MessageIdentifier::from(data[1]),
+            estimated: data[2] & INFO_ESTIMATED != 0,
+            length: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+        })
+    }
+
+    /// Header announcing an exact payload length
+    pub fn exact(identifier: MessageIdentifier, length: u32) -> Self {
+        Self {
+            identifier,
+            estimated: false,
+            length,
+        }
+    }
+
+    /// Encode the header into its 8-byte wire form
+    pub fn encode(&self) -> [u8; MESSAGE_HEADER_LEN] {
+        let length = self.length.to_le_bytes();
+        [
+            MESSAGE_HEADER_BIN_TYPE,
+            self.identifier.into(),
+            if self.estimated { INFO_ESTIMATED } else { 0 },
+            0,
+            length[0],
+            length[1],
+            length[2],
+            length[3],
+        ]
+    }
+
+    /// Check whether a frame looks like a message header
+    pub fn is_header_frame(data: &[u8]) -> bool {
+        data.len() == MESSAGE_HEADER_LEN && data[0] == MESSAGE_HEADER_BIN_TYPE
+    }
+}
+
+/// Value state event (`EvData`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValueEvent {
+    /// State UUID
+    pub uuid: String,
+    /// New value
+    pub value: f64,
+}
+
+/// Text state event (`EvDataText`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEvent {
+    /// State UUID
+    pub uuid: String,
+    /// Icon UUID (all zeros when unset)
+    pub icon_uuid: String,
+    /// New text value
+    pub text: String,
+}
+
+/// Single daytimer entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaytimerEntry {
+    /// Operating mode the entry applies to
+    pub mode: i32,
+    /// Start in minutes since midnight
+    pub from: i32,
+    /// End in minutes since midnight
+    pub to: i32,
+    /// Entry requires an activation trigger
+    pub need_activate: bool,
+    /// Value while the entry is active
+    pub value: f64,
+}
+
+/// Daytimer state event (`EvDataDaytimer`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaytimerEvent {
+    /// State UUID
+    pub uuid: String,
+    /// Value outside of any entry
+    pub default_value: f64,
+    /// Schedule entries
+    pub entries: Vec<DaytimerEntry>,
+}
+
+/// Single weather forecast entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherEntry {
+    /// Forecast time in seconds since 2009-01-01 (Loxone epoch)
+    pub timestamp: i32,
+    /// Weather type code
+    pub weather_type: i32,
+    /// Wind direction in degrees
+    pub wind_direction: i32,
+    /// Solar radiation in W/m²
+    pub solar_radiation: i32,
+    /// Relative humidity in %
+    pub relative_humidity: i32,
+    /// Temperature in °C
+    pub temperature: f64,
+    /// Perceived temperature in °C
+    pub perceived_temperature: f64,
+    /// Dew point in °C
+    pub dew_point: f64,
+    /// Precipitation in mm
+    pub precipitation: f64,
+    /// Wind speed in km/h
+    pub wind_speed: f64,
+    /// Barometric pressure in hPa
+    pub barometric_pressure: f64,
+}
+
+/// Weather state event (`EvDataWeather`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherEvent {
+    /// State UUID
+    pub uuid: String,
+    /// Last update in seconds since 2009-01-01 (Loxone epoch)
+    pub last_update: u32,
+    /// Forecast entries
+    pub entries: Vec<WeatherEntry>,
+}
+
+/// Decoded event table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "table", content = "events", rename_all = "snake_case")]
+pub enum EventTable {
+    Values(Vec<ValueEvent>),
+    Texts(Vec<TextEvent>),
+    Daytimers(Vec<DaytimerEvent>),
+    Weather(Vec<WeatherEvent>),
+}
+
+impl EventTable {
+    /// Number of events in the table
+    pub fn len(&self) -> usize {
+        match self {
+            EventTable::Values(events) => events.len(),
+            EventTable::Texts(events) => events.len(),
+            EventTable::Daytimers(events) => events.len(),
+            EventTable::Weather(events) => events.len(),
+        }
+    }
+
+    /// Whether the table contains no events
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Complete message produced by [`BinaryMessageDecoder`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinaryMessage {
+    /// Decoded event table
+    EventTable(EventTable),
+    /// Binary file payload
+    BinaryFile(Vec<u8>),
+    /// Keep-alive response
+    Keepalive,
+    /// Miniserver going out of service
+    OutOfService,
+    /// Payload for an identifier we cannot decode
+    Unknown { identifier: u8, payload: Vec<u8> },
+}
+
+/// Stateful decoder pairing header frames with their payload frames
+#[derive(Debug, Default)]
+pub struct BinaryMessageDecoder {
+    pending: Option<MessageHeader>,
+}
+
+impl BinaryMessageDecoder {
+    /// Create a decoder with no pending header
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Header waiting for its payload frame, if any
+    pub fn pending_header(&self) -> Option<&MessageHeader> {
+        self.pending.as_ref()
+    }
+
+    /// Drop any pending header (e.g. after a reconnect)
+    pub fn reset(&mut self) {
+        self.pending = None;
+    }
+
+    /// Feed a binary frame, returning a message once it is complete
+    pub fn feed_binary(&mut self, data: &[u8]) -> Result<Option<BinaryMessage>> {
+        if let Some(header) = self.pending.take()
+            && !header.estimated
+        {
+            return decode_payload(header.identifier, data).map(Some);
+        }
+
+        // Either nothing is pending or an estimated header awaits the exact one
+        let header = MessageHeader::parse(data)?;
+        if !header.identifier.has_payload() {
+            return Ok(Some(match header.identifier {
+                MessageIdentifier::Keepalive => BinaryMessage::Keepalive,
+                _ => BinaryMessage::OutOfService,
+            }));
+        }
+        if header.length == 0 && !header.estimated {
+            return decode_payload(header.identifier, &[]).map(Some);
+        }
+
+        self.pending = Some(header);
+        Ok(None)
+    }
+
+    /// Notify the decoder of a text frame; returns the header it completes
+    pub fn feed_text(&mut self) -> Option<MessageHeader> {
+        match self.pending {
+            Some(header) if header.identifier == MessageIdentifier::Text && !header.estimated => {
+                self.pending.take()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Decode a payload frame for the given identifier
+pub fn decode_payload(identifier: MessageIdentifier, payload: &[u8]) -> Result<BinaryMessage> {
+    Ok(match identifier {
+        MessageIdentifier::ValueStates => {
+            BinaryMessage::EventTable(EventTable::Values(decode_value_events(payload)?))
+        }
+        MessageIdentifier::TextStates => {
+            BinaryMessage::EventTable(EventTable::Texts(decode_text_events(payload)?))
+        }
+        MessageIdentifier::DaytimerStates => {
+            BinaryMessage::EventTable(EventTable::Daytimers(decode_daytimer_events(payload)?))
+        }
+        MessageIdentifier::WeatherStates => {
+            BinaryMessage::EventTable(EventTable::Weather(decode_weather_events(payload)?))
+        }
+        MessageIdentifier::BinaryFile => BinaryMessage::BinaryFile(payload.to_vec()),
+        MessageIdentifier::Keepalive => BinaryMessage::Keepalive,
+        MessageIdentifier::OutOfService => BinaryMessage::OutOfService,
+        MessageIdentifier::Text => BinaryMessage::Unknown {
+            identifier: 0,
+            payload: payload.to_vec(),
+        },
+        MessageIdentifier::Unknown(identifier) => BinaryMessage::Unknown {
+            identifier,
+            payload: payload.to_vec(),
+        },
+    })
+}
+
+/// Format a binary Loxone UUID as `xxxxxxxx-xxxx-xxxx-xxxxxxxxxxxxxxxx`
+pub fn format_uuid(bytes: &[u8; UUID_LEN]) -> String {
+    let data1 = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let data2 = u16::from_le_bytes([bytes[4], bytes[5]]);
+    let data3 = u16::from_le_bytes([bytes[6], bytes[7]]);
+    format!(
+        "{data1:08x}-{data2:04x}-{data3:04x}-{}",
+        hex::encode(&bytes[8..16])
+    )
+}
+
+/// Parse a `xxxxxxxx-xxxx-xxxx-xxxxxxxxxxxxxxxx` UUID into wire order
+pub fn parse_uuid(uuid: &str) -> Result<[u8; UUID_LEN]> {
+    let invalid = || LoxoneError::parsing_error(format!("Invalid Loxone UUID: {uuid}"));
+    let parts: Vec<&str> = uuid.split('-').collect();
+    let [data1, data2, data3, data4] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    if data1.len() != 8 || data2.len() != 4 || data3.len() != 4 || data4.len() != 16 {
+        return Err(invalid());
+    }
+
+    let data1 = u32::from_str_radix(data1, 16).map_err(|_| invalid())?;
+    let data2 = u16::from_str_radix(data2, 16).map_err(|_| invalid())?;
+    let data3 = u16::from_str_radix(data3, 16).map_err(|_| invalid())?;
+    let data4 = hex::decode(data4).map_err(|_| invalid())?;
+
+    let mut bytes = [0u8; UUID_LEN];
+    bytes[0..4].copy_from_slice(&data1.to_le_bytes());
+    bytes[4..6].copy_from_slice(&data2.to_le_bytes());
+    bytes[6..8].copy_from_slice(&data3.to_le_bytes());
+    bytes[8..16].copy_from_slice(&data4);
+    Ok(bytes)
+}
+
+/// Encode a value event table
+pub fn encode_value_events(events: &[ValueEvent]) -> Result<Vec<u8>> {
+    let mut payload = Vec::with_capacity(events.len() * VALUE_EVENT_LEN);
+    for event in events {
+        payload.extend_from_slice(&parse_uuid(&event.uuid)?);
+        payload.extend_from_slice(&event.value.to_le_bytes());
+    }
+    Ok(payload)
+}
+
+/// Decode a value event table
+pub fn decode_value_events(payload: &[u8]) -> Result<Vec<ValueEvent>> {
+    if !payload.len().is_multiple_of(VALUE_EVENT_LEN) {
+        return Err(LoxoneError::parsing_error(format!(
+            "Value event table length {} is not a multiple of {VALUE_EVENT_LEN}",
+            payload.len()
+        )));
+    }
+
+    let mut reader = EventReader::new(payload);
+    let mut events = Vec::with_capacity(payload.len() / VALUE_EVENT_LEN);
+    while !reader.is_empty() {
+        events.push(ValueEvent {
+            uuid: reader.uuid()?,
+            value: reader.f64()?,
+        });
+    }
+    Ok(events)
+}
+
+/// Encode a text event table, padding every text to 4 bytes
+pub fn encode_text_events(events: &[TextEvent]) -> Result<Vec<u8>> {
+    let mut payload = Vec::new();
+    for event in events {
+        payload.extend_from_slice(&parse_uuid(&event.uuid)?);
+        payload.extend_from_slice(&parse_uuid(&event.icon_uuid)?);
+        let text = event.text.as_bytes();
+        payload.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        payload.extend_from_slice(text);
+        payload.resize(payload.len() + (4 - text.len() % 4) % 4, 0);
+    }
+    Ok(payload)
+}
+
+/// Decode a text event table
+pub fn decode_text_events(payload: &[u8]) -> Result<Vec<TextEvent>> {
+    let mut reader = EventReader::new(payload);
+    let mut events = Vec::new();
+    while !reader.is_empty() {
+        let uuid = reader.uuid()?;
+        let icon_uuid = reader.uuid()?;
+        let text_len = reader.u32()? as usize;
+        let text = String::from_utf8_lossy(reader.take(text_len)?)
+            .trim_end_matches('\0')
+            .to_string();
+        // Entries are aligned to 4 bytes; the last one may omit its padding
+        let padding = (4 - text_len % 4) % 4;
+        reader.skip(padding.min(reader.remaining()));
+        events.push(TextEvent {
+            uuid,
+            icon_uuid,
+            text,
+        });
+    }
+    Ok(events)
+}
+
+/// Encode a daytimer event table
+pub fn encode_daytimer_events(events: &[DaytimerEvent]) -> Result<Vec<u8>> {
+    let mut payload = Vec::new();
+    for event in events {
+        payload.extend_from_slice(&parse_uuid(&event.uuid)?);
+        payload.extend_from_slice(&event.default_value.to_le_bytes());
+        payload.extend_from_slice(&(event.entries.len() as i32).to_le_bytes());
+        for entry in &event.entries {
+            payload.extend_from_slice(&entry.mode.to_le_bytes());
+            payload.extend_from_slice(&entry.from.to_le_bytes());
+            payload.extend_from_slice(&entry.to.to_le_bytes());
+            payload.extend_from_slice(&i32::from(entry.need_activate).to_le_bytes());
+            payload.extend_from_slice(&entry.value.to_le_bytes());
+        }
+    }
+    Ok(payload)
+}
+
+/// Decode a daytimer event table
+pub fn decode_daytimer_events(payload: &[u8]) -> Result<Vec<DaytimerEvent>> {
+    let mut reader = EventReader::new(payload);
+    let mut events = Vec::new();
+    while !reader.is_empty() {
+        let uuid = reader.uuid()?;
+        let default_value = reader.f64()?;
+        let count = reader.count(DAYTIMER_ENTRY_LEN)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            entries.push(DaytimerEntry {
+                mode: reader.i32()?,
+                from: reader.i32()?,
+                to: reader.i32()?,
+                need_activate: reader.i32()? != 0,
+                value: reader.f64()?,
+            });
+        }
+        events.push(DaytimerEvent {
+            uuid,
+            default_value,
+            entries,
+        });
+    }
+    Ok(events)
+}
+
+/// Decode a weather event table
+pub fn decode_weather_events(payload: &[u8]) -> Result<Vec<WeatherEvent>> {
+    let mut reader = EventReader::new(payload);
+    let mut events = Vec::new();
+    while !reader.is_empty() {
+        let uuid = reader.uuid()?;
+        let last_update = reader.u32()?;
+        let count = reader.count(WEATHER_ENTRY_LEN)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            entries.push(WeatherEntry {
+                timestamp: reader.i32()?,
+                weather_type: reader.i32()?,
+                wind_direction: reader.i32()?,
+                solar_radiation: reader.i32()?,
+                relative_humidity: reader.i32()?,
+                temperature: reader.f64()?,
+                perceived_temperature: reader.f64()?,
+                dew_point: reader.f64()?,
+                precipitation: reader.f64()?,
+                wind_speed: reader.f64()?,
+                barometric_pressure: reader.f64()?,
+            });
+        }
+        events.push(WeatherEvent {
+            uuid,
+            last_update,
+            entries,
+        });
+    }
+    Ok(events)
+}
+
+/// Bounds-checked little-endian reader over an event table payload
+struct EventReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> EventReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.remaining() < len {
+            return Err(LoxoneError::parsing_error(format!(
+                "Event table truncated at offset {}: need {len} bytes, {} left",
+                self.pos,
+                self.remaining()
+            )));
+        }
+        let slice = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) {
+        self.pos += len;
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn uuid(&mut self) -> Result<String> {
+        Ok(format_uuid(&self.array::<UUID_LEN>()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+
+    /// Read an entry count and make sure that many entries fit
+    fn count(&mut self, entry_len: usize) -> Result<usize> {
+        let count = self.i32()?;
+        let count = usize::try_from(count).map_err(|_| {
+            LoxoneError::parsing_error(format!("Negative event entry count {count}"))
+        })?;
+        if count.saturating_mul(entry_len) > self.remaining() {
+            return Err(LoxoneError::parsing_error(format!(
+                "Event entry count {count} exceeds remaining {} bytes",
+                self.remaining()
+            )));
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `0b734138-037d-034e-ffff403fb0c34b9e` in wire order
+    const UUID_A: [u8; 16] = [
+        0x38, 0x41, 0x73, 0x0b, 0x7d, 0x03, 0x4e, 0x03, 0xff, 0xff, 0x40, 0x3f, 0xb0, 0xc3, 0x4b,
+        0x9e,
+    ];
+    /// `1234abcd-0001-0002-0102030405060708` in wire order
+    const UUID_B: [u8; 16] = [
+        0xcd, 0xab, 0x34, 0x12, 0x01, 0x00, 0x02, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+        0x08,
+    ];
+
+    #[test]
+    fn test_format_uuid() {
+        assert_eq!(format_uuid(&UUID_A), "0b734138-037d-034e-ffff403fb0c34b9e");
+        assert_eq!(format_uuid(&UUID_B), "1234abcd-0001-0002-0102030405060708");
+    }
+
+    #[test]
+    fn test_parse_uuid_round_trip() {
+        assert_eq!(
+            parse_uuid("0b734138-037d-034e-ffff403fb0c34b9e").unwrap(),
+            UUID_A
+        );
+        assert_eq!(
+            format_uuid(&parse_uuid("1234abcd-0001-0002-0102030405060708").unwrap()),
+            "1234abcd-0001-0002-0102030405060708"
+        );
+        assert!(parse_uuid("0cd8c06b-855703-ffff-ffff000000000010").is_err());
+        assert!(parse_uuid("not-a-uuid").is_err());
+    }
+
+    #[test]
+    fn test_encode_header() {
+        let header = MessageHeader::exact(MessageIdentifier::ValueStates, 48);
+        assert_eq!(
+            header.encode(),
+            [0x03, 0x02, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(MessageHeader::parse(&header.encode()).unwrap(), header);
+    }
+
+    #[test]
+    fn test_encode_value_events_round_trip() {
+        let events = vec![
+            ValueEvent {
+                uuid: "0b734138-037d-034e-ffff403fb0c34b9e".to_string(),
+                value: 21.0,
+            },
+            ValueEvent {
+                uuid: "1234abcd-0001-0002-0102030405060708".to_string(),
+                value: 0.5,
+            },
+        ];
+
+        let payload = encode_value_events(&events).unwrap();
+        assert_eq!(payload.len(), 2 * VALUE_EVENT_LEN);
+        assert_eq!(decode_value_events(&payload).unwrap(), events);
+    }
+
+    #[test]
+    fn test_encode_text_and_daytimer_events_round_trip() {
+        let texts = vec![
+            TextEvent {
+                uuid: "0b734138-037d-034e-ffff403fb0c34b9e".to_string(),
+                icon_uuid: "00000000-0000-0000-0000000000000000".to_string(),
+                text: "Evening".to_string(),
+            },
+            TextEvent {
+                uuid: "1234abcd-0001-0002-0102030405060708".to_string(),
+                icon_uuid: "00000000-0000-0000-0000000000000000".to_string(),
+                text: "[1]".to_string(),
+            },
+        ];
+        let payload = encode_text_events(&texts).unwrap();
+        assert_eq!(payload.len() % 4, 0);
+        assert_eq!(decode_text_events(&payload).unwrap(), texts);
+
+        let daytimers = vec![DaytimerEvent {
+            uuid: "0b734138-037d-034e-ffff403fb0c34b9e".to_string(),
+            default_value: 0.0,
+            entries: vec![DaytimerEntry {
+                mode: 3,
+                from: 360,
+                to: 1320,
+                need_activate: true,
+                value: 1.0,
+            }],
+        }];
+        let payload = encode_daytimer_events(&daytimers).unwrap();
+        assert_eq!(decode_daytimer_events(&payload).unwrap(), daytimers);
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header =
+            MessageHeader::parse(&[0x03, 0x02, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(header.identifier, MessageIdentifier::ValueStates);
+        assert!(!header.estimated);
+        assert_eq!(header.length, 48);
+
+        let header =
+            MessageHeader::parse(&[0x03, 0x01, 0x80, 0x00, 0x00, 0x10, 0x02, 0x00]).unwrap();
+        assert_eq!(header.identifier, MessageIdentifier::BinaryFile);
+        assert!(header.estimated);
+        assert_eq!(header.length, 0x0002_1000);
+
+        assert!(MessageHeader::parse(&[0x04, 0x02, 0, 0, 0, 0, 0, 0]).is_err());
+        assert!(MessageHeader::parse(&[0x03, 0x02, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_decode_value_events() {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&UUID_A);
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x35, 0x40]); // 21.0
+        payload.extend_from_slice(&UUID_B);
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x3f]); // 1.0
+
+        let events = decode_value_events(&payload).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                ValueEvent {
+                    uuid: "0b734138-037d-034e-ffff403fb0c34b9e".to_string(),
+                    value: 21.0,
+                },
+                ValueEvent {
+                    uuid: "1234abcd-0001-0002-0102030405060708".to_string(),
+                    value: 1.0,
+                },
+            ]
+        );
+
+        assert!(decode_value_events(&payload[..30]).is_err());
+    }
+
+    #[test]
+    fn test_decode_text_events_with_padding() {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&UUID_A);
+        payload.extend_from_slice(&[0u8; 16]);
+        payload.extend_from_slice(&[0x05, 0x00, 0x00, 0x00]);
+        payload.extend_from_slice(b"Hallo\0\0\0");
+        payload.extend_from_slice(&UUID_B);
+        payload.extend_from_slice(&UUID_A);
+        payload.extend_from_slice(&[0x04, 0x00, 0x00, 0x00]);
+        payload.extend_from_slice(b"Open");
+
+        let events = decode_text_events(&payload).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].uuid, "0b734138-037d-034e-ffff403fb0c34b9e");
+        assert_eq!(events[0].icon_uuid, "00000000-0000-0000-0000000000000000");
+        assert_eq!(events[0].text, "Hallo");
+        assert_eq!(events[1].uuid, "1234abcd-0001-0002-0102030405060708");
+        assert_eq!(events[1].icon_uuid, "0b734138-037d-034e-ffff403fb0c34b9e");
+        assert_eq!(events[1].text, "Open");
+    }
+
+    #[test]
+    fn test_decode_daytimer_events() {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&UUID_A);
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0x40]); // 20.0
+        payload.extend_from_slice(&[0x01, 0x00, 0x00, 0x00]); // 1 entry
+        payload.extend_from_slice(&[0x03, 0x00, 0x00, 0x00]); // mode 3
+        payload.extend_from_slice(&[0x68, 0x01, 0x00, 0x00]); // from 360
+        payload.extend_from_slice(&[0x38, 0x04, 0x00, 0x00]); // to 1080
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // no activation
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x36, 0x40]); // 22.0
+
+        let events = decode_daytimer_events(&payload).unwrap();
+        assert_eq!(
+            events,
+            vec![DaytimerEvent {
+                uuid: "0b734138-037d-034e-ffff403fb0c34b9e".to_string(),
+                default_value: 20.0,
+                entries: vec![DaytimerEntry {
+                    mode: 3,
+                    from: 360,
+                    to: 1080,
+                    need_activate: false,
+                    value: 22.0,
+                }],
+            }]
+        );
+
+        // Entry count larger than the remaining payload
+        let mut truncated = payload[..28].to_vec();
+        truncated[24] = 0x02;
+        assert!(decode_daytimer_events(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_decode_weather_events() {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&UUID_B);
+        payload.extend_from_slice(&[0x10, 0x27, 0x00, 0x00]); // last update 10000
+        payload.extend_from_slice(&[0x01, 0x00, 0x00, 0x00]); // 1 entry
+        payload.extend_from_slice(&[0x20, 0x4e, 0x00, 0x00]); // timestamp 20000
+        payload.extend_from_slice(&[0x02, 0x00, 0x00, 0x00]); // weather type 2
+        payload.extend_from_slice(&[0xb4, 0x00, 0x00, 0x00]); // wind direction 180
+        payload.extend_from_slice(&[0x2c, 0x01, 0x00, 0x00]); // solar radiation 300
+        payload.extend_from_slice(&[0x41, 0x00, 0x00, 0x00]); // humidity 65
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2e, 0x40]); // 15.0
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2a, 0x40]); // 13.0
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x40]); // 8.0
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xe0, 0x3f]); // 0.5
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x28, 0x40]); // 12.0
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0xa4, 0x8f, 0x40]); // 1012.5
+
+        let events = decode_weather_events(&payload).unwrap();
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.uuid, "1234abcd-0001-0002-0102030405060708");
+        assert_eq!(event.last_update, 10000);
+        assert_eq!(
+            event.entries,
+            vec![WeatherEntry {
+                timestamp: 20000,
+                weather_type: 2,
+                wind_direction: 180,
+                solar_radiation: 300,
+                relative_humidity: 65,
+                temperature: 15.0,
+                perceived_temperature: 13.0,
+                dew_point: 8.0,
+                precipitation: 0.5,
+                wind_speed: 12.0,
+                barometric_pressure: 1012.5,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decoder_pairs_header_and_payload() {
+        let mut decoder = BinaryMessageDecoder::new();
+
+        let header = [0x03, 0x02, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00];
+        assert_eq!(decoder.feed_binary(&header).unwrap(), None);
+        assert!(decoder.pending_header().is_some());
+
+        let mut payload = UUID_A.to_vec();
+        payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x3f]);
+        let message = decoder.feed_binary(&payload).unwrap().unwrap();
+        assert_eq!(
+            message,
+            BinaryMessage::EventTable(EventTable::Values(vec![ValueEvent {
+                uuid: "0b734138-037d-034e-ffff403fb0c34b9e".to_string(),
+                value: 1.0,
+            }]))
+        );
+        assert!(decoder.pending_header().is_none());
+    }
+
+    #[test]
+    fn test_decoder_estimated_header() {
+        let mut decoder = BinaryMessageDecoder::new();
+
+        // Estimated header, exact header, then the payload
+        let estimated = [0x03, 0x01, 0x80, 0x00, 0x00, 0x10, 0x00, 0x00];
+        let exact = [0x03, 0x01, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00];
+        assert_eq!(decoder.feed_binary(&estimated).unwrap(), None);
+        assert_eq!(decoder.feed_binary(&exact).unwrap(), None);
+        assert_eq!(
+            decoder.feed_binary(b"\x03\x02\x00\x00").unwrap(),
+            Some(BinaryMessage::BinaryFile(b"\x03\x02\x00\x00".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_decoder_header_only_messages() {
+        let mut decoder = BinaryMessageDecoder::new();
+        assert_eq!(
+            decoder
+                .feed_binary(&[0x03, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])
+                .unwrap(),
+            Some(BinaryMessage::Keepalive)
+        );
+        assert_eq!(
+            decoder
+                .feed_binary(&[0x03, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])
+                .unwrap(),
+            Some(BinaryMessage::OutOfService)
+        );
+        assert!(decoder.pending_header().is_none());
+    }
+
+    #[test]
+    fn test_decoder_text_header() {
+        let mut decoder = BinaryMessageDecoder::new();
+        assert_eq!(
+            decoder
+                .feed_binary(&[0x03, 0x00, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00])
+                .unwrap(),
+            None
+        );
+        let header = decoder.feed_text().unwrap();
+        assert_eq!(header.identifier, MessageIdentifier::Text);
+        assert_eq!(header.length, 42);
+        assert!(decoder.feed_text().is_none());
+    }
+}
@@ -7,7 +7,7 @@
 use crate::client::LoxoneWebSocketClient;
 #[cfg(feature = "crypto-openssl")]
 use crate::client::TokenHttpClient;
-use crate::client::{LoxoneClient, LoxoneHttpClient};
+use crate::client::{ClientContext, LoxoneClient, LoxoneHttpClient};
 use crate::config::{AuthMethod, LoxoneConfig, credentials::LoxoneCredentials};
 use crate::error::{LoxoneError, Result};
 use async_trait::async_trait;
@@ -63,8 +63,74 @@ pub trait ClientFactory: Send + Sync {
 
     /// Get cached capabilities if available
     fn get_cached_capabilities(&self) -> Option<ServerCapabilities>;
+
+    /// Create a client and connect it, returning the backend that authenticated
+    async fn connect_client(
+        &self,
+        config: &LoxoneConfig,
+        credentials: &LoxoneCredentials,
+        preferred_method: Option<AuthMethod>,
+    ) -> Result<(Box<dyn LoxoneClient>, AuthMethod)> {
+        let (mut client, method) = self
+            .create_client(config, credentials, preferred_method)
+            .await?;
+        client.connect().await?;
+        Ok((client, method))
+    }
 }
 
+/// Build and connect the client selected by `config.auth_method`
+///
+/// Token authentication falls back to basic authentication when the Miniserver
+/// runs Gen 1 firmware (< 9) or the key exchange fails. WebSocket runs as a
+/// hybrid client and falls back to the HTTP backends.
+///
+/// The client loads the structure and records pushed states into `context`,
+/// so the caller sees them without going through the client.
+pub async fn connect_configured_client(
+    config: &LoxoneConfig,
+    credentials: &LoxoneCredentials,
+    context: Arc<ClientContext>,
+) -> Result<(Box<dyn LoxoneClient>, AuthMethod)> {
+    let factory: Box<dyn ClientFactory> = match config.auth_method {
+        AuthMethod::Basic => {
+            Box::new(StaticClientFactory::new(AuthMethod::Basic).with_context(context))
+        }
+        AuthMethod::Token => Box::new(
+            AdaptiveClientFactory::with_config(
+                DEFAULT_DISCOVERY_TIMEOUT,
+                vec![AuthMethod::Token, AuthMethod::Basic],
+            )
+            .with_context(context),
+        ),
+        #[cfg(feature = "websocket")]
+        AuthMethod::WebSocket => Box::new(
+            AdaptiveClientFactory::with_config(
+                DEFAULT_DISCOVERY_TIMEOUT,
+                vec![AuthMethod::WebSocket, AuthMethod::Token, AuthMethod::Basic],
+            )
+            .with_context(context),
+        ),
+    };
+
+    let (client, method) = factory
+        .connect_client(config, credentials, Some(config.auth_method))
+        .await?;
+
+    if method == config.auth_method {
+        info!("🔐 Negotiated {:?} backend", method);
+    } else {
+        warn!(
+            "🔐 Negotiated {:?} backend (configured {:?} was not available)",
+            method, config.auth_method
+        );
+    }
+    Ok((client, method))
+}
+
+/// Default timeout for capability discovery probes
+const DEFAULT_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Adaptive client factory that negotiates the best authentication method
 ///
 /// This factory automatically detects server capabilities and chooses the appropriate
@@ -77,6 +143,8 @@ pub struct AdaptiveClientFactory {
     discovery_timeout: Duration,
     /// Fallback chain for authentication methods
     fallback_chain: Vec<AuthMethod>,
+    /// Context handed to created clients (each gets its own when unset)
+    context: Option<Arc<ClientContext>>,
 }
 
 impl Default for AdaptiveClientFactory {
@@ -90,7 +158,7 @@ impl AdaptiveClientFactory {
     pub fn new() -> Self {
         Self {
             cached_capabilities: Arc::new(tokio::sync::RwLock::new(None)),
-            discovery_timeout: Duration::from_secs(5),
+            discovery_timeout: DEFAULT_DISCOVERY_TIMEOUT,
             fallback_chain: vec![
                 #[cfg(feature = "crypto-openssl")]
                 AuthMethod::Token,
@@ -98,6 +166,7 @@ impl AdaptiveClientFactory {
                 #[cfg(feature = "websocket")]
                 AuthMethod::WebSocket,
             ],
+            context: None,
         }
     }
 
@@ -107,9 +176,16 @@ impl AdaptiveClientFactory {
             cached_capabilities: Arc::new(tokio::sync::RwLock::new(None)),
             discovery_timeout,
             fallback_chain,
+            context: None,
         }
     }
 
+    /// Share `context` with every client this factory creates
+    pub fn with_context(mut self, context: Arc<ClientContext>) -> Self {
+        self.context = Some(context);
+        self
+    }
+
     /// Probe server for token authentication support
     async fn probe_token_auth(&self, config: &LoxoneConfig) -> bool {
         #[cfg(feature = "crypto-openssl")]
@@ -183,20 +259,29 @@ impl AdaptiveClientFactory {
         match method {
             AuthMethod::Basic => {
                 debug!("Creating basic HTTP client");
-                let client = LoxoneHttpClient::new(config.clone(), credentials.clone()).await?;
+                let mut client = LoxoneHttpClient::new(config.clone(), credentials.clone()).await?;
+                if let Some(context) = &self.context {
+                    client = client.with_context(context.clone());
+                }
                 Ok(Box::new(client))
             }
             #[cfg(feature = "crypto-openssl")]
             AuthMethod::Token => {
                 debug!("Creating token-based HTTP client");
-                let client = TokenHttpClient::new(config.clone(), credentials.clone()).await?;
+                let mut client = TokenHttpClient::new(config.clone(), credentials.clone()).await?;
+                if let Some(context) = &self.context {
+                    client = client.with_context(context.clone());
+                }
                 Ok(Box::new(client))
             }
             #[cfg(feature = "websocket")]
             AuthMethod::WebSocket => {
-                debug!("Creating WebSocket client");
-                let client =
-                    LoxoneWebSocketClient::new(config.clone(), credentials.clone()).await?;
+                debug!("Creating hybrid WebSocket client");
+                let mut client: LoxoneWebSocketClient =
+                    crate::client::create_hybrid_client(config, credentials).await?;
+                if let Some(context) = &self.context {
+                    client = client.with_context(context.clone());
+                }
                 Ok(Box::new(client))
             }
             #[allow(unreachable_patterns)]
@@ -205,16 +290,13 @@ impl AdaptiveClientFactory {
             ))),
         }
     }
-}
 
-#[async_trait]
-impl ClientFactory for AdaptiveClientFactory {
-    async fn create_client(
+    /// Order the authentication methods to try from capabilities and preference
+    async fn negotiate_methods(
         &self,
         config: &LoxoneConfig,
-        credentials: &LoxoneCredentials,
         preferred_method: Option<AuthMethod>,
-    ) -> Result<(Box<dyn LoxoneClient>, AuthMethod)> {
+    ) -> Result<Vec<AuthMethod>> {
         // Check if we have cached capabilities
         let capabilities = if let Some(caps) = self.get_cached_capabilities() {
             // Use cached capabilities if they're recent (< 5 minutes old)
@@ -274,11 +356,25 @@ impl ClientFactory for AdaptiveClientFactory {
             }
         }
 
-        // Try each method in order
+        Ok(auth_methods)
+    }
+
+    /// Try each method in order, optionally connecting, until one succeeds
+    async fn try_methods(
+        &self,
+        config: &LoxoneConfig,
+        credentials: &LoxoneCredentials,
+        auth_methods: Vec<AuthMethod>,
+        connect: bool,
+    ) -> Result<(Box<dyn LoxoneClient>, AuthMethod)> {
         let mut last_error = None;
         for method in auth_methods {
             info!("Attempting authentication with method: {:?}", method);
-            match self.try_create_client(config, credentials, method).await {
+            let attempt = match self.try_create_client(config, credentials, method).await {
+                Ok(mut client) if connect => client.connect().await.map(|()| client),
+                other => other,
+            };
+            match attempt {
                 Ok(client) => {
                     info!("Successfully created client with method: {:?}", method);
                     return Ok((client, method));
@@ -295,6 +391,31 @@ impl ClientFactory for AdaptiveClientFactory {
             LoxoneError::config("No authentication methods available or all methods failed")
         }))
     }
+}
+
+#[async_trait]
+impl ClientFactory for AdaptiveClientFactory {
+    async fn create_client(
+        &self,
+        config: &LoxoneConfig,
+        credentials: &LoxoneCredentials,
+        preferred_method: Option<AuthMethod>,
+    ) -> Result<(Box<dyn LoxoneClient>, AuthMethod)> {
+        let auth_methods = self.negotiate_methods(config, preferred_method).await?;
+        self.try_methods(config, credentials, auth_methods, false)
+            .await
+    }
+
+    async fn connect_client(
+        &self,
+        config: &LoxoneConfig,
+        credentials: &LoxoneCredentials,
+        preferred_method: Option<AuthMethod>,
+    ) -> Result<(Box<dyn LoxoneClient>, AuthMethod)> {
+        let auth_methods = self.negotiate_methods(config, preferred_method).await?;
+        self.try_methods(config, credentials, auth_methods, true)
+            .await
+    }
 
     async fn discover_capabilities(&self, config: &LoxoneConfig) -> Result<ServerCapabilities> {
         info!(
@@ -369,19 +490,33 @@ impl ClientFactory for AdaptiveClientFactory {
     }
 
     fn get_cached_capabilities(&self) -> Option<ServerCapabilities> {
-        self.cached_capabilities.blocking_read().clone()
+        // Called from async contexts, so never block on the lock
+        self.cached_capabilities
+            .try_read()
+            .ok()
+            .and_then(|caps| caps.clone())
     }
 }
 
 /// Simple factory that always creates the same client type
 pub struct StaticClientFactory {
     auth_method: AuthMethod,
+    context: Option<Arc<ClientContext>>,
 }
 
 impl StaticClientFactory {
     /// Create factory for specific auth method
     pub fn new(auth_method: AuthMethod) -> Self {
-        Self { auth_method }
+        Self {
+            auth_method,
+            context: None,
+        }
+    }
+
+    /// Share `context` with every client this factory creates
+    pub fn with_context(mut self, context: Arc<ClientContext>) -> Self {
+        self.context = Some(context);
+        self
     }
 }
 
@@ -393,7 +528,8 @@ impl ClientFactory for StaticClientFactory {
         credentials: &LoxoneCredentials,
         _preferred_method: Option<AuthMethod>,
     ) -> Result<(Box<dyn LoxoneClient>, AuthMethod)> {
-        let factory = AdaptiveClientFactory::new();
+        let mut factory = AdaptiveClientFactory::new();
+        factory.context = self.context.clone();
         let client = factory
             .try_create_client(config, credentials, self.auth_method)
             .await?;
@@ -0,0 +1,181 @@
+//! Encrypted commands for connections without TLS
+//!
+//! Wraps a [`CommandCipher`] with the RSA encrypted session key of the
+//! Miniserver's key exchange. HTTP clients send the session key with every
+//! request (`?sk=`); WebSocket clients send `jdev/sys/keyexchange/` once.
+
+use crate::client::auth::{encrypt_credentials, get_public_key_from_certificate};
+use crate::config::CommandEncryption;
+use crate::error::{LoxoneError, Result};
+use crate::security::encryption::{CommandCipher, EncryptedEndpoint};
+use std::sync::Mutex;
+use tracing::debug;
+use url::Url;
+
+/// Command encryption state of one connection
+#[derive(Debug)]
+pub struct CommandEncryptor {
+    endpoint: EncryptedEndpoint,
+    cipher: Mutex<CommandCipher>,
+    /// Base64 session key, RSA encrypted with the Miniserver's public key
+    session_key: String,
+}
+
+impl CommandEncryptor {
+    /// Set up encryption for the configured mode; `None` if it is disabled
+    pub fn new(mode: CommandEncryption, public_key_pem: &str) -> Result<Option<Self>> {
+        let endpoint = match mode {
+            CommandEncryption::None => return Ok(None),
+            CommandEncryption::Request => EncryptedEndpoint::Command,
+            CommandEncryption::Full => EncryptedEndpoint::Full,
+        };
+        Self::with_cipher(endpoint, CommandCipher::new(), public_key_pem).map(Some)
+    }
+
+    /// Set up encryption with a given cipher
+    pub fn with_cipher(
+        endpoint: EncryptedEndpoint,
+        cipher: CommandCipher,
+        public_key_pem: &str,
+    ) -> Result<Self> {
+        let public_key = get_public_key_from_certificate(public_key_pem)?;
+        let session_key = encrypt_credentials(&public_key, &cipher.session_key())?;
+        Ok(Self {
+            endpoint,
+            cipher: Mutex::new(cipher),
+            session_key,
+        })
+    }
+
+    /// Whether responses come back encrypted (`fenc`)
+    pub fn encrypts_responses(&self) -> bool {
+        self.endpoint == EncryptedEndpoint::Full
+    }
+
+    /// Key exchange command announcing the session key over WebSocket
+    pub fn keyexchange_command(&self) -> String {
+        format!(
+            "jdev/sys/keyexchange/{}",
+            urlencoding::encode(&self.session_key)
+        )
+    }
+
+    /// Encrypt a command for the WebSocket (`jdev/sys/enc/...`)
+    pub fn encrypt(&self, command: &str) -> String {
+        self.cipher
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .encrypt_command(command, self.endpoint)
+    }
+
+    /// Encrypt a command for HTTP, carrying the session key as `sk`
+    pub fn http_path(&self, command: &str) -> String {
+        format!(
+            "{}?sk={}",
+            self.encrypt(command),
+            urlencoding::encode(&self.session_key)
+        )
+    }
+
+    /// Plaintext of a response: decrypted for `fenc`, unchanged otherwise
+    ///
+    /// Errors raised before decryption (e.g. authentication) come back as
+    /// plain JSON even for `fenc` and are passed through.
+    pub fn decrypt_response(&self, body: &str) -> Result<String> {
+        let body = body.trim();
+        if !self.encrypts_responses() || body.starts_with('{') {
+            return Ok(body.to_string());
+        }
+        let cipher = self
+            .cipher
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        cipher
+            .decrypt_text(body)
+            .map_err(|e| LoxoneError::crypto(format!("Failed to decrypt response: {e}")))
+    }
+}
+
+/// Fetch the Miniserver's public key (`jdev/sys/getPublicKey`)
+pub async fn fetch_public_key(client: &reqwest::Client, base_url: &Url) -> Result<String> {
+    let url = base_url
+        .join("jdev/sys/getPublicKey")
+        .map_err(|e| LoxoneError::connection(format!("Invalid URL: {e}")))?;
+    let body: serde_json::Value = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| LoxoneError::connection(format!("Failed to fetch public key: {e}")))?
+        .json()
+        .await
+        .map_err(|e| LoxoneError::connection(format!("Invalid public key response: {e}")))?;
+    debug!("Fetched Miniserver public key for command encryption");
+    body["LL"]["value"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| LoxoneError::crypto("No public key in response"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use openssl::rsa::{Padding, Rsa};
+
+    #[test]
+    fn test_session_key_is_rsa_encrypted() {
+        let rsa = Rsa::generate(2048).unwrap();
+        let pem = String::from_utf8(rsa.public_key_to_pem().unwrap()).unwrap();
+        let cipher = CommandCipher::with_key([7; 32], [9; 16], "00ff");
+        let plain_key = cipher.session_key();
+
+        let encryptor =
+            CommandEncryptor::with_cipher(EncryptedEndpoint::Full, cipher, &pem).unwrap();
+
+        let encrypted = base64::engine::general_purpose::STANDARD
+            .decode(&encryptor.session_key)
+            .unwrap();
+        let mut decrypted = vec![0; rsa.size() as usize];
+        let len = rsa
+            .private_decrypt(&encrypted, &mut decrypted, Padding::PKCS1)
+            .unwrap();
+        assert_eq!(&decrypted[..len], plain_key.as_bytes());
+
+        assert!(
+            encryptor
+                .keyexchange_command()
+                .starts_with("jdev/sys/keyexchange/")
+        );
+        let path = encryptor.http_path("jdev/sps/io/x/on");
+        assert!(path.starts_with("jdev/sys/fenc/"));
+        assert!(path.contains("?sk="));
+    }
+
+    #[test]
+    fn test_disabled_and_plain_responses() {
+        assert!(
+            CommandEncryptor::new(CommandEncryption::None, "unused")
+                .unwrap()
+                .is_none()
+        );
+
+        let rsa = Rsa::generate(2048).unwrap();
+        let pem = String::from_utf8(rsa.public_key_to_pem().unwrap()).unwrap();
+        let cipher = CommandCipher::with_key([7; 32], [9; 16], "00ff");
+        let response = cipher.encrypt_text(r#"{"LL":{"Code":"200"}}"#);
+
+        let encryptor =
+            CommandEncryptor::with_cipher(EncryptedEndpoint::Full, cipher, &pem).unwrap();
+        assert_eq!(
+            encryptor.decrypt_response(&response).unwrap(),
+            r#"{"LL":{"Code":"200"}}"#
+        );
+        // Errors are not encrypted
+        assert_eq!(
+            encryptor
+                .decrypt_response(r#"{"LL":{"Code":"401"}}"#)
+                .unwrap(),
+            r#"{"LL":{"Code":"401"}}"#
+        );
+    }
+}
@@ -0,0 +1,988 @@
+//! Command catalog keyed by control type
+//!
+//! The Miniserver accepts free-form command strings on
+//! `jdev/sps/io/{uuid}/{command}` and either rejects a wrong one late or
+//! silently ignores it. The catalog lists the legal commands and argument
+//! ranges of each [`ControlType`] so that commands are built and validated
+//! before they are sent. Control types without an entry are passed through
+//! unvalidated.
+//!
+//! Commands take one of three shapes:
+//! - `name` or `name/arg1/arg2` (e.g. `FullUp`, `ManualPosition/40`)
+//! - `name(arg1,arg2)` (e.g. `hsv(120,100,80)` for color pickers)
+//! - a bare value (e.g. `60` for dimmers)
+
+use crate::client::controls::{ControlType, LoxoneControl};
+use crate::error::{LoxoneError, Result};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Argument of a catalog command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommandArg {
+    /// Number within an inclusive range
+    Number {
+        name: &'static str,
+        min: f64,
+        max: f64,
+    },
+    /// Integer within an inclusive range
+    Integer {
+        name: &'static str,
+        min: i64,
+        max: i64,
+    },
+    /// Number within the control's `details.min`/`details.max`
+    DetailsRange { name: &'static str },
+    /// Non-empty, URL-encoded text without path separators
+    Text { name: &'static str },
+}
+
+impl CommandArg {
+    /// Argument name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Number { name, .. }
+            | Self::Integer { name, .. }
+            | Self::DetailsRange { name }
+            | Self::Text { name } => name,
+        }
+    }
+
+    fn check(
+        &self,
+        value: &str,
+        details: Option<&Map<String, Value>>,
+    ) -> std::result::Result<(), String> {
+        let name = self.name();
+        if let Self::Text { .. } = self {
+            if value.is_empty() || value.contains(['/', ' ']) {
+                return Err(format!(
+                    "argument '{name}' must be non-empty URL-encoded text, got '{value}'"
+                ));
+            }
+            return Ok(());
+        }
+        let number = value
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| format!("argument '{name}' must be a number, got '{value}'"))?;
+
+        let (min, max) = match *self {
+            Self::Number { min, max, .. } => (min, max),
+            Self::Integer { min, max, .. } => {
+                if number.fract() != 0.0 {
+                    return Err(format!(
+                        "argument '{name}' must be an integer, got '{value}'"
+                    ));
+                }
+                (min as f64, max as f64)
+            }
+            Self::Text { .. } => unreachable!("text arguments are checked above"),
+            Self::DetailsRange { .. } => {
+                let bound = |key: &str| details.and_then(|d| d.get(key)).and_then(Value::as_f64);
+                (
+                    bound("min").unwrap_or(f64::NEG_INFINITY),
+                    bound("max").unwrap_or(f64::INFINITY),
+                )
+            }
+        };
+
+        if number < min || number > max {
+            return Err(format!(
+                "argument '{name}' must be between {min} and {max}, got {value}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CommandArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number { name, min, max } => write!(f, "{{{name}:{min}-{max}}}"),
+            Self::Integer { name, min, max } => write!(f, "{{{name}:{min}-{max}}}"),
+            Self::DetailsRange { name } => write!(f, "{{{name}:min-max}}"),
+            Self::Text { name } => write!(f, "{{{name}:text}}"),
+        }
+    }
+}
+
+/// Syntax of a catalog command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandSyntax {
+    /// `name/arg1/arg2`
+    Path,
+    /// `name(arg1,arg2)`
+    Call,
+    /// A bare value without a name
+    Value,
+}
+
+/// Legal command of a control type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommandSpec {
+    /// Command name as sent to the Miniserver (empty for bare values)
+    pub name: &'static str,
+    /// Command syntax
+    pub syntax: CommandSyntax,
+    /// Arguments, all required
+    pub args: &'static [CommandArg],
+    /// What the command does
+    pub description: &'static str,
+}
+
+impl CommandSpec {
+    const fn path(
+        name: &'static str,
+        args: &'static [CommandArg],
+        description: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            syntax: CommandSyntax::Path,
+            args,
+            description,
+        }
+    }
+
+    const fn simple(name: &'static str, description: &'static str) -> Self {
+        Self::path(name, &[], description)
+    }
+
+    const fn call(
+        name: &'static str,
+        args: &'static [CommandArg],
+        description: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            syntax: CommandSyntax::Call,
+            args,
+            description,
+        }
+    }
+
+    const fn value(args: &'static [CommandArg], description: &'static str) -> Self {
+        Self {
+            name: "",
+            syntax: CommandSyntax::Value,
+            args,
+            description,
+        }
+    }
+
+    /// Usage string, e.g. `ManualPosition/{position:0-100}`
+    pub fn usage(&self) -> String {
+        let args: Vec<String> = self.args.iter().map(ToString::to_string).collect();
+        self.format(&args)
+    }
+
+    /// Format the command with the given arguments (not validated)
+    fn format(&self, args: &[String]) -> String {
+        match self.syntax {
+            CommandSyntax::Path if args.is_empty() => self.name.to_string(),
+            CommandSyntax::Path => format!("{}/{}", self.name, args.join("/")),
+            CommandSyntax::Call => format!("{}({})", self.name, args.join(",")),
+            CommandSyntax::Value => args.join(""),
+        }
+    }
+
+    fn check_args(
+        &self,
+        args: &[&str],
+        details: Option<&Map<String, Value>>,
+    ) -> std::result::Result<(), String> {
+        if args.len() != self.args.len() {
+            return Err(format!(
+                "'{}' takes {} argument(s), got {}",
+                self.usage(),
+                self.args.len(),
+                args.len()
+            ));
+        }
+        self.args
+            .iter()
+            .zip(args)
+            .try_for_each(|(spec, value)| spec.check(value, details))
+    }
+}
+
+const PERCENT: &[CommandArg] = &[CommandArg::Number {
+    name: "percent",
+    min: 0.0,
+    max: 100.0,
+}];
+const POSITION: &[CommandArg] = &[CommandArg::Number {
+    name: "position",
+    min: 0.0,
+    max: 100.0,
+}];
+const MOOD_ID: &[CommandArg] = &[CommandArg::Integer {
+    name: "moodId",
+    min: 0,
+    max: 65535,
+}];
+const LEARN_MOOD: &[CommandArg] = &[
+    CommandArg::Integer {
+        name: "moodId",
+        min: 0,
+        max: 65535,
+    },
+    CommandArg::Text { name: "moodName" },
+];
+const MOVE_MOOD: &[CommandArg] = &[
+    CommandArg::Integer {
+        name: "moodId",
+        min: 0,
+        max: 65535,
+    },
+    CommandArg::Integer {
+        name: "position",
+        min: 0,
+        max: 65535,
+    },
+];
+const TEMPERATURE: &[CommandArg] = &[CommandArg::Number {
+    name: "temperature",
+    min: 5.0,
+    max: 40.0,
+}];
+const FLAG: &[CommandArg] = &[CommandArg::Integer {
+    name: "flag",
+    min: 0,
+    max: 1,
+}];
+const TTS: &[CommandArg] = &[CommandArg::Text { name: "text" }];
+const TTS_WITH_VOLUME: &[CommandArg] = &[
+    CommandArg::Text { name: "text" },
+    CommandArg::Number {
+        name: "volume",
+        min: 0.0,
+        max: 100.0,
+    },
+];
+
+const SWITCH: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Switch on"),
+    CommandSpec::simple("off", "Switch off"),
+    CommandSpec::simple("pulse", "Toggle or send a pulse"),
+];
+
+const DIMMER: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Switch on at the last level"),
+    CommandSpec::simple("off", "Switch off"),
+    CommandSpec::simple("plus", "Increase the level by one step"),
+    CommandSpec::simple("minus", "Decrease the level by one step"),
+    CommandSpec::value(PERCENT, "Set the level"),
+];
+
+const LIGHT_CONTROLLER: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Activate the default mood"),
+    CommandSpec::simple("off", "Switch all circuits off"),
+    CommandSpec::simple("plus", "Switch to the next mood"),
+    CommandSpec::simple("minus", "Switch to the previous mood"),
+    CommandSpec::path("changeTo", MOOD_ID, "Activate a mood"),
+];
+
+const LIGHT_CONTROLLER_V2: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Activate the default mood"),
+    CommandSpec::simple("off", "Switch all circuits off"),
+    CommandSpec::simple("plus", "Switch to the next mood"),
+    CommandSpec::simple("minus", "Switch to the previous mood"),
+    CommandSpec::path("changeTo", MOOD_ID, "Activate a mood exclusively"),
+    CommandSpec::path("addMood", MOOD_ID, "Mix a mood into the active moods"),
+    CommandSpec::path("removeMood", MOOD_ID, "Remove a mood from the active moods"),
+    CommandSpec::path(
+        "learn",
+        LEARN_MOOD,
+        "Save the current output levels as a mood (new if the ID is unused)",
+    ),
+    CommandSpec::path("delete", MOOD_ID, "Delete a mood"),
+    CommandSpec::path("addToFavoriteMood", MOOD_ID, "Add a mood to the favorites"),
+    CommandSpec::path(
+        "removeFromFavoriteMood",
+        MOOD_ID,
+        "Remove a mood from the favorites",
+    ),
+    CommandSpec::path(
+        "moveFavoriteMood",
+        MOVE_MOOD,
+        "Move a favorite mood to a position",
+    ),
+    CommandSpec::path(
+        "moveAdditionalMood",
+        MOVE_MOOD,
+        "Move a non-favorite mood to a position",
+    ),
+];
+
+const COLOR_PICKER: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Switch on"),
+    CommandSpec::simple("off", "Switch off"),
+    CommandSpec::call(
+        "hsv",
+        &[
+            CommandArg::Integer {
+                name: "hue",
+                min: 0,
+                max: 360,
+            },
+            CommandArg::Integer {
+                name: "saturation",
+                min: 0,
+                max: 100,
+            },
+            CommandArg::Integer {
+                name: "value",
+                min: 0,
+                max: 100,
+            },
+        ],
+        "Set a color",
+    ),
+    CommandSpec::call(
+        "temp",
+        &[
+            CommandArg::Integer {
+                name: "brightness",
+                min: 0,
+                max: 100,
+            },
+            CommandArg::Integer {
+                name: "kelvin",
+                min: 2700,
+                max: 6500,
+            },
+        ],
+        "Set a color temperature",
+    ),
+];
+
+const JALOUSIE: &[CommandSpec] = &[
+    CommandSpec::simple("up", "Move up while pressed"),
+    CommandSpec::simple("UpOff", "Release the up button"),
+    CommandSpec::simple("down", "Move down while pressed"),
+    CommandSpec::simple("DownOff", "Release the down button"),
+    CommandSpec::simple("FullUp", "Move fully up"),
+    CommandSpec::simple("FullDown", "Move fully down"),
+    CommandSpec::simple("shade", "Move to the shading position"),
+    CommandSpec::simple("auto", "Enable automatic shading"),
+    CommandSpec::simple("NoAuto", "Disable automatic shading"),
+    CommandSpec::simple("stop", "Stop moving"),
+    CommandSpec::path("ManualPosition", POSITION, "Move to a position (0 = up)"),
+    CommandSpec::path("ManualLamelle", POSITION, "Move the slats to a position"),
+];
+
+const CENTRAL_JALOUSIE: &[CommandSpec] = &[
+    CommandSpec::simple("FullUp", "Move all blinds fully up"),
+    CommandSpec::simple("FullDown", "Move all blinds fully down"),
+    CommandSpec::simple("shade", "Move all blinds to the shading position"),
+    CommandSpec::simple("auto", "Enable automatic shading"),
+    CommandSpec::simple("NoAuto", "Disable automatic shading"),
+    CommandSpec::simple("stop", "Stop all blinds"),
+];
+
+const GATE: &[CommandSpec] = &[
+    CommandSpec::simple("open", "Open"),
+    CommandSpec::simple("close", "Close"),
+    CommandSpec::simple("stop", "Stop moving"),
+];
+
+const DOOR_LOCK: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Lock"),
+    CommandSpec::simple("lock", "Lock"),
+    CommandSpec::simple("off", "Unlock"),
+    CommandSpec::simple("unlock", "Unlock"),
+    CommandSpec::simple("open", "Unlock and open the door"),
+];
+
+const INTERCOM: &[CommandSpec] = &[
+    CommandSpec::simple("answer", "Answer the doorbell"),
+    CommandSpec::simple("hangup", "End the call"),
+    CommandSpec::simple("talk", "Talk to the visitor"),
+    CommandSpec::simple("mute", "Mute the call"),
+    CommandSpec::path("mute", FLAG, "Mute (1) or unmute (0) the call"),
+    CommandSpec::simple("open", "Open the door"),
+];
+
+const ROOM_CONTROLLER: &[CommandSpec] = &[
+    CommandSpec::path(
+        "setmode",
+        &[CommandArg::Integer {
+            name: "mode",
+            min: 0,
+            max: 6,
+        }],
+        "Set the operating mode",
+    ),
+    CommandSpec::path(
+        "settemp",
+        &[
+            CommandArg::Integer {
+                name: "temperatureIndex",
+                min: 0,
+                max: 7,
+            },
+            CommandArg::Number {
+                name: "temperature",
+                min: 5.0,
+                max: 40.0,
+            },
+        ],
+        "Set one of the preset temperatures",
+    ),
+    CommandSpec::simple("stoptimer", "Stop a running override timer"),
+];
+
+const ROOM_CONTROLLER_V2: &[CommandSpec] = &[
+    CommandSpec::path(
+        "setComfortTemperature",
+        TEMPERATURE,
+        "Set the heating comfort temperature",
+    ),
+    CommandSpec::path(
+        "setComfortTemperatureCool",
+        TEMPERATURE,
+        "Set the cooling comfort temperature",
+    ),
+    CommandSpec::path(
+        "setComfortTolerance",
+        &[CommandArg::Number {
+            name: "tolerance",
+            min: 0.5,
+            max: 3.0,
+        }],
+        "Set the tolerance around the comfort temperature",
+    ),
+    CommandSpec::path(
+        "setAbsentMinTemperature",
+        TEMPERATURE,
+        "Set the minimum temperature while absent",
+    ),
+    CommandSpec::path(
+        "setAbsentMaxTemperature",
+        TEMPERATURE,
+        "Set the maximum temperature while absent",
+    ),
+    CommandSpec::path(
+        "setOperatingMode",
+        &[CommandArg::Integer {
+            name: "mode",
+            min: 0,
+            max: 5,
+        }],
+        "Set the operating mode (automatic or manual, heating and/or cooling)",
+    ),
+    CommandSpec::path(
+        "override",
+        &[
+            CommandArg::Integer {
+                name: "mode",
+                min: 0,
+                max: 4,
+            },
+            CommandArg::Integer {
+                name: "until",
+                min: 0,
+                max: i64::MAX,
+            },
+        ],
+        "Switch to a mode until a time (seconds since 2009-01-01)",
+    ),
+    CommandSpec::path(
+        "override",
+        &[
+            CommandArg::Integer {
+                name: "mode",
+                min: 0,
+                max: 4,
+            },
+            CommandArg::Integer {
+                name: "until",
+                min: 0,
+                max: i64::MAX,
+            },
+            CommandArg::Number {
+                name: "temperature",
+                min: 5.0,
+                max: 40.0,
+            },
+        ],
+        "Switch to a mode with a temperature until a time",
+    ),
+    CommandSpec::simple("stopOverride", "End an active override"),
+];
+
+const ALARM: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Arm"),
+    CommandSpec::path("on", FLAG, "Arm with (1) or without (0) motion detection"),
+    CommandSpec::simple("delayedon", "Arm after the arming delay"),
+    CommandSpec::path(
+        "delayedon",
+        FLAG,
+        "Arm after the delay with (1) or without (0) motion detection",
+    ),
+    CommandSpec::simple("off", "Disarm"),
+    CommandSpec::simple("quit", "Acknowledge an alarm"),
+    CommandSpec::path("dismv", FLAG, "Disable (1) or enable (0) motion detection"),
+];
+
+const CENTRAL_ALARM: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Arm all alarms"),
+    CommandSpec::simple("delayedon", "Arm all alarms after the delay"),
+    CommandSpec::simple("off", "Disarm all alarms"),
+    CommandSpec::simple("quit", "Acknowledge all alarms"),
+];
+
+const SMOKE_ALARM: &[CommandSpec] = &[
+    CommandSpec::simple("mute", "Mute the acoustic alarm"),
+    CommandSpec::simple("quit", "Acknowledge the alarm"),
+];
+
+const AUDIO_ZONE: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Switch the zone on"),
+    CommandSpec::simple("off", "Switch the zone off"),
+    CommandSpec::simple("play", "Start playback"),
+    CommandSpec::simple("pause", "Pause playback"),
+    CommandSpec::simple("stop", "Stop playback"),
+    CommandSpec::simple("queueplus", "Next track"),
+    CommandSpec::simple("queueminus", "Previous track"),
+    CommandSpec::simple("mute", "Mute"),
+    CommandSpec::simple("unmute", "Unmute"),
+    CommandSpec::simple("volup", "Increase the volume"),
+    CommandSpec::simple("voldown", "Decrease the volume"),
+    CommandSpec::path("volume", PERCENT, "Set the volume"),
+];
+
+const AUDIO_ZONE_V2: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Switch the zone on"),
+    CommandSpec::simple("off", "Switch the zone off"),
+    CommandSpec::simple("play", "Start playback"),
+    CommandSpec::simple("pause", "Pause playback"),
+    CommandSpec::simple("next", "Next track"),
+    CommandSpec::simple("prev", "Previous track"),
+    CommandSpec::simple("volUp", "Increase the volume"),
+    CommandSpec::simple("volDown", "Decrease the volume"),
+    CommandSpec::path("volume", PERCENT, "Set the volume"),
+    CommandSpec::path(
+        "source",
+        &[CommandArg::Integer {
+            name: "source",
+            min: 1,
+            max: 64,
+        }],
+        "Select a favorite source",
+    ),
+    CommandSpec::path(
+        "playZoneFav",
+        &[CommandArg::Integer {
+            name: "favorite",
+            min: 1,
+            max: 64,
+        }],
+        "Play a room favorite",
+    ),
+    CommandSpec::path(
+        "playFav",
+        &[CommandArg::Integer {
+            name: "favorite",
+            min: 1,
+            max: 65535,
+        }],
+        "Play a global favorite",
+    ),
+    CommandSpec::path("shuffle", FLAG, "Shuffle off (0) or on (1)"),
+    CommandSpec::path(
+        "repeat",
+        &[CommandArg::Integer {
+            name: "mode",
+            min: 0,
+            max: 2,
+        }],
+        "Repeat off (0), the queue (1) or the track (2)",
+    ),
+    CommandSpec::path(
+        "syncWith",
+        &[CommandArg::Integer {
+            name: "player",
+            min: 1,
+            max: 65535,
+        }],
+        "Join the sync group of another player",
+    ),
+    CommandSpec::simple("unsync", "Leave the sync group"),
+    CommandSpec::path(
+        "getqueue",
+        &[
+            CommandArg::Integer {
+                name: "start",
+                min: 0,
+                max: 65535,
+            },
+            CommandArg::Integer {
+                name: "count",
+                min: 1,
+                max: 100,
+            },
+        ],
+        "Read the play queue",
+    ),
+    CommandSpec::simple("getroomfavs", "Read the room favorites"),
+    CommandSpec::simple("getfavorites", "Read the global favorites"),
+    CommandSpec::simple("bell", "Play the doorbell announcement"),
+    CommandSpec::simple("alarm", "Play the alarm announcement"),
+    CommandSpec::simple("firealarm", "Play the fire alarm announcement"),
+    CommandSpec::path("tts", TTS, "Speak a text"),
+    CommandSpec::path("tts", TTS_WITH_VOLUME, "Speak a text at a volume"),
+];
+
+/// The central zone announces and switches on every zone at once
+const CENTRAL_AUDIO_ZONE: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Switch every zone on"),
+    CommandSpec::simple("off", "Switch every zone off"),
+    CommandSpec::simple("play", "Start playback everywhere"),
+    CommandSpec::simple("pause", "Pause playback everywhere"),
+    CommandSpec::simple("stop", "Stop playback everywhere"),
+    CommandSpec::simple("mute", "Mute every zone"),
+    CommandSpec::simple("unmute", "Unmute every zone"),
+    CommandSpec::simple("volup", "Increase the volume everywhere"),
+    CommandSpec::simple("voldown", "Decrease the volume everywhere"),
+    CommandSpec::simple("bell", "Play the doorbell announcement everywhere"),
+    CommandSpec::simple("alarm", "Play the alarm announcement everywhere"),
+    CommandSpec::simple("firealarm", "Play the fire alarm announcement everywhere"),
+    CommandSpec::path("tts", TTS, "Speak a text everywhere"),
+    CommandSpec::path(
+        "tts",
+        TTS_WITH_VOLUME,
+        "Speak a text everywhere at a volume",
+    ),
+];
+
+const WALLBOX: &[CommandSpec] = &[
+    CommandSpec::simple("on", "Start charging"),
+    CommandSpec::simple("off", "Stop charging"),
+    CommandSpec::path(
+        "setlimit",
+        &[CommandArg::Number {
+            name: "limit",
+            min: 0.0,
+            max: 1000.0,
+        }],
+        "Limit the energy of the session",
+    ),
+];
+
+const WALLBOX2: &[CommandSpec] = &[
+    CommandSpec::path("allow", FLAG, "Allow (1) or block (0) charging"),
+    CommandSpec::path(
+        "limit",
+        &[CommandArg::Number {
+            name: "limit",
+            min: 0.0,
+            max: 1000.0,
+        }],
+        "Limit the charging power",
+    ),
+];
+
+const SLIDER: &[CommandSpec] = &[CommandSpec::value(
+    &[CommandArg::DetailsRange { name: "value" }],
+    "Set the value",
+)];
+
+/// Read-only controls accept no commands
+const READ_ONLY: &[CommandSpec] = &[];
+
+/// Legal commands of a control type; `None` if the type is not cataloged
+pub fn command_specs(control_type: &ControlType) -> Option<&'static [CommandSpec]> {
+    use ControlType::*;
+
+    let specs = match control_type {
+        Switch | TimedSwitch | Pushbutton => SWITCH,
+        Dimmer | EIBDimmer => DIMMER,
+        LightController => LIGHT_CONTROLLER,
+        LightControllerV2 => LIGHT_CONTROLLER_V2,
+        ColorPicker | ColorPickerV2 => COLOR_PICKER,
+        Jalousie => JALOUSIE,
+        CentralJalousie => CENTRAL_JALOUSIE,
+        Gate | CentralGate => GATE,
+        Other(name) if name == "DoorLock" => DOOR_LOCK,
+        Intercom | IntercomV2 => INTERCOM,
+        IRoomController => ROOM_CONTROLLER,
+        IRoomControllerV2 => ROOM_CONTROLLER_V2,
+        Alarm => ALARM,
+        CentralAlarm => CENTRAL_ALARM,
+        SmokeAlarm => SMOKE_ALARM,
+        AudioZone => AUDIO_ZONE,
+        CentralAudioZone => CENTRAL_AUDIO_ZONE,
+        AudioZoneV2 => AUDIO_ZONE_V2,
+        Wallbox => WALLBOX,
+        Wallbox2 => WALLBOX2,
+        Slider | ValueSelector => SLIDER,
+        InfoOnlyAnalog | InfoOnlyDigital | InfoOnlyText | TextState | WeatherServer => READ_ONLY,
+        _ => return None,
+    };
+    Some(specs)
+}
+
+/// Split a command string into its name and arguments
+fn parse_command(command: &str) -> (CommandSyntax, &str, Vec<&str>) {
+    if let Some(inner) = command.strip_suffix(')')
+        && let Some((name, args)) = inner.split_once('(')
+    {
+        let args = if args.is_empty() {
+            Vec::new()
+        } else {
+            args.split(',').map(str::trim).collect()
+        };
+        return (CommandSyntax::Call, name, args);
+    }
+    if command.parse::<f64>().is_ok() {
+        return (CommandSyntax::Value, "", vec![command]);
+    }
+    let mut parts = command.split('/');
+    let name = parts.next().unwrap_or_default();
+    (CommandSyntax::Path, name, parts.collect())
+}
+
+/// Check a command against a list of specs
+fn check_command(
+    specs: &[CommandSpec],
+    command: &str,
+    details: Option<&Map<String, Value>>,
+) -> std::result::Result<(), String> {
+    if specs.is_empty() {
+        return Err("control is read-only".to_string());
+    }
+
+    let (syntax, name, args) = parse_command(command);
+    let candidates: Vec<&CommandSpec> = specs
+        .iter()
+        .filter(|spec| spec.syntax == syntax && spec.name.eq_ignore_ascii_case(name))
+        .collect();
+    if candidates.is_empty() {
+        return Err(format!("unknown command '{command}'"));
+    }
+
+    // Several specs may share a name with different arities (e.g. `on` and `on/{flag}`)
+    let mut first_error = None;
+    for spec in candidates {
+        match spec.check_args(&args, details) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_default())
+}
+
+fn usages(specs: &[CommandSpec]) -> String {
+    specs
+        .iter()
+        .map(CommandSpec::usage)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Validate a command for a control type
+///
+/// Uncataloged types are accepted as is.
+pub fn validate_command(control_type: &ControlType, command: &str) -> Result<()> {
+    let Some(specs) = command_specs(control_type) else {
+        return Ok(());
+    };
+    check_command(specs, command, None).map_err(|reason| {
+        LoxoneError::validation(format!(
+            "Invalid command '{command}' for {control_type}: {reason}. Valid commands: {}",
+            usages(specs)
+        ))
+    })
+}
+
+impl LoxoneControl {
+    /// Legal commands of this control; `None` if its type is not cataloged
+    pub fn commands(&self) -> Option<&'static [CommandSpec]> {
+        command_specs(&self.control_type)
+    }
+
+    /// Validate a command for this control, including `details` ranges
+    pub fn validate_command(&self, command: &str) -> Result<()> {
+        let Some(specs) = self.commands() else {
+            return Ok(());
+        };
+        check_command(specs, command, Some(&self.details)).map_err(|reason| {
+            LoxoneError::validation(format!(
+                "Invalid command '{command}' for {} '{}': {reason}. Valid commands: {}",
+                self.control_type,
+                self.name,
+                usages(specs)
+            ))
+        })
+    }
+
+    /// Build and validate a path command (e.g. `ManualPosition/40`)
+    ///
+    /// The name is matched case-insensitively and replaced by the cataloged
+    /// spelling; uncataloged types get the command as given.
+    pub fn build_command(&self, name: &str, args: &[&dyn fmt::Display]) -> Result<String> {
+        let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+        let spec = self.commands().and_then(|specs| {
+            specs.iter().find(|spec| {
+                spec.syntax != CommandSyntax::Value
+                    && spec.name.eq_ignore_ascii_case(name)
+                    && spec.args.len() == args.len()
+            })
+        });
+        let command = match spec {
+            Some(spec) => spec.format(&args),
+            None => std::iter::once(name.to_string())
+                .chain(args)
+                .collect::<Vec<_>>()
+                .join("/"),
+        };
+        self.validate_command(&command)?;
+        Ok(command)
+    }
+
+    /// Build and validate a bare value command (e.g. `60` for a dimmer)
+    pub fn build_value_command(&self, value: f64) -> Result<String> {
+        let command = value.to_string();
+        self.validate_command(&command)?;
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn control(control_type: &str, details: Value) -> LoxoneControl {
+        LoxoneControl::from_value(
+            "uuid-1",
+            &json!({ "name": "Test", "type": control_type, "details": details }),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_jalousie_commands() {
+        let blind = control("Jalousie", json!({}));
+        assert_eq!(
+            blind.build_command("manualposition", &[&40]).unwrap(),
+            "ManualPosition/40"
+        );
+        assert_eq!(blind.build_command("Stop", &[]).unwrap(), "stop");
+        assert!(blind.validate_command("FullUp").is_ok());
+
+        let error = blind.validate_command("ManualPosition/140").unwrap_err();
+        assert!(matches!(error, LoxoneError::InvalidInput(_)));
+        assert!(error.to_string().contains("between 0 and 100"));
+        assert!(blind.validate_command("ManualPosition").is_err());
+        assert!(blind.validate_command("changeTo/1").is_err());
+    }
+
+    #[test]
+    fn test_value_and_call_commands() {
+        let dimmer = control("Dimmer", json!({}));
+        assert_eq!(dimmer.build_value_command(60.0).unwrap(), "60");
+        assert!(dimmer.build_value_command(101.0).is_err());
+
+        let picker = control("ColorPickerV2", json!({}));
+        assert!(picker.validate_command("hsv(120,100,80)").is_ok());
+        assert!(picker.validate_command("hsv(400,100,80)").is_err());
+        assert!(picker.validate_command("temp(50,1000)").is_err());
+        assert!(picker.validate_command("60").is_err());
+
+        let slider = control("Slider", json!({ "min": 10, "max": 20 }));
+        assert!(slider.validate_command("15").is_ok());
+        assert!(slider.validate_command("25").is_err());
+    }
+
+    #[test]
+    fn test_overloaded_and_integer_arguments() {
+        let alarm = control("Alarm", json!({}));
+        assert!(alarm.validate_command("on").is_ok());
+        assert!(alarm.validate_command("on/1").is_ok());
+        assert!(alarm.validate_command("on/1234").is_err());
+
+        let lights = control("LightControllerV2", json!({}));
+        assert_eq!(
+            lights.build_command("changeTo", &[&778]).unwrap(),
+            "changeTo/778"
+        );
+        assert!(lights.validate_command("changeTo/reading").is_err());
+        assert!(lights.validate_command("changeTo/1.5").is_err());
+        assert!(lights.validate_command("60").is_err());
+        assert_eq!(
+            lights
+                .build_command("learn", &[&3, &"Movie%20Night"])
+                .unwrap(),
+            "learn/3/Movie%20Night"
+        );
+        assert!(lights.validate_command("learn/3").is_err());
+        assert!(lights.validate_command("moveFavoriteMood/3/0").is_ok());
+        assert!(lights.validate_command("addToFavoriteMood/3").is_ok());
+        assert!(
+            control("LightController", json!({}))
+                .validate_command("learn/3/Movie")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_audio_zone_commands() {
+        let zone = control("AudioZoneV2", json!({ "playerid": 3 }));
+        assert_eq!(
+            zone.build_command("playzonefav", &[&2]).unwrap(),
+            "playZoneFav/2"
+        );
+        assert_eq!(zone.build_command("repeat", &[&2]).unwrap(), "repeat/2");
+        assert!(zone.validate_command("repeat/3").is_err());
+        assert!(zone.validate_command("getqueue/0/50").is_ok());
+        assert!(zone.validate_command("tts/Hello%20there").is_ok());
+        assert!(zone.validate_command("tts/Hello%20there/40").is_ok());
+        assert!(zone.validate_command("tts/Hello there").is_err());
+        assert!(zone.validate_command("tts/").is_err());
+
+        let central = control("CentralAudioZone", json!({}));
+        assert!(central.validate_command("firealarm").is_ok());
+        assert!(central.validate_command("playZoneFav/1").is_err());
+    }
+
+    #[test]
+    fn test_security_device_commands() {
+        let lock = control("DoorLock", json!({}));
+        assert!(lock.validate_command("on").is_ok());
+        assert!(lock.validate_command("Unlock").is_ok());
+        assert!(lock.validate_command("unlock/1").is_err());
+        assert!(lock.validate_command("unlatch").is_err());
+
+        let intercom = control("IntercomV2", json!({}));
+        assert!(intercom.validate_command("open").is_ok());
+        assert!(intercom.validate_command("mute/1").is_ok());
+        assert!(intercom.validate_command("open/1").is_err());
+        assert!(intercom.validate_command("opendoor").is_err());
+    }
+
+    #[test]
+    fn test_read_only_and_uncataloged_types() {
+        let sensor = control("InfoOnlyAnalog", json!({}));
+        assert!(
+            sensor
+                .validate_command("on")
+                .unwrap_err()
+                .to_string()
+                .contains("read-only")
+        );
+
+        let custom = control("CustomThing", json!({}));
+        assert!(custom.commands().is_none());
+        assert_eq!(custom.build_command("doIt", &[&1]).unwrap(), "doIt/1");
+        assert!(validate_command(&ControlType::Other("X".into()), "anything").is_ok());
+        assert!(validate_command(&ControlType::Switch, "dim").is_err());
+    }
+}
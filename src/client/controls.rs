@@ -0,0 +1,752 @@
+//! Typed control model built from the structure file
+//!
+//! `LoxAPP3.json` describes every control as a loosely typed JSON object.
+//! [`ControlModel`] parses the `controls` section once per structure load into
+//! [`LoxoneControl`]s with a [`ControlType`], their `states` and `details`
+//! maps and nested sub-controls, so consumers no longer dig through
+//! `serde_json::Value` by hand.
+
+use crate::client::LoxoneStructure;
+use crate::client::statistics::StatisticConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+macro_rules! control_types {
+    ($($variant:ident),* $(,)?) => {
+        /// Control type as reported in the `type` field of a control
+        ///
+        /// Covers the control types documented in the Loxone structure file
+        /// specification; anything else is kept verbatim in
+        /// [`ControlType::Other`].
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[serde(from = "String", into = "String")]
+        pub enum ControlType {
+            $(
+                #[allow(missing_docs)]
+                $variant,
+            )*
+            /// Control type without a dedicated variant
+            Other(String),
+        }
+
+        impl ControlType {
+            /// Type name as used in the structure file
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => stringify!($variant),)*
+                    Self::Other(name) => name,
+                }
+            }
+
+            fn documented(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($variant) => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+control_types!(
+    AalEmergency,
+    AalSmartAlarm,
+    Alarm,
+    AlarmChain,
+    AlarmClock,
+    Application,
+    AudioZone,
+    AudioZoneV2,
+    CentralAlarm,
+    CentralAudioZone,
+    CentralGate,
+    CentralJalousie,
+    CentralLightController,
+    ClimateController,
+    ColorPicker,
+    ColorPickerV2,
+    Daytimer,
+    Dimmer,
+    EIBDimmer,
+    EnergyFlowMonitor,
+    EnergyManager,
+    EnergyManager2,
+    Fronius,
+    Gate,
+    Heatmixer,
+    Hourcounter,
+    InfoOnlyAnalog,
+    InfoOnlyDigital,
+    InfoOnlyText,
+    Intercom,
+    IntercomV2,
+    IRCDaytimer,
+    IRCV2Daytimer,
+    IRoomController,
+    IRoomControllerV2,
+    Irrigation,
+    Jalousie,
+    LightController,
+    LightControllerV2,
+    LoadManager,
+    MailBox,
+    Meter,
+    NfcCodeTouch,
+    PoolController,
+    PresenceDetector,
+    Pushbutton,
+    Radio,
+    Remote,
+    Sauna,
+    Slider,
+    SmokeAlarm,
+    SteakThermo,
+    Switch,
+    TextState,
+    TimedSwitch,
+    Tracker,
+    UpDownLeftRight,
+    ValueSelector,
+    Ventilation,
+    Wallbox,
+    Wallbox2,
+    WeatherServer,
+    Webpage,
+    Window,
+    WindowMonitor,
+);
+
+impl ControlType {
+    /// Parse a `type` field, mapping legacy and localized aliases
+    pub fn from_type_str(name: &str) -> Self {
+        if let Some(control_type) = Self::documented(name) {
+            return control_type;
+        }
+        match name {
+            "Blinds" | "Rolladen" => Self::Jalousie,
+            "Intelligent Room Controller" => Self::IRoomController,
+            _ => Self::Other(name.to_string()),
+        }
+    }
+
+    /// Switches, dimmers, color pickers and light controllers
+    pub fn is_lighting(&self) -> bool {
+        matches!(
+            self,
+            Self::Switch
+                | Self::Dimmer
+                | Self::EIBDimmer
+                | Self::LightController
+                | Self::LightControllerV2
+                | Self::CentralLightController
+                | Self::ColorPicker
+                | Self::ColorPickerV2
+        )
+    }
+
+    /// Light controllers that can switch between moods
+    pub fn has_moods(&self) -> bool {
+        matches!(self, Self::LightController | Self::LightControllerV2)
+    }
+
+    /// Blinds, shutters and awnings
+    pub fn is_blind(&self) -> bool {
+        matches!(self, Self::Jalousie | Self::CentralJalousie)
+    }
+
+    /// Intelligent room controllers accepting target temperatures
+    pub fn is_room_controller(&self) -> bool {
+        matches!(self, Self::IRoomController | Self::IRoomControllerV2)
+    }
+
+    /// Audio zones and media controllers
+    pub fn is_audio(&self) -> bool {
+        match self {
+            Self::AudioZone | Self::AudioZoneV2 | Self::CentralAudioZone => true,
+            Self::Other(name) => name.contains("Audio") || name == "MediaController",
+            _ => false,
+        }
+    }
+
+    /// Read-only inputs such as analog values, contacts and detectors
+    pub fn is_sensor(&self) -> bool {
+        match self {
+            Self::InfoOnlyAnalog
+            | Self::InfoOnlyDigital
+            | Self::PresenceDetector
+            | Self::SmokeAlarm
+            | Self::Meter => true,
+            Self::Other(name) => matches!(name.as_str(), "MotionSensor" | "Sensor"),
+            _ => false,
+        }
+    }
+
+    /// Presence and motion detectors
+    pub fn is_presence(&self) -> bool {
+        match self {
+            Self::PresenceDetector => true,
+            Self::Other(name) => name == "MotionSensor",
+            _ => false,
+        }
+    }
+
+    /// Weather service and weather stations
+    pub fn is_weather(&self) -> bool {
+        match self {
+            Self::WeatherServer => true,
+            Self::Other(name) => name.contains("Weather"),
+            _ => false,
+        }
+    }
+
+    /// Meters, energy managers and inverters
+    pub fn is_energy(&self) -> bool {
+        match self {
+            Self::Meter
+            | Self::EnergyManager
+            | Self::EnergyManager2
+            | Self::EnergyFlowMonitor
+            | Self::Fronius => true,
+            Self::Other(name) => name.contains("Energy"),
+            _ => false,
+        }
+    }
+
+    /// Alarm systems that can be armed and disarmed
+    pub fn is_alarm(&self) -> bool {
+        match self {
+            Self::Alarm | Self::CentralAlarm => true,
+            Self::Other(name) => name == "AccessControl" || name.contains("Security"),
+            _ => false,
+        }
+    }
+
+    /// Alarms, smoke detectors, gates, locks and intercoms (which open doors)
+    pub fn is_security(&self) -> bool {
+        match self {
+            Self::SmokeAlarm
+            | Self::AalSmartAlarm
+            | Self::Gate
+            | Self::CentralGate
+            | Self::Intercom
+            | Self::IntercomV2 => true,
+            Self::Other(name) => name == "DoorLock" || self.is_alarm(),
+            _ => self.is_alarm(),
+        }
+    }
+
+    /// Intercoms and cameras
+    pub fn is_camera(&self) -> bool {
+        match self {
+            Self::Intercom | Self::IntercomV2 => true,
+            Self::Other(name) => name == "Doorbell" || name.contains("Camera"),
+            _ => false,
+        }
+    }
+
+    /// Wallboxes for EV charging
+    pub fn is_ev_charger(&self) -> bool {
+        matches!(self, Self::Wallbox | Self::Wallbox2)
+    }
+}
+
+impl From<String> for ControlType {
+    fn from(name: String) -> Self {
+        Self::from_type_str(&name)
+    }
+}
+
+impl From<ControlType> for String {
+    fn from(control_type: ControlType) -> Self {
+        control_type.as_str().to_string()
+    }
+}
+
+impl fmt::Display for ControlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Mood defined on a light controller
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mood {
+    /// Mood ID used by `changeTo/{id}`
+    pub id: String,
+    /// Display name
+    pub name: String,
+}
+
+/// Control parsed from the structure file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoxoneControl {
+    /// Control UUID
+    pub uuid: String,
+    /// Display name
+    pub name: String,
+    /// Control type
+    #[serde(rename = "type")]
+    pub control_type: ControlType,
+    /// Room UUID
+    pub room: Option<String>,
+    /// Category UUID
+    pub category: Option<String>,
+    /// Whether commands require the visualization password
+    pub is_secured: bool,
+    /// State name to state UUID
+    pub states: HashMap<String, String>,
+    /// Type specific configuration
+    pub details: Map<String, Value>,
+    /// Moods of legacy light controllers
+    pub moods: Vec<Mood>,
+    /// Nested controls (e.g. the circuits of a light controller)
+    pub sub_controls: Vec<LoxoneControl>,
+    /// Statistics recorded by the Miniserver, if enabled
+    pub statistic: Option<StatisticConfig>,
+}
+
+impl LoxoneControl {
+    /// Parse a control object; returns `None` if it is not an object
+    pub fn from_value(uuid: &str, value: &Value) -> Option<Self> {
+        let object = value.as_object()?;
+        let text = |key: &str| object.get(key).and_then(Value::as_str).map(str::to_string);
+
+        let states = object
+            .get("states")
+            .and_then(Value::as_object)
+            .map(|states| {
+                states
+                    .iter()
+                    .filter_map(|(name, state)| {
+                        state
+                            .as_str()
+                            .map(|state| (name.clone(), state.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut moods: Vec<Mood> = object
+            .get("moods")
+            .and_then(Value::as_object)
+            .map(|moods| {
+                moods
+                    .iter()
+                    .filter_map(|(id, name)| {
+                        name.as_str().map(|name| Mood {
+                            id: id.clone(),
+                            name: name.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        moods.sort_by_key(|mood| mood.id.parse::<i64>().unwrap_or(i64::MAX));
+
+        let mut sub_controls: Vec<LoxoneControl> = object
+            .get("subControls")
+            .and_then(Value::as_object)
+            .map(|subs| {
+                subs.iter()
+                    .filter_map(|(sub_uuid, sub)| Self::from_value(sub_uuid, sub))
+                    .collect()
+            })
+            .unwrap_or_default();
+        sub_controls.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Some(Self {
+            uuid: text("uuidAction").unwrap_or_else(|| uuid.to_string()),
+            name: text("name").unwrap_or_else(|| "Unknown".to_string()),
+            control_type: ControlType::from_type_str(&text("type").unwrap_or_default()),
+            room: text("room"),
+            category: text("cat"),
+            is_secured: object
+                .get("isSecured")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            states,
+            details: object
+                .get("details")
+                .and_then(Value::as_object)
+                .cloned()
+                .unwrap_or_default(),
+            moods,
+            sub_controls,
+            statistic: object
+                .get("statistic")
+                .and_then(StatisticConfig::from_value),
+        })
+    }
+
+    /// Type name as used in the structure file
+    pub fn type_name(&self) -> &str {
+        self.control_type.as_str()
+    }
+
+    /// UUID of a named state (e.g. `"position"`, `"tempActual"`)
+    pub fn state_uuid(&self, name: &str) -> Option<&str> {
+        self.states.get(name).map(String::as_str)
+    }
+
+    /// Raw `details` entry
+    pub fn detail(&self, key: &str) -> Option<&Value> {
+        self.details.get(key)
+    }
+
+    /// String `details` entry
+    pub fn detail_str(&self, key: &str) -> Option<&str> {
+        self.detail(key).and_then(Value::as_str)
+    }
+
+    /// Numeric `details` entry
+    pub fn detail_f64(&self, key: &str) -> Option<f64> {
+        self.detail(key).and_then(Value::as_f64)
+    }
+
+    /// Boolean `details` entry
+    pub fn detail_bool(&self, key: &str) -> Option<bool> {
+        self.detail(key).and_then(Value::as_bool)
+    }
+
+    /// Value format of info-only and meter controls (e.g. `"%.1f°"`)
+    pub fn format(&self) -> Option<&str> {
+        self.detail_str("format")
+    }
+
+    /// Jalousie animation (0 blinds, 1 shutters, 2 curtain both sides, ...)
+    pub fn jalousie_animation(&self) -> Option<i64> {
+        self.detail("animation").and_then(Value::as_i64)
+    }
+
+    /// Whether a blind supports slat (lamella) positions
+    pub fn has_slats(&self) -> bool {
+        self.control_type.is_blind() && self.jalousie_animation().is_none_or(|a| a == 0)
+    }
+
+    /// First mood whose name contains `query` (case-insensitive)
+    pub fn find_mood(&self, query: &str) -> Option<&Mood> {
+        let query = query.to_lowercase();
+        self.moods
+            .iter()
+            .find(|mood| mood.name.to_lowercase() == query)
+            .or_else(|| {
+                self.moods
+                    .iter()
+                    .find(|mood| mood.name.to_lowercase().contains(&query))
+            })
+    }
+
+    /// Whether the name equals or contains `query` (case-insensitive)
+    pub fn name_matches(&self, query: &str) -> bool {
+        let name = self.name.to_lowercase();
+        let query = query.to_lowercase();
+        name == query || name.contains(&query)
+    }
+
+    /// This control followed by all nested sub-controls
+    pub fn flatten(&self) -> Vec<&LoxoneControl> {
+        let mut controls = vec![self];
+        for sub in &self.sub_controls {
+            controls.extend(sub.flatten());
+        }
+        controls
+    }
+}
+
+/// All controls of a structure, indexed by UUID
+#[derive(Debug, Clone, Default)]
+pub struct ControlModel {
+    controls: HashMap<String, LoxoneControl>,
+    rooms: HashMap<String, String>,
+    categories: HashMap<String, String>,
+    operating_modes: BTreeMap<i32, String>,
+    last_modified: String,
+}
+
+impl ControlModel {
+    /// Build the model from a structure file
+    pub fn from_structure(structure: &LoxoneStructure) -> Self {
+        let names = |entries: &HashMap<String, Value>| {
+            entries
+                .iter()
+                .filter_map(|(uuid, entry)| {
+                    entry
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .map(|name| (uuid.clone(), name.to_string()))
+                })
+                .collect()
+        };
+
+        Self {
+            controls: structure
+                .controls
+                .iter()
+                .filter_map(|(uuid, control)| {
+                    LoxoneControl::from_value(uuid, control).map(|control| (uuid.clone(), control))
+                })
+                .collect(),
+            rooms: names(&structure.rooms),
+            categories: names(&structure.cats),
+            operating_modes: structure
+                .operating_modes
+                .iter()
+                .filter_map(|(id, name)| Some((id.parse().ok()?, name.as_str()?.to_string())))
+                .collect(),
+            last_modified: structure.last_modified.clone(),
+        }
+    }
+
+    /// `lastModified` of the structure the model was built from
+    pub fn last_modified(&self) -> &str {
+        &self.last_modified
+    }
+
+    /// Number of top-level controls
+    pub fn len(&self) -> usize {
+        self.controls.len()
+    }
+
+    /// Whether the structure has no controls
+    pub fn is_empty(&self) -> bool {
+        self.controls.is_empty()
+    }
+
+    /// Top-level control by UUID
+    pub fn get(&self, uuid: &str) -> Option<&LoxoneControl> {
+        self.controls.get(uuid)
+    }
+
+    /// All top-level controls, in no particular order
+    pub fn controls(&self) -> impl Iterator<Item = &LoxoneControl> {
+        self.controls.values()
+    }
+
+    /// Top-level controls matching `filter`, sorted by name
+    pub fn filter<F>(&self, filter: F) -> Vec<&LoxoneControl>
+    where
+        F: Fn(&LoxoneControl) -> bool,
+    {
+        let mut controls: Vec<_> = self.controls().filter(|c| filter(c)).collect();
+        controls.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.uuid.cmp(&b.uuid)));
+        controls
+    }
+
+    /// Top-level controls whose type matches `predicate`, sorted by name
+    pub fn of_type(&self, predicate: fn(&ControlType) -> bool) -> Vec<&LoxoneControl> {
+        self.filter(|control| predicate(&control.control_type))
+    }
+
+    /// Controls in a room (by room UUID) whose type matches `predicate`
+    pub fn of_type_in_room(
+        &self,
+        room_uuid: &str,
+        predicate: fn(&ControlType) -> bool,
+    ) -> Vec<&LoxoneControl> {
+        self.filter(|control| {
+            control.room.as_deref() == Some(room_uuid) && predicate(&control.control_type)
+        })
+    }
+
+    /// Find a control by UUID, exact name or partial name (case-insensitive)
+    pub fn find(&self, identifier: &str) -> Option<&LoxoneControl> {
+        if let Some(control) = self.get(identifier) {
+            return Some(control);
+        }
+        let lower = identifier.to_lowercase();
+        let matches = self.filter(|control| control.name_matches(identifier));
+        matches
+            .iter()
+            .find(|control| control.name.to_lowercase() == lower)
+            .or_else(|| matches.first())
+            .copied()
+    }
+
+    /// Room UUID to name
+    pub fn rooms(&self) -> &HashMap<String, String> {
+        &self.rooms
+    }
+
+    /// Name of a room UUID
+    pub fn room_name(&self, room_uuid: &str) -> Option<&str> {
+        self.rooms.get(room_uuid).map(String::as_str)
+    }
+
+    /// Name of a category UUID
+    pub fn category_name(&self, category_uuid: &str) -> Option<&str> {
+        self.categories.get(category_uuid).map(String::as_str)
+    }
+
+    /// Calendar modes of daytimer schedules by ID (`operatingModes`)
+    pub fn operating_modes(&self) -> &BTreeMap<i32, String> {
+        &self.operating_modes
+    }
+
+    /// Resolve a room name (exact, then partial, case-insensitive) to its UUID
+    pub fn resolve_room(&self, room_name: &str) -> Option<&str> {
+        resolve_name(&self.rooms, room_name)
+    }
+
+    /// Resolve a category name (exact, then partial, case-insensitive) to its UUID
+    pub fn resolve_category(&self, category_name: &str) -> Option<&str> {
+        resolve_name(&self.categories, category_name)
+    }
+}
+
+/// Look up a UUID by name: an exact case-insensitive match, then a partial one
+fn resolve_name<'a>(names: &'a HashMap<String, String>, query: &str) -> Option<&'a str> {
+    let lower = query.to_lowercase();
+    let mut entries: Vec<_> = names.iter().collect();
+    entries.sort();
+    entries
+        .iter()
+        .find(|(_, name)| name.to_lowercase() == lower)
+        .or_else(|| {
+            entries
+                .iter()
+                .find(|(_, name)| name.to_lowercase().contains(&lower))
+        })
+        .map(|(uuid, _)| uuid.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn structure() -> LoxoneStructure {
+        serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {
+                "room-1": { "name": "Living Room" },
+                "room-2": { "name": "Living Room Annex" }
+            },
+            "cats": { "cat-1": { "name": "Lighting" } },
+            "controls": {
+                "lc-1": {
+                    "name": "Living Light",
+                    "type": "LightControllerV2",
+                    "uuidAction": "lc-1",
+                    "room": "room-1",
+                    "cat": "cat-1",
+                    "states": { "activeMoods": "lc-1-s1", "moodList": "lc-1-s2" },
+                    "details": { "masterValue": "dim-1" },
+                    "subControls": {
+                        "dim-1": {
+                            "name": "Spots",
+                            "type": "Dimmer",
+                            "states": { "position": "dim-1-s1", "min": "dim-1-s2" }
+                        }
+                    }
+                },
+                "legacy-1": {
+                    "name": "Annex Light",
+                    "type": "LightController",
+                    "room": "room-2",
+                    "moods": { "778": "Off", "2": "Reading", "1": "Bright" }
+                },
+                "blind-1": {
+                    "name": "Blind",
+                    "type": "Rolladen",
+                    "room": "room-1",
+                    "details": { "animation": 1 },
+                    "isSecured": true
+                },
+                "custom-1": { "name": "Doorbell Camera", "type": "IPCamera" }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_control_type_parsing() {
+        assert_eq!(
+            ControlType::from_type_str("IRoomControllerV2"),
+            ControlType::IRoomControllerV2
+        );
+        assert_eq!(
+            ControlType::from_type_str("Rolladen"),
+            ControlType::Jalousie
+        );
+        assert_eq!(
+            ControlType::from_type_str("Intelligent Room Controller"),
+            ControlType::IRoomController
+        );
+        let other = ControlType::from_type_str("IPCamera");
+        assert_eq!(other, ControlType::Other("IPCamera".to_string()));
+        assert_eq!(other.as_str(), "IPCamera");
+        assert!(other.is_camera());
+        assert!(ControlType::AudioZoneV2.is_audio());
+        assert!(ControlType::Alarm.is_security());
+        assert!(ControlType::IntercomV2.is_security());
+        assert!(!ControlType::Switch.is_blind());
+
+        let serialized = serde_json::to_value(ControlType::Wallbox2).unwrap();
+        assert_eq!(serialized, json!("Wallbox2"));
+        assert!(
+            serde_json::from_value::<ControlType>(serialized)
+                .unwrap()
+                .is_ev_charger()
+        );
+    }
+
+    #[test]
+    fn test_model_parses_states_details_and_sub_controls() {
+        let model = ControlModel::from_structure(&structure());
+        assert_eq!(model.len(), 4);
+
+        let light = model.get("lc-1").unwrap();
+        assert_eq!(light.control_type, ControlType::LightControllerV2);
+        assert_eq!(light.state_uuid("activeMoods"), Some("lc-1-s1"));
+        assert_eq!(light.detail_str("masterValue"), Some("dim-1"));
+        assert_eq!(
+            model.category_name(light.category.as_deref().unwrap()),
+            Some("Lighting")
+        );
+        assert_eq!(light.sub_controls.len(), 1);
+        assert_eq!(light.sub_controls[0].control_type, ControlType::Dimmer);
+        assert_eq!(light.flatten().len(), 2);
+
+        let blind = model.get("blind-1").unwrap();
+        assert!(blind.is_secured);
+        assert_eq!(blind.jalousie_animation(), Some(1));
+        assert!(!blind.has_slats());
+    }
+
+    #[test]
+    fn test_moods_are_sorted_and_searchable() {
+        let model = ControlModel::from_structure(&structure());
+        let legacy = model.get("legacy-1").unwrap();
+        let ids: Vec<_> = legacy.moods.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["1", "2", "778"]);
+        assert_eq!(legacy.find_mood("read").unwrap().id, "2");
+        assert!(legacy.find_mood("party").is_none());
+    }
+
+    #[test]
+    fn test_lookup_helpers() {
+        let model = ControlModel::from_structure(&structure());
+
+        assert_eq!(model.resolve_room("living room"), Some("room-1"));
+        assert_eq!(model.resolve_room("annex"), Some("room-2"));
+        assert_eq!(model.resolve_room("attic"), None);
+        assert_eq!(model.resolve_category("LIGHT"), Some("cat-1"));
+        assert_eq!(model.resolve_category("shading"), None);
+
+        assert_eq!(model.find("blind-1").unwrap().name, "Blind");
+        assert_eq!(model.find("annex light").unwrap().uuid, "legacy-1");
+
+        let lights = model.of_type(ControlType::is_lighting);
+        assert_eq!(lights.len(), 2);
+        assert_eq!(lights[0].name, "Annex Light");
+        assert_eq!(
+            model
+                .of_type_in_room("room-1", ControlType::is_lighting)
+                .len(),
+            1
+        );
+        assert_eq!(model.of_type(ControlType::has_moods).len(), 2);
+    }
+}
@@ -4,8 +4,10 @@
 //! Miniservers using basic authentication and REST API calls.
 
 use crate::client::{
-    ClientContext, LoxoneClient, LoxoneDevice, LoxoneResponse, LoxoneStructure,
+    ClientContext, LoxoneClient, LoxoneDevice, LoxoneImage, LoxoneResponse, LoxoneStructure,
+    StatisticFile, StatisticMonth, camera_image_path,
     connection_pool::{ConnectionPool, PoolBuilder},
+    statistics::{parse_statistics_listing, statistics_path},
 };
 use crate::config::{LoxoneConfig, credentials::LoxoneCredentials};
 use crate::error::{LoxoneError, Result};
@@ -81,6 +83,10 @@ impl LoxoneHttpClient {
             client_builder = client_builder.danger_accept_invalid_certs(true);
         }
 
+        if config.command_encryption != crate::config::CommandEncryption::None {
+            warn!("Command encryption requires token authentication; sending commands unencrypted");
+        }
+
         // Add basic authentication via header
         let auth_header = format!(
             "Basic {}",
@@ -209,6 +215,13 @@ impl LoxoneHttpClient {
         &self.context
     }
 
+    /// Load the structure into a context shared with the caller
+    #[must_use]
+    pub fn with_context(mut self, context: Arc<ClientContext>) -> Self {
+        self.context = context;
+        self
+    }
+
     /// Get connection pool statistics
     pub async fn pool_stats(&self) -> crate::client::connection_pool::PoolStats {
         self.connection_pool.stats().await
@@ -331,6 +344,12 @@ impl LoxoneClient for LoxoneHttpClient {
         Ok(structure)
     }
 
+    async fn get_structure_version(&self) -> Result<String> {
+        let url = self.build_url("jdev/sps/LoxAPPversion3")?;
+        let response = self.execute_request(url).await?;
+        read_structure_version(response).await
+    }
+
     async fn get_device_states(
         &self,
         uuids: &[String],
@@ -439,6 +458,23 @@ impl LoxoneClient for LoxoneHttpClient {
         Ok(loxone_response.value)
     }
 
+    async fn fetch_camera_image(&self, uuid: &str, timestamp: Option<&str>) -> Result<LoxoneImage> {
+        let url = self.build_url(&camera_image_path(uuid, timestamp)?)?;
+        let response = self.execute_request(url).await?;
+        read_image(response).await
+    }
+
+    async fn list_statistics(&self) -> Result<Vec<StatisticFile>> {
+        let response = self.execute_request(self.build_url("stats")?).await?;
+        read_statistics_listing(response).await
+    }
+
+    async fn fetch_statistics(&self, uuid: &str, month: StatisticMonth) -> Result<Vec<u8>> {
+        let url = self.build_url(&statistics_path(uuid, month)?)?;
+        let response = self.execute_request(url).await?;
+        read_statistics(response).await
+    }
+
     async fn health_check(&self) -> Result<bool> {
         debug!("Performing health check");
 
@@ -690,6 +726,76 @@ impl LoxoneHttpClient {
     }
 }
 
+/// Read an image response, rejecting anything that is not an image
+pub(crate) async fn read_image(response: reqwest::Response) -> Result<LoxoneImage> {
+    let mime_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap_or(value).trim().to_string())
+        .unwrap_or_else(|| "image/jpeg".to_string());
+    if !mime_type.starts_with("image/") {
+        return Err(LoxoneError::device_control(format!(
+            "Miniserver returned {mime_type} instead of an image"
+        )));
+    }
+    let data = response
+        .bytes()
+        .await
+        .map_err(|e| LoxoneError::connection(format!("Failed to read image: {e}")))?;
+    Ok(LoxoneImage {
+        data: data.to_vec(),
+        mime_type,
+    })
+}
+
+/// Read the `lastModified` version from a `jdev/sps/LoxAPPversion3` response
+pub(crate) async fn read_structure_version(response: reqwest::Response) -> Result<String> {
+    let text = response
+        .text()
+        .await
+        .map_err(|e| LoxoneError::connection(format!("Failed to read structure version: {e}")))?;
+    let value = LoxoneHttpClient::parse_loxone_response(&text).value;
+    // The Miniserver wraps the version in its `LL` response envelope
+    let version = value.pointer("/LL/value").unwrap_or(&value);
+    version
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| LoxoneError::connection(format!("Unexpected structure version: {value}")))
+}
+
+/// Parse a `/stats` listing response
+pub(crate) async fn read_statistics_listing(
+    response: reqwest::Response,
+) -> Result<Vec<StatisticFile>> {
+    let listing = response
+        .text()
+        .await
+        .map_err(|e| LoxoneError::connection(format!("Failed to read statistics list: {e}")))?;
+    Ok(parse_statistics_listing(&listing))
+}
+
+/// Read binary statistics, rejecting the JSON error responses of missing files
+pub(crate) async fn read_statistics(response: reqwest::Response) -> Result<Vec<u8>> {
+    let is_json = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("json"));
+    let data = response
+        .bytes()
+        .await
+        .map_err(|e| LoxoneError::connection(format!("Failed to read statistics: {e}")))?;
+    if is_json {
+        let response = LoxoneHttpClient::parse_loxone_response(&String::from_utf8_lossy(&data));
+        return Err(LoxoneError::not_found(format!(
+            "No statistics available: {}",
+            response.value
+        )));
+    }
+    Ok(data.to_vec())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3,18 +3,28 @@
 pub mod adaptive_pool;
 #[cfg(feature = "crypto-openssl")]
 pub mod auth;
+pub mod binary_protocol;
 pub mod client_factory;
+#[cfg(feature = "crypto-openssl")]
+pub mod command_encryption;
 pub mod command_queue;
+pub mod commands;
 pub mod connection_pool;
+pub mod controls;
 pub mod http_client;
 pub mod load_balancer;
 pub mod pool_health_monitor;
+pub mod statistics;
 pub mod streaming_parser;
 #[cfg(feature = "crypto-openssl")]
+pub mod token_cache;
+#[cfg(feature = "crypto-openssl")]
 pub mod token_http_client;
 #[cfg(feature = "websocket")]
 pub mod websocket_client;
 #[cfg(feature = "websocket")]
+pub mod websocket_handshake;
+#[cfg(feature = "websocket")]
 pub mod websocket_resilience;
 
 pub use adaptive_pool::{
@@ -23,6 +33,8 @@ pub use adaptive_pool::{
 pub use client_factory::{
     AdaptiveClientFactory, ClientFactory, EncryptionLevel, ServerCapabilities, StaticClientFactory,
 };
+pub use commands::{CommandSpec, validate_command};
+pub use controls::{ControlModel, ControlType, LoxoneControl, Mood};
 pub use http_client::LoxoneHttpClient;
 pub use load_balancer::{
     LoadBalancer, LoadBalancingStatistics, LoadBalancingStrategy, WeightMethod,
@@ -31,6 +43,7 @@ pub use pool_health_monitor::{
     AlertThresholds, HealthAlert, HealthMetrics, HealthMonitorConfig, HealthStatus,
     PoolHealthMonitor,
 };
+pub use statistics::{StatisticConfig, StatisticEntry, StatisticFile, StatisticMonth};
 #[cfg(feature = "crypto-openssl")]
 pub use token_http_client::TokenHttpClient;
 #[cfg(feature = "websocket")]
@@ -42,12 +55,12 @@ pub use websocket_resilience::{
 };
 
 use crate::config::{LoxoneConfig, credentials::LoxoneCredentials};
-use crate::error::Result;
+use crate::error::{LoxoneError, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, broadcast};
 
 /// Loxone device information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +107,9 @@ pub struct LoxoneStructure {
     /// Global states (optional, not present in all Loxone versions)
     #[serde(default)]
     pub global_states: HashMap<String, serde_json::Value>,
+    /// Calendar modes of daytimer schedules by ID (weekdays, holidays, ...)
+    #[serde(default, rename = "operatingModes")]
+    pub operating_modes: HashMap<String, serde_json::Value>,
 }
 
 /// Command response from Loxone
@@ -106,6 +122,38 @@ pub struct LoxoneResponse {
     pub value: serde_json::Value,
 }
 
+/// Image served by the Miniserver (camera snapshot or bell event picture)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoxoneImage {
+    /// Raw image bytes
+    pub data: Vec<u8>,
+    /// MIME type reported by the Miniserver (usually `image/jpeg`)
+    pub mime_type: String,
+}
+
+/// Path of an intercom camera image: the live picture or a bell event
+///
+/// Bell event timestamps use the Miniserver's `YYYYMMDDhhmmss` format.
+pub fn camera_image_path(uuid: &str, timestamp: Option<&str>) -> Result<String> {
+    let valid_uuid = !uuid.is_empty()
+        && uuid.len() <= 50
+        && uuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    if !valid_uuid {
+        return Err(LoxoneError::validation(format!(
+            "Invalid Loxone UUID format: {uuid}"
+        )));
+    }
+    match timestamp {
+        Some(ts) if ts.len() == 14 && ts.chars().all(|c| c.is_ascii_digit()) => {
+            Ok(format!("camimage/{uuid}/{ts}"))
+        }
+        Some(ts) => Err(LoxoneError::validation(format!(
+            "Invalid bell event timestamp: {ts}"
+        ))),
+        None => Ok(format!("camimage/{uuid}")),
+    }
+}
+
 /// System capabilities detected from structure
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SystemCapabilities {
@@ -143,6 +191,14 @@ pub trait LoxoneClient: Send + Sync {
     /// Get the structure file (LoxAPP3.json)
     async fn get_structure(&self) -> Result<LoxoneStructure>;
 
+    /// `lastModified` of the current structure file
+    ///
+    /// Clients override this with the cheap `jdev/sps/LoxAPPversion3` request;
+    /// the default downloads the whole structure.
+    async fn get_structure_version(&self) -> Result<String> {
+        Ok(self.get_structure().await?.last_modified)
+    }
+
     /// Get current device states
     async fn get_device_states(
         &self,
@@ -171,10 +227,54 @@ pub trait LoxoneClient: Send + Sync {
     /// Health check
     async fn health_check(&self) -> Result<bool>;
 
+    /// Fetch an intercom camera image through the authenticated HTTP API
+    ///
+    /// Without a timestamp the current picture is returned, otherwise the one
+    /// stored for that bell event (see [`camera_image_path`]).
+    async fn fetch_camera_image(
+        &self,
+        uuid: &str,
+        _timestamp: Option<&str>,
+    ) -> Result<LoxoneImage> {
+        Err(LoxoneError::connection(format!(
+            "Camera images of {uuid} are not available through this client"
+        )))
+    }
+
+    /// List the statistics files stored on the Miniserver
+    async fn list_statistics(&self) -> Result<Vec<StatisticFile>> {
+        Err(LoxoneError::connection(
+            "Statistics are not available through this client",
+        ))
+    }
+
+    /// Download the binary statistics of a control for one month
+    ///
+    /// Decode the data with [`statistics::parse_statistics`].
+    async fn fetch_statistics(&self, uuid: &str, _month: StatisticMonth) -> Result<Vec<u8>> {
+        Err(LoxoneError::connection(format!(
+            "Statistics of {uuid} are not available through this client"
+        )))
+    }
+
+    /// Release server-side resources such as auth tokens on clean shutdown
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
     /// Cast to Any for type checking
     fn as_any(&self) -> &dyn std::any::Any;
 }
 
+/// Location of a state UUID within the structure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateRef {
+    /// UUID of the control (or sub-control) owning the state
+    pub control_uuid: String,
+    /// State name (e.g. "value", "position", "tempActual")
+    pub state_name: String,
+}
+
 /// Shared client context for caching and state management
 #[derive(Debug, Clone)]
 pub struct ClientContext {
@@ -187,6 +287,9 @@ pub struct ClientContext {
     /// Parsed rooms
     pub rooms: Arc<RwLock<HashMap<String, LoxoneRoom>>>,
 
+    /// Typed controls, rebuilt on every structure load
+    pub controls: Arc<RwLock<Option<Arc<ControlModel>>>>,
+
     /// System capabilities
     pub capabilities: Arc<RwLock<SystemCapabilities>>,
 
@@ -198,6 +301,21 @@ pub struct ClientContext {
 
     /// Sensor state logger (optional)
     pub sensor_logger: Arc<RwLock<Option<Arc<crate::services::SensorStateLogger>>>>,
+
+    /// State UUID to owning control/state name, built from the structure
+    pub state_index: Arc<RwLock<HashMap<String, StateRef>>>,
+
+    /// Latest pushed value per state UUID
+    pub state_values: Arc<RwLock<HashMap<String, serde_json::Value>>>,
+
+    /// Latest decoded weather forecast per weather state UUID
+    pub weather_events: Arc<RwLock<HashMap<String, binary_protocol::WeatherEvent>>>,
+
+    /// Broadcasts the UUID of every state whose pushed value changed
+    pub state_changes: broadcast::Sender<String>,
+
+    /// Broadcasts the `lastModified` version of every reloaded structure
+    pub structure_changes: broadcast::Sender<String>,
 }
 
 impl Default for ClientContext {
@@ -206,10 +324,16 @@ impl Default for ClientContext {
             structure: Arc::new(RwLock::new(None)),
             devices: Arc::new(RwLock::new(HashMap::new())),
             rooms: Arc::new(RwLock::new(HashMap::new())),
+            controls: Arc::new(RwLock::new(None)),
             capabilities: Arc::new(RwLock::new(SystemCapabilities::default())),
             connected: Arc::new(RwLock::new(false)),
             last_update: Arc::new(RwLock::new(None)),
             sensor_logger: Arc::new(RwLock::new(None)),
+            state_index: Arc::new(RwLock::new(HashMap::new())),
+            state_values: Arc::new(RwLock::new(HashMap::new())),
+            weather_events: Arc::new(RwLock::new(HashMap::new())),
+            state_changes: broadcast::channel(1024).0,
+            structure_changes: broadcast::channel(16).0,
         }
     }
 }
@@ -226,6 +350,7 @@ impl ClientContext {
         let mut devices = HashMap::new();
         let mut rooms = HashMap::new();
         let mut capabilities = SystemCapabilities::default();
+        let mut state_index = HashMap::new();
 
         // Parse rooms first
         for (uuid, room_data) in &structure.rooms {
@@ -292,6 +417,12 @@ impl ClientContext {
                     })
                     .unwrap_or_default();
 
+                // Index state UUIDs of the control and its sub-controls
+                Self::index_states(&mut state_index, uuid, control_obj.get("states"));
+                for (sub_uuid, sub_control) in &sub_controls {
+                    Self::index_states(&mut state_index, sub_uuid, sub_control.get("states"));
+                }
+
                 // Determine category based on type
                 let category = self.categorize_device(&device_type);
 
@@ -320,16 +451,163 @@ impl ClientContext {
             }
         }
 
-        // Update context
-        *self.structure.write().await = Some(structure);
-        *self.devices.write().await = devices;
-        *self.rooms.write().await = rooms;
-        *self.capabilities.write().await = capabilities;
-        *self.last_update.write().await = Some(chrono::Utc::now());
+        // Global states map a name directly to a state UUID
+        for (name, state_uuid) in &structure.global_states {
+            if let Some(state_uuid) = state_uuid.as_str() {
+                state_index.insert(
+                    state_uuid.to_string(),
+                    StateRef {
+                        control_uuid: state_uuid.to_string(),
+                        state_name: name.clone(),
+                    },
+                );
+            }
+        }
+
+        let controls = ControlModel::from_structure(&structure);
+
+        // Swap everything under all write locks so readers never see a mix
+        // of old and new structure. Locks follow the rooms, devices,
+        // capabilities order used by readers holding several at once.
+        let mut rooms_guard = self.rooms.write().await;
+        let mut devices_guard = self.devices.write().await;
+        let mut capabilities_guard = self.capabilities.write().await;
+        let mut controls_guard = self.controls.write().await;
+        let mut structure_guard = self.structure.write().await;
+        let mut state_index_guard = self.state_index.write().await;
+        let mut last_update_guard = self.last_update.write().await;
+
+        let previous_version = structure_guard
+            .as_ref()
+            .map(|previous| previous.last_modified.clone());
+        let version = structure.last_modified.clone();
+
+        *rooms_guard = rooms;
+        *devices_guard = devices;
+        *capabilities_guard = capabilities;
+        *controls_guard = Some(Arc::new(controls));
+        *structure_guard = Some(structure);
+        *state_index_guard = state_index;
+        *last_update_guard = Some(chrono::Utc::now());
+
+        if previous_version.is_some_and(|previous| previous != version) {
+            // No receivers simply means nobody is watching for changes
+            let _ = self.structure_changes.send(version);
+        }
 
         Ok(())
     }
 
+    /// `lastModified` of the loaded structure
+    pub async fn structure_version(&self) -> Option<String> {
+        self.structure
+            .read()
+            .await
+            .as_ref()
+            .map(|structure| structure.last_modified.clone())
+    }
+
+    /// Reload the structure if the Miniserver reports a different version
+    ///
+    /// Only the `lastModified` version is requested unless it changed, so this
+    /// is cheap enough to poll. Returns whether a new structure was loaded.
+    pub async fn refresh_structure(&self, client: &dyn LoxoneClient) -> Result<bool> {
+        let version = client.get_structure_version().await?;
+        if self.structure_version().await.as_deref() == Some(version.as_str()) {
+            return Ok(false);
+        }
+
+        let structure = client.get_structure().await?;
+        tracing::info!(
+            "Structure changed (lastModified {}), reloading {} controls",
+            structure.last_modified,
+            structure.controls.len()
+        );
+        self.update_structure(structure).await?;
+        Ok(true)
+    }
+
+    /// Subscribe to the `lastModified` version of every reloaded structure
+    ///
+    /// Only replacements of an already loaded structure are sent, not the
+    /// initial load.
+    pub fn subscribe_structure_changes(&self) -> broadcast::Receiver<String> {
+        self.structure_changes.subscribe()
+    }
+
+    /// Typed controls of the last loaded structure
+    pub async fn control_model(&self) -> Option<Arc<ControlModel>> {
+        self.controls.read().await.clone()
+    }
+
+    /// Add the state UUIDs of a control's `states` object to the index
+    fn index_states(
+        index: &mut HashMap<String, StateRef>,
+        control_uuid: &str,
+        states: Option<&serde_json::Value>,
+    ) {
+        let Some(states) = states.and_then(|v| v.as_object()) else {
+            return;
+        };
+        for (state_name, state_uuid) in states {
+            if let Some(state_uuid) = state_uuid.as_str() {
+                index.insert(
+                    state_uuid.to_string(),
+                    StateRef {
+                        control_uuid: control_uuid.to_string(),
+                        state_name: state_name.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Resolve a state UUID to its owning control and state name
+    pub async fn resolve_state(&self, state_uuid: &str) -> Option<StateRef> {
+        self.state_index.read().await.get(state_uuid).cloned()
+    }
+
+    /// Record a pushed state value, returning the previous value
+    pub async fn record_state_value(
+        &self,
+        state_uuid: &str,
+        value: serde_json::Value,
+    ) -> Option<serde_json::Value> {
+        let previous = self
+            .state_values
+            .write()
+            .await
+            .insert(state_uuid.to_string(), value.clone());
+        if previous.as_ref() != Some(&value) {
+            // No receivers simply means nobody is watching for changes
+            let _ = self.state_changes.send(state_uuid.to_string());
+        }
+        previous
+    }
+
+    /// Subscribe to the UUIDs of states whose pushed value changed
+    pub fn subscribe_state_changes(&self) -> broadcast::Receiver<String> {
+        self.state_changes.subscribe()
+    }
+
+    /// Get the latest pushed value for a state UUID
+    pub async fn get_state_value(&self, state_uuid: &str) -> Option<serde_json::Value> {
+        self.state_values.read().await.get(state_uuid).cloned()
+    }
+
+    /// Keep a decoded weather forecast, replacing the previous one of its state
+    pub async fn record_weather_event(&self, event: &binary_protocol::WeatherEvent) {
+        self.weather_events
+            .write()
+            .await
+            .insert(event.uuid.clone(), event.clone());
+    }
+
+    /// Latest decoded weather forecasts, one per weather state
+    pub async fn weather_events(&self) -> Vec<binary_protocol::WeatherEvent> {
+        self.weather_events.read().await.values().cloned().collect()
+    }
+
     /// Categorize device based on type
     fn categorize_device(&self, device_type: &str) -> String {
         match device_type.to_lowercase().as_str() {
@@ -554,39 +832,64 @@ pub async fn create_hybrid_client(
     use crate::client::websocket_client::LoxoneWebSocketClient;
     use std::sync::Arc;
 
-    // We need to create the concrete HTTP client directly to avoid Box<dyn> issues
-    let http_client: Arc<dyn LoxoneClient> = match config.auth_method {
+    // The HTTP half serves structure and commands, so it must be connected
+    // before it is shared with the WebSocket client
+    let mut http_client: Box<dyn LoxoneClient> = match config.auth_method {
         crate::config::AuthMethod::Token => {
             #[cfg(feature = "crypto-openssl")]
             {
                 use crate::client::token_http_client::TokenHttpClient;
-                Arc::new(TokenHttpClient::new(config.clone(), credentials.clone()).await?)
+                Box::new(TokenHttpClient::new(config.clone(), credentials.clone()).await?)
             }
             #[cfg(not(feature = "crypto-openssl"))]
             {
                 tracing::warn!(
                     "Token authentication requested but crypto feature is disabled, falling back to basic auth"
                 );
-                Arc::new(
+                Box::new(
                     http_client::LoxoneHttpClient::new(config.clone(), credentials.clone()).await?,
                 )
             }
         }
         crate::config::AuthMethod::Basic => {
-            Arc::new(http_client::LoxoneHttpClient::new(config.clone(), credentials.clone()).await?)
+            Box::new(http_client::LoxoneHttpClient::new(config.clone(), credentials.clone()).await?)
         }
         #[cfg(feature = "websocket")]
         crate::config::AuthMethod::WebSocket => {
             // For hybrid client, use basic HTTP for structure loading
-            Arc::new(http_client::LoxoneHttpClient::new(config.clone(), credentials.clone()).await?)
+            Box::new(http_client::LoxoneHttpClient::new(config.clone(), credentials.clone()).await?)
         }
     };
+    http_client.connect().await?;
+    let http_client: Arc<dyn LoxoneClient> = Arc::from(http_client);
 
     // Create WebSocket client with HTTP client for hybrid operation
     LoxoneWebSocketClient::new_with_http_client(config.clone(), credentials.clone(), http_client)
         .await
 }
 
+/// Reload the structure into the context whenever its version changes
+///
+/// Every `interval` the `lastModified` version is compared with the loaded
+/// structure (see [`ClientContext::refresh_structure`]); subscribers of
+/// [`ClientContext::subscribe_structure_changes`] are told about reloads.
+pub fn watch_structure(
+    client: Arc<dyn LoxoneClient>,
+    context: Arc<ClientContext>,
+    interval: std::time::Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = context.refresh_structure(client.as_ref()).await {
+                tracing::debug!("Structure version check failed: {}", e);
+            }
+        }
+    })
+}
+
 /// Create standalone WebSocket client (for real-time monitoring only)
 #[cfg(feature = "websocket")]
 pub async fn create_websocket_client(
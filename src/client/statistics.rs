@@ -0,0 +1,476 @@
+//! Miniserver statistics: structure metadata, file listing and binary format
+//!
+//! Controls with statistics enabled carry a `statistic` object in the
+//! structure file with the recording frequency and the recorded outputs. The
+//! Miniserver keeps one statistics file per control and month, listed by
+//! `/stats` and served in binary form by `binstatisticdata/{uuid}/{yyyymm}`
+//! (the same format Loxone Config exports as `.LoxStat`):
+//!
+//! ```text
+//! +-------------+--------------------+---------------------------+
+//! | control UUID | timestamp (LE u32) | one f64 (LE) per output   |
+//! | 16 bytes     | 4 bytes            | 8 bytes x output count    |
+//! +-------------+--------------------+---------------------------+
+//! ```
+//!
+//! Timestamps count seconds since the Loxone epoch in Miniserver time.
+
+use super::LoxoneClient;
+use super::binary_protocol::{LOXONE_EPOCH_UNIX, UUID_LEN, format_uuid};
+use crate::error::{LoxoneError, Result};
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Size of an entry without its values
+const ENTRY_HEADER_LEN: usize = UUID_LEN + 4;
+
+/// One recorded output of a control
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatisticOutput {
+    /// Position of the output's value in each entry
+    pub id: u32,
+    /// Display name (e.g. `"Total"`)
+    pub name: String,
+    /// Value format (e.g. `"%.1fkWh"`)
+    pub format: Option<String>,
+    /// State UUID the output records, if the structure names it
+    pub uuid: Option<String>,
+}
+
+/// `statistic` object of a control in the structure file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatisticConfig {
+    /// Recording frequency as configured in Loxone Config
+    pub frequency: u32,
+    /// Recorded outputs ordered by ID
+    pub outputs: Vec<StatisticOutput>,
+}
+
+impl StatisticConfig {
+    /// Parse a `statistic` object; `None` if statistics are disabled
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let frequency = value.get("frequency").and_then(Value::as_u64)? as u32;
+        if frequency == 0 {
+            return None;
+        }
+        let mut outputs: Vec<StatisticOutput> = value
+            .get("outputs")
+            .and_then(Value::as_array)
+            .map(|outputs| {
+                outputs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, output)| {
+                        let text =
+                            |key: &str| output.get(key).and_then(Value::as_str).map(str::to_string);
+                        StatisticOutput {
+                            id: output
+                                .get("id")
+                                .and_then(Value::as_u64)
+                                .map_or(index as u32, |id| id as u32),
+                            name: text("name").unwrap_or_else(|| format!("Output {index}")),
+                            format: text("format"),
+                            uuid: text("uuid"),
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        outputs.sort_by_key(|output| output.id);
+        Some(Self { frequency, outputs })
+    }
+
+    /// Human readable recording frequency
+    pub fn frequency_label(&self) -> &'static str {
+        match self.frequency {
+            1 => "every change",
+            2 => "average per minute",
+            3 => "average per 5 minutes",
+            4 => "average per 10 minutes",
+            5 => "average per 30 minutes",
+            6 => "average per hour",
+            7 => "every change (digital)",
+            _ => "unknown",
+        }
+    }
+
+    /// Minimum seconds between two recorded entries
+    pub fn interval_seconds(&self) -> u32 {
+        match self.frequency {
+            3 => 300,
+            4 => 600,
+            5 => 1800,
+            6 => 3600,
+            _ => 60,
+        }
+    }
+
+    /// Output by ID or case-insensitive name
+    pub fn output(&self, query: &str) -> Option<&StatisticOutput> {
+        self.outputs.iter().find(|output| {
+            output.id.to_string() == query || output.name.eq_ignore_ascii_case(query)
+        })
+    }
+}
+
+/// Statistics file of one control and month
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StatisticMonth {
+    /// Year, e.g. 2024
+    pub year: i32,
+    /// Month, 1-12
+    pub month: u32,
+}
+
+impl StatisticMonth {
+    /// Month of a timestamp
+    pub fn of(timestamp: DateTime<Utc>) -> Self {
+        Self {
+            year: timestamp.year(),
+            month: timestamp.month(),
+        }
+    }
+
+    /// Parse the `yyyymm` form used in file names
+    pub fn parse(text: &str) -> Option<Self> {
+        if text.len() != 6 || !text.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let year = text[..4].parse().ok()?;
+        let month = text[4..].parse().ok()?;
+        (1..=12).contains(&month).then_some(Self { year, month })
+    }
+
+    /// The following month
+    pub fn next(self) -> Self {
+        if self.month == 12 {
+            Self {
+                year: self.year + 1,
+                month: 1,
+            }
+        } else {
+            Self {
+                year: self.year,
+                month: self.month + 1,
+            }
+        }
+    }
+
+    /// Months from `from` to `to`, both inclusive
+    pub fn range(from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Self> {
+        let last = Self::of(to);
+        let mut month = Self::of(from);
+        let mut months = Vec::new();
+        while month <= last {
+            months.push(month);
+            month = month.next();
+        }
+        months
+    }
+}
+
+impl std::fmt::Display for StatisticMonth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}{:02}", self.year, self.month)
+    }
+}
+
+/// Statistics file available on the Miniserver
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StatisticFile {
+    /// Control UUID
+    pub control_uuid: String,
+    /// Recorded month
+    pub month: StatisticMonth,
+}
+
+/// Parse the `/stats` listing into files, sorted by control and month
+///
+/// The Miniserver answers with an HTML page linking `{uuid}.{yyyymm}` files
+/// (with an `.xml` suffix on older firmware); anything else is ignored.
+pub fn parse_statistics_listing(listing: &str) -> Vec<StatisticFile> {
+    let files: BTreeSet<StatisticFile> = listing
+        .split(|c: char| !(c.is_ascii_hexdigit() || c == '-' || c == '.'))
+        .filter_map(|token| {
+            let mut parts = token.split('.');
+            let (uuid, month) = (parts.next()?, parts.next()?);
+            let valid_uuid = uuid.len() == 35 && uuid.matches('-').count() == 3;
+            Some(StatisticFile {
+                control_uuid: valid_uuid.then(|| uuid.to_string())?,
+                month: StatisticMonth::parse(month)?,
+            })
+        })
+        .collect();
+    files.into_iter().collect()
+}
+
+/// Path of the binary statistics of a control for one month
+pub fn statistics_path(uuid: &str, month: StatisticMonth) -> Result<String> {
+    let valid_uuid = !uuid.is_empty()
+        && uuid.len() <= 50
+        && uuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    if !valid_uuid {
+        return Err(LoxoneError::validation(format!(
+            "Invalid Loxone UUID format: {uuid}"
+        )));
+    }
+    Ok(format!("binstatisticdata/{uuid}/{month}"))
+}
+
+/// One recorded statistics entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatisticEntry {
+    /// Recording time
+    pub timestamp: DateTime<Utc>,
+    /// Output values ordered by output ID
+    pub values: Vec<f64>,
+}
+
+/// Convert a statistics timestamp to a date
+pub fn statistics_time(seconds: u32) -> DateTime<Utc> {
+    Utc.timestamp_opt(LOXONE_EPOCH_UNIX + i64::from(seconds), 0)
+        .single()
+        .unwrap_or_default()
+}
+
+/// Seconds since the Loxone epoch; dates before it clamp to zero
+pub fn loxone_seconds(timestamp: DateTime<Utc>) -> u32 {
+    u32::try_from(timestamp.timestamp() - LOXONE_EPOCH_UNIX).unwrap_or(0)
+}
+
+/// Number of outputs per entry, derived from where the control UUID repeats
+///
+/// Used for exported `.LoxStat` files read without their structure file.
+pub fn infer_output_count(data: &[u8]) -> Result<usize> {
+    if data.len() < ENTRY_HEADER_LEN {
+        return Err(LoxoneError::parsing_error(
+            "Statistics data is shorter than one entry",
+        ));
+    }
+    let uuid = &data[..UUID_LEN];
+    let entry_len = (ENTRY_HEADER_LEN..data.len())
+        .step_by(8)
+        .find(|&offset| data[offset..].starts_with(uuid))
+        .unwrap_or(data.len());
+    if !(entry_len - ENTRY_HEADER_LEN).is_multiple_of(8) || entry_len == ENTRY_HEADER_LEN {
+        return Err(LoxoneError::parsing_error(format!(
+            "Cannot derive the output count from a {entry_len} byte entry"
+        )));
+    }
+    Ok((entry_len - ENTRY_HEADER_LEN) / 8)
+}
+
+/// Decode binary statistics with `output_count` values per entry
+pub fn parse_statistics(data: &[u8], output_count: usize) -> Result<Vec<StatisticEntry>> {
+    let entry_len = ENTRY_HEADER_LEN + 8 * output_count;
+    if output_count == 0 || !data.len().is_multiple_of(entry_len) {
+        return Err(LoxoneError::parsing_error(format!(
+            "Statistics length {} does not fit entries of {output_count} outputs",
+            data.len()
+        )));
+    }
+
+    let mut expected_uuid = None;
+    data.chunks_exact(entry_len)
+        .map(|entry| {
+            let mut uuid = [0u8; UUID_LEN];
+            uuid.copy_from_slice(&entry[..UUID_LEN]);
+            if *expected_uuid.get_or_insert(uuid) != uuid {
+                return Err(LoxoneError::parsing_error(format!(
+                    "Statistics entry of {} in a file of {}",
+                    format_uuid(&uuid),
+                    format_uuid(&expected_uuid.unwrap_or(uuid))
+                )));
+            }
+            let seconds = u32::from_le_bytes([entry[16], entry[17], entry[18], entry[19]]);
+            let values = entry[ENTRY_HEADER_LEN..]
+                .as_chunks::<8>()
+                .0
+                .iter()
+                .map(|value| f64::from_le_bytes(*value))
+                .collect();
+            Ok(StatisticEntry {
+                timestamp: statistics_time(seconds),
+                values,
+            })
+        })
+        .collect()
+}
+
+/// Decode an exported `.LoxStat` file into its control UUID and entries
+pub fn parse_loxstat(data: &[u8]) -> Result<(String, Vec<StatisticEntry>)> {
+    let entries = parse_statistics(data, infer_output_count(data)?)?;
+    let mut uuid = [0u8; UUID_LEN];
+    uuid.copy_from_slice(&data[..UUID_LEN]);
+    Ok((format_uuid(&uuid), entries))
+}
+
+/// Entries of a control in `[from, to]`, downloaded month by month
+///
+/// Only months the Miniserver lists are fetched. With `output_count` 0 the
+/// count is derived from the data.
+pub async fn load_statistics(
+    client: &dyn LoxoneClient,
+    control_uuid: &str,
+    output_count: usize,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<StatisticEntry>> {
+    let months = StatisticMonth::range(from, to);
+    let files = client.list_statistics().await?;
+    let mut entries = Vec::new();
+    for file in files
+        .iter()
+        .filter(|file| file.control_uuid == control_uuid && months.contains(&file.month))
+    {
+        let data = client.fetch_statistics(control_uuid, file.month).await?;
+        let count = match output_count {
+            0 => infer_output_count(&data)?,
+            count => count,
+        };
+        entries.extend(
+            parse_statistics(&data, count)?
+                .into_iter()
+                .filter(|entry| entry.timestamp >= from && entry.timestamp <= to),
+        );
+    }
+    entries.sort_by_key(|entry| entry.timestamp);
+    Ok(entries)
+}
+
+/// Encode entries of a control in the binary statistics format
+pub fn encode_statistics(control_uuid: &str, entries: &[StatisticEntry]) -> Result<Vec<u8>> {
+    let uuid = super::binary_protocol::parse_uuid(control_uuid)?;
+    let mut data = Vec::new();
+    for entry in entries {
+        data.extend_from_slice(&uuid);
+        data.extend_from_slice(&loxone_seconds(entry.timestamp).to_le_bytes());
+        for value in &entry.values {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const UUID: &str = "1c8f8a16-0300-0004-ffff000000000000";
+
+    fn entry(minutes: i64, values: &[f64]) -> StatisticEntry {
+        StatisticEntry {
+            timestamp: statistics_time(500_000_000) + chrono::Duration::minutes(minutes),
+            values: values.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_statistic_config() {
+        let config = StatisticConfig::from_value(&json!({
+            "frequency": 3,
+            "outputs": [
+                {"id": 1, "name": "Target", "format": "%.1f°"},
+                {"id": 0, "name": "Actual", "format": "%.1f°", "uuid": "abc"}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(config.interval_seconds(), 300);
+        assert_eq!(config.frequency_label(), "average per 5 minutes");
+        assert_eq!(config.outputs[0].name, "Actual");
+        assert_eq!(config.outputs[0].uuid.as_deref(), Some("abc"));
+        assert_eq!(config.output("target").unwrap().id, 1);
+        assert_eq!(config.output("0").unwrap().name, "Actual");
+
+        assert!(StatisticConfig::from_value(&json!({"frequency": 0, "outputs": []})).is_none());
+    }
+
+    #[test]
+    fn test_months() {
+        assert_eq!(
+            StatisticMonth::parse("202412"),
+            Some(StatisticMonth {
+                year: 2024,
+                month: 12
+            })
+        );
+        assert_eq!(StatisticMonth::parse("202413"), None);
+        assert_eq!(StatisticMonth::parse("2024-1"), None);
+
+        let from = "2024-11-20T00:00:00Z".parse().unwrap();
+        let to = "2025-02-01T00:00:00Z".parse().unwrap();
+        let months: Vec<String> = StatisticMonth::range(from, to)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(months, ["202411", "202412", "202501", "202502"]);
+    }
+
+    #[test]
+    fn test_listing() {
+        let listing = format!(
+            "<html><body>\
+             <a href=\"{UUID}.202502.xml\">{UUID}.202502.xml</a><br>\
+             <a href=\"{UUID}.202501\">{UUID}.202501</a><br>\
+             <a href=\"index.html\">back</a>\
+             </body></html>"
+        );
+        let files = parse_statistics_listing(&listing);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].control_uuid, UUID);
+        assert_eq!(files[0].month.to_string(), "202501");
+        assert_eq!(files[1].month.to_string(), "202502");
+
+        assert_eq!(
+            statistics_path(UUID, files[0].month).unwrap(),
+            format!("binstatisticdata/{UUID}/202501")
+        );
+        assert!(statistics_path("../stats", files[0].month).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let entries = vec![entry(0, &[21.5, 22.0]), entry(5, &[21.75, 22.0])];
+        let data = encode_statistics(UUID, &entries).unwrap();
+        assert_eq!(data.len(), 2 * (20 + 16));
+        assert_eq!(infer_output_count(&data).unwrap(), 2);
+        assert_eq!(parse_statistics(&data, 2).unwrap(), entries);
+
+        // A single entry has no second UUID to measure against
+        let single = encode_statistics(UUID, &entries[..1]).unwrap();
+        assert_eq!(infer_output_count(&single).unwrap(), 2);
+
+        let (uuid, exported) = parse_loxstat(&data).unwrap();
+        assert_eq!(uuid, UUID);
+        assert_eq!(exported, entries);
+    }
+
+    #[test]
+    fn test_invalid_statistics() {
+        let data = encode_statistics(UUID, &[entry(0, &[1.0, 2.0])]).unwrap();
+        assert!(parse_statistics(&data, 3).is_err());
+        assert!(parse_statistics(&data[..30], 1).is_err());
+        assert!(infer_output_count(&data[..10]).is_err());
+
+        let mut mixed = data.clone();
+        mixed.extend(
+            encode_statistics(
+                "1c8f8a16-0300-0007-ffff000000000000",
+                &[entry(1, &[1.0, 2.0])],
+            )
+            .unwrap(),
+        );
+        assert!(parse_statistics(&mixed, 2).is_err());
+    }
+
+    #[test]
+    fn test_epoch_conversion() {
+        let timestamp = "2024-06-01T12:00:00Z".parse().unwrap();
+        assert_eq!(statistics_time(loxone_seconds(timestamp)), timestamp);
+        assert_eq!(statistics_time(0).to_rfc3339(), "2009-01-01T00:00:00+00:00");
+        assert_eq!(loxone_seconds("2000-01-01T00:00:00Z".parse().unwrap()), 0);
+    }
+}
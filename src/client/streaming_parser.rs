@@ -133,6 +133,7 @@ struct PartialStructure {
     rooms: HashMap<String, Value>,
     cats: HashMap<String, Value>,
     global_states: HashMap<String, Value>,
+    operating_modes: HashMap<String, Value>,
     #[allow(dead_code)]
     total_size: usize,
 }
@@ -293,6 +294,11 @@ impl StreamingStructureParser {
             {
                 items_parsed += self.parse_global_states_section(gs_obj.clone()).await?;
             }
+
+            // A handful of calendar names, kept with any section
+            if let Some(Value::Object(modes)) = obj.get("operatingModes") {
+                self.parsed_structure.operating_modes = modes.clone().into_iter().collect();
+            }
         }
 
         // Clear buffer after successful parse
@@ -452,6 +458,7 @@ impl StreamingStructureParser {
             rooms: self.parsed_structure.rooms.clone(),
             cats: self.parsed_structure.cats.clone(),
             global_states: self.parsed_structure.global_states.clone(),
+            operating_modes: self.parsed_structure.operating_modes.clone(),
         })
     }
 
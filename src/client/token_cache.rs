@@ -0,0 +1,230 @@
+//! Encrypted on-disk cache for Loxone JWT tokens
+//!
+//! Tokens are stored per credential ID so a restart after a crash, or after
+//! any shutdown with `reuse_token` set, can reuse (and refresh) the token
+//! instead of repeating the RSA/HMAC handshake, which would occupy another
+//! token slot on the Miniserver. Entries are sealed with AES-256-GCM
+//! under the PulseEngine master key (see [`crate::config::master_key`]).
+
+use crate::client::auth::CachedToken;
+use crate::error::{LoxoneError, Result};
+use openssl::symm::{Cipher, decrypt_aead, encrypt_aead};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+/// Subdirectory of the `loxone-mcp` config directory holding cached tokens
+const TOKEN_DIR: &str = "tokens";
+
+/// AES-GCM nonce length
+const NONCE_LEN: usize = 12;
+
+/// AES-GCM authentication tag length
+const TAG_LEN: usize = 16;
+
+/// Encrypted token store keyed by credential ID
+pub struct TokenCache {
+    dir: PathBuf,
+    key: [u8; 32],
+}
+
+impl TokenCache {
+    /// Create a cache in `dir` sealed with `key`
+    pub fn new(dir: impl Into<PathBuf>, key: [u8; 32]) -> Self {
+        Self {
+            dir: dir.into(),
+            key,
+        }
+    }
+
+    /// Open the cache in the user config directory using the master key
+    pub fn open_default() -> Result<Self> {
+        let key = crate::config::master_key::master_key_bytes()
+            .map_err(|e| LoxoneError::config(format!("Master key unavailable: {e}")))?;
+        let dir = crate::config::master_key::config_dir()
+            .map_err(|e| LoxoneError::config(e.to_string()))?
+            .join(TOKEN_DIR);
+        Ok(Self::new(dir, key))
+    }
+
+    /// Load the cached token for a credential ID, if present and readable
+    pub fn load(&self, credential_id: &str) -> Option<CachedToken> {
+        let path = self.path_for(credential_id);
+        let sealed = fs::read(&path).ok()?;
+
+        match self.open(credential_id, &sealed) {
+            Ok(token) => Some(token),
+            Err(e) => {
+                // A rotated master key or a corrupt file just means a fresh login
+                warn!(
+                    "Discarding unreadable token cache {}: {}",
+                    path.display(),
+                    e
+                );
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Store the token for a credential ID
+    pub fn store(&self, credential_id: &str, token: &CachedToken) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(credential_id);
+        let sealed = self.seal(credential_id, token)?;
+
+        // Readable by the owner only from the moment the file exists
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // An older file keeps its mode on open, so tighten it before writing
+            if path.exists() {
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        options.open(&path)?.write_all(&sealed)?;
+
+        debug!("Cached token for credential {}", credential_id);
+        Ok(())
+    }
+
+    /// Remove the cached token for a credential ID
+    pub fn remove(&self, credential_id: &str) -> Result<()> {
+        match fs::remove_file(self.path_for(credential_id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// File name derived from the credential ID, so IDs never reach the path verbatim
+    fn path_for(&self, credential_id: &str) -> PathBuf {
+        let digest = Sha256::digest(credential_id.as_bytes());
+        self.dir
+            .join(format!("{}.token", hex::encode(&digest[..16])))
+    }
+
+    /// Encrypt as `nonce || ciphertext || tag`, binding the credential ID as AAD
+    fn seal(&self, credential_id: &str, token: &CachedToken) -> Result<Vec<u8>> {
+        let plaintext = serde_json::to_vec(token)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::RngCore::fill_bytes(&mut rand::rng(), &mut nonce);
+
+        let mut tag = [0u8; TAG_LEN];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key,
+            Some(&nonce),
+            credential_id.as_bytes(),
+            &plaintext,
+            &mut tag,
+        )
+        .map_err(|e| LoxoneError::crypto(format!("Token cache encryption failed: {e}")))?;
+
+        Ok([nonce.as_slice(), &ciphertext, &tag].concat())
+    }
+
+    fn open(&self, credential_id: &str, sealed: &[u8]) -> Result<CachedToken> {
+        if sealed.len() < NONCE_LEN + TAG_LEN {
+            return Err(LoxoneError::crypto("Token cache entry is truncated"));
+        }
+        let (nonce, rest) = sealed.split_at(NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+
+        let plaintext = decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.key,
+            Some(nonce),
+            credential_id.as_bytes(),
+            ciphertext,
+            tag,
+        )
+        .map_err(|e| LoxoneError::crypto(format!("Token cache decryption failed: {e}")))?;
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::auth::AuthToken;
+
+    fn cached_token() -> CachedToken {
+        CachedToken {
+            username: "admin".to_string(),
+            hash_alg: "SHA256".to_string(),
+            token: AuthToken {
+                token: "jwt-token".to_string(),
+                key: String::new(),
+                salt: String::new(),
+                valid_until: 500_000_000,
+                token_rights: 4,
+                unsecure_pass: false,
+            },
+        }
+    }
+
+    #[test]
+    fn test_round_trip_is_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = TokenCache::new(dir.path(), [1u8; 32]);
+
+        cache.store("home", &cached_token()).unwrap();
+
+        let raw = fs::read(cache.path_for("home")).unwrap();
+        assert!(!raw.windows(9).any(|w| w == b"jwt-token"));
+
+        let loaded = cache.load("home").unwrap();
+        assert_eq!(loaded.token.token, "jwt-token");
+        assert_eq!(loaded.hash_alg, "SHA256");
+        assert!(cache.load("other").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_entry_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = TokenCache::new(dir.path(), [1u8; 32]);
+        cache.store("home", &cached_token()).unwrap();
+
+        let path = cache.path_for("home");
+        let mode = |path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+
+        // A replaced entry that was readable by others is tightened first
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        cache.store("home", &cached_token()).unwrap();
+        assert_eq!(mode(&path), 0o600);
+    }
+
+    #[test]
+    fn test_wrong_key_discards_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        TokenCache::new(dir.path(), [1u8; 32])
+            .store("home", &cached_token())
+            .unwrap();
+
+        let rotated = TokenCache::new(dir.path(), [2u8; 32]);
+        assert!(rotated.load("home").is_none());
+        assert!(!rotated.path_for("home").exists());
+    }
+
+    #[test]
+    fn test_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = TokenCache::new(dir.path(), [1u8; 32]);
+        cache.store("home", &cached_token()).unwrap();
+
+        cache.remove("home").unwrap();
+        assert!(cache.load("home").is_none());
+        // Removing a missing entry is not an error
+        cache.remove("home").unwrap();
+    }
+}
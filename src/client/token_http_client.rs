@@ -4,13 +4,18 @@
 //! Miniservers using token-based authentication (recommended for V9+).
 
 // Token validation removed with custom auth - using simpler validation
+use crate::client::command_encryption::{CommandEncryptor, fetch_public_key};
 use crate::client::{
-    ClientContext, LoxoneClient, LoxoneDevice, LoxoneResponse, LoxoneStructure,
+    ClientContext, LoxoneClient, LoxoneDevice, LoxoneImage, LoxoneResponse, LoxoneStructure,
+    StatisticFile, StatisticMonth,
     auth::TokenAuthClient,
+    camera_image_path,
     command_queue::{CommandPriority, CommandQueue, QueuedCommand},
     connection_pool::{ConnectionPool, PoolBuilder},
+    statistics::statistics_path,
+    token_cache::TokenCache,
 };
-use crate::config::{LoxoneConfig, credentials::LoxoneCredentials};
+use crate::config::{CommandEncryption, LoxoneConfig, credentials::LoxoneCredentials};
 use crate::error::{LoxoneError, Result};
 use crate::mcp_consent::{ConsentDecision, ConsentManager, OperationType};
 use async_trait::async_trait;
@@ -20,9 +25,68 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 use url::Url;
 
+/// Renew tokens once they are this close to expiry
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(3600);
+
+/// Lower bound between two renewals, so short-lived tokens cannot cause a refresh storm
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Delay until the background refresher should renew a token with `remaining` validity
+///
+/// Renews [`TOKEN_REFRESH_MARGIN`] before expiry, or at half-life for tokens
+/// that are shorter-lived than the margin.
+pub fn refresh_delay(remaining: Duration) -> Duration {
+    remaining
+        .saturating_sub(TOKEN_REFRESH_MARGIN)
+        .max(remaining / 2)
+        .max(MIN_REFRESH_INTERVAL)
+}
+
+/// Token cache entry the client persists to
+#[derive(Clone)]
+struct TokenPersistence {
+    cache: Arc<TokenCache>,
+    credential_id: String,
+}
+
+impl TokenPersistence {
+    fn save(&self, auth: &TokenAuthClient) {
+        if let Some(snapshot) = auth.snapshot()
+            && let Err(e) = self.cache.store(&self.credential_id, &snapshot)
+        {
+            warn!("Failed to persist token: {}", e);
+        }
+    }
+
+    fn remove(&self) {
+        if let Err(e) = self.cache.remove(&self.credential_id) {
+            warn!("Failed to remove cached token: {}", e);
+        }
+    }
+}
+
+/// Refresh the token via `refreshjwt`, re-authenticating if that fails
+async fn renew_token(
+    auth_client: &RwLock<TokenAuthClient>,
+    credentials: &LoxoneCredentials,
+    persistence: Option<&TokenPersistence>,
+) -> Result<()> {
+    let mut auth = auth_client.write().await;
+    if let Err(e) = auth.refresh_token().await {
+        warn!("Token refresh failed, re-authenticating: {}", e);
+        auth.authenticate(&credentials.username, &credentials.password)
+            .await?;
+    }
+    if let Some(persistence) = persistence {
+        persistence.save(&auth);
+    }
+    Ok(())
+}
+
 /// Validate a Loxone UUID format.
 ///
 /// Loxone UUIDs typically follow the pattern `XXXXXXXX-XXXX-XXXX-XXXX` (hex chars with dashes),
@@ -80,11 +144,45 @@ pub struct TokenHttpClient {
 
     /// Command queue for handling commands during disconnection
     command_queue: Option<Arc<CommandQueue>>,
+
+    /// Encrypted token cache entry, when the connection has a credential ID
+    persistence: Option<TokenPersistence>,
+
+    /// Background task renewing the token before it expires
+    refresh_task: Option<JoinHandle<()>>,
+
+    /// Command encryption (`jdev/sys/enc`), set up on connect if configured
+    command_encryption: Option<Arc<CommandEncryptor>>,
 }
 
 impl TokenHttpClient {
     /// Create a new token-based HTTP client
+    ///
+    /// When `config.credential_id` is set, tokens are cached (encrypted) in the
+    /// user config directory. They are reused after a crash, and after a clean
+    /// shutdown only if `config.reuse_token` is set.
     pub async fn new(config: LoxoneConfig, credentials: LoxoneCredentials) -> Result<Self> {
+        let cache = match &config.credential_id {
+            Some(_) => match TokenCache::open_default() {
+                Ok(cache) => Some(cache),
+                Err(e) => {
+                    warn!("Token cache unavailable, tokens will not persist: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        Self::with_token_cache(config, credentials, cache).await
+    }
+
+    /// Create a client that persists its token in the given cache
+    ///
+    /// The cache is keyed by `config.credential_id` and ignored without one.
+    pub async fn with_token_cache(
+        config: LoxoneConfig,
+        credentials: LoxoneCredentials,
+        cache: Option<TokenCache>,
+    ) -> Result<Self> {
         // Build HTTP client without default auth headers
         let mut client_builder = ClientBuilder::new()
             .timeout(config.timeout)
@@ -128,8 +226,30 @@ impl TokenHttpClient {
             last_refresh: Arc::new(RwLock::new(None)),
             consent_manager: None,
             command_queue: None,
+            persistence: None,
+            refresh_task: None,
+            command_encryption: None,
         };
 
+        let mut client = client;
+        if let (Some(cache), Some(credential_id)) = (cache, client.config.credential_id.clone()) {
+            let persistence = TokenPersistence {
+                cache: Arc::new(cache),
+                credential_id,
+            };
+            if let Some(cached) = persistence.cache.load(&persistence.credential_id) {
+                if cached.username == client.credentials.username
+                    && !cached.token.remaining().is_zero()
+                {
+                    info!("♻️ Reusing cached token for {}", cached.username);
+                    client.auth_client.write().await.restore(cached);
+                } else {
+                    debug!("Cached token expired or for another user, ignoring");
+                }
+            }
+            client.persistence = Some(persistence);
+        }
+
         // Test authentication during construction to enable fallback
         client.ensure_authenticated().await?;
 
@@ -159,12 +279,12 @@ impl TokenHttpClient {
 
     /// Ensure we have a valid authentication token
     async fn ensure_authenticated(&self) -> Result<()> {
-        let auth = self.auth_client.write().await;
+        // The lock is released before validating or authenticating, both of
+        // which take it again
+        let authenticated = self.auth_client.read().await.is_authenticated();
 
         // First check with enhanced validation
-        let needs_auth = if auth.is_authenticated() {
-            // Drop the write lock temporarily to call validate_current_token
-            drop(auth);
+        let needs_auth = if authenticated {
             !self.validate_current_token(None).await?
         } else {
             true
@@ -183,34 +303,97 @@ impl TokenHttpClient {
                     return Err(e);
                 }
             }
+            if let Some(persistence) = &self.persistence {
+                persistence.save(&auth);
+            }
             *self.last_refresh.write().await = Some(std::time::Instant::now());
         } else {
-            // Check if we should refresh the token proactively
-            let should_refresh = {
-                let last_refresh = self.last_refresh.read().await;
-                match *last_refresh {
-                    Some(time) => time.elapsed() > Duration::from_secs(3600), // Refresh every hour
-                    None => true,
-                }
-            };
+            // Renew tokens close to expiry in case the background refresher is not running
+            let expiring = self
+                .auth_client
+                .read()
+                .await
+                .get_token()
+                .is_some_and(|token| token.remaining() < TOKEN_REFRESH_MARGIN);
+            let recently_refreshed = self
+                .last_refresh
+                .read()
+                .await
+                .is_some_and(|time| time.elapsed() < MIN_REFRESH_INTERVAL);
 
-            if should_refresh {
+            if expiring && !recently_refreshed {
                 info!("Proactively refreshing authentication token");
-                let mut auth = self.auth_client.write().await;
-                match auth.refresh_token().await {
+                renew_token(
+                    &self.auth_client,
+                    &self.credentials,
+                    self.persistence.as_ref(),
+                )
+                .await?;
+                *self.last_refresh.write().await = Some(std::time::Instant::now());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Start the background task that renews the token before it expires
+    fn start_token_refresher(&mut self) {
+        if let Some(task) = self.refresh_task.take() {
+            task.abort();
+        }
+
+        let auth_client = self.auth_client.clone();
+        let credentials = self.credentials.clone();
+        let persistence = self.persistence.clone();
+        let last_refresh = self.last_refresh.clone();
+
+        self.refresh_task = Some(tokio::spawn(async move {
+            loop {
+                let remaining = auth_client
+                    .read()
+                    .await
+                    .get_token()
+                    .map(|token| token.remaining())
+                    .unwrap_or_default();
+                let delay = refresh_delay(remaining);
+                debug!("Next token refresh in {:?}", delay);
+                tokio::time::sleep(delay).await;
+
+                match renew_token(&auth_client, &credentials, persistence.as_ref()).await {
                     Ok(()) => {
-                        *self.last_refresh.write().await = Some(std::time::Instant::now());
-                    }
-                    Err(e) => {
-                        warn!("Token refresh failed, re-authenticating: {}", e);
-                        auth.authenticate(&self.credentials.username, &self.credentials.password)
-                            .await?;
-                        *self.last_refresh.write().await = Some(std::time::Instant::now());
+                        info!("🔄 Authentication token refreshed");
+                        *last_refresh.write().await = Some(std::time::Instant::now());
                     }
+                    Err(e) => warn!("Background token refresh failed: {}", e),
                 }
             }
+        }));
+    }
+
+    /// Stop refreshing and release the token on the Miniserver
+    ///
+    /// With `reuse_token` the cached token is kept for the next start.
+    /// Otherwise it is revoked with `killtoken` so it does not occupy a token
+    /// slot, and its cache entry is removed.
+    async fn release_token(&self) -> Result<()> {
+        if let Some(task) = &self.refresh_task {
+            task.abort();
         }
 
+        let mut auth = self.auth_client.write().await;
+        if self.config.reuse_token
+            && let Some(persistence) = &self.persistence
+        {
+            persistence.save(&auth);
+            return Ok(());
+        }
+        if auth.get_token().is_some() {
+            auth.kill_token().await?;
+            info!("🔒 Token revoked on Miniserver");
+        }
+        if let Some(persistence) = &self.persistence {
+            persistence.remove();
+        }
         Ok(())
     }
 
@@ -291,13 +474,43 @@ impl TokenHttpClient {
         })
     }
 
+    /// Send an encrypted command and return the (decrypted) response body
+    ///
+    /// The token goes inside the encrypted command so it never appears in
+    /// plain text on the wire.
+    async fn execute_encrypted(
+        &self,
+        encryptor: &CommandEncryptor,
+        command: &str,
+    ) -> Result<String> {
+        self.ensure_authenticated().await?;
+        let auth_params = {
+            let auth = self.auth_client.read().await;
+            auth.get_auth_params()?
+        };
+        let url = self.build_url(&encryptor.http_path(&format!("{command}?{auth_params}")))?;
+
+        let _permit = self.connection_pool.acquire().await?;
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Encrypted request failed: {e}")))?;
+        let text = response
+            .text()
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Failed to read response: {e}")))?;
+        encryptor.decrypt_response(&text)
+    }
+
     /// Execute HTTP request with token authentication and retry logic
     async fn execute_request(&self, url: Url) -> Result<reqwest::Response> {
         // Ensure we have valid authentication
         self.ensure_authenticated().await?;
 
         // Get auth params
-        let auth_params = {
+        let mut auth_params = {
             let auth = self.auth_client.read().await;
             auth.get_auth_params()?
         };
@@ -333,24 +546,15 @@ impl TokenHttpClient {
                     // Handle token expiration
                     if status.as_u16() == 401 {
                         warn!("Authentication failed, refreshing token");
-                        // Try to refresh token
-                        let mut auth = self.auth_client.write().await;
-                        match auth.refresh_token().await {
-                            Ok(()) => {
-                                *self.last_refresh.write().await = Some(std::time::Instant::now());
-                                continue; // Retry with new token
-                            }
-                            Err(_) => {
-                                // Re-authenticate if refresh fails
-                                auth.authenticate(
-                                    &self.credentials.username,
-                                    &self.credentials.password,
-                                )
-                                .await?;
-                                *self.last_refresh.write().await = Some(std::time::Instant::now());
-                                continue; // Retry with new token
-                            }
-                        }
+                        renew_token(
+                            &self.auth_client,
+                            &self.credentials,
+                            self.persistence.as_ref(),
+                        )
+                        .await?;
+                        *self.last_refresh.write().await = Some(std::time::Instant::now());
+                        auth_params = self.auth_client.read().await.get_auth_params()?;
+                        continue; // Retry with new token
                     }
 
                     last_error = Some(match status.as_u16() {
@@ -412,6 +616,13 @@ impl TokenHttpClient {
         &self.context
     }
 
+    /// Load the structure into a context shared with the caller
+    #[must_use]
+    pub fn with_context(mut self, context: Arc<ClientContext>) -> Self {
+        self.context = context;
+        self
+    }
+
     /// Get authentication parameters for sharing with other clients (e.g., WebSocket)
     /// Returns parameters in the format: "autht={token}&user={username}"
     /// This method ensures authentication is valid before returning parameters.
@@ -424,6 +635,16 @@ impl TokenHttpClient {
         auth.get_auth_params()
     }
 
+    /// Current token with its user and hash algorithm, for `authwithtoken`
+    /// on a WebSocket
+    pub async fn cached_token(&self) -> Result<crate::client::auth::CachedToken> {
+        self.ensure_authenticated().await?;
+
+        let auth = self.auth_client.read().await;
+        auth.snapshot()
+            .ok_or_else(|| LoxoneError::authentication("No token available"))
+    }
+
     /// Get connection pool statistics
     pub async fn pool_stats(&self) -> crate::client::connection_pool::PoolStats {
         self.connection_pool.stats().await
@@ -485,6 +706,19 @@ impl LoxoneClient for TokenHttpClient {
         // Perform initial authentication
         self.ensure_authenticated().await?;
 
+        if self.command_encryption.is_none() {
+            let public_key = match self.config.command_encryption {
+                CommandEncryption::None => None,
+                _ => Some(fetch_public_key(&self.client, &self.base_url).await?),
+            };
+            if let Some(public_key) = public_key {
+                self.command_encryption =
+                    CommandEncryptor::new(self.config.command_encryption, &public_key)?
+                        .map(Arc::new);
+                info!("🔐 Command encryption enabled");
+            }
+        }
+
         // Test connection with a simple health check
         match self.health_check().await {
             Ok(true) => {
@@ -510,6 +744,7 @@ impl LoxoneClient for TokenHttpClient {
                 }
 
                 info!("✅ Connected to Loxone Miniserver with token authentication");
+                self.start_token_refresher();
 
                 // Process queued commands if command queue is enabled
                 if let Some(_queue) = &self.command_queue {
@@ -534,6 +769,11 @@ impl LoxoneClient for TokenHttpClient {
         self.connected = false;
         *self.context.connected.write().await = false;
 
+        if let Err(e) = self.release_token().await {
+            warn!("Failed to release token: {}", e);
+        }
+        self.refresh_task = None;
+
         // Clear authentication
         let mut auth = self.auth_client.write().await;
         auth.clear();
@@ -667,6 +907,12 @@ impl LoxoneClient for TokenHttpClient {
         Ok(structure)
     }
 
+    async fn get_structure_version(&self) -> Result<String> {
+        let url = self.build_url("jdev/sps/LoxAPPversion3")?;
+        let response = self.execute_request(url).await?;
+        super::http_client::read_structure_version(response).await
+    }
+
     async fn get_device_states(
         &self,
         uuids: &[String],
@@ -775,6 +1021,23 @@ impl LoxoneClient for TokenHttpClient {
         Ok(loxone_response.value)
     }
 
+    async fn fetch_camera_image(&self, uuid: &str, timestamp: Option<&str>) -> Result<LoxoneImage> {
+        let url = self.build_url(&camera_image_path(uuid, timestamp)?)?;
+        let response = self.execute_request(url).await?;
+        super::http_client::read_image(response).await
+    }
+
+    async fn list_statistics(&self) -> Result<Vec<StatisticFile>> {
+        let response = self.execute_request(self.build_url("stats")?).await?;
+        super::http_client::read_statistics_listing(response).await
+    }
+
+    async fn fetch_statistics(&self, uuid: &str, month: StatisticMonth) -> Result<Vec<u8>> {
+        let url = self.build_url(&statistics_path(uuid, month)?)?;
+        let response = self.execute_request(url).await?;
+        super::http_client::read_statistics(response).await
+    }
+
     async fn health_check(&self) -> Result<bool> {
         debug!("Performing health check");
 
@@ -791,11 +1054,23 @@ impl LoxoneClient for TokenHttpClient {
         }
     }
 
+    async fn shutdown(&self) -> Result<()> {
+        self.release_token().await
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
 }
 
+impl Drop for TokenHttpClient {
+    fn drop(&mut self) {
+        if let Some(task) = self.refresh_task.take() {
+            task.abort();
+        }
+    }
+}
+
 impl TokenHttpClient {
     /// Get all devices from cache or fresh from server
     pub async fn get_all_devices(&self) -> Result<Vec<LoxoneDevice>> {
@@ -997,13 +1272,17 @@ impl TokenHttpClient {
             )));
         }
 
-        let url = self.build_url(&format!("jdev/sps/io/{uuid}/{command}"))?;
-
-        let response = self.execute_request(url).await?;
-        let text = response
-            .text()
-            .await
-            .map_err(|e| LoxoneError::connection(format!("Failed to read response: {e}")))?;
+        let command = format!("jdev/sps/io/{uuid}/{command}");
+        let text = match &self.command_encryption {
+            Some(encryptor) => self.execute_encrypted(encryptor, &command).await?,
+            None => {
+                let response = self.execute_request(self.build_url(&command)?).await?;
+                response
+                    .text()
+                    .await
+                    .map_err(|e| LoxoneError::connection(format!("Failed to read response: {e}")))?
+            }
+        };
 
         let loxone_response = Self::parse_loxone_response(&text);
 
@@ -1192,6 +1471,20 @@ impl TokenHttpClient {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_refresh_delay() {
+        let day = Duration::from_secs(24 * 3600);
+        // Long-lived tokens are renewed one margin before expiry
+        assert_eq!(refresh_delay(day), day - TOKEN_REFRESH_MARGIN);
+        // Short-lived tokens at half-life
+        assert_eq!(
+            refresh_delay(Duration::from_secs(1200)),
+            Duration::from_secs(600)
+        );
+        // Never faster than the minimum interval, even once expired
+        assert_eq!(refresh_delay(Duration::ZERO), MIN_REFRESH_INTERVAL);
+    }
+
     #[tokio::test]
     #[ignore] // Ignore this test as it requires actual network access to a specific host
     async fn test_token_client_creation() {
@@ -1205,6 +1498,9 @@ mod tests {
             #[cfg(feature = "websocket")]
             websocket: Default::default(),
             auth_method: crate::config::AuthMethod::Token,
+            credential_id: None,
+            command_encryption: Default::default(),
+            reuse_token: false,
         };
 
         let credentials = LoxoneCredentials {
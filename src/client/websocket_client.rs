@@ -6,22 +6,33 @@
 //! Features:
 //! - Real-time device state updates
 //! - Event filtering and subscription management
-//! - Automatic reconnection with exponential backoff
+//! - Token authentication over the socket and binary status updates
+//!   (see [`websocket_handshake`](crate::client::websocket_handshake))
+//! - Keep-alive with automatic reconnection and exponential backoff
 //! - Integration with HTTP clients for hybrid operation
-//! - Efficient binary message parsing for sensor data
+//! - Binary event-table decoding (value, text, daytimer and weather states)
 
 #[cfg(feature = "websocket")]
-use crate::client::{ClientContext, LoxoneClient, LoxoneResponse, LoxoneStructure};
+use crate::client::binary_protocol::{
+    BinaryMessage, BinaryMessageDecoder, EventTable, LOXONE_EPOCH_UNIX, WeatherEvent,
+};
+#[cfg(all(feature = "websocket", feature = "crypto-openssl"))]
+use crate::client::command_encryption::{CommandEncryptor, fetch_public_key};
 #[cfg(feature = "websocket")]
-use crate::config::{AuthMethod, LoxoneConfig, credentials::LoxoneCredentials};
+use crate::client::websocket_handshake::{WebSocketHandshake, WsReader, WsSink};
 #[cfg(feature = "websocket")]
-use crate::error::{LoxoneError, Result};
+use crate::client::{
+    ClientContext, LoxoneClient, LoxoneImage, LoxoneResponse, LoxoneStructure, StatisticFile,
+    StatisticMonth,
+};
+#[cfg(feature = "websocket")]
+use crate::config::{LoxoneConfig, credentials::LoxoneCredentials};
 #[cfg(feature = "websocket")]
-use crate::security::encryption::EncryptionManager;
+use crate::error::{LoxoneError, Result};
 #[cfg(feature = "websocket")]
 use async_trait::async_trait;
 #[cfg(feature = "websocket")]
-use futures_util::SinkExt;
+use futures_util::{SinkExt, StreamExt};
 #[cfg(feature = "websocket")]
 use rand;
 #[cfg(feature = "websocket")]
@@ -31,27 +42,26 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "websocket")]
 use std::collections::{HashMap, HashSet};
 #[cfg(feature = "websocket")]
-use std::io::{Cursor, Read};
-#[cfg(feature = "websocket")]
 use std::sync::Arc;
 #[cfg(feature = "websocket")]
 use std::time::Duration;
 #[cfg(feature = "websocket")]
 use tokio::sync::{Mutex, RwLock, mpsc};
 #[cfg(feature = "websocket")]
-use tokio::time::{Instant, sleep};
+use tokio::time::{Instant, MissedTickBehavior, sleep};
 #[cfg(feature = "websocket")]
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use tokio_tungstenite::tungstenite::Message;
 #[cfg(feature = "websocket")]
 use tracing::{debug, error, info, warn};
 #[cfg(feature = "websocket")]
 use url::Url;
 
 #[cfg(feature = "websocket")]
-type WsStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+type SubscriberList = Arc<RwLock<Vec<(mpsc::UnboundedSender<StateUpdate>, FilterType)>>>;
 
+/// Weather storage shared with the session task, so it can be toggled while connected
 #[cfg(feature = "websocket")]
-type SubscriberList = Arc<RwLock<Vec<(mpsc::UnboundedSender<StateUpdate>, FilterType)>>>;
+type WeatherStorageSlot = Arc<std::sync::RwLock<Option<Arc<crate::storage::WeatherStorage>>>>;
 
 /// Filter type enumeration to support both basic and advanced filters
 #[cfg(feature = "websocket")]
@@ -95,6 +105,8 @@ pub enum LoxoneEventType {
     System,
     /// Binary sensor data
     Sensor,
+    /// Daytimer schedule update
+    Daytimer,
     /// Unknown event type
     Unknown(String),
 }
@@ -108,6 +120,7 @@ impl From<String> for LoxoneEventType {
             "alarm" => LoxoneEventType::Alarm,
             "system" => LoxoneEventType::System,
             "sensor" => LoxoneEventType::Sensor,
+            "daytimer" => LoxoneEventType::Daytimer,
             _ => LoxoneEventType::Unknown(s),
         }
     }
@@ -122,6 +135,7 @@ impl std::fmt::Display for LoxoneEventType {
             LoxoneEventType::Alarm => write!(f, "alarm"),
             LoxoneEventType::System => write!(f, "system"),
             LoxoneEventType::Sensor => write!(f, "sensor"),
+            LoxoneEventType::Daytimer => write!(f, "daytimer"),
             LoxoneEventType::Unknown(s) => write!(f, "unknown({s})"),
         }
     }
@@ -502,10 +516,11 @@ pub struct LoxoneWebSocketClient {
     config: LoxoneConfig,
 
     /// Shared context for caching
-    context: ClientContext,
+    context: Arc<ClientContext>,
 
-    /// WebSocket stream
-    ws_stream: Option<Arc<Mutex<WsStream>>>,
+    /// Sending half of the WebSocket; the reading half belongs to the
+    /// connection task
+    ws_sink: Arc<Mutex<Option<WsSink>>>,
 
     /// State update channel sender
     state_sender: Option<mpsc::UnboundedSender<StateUpdate>>,
@@ -532,18 +547,16 @@ pub struct LoxoneWebSocketClient {
     /// Statistics
     stats: Arc<RwLock<WebSocketStats>>,
 
-    /// Encryption manager for secure communication
-    encryption_manager: Arc<RwLock<EncryptionManager>>,
-
-    /// Current encryption session (if enabled)
-    encryption_session: Arc<RwLock<Option<String>>>, // session_id
+    /// Command encryption (`jdev/sys/enc`), set up on connect if configured
+    #[cfg(feature = "crypto-openssl")]
+    command_encryption: Option<Arc<CommandEncryptor>>,
 
     /// WebSocket resilience manager
     resilience_manager:
         Option<Arc<crate::client::websocket_resilience::WebSocketResilienceManager>>,
 
     /// Weather data storage
-    weather_storage: Option<Arc<crate::storage::WeatherStorage>>,
+    weather_storage: WeatherStorageSlot,
 }
 
 #[cfg(feature = "websocket")]
@@ -554,8 +567,8 @@ impl LoxoneWebSocketClient {
             base_url: config.url.clone(),
             credentials,
             config,
-            context: ClientContext::new(),
-            ws_stream: None,
+            context: Arc::new(ClientContext::new()),
+            ws_sink: Arc::new(Mutex::new(None)),
             state_sender: None,
             subscribers: Arc::new(RwLock::new(Vec::new())),
             connected: Arc::new(RwLock::new(false)),
@@ -564,10 +577,10 @@ impl LoxoneWebSocketClient {
             last_event_times: Arc::new(RwLock::new(HashMap::new())),
             http_client: None,
             stats: Arc::new(RwLock::new(WebSocketStats::default())),
-            encryption_manager: Arc::new(RwLock::new(EncryptionManager::new(10))), // Max 10 sessions
-            encryption_session: Arc::new(RwLock::new(None)),
+            #[cfg(feature = "crypto-openssl")]
+            command_encryption: None,
             resilience_manager: None,
-            weather_storage: None,
+            weather_storage: Arc::new(std::sync::RwLock::new(None)),
         })
     }
 
@@ -582,6 +595,15 @@ impl LoxoneWebSocketClient {
         Ok(client)
     }
 
+    /// Record pushed states into a context shared with the caller
+    ///
+    /// Must be called before [`LoxoneClient::connect`], which hands the
+    /// context to the session task.
+    pub fn with_context(mut self, context: Arc<ClientContext>) -> Self {
+        self.context = context;
+        self
+    }
+
     /// Configure reconnection behavior
     pub fn set_reconnection_config(&mut self, config: ReconnectionConfig) {
         self.reconnection_config = config;
@@ -592,7 +614,7 @@ impl LoxoneWebSocketClient {
         &mut self,
         resilience_config: crate::client::websocket_resilience::WebSocketResilienceConfig,
     ) -> Result<()> {
-        let ws_url = self.build_ws_url().await?;
+        let ws_url = self.handshake().ws_url()?;
         let manager = Arc::new(
             crate::client::websocket_resilience::WebSocketResilienceManager::new(
                 ws_url.to_string(),
@@ -629,21 +651,37 @@ impl LoxoneWebSocketClient {
             storage.update_device_structure(&devices).await;
         }
 
-        self.weather_storage = Some(storage);
+        *self
+            .weather_storage
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Some(storage);
         info!("Weather data storage enabled");
         Ok(())
     }
 
     /// Disable weather data storage
     pub async fn disable_weather_storage(&mut self) {
-        if self.weather_storage.take().is_some() {
+        let previous = self
+            .weather_storage
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if previous.is_some() {
             info!("Weather data storage disabled");
         }
     }
 
     /// Check if weather storage is enabled
     pub fn is_weather_storage_enabled(&self) -> bool {
-        self.weather_storage.is_some()
+        self.weather_storage().is_some()
+    }
+
+    /// Weather data storage fed by decoded weather tables (if enabled)
+    pub fn weather_storage(&self) -> Option<Arc<crate::storage::WeatherStorage>> {
+        self.weather_storage
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
     }
 
     /// Check if resilience is enabled
@@ -687,114 +725,24 @@ impl LoxoneWebSocketClient {
         }
     }
 
-    /// Build WebSocket URL with authentication
-    async fn build_ws_url(&self) -> Result<Url> {
-        let mut ws_url = self.base_url.clone();
-
-        // Convert HTTP(S) to WS(S)
-        match ws_url.scheme() {
-            "http" => ws_url
-                .set_scheme("ws")
-                .map_err(|_| LoxoneError::connection("Failed to convert HTTP to WebSocket URL"))?,
-            "https" => ws_url
-                .set_scheme("wss")
-                .map_err(|_| LoxoneError::connection("Failed to convert HTTPS to WebSocket URL"))?,
-            _ => {
-                return Err(LoxoneError::connection(
-                    "Unsupported URL scheme for WebSocket",
-                ));
-            }
-        }
-
-        // Add WebSocket endpoint path
-        ws_url.set_path("/ws/rfc6455");
-
-        // Add authentication based on method
-        match self.config.auth_method {
-            AuthMethod::Basic => {
-                // Basic auth via query parameters (legacy)
-                ws_url
-                    .query_pairs_mut()
-                    .append_pair("user", &self.credentials.username)
-                    .append_pair("password", &self.credentials.password);
-            }
-            AuthMethod::Token => {
-                // Use proper token authentication from HTTP client
-                match self.get_token_auth_params().await {
-                    Ok(Some(auth_params)) => {
-                        // Use token-based authentication
-                        ws_url.set_query(Some(&auth_params));
-                        debug!("Using token authentication for WebSocket connection");
-                    }
-                    Ok(None) => {
-                        // No token available, fall back to basic auth
-                        warn!(
-                            "No token available from HTTP client, falling back to basic auth for WebSocket"
-                        );
-                        ws_url
-                            .query_pairs_mut()
-                            .append_pair("user", &self.credentials.username)
-                            .append_pair("password", &self.credentials.password);
-                    }
-                    Err(e) => {
-                        // Token extraction failed, fall back to basic auth
-                        warn!(
-                            "Failed to get token authentication parameters: {}, falling back to basic auth",
-                            e
-                        );
-                        ws_url
-                            .query_pairs_mut()
-                            .append_pair("user", &self.credentials.username)
-                            .append_pair("password", &self.credentials.password);
-                    }
-                }
-            }
-            #[cfg(feature = "websocket")]
-            AuthMethod::WebSocket => {
-                // WebSocket native authentication - use basic auth for simplicity
-                ws_url
-                    .query_pairs_mut()
-                    .append_pair("user", &self.credentials.username)
-                    .append_pair("password", &self.credentials.password);
-            }
-        }
-
-        Ok(ws_url)
-    }
-
-    /// Get token authentication parameters from the HTTP client
-    async fn get_token_auth_params(&self) -> Result<Option<String>> {
-        if let Some(http_client) = &self.http_client {
-            // Try to downcast to TokenHttpClient to access token authentication
-            #[cfg(feature = "crypto-openssl")]
-            if let Some(token_client) = http_client
-                .as_ref()
-                .as_any()
-                .downcast_ref::<crate::client::TokenHttpClient>()
-            {
-                // Get auth parameters (this method ensures authentication internally)
-                match token_client.get_auth_params().await {
-                    Ok(auth_params) => {
-                        debug!("Successfully extracted token auth parameters from HTTP client");
-                        return Ok(Some(auth_params));
-                    }
-                    Err(e) => {
-                        warn!("Failed to get auth parameters from TokenHttpClient: {}", e);
-                        return Ok(None);
-                    }
-                }
-            }
-
-            // If not a TokenHttpClient or crypto-openssl feature not enabled, no token auth available
-            debug!("HTTP client is not a TokenHttpClient, token authentication not available");
-        }
-
-        // No HTTP client or no token authentication available
-        Ok(None)
+    /// Handshake for new sessions, sharing the HTTP client's token
+    fn handshake(&self) -> WebSocketHandshake {
+        let handshake = WebSocketHandshake::new(
+            self.config.clone(),
+            self.credentials.clone(),
+            self.http_client.clone(),
+        );
+        #[cfg(feature = "crypto-openssl")]
+        let handshake = handshake.with_encryptor(self.command_encryption.clone());
+        handshake
     }
 
     /// Start background tasks for message processing and reconnection
-    async fn start_background_tasks(&mut self) -> Result<()> {
+    async fn start_background_tasks(
+        &mut self,
+        handshake: WebSocketHandshake,
+        reader: WsReader,
+    ) -> Result<()> {
         let (state_tx, mut state_rx) = mpsc::unbounded_channel::<StateUpdate>();
         self.state_sender = Some(state_tx);
 
@@ -891,217 +839,28 @@ impl LoxoneWebSocketClient {
             }
         });
 
-        // Task 2: WebSocket message processor
-        let ws_stream = self.ws_stream.clone();
-        let state_sender_clone = self.state_sender.clone();
-        let stats_clone = self.stats.clone();
-        let connected_clone = self.connected.clone();
-
-        #[allow(clippy::manual_map)]
-        let message_task = if let Some(ws_stream) = ws_stream {
-            Some(tokio::spawn(async move {
-                loop {
-                    let message = {
-                        use futures_util::StreamExt;
-                        let mut stream = ws_stream.lock().await;
-                        stream.next().await
-                    };
-
-                    match message {
-                        Some(Ok(msg)) => {
-                            // Update message statistics
-                            {
-                                let mut stats_guard = stats_clone.write().await;
-                                stats_guard.messages_received += 1;
-                                stats_guard.last_message = Some(chrono::Utc::now());
-
-                                if let tokio_tungstenite::tungstenite::Message::Binary(ref data) =
-                                    msg
-                                {
-                                    stats_guard.bytes_received += data.len() as u64;
-                                } else if let tokio_tungstenite::tungstenite::Message::Text(
-                                    ref text,
-                                ) = msg
-                                {
-                                    stats_guard.bytes_received += text.len() as u64;
-                                }
-                            }
-
-                            // Process the message
-                            if let Err(e) = Self::process_ws_message(msg, &state_sender_clone).await
-                            {
-                                warn!("Error processing WebSocket message: {}", e);
-                            }
-                        }
-                        Some(Err(e)) => {
-                            error!("WebSocket error: {}", e);
-                            *connected_clone.write().await = false;
-                            break;
-                        }
-                        None => {
-                            info!("WebSocket stream ended");
-                            *connected_clone.write().await = false;
-                            break;
-                        }
-                    }
-                }
-            }))
-        } else {
-            None
-        };
-
-        // Task 3: Reconnection and token refresh manager (if enabled)
-        let reconnection_task = if self.reconnection_config.enabled {
-            let connected = self.connected.clone();
-            let base_url = self.base_url.clone();
-            let credentials = self.credentials.clone();
-            let config = self.config.clone();
-            let reconnection_config = self.reconnection_config.clone();
-            let stats_clone = self.stats.clone();
-            let ws_stream_ref = self.ws_stream.clone();
-            let http_client = self.http_client.clone();
-
-            Some(tokio::spawn(async move {
-                let mut attempt = 0;
-                let mut delay = reconnection_config.initial_delay;
-
-                loop {
-                    // Check if we're still connected
-                    if *connected.read().await {
-                        // Check if we need to refresh tokens proactively
-                        #[cfg(feature = "crypto-openssl")]
-                        if let Some(http_client) = &http_client
-                            && let Some(token_client) = http_client
-                                .as_ref()
-                                .as_any()
-                                .downcast_ref::<crate::client::TokenHttpClient>(
-                            )
-                        {
-                            // Try to get auth params - this will internally handle token expiration and refresh
-                            match token_client.get_auth_params().await {
-                                Ok(_) => {
-                                    debug!(
-                                        "WebSocket token authentication verified and refreshed if needed"
-                                    );
-                                }
-                                Err(e) => {
-                                    warn!("Failed to refresh authentication for WebSocket: {}", e);
-                                    // Token authentication failed, force WebSocket reconnection with new token
-                                    *connected.write().await = false;
-                                    continue;
-                                }
-                            }
-                        }
-
-                        // Wait before checking again (30 seconds for token checks)
-                        sleep(Duration::from_secs(30)).await;
-                        continue;
-                    }
-
-                    // Check if we've exceeded max attempts
-                    if let Some(max_attempts) = reconnection_config.max_attempts
-                        && attempt >= max_attempts
-                    {
-                        error!("Max reconnection attempts ({}) exceeded", max_attempts);
-                        break;
-                    }
-
-                    attempt += 1;
-                    {
-                        let mut stats_guard = stats_clone.write().await;
-                        stats_guard.reconnection_attempts = attempt;
-                    }
-
-                    info!("Attempting WebSocket reconnection #{}", attempt);
-
-                    // Add jitter to prevent thundering herd
-                    let jitter =
-                        (delay.as_millis() as f64 * reconnection_config.jitter_factor) as u64;
-                    let random_jitter = if jitter > 0 {
-                        rand::random::<u64>() % jitter
-                    } else {
-                        0
-                    };
-                    let jittered_delay = delay + Duration::from_millis(random_jitter);
-
-                    sleep(jittered_delay).await;
-
-                    // Attempt reconnection - try with token first if available
-                    let reconnection_result = if let Some(http_client) = &http_client {
-                        #[cfg(feature = "crypto-openssl")]
-                        if let Some(token_client) = http_client
-                            .as_ref()
-                            .as_any()
-                            .downcast_ref::<crate::client::TokenHttpClient>(
-                        ) {
-                            // Try reconnection with token authentication
-                            match Self::attempt_token_reconnection(&base_url, token_client, &config)
-                                .await
-                            {
-                                Ok(stream) => {
-                                    info!(
-                                        "WebSocket reconnection successful with token authentication"
-                                    );
-                                    Ok(stream)
-                                }
-                                Err(e) => {
-                                    warn!(
-                                        "Token-based reconnection failed: {}, falling back to basic auth",
-                                        e
-                                    );
-                                    Self::attempt_reconnection(&base_url, &credentials, &config)
-                                        .await
-                                }
-                            }
-                        } else {
-                            Self::attempt_reconnection(&base_url, &credentials, &config).await
-                        }
-
-                        #[cfg(not(feature = "crypto-openssl"))]
-                        Self::attempt_reconnection(&base_url, &credentials, &config).await
-                    } else {
-                        Self::attempt_reconnection(&base_url, &credentials, &config).await
-                    };
-
-                    match reconnection_result {
-                        Ok(new_stream) => {
-                            info!("✅ WebSocket reconnection successful");
-
-                            // Replace the WebSocket stream
-                            if let Some(ws_stream_arc) = &ws_stream_ref {
-                                *ws_stream_arc.lock().await = new_stream;
-                            }
-
-                            *connected.write().await = true;
-                            attempt = 0; // Reset attempt counter
-                            delay = reconnection_config.initial_delay; // Reset delay
-                        }
-                        Err(e) => {
-                            warn!("Reconnection attempt #{} failed: {}", attempt, e);
-
-                            // Exponential backoff
-                            delay = Duration::from_millis(
-                                (delay.as_millis() as f64 * reconnection_config.backoff_multiplier)
-                                    as u64,
-                            )
-                            .min(reconnection_config.max_delay);
-                        }
-                    }
-                }
-            }))
-        } else {
-            None
+        // Task 2: Read messages, send keep-alives and reconnect
+        let session = SessionTask {
+            handshake,
+            sink: self.ws_sink.clone(),
+            connected: self.connected.clone(),
+            context: self.context.clone(),
+            state_sender: self.state_sender.clone(),
+            weather_storage: self.weather_storage.clone(),
+            stats: self.stats.clone(),
+            keepalive_interval: self
+                .config
+                .websocket
+                .keepalive_interval
+                .max(Duration::from_millis(100)),
+            reconnection: self.reconnection_config.clone(),
         };
+        let session_task = tokio::spawn(session.run(reader));
 
         // Store task handles
         let mut handles = self.task_handles.lock().await;
         handles.push(state_task);
-        if let Some(message_task) = message_task {
-            handles.push(message_task);
-        }
-        if let Some(reconnection_task) = reconnection_task {
-            handles.push(reconnection_task);
-        }
+        handles.push(session_task);
 
         Ok(())
     }
@@ -1394,120 +1153,21 @@ impl LoxoneWebSocketClient {
         monitored
     }
 
-    /// Helper method for reconnection attempts
-    async fn attempt_reconnection(
-        base_url: &Url,
-        credentials: &LoxoneCredentials,
-        _config: &LoxoneConfig,
-    ) -> Result<WsStream> {
-        use tokio_tungstenite::connect_async;
-
-        // Build WebSocket URL
-        let mut ws_url = base_url.clone();
-
-        // Convert HTTP(S) to WS(S)
-        match ws_url.scheme() {
-            "http" => {
-                ws_url.set_scheme("ws").map_err(|_| {
-                    LoxoneError::connection("Failed to convert HTTP to WebSocket URL")
-                })?;
-            }
-            "https" => {
-                ws_url.set_scheme("wss").map_err(|_| {
-                    LoxoneError::connection("Failed to convert HTTPS to WebSocket URL")
-                })?;
-            }
-            _ => {
-                return Err(LoxoneError::connection(
-                    "Unsupported URL scheme for WebSocket",
-                ));
-            }
-        }
-
-        // Add WebSocket endpoint path
-        ws_url.set_path("/ws/rfc6455");
-
-        // Add authentication
-        ws_url
-            .query_pairs_mut()
-            .append_pair("user", &credentials.username)
-            .append_pair("password", &credentials.password);
-
-        // Attempt connection
-        let (ws_stream, response) = connect_async(ws_url.as_str())
-            .await
-            .map_err(|e| LoxoneError::connection(format!("WebSocket reconnection failed: {e}")))?;
-
-        debug!("WebSocket reconnected, response: {:?}", response.status());
-        Ok(ws_stream)
-    }
-
-    /// Helper method for token-based reconnection attempts
-    #[cfg(feature = "crypto-openssl")]
-    async fn attempt_token_reconnection(
-        base_url: &Url,
-        token_client: &crate::client::TokenHttpClient,
-        _config: &LoxoneConfig,
-    ) -> Result<WsStream> {
-        use tokio_tungstenite::connect_async;
-
-        // Build WebSocket URL
-        let mut ws_url = base_url.clone();
-
-        // Convert HTTP(S) to WS(S)
-        match ws_url.scheme() {
-            "http" => {
-                ws_url.set_scheme("ws").map_err(|_| {
-                    LoxoneError::connection("Failed to convert HTTP to WebSocket URL")
-                })?;
-            }
-            "https" => {
-                ws_url.set_scheme("wss").map_err(|_| {
-                    LoxoneError::connection("Failed to convert HTTPS to WebSocket URL")
-                })?;
-            }
-            _ => {
-                return Err(LoxoneError::connection(
-                    "Unsupported URL scheme for WebSocket",
-                ));
-            }
-        }
-
-        // Add WebSocket endpoint path
-        ws_url.set_path("/ws/rfc6455");
-
-        // Get fresh authentication parameters from the token client
-        let auth_params = token_client.get_auth_params().await.map_err(|e| {
-            LoxoneError::connection(format!(
-                "Failed to get auth params for WebSocket reconnection: {e}"
-            ))
-        })?;
-
-        // Add token authentication
-        ws_url.set_query(Some(&auth_params));
-
-        // Attempt connection
-        let (ws_stream, response) = connect_async(ws_url.as_str()).await.map_err(|e| {
-            LoxoneError::connection(format!("WebSocket token reconnection failed: {e}"))
-        })?;
-
-        debug!(
-            "WebSocket reconnected with token auth, response: {:?}",
-            response.status()
-        );
-        Ok(ws_stream)
-    }
-
     /// Process WebSocket messages (static method for background task)
     async fn process_ws_message(
         message: tokio_tungstenite::tungstenite::Message,
+        decoder: &mut BinaryMessageDecoder,
+        context: &ClientContext,
         state_sender: &Option<mpsc::UnboundedSender<StateUpdate>>,
+        weather_storage: Option<&crate::storage::WeatherStorage>,
     ) -> Result<()> {
         use tokio_tungstenite::tungstenite::Message;
 
         match message {
             Message::Text(text) => {
                 debug!("Received text message: {}", text);
+                // Text frames complete a pending text message header
+                decoder.feed_text();
 
                 // Try parsing as Loxone message
                 if let Ok(loxone_msg) = serde_json::from_str::<LoxoneWebSocketMessage>(&text) {
@@ -1516,7 +1176,8 @@ impl LoxoneWebSocketClient {
             }
             Message::Binary(data) => {
                 debug!("Received binary message: {} bytes", data.len());
-                Self::handle_binary_message_static(data).await?;
+                Self::handle_binary_frame(&data, decoder, context, state_sender, weather_storage)
+                    .await?;
             }
             Message::Ping(_data) => {
                 debug!("Received ping - pong will be sent automatically by tungstenite");
@@ -1544,15 +1205,6 @@ impl LoxoneWebSocketClient {
         Ok(())
     }
 
-    /// Instance method for backward compatibility
-    #[allow(dead_code)]
-    async fn process_message(
-        &self,
-        message: tokio_tungstenite::tungstenite::Message,
-    ) -> Result<()> {
-        Self::process_ws_message(message, &self.state_sender).await
-    }
-
     /// Handle Loxone-specific message (static method for background task)
     async fn handle_loxone_message_static(
         message: LoxoneWebSocketMessage,
@@ -1665,105 +1317,60 @@ impl LoxoneWebSocketClient {
         Self::handle_loxone_message_static(message, &self.state_sender).await
     }
 
-    /// Handle binary message (sensor data) - static method with enhanced parsing
-    async fn handle_binary_message_static(data: Vec<u8>) -> Result<()> {
-        // Binary messages in Loxone follow the Miniserver binary protocol
-        // Header format (8 bytes):
-        // - Bytes 0-3: Message type (little-endian u32)
-        // - Bytes 4-7: Data length (little-endian u32)
-        // Payload: Variable length data depending on message type
-
-        if data.len() < 8 {
-            debug!("Binary message too short: {} bytes", data.len());
-            return Ok(());
-        }
-
-        // Extract header
-        let msg_type = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        let data_length = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
-
-        debug!(
-            "Binary message - type: 0x{:08X}, length: {}, total: {} bytes",
-            msg_type,
-            data_length,
-            data.len()
-        );
-
-        // Validate data length
-        if data.len() < 8 + data_length {
-            warn!(
-                "Binary message data length mismatch: expected {}, got {}",
-                8 + data_length,
-                data.len()
-            );
-            return Ok(());
-        }
-
-        // Extract payload
-        let payload = &data[8..8 + data_length];
-
-        // Parse based on Loxone binary protocol specification
-        match msg_type {
-            // Header message (connection established)
-            0x03000000 => {
-                debug!("Binary: Connection header received");
-                Self::parse_header_message(payload).await?;
-            }
-
-            // Event table definition
-            0x04000000 => {
-                debug!("Binary: Event table definition");
-                Self::parse_event_table(payload).await?;
-            }
-
-            // Value state updates (most common)
-            0x00000000 => {
-                debug!("Binary: Value state updates");
-                Self::parse_value_states(payload).await?;
-            }
-
-            // Text state updates
-            0x01000000 => {
-                debug!("Binary: Text state updates");
-                Self::parse_text_states(payload).await?;
-            }
-
-            // Daylight saving info
-            0x02000000 => {
-                debug!("Binary: Daylight saving information");
-                Self::parse_daylight_saving(payload).await?;
+    /// Handle a binary frame: pair headers with payloads and dispatch event tables
+    async fn handle_binary_frame(
+        data: &[u8],
+        decoder: &mut BinaryMessageDecoder,
+        context: &ClientContext,
+        state_sender: &Option<mpsc::UnboundedSender<StateUpdate>>,
+        weather_storage: Option<&crate::storage::WeatherStorage>,
+    ) -> Result<()> {
+        let message = match decoder.feed_binary(data) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                // Never stay stuck waiting for a payload after a framing error
+                decoder.reset();
+                return Err(e);
             }
+        };
 
-            // Weather data
-            0x05000000 => {
-                debug!("Binary: Weather data");
-                return Err(LoxoneError::internal(
-                    "Weather data parsing requires instance method - use handle_binary_message_instance",
-                ));
+        match message {
+            BinaryMessage::EventTable(table) => {
+                debug!("Binary: event table with {} events", table.len());
+                if let (EventTable::Weather(events), Some(storage)) = (&table, weather_storage) {
+                    Self::store_weather_events(events, context, storage).await;
+                }
+                let updates = Self::event_table_to_state_updates(table, context).await;
+                if let Some(sender) = state_sender {
+                    for update in updates {
+                        if sender.send(update).is_err() {
+                            warn!("Failed to send state update - receiver may be closed");
+                            break;
+                        }
+                    }
+                }
             }
-
-            // Out-of-service indicator
-            0x06000000 => {
-                debug!("Binary: Out-of-service indicator");
-                Self::parse_out_of_service(payload).await?;
+            BinaryMessage::BinaryFile(payload) => {
+                debug!("Binary: file with {} bytes", payload.len());
             }
-
-            // Keep-alive response
-            0x07000000 => {
+            BinaryMessage::Keepalive => {
                 debug!("Binary: Keep-alive response");
-                // No additional data expected
             }
-
-            // Unknown message type
-            _ => {
+            BinaryMessage::OutOfService => {
+                warn!("Miniserver reported out-of-service (e.g. firmware update or reboot)");
+            }
+            BinaryMessage::Unknown {
+                identifier,
+                payload,
+            } => {
                 debug!(
-                    "Binary: Unknown message type 0x{:08X}, payload: {} bytes",
-                    msg_type,
+                    "Binary: Unknown message identifier {}, payload: {} bytes",
+                    identifier,
                     payload.len()
                 );
-                // Log payload as hex for debugging
                 if payload.len() <= 64 {
-                    debug!("Payload hex: {}", hex::encode(payload));
+                    debug!("Payload hex: {}", hex::encode(&payload));
                 }
             }
         }
@@ -1771,402 +1378,132 @@ impl LoxoneWebSocketClient {
         Ok(())
     }
 
-    /// Parse header message (connection info)
-    async fn parse_header_message(payload: &[u8]) -> Result<()> {
-        if payload.len() >= 4 {
-            let version = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
-            info!("Loxone Miniserver protocol version: {}", version);
-        }
-        Ok(())
-    }
-
-    /// Parse event table definition
-    async fn parse_event_table(payload: &[u8]) -> Result<()> {
-        // Event table contains UUID to index mappings
-        debug!(
-            "Event table with {} bytes - contains UUID mappings",
-            payload.len()
-        );
-        // Implementation would parse UUID->index mappings for efficient state updates
-        Ok(())
-    }
-
-    /// Parse value state updates (double values)
-    async fn parse_value_states(payload: &[u8]) -> Result<()> {
-        let mut cursor = Cursor::new(payload);
-        let mut states_parsed = 0;
-
-        // Each value state entry: 4 bytes (index) + 8 bytes (double value)
-        while cursor.position() + 12 <= payload.len() as u64 {
-            let mut index_bytes = [0u8; 4];
-            let mut value_bytes = [0u8; 8];
-
-            if cursor.read_exact(&mut index_bytes).is_ok()
-                && cursor.read_exact(&mut value_bytes).is_ok()
-            {
-                let index = u32::from_le_bytes(index_bytes);
-                let value = f64::from_le_bytes(value_bytes);
-
-                debug!("Value state update - index: {}, value: {}", index, value);
-                states_parsed += 1;
-            } else {
-                break;
-            }
-        }
-
-        debug!("Parsed {} value state updates", states_parsed);
-        Ok(())
-    }
-
-    /// Parse text state updates (string values)
-    async fn parse_text_states(payload: &[u8]) -> Result<()> {
-        let mut cursor = Cursor::new(payload);
-        let mut states_parsed = 0;
-
-        // Each text state entry: 4 bytes (index) + 4 bytes (text length) + text data
-        while cursor.position() + 8 <= payload.len() as u64 {
-            let mut index_bytes = [0u8; 4];
-            let mut length_bytes = [0u8; 4];
-
-            if cursor.read_exact(&mut index_bytes).is_ok()
-                && cursor.read_exact(&mut length_bytes).is_ok()
-            {
-                let index = u32::from_le_bytes(index_bytes);
-                let text_length = u32::from_le_bytes(length_bytes) as usize;
-
-                if cursor.position() + text_length as u64 <= payload.len() as u64 {
-                    let mut text_bytes = vec![0u8; text_length];
-                    if cursor.read_exact(&mut text_bytes).is_ok()
-                        && let Ok(text) = String::from_utf8(text_bytes)
-                    {
-                        debug!("Text state update - index: {}, text: '{}'", index, text);
-                        states_parsed += 1;
-                    }
-                } else {
-                    break;
+    /// Convert a decoded event table into state updates
+    ///
+    /// Each event's state UUID is resolved against the structure's state index
+    /// so subscribers see the owning control UUID and state name. The value is
+    /// also recorded in the [`ClientContext`] state value cache.
+    pub async fn event_table_to_state_updates(
+        table: EventTable,
+        context: &ClientContext,
+    ) -> Vec<StateUpdate> {
+        let events: Vec<(String, serde_json::Value, LoxoneEventType)> = match table {
+            EventTable::Values(events) => events
+                .into_iter()
+                .map(|e| {
+                    let value = serde_json::Number::from_f64(e.value)
+                        .map(serde_json::Value::Number)
+                        .unwrap_or(serde_json::Value::Null);
+                    (e.uuid, value, LoxoneEventType::State)
+                })
+                .collect(),
+            EventTable::Texts(events) => events
+                .into_iter()
+                .map(|e| {
+                    (
+                        e.uuid,
+                        serde_json::Value::String(e.text),
+                        LoxoneEventType::Text,
+                    )
+                })
+                .collect(),
+            EventTable::Daytimers(events) => events
+                .into_iter()
+                .map(|e| {
+                    let uuid = e.uuid.clone();
+                    let value = serde_json::to_value(&e).unwrap_or_default();
+                    (uuid, value, LoxoneEventType::Daytimer)
+                })
+                .collect(),
+            EventTable::Weather(events) => {
+                let mut decoded = Vec::with_capacity(events.len());
+                for e in events {
+                    context.record_weather_event(&e).await;
+                    let value = serde_json::to_value(&e).unwrap_or_default();
+                    decoded.push((e.uuid, value, LoxoneEventType::Weather));
                 }
-            } else {
-                break;
-            }
-        }
-
-        debug!("Parsed {} text state updates", states_parsed);
-        Ok(())
-    }
-
-    /// Parse daylight saving information
-    async fn parse_daylight_saving(payload: &[u8]) -> Result<()> {
-        if payload.len() >= 8 {
-            let dst_offset = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
-            let timezone_offset =
-                u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]);
-            debug!(
-                "Daylight saving - DST offset: {}, timezone offset: {}",
-                dst_offset, timezone_offset
-            );
-        }
-        Ok(())
-    }
-
-    /// Parse weather data
-    async fn parse_weather_data(&self, payload: &[u8]) -> Result<()> {
-        debug!("Weather data received: {} bytes", payload.len());
-
-        if payload.is_empty() {
-            return Ok(());
-        }
-
-        let mut cursor = Cursor::new(payload);
-        let mut weather_updates_parsed = 0;
-
-        // Loxone weather data format:
-        // Each weather data entry: 4 bytes (UUID index) + 8 bytes (double value) + 4 bytes (timestamp)
-        while cursor.position() + 16 <= payload.len() as u64 {
-            let mut uuid_index_bytes = [0u8; 4];
-            let mut value_bytes = [0u8; 8];
-            let mut timestamp_bytes = [0u8; 4];
-
-            if cursor.read_exact(&mut uuid_index_bytes).is_ok()
-                && cursor.read_exact(&mut value_bytes).is_ok()
-                && cursor.read_exact(&mut timestamp_bytes).is_ok()
-            {
-                let uuid_index = u32::from_le_bytes(uuid_index_bytes);
-                let value = f64::from_le_bytes(value_bytes);
-                let timestamp = u32::from_le_bytes(timestamp_bytes);
-
-                debug!(
-                    "Weather update - UUID index: {}, value: {:.2}, timestamp: {}",
-                    uuid_index, value, timestamp
-                );
-
-                // Store weather data for retrieval by weather resources
-                self.store_weather_update(uuid_index, value, timestamp)
-                    .await?;
-
-                weather_updates_parsed += 1;
-            } else {
-                break;
+                decoded
             }
-        }
-
-        if weather_updates_parsed > 0 {
-            debug!("Parsed {} weather data updates", weather_updates_parsed);
-        } else {
-            // Try alternative weather data format (some stations use different layouts)
-            Self::parse_alternative_weather_format(payload).await?;
-        }
-
-        Ok(())
-    }
-
-    /// Store weather update data for later retrieval
-    async fn store_weather_update(
-        &self,
-        uuid_index: u32,
-        value: f64,
-        timestamp: u32,
-    ) -> Result<()> {
-        if let Some(storage) = &self.weather_storage {
-            // Store weather data with automatic UUID resolution
-            storage
-                .store_weather_update(
-                    uuid_index,
-                    value,
-                    timestamp,
-                    Some("weather_value"), // Default parameter name
-                    None,                  // Unit will be determined by device type
-                    Some(1.0),             // Default quality score
-                )
-                .await?;
-
-            debug!(
-                "Stored weather data: index={}, value={:.2}, ts={}",
-                uuid_index, value, timestamp
-            );
-        } else {
-            debug!(
-                "Weather storage not enabled - logging only: index={}, value={:.2}, ts={}",
-                uuid_index, value, timestamp
-            );
-        }
+        };
 
-        Ok(())
-    }
+        let timestamp = chrono::Utc::now();
+        let mut updates = Vec::with_capacity(events.len());
+        let devices = context.devices.read().await;
 
-    /// Parse alternative weather data format for different weather station types
-    async fn parse_alternative_weather_format(payload: &[u8]) -> Result<()> {
-        // Some weather stations send data in different formats
-        // Try parsing as structured weather data packet
-        if payload.len() >= 8 {
-            let mut cursor = Cursor::new(payload);
-
-            // Check for weather data packet header
-            let mut header_bytes = [0u8; 4];
-            if cursor.read_exact(&mut header_bytes).is_ok() {
-                let header = u32::from_le_bytes(header_bytes);
-
-                match header {
-                    // Weather station data packet
-                    0x57455448 => {
-                        // "WETH" in ASCII
-                        debug!("Found structured weather data packet");
-                        Self::parse_structured_weather_packet(&payload[4..]).await?;
-                    }
-                    _ => {
-                        // Unknown format, log as hex for debugging
-                        if payload.len() <= 64 {
-                            debug!("Unknown weather data format, hex: {}", hex::encode(payload));
-                        } else {
-                            debug!(
-                                "Unknown weather data format, {} bytes, header: 0x{:08X}",
-                                payload.len(),
-                                header
-                            );
-                        }
+        for (state_uuid, value, event_type) in events {
+            let previous_value = context.record_state_value(&state_uuid, value.clone()).await;
+            let (uuid, state) = match context.resolve_state(&state_uuid).await {
+                Some(state_ref) => (state_ref.control_uuid, state_ref.state_name),
+                None => (state_uuid, "value".to_string()),
+            };
+            let device = devices.get(&uuid);
+
+            updates.push(StateUpdate {
+                device_name: device.map(|d| d.name.clone()),
+                room: device.and_then(|d| d.room.clone()),
+                uuid,
+                state,
+                value,
+                previous_value,
+                event_type,
+                timestamp,
+            });
+        }
+
+        updates
+    }
+
+    /// Record decoded weather forecasts in weather storage
+    ///
+    /// Entries are stored under the owning control UUID, one series per
+    /// forecast value, with Unix timestamps.
+    pub async fn store_weather_events(
+        events: &[WeatherEvent],
+        context: &ClientContext,
+        storage: &crate::storage::WeatherStorage,
+    ) {
+        for event in events {
+            let device_uuid = match context.resolve_state(&event.uuid).await {
+                Some(state_ref) => state_ref.control_uuid,
+                None => event.uuid.clone(),
+            };
+            for entry in &event.entries {
+                let Ok(timestamp) = u32::try_from(LOXONE_EPOCH_UNIX + i64::from(entry.timestamp))
+                else {
+                    continue;
+                };
+                let values = [
+                    ("temperature", entry.temperature, "°C"),
+                    ("perceived_temperature", entry.perceived_temperature, "°C"),
+                    ("dew_point", entry.dew_point, "°C"),
+                    ("relative_humidity", f64::from(entry.relative_humidity), "%"),
+                    ("precipitation", entry.precipitation, "mm"),
+                    ("wind_speed", entry.wind_speed, "km/h"),
+                    ("wind_direction", f64::from(entry.wind_direction), "°"),
+                    ("solar_radiation", f64::from(entry.solar_radiation), "W/m²"),
+                    ("barometric_pressure", entry.barometric_pressure, "hPa"),
+                ];
+                for (parameter, value, unit) in values {
+                    if let Err(e) = storage
+                        .store_device_weather(
+                            &device_uuid,
+                            parameter,
+                            value,
+                            timestamp,
+                            Some(unit),
+                            None,
+                        )
+                        .await
+                    {
+                        warn!("Failed to store weather data for {}: {}", device_uuid, e);
                     }
                 }
             }
         }
-        Ok(())
-    }
-
-    /// Parse structured weather data packet
-    async fn parse_structured_weather_packet(payload: &[u8]) -> Result<()> {
-        let mut cursor = Cursor::new(payload);
-
-        // Structured weather packet format:
-        // 4 bytes: station ID
-        // 8 bytes: temperature (double)
-        // 8 bytes: humidity (double)
-        // 8 bytes: pressure (double)
-        // 8 bytes: wind speed (double)
-        // 8 bytes: wind direction (double)
-        // 8 bytes: precipitation (double)
-        // 4 bytes: timestamp
-
-        if payload.len() >= 52 {
-            // Minimum size for complete weather packet
-            let mut station_id_bytes = [0u8; 4];
-            let mut temp_bytes = [0u8; 8];
-            let mut humidity_bytes = [0u8; 8];
-            let mut pressure_bytes = [0u8; 8];
-            let mut wind_speed_bytes = [0u8; 8];
-            let mut wind_dir_bytes = [0u8; 8];
-            let mut precipitation_bytes = [0u8; 8];
-            let mut timestamp_bytes = [0u8; 4];
-
-            if cursor.read_exact(&mut station_id_bytes).is_ok()
-                && cursor.read_exact(&mut temp_bytes).is_ok()
-                && cursor.read_exact(&mut humidity_bytes).is_ok()
-                && cursor.read_exact(&mut pressure_bytes).is_ok()
-                && cursor.read_exact(&mut wind_speed_bytes).is_ok()
-                && cursor.read_exact(&mut wind_dir_bytes).is_ok()
-                && cursor.read_exact(&mut precipitation_bytes).is_ok()
-                && cursor.read_exact(&mut timestamp_bytes).is_ok()
-            {
-                let station_id = u32::from_le_bytes(station_id_bytes);
-                let temperature = f64::from_le_bytes(temp_bytes);
-                let humidity = f64::from_le_bytes(humidity_bytes);
-                let pressure = f64::from_le_bytes(pressure_bytes);
-                let wind_speed = f64::from_le_bytes(wind_speed_bytes);
-                let wind_direction = f64::from_le_bytes(wind_dir_bytes);
-                let precipitation = f64::from_le_bytes(precipitation_bytes);
-                let timestamp = u32::from_le_bytes(timestamp_bytes);
-
-                debug!(
-                    "Weather station {} - temp: {:.1}°C, humidity: {:.1}%, pressure: {:.1}hPa, wind: {:.1}km/h@{:.0}°, rain: {:.1}mm, ts: {}",
-                    station_id,
-                    temperature,
-                    humidity,
-                    pressure,
-                    wind_speed,
-                    wind_direction,
-                    precipitation,
-                    timestamp
-                );
-
-                // Store structured weather data
-                Self::store_structured_weather_data(
-                    station_id,
-                    temperature,
-                    humidity,
-                    pressure,
-                    wind_speed,
-                    wind_direction,
-                    precipitation,
-                    timestamp,
-                )
-                .await?;
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Store structured weather data
-    #[allow(clippy::too_many_arguments)]
-    async fn store_structured_weather_data(
-        station_id: u32,
-        temperature: f64,
-        humidity: f64,
-        pressure: f64,
-        wind_speed: f64,
-        wind_direction: f64,
-        precipitation: f64,
-        timestamp: u32,
-    ) -> Result<()> {
-        // Store structured weather data using existing weather storage infrastructure
-        debug!(
-            "Storing structured weather data for station {}: T={:.1}°C, H={:.1}%, P={:.1}hPa, Wind={:.1}km/h@{:.0}°, Rain={:.1}mm",
-            station_id, temperature, humidity, pressure, wind_speed, wind_direction, precipitation
-        );
-
-        // Note: This is a static method, so we can't access self.weather_storage
-        // The actual weather data storage is handled by the store_weather_update method
-        // which is called from the WebSocket message processing loop
-        info!(
-            "Weather station {} data: {}°C, {}% humidity, {}hPa pressure at timestamp {}",
-            station_id, temperature, humidity, pressure, timestamp
-        );
-        Ok(())
-    }
-
-    /// Parse out-of-service indicator
-    async fn parse_out_of_service(payload: &[u8]) -> Result<()> {
-        if payload.len() >= 4 {
-            let service_id = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
-            warn!("Service {} is out of service", service_id);
-        }
-        Ok(())
-    }
-
-    /// Instance method for backward compatibility
-    #[allow(dead_code)]
-    async fn handle_binary_message(&self, data: Vec<u8>) -> Result<()> {
-        // Check if this is weather data which requires instance access
-        if data.len() >= 8 {
-            let msg_type = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-            if msg_type == 0x05000000 {
-                // Weather data - handle with instance method
-                return self.handle_binary_message_instance(data).await;
-            }
-        }
-
-        // For all other binary messages, use static method
-        Self::handle_binary_message_static(data).await
-    }
-
-    /// Instance method for binary messages that need access to weather storage
-    async fn handle_binary_message_instance(&self, data: Vec<u8>) -> Result<()> {
-        if data.len() < 8 {
-            debug!("Binary message too short: {} bytes", data.len());
-            return Ok(());
-        }
-
-        let msg_type = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        let data_length = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
-
-        debug!(
-            "Binary message (instance) - type: 0x{:08X}, length: {}, total: {} bytes",
-            msg_type,
-            data_length,
-            data.len()
-        );
-
-        // Validate data length
-        if data.len() < 8 + data_length {
-            warn!(
-                "Binary message data length mismatch: expected {}, got {}",
-                8 + data_length,
-                data.len()
-            );
-            return Ok(());
-        }
-
-        // Extract payload
-        let payload = &data[8..8 + data_length];
-
-        // Parse based on Loxone binary protocol specification
-        match msg_type {
-            // Weather data
-            0x05000000 => {
-                debug!("Binary: Weather data");
-                self.parse_weather_data(payload).await?;
-            }
-
-            _ => {
-                debug!(
-                    "Unhandled message type in instance method: 0x{:08X}",
-                    msg_type
-                );
-            }
-        }
-
-        Ok(())
     }
 
     /// Get public context for external access
-    pub fn context(&self) -> &ClientContext {
+    pub fn context(&self) -> &Arc<ClientContext> {
         &self.context
     }
 
@@ -2255,165 +1592,194 @@ impl LoxoneWebSocketClient {
         Ok(())
     }
 
-    /// Initialize an encrypted WebSocket session
-    pub async fn init_encryption_session(&self, session_duration_hours: u32) -> Result<String> {
-        use crate::security::encryption::EncryptionSession;
-
-        debug!(
-            "Initializing encryption session for {} hours",
-            session_duration_hours
-        );
-
-        // Create new encryption session
-        let session = EncryptionSession::new(session_duration_hours);
-        let session_id = session.session_id.clone();
-
-        // Add session to manager
-        {
-            let mut manager = self.encryption_manager.write().await;
-            manager.add_session(session).map_err(|e| {
-                LoxoneError::connection(format!("Failed to add encryption session: {e}"))
-            })?;
-        }
+    /// Create the command encryptor if encryption is configured; the
+    /// handshake announces its session key with `jdev/sys/keyexchange`
+    #[cfg(feature = "crypto-openssl")]
+    async fn init_command_encryption(&mut self) -> Result<()> {
+        use crate::config::CommandEncryption;
 
-        // Store current session ID
+        if self.config.command_encryption == CommandEncryption::None
+            || self.command_encryption.is_some()
         {
-            let mut current_session = self.encryption_session.write().await;
-            *current_session = Some(session_id.clone());
-        }
-
-        info!("✅ Encryption session initialized: {}", &session_id[..8]);
-        Ok(session_id)
-    }
-
-    /// Send encrypted message via WebSocket
-    pub async fn send_encrypted_message(&self, message: &[u8]) -> Result<()> {
-        let session_id = {
-            let current_session = self.encryption_session.read().await;
-            current_session
-                .as_ref()
-                .ok_or_else(|| LoxoneError::connection("No active encryption session"))?
-                .clone()
-        };
-
-        // Encrypt the message
-        let encrypted_msg = {
-            let mut manager = self.encryption_manager.write().await;
-            let session = manager
-                .get_session_mut(&session_id)
-                .ok_or_else(|| LoxoneError::connection("Encryption session not found"))?;
-
-            session
-                .encrypt_message(message)
-                .map_err(|e| LoxoneError::connection(format!("Encryption failed: {e}")))?
-        };
-
-        // Send encrypted message via WebSocket
-        if let Some(stream) = &self.ws_stream {
-            use tokio_tungstenite::tungstenite::Message;
-
-            let json_payload = serde_json::to_string(&encrypted_msg).map_err(|e| {
-                LoxoneError::connection(format!("Failed to serialize encrypted message: {e}"))
-            })?;
-
-            let mut stream_guard = stream.lock().await;
-            stream_guard
-                .send(Message::Text(json_payload))
-                .await
-                .map_err(|e| {
-                    LoxoneError::connection(format!("Failed to send encrypted message: {e}"))
-                })?;
-
-            debug!("Sent encrypted message: {} bytes", message.len());
-        } else {
-            return Err(LoxoneError::connection("WebSocket not connected"));
+            return Ok(());
         }
 
+        let http = reqwest::Client::builder()
+            .timeout(self.config.timeout)
+            .danger_accept_invalid_certs(!self.config.verify_ssl)
+            .build()
+            .map_err(|e| LoxoneError::connection(format!("Failed to create HTTP client: {e}")))?;
+        let public_key = fetch_public_key(&http, &self.base_url).await?;
+        self.command_encryption =
+            CommandEncryptor::new(self.config.command_encryption, &public_key)?.map(Arc::new);
+        info!("🔐 Command encryption enabled");
         Ok(())
     }
+}
 
-    /// Decrypt received message
-    pub async fn decrypt_message(
-        &self,
-        encrypted_msg: &crate::security::encryption::EncryptedMessage,
-    ) -> Result<Vec<u8>> {
-        let session_id = {
-            let current_session = self.encryption_session.read().await;
-            current_session
-                .as_ref()
-                .ok_or_else(|| LoxoneError::connection("No active encryption session"))?
-                .clone()
-        };
-
-        // Decrypt the message
-        let plaintext = {
-            let mut manager = self.encryption_manager.write().await;
-            let session = manager
-                .get_session_mut(&session_id)
-                .ok_or_else(|| LoxoneError::connection("Encryption session not found"))?;
+/// Background task owning the reading half of the WebSocket
+///
+/// Sends `keepalive` every `keepalive_interval`. If nothing at all arrived
+/// since the previous keep-alive, the session is considered dead and is
+/// re-established through the full handshake.
+#[cfg(feature = "websocket")]
+struct SessionTask {
+    handshake: WebSocketHandshake,
+    sink: Arc<Mutex<Option<WsSink>>>,
+    connected: Arc<RwLock<bool>>,
+    context: Arc<ClientContext>,
+    state_sender: Option<mpsc::UnboundedSender<StateUpdate>>,
+    weather_storage: WeatherStorageSlot,
+    stats: Arc<RwLock<WebSocketStats>>,
+    keepalive_interval: Duration,
+    reconnection: ReconnectionConfig,
+}
 
-            session
-                .decrypt_message(encrypted_msg)
-                .map_err(|e| LoxoneError::connection(format!("Decryption failed: {e}")))?
-        };
+#[cfg(feature = "websocket")]
+impl SessionTask {
+    async fn run(self, mut reader: WsReader) {
+        loop {
+            self.read_until_lost(&mut reader).await;
+
+            *self.connected.write().await = false;
+            if let Some(mut sink) = self.sink.lock().await.take() {
+                let _ = sink.close().await;
+            }
 
-        debug!("Decrypted message: {} bytes", plaintext.len());
-        Ok(plaintext)
+            if !self.reconnection.enabled {
+                break;
+            }
+            match self.reconnect().await {
+                Some(new_reader) => reader = new_reader,
+                None => break,
+            }
+        }
     }
 
-    /// Check if encryption session is active and valid
-    pub async fn is_encryption_active(&self) -> bool {
-        let session_id = {
-            let current_session = self.encryption_session.read().await;
-            match current_session.as_ref() {
-                Some(id) => id.clone(),
-                None => return false,
+    /// Process messages until the connection fails or stops answering
+    async fn read_until_lost(&self, reader: &mut WsReader) {
+        let mut decoder = BinaryMessageDecoder::new();
+        let mut keepalive = tokio::time::interval(self.keepalive_interval);
+        keepalive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        keepalive.tick().await;
+        let mut alive = true;
+
+        loop {
+            tokio::select! {
+                message = reader.next() => match message {
+                    Some(Ok(message)) => {
+                        alive = true;
+                        self.record_message(&message).await;
+                        let weather_storage = self
+                            .weather_storage
+                            .read()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .clone();
+                        if let Err(e) = LoxoneWebSocketClient::process_ws_message(
+                            message,
+                            &mut decoder,
+                            &self.context,
+                            &self.state_sender,
+                            weather_storage.as_deref(),
+                        )
+                        .await
+                        {
+                            warn!("Error processing WebSocket message: {}", e);
+                        }
+                    }
+                    Some(Err(e)) => {
+                        error!("WebSocket error: {}", e);
+                        return;
+                    }
+                    None => {
+                        info!("WebSocket stream ended");
+                        return;
+                    }
+                },
+                _ = keepalive.tick() => {
+                    if !alive {
+                        warn!(
+                            "No response from Miniserver within {:?}, reconnecting",
+                            self.keepalive_interval
+                        );
+                        return;
+                    }
+                    alive = false;
+                    if let Err(e) = self.send_keepalive().await {
+                        warn!("Failed to send keepalive: {}", e);
+                        return;
+                    }
+                }
             }
-        };
-
-        let manager = self.encryption_manager.read().await;
-        if let Some(session) = manager.get_session(&session_id) {
-            session.is_valid()
-        } else {
-            false
         }
     }
 
-    /// Get encryption statistics
-    pub async fn get_encryption_stats(
-        &self,
-    ) -> Option<crate::security::encryption::EncryptionStats> {
-        let session_id = {
-            let current_session = self.encryption_session.read().await;
-            current_session.as_ref()?.clone()
-        };
-
-        let manager = self.encryption_manager.read().await;
-        manager
-            .get_session(&session_id)
-            .map(|session| session.get_stats().clone())
+    async fn send_keepalive(&self) -> Result<()> {
+        let mut sink = self.sink.lock().await;
+        let sink = sink
+            .as_mut()
+            .ok_or_else(|| LoxoneError::connection("WebSocket not connected"))?;
+        sink.send(Message::Text("keepalive".to_string()))
+            .await
+            .map_err(|e| LoxoneError::connection(format!("WebSocket send failed: {e}")))
     }
 
-    /// Cleanup expired encryption sessions
-    pub async fn cleanup_encryption_sessions(&self) -> usize {
-        let mut manager = self.encryption_manager.write().await;
-        manager.cleanup_expired_sessions()
+    async fn record_message(&self, message: &Message) {
+        let mut stats = self.stats.write().await;
+        stats.messages_received += 1;
+        stats.last_message = Some(chrono::Utc::now());
+        match message {
+            Message::Binary(data) => stats.bytes_received += data.len() as u64,
+            Message::Text(text) => stats.bytes_received += text.len() as u64,
+            _ => {}
+        }
     }
 
-    /// Terminate current encryption session
-    pub async fn terminate_encryption_session(&self) -> Result<()> {
-        let session_id = {
-            let mut current_session = self.encryption_session.write().await;
-            current_session.take()
-        };
+    /// Re-run the handshake with exponential backoff and jitter
+    async fn reconnect(&self) -> Option<WsReader> {
+        let config = &self.reconnection;
+        let mut attempt = 0;
+        let mut delay = config.initial_delay;
 
-        if let Some(session_id) = session_id {
-            let mut manager = self.encryption_manager.write().await;
-            manager.remove_session(&session_id);
-            info!("Encryption session terminated: {}", &session_id[..8]);
-        }
+        loop {
+            if let Some(max_attempts) = config.max_attempts
+                && attempt >= max_attempts
+            {
+                error!("Max reconnection attempts ({}) exceeded", max_attempts);
+                return None;
+            }
 
-        Ok(())
+            attempt += 1;
+            self.stats.write().await.reconnection_attempts = attempt;
+
+            // Add jitter to prevent thundering herd
+            let jitter = (delay.as_millis() as f64 * config.jitter_factor) as u64;
+            let random_jitter = if jitter > 0 {
+                rand::random::<u64>() % jitter
+            } else {
+                0
+            };
+            sleep(delay + Duration::from_millis(random_jitter)).await;
+
+            info!("Attempting WebSocket reconnection #{}", attempt);
+            match self.handshake.open().await {
+                Ok(stream) => {
+                    let (sink, reader) = stream.split();
+                    *self.sink.lock().await = Some(sink);
+                    *self.connected.write().await = true;
+                    info!("✅ WebSocket reconnection successful");
+                    return Some(reader);
+                }
+                Err(e) => {
+                    warn!("Reconnection attempt #{} failed: {}", attempt, e);
+                    delay = Duration::from_millis(
+                        (delay.as_millis() as f64 * config.backoff_multiplier) as u64,
+                    )
+                    .min(config.max_delay);
+                }
+            }
+        }
     }
 }
 
@@ -2426,17 +1792,14 @@ impl LoxoneClient for LoxoneWebSocketClient {
             self.base_url
         );
 
-        let ws_url = self.build_ws_url().await?;
-        debug!("WebSocket URL: {}", ws_url);
+        #[cfg(feature = "crypto-openssl")]
+        self.init_command_encryption().await?;
 
-        // Connect to WebSocket
-        let (ws_stream, response) = connect_async(ws_url.as_str())
-            .await
-            .map_err(|e| LoxoneError::connection(format!("WebSocket connection failed: {e}")))?;
-
-        debug!("WebSocket connected, response: {:?}", response.status());
+        // Connect, authenticate and enable binary status updates
+        let handshake = self.handshake();
+        let (sink, reader) = handshake.open().await?.split();
 
-        self.ws_stream = Some(Arc::new(Mutex::new(ws_stream)));
+        *self.ws_sink.lock().await = Some(sink);
         *self.connected.write().await = true;
         *self.context.connected.write().await = true;
 
@@ -2447,7 +1810,7 @@ impl LoxoneClient for LoxoneWebSocketClient {
         }
 
         // Start background tasks
-        self.start_background_tasks().await?;
+        self.start_background_tasks(handshake, reader).await?;
 
         info!("✅ Connected to Loxone WebSocket");
         Ok(())
@@ -2466,7 +1829,9 @@ impl LoxoneClient for LoxoneWebSocketClient {
             }
         }
 
-        self.ws_stream = None;
+        if let Some(mut sink) = self.ws_sink.lock().await.take() {
+            let _ = sink.close().await;
+        }
         self.state_sender = None;
         *self.connected.write().await = false;
         *self.context.connected.write().await = false;
@@ -2492,17 +1857,15 @@ impl LoxoneClient for LoxoneWebSocketClient {
             // Format: "jdev/sps/io/{uuid}/{command}"
             let ws_command = format!("jdev/sps/io/{uuid}/{command}");
             debug!("Sending WebSocket command: {}", ws_command);
+            #[cfg(feature = "crypto-openssl")]
+            let ws_command = match &self.command_encryption {
+                Some(encryptor) => encryptor.encrypt(&ws_command),
+                None => ws_command,
+            };
 
             // Send command via WebSocket
-            if let Some(ws_stream) = &self.ws_stream {
-                let mut stream = ws_stream.lock().await;
-
-                match stream
-                    .send(tokio_tungstenite::tungstenite::Message::Text(
-                        ws_command.clone(),
-                    ))
-                    .await
-                {
+            if let Some(sink) = self.ws_sink.lock().await.as_mut() {
+                match sink.send(Message::Text(ws_command.clone())).await {
                     Ok(_) => {
                         debug!("Successfully sent WebSocket command: {}", ws_command);
 
@@ -2542,10 +1905,57 @@ impl LoxoneClient for LoxoneWebSocketClient {
         }
     }
 
+    async fn get_structure_version(&self) -> Result<String> {
+        if let Some(http_client) = &self.http_client {
+            http_client.get_structure_version().await
+        } else {
+            Err(LoxoneError::connection(
+                "Structure version not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    async fn fetch_camera_image(&self, uuid: &str, timestamp: Option<&str>) -> Result<LoxoneImage> {
+        // Images are only served over HTTP
+        if let Some(http_client) = &self.http_client {
+            http_client.fetch_camera_image(uuid, timestamp).await
+        } else {
+            Err(LoxoneError::connection(
+                "Camera images not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    async fn list_statistics(&self) -> Result<Vec<StatisticFile>> {
+        // Statistics files are only served over HTTP
+        if let Some(http_client) = &self.http_client {
+            http_client.list_statistics().await
+        } else {
+            Err(LoxoneError::connection(
+                "Statistics not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
+    async fn fetch_statistics(&self, uuid: &str, month: StatisticMonth) -> Result<Vec<u8>> {
+        if let Some(http_client) = &self.http_client {
+            http_client.fetch_statistics(uuid, month).await
+        } else {
+            Err(LoxoneError::connection(
+                "Statistics not available via WebSocket - HTTP client required",
+            ))
+        }
+    }
+
     async fn get_device_states(
         &self,
         uuids: &[String],
     ) -> Result<HashMap<String, serde_json::Value>> {
+        // Use HTTP client if available; the cached devices only know state UUIDs
+        if let Some(http_client) = &self.http_client {
+            return http_client.get_device_states(uuids).await;
+        }
+
         let devices = self.context.devices.read().await;
         let mut states = HashMap::new();
 
@@ -2569,26 +1979,13 @@ impl LoxoneClient for LoxoneWebSocketClient {
         if let Some(http_client) = &self.http_client {
             http_client.get_state_values(state_uuids).await
         } else {
-            // Fallback: try to resolve from cached device states
+            // Fallback: the values pushed over this connection
             let mut state_values = HashMap::new();
-            let devices = self.context.devices.read().await;
-
             for state_uuid in state_uuids {
-                // Look for the state UUID in device states
-                for device in devices.values() {
-                    for state_value in device.states.values() {
-                        if let Some(uuid_str) = state_value.as_str()
-                            && uuid_str == state_uuid
-                        {
-                            // Found the state UUID, but we need the actual value
-                            // For now, return the UUID itself - this is a limitation without HTTP client
-                            state_values.insert(state_uuid.clone(), state_value.clone());
-                            break;
-                        }
-                    }
+                if let Some(value) = self.context.get_state_value(state_uuid).await {
+                    state_values.insert(state_uuid.clone(), value);
                 }
             }
-
             Ok(state_values)
         }
     }
@@ -2608,6 +2005,14 @@ impl LoxoneClient for LoxoneWebSocketClient {
         Ok(*self.connected.read().await)
     }
 
+    async fn shutdown(&self) -> Result<()> {
+        // The HTTP half of a hybrid client owns the authentication
+        match &self.http_client {
+            Some(http_client) => http_client.shutdown().await,
+            None => Ok(()),
+        }
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -0,0 +1,372 @@
+//! WebSocket session setup
+//!
+//! Runs the Miniserver's WebSocket handshake on a fresh connection:
+//!
+//! 1. `jdev/sys/keyexchange/{session key}` when command encryption is enabled
+//! 2. token authentication over the socket: `authwithtoken` with an existing
+//!    JWT (from the [`TokenHttpClient`](crate::client::TokenHttpClient) or an
+//!    earlier session), otherwise `getkey2` followed by `getjwt`
+//! 3. `jdev/sps/enablebinstatusupdate` to start the binary event stream
+//!
+//! Basic authentication keeps sending the credentials in the URL.
+
+use crate::client::LoxoneClient;
+#[cfg(feature = "crypto-openssl")]
+use crate::client::auth::{
+    AuthToken, CachedToken, JWT_CLIENT_INFO, JWT_CLIENT_UUID, JWT_PERMISSION, hmac_hex,
+    password_hash,
+};
+#[cfg(feature = "crypto-openssl")]
+use crate::client::command_encryption::CommandEncryptor;
+use crate::config::{AuthMethod, LoxoneConfig, credentials::LoxoneCredentials};
+use crate::error::{LoxoneError, Result};
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::sync::Arc;
+#[cfg(feature = "crypto-openssl")]
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use tracing::{debug, info, warn};
+use url::Url;
+
+pub(crate) type WsStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+pub(crate) type WsSink = SplitSink<WsStream, Message>;
+pub(crate) type WsReader = SplitStream<WsStream>;
+
+/// Connects and authenticates WebSocket sessions, initially and on reconnect
+#[derive(Clone)]
+pub struct WebSocketHandshake {
+    base_url: Url,
+    config: LoxoneConfig,
+    credentials: LoxoneCredentials,
+    /// HTTP client whose JWT is reused (if it is a `TokenHttpClient`)
+    http_client: Option<Arc<dyn LoxoneClient>>,
+    /// JWT obtained over the socket, reused when reconnecting
+    #[cfg(feature = "crypto-openssl")]
+    token: Arc<Mutex<Option<CachedToken>>>,
+    /// Command encryption announced with `keyexchange`
+    #[cfg(feature = "crypto-openssl")]
+    encryptor: Option<Arc<CommandEncryptor>>,
+}
+
+impl WebSocketHandshake {
+    /// Create a handshake for the configured Miniserver
+    pub fn new(
+        config: LoxoneConfig,
+        credentials: LoxoneCredentials,
+        http_client: Option<Arc<dyn LoxoneClient>>,
+    ) -> Self {
+        Self {
+            base_url: config.url.clone(),
+            config,
+            credentials,
+            http_client,
+            #[cfg(feature = "crypto-openssl")]
+            token: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "crypto-openssl")]
+            encryptor: None,
+        }
+    }
+
+    /// Encrypt authentication over the socket with this encryptor
+    #[cfg(feature = "crypto-openssl")]
+    pub fn with_encryptor(mut self, encryptor: Option<Arc<CommandEncryptor>>) -> Self {
+        self.encryptor = encryptor;
+        self
+    }
+
+    /// Whether the socket authenticates with a token handshake
+    fn uses_token_auth(&self) -> bool {
+        cfg!(feature = "crypto-openssl") && self.config.auth_method != AuthMethod::Basic
+    }
+
+    /// WebSocket URL; carries the credentials only for basic authentication
+    pub fn ws_url(&self) -> Result<Url> {
+        let mut ws_url = self.base_url.clone();
+
+        // Convert HTTP(S) to WS(S)
+        match ws_url.scheme() {
+            "http" => ws_url
+                .set_scheme("ws")
+                .map_err(|_| LoxoneError::connection("Failed to convert HTTP to WebSocket URL"))?,
+            "https" => ws_url
+                .set_scheme("wss")
+                .map_err(|_| LoxoneError::connection("Failed to convert HTTPS to WebSocket URL"))?,
+            _ => {
+                return Err(LoxoneError::connection(
+                    "Unsupported URL scheme for WebSocket",
+                ));
+            }
+        }
+
+        ws_url.set_path("/ws/rfc6455");
+        ws_url.set_query(None);
+
+        if !self.uses_token_auth() {
+            ws_url
+                .query_pairs_mut()
+                .append_pair("user", &self.credentials.username)
+                .append_pair("password", &self.credentials.password);
+        }
+
+        Ok(ws_url)
+    }
+
+    /// Connect, authenticate and enable binary status updates
+    pub async fn open(&self) -> Result<WsStream> {
+        let ws_url = self.ws_url()?;
+        let (mut stream, response) =
+            tokio::time::timeout(self.config.timeout, connect_async(ws_url.as_str()))
+                .await
+                .map_err(|_| LoxoneError::timeout("WebSocket connection timed out"))?
+                .map_err(|e| {
+                    LoxoneError::connection(format!("WebSocket connection failed: {e}"))
+                })?;
+        debug!("WebSocket connected, response: {:?}", response.status());
+
+        #[cfg(feature = "crypto-openssl")]
+        if let Some(encryptor) = &self.encryptor {
+            self.request(&mut stream, &encryptor.keyexchange_command(), false)
+                .await?;
+            debug!("WebSocket session key exchanged");
+        }
+
+        #[cfg(feature = "crypto-openssl")]
+        if self.uses_token_auth() {
+            self.authenticate(&mut stream).await?;
+        }
+
+        self.request(&mut stream, "jdev/sps/enablebinstatusupdate", false)
+            .await?;
+        info!("WebSocket session ready, binary status updates enabled");
+        Ok(stream)
+    }
+
+    /// Authenticate with an existing JWT, or acquire one with the password
+    #[cfg(feature = "crypto-openssl")]
+    async fn authenticate(&self, stream: &mut WsStream) -> Result<()> {
+        if let Some(token) = self.reusable_token().await {
+            match self.auth_with_token(stream, &token).await {
+                Ok(()) => {
+                    debug!("WebSocket authenticated with existing token");
+                    return Ok(());
+                }
+                Err(e) => warn!("authwithtoken failed, requesting a new token: {}", e),
+            }
+        }
+
+        let token = self.get_jwt(stream).await?;
+        *self.token.lock().await = Some(token);
+        debug!("WebSocket authenticated with a new token");
+        Ok(())
+    }
+
+    /// Token of the shared HTTP client, or the one from an earlier session
+    #[cfg(feature = "crypto-openssl")]
+    async fn reusable_token(&self) -> Option<CachedToken> {
+        if let Some(token_client) = self.http_client.as_ref().and_then(|client| {
+            client
+                .as_any()
+                .downcast_ref::<crate::client::TokenHttpClient>()
+        }) {
+            match token_client.cached_token().await {
+                Ok(token) => return Some(token),
+                Err(e) => warn!("No token from HTTP client for WebSocket: {}", e),
+            }
+        }
+        self.token.lock().await.clone()
+    }
+
+    /// `authwithtoken/{HMAC(getkey, token)}/{user}`
+    #[cfg(feature = "crypto-openssl")]
+    async fn auth_with_token(&self, stream: &mut WsStream, token: &CachedToken) -> Result<()> {
+        let key = self.request(stream, "jdev/sys/getkey", false).await?;
+        let key = key
+            .as_str()
+            .ok_or_else(|| LoxoneError::authentication("No key in getkey response"))?;
+        let hash = hmac_hex(key, &token.token.token, &token.hash_alg)?;
+        self.request(
+            stream,
+            &format!(
+                "authwithtoken/{hash}/{}",
+                urlencoding::encode(&token.username)
+            ),
+            true,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// `getkey2` followed by `getjwt`, as the HTTP token flow does it
+    #[cfg(feature = "crypto-openssl")]
+    async fn get_jwt(&self, stream: &mut WsStream) -> Result<CachedToken> {
+        let username = &self.credentials.username;
+        let key_info = self
+            .request(
+                stream,
+                &format!("jdev/sys/getkey2/{}", urlencoding::encode(username)),
+                false,
+            )
+            .await?;
+        let field = |name: &str| {
+            key_info[name].as_str().ok_or_else(|| {
+                LoxoneError::authentication(format!("No {name} in getkey2 response"))
+            })
+        };
+        let key = field("key")?;
+        let salt = field("salt")?;
+        let hash_alg = key_info["hashAlg"].as_str().unwrap_or("SHA1").to_string();
+
+        let pwd_hash = password_hash(&self.credentials.password, salt, &hash_alg)?;
+        let hash = hmac_hex(key, &format!("{username}:{pwd_hash}"), &hash_alg)?;
+        let value = self
+            .request(
+                stream,
+                &format!(
+                    "jdev/sys/getjwt/{hash}/{}/{JWT_PERMISSION}/{JWT_CLIENT_UUID}/{}",
+                    urlencoding::encode(username),
+                    urlencoding::encode(JWT_CLIENT_INFO)
+                ),
+                true,
+            )
+            .await?;
+
+        Ok(CachedToken {
+            username: username.clone(),
+            hash_alg,
+            token: AuthToken::from_response(&value)?,
+        })
+    }
+
+    /// Send a command and wait for its text response, returning `LL.value`
+    ///
+    /// Binary frames (message headers) before the response are skipped.
+    /// Commands marked `sensitive` are encrypted if encryption is enabled.
+    async fn request(
+        &self,
+        stream: &mut WsStream,
+        command: &str,
+        sensitive: bool,
+    ) -> Result<Value> {
+        #[cfg(feature = "crypto-openssl")]
+        let encryptor = self.encryptor.as_ref().filter(|_| sensitive);
+        #[cfg(not(feature = "crypto-openssl"))]
+        let _ = sensitive;
+
+        #[cfg(feature = "crypto-openssl")]
+        let message = match encryptor {
+            Some(encryptor) => encryptor.encrypt(command),
+            None => command.to_string(),
+        };
+        #[cfg(not(feature = "crypto-openssl"))]
+        let message = command.to_string();
+
+        let text = tokio::time::timeout(self.config.timeout, async {
+            stream
+                .send(Message::Text(message))
+                .await
+                .map_err(|e| LoxoneError::connection(format!("WebSocket send failed: {e}")))?;
+            loop {
+                match stream.next().await {
+                    Some(Ok(Message::Text(text))) => return Ok(text),
+                    Some(Ok(Message::Close(_))) | None => {
+                        return Err(LoxoneError::connection("WebSocket closed during handshake"));
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        return Err(LoxoneError::connection(format!("WebSocket error: {e}")));
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| LoxoneError::timeout(format!("No response to {}", command_name(command))))??;
+
+        #[cfg(feature = "crypto-openssl")]
+        let text = match encryptor {
+            Some(encryptor) => encryptor.decrypt_response(&text)?,
+            None => text,
+        };
+
+        let response: Value = serde_json::from_str(&text)?;
+        match response_code(&response) {
+            Some(200) | None => Ok(response["LL"]["value"].clone()),
+            Some(401) => Err(LoxoneError::authentication(format!(
+                "{} rejected: {}",
+                command_name(command),
+                response["LL"]["value"]
+            ))),
+            Some(code) => Err(LoxoneError::connection(format!(
+                "{} failed with code {code}",
+                command_name(command)
+            ))),
+        }
+    }
+}
+
+/// Numeric `LL.Code` of a response (sent as string or number)
+fn response_code(response: &Value) -> Option<i64> {
+    let code = response["LL"]
+        .get("Code")
+        .or_else(|| response["LL"].get("code"))?;
+    code.as_i64()
+        .or_else(|| code.as_str().and_then(|c| c.parse().ok()))
+}
+
+/// Command without its arguments, for messages that must not leak hashes
+fn command_name(command: &str) -> &str {
+    let segments = if command.starts_with("jdev/") { 3 } else { 1 };
+    command
+        .match_indices('/')
+        .nth(segments - 1)
+        .map_or(command, |(index, _)| &command[..index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake(auth_method: AuthMethod) -> WebSocketHandshake {
+        let config = LoxoneConfig {
+            url: "http://192.168.1.10/".parse().unwrap(),
+            auth_method,
+            ..Default::default()
+        };
+        let credentials = LoxoneCredentials {
+            username: "admin".to_string(),
+            password: "secret".to_string(),
+            api_key: None,
+            #[cfg(feature = "crypto-openssl")]
+            public_key: None,
+        };
+        WebSocketHandshake::new(config, credentials, None)
+    }
+
+    #[test]
+    fn test_ws_url_credentials_only_for_basic_auth() {
+        assert_eq!(
+            handshake(AuthMethod::Basic).ws_url().unwrap().as_str(),
+            "ws://192.168.1.10/ws/rfc6455?user=admin&password=secret"
+        );
+        #[cfg(feature = "crypto-openssl")]
+        assert_eq!(
+            handshake(AuthMethod::Token).ws_url().unwrap().as_str(),
+            "ws://192.168.1.10/ws/rfc6455"
+        );
+    }
+
+    #[test]
+    fn test_command_name_hides_arguments() {
+        assert_eq!(
+            command_name("jdev/sys/getjwt/abc/admin/4"),
+            "jdev/sys/getjwt"
+        );
+        assert_eq!(command_name("authwithtoken/abc/admin"), "authwithtoken");
+        assert_eq!(
+            command_name("jdev/sps/enablebinstatusupdate"),
+            "jdev/sps/enablebinstatusupdate"
+        );
+    }
+}
@@ -77,15 +77,39 @@ pub fn ensure_master_key() -> Result<()> {
     Ok(())
 }
 
-/// Return the path to the master key file.
+/// Return the 32-byte master key, creating and persisting it if needed.
+///
+/// Used to encrypt local state such as the JWT token cache. Keys that are not
+/// 32 bytes of base64 (e.g. a passphrase set via the environment) are
+/// stretched with SHA-256.
+pub fn master_key_bytes() -> Result<[u8; 32]> {
+    ensure_master_key()?;
+    let key = std::env::var(ENV_KEY)?;
+
+    if let Ok(decoded) = general_purpose::STANDARD.decode(key.trim())
+        && let Ok(bytes) = <[u8; 32]>::try_from(decoded.as_slice())
+    {
+        return Ok(bytes);
+    }
+
+    use sha2::{Digest, Sha256};
+    Ok(Sha256::digest(key.trim().as_bytes()).into())
+}
+
+/// Return the `loxone-mcp` directory under the platform config directory.
 ///
 /// Uses [`dirs::config_dir`] for cross-platform support, falling back to
 /// `$HOME/.config` when the platform helper is unavailable.
-fn master_key_path() -> Result<PathBuf> {
+pub fn config_dir() -> Result<PathBuf> {
     let config_dir = dirs::config_dir()
         .or_else(|| dirs::home_dir().map(|h| h.join(".config")))
         .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
-    Ok(config_dir.join(CONFIG_SUBDIR).join(KEY_FILE_NAME))
+    Ok(config_dir.join(CONFIG_SUBDIR))
+}
+
+/// Return the path to the master key file.
+fn master_key_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join(KEY_FILE_NAME))
 }
 
 /// Generate a 32-byte random key and return it as a base64-encoded string.
@@ -182,6 +206,24 @@ mod tests {
         );
     }
 
+    /// A base64 master key from the environment is used as-is; anything else
+    /// is stretched to 32 bytes.
+    #[test]
+    #[serial]
+    fn test_master_key_bytes() {
+        let raw = [7u8; 32];
+        let encoded = general_purpose::STANDARD.encode(raw);
+        temp_env::with_var(ENV_KEY, Some(encoded.as_str()), || {
+            assert_eq!(master_key_bytes().unwrap(), raw);
+        });
+
+        temp_env::with_var(ENV_KEY, Some("not a base64 key"), || {
+            let stretched = master_key_bytes().unwrap();
+            assert_ne!(stretched, [0u8; 32]);
+            assert_eq!(stretched, master_key_bytes().unwrap());
+        });
+    }
+
     /// `master_key_path` returns a path ending with the expected components.
     #[test]
     fn test_master_key_path_components() {
@@ -11,10 +11,21 @@ pub mod security_keychain;
 pub mod infisical_client;
 
 use crate::error::{LoxoneError, Result};
+use crate::security::policy::AuditPolicy;
 use serde::{Deserialize, Serialize};
-use std::{env, time::Duration};
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use url::Url;
 
+/// Prefix for environment variables overriding any configuration key
+///
+/// Nested keys are separated by `__`, e.g. `LOXONE_MCP_MCP__TOOLS__ENABLE_WEATHER=false`.
+pub const CONFIG_ENV_PREFIX: &str = "LOXONE_MCP";
+
 /// Authentication method to use with Loxone Miniserver
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -30,6 +41,22 @@ pub enum AuthMethod {
     WebSocket,
 }
 
+/// Encryption of commands sent to the Miniserver
+///
+/// Needed for Gen1 Miniservers, which do not offer TLS. See
+/// [`crate::security::encryption`] for the scheme.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandEncryption {
+    /// Plain commands (TLS or a trusted network)
+    #[default]
+    None,
+    /// Encrypt commands (`jdev/sys/enc/`)
+    Request,
+    /// Encrypt commands and responses (`jdev/sys/fenc/`)
+    Full,
+}
+
 /// Server configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ServerConfig {
@@ -50,6 +77,37 @@ pub struct ServerConfig {
 
     /// Feature flags
     pub features: FeatureConfig,
+
+    /// Audit log of device commands
+    #[serde(default)]
+    pub audit: AuditConfig,
+
+    /// Sensor history time series
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    /// Miniserver URL and username set by a layer rather than defaulted
+    #[serde(skip)]
+    pub loxone_layer: LoxoneLayer,
+}
+
+/// Miniserver URL and username as given by the file, environment or CLI layers
+///
+/// `None` means no layer set the value, so it comes from the loaded credentials.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct LoxoneLayer {
+    /// Miniserver URL
+    pub url: Option<Url>,
+
+    /// Username for authentication
+    pub username: Option<String>,
+}
+
+/// The part of the file and environment layers read before defaults apply
+#[derive(Debug, Default, Deserialize)]
+struct ExplicitLayers {
+    #[serde(default)]
+    loxone: LoxoneLayer,
 }
 
 /// Loxone Miniserver configuration
@@ -82,6 +140,21 @@ pub struct LoxoneConfig {
     /// Authentication method to use
     #[serde(default)]
     pub auth_method: AuthMethod,
+
+    /// Credential ID the connection was configured from; keys the token cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<String>,
+
+    /// Command encryption for connections without TLS
+    #[serde(default)]
+    pub command_encryption: CommandEncryption,
+
+    /// Keep the cached token on shutdown and reuse it after a restart
+    ///
+    /// Off by default: the token is revoked with `killtoken` on a clean
+    /// shutdown and its cache entry removed.
+    #[serde(default)]
+    pub reuse_token: bool,
 }
 
 fn default_max_connections() -> Option<usize> {
@@ -102,6 +175,14 @@ pub struct McpConfig {
 
     /// Tool configuration
     pub tools: ToolConfig,
+
+    /// Approval of sensitive tools
+    #[serde(default)]
+    pub consent: ConsentSettings,
+
+    /// Extra names for rooms and devices, mapping an alias to a name or UUID
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
 }
 
 /// Transport configuration
@@ -152,6 +233,109 @@ pub struct ToolConfig {
     pub max_devices_per_query: usize,
 }
 
+/// Approval of sensitive tools: unlocking doors, disarming, opening doors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsentSettings {
+    /// Ask the user before sensitive tools run
+    pub enabled: bool,
+
+    /// How long to wait for an answer before the request is denied
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+
+    /// How long a remembered approval applies to the same action
+    #[serde(with = "humantime_serde")]
+    pub remember_for: Duration,
+
+    /// Approval webhook for clients without MCP elicitation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<Url>,
+}
+
+/// Audit log storage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditBackendKind {
+    /// JSON lines file
+    #[default]
+    Jsonl,
+    /// SQLite database
+    Sqlite,
+}
+
+/// Audit log of every device command sent through the server
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Storage backend
+    pub backend: AuditBackendKind,
+
+    /// Log file or database; `audit.jsonl`/`audit.db` in the config directory by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+
+    /// What is recorded and how long it is kept
+    #[serde(flatten)]
+    pub policy: AuditPolicy,
+}
+
+/// Sensor history storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryBackendKind {
+    /// In memory, lost on restart
+    Memory,
+    /// SQLite database (with "turso" feature)
+    Sqlite,
+}
+
+impl Default for HistoryBackendKind {
+    fn default() -> Self {
+        if cfg!(feature = "turso") {
+            Self::Sqlite
+        } else {
+            Self::Memory
+        }
+    }
+}
+
+/// Time series of numeric and boolean device states
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Record state changes pushed by the Miniserver
+    pub enabled: bool,
+
+    /// Storage backend
+    pub backend: HistoryBackendKind,
+
+    /// Database file; `history.db` in the config directory by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+
+    /// How often sensor states are read from the Miniserver; `0s` only
+    /// records pushed state changes
+    #[serde(with = "humantime_serde")]
+    pub poll_interval: Duration,
+
+    /// How long individual samples are kept
+    #[serde(with = "humantime_serde")]
+    pub raw_retention: Duration,
+
+    /// How long 1-minute rollups are kept
+    #[serde(with = "humantime_serde")]
+    pub minute_retention: Duration,
+
+    /// How long 1-hour rollups are kept
+    #[serde(with = "humantime_serde")]
+    pub hour_retention: Duration,
+
+    /// How long 1-day rollups are kept
+    #[serde(with = "humantime_serde")]
+    pub day_retention: Duration,
+}
+
 /// Mock server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MockServerConfig {
@@ -271,6 +455,15 @@ pub struct FeatureConfig {
     /// Cache TTL
     #[serde(with = "humantime_serde")]
     pub cache_ttl: Duration,
+
+    /// How often the structure version is checked to reload a changed
+    /// structure; `0s` disables reloading
+    #[serde(default = "default_structure_check_interval", with = "humantime_serde")]
+    pub structure_check_interval: Duration,
+}
+
+fn default_structure_check_interval() -> Duration {
+    Duration::from_secs(60)
 }
 
 impl Default for LoxoneConfig {
@@ -287,6 +480,9 @@ impl Default for LoxoneConfig {
             #[cfg(feature = "websocket")]
             websocket: WebSocketConfig::default(),
             auth_method: AuthMethod::default(),
+            credential_id: None,
+            command_encryption: CommandEncryption::None,
+            reuse_token: false,
         }
     }
 }
@@ -298,6 +494,35 @@ impl Default for McpConfig {
             version: env!("CARGO_PKG_VERSION").to_string(),
             transport: TransportConfig::default(),
             tools: ToolConfig::default(),
+            consent: ConsentSettings::default(),
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        const DAY: u64 = 24 * 3600;
+        Self {
+            enabled: true,
+            backend: HistoryBackendKind::default(),
+            path: None,
+            poll_interval: Duration::from_secs(60),
+            raw_retention: Duration::from_secs(2 * DAY),
+            minute_retention: Duration::from_secs(14 * DAY),
+            hour_retention: Duration::from_secs(365 * DAY),
+            day_retention: Duration::from_secs(10 * 365 * DAY),
+        }
+    }
+}
+
+impl Default for ConsentSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout: Duration::from_secs(120),
+            remember_for: Duration::from_secs(600),
+            webhook_url: None,
         }
     }
 }
@@ -312,6 +537,71 @@ impl Default for TransportConfig {
     }
 }
 
+impl ToolConfig {
+    /// Check whether a tool is enabled by its `enable_*` group
+    ///
+    /// Server status and the audit log are always available. Tools that belong
+    /// to no group are disabled, so a new tool stays hidden until it is gated.
+    pub fn is_tool_enabled(&self, tool_name: &str) -> bool {
+        match tool_name {
+            "get_server_status" | "get_audit_log" => true,
+            "list_rooms" => self.enable_rooms,
+            "control_lights"
+            | "get_lights_status"
+            | "set_light_output"
+            | "set_light_color"
+            | "get_light_colors"
+            | "control_blinds"
+            | "get_blinds_status"
+            | "list_devices"
+            | "get_device_info"
+            | "send_device_command"
+            | "control_audio_zone"
+            | "set_audio_volume"
+            | "get_audio_status"
+            | "list_audio_favorites"
+            | "play_audio_favorite"
+            | "get_audio_queue"
+            | "set_audio_playback_mode"
+            | "group_audio_zones"
+            | "ungroup_audio_zones"
+            | "audio_announcement"
+            | "control_ev_charging"
+            | "get_security_status"
+            | "set_security_mode"
+            | "control_door_lock"
+            | "get_camera_status"
+            | "control_intercom"
+            | "get_intercom_history"
+            | "get_camera_snapshot"
+            | "activate_scene"
+            | "list_scenes"
+            | "mix_moods"
+            | "learn_mood"
+            | "delete_mood"
+            | "set_favorite_mood"
+            | "bulk_action"
+            | "execute_natural_language" => self.enable_devices,
+            "get_sensor_readings"
+            | "get_door_window_status"
+            | "get_motion_status"
+            | "get_energy_status"
+            | "get_sensor_history"
+            | "list_statistics"
+            | "get_statistics" => self.enable_sensors,
+            "set_temperature"
+            | "get_climate_status"
+            | "set_climate_mode"
+            | "set_comfort_temperatures"
+            | "set_climate_override"
+            | "get_climate_schedule"
+            | "set_climate_schedule" => self.enable_climate,
+            "get_weather" => self.enable_weather,
+            _ => false,
+        }
+    }
+}
+
 impl Default for ToolConfig {
     fn default() -> Self {
         Self {
@@ -431,6 +721,7 @@ impl Default for FeatureConfig {
             enable_websocket: cfg!(feature = "websocket"),
             enable_caching: true,
             cache_ttl: Duration::from_secs(30),
+            structure_check_interval: default_structure_check_interval(),
         }
     }
 }
@@ -489,12 +780,101 @@ impl ServerConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self> {
         let mut config = Self::default();
+        config.apply_env_overrides()?;
+        Ok(config)
+    }
+
+    /// Load layered configuration: defaults < config file < environment
+    ///
+    /// The file format (TOML, YAML or JSON) is taken from its extension. Any key
+    /// can be overridden with a `LOXONE_MCP_`-prefixed variable (see
+    /// [`CONFIG_ENV_PREFIX`]); the established variables such as `LOXONE_URL`,
+    /// `LOXONE_USER` or `MCP_PORT` are applied last. CLI flags are layered on
+    /// top by the caller.
+    pub fn load(config_file: Option<&Path>) -> Result<Self> {
+        let defaults = config::Config::try_from(&Self::default())
+            .map_err(|e| LoxoneError::config(format!("Invalid default configuration: {e}")))?;
+        let mut builder = config::Config::builder();
+
+        if let Some(path) = config_file {
+            if !path.is_file() {
+                return Err(LoxoneError::config(format!(
+                    "Configuration file not found: {}",
+                    path.display()
+                )));
+            }
+            builder = builder.add_source(config::File::from(path));
+        }
+
+        let layers = builder
+            .add_source(
+                config::Environment::with_prefix(CONFIG_ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true),
+            )
+            .build()
+            .map_err(|e| LoxoneError::config(format!("Failed to load configuration: {e}")))?;
+        let explicit: ExplicitLayers = layers
+            .clone()
+            .try_deserialize()
+            .map_err(|e| LoxoneError::config(format!("Failed to load configuration: {e}")))?;
+
+        let mut config: Self = config::Config::builder()
+            .add_source(defaults)
+            .add_source(layers)
+            .build()
+            .and_then(|layered| layered.try_deserialize())
+            .map_err(|e| LoxoneError::config(format!("Failed to load configuration: {e}")))?;
+        config.loxone_layer = explicit.loxone;
+
+        config.apply_env_overrides()?;
+        Ok(config)
+    }
 
+    /// Miniserver connection settings for credentials loaded from one source
+    ///
+    /// The URL falls back to the credentials' host when no layer set one. The
+    /// username always comes with the password, so a layered username has to
+    /// name the same user as the credentials.
+    pub fn connection_config(
+        &self,
+        credential_url: Url,
+        credential_username: &str,
+    ) -> Result<LoxoneConfig> {
+        if let Some(username) = &self.loxone_layer.username
+            && username != credential_username
+        {
+            return Err(LoxoneError::config(format!(
+                "Configured username '{username}' does not match the loaded credentials \
+                 ('{credential_username}'); provide its password from the same source"
+            )));
+        }
+        Ok(LoxoneConfig {
+            url: self.loxone_layer.url.clone().unwrap_or(credential_url),
+            username: credential_username.to_string(),
+            ..self.loxone.clone()
+        })
+    }
+
+    /// Copy of the configuration with secrets masked, for display
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        #[cfg(feature = "infisical")]
+        if let CredentialStore::Infisical { client_secret, .. } = &mut config.credentials {
+            *client_secret = "********".to_string();
+        }
+        config
+    }
+
+    /// Apply the established environment variables on top of this configuration
+    fn apply_env_overrides(&mut self) -> Result<()> {
         // Load Loxone configuration - support both LOXONE_URL and LOXONE_HOST
         if let Ok(url) = env::var("LOXONE_URL") {
-            config.loxone.url = url
+            self.loxone.url = url
                 .parse()
                 .map_err(|e| LoxoneError::config(format!("Invalid LOXONE_URL: {e}")))?;
+            self.loxone_layer.url = Some(self.loxone.url.clone());
         } else if let Ok(host) = env::var("LOXONE_HOST") {
             // Convert LOXONE_HOST to URL format (add http:// if missing)
             let url_str = if host.starts_with("http://") || host.starts_with("https://") {
@@ -502,25 +882,27 @@ impl ServerConfig {
             } else {
                 format!("http://{host}")
             };
-            config.loxone.url = url_str
+            self.loxone.url = url_str
                 .parse()
                 .map_err(|e| LoxoneError::config(format!("Invalid LOXONE_HOST: {e}")))?;
+            self.loxone_layer.url = Some(self.loxone.url.clone());
         }
 
         if let Ok(username) = env::var("LOXONE_USER") {
-            config.loxone.username = username;
+            self.loxone.username = username.clone();
+            self.loxone_layer.username = Some(username);
         }
 
         // Validate that password is available if username is set via environment
         // Note: Password is not stored in config for security, but we validate it exists
-        if !config.loxone.username.is_empty() && env::var("LOXONE_PASS").is_err() {
+        if !self.loxone.username.is_empty() && env::var("LOXONE_PASS").is_err() {
             tracing::warn!(
                 "LOXONE_USER is set but LOXONE_PASS is missing. Credential manager will be used instead."
             );
         }
 
         if let Ok(timeout) = env::var("LOXONE_TIMEOUT") {
-            config.loxone.timeout = Duration::from_secs(
+            self.loxone.timeout = Duration::from_secs(
                 timeout
                     .parse()
                     .map_err(|e| LoxoneError::config(format!("Invalid LOXONE_TIMEOUT: {e}")))?,
@@ -529,35 +911,68 @@ impl ServerConfig {
 
         // Load authentication method
         if let Ok(auth_method) = env::var("LOXONE_AUTH_METHOD") {
-            config.loxone.auth_method = match auth_method.to_lowercase().as_str() {
+            self.loxone.auth_method = match auth_method.to_lowercase().as_str() {
                 "basic" => AuthMethod::Basic,
                 "token" => AuthMethod::Token,
+                #[cfg(feature = "websocket")]
+                "websocket" => AuthMethod::WebSocket,
                 _ => {
                     return Err(LoxoneError::config(format!(
-                        "Invalid LOXONE_AUTH_METHOD: {auth_method}. Use 'basic' or 'token'"
+                        "Invalid LOXONE_AUTH_METHOD: {auth_method}. Use 'basic', 'token' or 'websocket'"
                     )));
                 }
             };
         }
 
+        if let Ok(encryption) = env::var("LOXONE_COMMAND_ENCRYPTION") {
+            self.loxone.command_encryption = match encryption.to_lowercase().as_str() {
+                "none" | "off" => CommandEncryption::None,
+                "request" | "enc" => CommandEncryption::Request,
+                "full" | "fenc" => CommandEncryption::Full,
+                _ => {
+                    return Err(LoxoneError::config(format!(
+                        "Invalid LOXONE_COMMAND_ENCRYPTION: {encryption}. Use 'none', 'request' or 'full'"
+                    )));
+                }
+            };
+        }
+
+        if let Ok(reuse) = env::var("LOXONE_REUSE_TOKEN") {
+            self.loxone.reuse_token = reuse
+                .parse()
+                .map_err(|e| LoxoneError::config(format!("Invalid LOXONE_REUSE_TOKEN: {e}")))?;
+        }
+
+        if let Ok(timeout) = env::var("LOXONE_CONSENT_TIMEOUT") {
+            self.mcp.consent.timeout = Duration::from_secs(timeout.parse().map_err(|e| {
+                LoxoneError::config(format!("Invalid LOXONE_CONSENT_TIMEOUT: {e}"))
+            })?);
+        }
+
+        if let Ok(webhook) = env::var("LOXONE_CONSENT_WEBHOOK") {
+            self.mcp.consent.webhook_url = Some(webhook.parse().map_err(|e| {
+                LoxoneError::config(format!("Invalid LOXONE_CONSENT_WEBHOOK: {e}"))
+            })?);
+        }
+
         // Load logging configuration
         if let Ok(level) = env::var("RUST_LOG") {
-            config.logging.level = level;
+            self.logging.level = level;
         }
 
         // Load transport configuration
         if let Ok(transport) = env::var("MCP_TRANSPORT") {
-            config.mcp.transport.transport_type = transport;
+            self.mcp.transport.transport_type = transport;
         }
 
         if let Ok(port) = env::var("MCP_PORT") {
-            config.mcp.transport.port = Some(
+            self.mcp.transport.port = Some(
                 port.parse()
                     .map_err(|e| LoxoneError::config(format!("Invalid MCP_PORT: {e}")))?,
             );
         }
 
-        Ok(config)
+        Ok(())
     }
 
     /// Load configuration for WASM environment
@@ -604,6 +1019,293 @@ impl ServerConfig {
             return Err(LoxoneError::config("Timeout must be greater than zero"));
         }
 
+        // Validate transport
+        match self.mcp.transport.transport_type.as_str() {
+            "stdio" | "http" | "streamable-http" => {}
+            other => {
+                return Err(LoxoneError::config(format!(
+                    "Unknown transport '{other}'. Use stdio, http or streamable-http"
+                )));
+            }
+        }
+
+        // Validate tool limits
+        if self.mcp.tools.max_devices_per_query == 0 {
+            return Err(LoxoneError::config(
+                "max_devices_per_query must be greater than zero",
+            ));
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::io::Write;
+
+    /// Established variables that would otherwise leak into layered loads
+    const CLEARED_VARS: [&str; 10] = [
+        "LOXONE_URL",
+        "LOXONE_HOST",
+        "LOXONE_USER",
+        "LOXONE_TIMEOUT",
+        "LOXONE_AUTH_METHOD",
+        "LOXONE_CONSENT_TIMEOUT",
+        "LOXONE_CONSENT_WEBHOOK",
+        "RUST_LOG",
+        "MCP_TRANSPORT",
+        "MCP_PORT",
+    ];
+
+    fn write_config(extension: &str, contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(extension)
+            .tempfile()
+            .unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    fn load_with(vars: &[(&str, Option<&str>)], path: Option<&Path>) -> Result<ServerConfig> {
+        let mut all: Vec<(&str, Option<&str>)> =
+            CLEARED_VARS.iter().map(|name| (*name, None)).collect();
+        all.extend_from_slice(vars);
+        temp_env::with_vars(all, || ServerConfig::load(path))
+    }
+
+    #[test]
+    #[serial]
+    fn test_load_toml_file() {
+        let file = write_config(
+            ".toml",
+            r#"
+[loxone]
+url = "http://192.168.1.77/"
+username = "operator"
+
+[mcp.tools]
+enable_weather = false
+max_devices_per_query = 25
+"#,
+        );
+
+        let config = load_with(&[], Some(file.path())).unwrap();
+        assert_eq!(config.loxone.url.as_str(), "http://192.168.1.77/");
+        assert_eq!(config.loxone.username, "operator");
+        assert!(!config.mcp.tools.enable_weather);
+        assert_eq!(config.mcp.tools.max_devices_per_query, 25);
+        // Untouched keys keep their defaults
+        assert!(config.mcp.tools.enable_rooms);
+        assert_eq!(config.mcp.transport.transport_type, "stdio");
+    }
+
+    #[test]
+    #[serial]
+    fn test_configured_url_wins_over_credentials() {
+        let file = write_config(".toml", "[loxone]\nurl = \"http://192.168.1.77/\"\n");
+        let credential_url: Url = "http://10.0.0.5/".parse().unwrap();
+
+        let config = load_with(&[], Some(file.path())).unwrap();
+        let loxone = config
+            .connection_config(credential_url.clone(), "stored")
+            .unwrap();
+        assert_eq!(loxone.url.as_str(), "http://192.168.1.77/");
+        assert_eq!(loxone.username, "stored");
+
+        // Nothing set: both come from the credentials
+        let config = load_with(&[], None).unwrap();
+        let loxone = config
+            .connection_config(credential_url.clone(), "stored")
+            .unwrap();
+        assert_eq!(loxone.url.as_str(), "http://10.0.0.5/");
+        assert_eq!(loxone.username, "stored");
+
+        // A value equal to the default still counts as set
+        let file = write_config(".toml", "[loxone]\nurl = \"http://127.0.0.1:80\"\n");
+        let config = load_with(&[], Some(file.path())).unwrap();
+        let loxone = config
+            .connection_config(credential_url.clone(), "stored")
+            .unwrap();
+        assert_eq!(loxone.url.as_str(), "http://127.0.0.1/");
+    }
+
+    #[test]
+    #[serial]
+    fn test_configured_username_needs_matching_credentials() {
+        let credential_url: Url = "http://10.0.0.5/".parse().unwrap();
+
+        let config = load_with(&[("LOXONE_USER", Some("from-env"))], None).unwrap();
+        assert_eq!(config.loxone_layer.username.as_deref(), Some("from-env"));
+        let loxone = config
+            .connection_config(credential_url.clone(), "from-env")
+            .unwrap();
+        assert_eq!(loxone.username, "from-env");
+
+        // The password belongs to "stored", so "from-env" cannot be used with it
+        assert!(config.connection_config(credential_url, "stored").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_load_yaml_file() {
+        let file = write_config(
+            ".yaml",
+            "mcp:\n  transport:\n    transport_type: http\n    port: 3003\n",
+        );
+
+        let config = load_with(&[], Some(file.path())).unwrap();
+        assert_eq!(config.mcp.transport.transport_type, "http");
+        assert_eq!(config.mcp.transport.port, Some(3003));
+    }
+
+    #[test]
+    #[serial]
+    fn test_env_overrides_file() {
+        let file = write_config(".toml", "[mcp.tools]\nenable_climate = true\n");
+
+        let config = load_with(
+            &[
+                ("LOXONE_MCP_MCP__TOOLS__ENABLE_CLIMATE", Some("false")),
+                ("LOXONE_USER", Some("from-env")),
+            ],
+            Some(file.path()),
+        )
+        .unwrap();
+        assert!(!config.mcp.tools.enable_climate);
+        assert_eq!(config.loxone.username, "from-env");
+    }
+
+    #[test]
+    #[serial]
+    fn test_consent_settings() {
+        let file = write_config(".toml", "[mcp.consent]\ntimeout = \"30s\"\n");
+
+        let config = load_with(&[], Some(file.path())).unwrap();
+        assert!(config.mcp.consent.enabled);
+        assert_eq!(config.mcp.consent.timeout, Duration::from_secs(30));
+        assert!(config.mcp.consent.webhook_url.is_none());
+
+        let config = load_with(
+            &[
+                ("LOXONE_CONSENT_TIMEOUT", Some("45")),
+                (
+                    "LOXONE_CONSENT_WEBHOOK",
+                    Some("https://approve.local/loxone"),
+                ),
+            ],
+            Some(file.path()),
+        )
+        .unwrap();
+        assert_eq!(config.mcp.consent.timeout, Duration::from_secs(45));
+        assert_eq!(
+            config.mcp.consent.webhook_url.unwrap().as_str(),
+            "https://approve.local/loxone"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_audit_settings() {
+        let config = load_with(&[], None).unwrap();
+        assert!(config.audit.policy.enabled);
+        assert!(config.audit.policy.log_success);
+        assert_eq!(config.audit.backend, AuditBackendKind::Jsonl);
+        assert_eq!(config.audit.policy.retention_days, 90);
+
+        let file = write_config(
+            ".toml",
+            "[audit]\nbackend = \"sqlite\"\npath = \"/var/lib/loxone/audit.db\"\nretention_days = 30\nlog_success = false\n",
+        );
+        let config = load_with(&[], Some(file.path())).unwrap();
+        assert_eq!(config.audit.backend, AuditBackendKind::Sqlite);
+        assert_eq!(
+            config.audit.path.as_deref(),
+            Some(Path::new("/var/lib/loxone/audit.db"))
+        );
+        assert_eq!(config.audit.policy.retention_days, 30);
+        assert!(!config.audit.policy.log_success);
+        assert!(config.audit.policy.log_denials);
+    }
+
+    #[test]
+    #[serial]
+    fn test_history_settings() {
+        let config = load_with(&[], None).unwrap();
+        assert!(config.history.enabled);
+        assert_eq!(
+            config.history.raw_retention,
+            Duration::from_secs(2 * 24 * 3600)
+        );
+
+        let file = write_config(
+            ".toml",
+            "[history]\nbackend = \"memory\"\nhour_retention = \"30days\"\n",
+        );
+        let config = load_with(&[], Some(file.path())).unwrap();
+        assert_eq!(config.history.backend, HistoryBackendKind::Memory);
+        assert_eq!(
+            config.history.hour_retention,
+            Duration::from_secs(30 * 24 * 3600)
+        );
+        assert_eq!(
+            config.history.day_retention,
+            HistoryConfig::default().day_retention
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_name_aliases() {
+        let config = load_with(&[], None).unwrap();
+        assert!(config.mcp.aliases.is_empty());
+
+        let file = write_config(
+            ".toml",
+            "[mcp.aliases]\n\"Leselampe\" = \"Stehlampe\"\n\"Bad oben\" = \"room-bath-og\"\n",
+        );
+        let config = load_with(&[], Some(file.path())).unwrap();
+        assert_eq!(config.mcp.aliases["Leselampe"], "Stehlampe");
+        assert_eq!(config.mcp.aliases["Bad oben"], "room-bath-og");
+    }
+
+    #[test]
+    #[serial]
+    fn test_missing_config_file() {
+        let err = load_with(&[], Some(Path::new("/nonexistent/loxone.toml"))).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_tool_gating() {
+        let tools = ToolConfig {
+            enable_weather: false,
+            enable_devices: false,
+            ..Default::default()
+        };
+
+        assert!(!tools.is_tool_enabled("get_weather"));
+        assert!(!tools.is_tool_enabled("control_lights"));
+        assert!(tools.is_tool_enabled("list_rooms"));
+        assert!(tools.is_tool_enabled("get_climate_status"));
+        assert!(!tools.is_tool_enabled("set_security_mode"));
+        assert!(tools.is_tool_enabled("get_server_status"));
+        // Tools outside any group are disabled
+        assert!(!tools.is_tool_enabled("get_system_status"));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_values() {
+        let mut config = ServerConfig::default();
+        assert!(config.validate().is_ok());
+
+        config.mcp.transport.transport_type = "carrier-pigeon".to_string();
+        assert!(config.validate().is_err());
+
+        config.mcp.transport.transport_type = "stdio".to_string();
+        config.mcp.tools.max_devices_per_query = 0;
+        assert!(config.validate().is_err());
+    }
+}
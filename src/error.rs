@@ -937,6 +937,13 @@ impl From<tokio_tungstenite::tungstenite::Error> for LoxoneError {
 // - rsa::Error: RSA crate disabled due to RUSTSEC-2023-0071 vulnerability
 // - keyring::Error: Keyring crate disabled due to unmaintained dependencies
 
+/// MCP tools report failures as text, so `?` renders the typed error at the tool boundary
+impl From<LoxoneError> for String {
+    fn from(err: LoxoneError) -> Self {
+        err.to_string()
+    }
+}
+
 impl From<regex::Error> for LoxoneError {
     fn from(err: regex::Error) -> Self {
         LoxoneError::InvalidInput(format!("Regex pattern error: {err}"))
@@ -22,9 +22,9 @@ pub async fn get_unified_dashboard_data(server: &LoxoneMcpServer) -> Value {
         "Disconnected"
     };
 
-    // Get all devices and rooms
-    let devices = context.devices.read().await;
+    // Get all rooms and devices (in the lock order of structure updates)
     let rooms = context.rooms.read().await;
+    let devices = context.devices.read().await;
 
     // Get all device UUIDs
     let all_device_uuids: Vec<String> = devices.keys().cloned().collect();
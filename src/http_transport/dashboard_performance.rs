@@ -180,11 +180,10 @@ impl PerformanceDashboard {
         let resolver = server.get_value_resolver();
         let context = &server.context;
 
-        // Use concurrent operations for maximum speed
-        let (devices, rooms, connection_status) =
-            tokio::join!(context.devices.read(), context.rooms.read(), async {
-                *context.connected.read().await
-            });
+        // Rooms before devices, the lock order of structure updates
+        let rooms = context.rooms.read().await;
+        let devices = context.devices.read().await;
+        let connection_status = *context.connected.read().await;
 
         // Get device UUIDs and resolve values in batch (fastest method)
         let device_uuids: Vec<String> = devices.keys().cloned().collect();
@@ -15,6 +15,7 @@
 //! - WASM-compatible for server deployment via WASIP2
 
 // Core modules
+pub mod audit;
 pub mod client;
 pub mod config;
 pub mod crypto;
@@ -32,6 +33,8 @@ pub mod security;
 pub mod server;
 pub mod services;
 pub mod shared_styles;
+#[cfg(all(feature = "http-server", feature = "crypto-openssl"))]
+pub mod simulator;
 pub mod storage;
 pub mod utils;
 pub mod validation;
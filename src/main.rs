@@ -14,9 +14,11 @@ use loxone_mcp_rust::{
         credential_registry::CredentialRegistry, credentials::create_best_credential_manager,
     },
     server::macro_backend::LoxoneMcpServer,
+    server::subscription_runtime::LoxoneMcpRuntime,
 };
 
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{info, warn};
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
@@ -54,6 +56,10 @@ struct Config {
     /// Disable SSL certificate verification (not recommended for production)
     #[arg(long, global = true)]
     insecure: bool,
+
+    /// Configuration file (TOML, YAML or JSON), layered under environment and CLI
+    #[arg(long, global = true, env = "LOXONE_MCP_CONFIG")]
+    config: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -96,6 +102,23 @@ enum TransportCommand {
         #[arg(long)]
         enable_cors: bool,
     },
+    /// Inspect the layered server configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Load and validate the effective configuration
+    Validate,
+    /// Print the effective configuration after file, environment and CLI layering
+    PrintEffective {
+        /// Output format (toml or json)
+        #[arg(long, default_value = "toml")]
+        format: String,
+    },
 }
 
 impl Config {
@@ -142,9 +165,85 @@ impl Config {
                     ));
                 }
             }
+            TransportCommand::Config { .. } => {}
         }
         Ok(())
     }
+
+    /// Load the server configuration with precedence file < environment < CLI
+    fn effective_server_config(&self) -> Result<LoxoneServerConfig> {
+        let mut server_config = LoxoneServerConfig::load(self.config.as_deref())?;
+
+        if let Some(host) = &self.loxone_host {
+            server_config.loxone.url = loxone_url(host)?;
+            server_config.loxone_layer.url = Some(server_config.loxone.url.clone());
+        }
+        if let Some(user) = &self.loxone_user {
+            server_config.loxone.username = user.clone();
+            server_config.loxone_layer.username = Some(user.clone());
+        }
+        if self.insecure {
+            server_config.loxone.verify_ssl = false;
+        }
+        if self.debug {
+            server_config.logging.level = "debug".to_string();
+        }
+
+        let transport = &mut server_config.mcp.transport;
+        match &self.transport {
+            TransportCommand::Stdio { .. } => transport.transport_type = "stdio".to_string(),
+            TransportCommand::Http { port, .. } => {
+                transport.transport_type = "http".to_string();
+                transport.port = Some(*port);
+            }
+            TransportCommand::StreamableHttp { port, .. } => {
+                transport.transport_type = "streamable-http".to_string();
+                transport.port = Some(*port);
+            }
+            TransportCommand::Config { .. } => {}
+        }
+
+        Ok(server_config)
+    }
+}
+
+/// Turn a host (optionally with scheme) into a Miniserver URL
+fn loxone_url(host: &str) -> Result<url::Url> {
+    let url = if host.starts_with("http://") || host.starts_with("https://") {
+        host.to_string()
+    } else {
+        format!("http://{host}")
+    };
+    url.parse()
+        .map_err(|e| loxone_mcp_rust::LoxoneError::config(format!("Invalid Loxone host: {e}")))
+}
+
+/// Handle `config validate` / `config print-effective`
+fn run_config_command(config: &Config, action: &ConfigAction) -> Result<()> {
+    let server_config = config.effective_server_config()?;
+
+    match action {
+        ConfigAction::Validate => {
+            server_config.validate()?;
+            println!("Configuration is valid");
+        }
+        ConfigAction::PrintEffective { format } => {
+            let redacted = server_config.redacted();
+            let rendered = match format.as_str() {
+                "toml" => toml::to_string_pretty(&redacted).map_err(|e| {
+                    loxone_mcp_rust::LoxoneError::config(format!("Failed to render TOML: {e}"))
+                })?,
+                "json" => serde_json::to_string_pretty(&redacted)?,
+                other => {
+                    return Err(loxone_mcp_rust::LoxoneError::config(format!(
+                        "Unknown format '{other}'. Use toml or json"
+                    )));
+                }
+            };
+            println!("{rendered}");
+        }
+    }
+    Ok(())
 }
 
 /// Load credentials from credential ID
@@ -187,6 +286,10 @@ async fn try_auto_detect_credentials() -> Result<(String, String, String)> {
 async fn main() -> Result<()> {
     let config = Config::parse();
 
+    if let TransportCommand::Config { action } = &config.transport {
+        return run_config_command(&config, action);
+    }
+
     // Initialize logging
     config.initialize_logging();
 
@@ -249,37 +352,44 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Effective server configuration: file < environment < CLI
+    let server_config = config.effective_server_config()?;
+    server_config.validate()?;
+
     // Build a LoxoneMcpServer with Loxone client for all online modes
-    let build_mcp_server =
-        |loxone_host: &str, loxone_user: &str, loxone_password: &str, insecure: bool| {
-            let host = loxone_host.to_string();
-            let user = loxone_user.to_string();
-            let pass = loxone_password.to_string();
-            async move {
-                use loxone_mcp_rust::client::{ClientContext, LoxoneHttpClient};
-                use loxone_mcp_rust::config::credentials::LoxoneCredentials;
-                use loxone_mcp_rust::services::SensorTypeRegistry;
-
-                let loxone_url: url::Url = format!("http://{host}").parse().map_err(|e| {
-                    loxone_mcp_rust::LoxoneError::config(format!("Invalid URL: {e}"))
-                })?;
-
-                let loxone_cfg = loxone_mcp_rust::config::LoxoneConfig {
-                    url: loxone_url,
-                    timeout: std::time::Duration::from_secs(30),
-                    verify_ssl: !insecure,
-                    ..Default::default()
-                };
-
-                let credentials = LoxoneCredentials {
-                    username: user,
-                    password: pass,
-                    api_key: None,
-                    #[cfg(feature = "crypto-openssl")]
-                    public_key: None,
-                };
-
-                let client = LoxoneHttpClient::new(loxone_cfg, credentials)
+    let credential_id = config.credential_id.clone();
+    let build_mcp_server = |loxone_host: &str,
+                            loxone_user: &str,
+                            loxone_password: &str,
+                            server_config: LoxoneServerConfig| {
+        let host = loxone_host.to_string();
+        let user = loxone_user.to_string();
+        let pass = loxone_password.to_string();
+        let credential_id = credential_id.clone();
+        async move {
+            use loxone_mcp_rust::client::client_factory::connect_configured_client;
+            use loxone_mcp_rust::client::{ClientContext, watch_structure};
+            use loxone_mcp_rust::config::credentials::LoxoneCredentials;
+            use loxone_mcp_rust::services::SensorTypeRegistry;
+            use loxone_mcp_rust::storage::history::{SensorHistory, poll_states};
+
+            let loxone_cfg = loxone_mcp_rust::config::LoxoneConfig {
+                credential_id,
+                ..server_config.connection_config(loxone_url(&host)?, &user)?
+            };
+
+            let credentials = LoxoneCredentials {
+                username: user,
+                password: pass,
+                api_key: None,
+                #[cfg(feature = "crypto-openssl")]
+                public_key: None,
+            };
+
+            // The client records pushed states into the context the server reads
+            let context = Arc::new(ClientContext::new());
+            let (client, auth_method) =
+                connect_configured_client(&loxone_cfg, &credentials, context.clone())
                     .await
                     .map_err(|e| {
                         loxone_mcp_rust::LoxoneError::connection(format!(
@@ -287,26 +397,50 @@ async fn main() -> Result<()> {
                         ))
                     })?;
 
-                let context = Arc::new(ClientContext::new());
-                let client_arc: Arc<dyn loxone_mcp_rust::client::LoxoneClient> = Arc::new(client);
-                let sensor_registry = Arc::new(SensorTypeRegistry::new());
-                let value_resolver =
-                    Arc::new(loxone_mcp_rust::services::UnifiedValueResolver::new(
-                        client_arc.clone(),
-                        sensor_registry,
-                    ));
+            let client_arc: Arc<dyn loxone_mcp_rust::client::LoxoneClient> = Arc::from(client);
+            let sensor_registry = Arc::new(SensorTypeRegistry::new());
+            let value_resolver = Arc::new(loxone_mcp_rust::services::UnifiedValueResolver::new(
+                client_arc.clone(),
+                sensor_registry,
+            ));
+
+            info!("✅ Loxone client connected ({:?})", auth_method);
+
+            let structure_check_interval = server_config.features.structure_check_interval;
+            if !structure_check_interval.is_zero() {
+                watch_structure(
+                    client_arc.clone(),
+                    context.clone(),
+                    structure_check_interval,
+                );
+            }
 
-                info!("✅ Loxone client connected");
+            let audit_log = loxone_mcp_rust::audit::AuditLog::open(&server_config.audit).await?;
+            let history = SensorHistory::open(&server_config.history).await?;
+            if let Some(history) = &history {
+                history.spawn_recorder(context.clone());
+                let poll_interval = server_config.history.poll_interval;
+                if !poll_interval.is_zero() {
+                    poll_states(client_arc.clone(), context.clone(), poll_interval);
+                }
+            }
 
-                Ok::<LoxoneMcpServer, loxone_mcp_rust::LoxoneError>(LoxoneMcpServer::with_context(
-                    client_arc,
-                    context,
-                    value_resolver,
-                    None,
-                    LoxoneServerConfig::default(),
-                ))
+            let mut server = LoxoneMcpServer::with_context(
+                client_arc,
+                context,
+                value_resolver,
+                None,
+                server_config,
+            );
+            if let Some(audit_log) = audit_log {
+                server = server.with_audit_log(audit_log);
             }
-        };
+            if let Some(history) = history {
+                server = server.with_sensor_history(history);
+            }
+            Ok::<LoxoneMcpServer, loxone_mcp_rust::LoxoneError>(server)
+        }
+    };
 
     match config.transport {
         TransportCommand::Stdio { offline } => {
@@ -314,31 +448,27 @@ async fn main() -> Result<()> {
 
             let server = if offline {
                 info!("🚀 Starting MCP server in offline mode (stdio)");
-                LoxoneMcpServer::with_defaults()
+                LoxoneMcpServer::with_defaults().with_config(server_config.clone())
             } else {
                 info!("🚀 Starting MCP server with Loxone connection (stdio)");
                 build_mcp_server(
                     &loxone_host,
                     &loxone_user,
                     &_loxone_password,
-                    config.insecure,
+                    server_config.clone(),
                 )
                 .await?
             };
 
-            let mut mcp_server = server.serve_stdio().await.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Failed to start server: {e}"))
-            })?;
+            let mut runtime = LoxoneMcpRuntime::stdio(server).await?;
             info!("✅ Server started (stdio)");
-            mcp_server.run().await.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Server error: {e}"))
-            })?;
+            runtime.run().await?;
         }
 
         TransportCommand::Http { port, dev_mode, .. } => {
             let server = if dev_mode {
                 warn!("Development mode enabled — no auth, localhost only");
-                LoxoneMcpServer::with_defaults()
+                LoxoneMcpServer::with_defaults().with_config(server_config.clone())
             } else {
                 info!(
                     "🚀 Starting MCP server with Loxone connection (HTTP port {})",
@@ -348,23 +478,14 @@ async fn main() -> Result<()> {
                     &loxone_host,
                     &loxone_user,
                     &_loxone_password,
-                    config.insecure,
+                    server_config.clone(),
                 )
                 .await?
             };
 
-            let serve_result: std::result::Result<
-                pulseengine_mcp_server::McpServer<LoxoneMcpServer>,
-                _,
-            > = server.serve_http(port).await;
-            let mut mcp_server = serve_result.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Failed to start server: {e}"))
-            })?;
+            let mut runtime = LoxoneMcpRuntime::streamable_http(server, port).await?;
             info!("✅ Server started (HTTP port {})", port);
-            let run_result: std::result::Result<(), _> = mcp_server.run().await;
-            run_result.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Server error: {e}"))
-            })?;
+            runtime.run().await?;
         }
 
         TransportCommand::StreamableHttp { port, .. } => {
@@ -376,23 +497,17 @@ async fn main() -> Result<()> {
                 &loxone_host,
                 &loxone_user,
                 &_loxone_password,
-                config.insecure,
+                server_config.clone(),
             )
             .await?;
 
-            let serve_result: std::result::Result<
-                pulseengine_mcp_server::McpServer<LoxoneMcpServer>,
-                _,
-            > = server.serve_http(port).await;
-            let mut mcp_server = serve_result.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Failed to start server: {e}"))
-            })?;
+            let mut runtime = LoxoneMcpRuntime::streamable_http(server, port).await?;
             info!("✅ Server started (Streamable HTTP port {})", port);
-            let run_result: std::result::Result<(), _> = mcp_server.run().await;
-            run_result.map_err(|e| {
-                loxone_mcp_rust::LoxoneError::connection(format!("Server error: {e}"))
-            })?;
+            runtime.run().await?;
         }
+
+        // Handled before logging and credential loading
+        TransportCommand::Config { .. } => unreachable!("config subcommand handled earlier"),
     }
 
     Ok(())
@@ -10,12 +10,17 @@
 //! - Bulk operation consent handling
 //! - Time-based consent expiration
 //! - Audit trail for consent decisions
+//!
+//! Requests are answered through a [`ConsentApprover`] (e.g. MCP elicitation or
+//! a webhook) or through the channels from [`ConsentManager::setup_channels`].
+//! Whatever answers first decides; without an answer the request times out.
 
 use crate::error::{LoxoneError, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
-use tokio::sync::{RwLock, mpsc};
+use tokio::sync::{RwLock, mpsc, oneshot};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
@@ -128,6 +133,21 @@ pub struct ConsentResponse {
     pub user_id: Option<String>,
 }
 
+impl ConsentResponse {
+    /// Answer to a request, without reason, caching or user
+    pub fn new(request_id: Uuid, approved: bool) -> Self {
+        Self {
+            request_id,
+            approved,
+            reason: None,
+            responded_at: SystemTime::now(),
+            validity_duration: None,
+            apply_to_similar: false,
+            user_id: None,
+        }
+    }
+}
+
 /// Consent decision record for audit trail
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsentRecord {
@@ -247,6 +267,19 @@ impl Default for ConsentConfig {
     }
 }
 
+/// Channel that asks a person to approve a consent request
+#[async_trait::async_trait]
+pub trait ConsentApprover: Send + Sync {
+    /// Ask for a decision; `Ok(None)` if nobody answered within `timeout`
+    ///
+    /// Errors mean the approval channel failed and deny the request.
+    async fn request_approval(
+        &self,
+        request: &ConsentRequest,
+        timeout: Duration,
+    ) -> Result<Option<ConsentResponse>>;
+}
+
 /// Consent manager for handling consent flows
 pub struct ConsentManager {
     /// Configuration
@@ -266,6 +299,12 @@ pub struct ConsentManager {
 
     /// Channel for consent responses from UI/user
     response_receiver: RwLock<Option<mpsc::UnboundedReceiver<ConsentResponse>>>,
+
+    /// Requests waiting for their decision
+    waiters: RwLock<HashMap<Uuid, oneshot::Sender<ConsentDecision>>>,
+
+    /// Asks the user for approval
+    approver: Option<Arc<dyn ConsentApprover>>,
 }
 
 impl ConsentManager {
@@ -283,9 +322,22 @@ impl ConsentManager {
             decision_history: RwLock::new(Vec::new()),
             request_sender: None,
             response_receiver: RwLock::new(None),
+            waiters: RwLock::new(HashMap::new()),
+            approver: None,
         }
     }
 
+    /// Ask for approval through the given channel
+    pub fn with_approver(mut self, approver: Arc<dyn ConsentApprover>) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> &ConsentConfig {
+        &self.config
+    }
+
     /// Setup consent flow channels
     pub async fn setup_channels(
         &mut self,
@@ -323,45 +375,23 @@ impl ConsentManager {
             });
         }
 
-        // Check auto-approve/deny lists
-        let operation_key = self.get_operation_key(&operation);
-        if self.config.auto_approve_operations.contains(&operation_key) {
-            return Ok(ConsentDecision::AutoApproved {
-                policy: "auto_approve_list".to_string(),
-            });
-        }
-
-        if self.config.auto_deny_operations.contains(&operation_key) {
-            return Ok(ConsentDecision::Denied {
-                reason: "Operation in auto-deny list".to_string(),
-            });
-        }
-
-        // Check cached consent
-        if let Some(cached_decision) = self.check_cached_consent(&operation).await {
-            return Ok(cached_decision);
-        }
-
-        // Check pending request limit
-        let pending_count = self.pending_requests.read().await.len();
-        if pending_count >= self.config.max_pending_requests {
-            return Ok(ConsentDecision::Denied {
-                reason: "Too many pending consent requests".to_string(),
-            });
-        }
-
-        // Create consent request
         let request = self
             .create_consent_request(operation, sensitivity, source)
             .await;
-        let request_id = request.id;
-
-        // Store pending request
-        {
-            let mut pending = self.pending_requests.write().await;
-            pending.insert(request_id, request.clone());
+        if let Some((decision, method)) = self.policy_decision(&request).await {
+            self.record_consent_decision(request, decision.clone(), method)
+                .await;
+            return Ok(decision);
         }
 
+        let request_id = request.id;
+        let (decision_tx, decision_rx) = oneshot::channel();
+        self.waiters.write().await.insert(request_id, decision_tx);
+        self.pending_requests
+            .write()
+            .await
+            .insert(request_id, request.clone());
+
         // Send request to UI if channel is available
         if let Some(sender) = &self.request_sender
             && let Err(e) = sender.send(request.clone())
@@ -369,22 +399,50 @@ impl ConsentManager {
             warn!("Failed to send consent request to UI: {}", e);
         }
 
-        // Wait for response or timeout
-        let decision = self.wait_for_consent_response(request_id).await?;
-
-        // Cache the decision if appropriate
-        if matches!(decision, ConsentDecision::Approved) {
-            self.cache_consent_decision(&request.operation, &decision)
+        // User decisions are recorded when the response is processed
+        let decision = self.wait_for_consent_response(&request, decision_rx).await;
+        if matches!(decision, ConsentDecision::TimedOut) {
+            self.record_consent_decision(request, decision.clone(), DecisionMethod::Timeout)
                 .await;
         }
 
-        // Record the decision
-        self.record_consent_decision(request, decision.clone())
-            .await;
-
         Ok(decision)
     }
 
+    /// Decision taken without asking: allow/deny lists, cache, request limit
+    async fn policy_decision(
+        &self,
+        request: &ConsentRequest,
+    ) -> Option<(ConsentDecision, DecisionMethod)> {
+        let operation_key = self.get_operation_key(&request.operation);
+        if self.config.auto_approve_operations.contains(&operation_key) {
+            let decision = ConsentDecision::AutoApproved {
+                policy: "auto_approve_list".to_string(),
+            };
+            return Some((decision, DecisionMethod::PolicyBased));
+        }
+
+        if self.config.auto_deny_operations.contains(&operation_key) {
+            let decision = ConsentDecision::Denied {
+                reason: "Operation in auto-deny list".to_string(),
+            };
+            return Some((decision, DecisionMethod::PolicyBased));
+        }
+
+        if let Some(cached_decision) = self.check_cached_consent(request).await {
+            return Some((cached_decision, DecisionMethod::CachedConsent));
+        }
+
+        if self.pending_requests.read().await.len() >= self.config.max_pending_requests {
+            let decision = ConsentDecision::Denied {
+                reason: "Too many pending consent requests".to_string(),
+            };
+            return Some((decision, DecisionMethod::PolicyBased));
+        }
+
+        None
+    }
+
     /// Process a consent response
     pub async fn process_response(&self, response: ConsentResponse) -> Result<()> {
         let request_id = response.request_id;
@@ -403,11 +461,21 @@ impl ConsentManager {
 
             // Cache decision if requested
             if response.apply_to_similar && response.approved {
-                self.cache_consent_decision(&request.operation, &decision)
-                    .await;
+                self.cache_consent_decision(&request, &decision).await;
+            }
+
+            if let Some(waiter) = self.waiters.write().await.remove(&request_id) {
+                let _ = waiter.send(decision.clone());
             }
 
             // Record the decision
+            info!(
+                request_id = %request_id,
+                operation = %self.get_operation_key(&request.operation),
+                approved = response.approved,
+                user = response.user_id.as_deref().unwrap_or("unknown"),
+                "Consent decision"
+            );
             let record = ConsentRecord {
                 request,
                 response,
@@ -418,8 +486,6 @@ impl ConsentManager {
 
             let mut history = self.decision_history.write().await;
             history.push(record);
-
-            info!("Processed consent response for request {}", request_id);
         } else {
             warn!(
                 "Received response for unknown consent request: {}",
@@ -472,10 +538,24 @@ impl ConsentManager {
         }
     }
 
+    /// Key a remembered decision is cached under
+    ///
+    /// Security actions are only remembered for the same devices and the same
+    /// requester: approving "unlock" of one door neither approves other doors
+    /// nor other clients.
+    fn cache_key(&self, request: &ConsentRequest) -> String {
+        match &request.operation {
+            OperationType::SecurityControl { action, scope } => {
+                format!("security:{action}:{scope}:{}", request.source)
+            }
+            operation => self.get_operation_key(operation),
+        }
+    }
+
     /// Check for cached consent decision
-    async fn check_cached_consent(&self, operation: &OperationType) -> Option<ConsentDecision> {
+    async fn check_cached_consent(&self, request: &ConsentRequest) -> Option<ConsentDecision> {
         let cache = self.consent_cache.read().await;
-        let operation_key = self.get_operation_key(operation);
+        let operation_key = self.cache_key(request);
 
         if let Some((decision, timestamp)) = cache.get(&operation_key) {
             let elapsed = SystemTime::now()
@@ -491,9 +571,9 @@ impl ConsentManager {
     }
 
     /// Cache a consent decision
-    async fn cache_consent_decision(&self, operation: &OperationType, decision: &ConsentDecision) {
+    async fn cache_consent_decision(&self, request: &ConsentRequest, decision: &ConsentDecision) {
         let mut cache = self.consent_cache.write().await;
-        let operation_key = self.get_operation_key(operation);
+        let operation_key = self.cache_key(request);
         cache.insert(operation_key, (decision.clone(), SystemTime::now()));
     }
 
@@ -613,53 +693,90 @@ impl ConsentManager {
     }
 
     /// Wait for consent response with timeout
-    async fn wait_for_consent_response(&self, request_id: Uuid) -> Result<ConsentDecision> {
-        // This is a simplified implementation
-        // In practice, you'd want to use proper async waiting with timeout
-        tokio::time::sleep(Duration::from_millis(100)).await;
+    async fn wait_for_consent_response(
+        &self,
+        request: &ConsentRequest,
+        mut decision_rx: oneshot::Receiver<ConsentDecision>,
+    ) -> ConsentDecision {
+        let timeout = request.timeout.unwrap_or(self.config.default_timeout);
+        let answered = async {
+            tokio::select! {
+                decision = &mut decision_rx => return decision.ok(),
+                () = self.ask_approver(request, timeout) => {}
+                () = self.forward_channel_responses() => {}
+            }
+            decision_rx.await.ok()
+        };
 
-        // Check if response was processed
-        let pending = self.pending_requests.read().await;
-        if pending.contains_key(&request_id) {
-            // Still pending, return timeout
-            Ok(ConsentDecision::TimedOut)
-        } else {
-            // Response was processed, find it in history
-            let history = self.decision_history.read().await;
-            for record in history.iter().rev() {
-                if record.request.id == request_id {
-                    return Ok(record.decision.clone());
+        match tokio::time::timeout(timeout, answered).await {
+            Ok(Some(decision)) => decision,
+            _ => {
+                self.pending_requests.write().await.remove(&request.id);
+                self.waiters.write().await.remove(&request.id);
+                ConsentDecision::TimedOut
+            }
+        }
+    }
+
+    /// Ask the approver and process its answer; pending if there is none
+    async fn ask_approver(&self, request: &ConsentRequest, timeout: Duration) {
+        let Some(approver) = &self.approver else {
+            return std::future::pending().await;
+        };
+        let response = match approver.request_approval(request, timeout).await {
+            Ok(Some(response)) => response,
+            Ok(None) => return std::future::pending().await,
+            Err(e) => {
+                warn!("Consent approval for request {} failed: {}", request.id, e);
+                ConsentResponse {
+                    reason: Some(format!("Approval failed: {e}")),
+                    ..ConsentResponse::new(request.id, false)
                 }
             }
-            Ok(ConsentDecision::TimedOut)
+        };
+        if let Err(e) = self.process_response(response).await {
+            warn!("Failed to process consent response: {}", e);
         }
     }
 
+    /// Process responses from the UI channel; never completes
+    async fn forward_channel_responses(&self) {
+        let mut receiver = self.response_receiver.write().await;
+        if let Some(receiver) = receiver.as_mut() {
+            while let Some(response) = receiver.recv().await {
+                if let Err(e) = self.process_response(response).await {
+                    warn!("Failed to process consent response: {}", e);
+                }
+            }
+        }
+        std::future::pending().await
+    }
+
     /// Record consent decision for audit trail
-    async fn record_consent_decision(&self, request: ConsentRequest, decision: ConsentDecision) {
+    async fn record_consent_decision(
+        &self,
+        request: ConsentRequest,
+        decision: ConsentDecision,
+        decision_method: DecisionMethod,
+    ) {
         if !self.config.audit_all_decisions {
             return;
         }
 
-        let decision_method = match &decision {
-            ConsentDecision::Approved | ConsentDecision::Denied { .. } => {
-                DecisionMethod::UserDecision
-            }
-            ConsentDecision::AutoApproved { .. } => DecisionMethod::PolicyBased,
-            ConsentDecision::TimedOut => DecisionMethod::Timeout,
-        };
-
+        info!(
+            request_id = %request.id,
+            operation = %self.get_operation_key(&request.operation),
+            decision = ?decision,
+            method = ?decision_method,
+            "Consent decision"
+        );
+        let approved = matches!(
+            decision,
+            ConsentDecision::Approved | ConsentDecision::AutoApproved { .. }
+        );
         let record = ConsentRecord {
+            response: ConsentResponse::new(request.id, approved),
             request,
-            response: ConsentResponse {
-                request_id: Uuid::new_v4(), // Placeholder
-                approved: matches!(decision, ConsentDecision::Approved),
-                reason: None,
-                responded_at: SystemTime::now(),
-                validity_duration: None,
-                apply_to_similar: false,
-                user_id: None,
-            },
             decision,
             decision_method,
             execution_result: None,
@@ -667,8 +784,11 @@ impl ConsentManager {
 
         let mut history = self.decision_history.write().await;
         history.push(record);
+    }
 
-        debug!("Recorded consent decision for operation");
+    /// Audit records of all consent decisions, oldest first
+    pub async fn decision_history(&self) -> Vec<ConsentRecord> {
+        self.decision_history.read().await.clone()
     }
 
     /// Get consent statistics
@@ -5,8 +5,10 @@
 
 use super::response_parser::{DeviceCommand, SamplingResponse};
 // Removed audit_log imports - module was unused
-use crate::client::ClientContext;
+use crate::client::commands::{CommandArg, command_specs};
+use crate::client::{ClientContext, ControlType};
 use crate::error::{LoxoneError, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -72,12 +74,31 @@ impl Default for ExecutionContext {
     }
 }
 
+/// Where validated commands are sent
+///
+/// Without a sink the executor only simulates commands; the server provides one
+/// that resolves names against the structure and sends real Loxone commands.
+#[async_trait]
+pub trait CommandSink: Send + Sync {
+    /// Resolve a device name (optionally scoped to a room) to its UUID
+    async fn resolve_device(&self, device: &str, room: Option<&str>) -> Result<String>;
+
+    /// Send a validated action (`on`, `dim`, `up`, `set_temperature`, `volume`, ...)
+    async fn send_action(
+        &self,
+        device_uuid: &str,
+        action: &str,
+        value: Option<&str>,
+    ) -> Result<String>;
+}
+
 /// Command executor that interfaces with Loxone system
 pub struct CommandExecutor {
     client_context: Arc<ClientContext>,
     // audit_logger removed - audit_log module was unused
     device_cache: Arc<tokio::sync::RwLock<HashMap<String, String>>>, // name -> UUID mapping
     safety_rules: SafetyRules,
+    sink: Option<Arc<dyn CommandSink>>,
 }
 
 /// Safety rules for command execution
@@ -123,9 +144,16 @@ impl CommandExecutor {
             // audit_logger removed
             device_cache: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
             safety_rules: SafetyRules::default(),
+            sink: None,
         }
     }
 
+    /// Resolve devices and send commands through a sink instead of simulating them
+    pub fn with_sink(mut self, sink: Arc<dyn CommandSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
     /// Execute a parsed sampling response
     pub async fn execute_sampling_response(
         &self,
@@ -180,6 +208,14 @@ impl CommandExecutor {
             if self.requires_approval(&command, &context) {
                 approval_required.push(command.clone());
 
+                // A dry run still validates the command but reports the approval need
+                if context.dry_run {
+                    let mut result = self.execute_single_command(command, &context).await;
+                    result.required_approval = true;
+                    results.push(result);
+                    continue;
+                }
+
                 if context.require_approval {
                     results.push(ExecutionResult {
                         command: command.clone(),
@@ -249,11 +285,12 @@ impl CommandExecutor {
 
         // Execute based on action type
         let result = if context.dry_run {
-            Ok("Dry run - command validated but not executed".to_string())
+            Self::validate_action(&command)
+                .map(|()| "Dry run - command validated but not executed".to_string())
         } else {
             match command.action.as_str() {
-                "on" | "off" => {
-                    self.execute_light_command(&device_uuid, &command.action)
+                "on" | "off" | "dim" | "brighten" => {
+                    self.execute_light_command(&device_uuid, &command.action, &command.value)
                         .await
                 }
                 "up" | "down" => {
@@ -294,13 +331,22 @@ impl CommandExecutor {
         }
     }
 
-    /// Check if command requires manual approval
-    fn requires_approval(&self, command: &DeviceCommand, context: &ExecutionContext) -> bool {
-        // Always allow in dry run mode
-        if context.dry_run {
-            return false;
+    /// Check that an action is supported and its value is usable
+    fn validate_action(command: &DeviceCommand) -> Result<()> {
+        match command.action.as_str() {
+            "on" | "off" | "up" | "down" | "play" | "stop" | "pause" => Ok(()),
+            "dim" | "brighten" => parse_percentage(&command.value, "brightness").map(|_| ()),
+            "volume" => parse_percentage(&command.value, "volume").map(|_| ()),
+            "set_temperature" => parse_temperature(&command.value).map(|_| ()),
+            _ => Err(LoxoneError::Generic(anyhow::anyhow!(
+                "Unsupported action: {}",
+                command.action
+            ))),
         }
+    }
 
+    /// Check if command requires manual approval
+    fn requires_approval(&self, command: &DeviceCommand, _context: &ExecutionContext) -> bool {
         // Check high-risk actions
         if self
             .safety_rules
@@ -342,6 +388,12 @@ impl CommandExecutor {
 
     /// Resolve device name to UUID
     async fn resolve_device_uuid(&self, command: &DeviceCommand) -> Result<String> {
+        if let Some(sink) = &self.sink {
+            return sink
+                .resolve_device(&command.device, command.room.as_deref())
+                .await;
+        }
+
         // First check cache
         {
             let cache = self.device_cache.read().await;
@@ -436,14 +488,29 @@ impl CommandExecutor {
     }
 
     /// Execute light control command
-    async fn execute_light_command(&self, device_uuid: &str, action: &str) -> Result<String> {
+    async fn execute_light_command(
+        &self,
+        device_uuid: &str,
+        action: &str,
+        value: &Option<String>,
+    ) -> Result<String> {
         debug!("Executing light command: {} on {}", action, device_uuid);
 
-        // This would typically call the actual Loxone tools
-        // For now, simulate the execution
         let _command = match action {
             "on" => "On",
             "off" => "Off",
+            "dim" | "brighten" => {
+                let brightness = parse_percentage(value, "brightness")?;
+                if let Some(sink) = &self.sink {
+                    return sink
+                        .send_action(device_uuid, action, Some(&brightness.to_string()))
+                        .await;
+                }
+                info!("Light {} set to {}%", device_uuid, brightness);
+                return Ok(format!(
+                    "Light {device_uuid} successfully set to {brightness}%"
+                ));
+            }
             _ => {
                 return Err(LoxoneError::Generic(anyhow::anyhow!(
                     "Invalid light action: {}",
@@ -452,8 +519,9 @@ impl CommandExecutor {
             }
         };
 
-        // In real implementation, this would call:
-        // self.loxone_client.send_command(device_uuid, command).await?;
+        if let Some(sink) = &self.sink {
+            return sink.send_action(device_uuid, action, None).await;
+        }
 
         info!("Light {} turned {}", device_uuid, action);
         Ok(format!("Light {device_uuid} successfully turned {action}"))
@@ -475,8 +543,9 @@ impl CommandExecutor {
             }
         };
 
-        // In real implementation, this would call:
-        // self.loxone_client.send_command(device_uuid, command).await?;
+        if let Some(sink) = &self.sink {
+            return sink.send_action(device_uuid, action, None).await;
+        }
 
         info!("Blind {} moved {}", device_uuid, action);
         Ok(format!("Blind {device_uuid} successfully moved {action}"))
@@ -488,30 +557,23 @@ impl CommandExecutor {
         device_uuid: &str,
         value: &Option<String>,
     ) -> Result<String> {
-        let temperature = value
-            .as_ref()
-            .ok_or_else(|| LoxoneError::Generic(anyhow::anyhow!("No temperature value provided")))?
-            .parse::<f32>()
-            .map_err(|e| {
-                LoxoneError::Generic(anyhow::anyhow!("Invalid temperature value: {}", e))
-            })?;
+        let temperature = parse_temperature(value)?;
 
         debug!(
             "Executing climate command: set temperature to {}°C on {}",
             temperature, device_uuid
         );
 
-        // Validate temperature range
-        if !(10.0..=30.0).contains(&temperature) {
-            return Err(LoxoneError::Generic(anyhow::anyhow!(
-                "Temperature {} is outside valid range (10-30°C)",
-                temperature
-            )));
+        if let Some(sink) = &self.sink {
+            return sink
+                .send_action(
+                    device_uuid,
+                    "set_temperature",
+                    Some(&temperature.to_string()),
+                )
+                .await;
         }
 
-        // In real implementation, this would call:
-        // self.loxone_client.set_temperature(device_uuid, temperature).await?;
-
         info!("Temperature set to {}°C on {}", temperature, device_uuid);
         Ok(format!(
             "Temperature successfully set to {temperature}°C on {device_uuid}"
@@ -530,6 +592,16 @@ impl CommandExecutor {
             action, device_uuid, value
         );
 
+        if let Some(sink) = &self.sink {
+            let volume = match action {
+                "volume" => Some(parse_percentage(value, "volume")?.to_string()),
+                _ => None,
+            };
+            return sink
+                .send_action(device_uuid, action, volume.as_deref())
+                .await;
+        }
+
         match action {
             "play" => {
                 // In real implementation: self.loxone_client.audio_play(device_uuid).await?;
@@ -544,23 +616,7 @@ impl CommandExecutor {
                 Ok(format!("Audio playback paused on {device_uuid}"))
             }
             "volume" => {
-                let volume = value
-                    .as_ref()
-                    .ok_or_else(|| {
-                        LoxoneError::Generic(anyhow::anyhow!("No volume value provided"))
-                    })?
-                    .parse::<u32>()
-                    .map_err(|e| {
-                        LoxoneError::Generic(anyhow::anyhow!("Invalid volume value: {}", e))
-                    })?;
-
-                if volume > 100 {
-                    return Err(LoxoneError::Generic(anyhow::anyhow!(
-                        "Volume cannot exceed 100%"
-                    )));
-                }
-
-                // In real implementation: self.loxone_client.set_volume(device_uuid, volume).await?;
+                let volume = parse_percentage(value, "volume")?;
                 Ok(format!("Volume set to {volume}% on {device_uuid}"))
             }
             _ => Err(LoxoneError::Generic(anyhow::anyhow!(
@@ -606,11 +662,77 @@ impl CommandExecutor {
     }
 }
 
+/// Parse a 0-100 percentage such as a volume or brightness
+fn parse_percentage(value: &Option<String>, what: &str) -> Result<u8> {
+    let raw = value
+        .as_deref()
+        .ok_or_else(|| LoxoneError::Generic(anyhow::anyhow!("No {} value provided", what)))?;
+    let percentage = raw
+        .trim()
+        .trim_end_matches('%')
+        .parse::<u32>()
+        .map_err(|e| LoxoneError::Generic(anyhow::anyhow!("Invalid {} value: {}", what, e)))?;
+    if percentage > 100 {
+        return Err(LoxoneError::Generic(anyhow::anyhow!(
+            "{} cannot exceed 100%",
+            what
+        )));
+    }
+    Ok(percentage as u8)
+}
+
+/// Range of target temperatures in the room controller command catalog
+fn temperature_range() -> (f64, f64) {
+    command_specs(&ControlType::IRoomControllerV2)
+        .and_then(|specs| {
+            specs
+                .iter()
+                .find(|spec| spec.name == "setComfortTemperature")
+        })
+        .and_then(|spec| match spec.args {
+            [CommandArg::Number { min, max, .. }] => Some((*min, *max)),
+            _ => None,
+        })
+        .unwrap_or((f64::NEG_INFINITY, f64::INFINITY))
+}
+
+/// Parse a target temperature within the range room controllers accept
+fn parse_temperature(value: &Option<String>) -> Result<f32> {
+    let temperature = value
+        .as_ref()
+        .ok_or_else(|| LoxoneError::Generic(anyhow::anyhow!("No temperature value provided")))?
+        .trim()
+        .trim_end_matches("°C")
+        .parse::<f32>()
+        .map_err(|e| LoxoneError::Generic(anyhow::anyhow!("Invalid temperature value: {}", e)))?;
+
+    let (min, max) = temperature_range();
+    if !(min..=max).contains(&f64::from(temperature)) {
+        return Err(LoxoneError::Generic(anyhow::anyhow!(
+            "Temperature {} is outside valid range ({}-{}°C)",
+            temperature,
+            min,
+            max
+        )));
+    }
+    Ok(temperature)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::client::LoxoneDevice;
 
+    #[test]
+    fn test_temperature_range_follows_command_catalog() {
+        assert_eq!(temperature_range(), (5.0, 40.0));
+        let parse = |value: &str| parse_temperature(&Some(value.to_string()));
+        assert_eq!(parse("35°C").unwrap(), 35.0);
+        assert_eq!(parse("5").unwrap(), 5.0);
+        assert!(parse("4.5").is_err());
+        assert!(parse("45").is_err());
+    }
+
     #[tokio::test]
     async fn test_device_resolution() {
         let client_context = Arc::new(ClientContext::new());
@@ -673,4 +795,110 @@ mod tests {
         assert!(!executor.requires_approval(&safe_command, &context));
         assert!(executor.requires_approval(&risky_command, &context));
     }
+
+    struct RecordingSink {
+        sent: tokio::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl CommandSink for RecordingSink {
+        async fn resolve_device(&self, device: &str, room: Option<&str>) -> Result<String> {
+            match (device, room) {
+                ("Kitchen Light", _) => Ok("kitchen-light".to_string()),
+                ("Front Door Lock", _) => Ok("front-door".to_string()),
+                _ => Err(LoxoneError::not_found(format!(
+                    "Device not found: {device}"
+                ))),
+            }
+        }
+
+        async fn send_action(
+            &self,
+            device_uuid: &str,
+            action: &str,
+            value: Option<&str>,
+        ) -> Result<String> {
+            let sent = format!("{device_uuid}:{action}:{}", value.unwrap_or("-"));
+            self.sent.lock().await.push(sent.clone());
+            Ok(sent)
+        }
+    }
+
+    fn command(device: &str, action: &str, value: Option<&str>) -> DeviceCommand {
+        DeviceCommand {
+            device: device.to_string(),
+            action: action.to_string(),
+            value: value.map(str::to_string),
+            room: None,
+            confidence: 0.9,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sink_receives_validated_commands() {
+        let sink = Arc::new(RecordingSink {
+            sent: tokio::sync::Mutex::new(Vec::new()),
+        });
+        let mut executor =
+            CommandExecutor::new(Arc::new(ClientContext::new())).with_sink(sink.clone());
+        executor.safety_rules.night_mode_restrictions = false;
+
+        let result = executor
+            .execute_command_batch(
+                vec![
+                    command("Kitchen Light", "dim", Some("40%")),
+                    command("Kitchen Light", "volume", Some("150")),
+                    command("Garage Light", "on", None),
+                ],
+                ExecutionContext::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.success_count, 1);
+        assert_eq!(
+            result.results[0].device_uuid.as_deref(),
+            Some("kitchen-light")
+        );
+        assert!(result.results[1].message.contains("cannot exceed 100%"));
+        assert!(
+            result.results[2]
+                .message
+                .contains("Failed to resolve device")
+        );
+        assert_eq!(*sink.sent.lock().await, ["kitchen-light:dim:40"]);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_validates_without_sending() {
+        let sink = Arc::new(RecordingSink {
+            sent: tokio::sync::Mutex::new(Vec::new()),
+        });
+        let mut executor =
+            CommandExecutor::new(Arc::new(ClientContext::new())).with_sink(sink.clone());
+        executor.safety_rules.night_mode_restrictions = false;
+        let context = ExecutionContext {
+            dry_run: true,
+            ..ExecutionContext::default()
+        };
+
+        let result = executor
+            .execute_command_batch(
+                vec![
+                    command("Kitchen Light", "on", None),
+                    command("Kitchen Light", "set_temperature", Some("45")),
+                    command("Front Door Lock", "unlock", None),
+                ],
+                context,
+            )
+            .await
+            .unwrap();
+
+        assert!(result.results[0].success);
+        assert!(!result.results[1].success);
+        assert!(result.results[2].required_approval);
+        // The out-of-range temperature needs approval as well
+        assert_eq!(result.approval_required.len(), 2);
+        assert!(sink.sent.lock().await.is_empty());
+    }
 }
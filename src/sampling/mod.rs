@@ -9,6 +9,7 @@ pub mod config;
 pub mod executor;
 pub mod ollama_http;
 pub mod protocol;
+pub mod providers;
 // pub mod provider; // Removed - compilation issues
 pub mod response_parser;
 pub mod service;
@@ -0,0 +1,514 @@
+//! LLM providers for the sampling pipeline
+//!
+//! [`sampling_client`] turns a [`ProviderFactoryConfig`] into a [`SamplingClient`]:
+//! the connected MCP client's own model through `sampling/createMessage`, a local
+//! Ollama, or the OpenAI and Anthropic APIs. Without an explicit provider the MCP
+//! client is asked first, then the enabled providers in priority order (only the
+//! first of them unless fallback is enabled).
+
+use super::client::{SamplingCapabilities, SamplingClient};
+use super::config::{AnthropicConfig, OpenAIConfig, ProviderFactoryConfig};
+use super::ollama_http::OllamaHttpClient;
+use super::{SamplingMessageContent, SamplingRequest, SamplingResponse};
+use crate::error::{LoxoneError, Result};
+use async_trait::async_trait;
+use pulseengine_mcp_server::{
+    CreateMessageRequest, SamplingContent, SamplingMessage, SamplingRole, ToolContextError,
+    try_current_context,
+};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Provider names accepted by [`sampling_client`]
+pub const PROVIDERS: [&str; 4] = ["mcp", "ollama", "openai", "anthropic"];
+
+const OPENAI_URL: &str = "https://api.openai.com/v1/chat/completions";
+const ANTHROPIC_URL: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const DEFAULT_MAX_TOKENS: u32 = 800;
+
+/// How long the MCP client may take to answer a sampling request
+const MCP_SAMPLING_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Sampling client for a provider name, or the default chain for `None`
+pub fn sampling_client(
+    config: &ProviderFactoryConfig,
+    provider: Option<&str>,
+) -> Result<Arc<dyn SamplingClient>> {
+    match provider.map(str::to_lowercase).as_deref() {
+        None | Some("") | Some("auto") => {
+            let mut enabled = config.get_enabled_providers();
+            if !config.selection.enable_fallback {
+                enabled.truncate(1);
+            }
+            let mut providers: Vec<(&'static str, Arc<dyn SamplingClient>)> =
+                vec![("mcp", Arc::new(McpClientSampling))];
+            for (name, _) in enabled {
+                // Cloud providers without a key are skipped rather than failing the chain
+                match provider_client(config, name) {
+                    Ok(client) => providers.push((provider_name(name), client)),
+                    Err(e) => debug!("Skipping LLM provider {name}: {e}"),
+                }
+            }
+            Ok(Arc::new(ProviderChain::new(providers)))
+        }
+        Some(name) => provider_client(config, name),
+    }
+}
+
+fn provider_client(config: &ProviderFactoryConfig, name: &str) -> Result<Arc<dyn SamplingClient>> {
+    match name {
+        "mcp" => Ok(Arc::new(McpClientSampling)),
+        "ollama" => Ok(Arc::new(OllamaSampling {
+            client: OllamaHttpClient::new(
+                config.ollama.base_url.clone(),
+                config.ollama.default_model.clone(),
+            )?,
+            model: config.ollama.default_model.clone(),
+        })),
+        "openai" => Ok(Arc::new(OpenAiSampling::new(&config.openai)?)),
+        "anthropic" => Ok(Arc::new(AnthropicSampling::new(&config.anthropic)?)),
+        other => Err(LoxoneError::invalid_input(format!(
+            "Unknown LLM provider '{other}'. Use: {}",
+            PROVIDERS.join(", ")
+        ))),
+    }
+}
+
+fn provider_name(name: &str) -> &'static str {
+    PROVIDERS
+        .iter()
+        .find(|provider| **provider == name)
+        .copied()
+        .unwrap_or("unknown")
+}
+
+fn capabilities(max_tokens: Option<u32>, models: Vec<String>) -> SamplingCapabilities {
+    SamplingCapabilities {
+        supported: true,
+        max_tokens,
+        supported_models: models,
+        supports_images: false,
+        supports_audio: false,
+    }
+}
+
+fn http_client(timeout_seconds: u32) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(u64::from(timeout_seconds)))
+        .build()
+        .map_err(|e| LoxoneError::connection(format!("Failed to create HTTP client: {e}")))
+}
+
+/// Send a JSON request to a provider API and return the JSON body
+async fn send_json(provider: &str, request: reqwest::RequestBuilder) -> Result<Value> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| LoxoneError::connection(format!("{provider} request failed: {e}")))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(LoxoneError::connection(format!(
+            "{provider} request failed: HTTP {status} - {body}"
+        )));
+    }
+    serde_json::from_str(&body)
+        .map_err(|e| LoxoneError::connection(format!("Invalid {provider} response: {e}")))
+}
+
+/// Chat messages as `{role, content}` objects, system prompt excluded
+fn chat_messages(request: &SamplingRequest) -> Vec<Value> {
+    request
+        .messages
+        .iter()
+        .filter_map(|message| {
+            let text = message.content.text.as_deref()?;
+            Some(json!({ "role": message.role, "content": text }))
+        })
+        .collect()
+}
+
+fn text_response(model: &str, stop_reason: Option<&str>, text: &str) -> SamplingResponse {
+    SamplingResponse {
+        model: model.to_string(),
+        stop_reason: stop_reason.unwrap_or("endTurn").to_string(),
+        role: "assistant".to_string(),
+        content: SamplingMessageContent::text(text),
+    }
+}
+
+/// Tries each provider in turn until one answers
+pub struct ProviderChain {
+    providers: Vec<(&'static str, Arc<dyn SamplingClient>)>,
+}
+
+impl ProviderChain {
+    /// Chain of named providers, tried in the given order
+    pub fn new(providers: Vec<(&'static str, Arc<dyn SamplingClient>)>) -> Self {
+        Self { providers }
+    }
+
+    /// Names of the providers in the chain
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.providers.iter().map(|(name, _)| *name).collect()
+    }
+}
+
+#[async_trait]
+impl SamplingClient for ProviderChain {
+    async fn create_message(&self, request: SamplingRequest) -> Result<SamplingResponse> {
+        let mut failures = Vec::new();
+        for (name, client) in &self.providers {
+            if !client.is_sampling_supported() {
+                // e.g. MCP sampling from `loxone-cli ask`, outside any tool call
+                info!("Skipping LLM provider {name}: not available here");
+                failures.push(format!("{name}: not available"));
+                continue;
+            }
+            match client.create_message(request.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    warn!("LLM provider {name} failed: {e}");
+                    failures.push(format!("{name}: {e}"));
+                }
+            }
+        }
+        Err(LoxoneError::ServiceUnavailable(format!(
+            "No LLM provider answered ({})",
+            failures.join("; ")
+        )))
+    }
+
+    fn is_sampling_supported(&self) -> bool {
+        self.providers
+            .iter()
+            .any(|(_, client)| client.is_sampling_supported())
+    }
+
+    fn get_sampling_capabilities(&self) -> SamplingCapabilities {
+        self.providers
+            .iter()
+            .find(|(_, client)| client.is_sampling_supported())
+            .map(|(_, client)| client.get_sampling_capabilities())
+            .unwrap_or_default()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// The model of the MCP client whose tool call is running
+///
+/// Only available inside a tool call; transports that cannot send requests
+/// to the client fail with [`ToolContextError::NotAvailable`].
+pub struct McpClientSampling;
+
+#[async_trait]
+impl SamplingClient for McpClientSampling {
+    async fn create_message(&self, request: SamplingRequest) -> Result<SamplingResponse> {
+        let context = try_current_context().ok_or_else(|| {
+            LoxoneError::ServiceUnavailable("No MCP client to sample from".to_string())
+        })?;
+        let messages = request
+            .messages
+            .iter()
+            .filter_map(|message| {
+                let text = message.content.text.clone()?;
+                Some(SamplingMessage {
+                    role: match message.role.as_str() {
+                        "assistant" => SamplingRole::Assistant,
+                        _ => SamplingRole::User,
+                    },
+                    content: SamplingContent::Text { text },
+                })
+            })
+            .collect();
+        let create = CreateMessageRequest {
+            messages,
+            max_tokens: request
+                .sampling_params
+                .max_tokens
+                .unwrap_or(DEFAULT_MAX_TOKENS),
+            system_prompt: request.system_prompt.clone(),
+            stop_sequences: request.sampling_params.stop_sequences.clone(),
+            temperature: request.sampling_params.temperature,
+            ..CreateMessageRequest::default()
+        };
+
+        let result = context
+            .request_sampling(create, MCP_SAMPLING_TIMEOUT)
+            .await
+            .map_err(|e| match e {
+                ToolContextError::NotAvailable => LoxoneError::ServiceUnavailable(
+                    "MCP client sampling: the transport cannot send requests to the client"
+                        .to_string(),
+                ),
+                e => LoxoneError::ServiceUnavailable(format!("MCP client sampling: {e}")),
+            })?;
+        let text = result.content.as_text().ok_or_else(|| {
+            LoxoneError::ServiceUnavailable("MCP client answered without text".to_string())
+        })?;
+        Ok(text_response(
+            &result.model,
+            result.stop_reason.as_deref(),
+            text,
+        ))
+    }
+
+    fn is_sampling_supported(&self) -> bool {
+        try_current_context().is_some()
+    }
+
+    fn get_sampling_capabilities(&self) -> SamplingCapabilities {
+        SamplingCapabilities {
+            supported: self.is_sampling_supported(),
+            ..SamplingCapabilities::default()
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// A local Ollama server
+struct OllamaSampling {
+    client: OllamaHttpClient,
+    model: String,
+}
+
+#[async_trait]
+impl SamplingClient for OllamaSampling {
+    async fn create_message(&self, request: SamplingRequest) -> Result<SamplingResponse> {
+        self.client.generate(&request).await
+    }
+
+    fn is_sampling_supported(&self) -> bool {
+        true
+    }
+
+    fn get_sampling_capabilities(&self) -> SamplingCapabilities {
+        capabilities(None, vec![self.model.clone()])
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// The OpenAI chat completions API
+struct OpenAiSampling {
+    http: reqwest::Client,
+    api_key: String,
+    organization: Option<String>,
+    model: String,
+}
+
+impl OpenAiSampling {
+    fn new(config: &OpenAIConfig) -> Result<Self> {
+        let api_key = config
+            .api_key
+            .clone()
+            .ok_or_else(|| LoxoneError::config("OpenAI needs OPENAI_API_KEY"))?;
+        Ok(Self {
+            http: http_client(config.timeout_seconds)?,
+            api_key,
+            organization: config.organization.clone(),
+            model: config.default_model.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl SamplingClient for OpenAiSampling {
+    async fn create_message(&self, request: SamplingRequest) -> Result<SamplingResponse> {
+        let mut messages = Vec::new();
+        if let Some(system) = &request.system_prompt {
+            messages.push(json!({ "role": "system", "content": system }));
+        }
+        messages.extend(chat_messages(&request));
+
+        let mut http = self
+            .http
+            .post(OPENAI_URL)
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "model": self.model,
+                "messages": messages,
+                "max_tokens": request.sampling_params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+                "temperature": request.sampling_params.temperature,
+            }));
+        if let Some(organization) = &self.organization {
+            http = http.header("OpenAI-Organization", organization);
+        }
+        let body = send_json("OpenAI", http).await?;
+
+        let choice = &body["choices"][0];
+        let text = choice["message"]["content"]
+            .as_str()
+            .ok_or_else(|| LoxoneError::connection("OpenAI answered without text"))?;
+        Ok(text_response(
+            body["model"].as_str().unwrap_or(&self.model),
+            choice["finish_reason"].as_str(),
+            text,
+        ))
+    }
+
+    fn is_sampling_supported(&self) -> bool {
+        true
+    }
+
+    fn get_sampling_capabilities(&self) -> SamplingCapabilities {
+        capabilities(None, vec![self.model.clone()])
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// The Anthropic messages API
+struct AnthropicSampling {
+    http: reqwest::Client,
+    api_key: String,
+    model: String,
+}
+
+impl AnthropicSampling {
+    fn new(config: &AnthropicConfig) -> Result<Self> {
+        let api_key = config
+            .api_key
+            .clone()
+            .ok_or_else(|| LoxoneError::config("Anthropic needs ANTHROPIC_API_KEY"))?;
+        Ok(Self {
+            http: http_client(config.timeout_seconds)?,
+            api_key,
+            model: config.default_model.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl SamplingClient for AnthropicSampling {
+    async fn create_message(&self, request: SamplingRequest) -> Result<SamplingResponse> {
+        let mut payload = json!({
+            "model": self.model,
+            "max_tokens": request.sampling_params.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "messages": chat_messages(&request),
+        });
+        if let Some(system) = &request.system_prompt {
+            payload["system"] = json!(system);
+        }
+        if let Some(temperature) = request.sampling_params.temperature {
+            payload["temperature"] = json!(temperature);
+        }
+        let http = self
+            .http
+            .post(ANTHROPIC_URL)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&payload);
+        let body = send_json("Anthropic", http).await?;
+
+        let text = body["content"]
+            .as_array()
+            .and_then(|blocks| blocks.iter().find_map(|block| block["text"].as_str()))
+            .ok_or_else(|| LoxoneError::connection("Anthropic answered without text"))?;
+        Ok(text_response(
+            body["model"].as_str().unwrap_or(&self.model),
+            body["stop_reason"].as_str(),
+            text,
+        ))
+    }
+
+    fn is_sampling_supported(&self) -> bool {
+        true
+    }
+
+    fn get_sampling_capabilities(&self) -> SamplingCapabilities {
+        capabilities(None, vec![self.model.clone()])
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::client::MockSamplingClient;
+
+    #[tokio::test]
+    async fn test_chain_skips_unavailable_providers() {
+        let chain = ProviderChain::new(vec![
+            ("mcp", Arc::new(McpClientSampling)),
+            ("ollama", Arc::new(MockSamplingClient::new(false))),
+            (
+                "openai",
+                Arc::new(MockSamplingClient::new_with_provider("openai")),
+            ),
+        ]);
+        assert!(chain.is_sampling_supported());
+
+        let request = SamplingRequest::new(vec![crate::sampling::SamplingMessage::user(
+            "Make my home cozy",
+        )]);
+        let response = chain.create_message(request).await.unwrap();
+        assert_eq!(response.model, "gpt-4o");
+    }
+
+    #[tokio::test]
+    async fn test_chain_reports_every_failure() {
+        let chain = ProviderChain::new(vec![("mcp", Arc::new(McpClientSampling))]);
+        assert!(!chain.is_sampling_supported());
+
+        let request = SamplingRequest::new(vec![]);
+        let error = chain.create_message(request).await.unwrap_err();
+        assert!(error.to_string().contains("mcp: not available"));
+    }
+
+    #[tokio::test]
+    async fn test_mcp_sampling_without_client_requests() {
+        use pulseengine_mcp_server::{NoOpToolContext, with_context};
+
+        let context = Arc::new(NoOpToolContext::new("1", "execute_natural_language"));
+        let error = with_context(context, async {
+            assert!(McpClientSampling.is_sampling_supported());
+            McpClientSampling
+                .create_message(SamplingRequest::new(vec![]))
+                .await
+                .unwrap_err()
+        })
+        .await;
+        assert!(
+            error
+                .to_string()
+                .contains("transport cannot send requests to the client")
+        );
+    }
+
+    #[test]
+    fn test_provider_selection() {
+        let mut config = ProviderFactoryConfig::default();
+        config.anthropic.enabled = true;
+        config.anthropic.api_key = Some("key".to_string());
+
+        assert!(sampling_client(&config, Some("Anthropic")).is_ok());
+        assert!(sampling_client(&config, Some("openai")).is_err());
+        assert!(sampling_client(&config, Some("gemini")).is_err());
+
+        let chain = sampling_client(&config, None).unwrap();
+        let chain = chain.as_any().downcast_ref::<ProviderChain>().unwrap();
+        assert_eq!(chain.provider_names(), ["mcp", "ollama", "anthropic"]);
+
+        config.selection.enable_fallback = false;
+        let chain = sampling_client(&config, None).unwrap();
+        let chain = chain.as_any().downcast_ref::<ProviderChain>().unwrap();
+        assert_eq!(chain.provider_names(), ["mcp", "ollama"]);
+    }
+}
@@ -4,7 +4,7 @@
 //! to provide a complete LLM-powered home automation solution.
 
 use super::client::{SamplingCapabilities, SamplingClient};
-use super::executor::{BatchExecutionResult, CommandExecutor, ExecutionContext};
+use super::executor::{BatchExecutionResult, CommandExecutor, CommandSink, ExecutionContext};
 use super::response_parser::{CommandExtractor, SamplingResponse as ParsedResponse};
 use super::{AutomationSamplingBuilder, SamplingMessage, SamplingRequest};
 // Removed audit_log imports - module was unused
@@ -113,6 +113,12 @@ impl SamplingService {
         }
     }
 
+    /// Send extracted commands through a sink instead of simulating them
+    pub fn with_command_sink(mut self, sink: Arc<dyn CommandSink>) -> Self {
+        self.command_executor = self.command_executor.with_sink(sink);
+        self
+    }
+
     /// Process a complete sampling request from user input to execution
     pub async fn process_automation_request(
         &self,
@@ -370,13 +376,26 @@ impl SamplingService {
             );
 
             if self.config.require_human_approval {
-                return Ok(BatchExecutionResult {
-                    results: Vec::new(),
-                    success_count: 0,
-                    failure_count: 0,
-                    total_time_ms: 0,
-                    approval_required: commands,
-                });
+                if !context.dry_run {
+                    return Ok(BatchExecutionResult {
+                        results: Vec::new(),
+                        success_count: 0,
+                        failure_count: 0,
+                        total_time_ms: 0,
+                        approval_required: commands,
+                    });
+                }
+
+                // A dry run still validates the plan, with every command flagged
+                let mut result = self
+                    .command_executor
+                    .execute_command_batch(commands.clone(), context)
+                    .await?;
+                for command_result in &mut result.results {
+                    command_result.required_approval = true;
+                }
+                result.approval_required = commands;
+                return Ok(result);
             }
         }
 
@@ -1,8 +1,14 @@
-//! AES encryption utilities for secure Loxone WebSocket communication
+//! Loxone command encryption
 //!
-//! This module provides AES-CBC encryption/decryption functionality for securing
-//! WebSocket message payloads in Loxone communication. It implements the encryption
-//! protocol used by Loxone Miniservers for sensitive data transmission.
+//! Implements the Miniserver's command encryption for connections without TLS
+//! (Gen1 Miniservers): the client picks an AES-256 key and IV, sends them RSA
+//! encrypted via `jdev/sys/keyexchange/{session key}` (or as `?sk=` on every
+//! HTTP request), and wraps each command as `jdev/sys/enc/{cipher}` or
+//! `jdev/sys/fenc/{cipher}`. The plaintext is salted (`salt/{salt}/{cmd}`) and
+//! the salt is rotated with `nextSalt/{old}/{new}/{cmd}`. With `fenc` the
+//! Miniserver encrypts its response with the same key and IV.
+//!
+//! Commands use AES-256-CBC with zero-byte padding, as the Miniserver expects.
 
 // Allow deprecated GenericArray until aes crate updates to generic-array 1.x
 #![allow(deprecated)]
@@ -13,13 +19,8 @@ use aes::{
 };
 use base64::{Engine as _, engine::general_purpose};
 use rand::RngCore;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use tracing::debug;
-
-// For CBC mode, we'll implement a simple version since the cbc crate version is old
-// In production, consider upgrading to a newer cbc crate version
 
 /// Encryption-related errors
 #[derive(Error, Debug)]
@@ -43,229 +44,213 @@ pub enum EncryptionError {
     KeyExchangeFailed(String),
 }
 
-/// Encryption session with key and metadata
-#[derive(Debug, Clone)]
-pub struct EncryptionSession {
-    /// AES-256 encryption key (32 bytes)
-    pub key: [u8; 32],
-    /// Session identifier
-    pub session_id: String,
-    /// Key creation timestamp
-    pub created_at: chrono::DateTime<chrono::Utc>,
-    /// Key expiration timestamp
-    pub expires_at: chrono::DateTime<chrono::Utc>,
-    /// Encryption statistics
-    pub stats: EncryptionStats,
+/// A salt is replaced after this many commands
+const SALT_MAX_USES: u32 = 30;
+
+/// A salt is replaced after this long, whatever its use count
+const SALT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Endpoint wrapping an encrypted command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptedEndpoint {
+    /// `jdev/sys/enc/`: only the command is encrypted
+    Command,
+    /// `jdev/sys/fenc/`: command and response are encrypted
+    Full,
 }
 
-/// Encryption statistics for monitoring
-#[derive(Debug, Clone, Default)]
-pub struct EncryptionStats {
-    /// Messages encrypted
-    pub messages_encrypted: u64,
-    /// Messages decrypted
-    pub messages_decrypted: u64,
-    /// Bytes encrypted
-    pub bytes_encrypted: u64,
-    /// Bytes decrypted
-    pub bytes_decrypted: u64,
-    /// Encryption errors
-    pub encryption_errors: u64,
-    /// Decryption errors
-    pub decryption_errors: u64,
+impl EncryptedEndpoint {
+    fn path(self) -> &'static str {
+        match self {
+            Self::Command => "jdev/sys/enc",
+            Self::Full => "jdev/sys/fenc",
+        }
+    }
 }
 
-/// Encrypted message container
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EncryptedMessage {
-    /// Base64-encoded encrypted payload
-    pub payload: String,
-    /// Base64-encoded initialization vector
-    pub iv: String,
-    /// Session identifier
-    pub session_id: String,
-    /// Message timestamp
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-    /// Optional message metadata
-    pub metadata: Option<HashMap<String, String>>,
+/// Salted plaintext of an encrypted command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaltedCommand<'a> {
+    /// Salt of this command
+    pub salt: &'a str,
+    /// Salt announced for the following commands (`nextSalt/`)
+    pub next_salt: Option<&'a str>,
+    /// The actual command
+    pub command: &'a str,
 }
 
-/// Key exchange request/response structures
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct KeyExchangeRequest {
-    /// Client public key (placeholder for future RSA implementation)
-    pub client_key: String,
-    /// Requested session duration in seconds
-    pub session_duration: u64,
-    /// Client capabilities
-    pub capabilities: Vec<String>,
+impl<'a> SaltedCommand<'a> {
+    /// Split a decrypted `salt/...` or `nextSalt/...` plaintext
+    pub fn parse(plaintext: &'a str) -> Option<Self> {
+        if let Some(rest) = plaintext.strip_prefix("salt/") {
+            let (salt, command) = rest.split_once('/')?;
+            return Some(Self {
+                salt,
+                next_salt: None,
+                command,
+            });
+        }
+        let rest = plaintext.strip_prefix("nextSalt/")?;
+        let (salt, rest) = rest.split_once('/')?;
+        let (next_salt, command) = rest.split_once('/')?;
+        Some(Self {
+            salt,
+            next_salt: Some(next_salt),
+            command,
+        })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct KeyExchangeResponse {
-    /// Server response status
-    pub status: String,
-    /// Encrypted session key
-    pub encrypted_key: String,
-    /// Session identifier
-    pub session_id: String,
-    /// Key expiration timestamp
-    pub expires_at: chrono::DateTime<chrono::Utc>,
-    /// Server capabilities
-    pub server_capabilities: Vec<String>,
+/// AES session of the command encryption: key, IV and the current salt
+#[derive(Debug, Clone)]
+pub struct CommandCipher {
+    key: [u8; 32],
+    iv: [u8; 16],
+    salt: String,
+    salt_uses: u32,
+    salt_created: Instant,
 }
 
-impl EncryptionSession {
-    /// Create a new encryption session with random key
-    pub fn new(session_duration_hours: u32) -> Self {
+impl CommandCipher {
+    /// Create a cipher with a random key, IV and salt
+    pub fn new() -> Self {
         let mut key = [0u8; 32];
+        let mut iv = [0u8; 16];
         rand::rng().fill_bytes(&mut key);
-
-        let now = chrono::Utc::now();
-        let session_id = generate_session_id();
-
-        Self {
-            key,
-            session_id,
-            created_at: now,
-            expires_at: now + chrono::Duration::hours(session_duration_hours as i64),
-            stats: EncryptionStats::default(),
-        }
+        rand::rng().fill_bytes(&mut iv);
+        Self::with_key(key, iv, &generate_salt())
     }
 
-    /// Create session from existing key
-    pub fn from_key(key: [u8; 32], session_duration_hours: u32) -> Self {
-        let now = chrono::Utc::now();
-        let session_id = generate_session_id();
-
+    /// Create a cipher from a known key, IV and salt
+    pub fn with_key(key: [u8; 32], iv: [u8; 16], salt: &str) -> Self {
         Self {
             key,
-            session_id,
-            created_at: now,
-            expires_at: now + chrono::Duration::hours(session_duration_hours as i64),
-            stats: EncryptionStats::default(),
+            iv,
+            salt: salt.to_string(),
+            salt_uses: 0,
+            salt_created: Instant::now(),
         }
     }
 
-    /// Check if the session is still valid
-    pub fn is_valid(&self) -> bool {
-        chrono::Utc::now() < self.expires_at
+    /// Create a cipher from the `{hex key}:{hex iv}` session key plaintext
+    pub fn from_session_key(session_key: &str) -> Result<Self, EncryptionError> {
+        let (key_hex, iv_hex) = session_key.split_once(':').ok_or_else(|| {
+            EncryptionError::KeyExchangeFailed("Session key must be {key}:{iv}".to_string())
+        })?;
+        let decode = |value: &str| {
+            hex::decode(value.trim()).map_err(|e| {
+                EncryptionError::KeyExchangeFailed(format!("Invalid hex in session key: {e}"))
+            })
+        };
+        let key = decode(key_hex)?;
+        let iv = decode(iv_hex)?;
+        let key_len = key.len();
+        let iv_len = iv.len();
+        Ok(Self::with_key(
+            key.try_into()
+                .map_err(|_| EncryptionError::InvalidKeyLength(key_len))?,
+            iv.try_into()
+                .map_err(|_| EncryptionError::InvalidIvLength(iv_len))?,
+            &generate_salt(),
+        ))
     }
 
-    /// Get remaining session time in seconds
-    pub fn remaining_seconds(&self) -> i64 {
-        (self.expires_at - chrono::Utc::now()).num_seconds().max(0)
+    /// Session key plaintext `{hex key}:{hex iv}`, RSA encrypted for the key exchange
+    pub fn session_key(&self) -> String {
+        format!("{}:{}", hex::encode(self.key), hex::encode(self.iv))
     }
 
-    /// Encrypt a message using AES-256-CBC
-    pub fn encrypt_message(
-        &mut self,
-        plaintext: &[u8],
-    ) -> Result<EncryptedMessage, EncryptionError> {
-        if !self.is_valid() {
-            return Err(EncryptionError::EncryptionFailed(
-                "Session expired".to_string(),
-            ));
-        }
-
-        // Generate random IV
-        let mut iv = [0u8; 16];
-        rand::rng().fill_bytes(&mut iv);
-
-        // Encrypt the message
-        let encrypted_data = encrypt_aes_cbc(&self.key, &iv, plaintext)?;
-
-        // Update statistics
-        self.stats.messages_encrypted += 1;
-        self.stats.bytes_encrypted += plaintext.len() as u64;
-
-        debug!(
-            "Encrypted {} bytes with session {}",
-            plaintext.len(),
-            &self.session_id[..8]
-        );
-
-        Ok(EncryptedMessage {
-            payload: general_purpose::STANDARD.encode(&encrypted_data),
-            iv: general_purpose::STANDARD.encode(iv),
-            session_id: self.session_id.clone(),
-            timestamp: chrono::Utc::now(),
-            metadata: None,
-        })
+    /// Current salt
+    pub fn salt(&self) -> &str {
+        &self.salt
     }
 
-    /// Decrypt a message using AES-256-CBC
-    pub fn decrypt_message(
-        &mut self,
-        encrypted_msg: &EncryptedMessage,
-    ) -> Result<Vec<u8>, EncryptionError> {
-        if !self.is_valid() {
-            return Err(EncryptionError::DecryptionFailed(
-                "Session expired".to_string(),
-            ));
-        }
-
-        if encrypted_msg.session_id != self.session_id {
-            return Err(EncryptionError::DecryptionFailed(
-                "Session ID mismatch".to_string(),
-            ));
+    /// Salt a command, announcing a new salt once the current one is used up
+    pub fn salt_command(&mut self, command: &str) -> String {
+        if self.salt_uses >= SALT_MAX_USES || self.salt_created.elapsed() >= SALT_MAX_AGE {
+            let next_salt = generate_salt();
+            let salted = format!("nextSalt/{}/{next_salt}/{command}", self.salt);
+            self.salt = next_salt;
+            self.salt_uses = 1;
+            self.salt_created = Instant::now();
+            return salted;
         }
+        self.salt_uses += 1;
+        format!("salt/{}/{command}", self.salt)
+    }
 
-        // Decode base64 data
-        let encrypted_data = general_purpose::STANDARD.decode(&encrypted_msg.payload)?;
-        let iv = general_purpose::STANDARD.decode(&encrypted_msg.iv)?;
-
-        if iv.len() != 16 {
-            return Err(EncryptionError::InvalidIvLength(iv.len()));
-        }
-
-        let iv_len = iv.len();
-        let iv_array: [u8; 16] = iv
-            .try_into()
-            .map_err(|_| EncryptionError::InvalidIvLength(iv_len))?;
-
-        // Decrypt the message
-        let plaintext = decrypt_aes_cbc(&self.key, &iv_array, &encrypted_data)?;
-
-        // Update statistics
-        self.stats.messages_decrypted += 1;
-        self.stats.bytes_decrypted += plaintext.len() as u64;
+    /// Encrypt a command into a `jdev/sys/enc/` or `jdev/sys/fenc/` path
+    pub fn encrypt_command(&mut self, command: &str, endpoint: EncryptedEndpoint) -> String {
+        let salted = self.salt_command(command);
+        format!(
+            "{}/{}",
+            endpoint.path(),
+            urlencoding::encode(&self.encrypt_text(&salted))
+        )
+    }
 
-        debug!(
-            "Decrypted {} bytes with session {}",
-            plaintext.len(),
-            &self.session_id[..8]
-        );
+    /// Encrypt text to base64 (zero padded AES-256-CBC)
+    pub fn encrypt_text(&self, plaintext: &str) -> String {
+        let ciphertext = encrypt_aes_cbc_zero_padded(&self.key, &self.iv, plaintext.as_bytes());
+        general_purpose::STANDARD.encode(ciphertext)
+    }
 
-        Ok(plaintext)
+    /// Decrypt base64 text encrypted with this session (commands and `fenc` responses)
+    pub fn decrypt_text(&self, encrypted: &str) -> Result<String, EncryptionError> {
+        let encrypted = urlencoding::decode(encrypted.trim())
+            .map_err(|e| EncryptionError::DecryptionFailed(format!("Invalid encoding: {e}")))?;
+        let ciphertext = general_purpose::STANDARD.decode(encrypted.as_bytes())?;
+        let plaintext = decrypt_aes_cbc_zero_padded(&self.key, &self.iv, &ciphertext)?;
+        String::from_utf8(plaintext)
+            .map_err(|e| EncryptionError::DecryptionFailed(format!("Invalid UTF-8: {e}")))
     }
+}
 
-    /// Get encryption statistics
-    pub fn get_stats(&self) -> &EncryptionStats {
-        &self.stats
+impl Default for CommandCipher {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-/// Encrypt data using AES-256-CBC with PKCS7 padding
-pub fn encrypt_aes_cbc(
+/// Random salt: two bytes as hex, like the Loxone apps use
+fn generate_salt() -> String {
+    let mut bytes = [0u8; 2];
+    rand::rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Encrypt with AES-256-CBC, padding the plaintext with zero bytes
+pub fn encrypt_aes_cbc_zero_padded(key: &[u8; 32], iv: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+    let mut padded = plaintext.to_vec();
+    padded.resize(plaintext.len().div_ceil(16) * 16, 0);
+    cbc_encrypt_blocks(key, iv, &padded)
+}
+
+/// Decrypt AES-256-CBC and strip trailing zero bytes
+pub fn decrypt_aes_cbc_zero_padded(
     key: &[u8; 32],
     iv: &[u8; 16],
-    plaintext: &[u8],
+    ciphertext: &[u8],
 ) -> Result<Vec<u8>, EncryptionError> {
-    // Add PKCS7 padding
-    let block_size = 16;
-    let padding_len = block_size - (plaintext.len() % block_size);
-    let mut padded_data = plaintext.to_vec();
-    padded_data.extend(vec![padding_len as u8; padding_len]);
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(16) {
+        return Err(EncryptionError::DecryptionFailed(
+            "Ciphertext length must be a non-zero multiple of 16".to_string(),
+        ));
+    }
+    let mut plaintext = cbc_decrypt_blocks(key, iv, ciphertext);
+    let end = plaintext.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    plaintext.truncate(end);
+    Ok(plaintext)
+}
 
-    // Initialize AES-256 cipher
+/// CBC encryption of block aligned data
+fn cbc_encrypt_blocks(key: &[u8; 32], iv: &[u8; 16], data: &[u8]) -> Vec<u8> {
     let cipher = Aes256::new(GenericArray::from_slice(key));
 
-    // Perform CBC encryption
-    let mut ciphertext = Vec::with_capacity(padded_data.len());
+    let mut ciphertext = Vec::with_capacity(data.len());
     let mut previous_block = *iv;
 
-    for chunk in padded_data.chunks(block_size) {
+    for chunk in data.chunks(16) {
         // XOR with previous ciphertext block (or IV for first block)
         let mut block = [0u8; 16];
         for i in 0..16 {
@@ -276,48 +261,63 @@ pub fn encrypt_aes_cbc(
         let mut encrypted_block = *GenericArray::from_slice(&block);
         cipher.encrypt_block(&mut encrypted_block);
 
-        // Store the encrypted block
         ciphertext.extend_from_slice(&encrypted_block);
         previous_block.copy_from_slice(&encrypted_block);
     }
 
-    Ok(ciphertext)
+    ciphertext
 }
 
-/// Decrypt data using AES-256-CBC with PKCS7 padding
-pub fn decrypt_aes_cbc(
-    key: &[u8; 32],
-    iv: &[u8; 16],
-    ciphertext: &[u8],
-) -> Result<Vec<u8>, EncryptionError> {
-    if !ciphertext.len().is_multiple_of(16) {
-        return Err(EncryptionError::DecryptionFailed(
-            "Ciphertext length must be multiple of 16".to_string(),
-        ));
-    }
-
-    // Initialize AES-256 cipher
+/// CBC decryption of block aligned data
+fn cbc_decrypt_blocks(key: &[u8; 32], iv: &[u8; 16], data: &[u8]) -> Vec<u8> {
     let cipher = Aes256::new(GenericArray::from_slice(key));
 
-    // Perform CBC decryption
-    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let mut plaintext = Vec::with_capacity(data.len());
     let mut previous_block = *iv;
 
-    for chunk in ciphertext.chunks(16) {
+    for chunk in data.chunks(16) {
         // Decrypt the block
         let mut decrypted_block = *GenericArray::from_slice(chunk);
         cipher.decrypt_block(&mut decrypted_block);
 
         // XOR with previous ciphertext block (or IV for first block)
-        let mut block = [0u8; 16];
         for i in 0..16 {
-            block[i] = decrypted_block[i] ^ previous_block[i];
+            plaintext.push(decrypted_block[i] ^ previous_block[i]);
         }
-
-        plaintext.extend_from_slice(&block);
         previous_block.copy_from_slice(chunk);
     }
 
+    plaintext
+}
+
+/// Encrypt data using AES-256-CBC with PKCS7 padding
+pub fn encrypt_aes_cbc(
+    key: &[u8; 32],
+    iv: &[u8; 16],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, EncryptionError> {
+    // Add PKCS7 padding
+    let padding_len = 16 - (plaintext.len() % 16);
+    let mut padded_data = plaintext.to_vec();
+    padded_data.extend(vec![padding_len as u8; padding_len]);
+
+    Ok(cbc_encrypt_blocks(key, iv, &padded_data))
+}
+
+/// Decrypt data using AES-256-CBC with PKCS7 padding
+pub fn decrypt_aes_cbc(
+    key: &[u8; 32],
+    iv: &[u8; 16],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, EncryptionError> {
+    if !ciphertext.len().is_multiple_of(16) {
+        return Err(EncryptionError::DecryptionFailed(
+            "Ciphertext length must be multiple of 16".to_string(),
+        ));
+    }
+
+    let mut plaintext = cbc_decrypt_blocks(key, iv, ciphertext);
+
     // Remove PKCS7 padding
     if let Some(&padding_len) = plaintext.last() {
         let padding_len = padding_len as usize;
@@ -344,179 +344,106 @@ pub fn decrypt_aes_cbc(
     Ok(plaintext)
 }
 
-/// Generate a secure session ID
-fn generate_session_id() -> String {
-    let mut bytes = [0u8; 16];
-    rand::rng().fill_bytes(&mut bytes);
-    hex::encode(bytes)
-}
-
-/// Key derivation for Loxone-specific key exchange
-pub fn derive_loxone_key(
-    username: &str,
-    password: &str,
-    salt: &[u8],
-) -> Result<[u8; 32], EncryptionError> {
-    use sha2::{Digest, Sha256};
-
-    let mut key = [0u8; 32];
-    let credential_data = format!("{username}:{password}");
-
-    // Simplified key derivation - in production, use proper PBKDF2
-    let mut hasher = Sha256::new();
-    hasher.update(credential_data.as_bytes());
-    hasher.update(salt);
-
-    // Hash multiple times to simulate PBKDF2 iterations
-    let mut hash = hasher.finalize();
-    for _ in 0..1000 {
-        let mut hasher = Sha256::new();
-        hasher.update(hash);
-        hash = hasher.finalize();
-    }
-
-    key.copy_from_slice(&hash);
-    Ok(key)
-}
-
-/// Simple key exchange implementation (placeholder for future RSA)
-pub async fn perform_key_exchange(
-    request: KeyExchangeRequest,
-    server_capabilities: Vec<String>,
-) -> Result<KeyExchangeResponse, EncryptionError> {
-    // In a real implementation, this would use RSA key exchange
-    // For now, we'll simulate a successful exchange
-
-    debug!(
-        "Performing key exchange for client with capabilities: {:?}",
-        request.capabilities
-    );
-
-    // Generate a new session
-    let session = EncryptionSession::new((request.session_duration / 3600) as u32);
-
-    // In real implementation, encrypt the session key with client's public key
-    let encrypted_key = general_purpose::STANDARD.encode(session.key);
-
-    Ok(KeyExchangeResponse {
-        status: "success".to_string(),
-        encrypted_key,
-        session_id: session.session_id,
-        expires_at: session.expires_at,
-        server_capabilities,
-    })
-}
-
-/// Encryption session manager for multiple concurrent sessions
-#[derive(Debug)]
-pub struct EncryptionManager {
-    sessions: HashMap<String, EncryptionSession>,
-    max_sessions: usize,
-}
-
-impl EncryptionManager {
-    /// Create a new encryption manager
-    pub fn new(max_sessions: usize) -> Self {
-        Self {
-            sessions: HashMap::new(),
-            max_sessions,
-        }
-    }
-
-    /// Add a new session
-    pub fn add_session(&mut self, session: EncryptionSession) -> Result<(), EncryptionError> {
-        if self.sessions.len() >= self.max_sessions {
-            self.cleanup_expired_sessions();
-
-            if self.sessions.len() >= self.max_sessions {
-                return Err(EncryptionError::KeyExchangeFailed(
-                    "Maximum sessions reached".to_string(),
-                ));
-            }
-        }
-
-        let session_id = session.session_id.clone();
-        self.sessions.insert(session_id, session);
-        Ok(())
-    }
-
-    /// Get a session by ID
-    pub fn get_session(&self, session_id: &str) -> Option<&EncryptionSession> {
-        self.sessions.get(session_id)
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    /// Get a mutable session by ID
-    pub fn get_session_mut(&mut self, session_id: &str) -> Option<&mut EncryptionSession> {
-        self.sessions.get_mut(session_id)
-    }
+    /// Known-answer vectors computed independently with
+    /// `openssl enc -aes-256-cbc -nopad` over the zero padded plaintext
+    const KEY: [u8; 32] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+        0x1e, 0x1f,
+    ];
+    const IV: [u8; 16] = [
+        0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xab, 0xac, 0xad, 0xae,
+        0xaf,
+    ];
+    const COMMAND: &str = "jdev/sps/io/0b2e3f0c-0123-4567-ffff89abcdef0123/on";
 
-    /// Remove a session
-    pub fn remove_session(&mut self, session_id: &str) -> Option<EncryptionSession> {
-        self.sessions.remove(session_id)
+    #[test]
+    fn test_known_answer_salted_command() {
+        let mut cipher = CommandCipher::with_key(KEY, IV, "2f3a");
+        assert_eq!(
+            cipher.encrypt_command(COMMAND, EncryptedEndpoint::Command),
+            "jdev/sys/enc/sl3UzeQchd8UdTXoFgQTGXM5Fuq%2FibGzwzuJXH3990RwJKJG1Zyp%2FFJaOriBMv%2FWqNKOiQUEB8nxiRRP93zFAg%3D%3D"
+        );
+        assert_eq!(
+            cipher
+                .decrypt_text("sl3UzeQchd8UdTXoFgQTGXM5Fuq/ibGzwzuJXH3990RwJKJG1Zyp/FJaOriBMv/WqNKOiQUEB8nxiRRP93zFAg==")
+                .unwrap(),
+            format!("salt/2f3a/{COMMAND}")
+        );
     }
 
-    /// Clean up expired sessions
-    pub fn cleanup_expired_sessions(&mut self) -> usize {
-        let before_count = self.sessions.len();
-        self.sessions.retain(|_, session| session.is_valid());
-        let removed_count = before_count - self.sessions.len();
-
-        if removed_count > 0 {
-            debug!("Cleaned up {} expired encryption sessions", removed_count);
-        }
-
-        removed_count
+    #[test]
+    fn test_known_answer_next_salt_and_response() {
+        let cipher = CommandCipher::with_key(KEY, IV, "2f3a");
+        assert_eq!(
+            cipher.encrypt_text(
+                "nextSalt/2f3a/9c01/jdev/sps/io/0b2e3f0c-0123-4567-ffff89abcdef0123/off"
+            ),
+            "sX/EwdeTfegcIeJGW2drtj6KDGlbN1EHlutNVlVdFoZ4TSlcbzEsRYKmIC0zcHKOwLpOVY/5z+K/MSgwcCaVemApOYOkvlkv1YZTYUCvUD8="
+        );
+        assert_eq!(
+            cipher
+                .decrypt_text("PmLkQMGsH26nHvitetMpmrFDuy24RtG6G14CVeY/VGkIIa+5OCY6H4j+kLP0YajDn1AMIHRoWUXT1Z04ggf1eimYzhA7wNcT9fu2/JoiJx4=")
+                .unwrap(),
+            r#"{"LL": {"control": "dev/sps/io/x/on", "value": "1", "Code": "200"}}"#
+        );
+        // Block aligned plaintext gets no padding block
+        assert_eq!(
+            cipher.encrypt_text("0123456789abcdef"),
+            "zhzzpOO4exObo8/xCs41tg=="
+        );
     }
 
-    /// Get total session statistics
-    pub fn get_total_stats(&self) -> EncryptionStats {
-        let mut total = EncryptionStats::default();
-
-        for session in self.sessions.values() {
-            let stats = &session.stats;
-            total.messages_encrypted += stats.messages_encrypted;
-            total.messages_decrypted += stats.messages_decrypted;
-            total.bytes_encrypted += stats.bytes_encrypted;
-            total.bytes_decrypted += stats.bytes_decrypted;
-            total.encryption_errors += stats.encryption_errors;
-            total.decryption_errors += stats.decryption_errors;
+    #[test]
+    fn test_salt_rotation() {
+        let mut cipher = CommandCipher::with_key(KEY, IV, "2f3a");
+        for _ in 0..SALT_MAX_USES {
+            assert_eq!(cipher.salt_command("x"), "salt/2f3a/x");
         }
 
-        total
-    }
-
-    /// Get session count and health info
-    pub fn get_session_info(&self) -> (usize, usize, usize) {
-        let total = self.sessions.len();
-        let valid = self.sessions.values().filter(|s| s.is_valid()).count();
-        let expired = total - valid;
-
-        (total, valid, expired)
+        let rotated = cipher.salt_command("x");
+        let salted = SaltedCommand::parse(&rotated).unwrap();
+        assert_eq!(salted.salt, "2f3a");
+        assert_eq!(salted.next_salt, Some(cipher.salt()));
+        assert_eq!(salted.command, "x");
+        assert_eq!(cipher.salt().len(), 4);
+        assert_eq!(
+            cipher.salt_command("x"),
+            format!("salt/{}/x", cipher.salt())
+        );
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn test_encryption_session_creation() {
-        let session = EncryptionSession::new(24);
-        assert!(session.is_valid());
-        assert!(session.remaining_seconds() > 0);
+    fn test_session_key_roundtrip() {
+        let cipher = CommandCipher::with_key(KEY, IV, "2f3a");
+        assert_eq!(
+            cipher.session_key(),
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f:a0a1a2a3a4a5a6a7a8a9aaabacadaeaf"
+        );
+        let restored = CommandCipher::from_session_key(&cipher.session_key()).unwrap();
+        assert_eq!(
+            restored.encrypt_text("0123456789abcdef"),
+            "zhzzpOO4exObo8/xCs41tg=="
+        );
+        assert!(CommandCipher::from_session_key("00:11").is_err());
+        assert!(CommandCipher::from_session_key("no-separator").is_err());
     }
 
     #[test]
-    fn test_encrypt_decrypt_roundtrip() {
-        let mut session = EncryptionSession::new(1);
-        let plaintext = b"Hello, Loxone!";
-
-        let encrypted = session.encrypt_message(plaintext).unwrap();
-        let decrypted = session.decrypt_message(&encrypted).unwrap();
-
-        assert_eq!(plaintext, decrypted.as_slice());
-        assert_eq!(session.stats.messages_encrypted, 1);
-        assert_eq!(session.stats.messages_decrypted, 1);
+    fn test_salted_command_parsing() {
+        assert_eq!(
+            SaltedCommand::parse("salt/ab12/jdev/sps/io/x/on"),
+            Some(SaltedCommand {
+                salt: "ab12",
+                next_salt: None,
+                command: "jdev/sps/io/x/on"
+            })
+        );
+        assert!(SaltedCommand::parse("jdev/sps/io/x/on").is_none());
     }
 
     #[test]
@@ -529,46 +456,7 @@ mod tests {
         let decrypted = decrypt_aes_cbc(&key, &iv, &encrypted).unwrap();
 
         assert_eq!(plaintext, decrypted.as_slice());
-        // Ensure we're not using the placeholder implementation
-        assert!(!encrypted.starts_with(b"AES_PLACEHOLDER_"));
         // Ensure the encrypted data is different from plaintext (actual encryption happened)
         assert_ne!(plaintext, encrypted.as_slice());
     }
-
-    #[tokio::test]
-    async fn test_key_exchange() {
-        let request = KeyExchangeRequest {
-            client_key: "client_public_key".to_string(),
-            session_duration: 3600,
-            capabilities: vec!["aes256".to_string()],
-        };
-
-        let server_caps = vec!["aes256".to_string(), "secure_websocket".to_string()];
-        let response = perform_key_exchange(request, server_caps).await.unwrap();
-
-        assert_eq!(response.status, "success");
-        assert!(!response.encrypted_key.is_empty());
-        assert!(!response.session_id.is_empty());
-    }
-
-    #[test]
-    fn test_encryption_manager() {
-        let mut manager = EncryptionManager::new(2);
-
-        let session1 = EncryptionSession::new(1);
-        let session2 = EncryptionSession::new(1);
-
-        let id1 = session1.session_id.clone();
-        let id2 = session2.session_id.clone();
-
-        manager.add_session(session1).unwrap();
-        manager.add_session(session2).unwrap();
-
-        assert!(manager.get_session(&id1).is_some());
-        assert!(manager.get_session(&id2).is_some());
-
-        let (total, valid, _expired) = manager.get_session_info();
-        assert_eq!(total, 2);
-        assert_eq!(valid, 2);
-    }
 }
@@ -277,19 +277,23 @@ pub struct ApiKeyPolicy {
 }
 
 /// Audit policy
+///
+/// Decides which device commands the [`AuditLog`](crate::audit::AuditLog)
+/// records and how long it keeps them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AuditPolicy {
     /// Enable audit logging
     pub enabled: bool,
-    /// Log successful authentications
+    /// Log commands the Miniserver accepted
     pub log_success: bool,
-    /// Log failed authentications
+    /// Log commands that failed at the Miniserver
     pub log_failures: bool,
-    /// Log permission denials
+    /// Log commands refused by validation or consent
     pub log_denials: bool,
-    /// Log sensitive operations
+    /// Log commands to security devices whatever their outcome
     pub log_sensitive_ops: bool,
-    /// Retention period
+    /// Retention period; 0 keeps entries forever
     pub retention_days: u32,
     /// Real-time alerting
     pub real_time_alerts: bool,
@@ -297,8 +301,24 @@ pub struct AuditPolicy {
     pub alert_thresholds: AlertThresholds,
 }
 
+impl Default for AuditPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            log_success: true,
+            log_failures: true,
+            log_denials: true,
+            log_sensitive_ops: true,
+            retention_days: 90,
+            real_time_alerts: false,
+            alert_thresholds: AlertThresholds::default(),
+        }
+    }
+}
+
 /// Alert thresholds for security events
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AlertThresholds {
     /// Failed login attempts threshold
     pub failed_logins: u32,
@@ -310,6 +330,17 @@ pub struct AlertThresholds {
     pub time_window_minutes: u32,
 }
 
+impl Default for AlertThresholds {
+    fn default() -> Self {
+        Self {
+            failed_logins: 10,
+            permission_denials: 20,
+            suspicious_score: 50,
+            time_window_minutes: 15,
+        }
+    }
+}
+
 impl SecurityPolicyConfig {
     /// Create secure production policy
     pub fn secure() -> Self {
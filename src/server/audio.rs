@@ -0,0 +1,360 @@
+//! Audio zones: playback status, favorites, queue, sync groups and announcements
+//!
+//! An `AudioZoneV2` of the Loxone Audio Server reports playback as numeric
+//! states (`playState`, `volume`, `shuffle`, ...) and the current track as text
+//! states. Favorites and the queue are read with the zone's `getroomfavs`,
+//! `getfavorites` and `getqueue` commands, which answer with JSON lists. Zones
+//! play in sync groups, addressed by the `playerid` in their details. These
+//! helpers translate between those values and what the audio tools accept and
+//! return.
+
+use super::climate::{compact, state_value};
+use crate::client::{ControlModel, ControlType, LoxoneControl};
+use crate::services::name_resolution::canonical;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+/// Audio zone states reported by the audio status
+pub const STATUS_STATES: &[&str] = &[
+    "power",
+    "playState",
+    "volume",
+    "shuffle",
+    "repeat",
+    "source",
+    "title",
+    "artist",
+    "album",
+    "coverurl",
+    "syncedZones",
+];
+
+/// Repeat modes of the `repeat` command and state, by ID
+pub const REPEAT_MODES: [&str; 3] = ["off", "queue", "track"];
+
+/// Resolve a repeat mode name (or ID) to its ID
+pub fn repeat_mode_id(mode: &str) -> Result<usize, String> {
+    let mode = match mode.trim().to_lowercase().as_str() {
+        "none" | "0" => "off",
+        "all" | "playlist" | "1" => "queue",
+        "one" | "song" | "title" | "2" => "track",
+        other => return mode_id(other),
+    };
+    mode_id(mode)
+}
+
+fn mode_id(mode: &str) -> Result<usize, String> {
+    REPEAT_MODES
+        .iter()
+        .position(|name| *name == mode)
+        .ok_or_else(|| {
+            format!(
+                "Invalid repeat mode '{mode}'. Use: {}",
+                REPEAT_MODES.join(", ")
+            )
+        })
+}
+
+/// Name of a `playState` value
+pub fn play_state_name(value: f64) -> &'static str {
+    match value as i64 {
+        0 => "stopped",
+        1 => "paused",
+        2 => "playing",
+        _ => "unknown",
+    }
+}
+
+/// Announcement command of an announcement kind
+///
+/// `tts` needs a text, the others play the Audio Server's built-in sounds.
+pub fn announcement_command(kind: &str) -> Result<&'static str, String> {
+    match kind.trim().to_lowercase().as_str() {
+        "doorbell" | "bell" => Ok("bell"),
+        "alarm" => Ok("alarm"),
+        "fire_alarm" | "firealarm" | "fire" => Ok("firealarm"),
+        "tts" | "speech" | "text" => Ok("tts"),
+        other => Err(format!(
+            "Invalid announcement '{other}'. Use: doorbell, alarm, fire_alarm, tts"
+        )),
+    }
+}
+
+/// Player ID of an audio zone, used to address it in sync groups
+pub fn player_id(control: &LoxoneControl) -> Option<i64> {
+    match control.details.get("playerid")? {
+        Value::Number(id) => id.as_i64(),
+        Value::String(id) => id.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Audio zones by player ID
+pub fn zones_by_player(model: &ControlModel) -> HashMap<i64, &LoxoneControl> {
+    model
+        .of_type(ControlType::is_audio)
+        .into_iter()
+        .filter_map(|control| Some((player_id(control)?, control)))
+        .collect()
+}
+
+/// Player IDs of a `syncedZones` value: a JSON array or a `,`/`|` separated list
+pub fn synced_players(value: &Value) -> Vec<i64> {
+    // State values arrive as floats
+    let id = |value: &Value| value.as_f64().map(|id| id as i64);
+    let ids: Vec<i64> = match value {
+        Value::Array(ids) => ids.iter().filter_map(id).collect(),
+        Value::Number(_) => id(value).into_iter().collect(),
+        Value::String(text) => match serde_json::from_str::<Value>(text) {
+            Ok(Value::Array(ids)) => ids.iter().filter_map(id).collect(),
+            _ => text
+                .split([',', '|'])
+                .filter_map(|id| id.trim().parse().ok())
+                .collect(),
+        },
+        _ => Vec::new(),
+    };
+    ids.into_iter().filter(|id| *id > 0).collect()
+}
+
+/// Typed playback status of an audio zone from its state values
+pub fn audio_status(
+    control: &LoxoneControl,
+    values: &HashMap<String, Value>,
+    zones: &HashMap<i64, &LoxoneControl>,
+) -> Value {
+    let state = |name: &str| state_value(control, name, values);
+    let number = |name: &str| state(name).as_ref().and_then(Value::as_f64);
+    let text = |name: &str| {
+        // Numeric-looking titles arrive parsed as numbers
+        state(name)
+            .and_then(|value| match value {
+                Value::String(text) => Some(text),
+                Value::Number(number) => Some(number.to_string()),
+                _ => None,
+            })
+            .filter(|text| !text.is_empty())
+    };
+
+    let mut status = Map::new();
+    let mut insert = |key: &str, value: Value| {
+        if !value.is_null() && value != json!({}) {
+            status.insert(key.to_string(), value);
+        }
+    };
+    insert("power", json!(number("power").map(|power| power > 0.0)));
+    insert(
+        "play_state",
+        json!(number("playState").map(play_state_name)),
+    );
+    insert("volume", json!(number("volume")));
+    insert(
+        "shuffle",
+        json!(number("shuffle").map(|shuffle| shuffle > 0.0)),
+    );
+    insert(
+        "repeat",
+        json!(
+            number("repeat")
+                .and_then(|mode| REPEAT_MODES.get(mode as usize))
+                .copied()
+        ),
+    );
+    insert("source", json!(number("source")));
+    insert(
+        "track",
+        compact(json!({
+            "title": text("title"),
+            "artist": text("artist"),
+            "album": text("album"),
+            "cover_url": text("coverurl"),
+        })),
+    );
+
+    let own_id = player_id(control);
+    let members: Vec<i64> = state("syncedZones")
+        .map(|value| synced_players(&value))
+        .unwrap_or_default();
+    if members.iter().any(|id| Some(*id) != own_id) {
+        let names: Vec<&str> = members
+            .iter()
+            .filter_map(|id| zones.get(id))
+            .map(|zone| zone.name.as_str())
+            .collect();
+        insert("group", json!({ "player_ids": members, "members": names }));
+    }
+    insert("player_id", json!(own_id));
+
+    Value::Object(status)
+}
+
+/// Items of a favorites or queue answer
+///
+/// The answer may be the list itself, a JSON string holding it, an object
+/// with `items`, or the Audio Server's `{"<command>_result": [{"items": ...}]}`
+/// envelope, optionally wrapped in an `LL` response.
+pub fn list_items(value: &Value) -> Vec<Value> {
+    let value = value.pointer("/LL/value").unwrap_or(value);
+    match value {
+        Value::Array(items) => match items.as_slice() {
+            [result] if result.get("items").is_some() => list_items(result),
+            _ => items.clone(),
+        },
+        Value::String(text) => serde_json::from_str::<Value>(text)
+            .map(|parsed| list_items(&parsed))
+            .unwrap_or_default(),
+        Value::Object(object) => match object.get("items") {
+            Some(items) => list_items(items),
+            None => object
+                .iter()
+                .find(|(key, _)| key.ends_with("_result"))
+                .map(|(_, result)| list_items(result))
+                .unwrap_or_default(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// ID of a favorite given by ID or by name
+///
+/// Names match after normalization, exactly first, then as a substring.
+pub fn find_favorite(items: &[Value], favorite: &str) -> Result<i64, String> {
+    if let Ok(id) = favorite.trim().parse::<i64>() {
+        return Ok(id);
+    }
+    let wanted = canonical(favorite);
+    let named = |item: &&Value| {
+        item.get("name")
+            .and_then(Value::as_str)
+            .map(canonical)
+            .unwrap_or_default()
+    };
+    let found = items
+        .iter()
+        .find(|item| named(item) == wanted)
+        .or_else(|| items.iter().find(|item| named(item).contains(&wanted)));
+    match found.and_then(|item| item.get("id").and_then(Value::as_i64)) {
+        Some(id) => Ok(id),
+        None => {
+            let names: Vec<&str> = items
+                .iter()
+                .filter_map(|item| item.get("name").and_then(Value::as_str))
+                .collect();
+            Err(format!(
+                "No favorite named '{favorite}'. Favorites: {}",
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                }
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::LoxoneStructure;
+
+    fn model() -> ControlModel {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {},
+            "cats": {},
+            "controls": {
+                "zone-1": {
+                    "name": "Kitchen Audio",
+                    "type": "AudioZoneV2",
+                    "details": { "playerid": 1 },
+                    "states": {
+                        "playState": "s-play", "volume": "s-volume", "repeat": "s-repeat",
+                        "shuffle": "s-shuffle", "title": "s-title", "artist": "s-artist",
+                        "coverurl": "s-cover", "syncedZones": "s-synced"
+                    }
+                },
+                "zone-2": { "name": "Patio Audio", "type": "AudioZoneV2", "details": { "playerid": "2" } }
+            }
+        }))
+        .unwrap();
+        ControlModel::from_structure(&structure)
+    }
+
+    #[test]
+    fn test_audio_status() {
+        let model = model();
+        let zones = zones_by_player(&model);
+        assert_eq!(zones.len(), 2);
+        let values: HashMap<String, Value> = [
+            ("s-play", json!("2")),
+            ("s-volume", json!(35)),
+            ("s-repeat", json!(1)),
+            ("s-shuffle", json!(0)),
+            ("s-title", json!({ "LL": { "value": "So What" } })),
+            ("s-artist", json!("Miles Davis")),
+            ("s-cover", json!("")),
+            ("s-synced", json!("1,2")),
+        ]
+        .into_iter()
+        .map(|(uuid, value)| (uuid.to_string(), value))
+        .collect();
+
+        let status = audio_status(zones[&1], &values, &zones);
+        assert_eq!(status["play_state"], "playing");
+        assert_eq!(status["volume"], 35.0);
+        assert_eq!(status["repeat"], "queue");
+        assert_eq!(status["shuffle"], false);
+        assert_eq!(
+            status["track"],
+            json!({ "title": "So What", "artist": "Miles Davis" })
+        );
+        assert_eq!(
+            status["group"]["members"],
+            json!(["Kitchen Audio", "Patio Audio"])
+        );
+        assert_eq!(status["player_id"], 1);
+
+        let idle = audio_status(zones[&2], &HashMap::new(), &zones);
+        assert_eq!(idle, json!({ "player_id": 2 }));
+    }
+
+    #[test]
+    fn test_list_items_envelopes() {
+        let items = json!([{ "id": 4, "name": "Radio Paradise" }, { "id": 7, "name": "Jazz" }]);
+        assert_eq!(list_items(&items).len(), 2);
+        assert_eq!(list_items(&json!({ "items": items })).len(), 2);
+        assert_eq!(
+            list_items(
+                &json!({ "getroomfavs_result": [{ "id": 1, "totalitems": 2, "items": items }] })
+            )
+            .len(),
+            2
+        );
+        let text = json!({ "LL": { "value": items.to_string() } });
+        assert_eq!(list_items(&text)[1]["name"], "Jazz");
+        assert!(list_items(&json!(1.0)).is_empty());
+
+        let favorites = list_items(&items);
+        assert_eq!(find_favorite(&favorites, "jazz").unwrap(), 7);
+        assert_eq!(find_favorite(&favorites, "paradise").unwrap(), 4);
+        assert_eq!(find_favorite(&favorites, "12").unwrap(), 12);
+        assert!(
+            find_favorite(&favorites, "Techno")
+                .unwrap_err()
+                .contains("Radio Paradise, Jazz")
+        );
+    }
+
+    #[test]
+    fn test_modes_and_announcements() {
+        assert_eq!(repeat_mode_id("all").unwrap(), 1);
+        assert_eq!(repeat_mode_id("Track").unwrap(), 2);
+        assert!(repeat_mode_id("sometimes").is_err());
+        assert_eq!(announcement_command("doorbell").unwrap(), "bell");
+        assert_eq!(announcement_command("Fire_Alarm").unwrap(), "firealarm");
+        assert!(announcement_command("siren").is_err());
+        assert_eq!(synced_players(&json!("[3, 4]")), vec![3, 4]);
+        assert_eq!(synced_players(&json!("0")), Vec::<i64>::new());
+        assert_eq!(synced_players(&json!(2.0)), vec![2]);
+    }
+}
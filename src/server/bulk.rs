@@ -0,0 +1,379 @@
+//! Bulk device actions
+//!
+//! A [`DeviceSelector`] picks controls from the structure by room, category,
+//! control type and name pattern; [`plan_action`] turns one action into a
+//! validated command per selected control. The `bulk_action` tool returns the
+//! plan as a dry run and only sends it when asked to execute.
+
+use crate::client::{ControlModel, ControlType, LoxoneControl};
+use crate::error::{LoxoneError, Result};
+use crate::services::name_resolution::ambiguity_message;
+use crate::services::{NameResolver, Resolution};
+use serde::Serialize;
+
+/// Which controls a bulk action applies to
+///
+/// Every given criterion must match; within a list any entry may match.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceSelector {
+    /// Room names
+    pub rooms: Vec<String>,
+    /// Category names
+    pub categories: Vec<String>,
+    /// Control type names (e.g. `Jalousie`) or groups (lights, blinds, climate, audio)
+    pub types: Vec<String>,
+    /// Name pattern: `*` and `?` wildcards, otherwise a substring
+    pub name: Option<String>,
+}
+
+impl DeviceSelector {
+    /// Whether no criterion is set
+    pub fn is_empty(&self) -> bool {
+        self.rooms.is_empty()
+            && self.categories.is_empty()
+            && self.types.is_empty()
+            && self.name.is_none()
+    }
+
+    /// Top-level controls matching the selector, sorted by name
+    ///
+    /// Room names resolve fuzzily through `names`. Unknown or ambiguous room
+    /// and unknown category names are errors rather than empty selections.
+    pub fn select<'a>(
+        &self,
+        model: &'a ControlModel,
+        names: &NameResolver,
+    ) -> Result<Vec<&'a LoxoneControl>> {
+        if self.is_empty() {
+            return Err(LoxoneError::invalid_input(
+                "Select devices by rooms, categories, types or name",
+            ));
+        }
+        let rooms = self
+            .rooms
+            .iter()
+            .map(|name| match names.resolve_room(model, name) {
+                Resolution::Found(uuid) => Ok(uuid),
+                Resolution::Ambiguous(candidates) => Err(LoxoneError::invalid_input(
+                    ambiguity_message("Room", name, &candidates),
+                )),
+                Resolution::NotFound => {
+                    Err(LoxoneError::not_found(format!("Room '{name}' not found")))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let categories = self
+            .categories
+            .iter()
+            .map(|name| {
+                model
+                    .resolve_category(name)
+                    .ok_or_else(|| LoxoneError::not_found(format!("Category '{name}' not found")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut controls = model.filter(|control| {
+            (rooms.is_empty()
+                || control
+                    .room
+                    .as_deref()
+                    .is_some_and(|room| rooms.contains(&room)))
+                && (categories.is_empty()
+                    || control
+                        .category
+                        .as_deref()
+                        .is_some_and(|category| categories.contains(&category)))
+                && (self.types.is_empty()
+                    || self
+                        .types
+                        .iter()
+                        .any(|name| type_matches(&control.control_type, name)))
+                && self
+                    .name
+                    .as_deref()
+                    .is_none_or(|pattern| name_matches(&control.name, pattern))
+        });
+        controls.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.uuid.cmp(&b.uuid)));
+        Ok(controls)
+    }
+}
+
+/// Match a control type by name or type group, case-insensitively
+fn type_matches(control_type: &ControlType, name: &str) -> bool {
+    let group: Option<fn(&ControlType) -> bool> = match name.to_lowercase().as_str() {
+        "lights" | "lighting" => Some(ControlType::is_lighting),
+        "blinds" | "shading" => Some(ControlType::is_blind),
+        "climate" => Some(ControlType::is_room_controller),
+        "audio" => Some(ControlType::is_audio),
+        _ => None,
+    };
+    match group {
+        Some(predicate) => predicate(control_type),
+        None => control_type.as_str().eq_ignore_ascii_case(name),
+    }
+}
+
+/// Match a name against a wildcard pattern, or a substring without wildcards
+pub fn name_matches(name: &str, pattern: &str) -> bool {
+    let (name, pattern) = (name.to_lowercase(), pattern.to_lowercase());
+    if !pattern.contains(['*', '?']) {
+        return name.contains(&pattern);
+    }
+    let name: Vec<char> = name.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+
+    // Greedy wildcard match, backtracking to the last `*`
+    let (mut n, mut p) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                n += 1;
+                p += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Command planned for one selected control
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedCommand {
+    /// Control UUID
+    pub uuid: String,
+    /// Control name
+    pub name: String,
+    /// Control type
+    #[serde(rename = "type")]
+    pub control_type: String,
+    /// Room name
+    pub room: Option<String>,
+    /// Validated command; `None` if the control is skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Why no command is sent to the control
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+/// Plan the command of `action` with `args` for every control
+///
+/// A numeric action without arguments is sent as a bare value (e.g. a dimmer
+/// level). Controls are skipped if the command is not in their type's catalog,
+/// their type has no catalog, or they are security devices, which only the
+/// dedicated tools control after asking the user.
+pub fn plan_action(
+    model: &ControlModel,
+    controls: &[&LoxoneControl],
+    action: &str,
+    args: &[String],
+) -> Vec<PlannedCommand> {
+    controls
+        .iter()
+        .map(|control| {
+            let command = if control.control_type.is_security() {
+                Err(format!(
+                    "{} is a security device; use its dedicated tool",
+                    control.type_name()
+                ))
+            } else if control.commands().is_none() {
+                Err(format!("No command catalog for {}", control.type_name()))
+            } else {
+                build_command(control, action, args).map_err(|e| e.to_string())
+            };
+            let (command, skipped) = match command {
+                Ok(command) => (Some(command), None),
+                Err(reason) => (None, Some(reason)),
+            };
+            PlannedCommand {
+                uuid: control.uuid.clone(),
+                name: control.name.clone(),
+                control_type: control.type_name().to_string(),
+                room: control
+                    .room
+                    .as_deref()
+                    .and_then(|room| model.room_name(room))
+                    .map(str::to_string),
+                command,
+                skipped,
+            }
+        })
+        .collect()
+}
+
+/// Build and validate the command of an action for one control
+fn build_command(control: &LoxoneControl, action: &str, args: &[String]) -> Result<String> {
+    match action.parse::<f64>() {
+        Ok(value) if args.is_empty() => control.build_value_command(value),
+        _ => {
+            let args: Vec<&dyn std::fmt::Display> = args
+                .iter()
+                .map(|arg| arg as &dyn std::fmt::Display)
+                .collect();
+            control.build_command(action, &args)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::LoxoneStructure;
+    use serde_json::json;
+
+    fn model() -> ControlModel {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {
+                "room-1": { "name": "Living Room" },
+                "room-2": { "name": "Kitchen" }
+            },
+            "cats": {
+                "cat-1": { "name": "Lighting" },
+                "cat-2": { "name": "Shading" }
+            },
+            "controls": {
+                "dim-1": { "name": "Ceiling Light", "type": "Dimmer", "room": "room-1", "cat": "cat-1" },
+                "sw-1": { "name": "Floor Lamp", "type": "Switch", "room": "room-1", "cat": "cat-1" },
+                "dim-2": { "name": "Kitchen Light", "type": "Dimmer", "room": "room-2", "cat": "cat-1" },
+                "blind-1": { "name": "Living Room Blinds", "type": "Jalousie", "room": "room-1", "cat": "cat-2" },
+                "gate-1": { "name": "Garage Gate", "type": "Gate", "room": "room-2" },
+                "cam-1": { "name": "Door Camera", "type": "IPCamera", "room": "room-2" },
+                "ic-1": { "name": "Front Intercom", "type": "Intercom", "room": "room-2" },
+                "ic-2": { "name": "Back Intercom", "type": "IntercomV2", "room": "room-2" }
+            }
+        }))
+        .unwrap();
+        ControlModel::from_structure(&structure)
+    }
+
+    fn names(controls: &[&LoxoneControl]) -> Vec<String> {
+        controls
+            .iter()
+            .map(|control| control.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_selector_criteria() {
+        let model = model();
+        let select = |selector: DeviceSelector| {
+            names(&selector.select(&model, &NameResolver::default()).unwrap())
+        };
+
+        let living_room_lights = DeviceSelector {
+            rooms: vec!["living".to_string()],
+            types: vec!["lights".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(select(living_room_lights), ["Ceiling Light", "Floor Lamp"]);
+
+        let shading = DeviceSelector {
+            categories: vec!["shading".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(select(shading), ["Living Room Blinds"]);
+
+        let dimmers = DeviceSelector {
+            types: vec!["dimmer".to_string()],
+            name: Some("*light".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(select(dimmers), ["Ceiling Light", "Kitchen Light"]);
+
+        assert!(
+            DeviceSelector::default()
+                .select(&model, &NameResolver::default())
+                .is_err()
+        );
+        let unknown_room = DeviceSelector {
+            rooms: vec!["Attic".to_string()],
+            ..Default::default()
+        };
+        assert!(
+            unknown_room
+                .select(&model, &NameResolver::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_name_patterns() {
+        assert!(name_matches("Kitchen Light", "light"));
+        assert!(name_matches("Kitchen Light", "k*light"));
+        assert!(name_matches("Kitchen Light", "Kitchen?Light"));
+        assert!(name_matches("Kitchen Light", "*"));
+        assert!(!name_matches("Kitchen Light", "k*lamp"));
+        assert!(!name_matches("Kitchen Light", "light*kitchen"));
+        assert!(name_matches("Kitchen Light", "kitchen*"));
+    }
+
+    #[test]
+    fn test_plan_validates_and_skips() {
+        let model = model();
+        let controls = DeviceSelector {
+            rooms: vec!["Kitchen".to_string()],
+            ..Default::default()
+        }
+        .select(&model, &NameResolver::default())
+        .unwrap();
+
+        let plan = plan_action(&model, &controls, "off", &[]);
+        let by_name = |name: &str| plan.iter().find(|p| p.name == name).unwrap();
+        assert_eq!(by_name("Kitchen Light").command.as_deref(), Some("off"));
+        assert_eq!(by_name("Kitchen Light").room.as_deref(), Some("Kitchen"));
+        assert!(
+            by_name("Garage Gate")
+                .skipped
+                .as_ref()
+                .unwrap()
+                .contains("security")
+        );
+        // Intercoms open doors, so bulk actions never reach them
+        let open = plan_action(&model, &controls, "open", &[]);
+        for intercom in ["Front Intercom", "Back Intercom"] {
+            let planned = open.iter().find(|p| p.name == intercom).unwrap();
+            assert!(planned.command.is_none());
+            assert!(planned.skipped.as_ref().unwrap().contains("security"));
+        }
+        assert!(
+            by_name("Door Camera")
+                .skipped
+                .as_ref()
+                .unwrap()
+                .contains("catalog")
+        );
+
+        let blinds = DeviceSelector {
+            types: vec!["blinds".to_string()],
+            ..Default::default()
+        }
+        .select(&model, &NameResolver::default())
+        .unwrap();
+        let plan = plan_action(&model, &blinds, "manualposition", &["40".to_string()]);
+        assert_eq!(plan[0].command.as_deref(), Some("ManualPosition/40"));
+        let plan = plan_action(&model, &blinds, "ManualPosition", &["140".to_string()]);
+        assert!(plan[0].command.is_none());
+
+        let dimmers = DeviceSelector {
+            types: vec!["Dimmer".to_string()],
+            ..Default::default()
+        }
+        .select(&model, &NameResolver::default())
+        .unwrap();
+        let plan = plan_action(&model, &dimmers, "40", &[]);
+        assert!(plan.iter().all(|p| p.command.as_deref() == Some("40")));
+    }
+}
@@ -0,0 +1,498 @@
+//! Room climate: operating modes, comfort settings and schedules
+//!
+//! An `IRoomControllerV2` reports its operating mode, active temperature mode
+//! and comfort settings as numeric states and keeps its weekly schedule in an
+//! `IRCV2Daytimer` sub-control, whose entries arrive as a daytimer event table
+//! over the WebSocket. These helpers translate between those values and the
+//! names the climate tools accept and return.
+
+use crate::client::binary_protocol::{DaytimerEntry, DaytimerEvent, LOXONE_EPOCH_UNIX};
+use crate::client::{ControlModel, ControlType, LoxoneControl};
+use crate::services::name_resolution::canonical;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, HashMap};
+
+/// Operating modes of `setOperatingMode` and the `operatingMode` state, by ID
+pub const OPERATING_MODES: [&str; 6] = [
+    "auto",
+    "auto_heating",
+    "auto_cooling",
+    "manual",
+    "manual_heating",
+    "manual_cooling",
+];
+
+/// Temperature modes of the `activeMode` state, overrides and schedules, by ID
+pub const TEMPERATURE_MODES: [&str; 4] = ["eco", "comfort", "building_protection", "manual"];
+
+/// Room controller states reported by the climate status
+pub const STATUS_STATES: &[&str] = &[
+    "tempActual",
+    "tempTarget",
+    "comfortTemperature",
+    "comfortTemperatureCool",
+    "comfortTolerance",
+    "absentMinOffset",
+    "absentMaxOffset",
+    "frostProtectTemperature",
+    "heatProtectTemperature",
+    "operatingMode",
+    "activeMode",
+    "openWindow",
+    "overrideEntries",
+];
+
+/// State of a climate controller telling whether the building heats or cools
+pub const PERIOD_STATE: &str = "currentMode";
+
+/// Daytimer state carrying the schedule entries
+pub const SCHEDULE_STATE: &str = "entriesAndDefaultValue";
+
+/// Minutes in a day; schedule entries end at most at midnight
+const MINUTES_PER_DAY: i32 = 24 * 60;
+
+/// Resolve an operating mode name (or ID) to its ID
+///
+/// `heat` and `cool` stand for automatic heating or cooling only, as in
+/// `set_temperature`.
+pub fn operating_mode_id(mode: &str) -> Result<usize, String> {
+    let alias = match mode.trim().to_lowercase().as_str() {
+        "automatic" => "auto",
+        "heat" | "heating" => "auto_heating",
+        "cool" | "cooling" => "auto_cooling",
+        other => return mode_id(&OPERATING_MODES, other, "operating mode"),
+    };
+    mode_id(&OPERATING_MODES, alias, "operating mode")
+}
+
+/// Resolve a temperature mode name (or ID) to its ID
+pub fn temperature_mode_id(mode: &str) -> Result<usize, String> {
+    let alias = match mode.trim().to_lowercase().as_str() {
+        "economy" | "absent" => "eco",
+        "protection" | "frost_protection" => "building_protection",
+        other => return mode_id(&TEMPERATURE_MODES, other, "temperature mode"),
+    };
+    mode_id(&TEMPERATURE_MODES, alias, "temperature mode")
+}
+
+fn mode_id(names: &[&str], mode: &str, kind: &str) -> Result<usize, String> {
+    if let Ok(id) = mode.parse::<usize>()
+        && id < names.len()
+    {
+        return Ok(id);
+    }
+    let mode = mode.replace([' ', '-'], "_");
+    names
+        .iter()
+        .position(|name| *name == mode)
+        .ok_or_else(|| format!("Invalid {kind} '{mode}'. Use: {}", names.join(", ")))
+}
+
+/// Name of a mode ID reported by a state, or the raw value if unknown
+fn mode_name(names: &[&str], value: Option<&Value>) -> Value {
+    let Some(value) = value else {
+        return Value::Null;
+    };
+    value
+        .as_f64()
+        .filter(|id| id.fract() == 0.0 && *id >= 0.0)
+        .and_then(|id| names.get(id as usize))
+        .map_or_else(|| value.clone(), |name| json!(name))
+}
+
+/// Structured climate settings of a room controller from its state values
+///
+/// `values` maps state UUIDs to their current values; absent states are left
+/// out. Eco temperatures are derived from the comfort temperatures and the
+/// absent offsets.
+pub fn climate_status(control: &LoxoneControl, values: &HashMap<String, Value>) -> Value {
+    let state = |name: &str| state_value(control, name, values);
+    let number = |name: &str| state(name).as_ref().and_then(Value::as_f64);
+
+    let mut status = Map::new();
+    let mut insert = |key: &str, value: Value| {
+        if !value.is_null() && value != json!({}) {
+            status.insert(key.to_string(), value);
+        }
+    };
+    insert(
+        "temperature",
+        compact(json!({
+            "actual": number("tempActual"),
+            "target": number("tempTarget"),
+        })),
+    );
+    insert(
+        "comfort",
+        compact(json!({
+            "heating": number("comfortTemperature"),
+            "cooling": number("comfortTemperatureCool"),
+            "tolerance": number("comfortTolerance"),
+        })),
+    );
+    let eco_heating = number("comfortTemperature")
+        .zip(number("absentMinOffset"))
+        .map(|(comfort, offset)| comfort - offset);
+    let eco_cooling = number("comfortTemperatureCool")
+        .or(number("comfortTemperature"))
+        .zip(number("absentMaxOffset"))
+        .map(|(comfort, offset)| comfort + offset);
+    insert(
+        "eco",
+        compact(json!({ "heating": eco_heating, "cooling": eco_cooling })),
+    );
+    insert(
+        "protection",
+        compact(json!({
+            "frost": number("frostProtectTemperature"),
+            "heat": number("heatProtectTemperature"),
+        })),
+    );
+    insert(
+        "operating_mode",
+        mode_name(&OPERATING_MODES, state("operatingMode").as_ref()),
+    );
+    insert(
+        "active_mode",
+        mode_name(&TEMPERATURE_MODES, state("activeMode").as_ref()),
+    );
+    insert(
+        "open_window",
+        number("openWindow").map_or(Value::Null, |open| json!(open != 0.0)),
+    );
+    // Overrides are a JSON text state
+    insert(
+        "overrides",
+        state("overrideEntries").map_or(Value::Null, |value| match value.as_str() {
+            Some(text) => serde_json::from_str(text).unwrap_or_else(|_| json!(text)),
+            None => value,
+        }),
+    );
+    if let Some(timer) = schedule_timer(control) {
+        insert("schedule_uuid", json!(timer.uuid));
+    }
+    Value::Object(status)
+}
+
+/// Whether a climate controller is heating or cooling the building
+pub fn heating_period(control: &LoxoneControl, values: &HashMap<String, Value>) -> Value {
+    let value = state_value(control, PERIOD_STATE, values);
+    match value.as_ref().and_then(Value::as_f64) {
+        Some(0.0) => json!("none"),
+        Some(1.0) => json!("heating"),
+        Some(2.0) => json!("cooling"),
+        _ => value.unwrap_or(Value::Null),
+    }
+}
+
+/// Value of a named state, unwrapped from an `LL` response and parsed if numeric
+pub(super) fn state_value(
+    control: &LoxoneControl,
+    name: &str,
+    values: &HashMap<String, Value>,
+) -> Option<Value> {
+    let value = values.get(control.state_uuid(name)?)?;
+    let value = value.pointer("/LL/value").unwrap_or(value);
+    match value
+        .as_str()
+        .map(str::trim)
+        .and_then(|text| text.parse::<f64>().ok())
+    {
+        Some(number) => Some(json!(number)),
+        None => Some(value.clone()),
+    }
+}
+
+/// Drop null fields of an object
+pub(super) fn compact(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        object.retain(|_, field| !field.is_null());
+    }
+    value
+}
+
+/// The schedule sub-control of a room controller
+pub fn schedule_timer(control: &LoxoneControl) -> Option<&LoxoneControl> {
+    control.sub_controls.iter().find(|sub| {
+        matches!(
+            sub.control_type,
+            ControlType::IRCV2Daytimer | ControlType::IRCDaytimer
+        )
+    })
+}
+
+/// Schedule from a daytimer state, pushed as an event or read as an `LL` response
+pub fn daytimer_event(value: Value) -> Option<DaytimerEvent> {
+    let value = match value.pointer("/LL/value") {
+        Some(inner) => inner.clone(),
+        None => value,
+    };
+    match value {
+        Value::String(text) => serde_json::from_str(&text).ok(),
+        value => serde_json::from_value(value).ok(),
+    }
+}
+
+/// Loxone time (seconds since 2009-01-01) `minutes` from now, for overrides
+pub fn override_until(minutes: u32) -> i64 {
+    chrono::Utc::now().timestamp() - LOXONE_EPOCH_UNIX + i64::from(minutes) * 60
+}
+
+/// Schedule entry as reported by `get_climate_schedule`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScheduleEntry {
+    /// Calendar mode name (e.g. a weekday), or its ID if unnamed
+    pub day: String,
+    /// Calendar mode ID
+    pub day_id: i32,
+    /// Start time (`HH:MM`)
+    pub from: String,
+    /// End time (`HH:MM`)
+    pub to: String,
+    /// Temperature mode while the entry is active
+    pub mode: Value,
+    /// Entry only applies after an activation (e.g. presence)
+    pub need_activate: bool,
+}
+
+/// Readable entries of a schedule, sorted by day and start time
+pub fn schedule_entries(
+    event: &DaytimerEvent,
+    calendar: &BTreeMap<i32, String>,
+) -> Vec<ScheduleEntry> {
+    let mut entries: Vec<ScheduleEntry> = event
+        .entries
+        .iter()
+        .map(|entry| ScheduleEntry {
+            day: calendar
+                .get(&entry.mode)
+                .cloned()
+                .unwrap_or_else(|| entry.mode.to_string()),
+            day_id: entry.mode,
+            from: format_minutes(entry.from),
+            to: format_minutes(entry.to),
+            mode: mode_name(&TEMPERATURE_MODES, Some(&json!(entry.value))),
+            need_activate: entry.need_activate,
+        })
+        .collect();
+    entries.sort_by(|a, b| a.day_id.cmp(&b.day_id).then_with(|| a.from.cmp(&b.from)));
+    entries
+}
+
+/// Parse an entry like `Monday 06:00-22:00 comfort`
+///
+/// The day is a calendar mode name in German or English, or its ID; the
+/// temperature mode defaults to comfort. A trailing `on_presence` makes the
+/// entry wait for an activation.
+pub fn parse_schedule_entry(
+    text: &str,
+    calendar: &BTreeMap<i32, String>,
+) -> Result<DaytimerEntry, String> {
+    let invalid = |reason: &str| {
+        format!("Invalid schedule entry '{text}': {reason}. Use e.g. 'Monday 06:00-22:00 comfort'")
+    };
+    let mut words = text.split_whitespace();
+    let (Some(day), Some(times)) = (words.next(), words.next()) else {
+        return Err(invalid("expected a day and a time range"));
+    };
+    let day_id = day
+        .parse::<i32>()
+        .ok()
+        .filter(|id| calendar.is_empty() || calendar.contains_key(id))
+        .or_else(|| {
+            let day = canonical(day);
+            calendar
+                .iter()
+                .find(|(_, name)| canonical(name) == day)
+                .map(|(id, _)| *id)
+        })
+        .ok_or_else(|| {
+            let days: Vec<&str> = calendar.values().map(String::as_str).collect();
+            invalid(&format!("unknown day '{day}' (days: {})", days.join(", ")))
+        })?;
+
+    let (from, to) = times
+        .split_once('-')
+        .ok_or_else(|| invalid("expected a time range like 06:00-22:00"))?;
+    let (from, to) = (
+        parse_time(from).ok_or_else(|| invalid("invalid start time"))?,
+        parse_time(to).ok_or_else(|| invalid("invalid end time"))?,
+    );
+    if from >= to {
+        return Err(invalid("the start must be before the end"));
+    }
+
+    let mut mode = TEMPERATURE_MODES[1];
+    let mut need_activate = false;
+    for word in words {
+        match word.to_lowercase().as_str() {
+            "on_presence" | "presence" => need_activate = true,
+            _ => mode = word,
+        }
+    }
+    let value = temperature_mode_id(mode).map_err(|e| invalid(&e))?;
+
+    Ok(DaytimerEntry {
+        mode: day_id,
+        from,
+        to,
+        need_activate,
+        value: value as f64,
+    })
+}
+
+/// Daytimer command replacing the whole schedule with `entries`
+pub fn schedule_command(entries: &[DaytimerEntry]) -> String {
+    let mut command = format!("set/{}", entries.len());
+    for entry in entries {
+        command.push_str(&format!(
+            "/{};{};{};{};{}",
+            entry.mode,
+            entry.from,
+            entry.to,
+            u8::from(entry.need_activate),
+            entry.value
+        ));
+    }
+    command
+}
+
+/// Minutes since midnight of `HH:MM`; `24:00` is the end of the day
+fn parse_time(time: &str) -> Option<i32> {
+    let (hours, minutes) = time.split_once(':')?;
+    let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    let total = hours * 60 + minutes;
+    ((0..60).contains(&minutes) && (0..=MINUTES_PER_DAY).contains(&total)).then_some(total)
+}
+
+fn format_minutes(minutes: i32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Room controllers of a model that support modes, overrides and schedules
+pub fn is_v2_controller(control: &LoxoneControl) -> bool {
+    control.control_type == ControlType::IRoomControllerV2
+}
+
+/// Climate controllers of the building, reporting the heating/cooling period
+pub fn climate_controllers(model: &ControlModel) -> Vec<&LoxoneControl> {
+    model.of_type(|control_type| *control_type == ControlType::ClimateController)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar() -> BTreeMap<i32, String> {
+        BTreeMap::from([
+            (3, "Montag".to_string()),
+            (4, "Dienstag".to_string()),
+            (9, "Sonntag".to_string()),
+        ])
+    }
+
+    fn controller() -> LoxoneControl {
+        LoxoneControl::from_value(
+            "irc-1",
+            &json!({
+                "name": "Climate",
+                "type": "IRoomControllerV2",
+                "states": {
+                    "tempActual": "s-actual",
+                    "comfortTemperature": "s-comfort",
+                    "comfortTemperatureCool": "s-cool",
+                    "absentMinOffset": "s-min",
+                    "absentMaxOffset": "s-max",
+                    "operatingMode": "s-op",
+                    "activeMode": "s-active",
+                    "openWindow": "s-window",
+                    "overrideEntries": "s-override"
+                },
+                "subControls": {
+                    "timer-1": { "name": "Schedule", "type": "IRCV2Daytimer" }
+                }
+            }),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_mode_names() {
+        assert_eq!(operating_mode_id("auto"), Ok(0));
+        assert_eq!(operating_mode_id("heat"), Ok(1));
+        assert_eq!(operating_mode_id("Manual Cooling"), Ok(5));
+        assert_eq!(operating_mode_id("3"), Ok(3));
+        assert!(operating_mode_id("6").is_err());
+        assert_eq!(temperature_mode_id("comfort"), Ok(1));
+        assert_eq!(temperature_mode_id("economy"), Ok(0));
+        assert!(temperature_mode_id("boost").is_err());
+    }
+
+    #[test]
+    fn test_climate_status() {
+        let values = HashMap::from([
+            ("s-actual".to_string(), json!(21.5)),
+            ("s-comfort".to_string(), json!(22.0)),
+            ("s-cool".to_string(), json!(24.0)),
+            ("s-min".to_string(), json!(3.0)),
+            ("s-max".to_string(), json!(2.0)),
+            (
+                "s-op".to_string(),
+                json!({ "LL": { "value": "1", "Code": "200" } }),
+            ),
+            ("s-active".to_string(), json!(7.0)),
+            ("s-window".to_string(), json!(1.0)),
+            ("s-override".to_string(), json!("[{\"reason\":4}]")),
+        ]);
+        let status = climate_status(&controller(), &values);
+        assert_eq!(status["temperature"], json!({ "actual": 21.5 }));
+        assert_eq!(status["comfort"]["cooling"], 24.0);
+        assert_eq!(status["eco"], json!({ "heating": 19.0, "cooling": 26.0 }));
+        assert!(status.get("protection").is_none());
+        assert_eq!(status["operating_mode"], "auto_heating");
+        assert_eq!(status["active_mode"], 7.0);
+        assert_eq!(status["open_window"], true);
+        assert_eq!(status["overrides"][0]["reason"], 4);
+        assert_eq!(status["schedule_uuid"], "timer-1");
+    }
+
+    #[test]
+    fn test_schedule_round_trip() {
+        let calendar = calendar();
+        let entries: Vec<DaytimerEntry> =
+            ["Monday 06:00-22:00", "Sonntag 08:30-24:00 eco on_presence"]
+                .iter()
+                .map(|text| parse_schedule_entry(text, &calendar).unwrap())
+                .collect();
+        assert_eq!(
+            schedule_command(&entries),
+            "set/2/3;360;1320;0;1/9;510;1440;1;0"
+        );
+
+        let event = DaytimerEvent {
+            uuid: "timer-1-entries".to_string(),
+            default_value: 0.0,
+            entries,
+        };
+        let readable = schedule_entries(&event, &calendar);
+        assert_eq!(readable[0].day, "Montag");
+        assert_eq!(readable[0].from, "06:00");
+        assert_eq!(readable[0].mode, "comfort");
+        assert_eq!(readable[1].to, "24:00");
+        assert!(readable[1].need_activate);
+
+        for invalid in [
+            "Monday",
+            "Funday 06:00-22:00",
+            "Monday 22:00-06:00",
+            "Monday 06:00-25:00",
+            "Monday 06:00-22:00 boost",
+        ] {
+            assert!(
+                parse_schedule_entry(invalid, &calendar).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+}
@@ -0,0 +1,469 @@
+//! Approval of sensitive tools
+//!
+//! Unlocking doors, disarming the alarm and opening gates or doors from an
+//! intercom ask the user first, whichever tool sends the command. The MCP
+//! client of the running tool call is asked with an elicitation; clients that
+//! cannot answer one fall back to the configured approval webhook. Without
+//! either, the operation is denied.
+
+use crate::client::{ControlType, LoxoneControl};
+use crate::config::ConsentSettings;
+use crate::error::{LoxoneError, Result};
+use crate::mcp_consent::{
+    ConsentApprover, ConsentConfig, ConsentManager, ConsentRequest, ConsentResponse,
+};
+use async_trait::async_trait;
+use pulseengine_mcp_server::{
+    ElicitationAction, ElicitationRequest, ElicitationResult, ToolContextError, try_current_context,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+use url::Url;
+
+/// Consent manager for the tools, configured from the server settings
+pub fn consent_manager(settings: &ConsentSettings) -> ConsentManager {
+    let config = ConsentConfig {
+        enabled: settings.enabled,
+        default_timeout: settings.timeout,
+        consent_cache_duration: settings.remember_for,
+        ..ConsentConfig::default()
+    };
+    let webhook = settings
+        .webhook_url
+        .clone()
+        .map(|url| Arc::new(WebhookApprover::new(url)) as Arc<dyn ConsentApprover>);
+    ConsentManager::with_config(config).with_approver(Arc::new(ElicitationApprover::new(webhook)))
+}
+
+/// Consent action of a command that disarms, unlocks or opens a security device
+///
+/// `None` for commands that can be sent without asking, such as arming the
+/// alarm or locking a door.
+pub fn security_action(control: &LoxoneControl, command: &str) -> Option<&'static str> {
+    let control_type = &control.control_type;
+    if !control_type.is_security() {
+        return None;
+    }
+    let name = command.split('/').next().unwrap_or(command);
+    match control_type {
+        _ if control_type.is_alarm() => name.eq_ignore_ascii_case("off").then_some("disarm"),
+        ControlType::Other(lock) if lock == "DoorLock" => ["off", "unlock", "open"]
+            .iter()
+            .any(|unlock| name.eq_ignore_ascii_case(unlock))
+            .then_some("unlock"),
+        ControlType::Gate
+        | ControlType::CentralGate
+        | ControlType::Intercom
+        | ControlType::IntercomV2 => name.eq_ignore_ascii_case("open").then_some("open_door"),
+        _ => None,
+    }
+}
+
+/// Asks the MCP client of the running tool call (`elicitation/create`)
+pub struct ElicitationApprover {
+    /// Used when the client cannot be asked
+    fallback: Option<Arc<dyn ConsentApprover>>,
+}
+
+impl ElicitationApprover {
+    /// Elicitation with an optional fallback channel
+    pub fn new(fallback: Option<Arc<dyn ConsentApprover>>) -> Self {
+        Self { fallback }
+    }
+}
+
+#[async_trait]
+impl ConsentApprover for ElicitationApprover {
+    async fn request_approval(
+        &self,
+        request: &ConsentRequest,
+        timeout: Duration,
+    ) -> Result<Option<ConsentResponse>> {
+        let elicited = match try_current_context() {
+            Some(context) => {
+                context
+                    .request_elicitation(elicitation_request(request), timeout)
+                    .await
+            }
+            None => Err(ToolContextError::NotAvailable),
+        };
+
+        match elicited {
+            Ok(result) => Ok(Some(elicitation_response(request, result))),
+            Err(ToolContextError::Timeout) => Ok(None),
+            Err(ToolContextError::Declined(reason)) => Ok(Some(ConsentResponse {
+                reason: Some(reason),
+                ..ConsentResponse::new(request.id, false)
+            })),
+            Err(e) => match &self.fallback {
+                Some(fallback) => {
+                    debug!("Cannot ask the MCP client ({e}), using the fallback approver");
+                    fallback.request_approval(request, timeout).await
+                }
+                None => Err(LoxoneError::config(format!(
+                    "No approval channel ({e}); use an MCP client with elicitation \
+                     support or configure mcp.consent.webhook_url"
+                ))),
+            },
+        }
+    }
+}
+
+/// Approve/remember form shown to the user
+fn elicitation_request(request: &ConsentRequest) -> ElicitationRequest {
+    let message = format!(
+        "{}\n{}\nRisks: {}",
+        request.description,
+        request.details,
+        request.risks.join("; ")
+    );
+    ElicitationRequest::with_schema(
+        message,
+        json!({
+            "type": "object",
+            "properties": {
+                "approve": {
+                    "type": "boolean",
+                    "title": "Approve",
+                    "description": "Allow this operation"
+                },
+                "remember": {
+                    "type": "boolean",
+                    "title": "Remember",
+                    "description": "Allow this action on the same devices again without asking for a while",
+                    "default": false
+                }
+            },
+            "required": ["approve"]
+        }),
+    )
+}
+
+/// Consent response for the user's answer to the form
+fn elicitation_response(request: &ConsentRequest, result: ElicitationResult) -> ConsentResponse {
+    let field = |name: &str| {
+        result
+            .content
+            .as_ref()
+            .and_then(|content| content.get(name))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+    };
+    let (approved, reason) = match result.action {
+        ElicitationAction::Accept if field("approve") => (true, None),
+        ElicitationAction::Accept => (false, Some("Not approved by user")),
+        ElicitationAction::Decline => (false, Some("Declined by user")),
+        ElicitationAction::Cancel => (false, Some("Cancelled by user")),
+    };
+    ConsentResponse {
+        reason: reason.map(str::to_string),
+        apply_to_similar: approved && field("remember"),
+        user_id: Some("mcp-client".to_string()),
+        ..ConsentResponse::new(request.id, approved)
+    }
+}
+
+/// Posts consent requests to an approval webhook
+///
+/// The webhook receives the [`ConsentRequest`] as JSON and answers, once a
+/// person decided, with `{"approved": bool, "reason"?, "remember"?, "user"?}`.
+pub struct WebhookApprover {
+    client: reqwest::Client,
+    url: Url,
+}
+
+/// Answer of the approval webhook
+#[derive(Debug, Deserialize)]
+struct WebhookAnswer {
+    approved: bool,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    remember: bool,
+    #[serde(default)]
+    user: Option<String>,
+}
+
+impl WebhookApprover {
+    /// Approver posting to the given URL
+    pub fn new(url: Url) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+}
+
+#[async_trait]
+impl ConsentApprover for WebhookApprover {
+    async fn request_approval(
+        &self,
+        request: &ConsentRequest,
+        timeout: Duration,
+    ) -> Result<Option<ConsentResponse>> {
+        let response = match self
+            .client
+            .post(self.url.clone())
+            .timeout(timeout)
+            .json(request)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => return Ok(None),
+            Err(e) => {
+                return Err(LoxoneError::connection(format!(
+                    "Approval webhook failed: {e}"
+                )));
+            }
+        };
+
+        let answer: WebhookAnswer = response
+            .error_for_status()
+            .map_err(|e| LoxoneError::connection(format!("Approval webhook failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| {
+                LoxoneError::connection(format!("Invalid approval webhook answer: {e}"))
+            })?;
+
+        Ok(Some(ConsentResponse {
+            reason: answer.reason,
+            apply_to_similar: answer.approved && answer.remember,
+            user_id: answer.user,
+            ..ConsentResponse::new(request.id, answer.approved)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_consent::ConsentDecision;
+    use pulseengine_mcp_protocol::LogLevel;
+    use pulseengine_mcp_server::{
+        CreateMessageRequest, CreateMessageResult, ToolContext, with_context,
+    };
+    use serde_json::Value;
+
+    /// Tool context answering elicitations with a fixed result
+    struct ElicitingContext(std::result::Result<ElicitationResult, ToolContextError>);
+
+    #[async_trait]
+    impl ToolContext for ElicitingContext {
+        async fn send_log(
+            &self,
+            _level: LogLevel,
+            _logger: Option<&str>,
+            _data: Value,
+        ) -> std::result::Result<(), ToolContextError> {
+            Ok(())
+        }
+
+        async fn send_progress(
+            &self,
+            _progress: u64,
+            _total: Option<u64>,
+        ) -> std::result::Result<(), ToolContextError> {
+            Ok(())
+        }
+
+        async fn send_progress_with_message(
+            &self,
+            _progress: u64,
+            _total: Option<u64>,
+            _message: String,
+        ) -> std::result::Result<(), ToolContextError> {
+            Ok(())
+        }
+
+        async fn request_sampling(
+            &self,
+            _request: CreateMessageRequest,
+            _timeout: Duration,
+        ) -> std::result::Result<CreateMessageResult, ToolContextError> {
+            Err(ToolContextError::NotAvailable)
+        }
+
+        async fn request_elicitation(
+            &self,
+            request: ElicitationRequest,
+            _timeout: Duration,
+        ) -> std::result::Result<ElicitationResult, ToolContextError> {
+            assert!(request.message.contains("unlock"));
+            match &self.0 {
+                Ok(result) => Ok(result.clone()),
+                Err(ToolContextError::Timeout) => Err(ToolContextError::Timeout),
+                Err(_) => Err(ToolContextError::RequestFailed("unsupported".to_string())),
+            }
+        }
+
+        fn request_id(&self) -> &str {
+            "1"
+        }
+
+        fn tool_name(&self) -> &str {
+            "control_door_lock"
+        }
+
+        fn progress_token(&self) -> Option<&str> {
+            None
+        }
+
+        fn session_id(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    fn settings() -> ConsentSettings {
+        ConsentSettings {
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        }
+    }
+
+    fn unlock() -> crate::mcp_consent::OperationType {
+        crate::mcp_consent::OperationType::SecurityControl {
+            action: "unlock".to_string(),
+            scope: "Front Door".to_string(),
+        }
+    }
+
+    async fn decide(
+        context: Option<ElicitingContext>,
+        settings: &ConsentSettings,
+    ) -> ConsentDecision {
+        let manager = consent_manager(settings);
+        let request = manager.request_consent(unlock(), "test".to_string());
+        match context {
+            Some(context) => with_context(Arc::new(context), request).await,
+            None => request.await,
+        }
+        .unwrap()
+    }
+
+    fn answer(action: ElicitationAction, content: Value) -> ElicitingContext {
+        ElicitingContext(Ok(ElicitationResult {
+            action,
+            content: Some(content),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_elicitation_answers() {
+        let approved = answer(ElicitationAction::Accept, json!({"approve": true}));
+        assert!(matches!(
+            decide(Some(approved), &settings()).await,
+            ConsentDecision::Approved
+        ));
+
+        let unchecked = answer(ElicitationAction::Accept, json!({"approve": false}));
+        assert!(matches!(
+            decide(Some(unchecked), &settings()).await,
+            ConsentDecision::Denied { .. }
+        ));
+
+        let declined = answer(ElicitationAction::Decline, Value::Null);
+        match decide(Some(declined), &settings()).await {
+            ConsentDecision::Denied { reason } => assert_eq!(reason, "Declined by user"),
+            other => panic!("Expected denial, got {other:?}"),
+        }
+
+        let silent = ElicitingContext(Err(ToolContextError::Timeout));
+        assert!(matches!(
+            decide(Some(silent), &settings()).await,
+            ConsentDecision::TimedOut
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_no_approval_channel_denies() {
+        let unsupported = ElicitingContext(Err(ToolContextError::NotAvailable));
+        for context in [Some(unsupported), None] {
+            match decide(context, &settings()).await {
+                ConsentDecision::Denied { reason } => {
+                    assert!(reason.contains("No approval channel"), "{reason}")
+                }
+                other => panic!("Expected denial, got {other:?}"),
+            }
+        }
+
+        let disabled = ConsentSettings {
+            enabled: false,
+            ..settings()
+        };
+        assert!(matches!(
+            decide(None, &disabled).await,
+            ConsentDecision::AutoApproved { .. }
+        ));
+    }
+
+    #[test]
+    fn test_security_actions() {
+        let structure: crate::client::LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {},
+            "cats": {},
+            "controls": {
+                "alarm-1": { "name": "House Alarm", "type": "Alarm" },
+                "lock-1": { "name": "Front Door", "type": "DoorLock" },
+                "gate-1": { "name": "Garage", "type": "Gate" },
+                "ic-1": { "name": "Intercom", "type": "IntercomV2" },
+                "sw-1": { "name": "Floor Lamp", "type": "Switch" }
+            }
+        }))
+        .unwrap();
+        let model = crate::client::ControlModel::from_structure(&structure);
+        let action = |uuid: &str, command: &str| security_action(model.get(uuid).unwrap(), command);
+
+        assert_eq!(action("alarm-1", "off"), Some("disarm"));
+        assert_eq!(action("alarm-1", "on/1"), None);
+        assert_eq!(action("alarm-1", "quit"), None);
+        assert_eq!(action("lock-1", "off"), Some("unlock"));
+        assert_eq!(action("lock-1", "on"), None);
+        assert_eq!(action("gate-1", "open"), Some("open_door"));
+        assert_eq!(action("gate-1", "close"), None);
+        assert_eq!(action("ic-1", "open"), Some("open_door"));
+        assert_eq!(action("ic-1", "answer"), None);
+        assert_eq!(action("sw-1", "off"), None);
+    }
+
+    #[test]
+    fn test_remember_needs_approval() {
+        let request = ConsentRequest {
+            id: uuid::Uuid::new_v4(),
+            operation: unlock(),
+            sensitivity: crate::mcp_consent::SensitivityLevel::Critical,
+            description: String::new(),
+            details: String::new(),
+            risks: Vec::new(),
+            impact: String::new(),
+            is_bulk: false,
+            created_at: std::time::SystemTime::now(),
+            timeout: None,
+            source: "test".to_string(),
+            metadata: Default::default(),
+        };
+
+        let remembered = elicitation_response(
+            &request,
+            ElicitationResult {
+                action: ElicitationAction::Accept,
+                content: Some(json!({"approve": true, "remember": true})),
+            },
+        );
+        assert!(remembered.approved && remembered.apply_to_similar);
+
+        let denied = elicitation_response(
+            &request,
+            ElicitationResult {
+                action: ElicitationAction::Accept,
+                content: Some(json!({"approve": false, "remember": true})),
+            },
+        );
+        assert!(!denied.approved && !denied.apply_to_similar);
+    }
+}
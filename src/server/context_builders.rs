@@ -6,12 +6,12 @@
 use crate::{
     error::{LoxoneError, Result},
     server::{
+        macro_backend::LoxoneMcpServer,
         resources::{ResourceHandler, ResourceManager},
-        LoxoneMcpServer,
     },
 };
 use chrono::{Datelike, Timelike};
-use serde_json::{json, Value};
+use serde_json::{Value, json};
 use std::collections::HashMap;
 use tracing::debug;
 
@@ -51,6 +51,8 @@ impl ContextType {
                 "loxone://system/capabilities",
             ],
             ContextType::Energy => vec![
+                "loxone://energy/consumption",
+                "loxone://energy/meters",
                 "loxone://devices/category/lighting",
                 "loxone://devices/category/climate",
                 "loxone://system/status",
@@ -226,7 +228,10 @@ impl<'a> ContextBuilder<'a> {
 
     /// Convert resource URI to a clean key name
     fn resource_key_from_uri(&self, uri: &str) -> String {
-        uri.replace("loxone://", "")
+        urlencoding::decode(uri)
+            .map(|decoded| decoded.into_owned())
+            .unwrap_or_else(|_| uri.to_string())
+            .replace("loxone://", "")
             .replace("/", "_")
             .replace("-", "_")
     }
@@ -298,7 +303,7 @@ impl<'a> ContextBuilder<'a> {
     // Analysis helper methods
     fn analyze_device_capabilities(&self, capabilities: &Value) -> DeviceCapabilities {
         DeviceCapabilities {
-            lighting_count: capabilities["lighting_count"].as_u64().unwrap_or(0),
+            lighting_count: capabilities["light_count"].as_u64().unwrap_or(0),
             blind_count: capabilities["blind_count"].as_u64().unwrap_or(0),
             sensor_count: capabilities["sensor_count"].as_u64().unwrap_or(0),
             climate_count: capabilities["climate_count"].as_u64().unwrap_or(0),
@@ -446,7 +451,7 @@ impl<'a> ContextBuilder<'a> {
     }
 
     fn calculate_automation_score(&self, capabilities: &Value) -> f64 {
-        let lighting = capabilities["lighting_count"].as_u64().unwrap_or(0) as f64;
+        let lighting = capabilities["light_count"].as_u64().unwrap_or(0) as f64;
         let climate = capabilities["climate_count"].as_u64().unwrap_or(0) as f64;
         let sensors = capabilities["sensor_count"].as_u64().unwrap_or(0) as f64;
         let blinds = capabilities["blind_count"].as_u64().unwrap_or(0) as f64;
@@ -647,6 +647,7 @@ mod tests {
                     controls: std::collections::HashMap::new(),
                     cats: std::collections::HashMap::new(),
                     global_states: std::collections::HashMap::new(),
+                    operating_modes: std::collections::HashMap::new(),
                 })
             }
         }
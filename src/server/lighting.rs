@@ -0,0 +1,502 @@
+//! Light outputs: circuits of light controllers, white tones and colour state
+//!
+//! A `LightControllerV2` drives its circuits as sub-controls: dimmers,
+//! switches and `ColorPickerV2` outputs for RGB and tunable-white (LumiTech)
+//! lights. Colour pickers report their colour as a text state, either
+//! `hsv(hue,saturation,value)` or `temp(brightness,kelvin)`, and take the same
+//! two forms as commands. Colours given as hex, RGB, HSV, names or Kelvin are
+//! converted to one of these forms.
+
+use super::macro_backend::LoxoneMcpServer;
+use crate::client::{ControlType, LoxoneControl};
+use crate::error::{LoxoneError, Result};
+use serde_json::{Value, json};
+
+/// Named white tones and their colour temperature in Kelvin
+pub const WHITE_TONES: &[(&str, u32)] = &[
+    ("warm", 2700),
+    ("soft", 3000),
+    ("neutral", 4000),
+    ("cool", 5000),
+    ("daylight", 6500),
+];
+
+/// Colour temperatures accepted by `temp(brightness,kelvin)`
+pub const KELVIN_RANGE: (u32, u32) = (2700, 6500);
+
+/// Named colours as hue and saturation
+pub const NAMED_COLORS: &[(&str, u16, u8)] = &[
+    ("red", 0, 100),
+    ("orange", 30, 100),
+    ("amber", 45, 100),
+    ("yellow", 60, 100),
+    ("lime", 90, 100),
+    ("green", 120, 100),
+    ("turquoise", 170, 100),
+    ("cyan", 180, 100),
+    ("blue", 240, 100),
+    ("purple", 270, 100),
+    ("magenta", 300, 100),
+    ("pink", 330, 60),
+    ("white", 0, 0),
+];
+
+/// Colour of a colour picker output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightColor {
+    /// `hsv(hue,saturation,value)`
+    Hsv { hue: u16, saturation: u8, value: u8 },
+    /// `temp(brightness,kelvin)`
+    Temp { brightness: u8, kelvin: u32 },
+}
+
+impl LightColor {
+    /// Parse a colour state or command (`hsv(...)` or `temp(...)`)
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (name, args) = text.strip_suffix(')')?.split_once('(')?;
+        let args: Vec<f64> = args
+            .split(',')
+            .map(|arg| arg.trim().parse::<f64>())
+            .collect::<std::result::Result<_, _>>()
+            .ok()?;
+        match (name.trim().to_lowercase().as_str(), args.as_slice()) {
+            ("hsv", [hue, saturation, value]) => Some(Self::Hsv {
+                hue: hue.round().clamp(0.0, 360.0) as u16,
+                saturation: saturation.round().clamp(0.0, 100.0) as u8,
+                value: value.round().clamp(0.0, 100.0) as u8,
+            }),
+            ("temp", [brightness, kelvin]) => Some(Self::Temp {
+                brightness: brightness.round().clamp(0.0, 100.0) as u8,
+                kelvin: kelvin.round().max(0.0) as u32,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Brightness in percent
+    pub fn brightness(&self) -> u8 {
+        match *self {
+            Self::Hsv { value, .. } => value,
+            Self::Temp { brightness, .. } => brightness,
+        }
+    }
+
+    /// The same colour at another brightness
+    pub fn with_brightness(self, level: u8) -> Self {
+        match self {
+            Self::Hsv {
+                hue, saturation, ..
+            } => Self::Hsv {
+                hue,
+                saturation,
+                value: level,
+            },
+            Self::Temp { kelvin, .. } => Self::Temp {
+                brightness: level,
+                kelvin,
+            },
+        }
+    }
+
+    /// Colour of an RGB value
+    pub fn from_rgb(red: u8, green: u8, blue: u8) -> Self {
+        let [r, g, b] = [red, green, blue].map(|c| f64::from(c) / 255.0);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        Self::Hsv {
+            hue: hue.round() as u16 % 360,
+            saturation: (saturation * 100.0).round() as u8,
+            value: (max * 100.0).round() as u8,
+        }
+    }
+
+    /// RGB value of an HSV colour at full brightness, as `#rrggbb`
+    pub fn hex(&self) -> Option<String> {
+        let Self::Hsv {
+            hue, saturation, ..
+        } = *self
+        else {
+            return None;
+        };
+        let s = f64::from(saturation) / 100.0;
+        let h = f64::from(hue % 360) / 60.0;
+        let x = s * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (s, x, 0.0),
+            1 => (x, s, 0.0),
+            2 => (0.0, s, x),
+            3 => (0.0, x, s),
+            4 => (x, 0.0, s),
+            _ => (s, 0.0, x),
+        };
+        let channel = |c: f64| ((c + 1.0 - s) * 255.0).round() as u8;
+        Some(format!(
+            "#{:02x}{:02x}{:02x}",
+            channel(r),
+            channel(g),
+            channel(b)
+        ))
+    }
+
+    /// Colour as reported by the colour tools
+    pub fn describe(&self) -> Value {
+        match *self {
+            Self::Hsv {
+                hue,
+                saturation,
+                value,
+            } => json!({
+                "mode": "color",
+                "hue": hue,
+                "saturation": saturation,
+                "brightness": value,
+                "hex": self.hex(),
+            }),
+            Self::Temp { brightness, kelvin } => json!({
+                "mode": "white",
+                "kelvin": kelvin,
+                "brightness": brightness,
+            }),
+        }
+    }
+
+    /// Validated command for a colour picker
+    pub fn command(&self, control: &LoxoneControl) -> Result<String> {
+        match *self {
+            Self::Hsv {
+                hue,
+                saturation,
+                value,
+            } => control.build_command("hsv", &[&hue, &saturation, &value]),
+            Self::Temp { brightness, kelvin } => {
+                control.build_command("temp", &[&brightness, &kelvin])
+            }
+        }
+    }
+}
+
+/// Colour temperature of a white tone name ("warm white") or Kelvin value ("3000K")
+pub fn parse_white(white: &str) -> std::result::Result<u32, String> {
+    let text = white.trim().to_lowercase();
+    let kelvin = match text.trim_end_matches('k').trim().parse::<u32>() {
+        Ok(kelvin) => kelvin,
+        Err(_) => {
+            let tone = text.trim_end_matches("white").trim();
+            WHITE_TONES
+                .iter()
+                .find(|(name, _)| *name == tone)
+                .map(|(_, kelvin)| *kelvin)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = WHITE_TONES.iter().map(|(name, _)| *name).collect();
+                    format!(
+                        "Invalid white '{white}'. Use a Kelvin value or: {}",
+                        names.join(", ")
+                    )
+                })?
+        }
+    };
+    let (min, max) = KELVIN_RANGE;
+    if !(min..=max).contains(&kelvin) {
+        return Err(format!(
+            "Colour temperature must be between {min}K and {max}K, got {kelvin}K"
+        ));
+    }
+    Ok(kelvin)
+}
+
+/// Parse a colour: `#rrggbb`, `rgb(r,g,b)`, `hsv(h,s,v)`, `temp(b,k)`, a
+/// colour name, a white tone ("warm white") or a Kelvin value ("3000K")
+///
+/// Colours without a brightness of their own are at full brightness.
+pub fn parse_color(color: &str) -> std::result::Result<LightColor, String> {
+    let text = color.trim().to_lowercase();
+    let invalid = || {
+        let names: Vec<&str> = NAMED_COLORS.iter().map(|(name, ..)| *name).collect();
+        format!(
+            "Invalid colour '{color}'. Use #rrggbb, rgb(r,g,b), hsv(h,s,v), a Kelvin value, a white tone or: {}",
+            names.join(", ")
+        )
+    };
+
+    if let Some(hex) = text.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(LightColor::from_rgb(r, g, b)),
+            _ => Err(invalid()),
+        };
+    }
+    if let Some(args) = text
+        .strip_prefix("rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let channels: Vec<u8> = args
+            .split(',')
+            .filter_map(|c| c.trim().parse().ok())
+            .collect();
+        return match channels[..] {
+            [r, g, b] => Ok(LightColor::from_rgb(r, g, b)),
+            _ => Err(invalid()),
+        };
+    }
+    if text.contains('(') {
+        return LightColor::parse(&text).ok_or_else(invalid);
+    }
+    let name = text.trim_end_matches("white").trim();
+    if let Some((_, hue, saturation)) = NAMED_COLORS.iter().find(|(known, ..)| *known == text) {
+        return Ok(LightColor::Hsv {
+            hue: *hue,
+            saturation: *saturation,
+            value: 100,
+        });
+    }
+    let is_white = WHITE_TONES.iter().any(|(tone, _)| *tone == name)
+        || text.trim_end_matches('k').trim().parse::<u32>().is_ok();
+    if is_white {
+        return parse_white(&text).map(|kelvin| LightColor::Temp {
+            brightness: 100,
+            kelvin,
+        });
+    }
+    Err(invalid())
+}
+
+/// Whether a control takes colours (`hsv`/`temp` commands)
+pub fn is_color_output(control: &LoxoneControl) -> bool {
+    matches!(
+        control.control_type,
+        ControlType::ColorPicker | ControlType::ColorPickerV2
+    )
+}
+
+/// Light outputs of a control: a controller's circuits, or the control itself
+pub fn light_outputs(control: &LoxoneControl) -> Vec<&LoxoneControl> {
+    if control.control_type.has_moods()
+        || control.control_type == ControlType::CentralLightController
+    {
+        control
+            .sub_controls
+            .iter()
+            .filter(|sub| sub.control_type.is_lighting() && !sub.control_type.has_moods())
+            .collect()
+    } else if control.control_type.is_lighting() {
+        vec![control]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Light outputs of a control, each with the controller it belongs to
+pub fn outputs_with_controller(
+    control: &LoxoneControl,
+) -> impl Iterator<Item = (&LoxoneControl, Option<&LoxoneControl>)> {
+    light_outputs(control)
+        .into_iter()
+        .map(move |output| (output, Some(control).filter(|c| c.uuid != output.uuid)))
+}
+
+/// Command setting an output's brightness and/or white tone
+///
+/// Dimmers take the brightness, switches go on or off, colour pickers keep
+/// their `current` colour at the new brightness unless a white tone is given.
+/// Without a known colour a brightness-only change is neutral white.
+pub fn output_command(
+    control: &LoxoneControl,
+    brightness: Option<u8>,
+    kelvin: Option<u32>,
+    current: Option<LightColor>,
+) -> Result<String> {
+    if is_color_output(control) {
+        let color = match (kelvin, brightness) {
+            (Some(kelvin), _) => LightColor::Temp {
+                brightness: brightness
+                    .or(current.map(|color| color.brightness()))
+                    .filter(|level| *level > 0)
+                    .unwrap_or(100),
+                kelvin,
+            },
+            (None, Some(level)) => current
+                .unwrap_or(LightColor::Hsv {
+                    hue: 0,
+                    saturation: 0,
+                    value: 100,
+                })
+                .with_brightness(level),
+            (None, None) => {
+                return Err(LoxoneError::invalid_input(
+                    "Give a brightness and/or a white tone",
+                ));
+            }
+        };
+        return color.command(control);
+    }
+
+    match (brightness, kelvin) {
+        (Some(level), _) => LoxoneMcpServer::light_command(control, "on", Some(level)),
+        (None, Some(_)) => Err(LoxoneError::invalid_input(format!(
+            "{} has no adjustable white tone",
+            control.name
+        ))),
+        (None, None) => Err(LoxoneError::invalid_input(
+            "Give a brightness and/or a white tone",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ControlModel, LoxoneStructure};
+    use serde_json::json;
+
+    fn model() -> ControlModel {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {},
+            "cats": {},
+            "controls": {
+                "lc-1": {
+                    "name": "Kitchen Lights",
+                    "type": "LightControllerV2",
+                    "subControls": {
+                        "dim-1": { "name": "Spots", "type": "Dimmer" },
+                        "rgb-1": { "name": "LED Strip", "type": "ColorPickerV2" },
+                        "lt-1": { "name": "Ceiling", "type": "ColorPickerV2" },
+                        "tmr-1": { "name": "Timer", "type": "IRCV2Daytimer" }
+                    }
+                },
+                "sw-1": { "name": "Hall Light", "type": "Switch" }
+            }
+        }))
+        .unwrap();
+        ControlModel::from_structure(&structure)
+    }
+
+    #[test]
+    fn test_white_tones_and_colors() {
+        assert_eq!(parse_white("warm white").unwrap(), 2700);
+        assert_eq!(parse_white("Neutral").unwrap(), 4000);
+        assert_eq!(parse_white("3500K").unwrap(), 3500);
+        assert!(parse_white("1800").unwrap_err().contains("between 2700K"));
+        assert!(parse_white("purple").is_err());
+
+        let color = LightColor::parse("hsv(120, 80, 50)").unwrap();
+        assert_eq!(color.brightness(), 50);
+        assert_eq!(
+            color.with_brightness(20),
+            LightColor::Hsv {
+                hue: 120,
+                saturation: 80,
+                value: 20
+            }
+        );
+        assert_eq!(
+            LightColor::parse("temp(40,3000)"),
+            Some(LightColor::Temp {
+                brightness: 40,
+                kelvin: 3000
+            })
+        );
+        assert_eq!(LightColor::parse("rgb(1,2,3)"), None);
+        assert_eq!(LightColor::parse("0"), None);
+    }
+
+    #[test]
+    fn test_parse_color_formats() {
+        let orange = LightColor::Hsv {
+            hue: 32,
+            saturation: 100,
+            value: 100,
+        };
+        assert_eq!(parse_color("#FF8800").unwrap(), orange);
+        assert_eq!(parse_color("rgb(255, 136, 0)").unwrap(), orange);
+        assert_eq!(orange.hex().as_deref(), Some("#ff8800"));
+        assert_eq!(
+            parse_color("#000080").unwrap(),
+            LightColor::Hsv {
+                hue: 240,
+                saturation: 100,
+                value: 50
+            }
+        );
+        assert_eq!(
+            parse_color("Blue").unwrap().describe(),
+            json!({ "mode": "color", "hue": 240, "saturation": 100, "brightness": 100, "hex": "#0000ff" })
+        );
+        assert_eq!(
+            parse_color("hsv(120,50,80)").unwrap(),
+            LightColor::Hsv {
+                hue: 120,
+                saturation: 50,
+                value: 80
+            }
+        );
+        assert_eq!(
+            parse_color("warm white").unwrap(),
+            LightColor::Temp {
+                brightness: 100,
+                kelvin: 2700
+            }
+        );
+        assert_eq!(
+            parse_color("4000K").unwrap().describe(),
+            json!({ "mode": "white", "kelvin": 4000, "brightness": 100 })
+        );
+        assert!(parse_color("1000K").is_err());
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("rgb(300,0,0)").is_err());
+        assert!(parse_color("mauve").unwrap_err().contains("magenta"));
+    }
+
+    #[test]
+    fn test_output_commands() {
+        let model = model();
+        let controller = model.get("lc-1").unwrap();
+        let outputs = light_outputs(controller);
+        let names: Vec<&str> = outputs.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Ceiling", "LED Strip", "Spots"]);
+        let [ceiling, strip, spots] = outputs[..] else {
+            unreachable!()
+        };
+
+        assert_eq!(
+            output_command(ceiling, Some(40), Some(2700), None).unwrap(),
+            "temp(40,2700)"
+        );
+        let current = LightColor::parse("hsv(240,100,90)");
+        assert_eq!(
+            output_command(strip, Some(30), None, current).unwrap(),
+            "hsv(240,100,30)"
+        );
+        assert_eq!(
+            output_command(strip, Some(30), None, None).unwrap(),
+            "hsv(0,0,30)"
+        );
+        assert_eq!(
+            output_command(strip, None, Some(5000), current).unwrap(),
+            "temp(90,5000)"
+        );
+        assert_eq!(
+            output_command(spots, Some(40), Some(2700), None).unwrap(),
+            "40"
+        );
+        assert!(output_command(spots, None, Some(2700), None).is_err());
+
+        let switch = model.get("sw-1").unwrap();
+        assert_eq!(light_outputs(switch).len(), 1);
+        assert_eq!(output_command(switch, Some(0), None, None).unwrap(), "off");
+    }
+}
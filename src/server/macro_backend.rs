@@ -7,9 +7,29 @@
 //! - Parameter validation
 //! - Error handling
 
-use crate::client::{ClientContext, LoxoneClient, LoxoneStructure};
-use crate::config::ServerConfig;
-use crate::services::{StateManager, UnifiedValueResolver};
+use crate::audit::{AuditCaller, AuditEntry, AuditFilter, AuditLog, AuditOutcome};
+use crate::client::binary_protocol::DaytimerEvent;
+use crate::client::statistics::load_statistics;
+use crate::client::{
+    ClientContext, CommandSpec, ControlModel, ControlType, LoxoneClient, LoxoneControl,
+    LoxoneResponse, StatisticConfig,
+};
+use crate::config::{ServerConfig, ToolConfig};
+use crate::error::LoxoneError;
+use crate::mcp_consent::{ConsentDecision, ConsentManager, OperationType};
+use crate::server::audio;
+use crate::server::bulk::{DeviceSelector, plan_action};
+use crate::server::climate;
+use crate::server::consent;
+use crate::server::lighting;
+use crate::server::media;
+use crate::server::moods;
+use crate::server::resources::ResourceManager;
+use crate::services::name_resolution::{self, ambiguity_message};
+use crate::services::{NameResolver, StateManager, UnifiedValueResolver};
+use crate::storage::history::{
+    HistoryQuery, MAX_POINTS, Resolution, SensorHistory, rollup, summarize, unit_from_format,
+};
 use pulseengine_mcp_macros::{mcp_server, mcp_tools};
 use serde_json::{Value, json};
 use std::sync::Arc;
@@ -34,8 +54,18 @@ pub struct LoxoneMcpServer {
     value_resolver: Option<Arc<UnifiedValueResolver>>,
     /// State manager for change detection (for future use)
     state_manager: Option<Arc<StateManager>>,
-    /// Server configuration (for future use)
+    /// Server configuration
     config: Option<ServerConfig>,
+    /// `loxone://` resource catalog and URI parser
+    resource_manager: Arc<ResourceManager>,
+    /// Approval of sensitive tools
+    consent: Arc<ConsentManager>,
+    /// Record of every command sent to the Miniserver
+    audit: Option<AuditLog>,
+    /// Time series of sensor states
+    history: Option<SensorHistory>,
+    /// Fuzzy room and device name lookup with configured aliases
+    names: Arc<NameResolver>,
 }
 
 impl LoxoneMcpServer {
@@ -53,1764 +83,3433 @@ impl LoxoneMcpServer {
             context: Some(context),
             value_resolver: Some(value_resolver),
             state_manager,
+            consent: Arc::new(consent::consent_manager(&config.mcp.consent)),
+            names: Arc::new(NameResolver::new(&config.mcp.aliases)),
             config: Some(config),
+            resource_manager: Arc::new(ResourceManager::new()),
+            audit: None,
+            history: None,
         }
     }
 
+    /// Replace the server configuration (e.g. for offline servers built from defaults)
+    pub fn with_config(mut self, config: ServerConfig) -> Self {
+        self.consent = Arc::new(consent::consent_manager(&config.mcp.consent));
+        self.names = Arc::new(NameResolver::new(&config.mcp.aliases));
+        self.config = Some(config);
+        self
+    }
+
+    /// Consent manager approving sensitive tools, with its audit records
+    pub fn consent_manager(&self) -> &Arc<ConsentManager> {
+        &self.consent
+    }
+
+    /// Record every command sent to the Miniserver in this audit log
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Audit log of sent commands, if auditing is enabled
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
+
+    /// Answer sensor history queries from this store
+    pub fn with_sensor_history(mut self, history: SensorHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Sensor history, if enabled
+    pub fn sensor_history(&self) -> Option<&SensorHistory> {
+        self.history.as_ref()
+    }
+
+    /// Get the Loxone client, if connected
+    pub fn client(&self) -> Option<&Arc<dyn LoxoneClient>> {
+        self.client.as_ref()
+    }
+
+    /// Get the shared client context, if connected
+    pub fn context(&self) -> Option<&Arc<ClientContext>> {
+        self.context.as_ref()
+    }
+
+    /// Get the `loxone://` resource catalog
+    pub fn resource_manager(&self) -> &ResourceManager {
+        &self.resource_manager
+    }
+
+    /// Tool gating and limits from the server configuration
+    pub fn tool_config(&self) -> ToolConfig {
+        self.config
+            .as_ref()
+            .map(|config| config.mcp.tools.clone())
+            .unwrap_or_default()
+    }
+
+    /// Cut a listing to `max_devices_per_query`, returning its full length
+    fn limit_listing<T>(&self, items: &mut Vec<T>) -> usize {
+        let total = items.len();
+        items.truncate(self.tool_config().max_devices_per_query);
+        total
+    }
+
     /// Check if connected to Loxone
-    fn ensure_connected(&self) -> std::result::Result<(), String> {
+    fn ensure_connected(&self) -> crate::error::Result<()> {
         if self.client.is_none() {
-            return Err("Server not initialized with Loxone client".to_string());
+            return Err(LoxoneError::connection(
+                "Server not initialized with Loxone client",
+            ));
         }
         Ok(())
     }
 
     /// Get the Loxone client
-    fn get_client(&self) -> std::result::Result<&Arc<dyn LoxoneClient>, String> {
+    pub(super) fn get_client(&self) -> crate::error::Result<&Arc<dyn LoxoneClient>> {
         self.client
             .as_ref()
-            .ok_or_else(|| "Client not initialized".to_string())
-    }
-
-    /// Resolve a room name to its UUID by searching the structure's rooms.
-    /// Returns None if no matching room is found.
-    fn resolve_room_uuid(structure: &LoxoneStructure, room_name: &str) -> Option<String> {
-        let lower = room_name.to_lowercase();
-        for (uuid, room) in &structure.rooms {
-            if let Some(name) = room.get("name").and_then(|v| v.as_str())
-                && (name.to_lowercase() == lower || name.to_lowercase().contains(&lower))
-            {
-                return Some(uuid.clone());
+            .ok_or_else(|| LoxoneError::connection("Client not initialized"))
+    }
+
+    /// Ask the user once before commands that disarm, unlock or open security devices
+    ///
+    /// Every command path goes through here, so the generic device tools cannot
+    /// skip the question. Controls sharing an action are approved together.
+    async fn authorize_security(
+        &self,
+        commands: &[(&LoxoneControl, &str)],
+    ) -> crate::error::Result<()> {
+        let mut scopes: Vec<(&str, Vec<&str>)> = Vec::new();
+        for (control, command) in commands {
+            let Some(action) = consent::security_action(control, command) else {
+                continue;
+            };
+            match scopes.iter_mut().find(|(known, _)| *known == action) {
+                Some((_, names)) => names.push(&control.name),
+                None => scopes.push((action, vec![&control.name])),
             }
         }
-        None
-    }
+        if scopes.is_empty() {
+            return Ok(());
+        }
 
-    /// Find controls matching the given types within a specific room (by room UUID).
-    fn find_controls_by_type_in_room<'a>(
-        structure: &'a LoxoneStructure,
-        room_uuid: &str,
-        types: &[&str],
-    ) -> Vec<(&'a String, &'a Value)> {
-        structure
-            .controls
-            .iter()
-            .filter(|(_, control)| {
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let control_room = control.get("room").and_then(|v| v.as_str()).unwrap_or("");
-                types.contains(&control_type) && control_room == room_uuid
-            })
-            .collect()
+        let tool = AuditCaller::current()
+            .tool
+            .unwrap_or_else(|| "device_command".to_string());
+        for (action, names) in scopes {
+            if let Err(error) = self.require_consent(&tool, action, &names.join(", ")).await {
+                // None of the commands is sent; keep the attempt on record
+                for (control, command) in commands {
+                    self.record_refused(control, command, &error.to_string())
+                        .await;
+                }
+                return Err(error);
+            }
+        }
+        Ok(())
     }
 
-    /// Find controls matching the given types across the entire system.
-    fn find_controls_by_type<'a>(
-        structure: &'a LoxoneStructure,
-        types: &[&str],
-    ) -> Vec<(&'a String, &'a Value)> {
-        structure
-            .controls
-            .iter()
-            .filter(|(_, control)| {
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                types.contains(&control_type)
-            })
-            .collect()
-    }
+    /// Ask the user before a sensitive security action
+    async fn require_consent(
+        &self,
+        tool: &str,
+        action: &str,
+        scope: &str,
+    ) -> crate::error::Result<()> {
+        let operation = OperationType::SecurityControl {
+            action: action.to_string(),
+            scope: scope.to_string(),
+        };
+        // Remembered approvals only apply to the client that gave them
+        let source = match AuditCaller::current().client {
+            Some(client) => format!("mcp_tool:{tool} ({client})"),
+            None => format!("mcp_tool:{tool}"),
+        };
+        let decision = self
+            .consent
+            .request_consent(operation, source)
+            .await
+            .map_err(|e| LoxoneError::internal(format!("Consent request failed: {e}")))?;
 
-    /// Find a single control by UUID or by name (case-insensitive partial match).
-    /// Returns the (uuid, control) pair.
-    fn find_control_by_id_or_name<'a>(
-        structure: &'a LoxoneStructure,
-        identifier: &str,
-    ) -> Option<(&'a String, &'a Value)> {
-        // Try exact UUID match first
-        if let Some(control) = structure.controls.get(identifier) {
-            return structure
-                .controls
-                .get_key_value(identifier)
-                .map(|(k, _)| (k, control));
-        }
-        // Fall back to name search
-        let lower = identifier.to_lowercase();
-        structure.controls.iter().find(|(_, control)| {
-            control
-                .get("name")
-                .and_then(|v| v.as_str())
-                .map(|n| n.to_lowercase() == lower || n.to_lowercase().contains(&lower))
-                .unwrap_or(false)
-        })
+        match decision {
+            ConsentDecision::Approved | ConsentDecision::AutoApproved { .. } => Ok(()),
+            ConsentDecision::Denied { reason } => Err(LoxoneError::consent_denied(reason)),
+            ConsentDecision::TimedOut => Err(LoxoneError::consent_denied(
+                "no answer before the consent timeout",
+            )),
+        }
     }
 
-    /// Search for climate controllers in a room by room name.
-    fn find_climate_in_room<'a>(
-        structure: &'a LoxoneStructure,
-        room_name: &str,
-        climate_types: &[&str],
-    ) -> std::result::Result<Vec<(&'a String, &'a Value)>, String> {
-        if let Some(room_uuid) = Self::resolve_room_uuid(structure, room_name) {
-            Ok(Self::find_controls_by_type_in_room(
-                structure,
-                &room_uuid,
-                climate_types,
-            ))
-        } else {
-            // If room name didn't resolve, try to find climate controllers whose name contains the room
-            let lower = room_name.to_lowercase();
-            Ok(structure
-                .controls
-                .iter()
-                .filter(|(_, control)| {
-                    let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                    let name = control
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_lowercase();
-                    climate_types.contains(&control_type) && name.contains(&lower)
-                })
-                .collect())
+    /// Typed controls of the current structure
+    ///
+    /// The model is built once per structure load and cached in the client
+    /// context; the structure is only fetched if nothing has been loaded yet.
+    pub(super) async fn control_model(&self) -> crate::error::Result<Arc<ControlModel>> {
+        if let Some(context) = &self.context
+            && let Some(model) = context.control_model().await
+        {
+            return Ok(model);
         }
+
+        let structure = self
+            .get_client()?
+            .get_structure()
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Failed to get structure: {e}")))?;
+        let Some(context) = &self.context else {
+            return Ok(Arc::new(ControlModel::from_structure(&structure)));
+        };
+        context
+            .update_structure(structure)
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Failed to load structure: {e}")))?;
+        context
+            .control_model()
+            .await
+            .ok_or_else(|| LoxoneError::connection("Structure not loaded"))
     }
 
-    /// Fetch live state for a list of UUIDs and return a mapping from UUID to state value.
-    async fn fetch_live_states(
-        client: &Arc<dyn LoxoneClient>,
-        uuids: &[String],
-    ) -> std::collections::HashMap<String, Value> {
-        if uuids.is_empty() {
-            return std::collections::HashMap::new();
-        }
-        match client.get_device_states(uuids).await {
-            Ok(states) => states,
-            Err(e) => {
-                warn!("Failed to fetch live device states: {e}");
-                std::collections::HashMap::new()
+    /// Find controls of a type in a room by room name.
+    ///
+    /// Falls back to controls whose name contains the room name if no room
+    /// matches, and fails with the candidates if the room name is ambiguous.
+    pub(super) fn find_controls_in_room<'a>(
+        &self,
+        model: &'a ControlModel,
+        room_name: &str,
+        predicate: fn(&ControlType) -> bool,
+    ) -> crate::error::Result<Vec<&'a LoxoneControl>> {
+        match self.names.resolve_room(model, room_name) {
+            name_resolution::Resolution::Found(room_uuid) => {
+                Ok(model.of_type_in_room(room_uuid, predicate))
             }
+            name_resolution::Resolution::Ambiguous(candidates) => Err(LoxoneError::invalid_input(
+                ambiguity_message("Room", room_name, &candidates),
+            )),
+            name_resolution::Resolution::NotFound => Ok(model.filter(|control| {
+                predicate(&control.control_type) && control.name_matches(room_name)
+            })),
         }
     }
-}
-
-/// All MCP tools defined in a single impl block
-#[mcp_tools]
-impl LoxoneMcpServer {
-    // ========================================================================
-    // LIGHTING TOOLS
-    // ========================================================================
 
-    /// Control lights in a room, by device, or system-wide
-    ///
-    /// Unified lighting control with scope-based targeting. Supports:
-    /// - scope: "device" (single light), "room" (all lights in room), "system" (all lights)
-    /// - action: "on", "off", "dim", "bright"
-    /// - brightness: 0-100 for dimming (optional)
-    pub async fn control_lights(
+    /// Resolve a room name or UUID, or fail with a tool error
+    pub(super) fn resolve_room<'a>(
         &self,
-        scope: String,
-        target: Option<String>,
-        action: String,
-        brightness: Option<u8>,
-    ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        model: &'a ControlModel,
+        room_name: &str,
+    ) -> crate::error::Result<&'a str> {
+        self.names
+            .resolve_room(model, room_name)
+            .into_result("Room", room_name)
+    }
 
-        // Normalize action (multi-language support)
-        let normalized_action = match action.to_lowercase().as_str() {
-            "on" | "ein" | "an" | "einschalten" => "on",
-            "off" | "aus" | "ab" | "ausschalten" => "off",
-            "dim" | "dimmen" => "dim",
-            "bright" | "hell" => "bright",
-            _ => {
-                return Err(format!(
-                    "Invalid action '{action}'. Supported: on, off, dim, bright"
-                ));
-            }
+    /// Room controllers addressed by a controller name or UUID, or a room name
+    pub(super) fn climate_targets<'a>(
+        &self,
+        model: &'a ControlModel,
+        room: &str,
+    ) -> crate::error::Result<Vec<&'a LoxoneControl>> {
+        let targets = match self.names.resolve_control(model, room).found() {
+            Some(control) if control.control_type.is_room_controller() => vec![control],
+            _ => self.find_controls_in_room(model, room, ControlType::is_room_controller)?,
         };
+        if targets.is_empty() {
+            return Err(LoxoneError::not_found(format!(
+                "No climate controller found for room '{room}'"
+            )));
+        }
+        Ok(targets)
+    }
 
-        // Validate brightness
-        if let Some(level) = brightness
-            && level > 100
-        {
-            return Err("Brightness must be between 0-100".to_string());
+    /// Room controllers supporting modes, overrides and schedules (`IRoomControllerV2`)
+    fn climate_v2_targets<'a>(
+        &self,
+        model: &'a ControlModel,
+        room: &str,
+    ) -> crate::error::Result<Vec<&'a LoxoneControl>> {
+        let targets: Vec<_> = self
+            .climate_targets(model, room)?
+            .into_iter()
+            .filter(|control| climate::is_v2_controller(control))
+            .collect();
+        if targets.is_empty() {
+            return Err(LoxoneError::not_found(format!(
+                "No IRoomControllerV2 found for room '{room}'; older room controllers only support set_temperature"
+            )));
         }
+        Ok(targets)
+    }
 
-        // Build the Loxone command string from the normalized action + brightness
-        let command = match (normalized_action, brightness) {
-            (_, Some(level)) => format!("{level}"),
-            ("on", None) => "on".to_string(),
-            ("off", None) => "off".to_string(),
-            ("dim", None) => "25".to_string(), // default dim level
-            ("bright", None) => "100".to_string(), // full brightness
-            _ => "on".to_string(),
+    /// Audio zones addressed by a zone name or UUID, or a room name
+    fn audio_targets<'a>(
+        &self,
+        model: &'a ControlModel,
+        zone: &str,
+    ) -> crate::error::Result<Vec<&'a LoxoneControl>> {
+        let targets = match self.names.resolve_control(model, zone).found() {
+            Some(control) if control.control_type.is_audio() => vec![control],
+            _ => self.find_controls_in_room(model, zone, ControlType::is_audio)?,
         };
+        if targets.is_empty() {
+            return Err(LoxoneError::not_found(format!(
+                "No audio zone found for '{zone}'"
+            )));
+        }
+        Ok(targets)
+    }
 
-        let client = self.get_client()?;
-        let light_types = &["Switch", "Dimmer", "LightController", "ColorPicker"];
-
-        match scope.to_lowercase().as_str() {
-            "device" => {
-                let target_id = target
-                    .as_deref()
-                    .ok_or_else(|| "target is required when scope is 'device'".to_string())?;
-                let response = client
-                    .send_command(target_id, &command)
-                    .await
-                    .map_err(|e| format!("Failed to send command to device {target_id}: {e}"))?;
-                Ok(json!({
-                    "scope": "device",
-                    "target": target_id,
-                    "action": normalized_action,
-                    "brightness": brightness,
-                    "command_sent": command,
-                    "status": "executed",
-                    "miniserver_response": response.value
-                }))
-            }
-            "room" => {
-                let room_name = target.as_deref().ok_or_else(|| {
-                    "target (room name) is required when scope is 'room'".to_string()
-                })?;
-                let structure = client
-                    .get_structure()
-                    .await
-                    .map_err(|e| format!("Failed to get structure: {e}"))?;
-                let room_uuid = Self::resolve_room_uuid(&structure, room_name)
-                    .ok_or_else(|| format!("Room '{room_name}' not found"))?;
-                let controls =
-                    Self::find_controls_by_type_in_room(&structure, &room_uuid, light_types);
-                if controls.is_empty() {
-                    return Err(format!("No lights found in room '{room_name}'"));
-                }
-                let mut results = Vec::new();
-                for (uuid, control) in &controls {
-                    let name = control
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    match client.send_command(uuid, &command).await {
-                        Ok(response) => {
-                            results.push(json!({
-                                "uuid": uuid,
-                                "name": name,
-                                "status": "executed",
-                                "miniserver_response": response.value
-                            }));
-                        }
-                        Err(e) => {
-                            results.push(json!({
-                                "uuid": uuid,
-                                "name": name,
-                                "status": "error",
-                                "error": format!("{e}")
-                            }));
-                        }
-                    }
-                }
-                Ok(json!({
-                    "scope": "room",
-                    "target": room_name,
-                    "action": normalized_action,
-                    "brightness": brightness,
-                    "command_sent": command,
-                    "devices_affected": results.len(),
-                    "results": results
-                }))
+    /// The one audio zone addressed by a zone name or UUID, or a room name
+    fn audio_zone<'a>(
+        &self,
+        model: &'a ControlModel,
+        zone: &str,
+    ) -> crate::error::Result<&'a LoxoneControl> {
+        match self.audio_targets(model, zone)?.as_slice() {
+            [control] => Ok(control),
+            controls => {
+                let names: Vec<&str> = controls.iter().map(|c| c.name.as_str()).collect();
+                Err(LoxoneError::invalid_input(format!(
+                    "'{zone}' matches several audio zones: {}. Use a zone name or UUID.",
+                    names.join(", ")
+                )))
             }
-            "system" => {
-                let structure = client
-                    .get_structure()
-                    .await
-                    .map_err(|e| format!("Failed to get structure: {e}"))?;
-                let controls = Self::find_controls_by_type(&structure, light_types);
-                if controls.is_empty() {
-                    return Err("No lights found in the system".to_string());
-                }
-                let mut results = Vec::new();
-                for (uuid, control) in &controls {
-                    let name = control
-                        .get("name")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    match client.send_command(uuid, &command).await {
-                        Ok(response) => {
-                            results.push(json!({
-                                "uuid": uuid,
-                                "name": name,
-                                "status": "executed",
-                                "miniserver_response": response.value
-                            }));
-                        }
-                        Err(e) => {
-                            results.push(json!({
-                                "uuid": uuid,
-                                "name": name,
-                                "status": "error",
-                                "error": format!("{e}")
-                            }));
-                        }
-                    }
-                }
-                Ok(json!({
-                    "scope": "system",
-                    "action": normalized_action,
-                    "brightness": brightness,
-                    "command_sent": command,
-                    "devices_affected": results.len(),
-                    "results": results
-                }))
+        }
+    }
+
+    /// The one light controller with moods addressed by a controller name or UUID, or a room name
+    fn mood_controller<'a>(
+        &self,
+        model: &'a ControlModel,
+        controller: &str,
+    ) -> crate::error::Result<&'a LoxoneControl> {
+        let targets = match self.names.resolve_control(model, controller).found() {
+            Some(control) if control.control_type.has_moods() => vec![control],
+            _ => self.find_controls_in_room(model, controller, ControlType::has_moods)?,
+        };
+        match targets.as_slice() {
+            [control] => Ok(control),
+            [] => Err(LoxoneError::not_found(format!(
+                "No light controller found for '{controller}'"
+            ))),
+            controls => {
+                let names: Vec<&str> = controls.iter().map(|c| c.name.as_str()).collect();
+                Err(LoxoneError::invalid_input(format!(
+                    "'{controller}' matches several light controllers: {}. Use a controller name or UUID.",
+                    names.join(", ")
+                )))
             }
-            _ => Err(format!(
-                "Invalid scope '{scope}'. Use: device, room, system"
-            )),
         }
     }
 
-    /// Get the current state of all lights
+    /// Light outputs addressed by an output, light controller or room name,
+    /// each with the controller it belongs to
     ///
-    /// Returns a list of all lighting devices with their current state,
-    /// brightness level, and room location.
-    pub async fn get_lights_status(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    /// Controllers are expanded to their circuits; `output` narrows the
+    /// outputs down by name.
+    fn light_output_targets<'a>(
+        &self,
+        model: &'a ControlModel,
+        target: &str,
+        output: Option<&str>,
+    ) -> crate::error::Result<Vec<(&'a LoxoneControl, Option<&'a LoxoneControl>)>> {
+        let controls = match self.names.resolve_control(model, target).found() {
+            Some(control) if control.control_type.is_lighting() => vec![control],
+            _ => self.find_controls_in_room(model, target, ControlType::is_lighting)?,
+        };
+        let mut seen = std::collections::HashSet::new();
+        let outputs: Vec<_> = controls
+            .into_iter()
+            .flat_map(lighting::outputs_with_controller)
+            .filter(|(control, _)| seen.insert(&control.uuid))
+            .filter(|(control, _)| output.is_none_or(|name| control.name_matches(name)))
+            .collect();
+        if outputs.is_empty() {
+            return Err(LoxoneError::not_found(format!(
+                "No light outputs found for '{target}'{}",
+                output
+                    .map(|name| format!(" named '{name}'"))
+                    .unwrap_or_default()
+            )));
+        }
+        Ok(outputs)
+    }
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+    /// Current colours of colour outputs, by UUID
+    async fn current_colors(
+        &self,
+        outputs: &[&LoxoneControl],
+    ) -> crate::error::Result<std::collections::HashMap<String, lighting::LightColor>> {
+        let color_outputs: Vec<&LoxoneControl> = outputs
+            .iter()
+            .copied()
+            .filter(|control| lighting::is_color_output(control))
+            .collect();
+        let values = self.read_state_values(&color_outputs, &["color"]).await?;
+        Ok(color_outputs
+            .iter()
+            .filter_map(|control| {
+                let value = climate::state_value(control, "color", &values)?;
+                let color = lighting::LightColor::parse(value.as_str()?)?;
+                Some((control.uuid.clone(), color))
+            })
+            .collect())
+    }
 
-        let mut light_uuids = Vec::new();
-        let mut light_info = Vec::new();
+    /// Moods of a light controller, read from its live mood list
+    async fn controller_moods(
+        &self,
+        control: &LoxoneControl,
+    ) -> crate::error::Result<Vec<moods::LiveMood>> {
+        let values = self
+            .read_state_values(&[control], moods::MOOD_STATES)
+            .await?;
+        Ok(moods::mood_list(control, &values))
+    }
+
+    /// Send commands to a control one after the other, stopping at the first failure
+    async fn send_commands(
+        &self,
+        control: &LoxoneControl,
+        commands: Vec<crate::error::Result<String>>,
+    ) -> crate::error::Result<Vec<String>> {
+        let mut sent = Vec::new();
+        for command in commands {
+            let command = command?;
+            self.send_control_command(control, &command).await?;
+            sent.push(command);
+        }
+        Ok(sent)
+    }
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    /// Send a read command (queue, favorites) to an audio zone and return its items
+    ///
+    /// Reads change nothing, so they are validated but not audited.
+    async fn read_audio_list(
+        &self,
+        control: &LoxoneControl,
+        command: &str,
+    ) -> crate::error::Result<Vec<Value>> {
+        control.validate_command(command)?;
+        let response = self
+            .get_client()?
+            .send_command(&control.uuid, command)
+            .await
+            .map_err(|e| {
+                LoxoneError::connection(format!(
+                    "Failed to read '{command}' from {}: {e}",
+                    control.name
+                ))
+            })?;
+        Ok(audio::list_items(&response.value))
+    }
 
-            if matches!(
-                control_type,
-                "Switch" | "Dimmer" | "LightController" | "ColorPicker"
-            ) {
-                light_uuids.push(uuid.clone());
-                light_info.push((uuid.clone(), control.clone()));
+    /// Read named states of controls
+    ///
+    /// Values come from the Miniserver, overlaid with values pushed over the
+    /// WebSocket connection: text states such as mood lists or track titles
+    /// are only reported that way.
+    pub(super) async fn read_state_values(
+        &self,
+        controls: &[&LoxoneControl],
+        names: &[&str],
+    ) -> crate::error::Result<std::collections::HashMap<String, Value>> {
+        let state_uuids: Vec<String> = controls
+            .iter()
+            .flat_map(|control| names.iter().filter_map(|name| control.state_uuid(name)))
+            .map(str::to_string)
+            .collect();
+        if state_uuids.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+        let mut values = self
+            .get_client()?
+            .get_state_values(&state_uuids)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to read {} states: {e}", names.join("/"));
+                std::collections::HashMap::new()
+            });
+        if let Some(context) = &self.context {
+            for uuid in &state_uuids {
+                if let Some(value) = context.get_state_value(uuid).await {
+                    values.insert(uuid.clone(), value);
+                }
             }
         }
+        Ok(values)
+    }
 
-        // Fetch live states for all lights
-        let live_states = Self::fetch_live_states(client, &light_uuids).await;
+    /// Latest value of a state: pushed over the WebSocket, else read from the Miniserver
+    pub(super) async fn state_value(&self, state_uuid: &str) -> Option<Value> {
+        if let Some(context) = &self.context
+            && let Some(value) = context.get_state_value(state_uuid).await
+        {
+            return Some(value);
+        }
+        match self
+            .get_client()
+            .ok()?
+            .get_state_values(&[state_uuid.to_string()])
+            .await
+        {
+            Ok(mut values) => values.remove(state_uuid),
+            Err(e) => {
+                warn!("Failed to read state {state_uuid}: {e}");
+                None
+            }
+        }
+    }
 
-        let lights: Vec<Value> = light_info
+    /// Describe controls together with their live state under `state_key`
+    pub(super) async fn describe_controls(
+        &self,
+        model: &ControlModel,
+        controls: &[&LoxoneControl],
+        state_key: &str,
+    ) -> crate::error::Result<Vec<Value>> {
+        let client = self.get_client()?;
+        let uuids: Vec<String> = controls
             .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+            .map(|control| control.uuid.clone())
+            .collect();
+        let live_states = Self::fetch_live_states(client, &uuids).await;
 
+        Ok(controls
+            .iter()
+            .map(|control| {
+                let room = control.room.as_deref().unwrap_or("Unknown");
                 json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
+                    "uuid": control.uuid,
+                    "name": control.name,
+                    "type": control.type_name(),
                     "room": room,
-                    "state": state
+                    "room_name": model.room_name(room),
+                    state_key: live_states.get(&control.uuid).cloned().unwrap_or(Value::Null)
                 })
             })
-            .collect();
-
-        Ok(json!({
-            "lights": lights,
-            "count": lights.len()
-        }))
+            .collect())
     }
 
-    // ========================================================================
-    // CLIMATE TOOLS
-    // ========================================================================
-
-    /// Control room temperature settings
+    /// Find a control by UUID or name, or fail with a tool error
     ///
-    /// Set target temperature for a room or zone. Supports heating and cooling modes.
-    pub async fn set_temperature(
+    /// Names match fuzzily, in German or English and together with the room
+    /// name; an ambiguous name fails with the candidates to choose from.
+    pub(super) fn resolve_control<'a>(
         &self,
-        room: String,
-        temperature: f64,
+        model: &'a ControlModel,
+        identifier: &str,
+    ) -> crate::error::Result<&'a LoxoneControl> {
+        self.names
+            .resolve_control(model, identifier)
+            .into_result("Device", identifier)
+    }
+
+    /// Controls with statistics enabled, optionally matching a UUID or name
+    pub(super) fn statistic_controls<'a>(
+        model: &'a ControlModel,
+        device: Option<&str>,
+    ) -> Vec<(&'a LoxoneControl, &'a StatisticConfig)> {
+        let mut controls: Vec<_> = model
+            .controls()
+            .flat_map(LoxoneControl::flatten)
+            .filter_map(|control| Some((control, control.statistic.as_ref()?)))
+            .filter(|(control, _)| {
+                device.is_none_or(|device| control.uuid == device || control.name_matches(device))
+            })
+            .collect();
+        // An exact name match wins over partial ones
+        if let Some(device) = device
+            && let Some(exact) = controls.iter().find(|(control, _)| {
+                control.uuid == device || control.name.eq_ignore_ascii_case(device)
+            })
+        {
+            controls = vec![*exact];
+        }
+        controls.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        controls
+    }
+
+    /// Validate a command against the command catalog and send it
+    pub(super) async fn send_control_command(
+        &self,
+        control: &LoxoneControl,
+        command: &str,
+    ) -> crate::error::Result<LoxoneResponse> {
+        if let Err(e) = control.validate_command(command) {
+            self.record_refused(control, command, &e.to_string()).await;
+            return Err(e);
+        }
+        self.authorize_security(&[(control, command)]).await?;
+        self.send_audited(self.get_client()?, control, command)
+            .await
+            .map_err(|e| {
+                LoxoneError::connection(format!(
+                    "Failed to send '{command}' to {}: {e}",
+                    control.name
+                ))
+            })
+    }
+
+    /// Send a command and record it in the audit log
+    async fn send_audited(
+        &self,
+        client: &Arc<dyn LoxoneClient>,
+        control: &LoxoneControl,
+        command: &str,
+    ) -> crate::error::Result<LoxoneResponse> {
+        let started = std::time::Instant::now();
+        let result = client.send_command(&control.uuid, command).await;
+        self.record_audit(control, command, &result, started).await;
+        result
+    }
+
+    /// Record a sent command in the audit log, if enabled
+    async fn record_audit(
+        &self,
+        control: &LoxoneControl,
+        command: &str,
+        result: &crate::error::Result<LoxoneResponse>,
+        started: std::time::Instant,
+    ) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+        let outcome = match result {
+            Ok(response) if response.code == 200 => AuditOutcome::Sent,
+            _ => AuditOutcome::Failed,
+        };
+        let caller = AuditCaller::current();
+        audit
+            .record(
+                AuditEntry {
+                    timestamp: chrono::Utc::now(),
+                    tool: caller.tool,
+                    client: caller.client,
+                    device_uuid: control.uuid.clone(),
+                    device_name: Some(control.name.clone()),
+                    command: command.to_string(),
+                    response_code: result.as_ref().ok().map(|response| response.code),
+                    error: result.as_ref().err().map(ToString::to_string),
+                    latency_ms: started.elapsed().as_millis() as u64,
+                },
+                outcome,
+                control.control_type.is_security(),
+            )
+            .await;
+    }
+
+    /// Record a command that was refused before reaching the Miniserver
+    ///
+    /// Covers commands rejected by validation and those the user did not
+    /// approve; `command` is empty if no command could be built.
+    async fn record_refused(&self, control: &LoxoneControl, command: &str, error: &str) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+        let caller = AuditCaller::current();
+        audit
+            .record(
+                AuditEntry {
+                    timestamp: chrono::Utc::now(),
+                    tool: caller.tool,
+                    client: caller.client,
+                    device_uuid: control.uuid.clone(),
+                    device_name: Some(control.name.clone()),
+                    command: command.to_string(),
+                    response_code: None,
+                    error: Some(error.to_string()),
+                    latency_ms: 0,
+                },
+                AuditOutcome::Refused,
+                control.control_type.is_security(),
+            )
+            .await;
+    }
+
+    /// Send one command per control in parallel and audit every result
+    ///
+    /// Token clients send the batch through
+    /// [`TokenHttpClient::control_devices_parallel`](crate::client::TokenHttpClient::control_devices_parallel),
+    /// which asks for bulk consent when configured; other clients send the
+    /// commands concurrently one by one.
+    async fn send_parallel(
+        &self,
+        commands: &[(&LoxoneControl, String)],
+    ) -> crate::error::Result<Vec<crate::error::Result<LoxoneResponse>>> {
+        let client = self.get_client()?;
+        let pending: Vec<(&LoxoneControl, &str)> = commands
+            .iter()
+            .map(|(control, command)| (*control, command.as_str()))
+            .collect();
+        self.authorize_security(&pending).await?;
+        let started = std::time::Instant::now();
+
+        #[cfg(feature = "crypto-openssl")]
+        let batch = match client
+            .as_any()
+            .downcast_ref::<crate::client::TokenHttpClient>()
+        {
+            Some(token_client) => Some(
+                token_client
+                    .control_devices_parallel(
+                        commands
+                            .iter()
+                            .map(|(control, command)| (control.uuid.clone(), command.clone()))
+                            .collect(),
+                    )
+                    .await
+                    .map_err(|e| LoxoneError::connection(format!("Bulk command failed: {e}")))?,
+            ),
+            None => None,
+        };
+        #[cfg(not(feature = "crypto-openssl"))]
+        let batch: Option<Vec<crate::error::Result<LoxoneResponse>>> = None;
+
+        let results = match batch {
+            Some(results) => results,
+            None => {
+                futures::future::join_all(
+                    commands
+                        .iter()
+                        .map(|(control, command)| client.send_command(&control.uuid, command)),
+                )
+                .await
+            }
+        };
+
+        for ((control, command), result) in commands.iter().zip(&results) {
+            self.record_audit(control, command, result, started).await;
+        }
+        Ok(results)
+    }
+
+    /// Build a command per control, send it and collect per-control results
+    ///
+    /// Controls whose command fails catalog validation are reported as
+    /// `rejected` without contacting the Miniserver.
+    async fn send_to_controls<F>(
+        &self,
+        controls: &[&LoxoneControl],
+        build: F,
+    ) -> crate::error::Result<Vec<Value>>
+    where
+        F: Fn(&LoxoneControl) -> crate::error::Result<String>,
+    {
+        let client = self.get_client()?;
+        let commands: Vec<_> = controls
+            .iter()
+            .map(|control| (*control, build(control)))
+            .collect();
+        let sendable: Vec<(&LoxoneControl, &str)> = commands
+            .iter()
+            .filter_map(|(control, command)| Some((*control, command.as_deref().ok()?)))
+            .collect();
+        self.authorize_security(&sendable).await?;
+
+        let mut results = Vec::new();
+        for (control, command) in commands {
+            let command = match command {
+                Ok(command) => command,
+                Err(e) => {
+                    self.record_refused(control, "", &e.to_string()).await;
+                    results.push(json!({
+                        "uuid": control.uuid,
+                        "name": control.name,
+                        "status": "rejected",
+                        "error": e.to_string()
+                    }));
+                    continue;
+                }
+            };
+            match self.send_audited(client, control, &command).await {
+                Ok(response) => {
+                    results.push(json!({
+                        "uuid": control.uuid,
+                        "name": control.name,
+                        "command_sent": command,
+                        "status": "executed",
+                        "miniserver_response": response.value
+                    }));
+                }
+                Err(e) => {
+                    results.push(json!({
+                        "uuid": control.uuid,
+                        "name": control.name,
+                        "command_sent": command,
+                        "status": "error",
+                        "error": format!("{e}")
+                    }));
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Lighting command for a control: dimmers take the level, everything
+    /// else is switched on or off
+    pub(super) fn light_command(
+        control: &LoxoneControl,
+        action: &str,
+        level: Option<u8>,
+    ) -> crate::error::Result<String> {
+        match (level, &control.control_type) {
+            (Some(level), ControlType::Dimmer | ControlType::EIBDimmer) => {
+                control.build_value_command(f64::from(level))
+            }
+            (Some(0), _) => control.build_command("off", &[]),
+            (Some(_), _) => control.build_command("on", &[]),
+            (None, _) => control.build_command(action, &[]),
+        }
+    }
+
+    /// Connection state and version, as reported by `get_server_status`
+    pub(super) fn server_status(&self) -> Value {
+        let connected = self.context.is_some() && self.client.is_some();
+
+        json!({
+            "connected": connected,
+            "version": env!("CARGO_PKG_VERSION"),
+            "name": "Loxone MCP Server"
+        })
+    }
+
+    /// Window monitors and digital inputs named after doors or windows
+    pub(super) fn is_door_window(control: &LoxoneControl) -> bool {
+        control.control_type == ControlType::WindowMonitor
+            || (control.control_type == ControlType::InfoOnlyDigital
+                && ["door", "window", "tür", "fenster"]
+                    .iter()
+                    .any(|keyword| control.name_matches(keyword)))
+    }
+
+    /// Controls matching `filter` with their live `state_key`, listed under `key`
+    ///
+    /// The read-only status tools and the resources showing the same devices
+    /// share this listing, cut to `max_devices_per_query`.
+    pub(super) async fn device_listing<F>(
+        &self,
+        key: &str,
+        state_key: &str,
+        filter: F,
+    ) -> crate::error::Result<Value>
+    where
+        F: Fn(&LoxoneControl) -> bool,
+    {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let mut controls = model.filter(filter);
+        let total = self.limit_listing(&mut controls);
+        let described = self.describe_controls(&model, &controls, state_key).await?;
+
+        Ok(json!({
+            key: described,
+            "count": described.len(),
+            "total": total,
+            "truncated": described.len() < total
+        }))
+    }
+
+    /// Devices of a room, or all of them, as listed by `list_devices`
+    pub(super) async fn device_inventory(&self, room: Option<&str>) -> crate::error::Result<Value> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let room_filter = room.map(str::to_lowercase);
+
+        let mut devices: Vec<_> = model
+            .filter(|control| {
+                let Some(ref room_filter) = room_filter else {
+                    return true;
+                };
+                control.room.as_deref().is_some_and(|room_uuid| {
+                    room_uuid.to_lowercase().contains(room_filter)
+                        || model
+                            .room_name(room_uuid)
+                            .is_some_and(|name| name.to_lowercase().contains(room_filter))
+                })
+            })
+            .into_iter()
+            .map(|control| {
+                let room = control.room.as_deref().unwrap_or("Unknown");
+                let category = control.category.as_deref().unwrap_or("Unknown");
+                json!({
+                    "uuid": control.uuid,
+                    "name": control.name,
+                    "type": control.type_name(),
+                    "room": room,
+                    "room_name": model.room_name(room),
+                    "category": category,
+                    "category_name": model.category_name(category)
+                })
+            })
+            .collect();
+
+        let total = self.limit_listing(&mut devices);
+
+        Ok(json!({
+            "devices": devices,
+            "count": devices.len(),
+            "total": total,
+            "truncated": devices.len() < total,
+            "filter": room
+        }))
+    }
+
+    /// Room controllers with their climate state, as reported by `get_climate_status`
+    pub(super) async fn climate_status(&self, room: Option<&str>) -> crate::error::Result<Value> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let mut controls = match room {
+            Some(room) => self.climate_targets(&model, room)?,
+            None => model.of_type(ControlType::is_room_controller),
+        };
+        let total = self.limit_listing(&mut controls);
+        let mut climate_controllers = self.describe_controls(&model, &controls, "state").await?;
+
+        let buildings = climate::climate_controllers(&model);
+        let state_uuids: Vec<String> = controls
+            .iter()
+            .flat_map(|control| {
+                climate::STATUS_STATES
+                    .iter()
+                    .filter_map(|name| control.state_uuid(name))
+            })
+            .chain(
+                buildings
+                    .iter()
+                    .filter_map(|control| control.state_uuid(climate::PERIOD_STATE)),
+            )
+            .map(str::to_string)
+            .collect();
+        let values = if state_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            self.get_client()?
+                .get_state_values(&state_uuids)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Failed to read climate states: {e}");
+                    std::collections::HashMap::new()
+                })
+        };
+
+        for (entry, control) in climate_controllers.iter_mut().zip(&controls) {
+            entry["climate"] = climate::climate_status(control, &values);
+        }
+        let periods: Vec<Value> = buildings
+            .iter()
+            .map(|control| {
+                json!({
+                    "uuid": control.uuid,
+                    "name": control.name,
+                    "period": climate::heating_period(control, &values)
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "climate_controllers": climate_controllers,
+            "count": climate_controllers.len(),
+            "total": total,
+            "truncated": climate_controllers.len() < total,
+            "heating_cooling": periods
+        }))
+    }
+
+    /// Audio zones with their playback state, as reported by `get_audio_status`
+    pub(super) async fn audio_status(&self, zone: Option<&str>) -> crate::error::Result<Value> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let mut controls = match zone {
+            Some(zone) => self.audio_targets(&model, zone)?,
+            None => model.of_type(ControlType::is_audio),
+        };
+        let total = self.limit_listing(&mut controls);
+        let mut audio_zones = self.describe_controls(&model, &controls, "state").await?;
+
+        let values = self
+            .read_state_values(&controls, audio::STATUS_STATES)
+            .await?;
+
+        let zones = audio::zones_by_player(&model);
+        for (entry, control) in audio_zones.iter_mut().zip(&controls) {
+            entry["audio"] = audio::audio_status(control, &values, &zones);
+        }
+
+        Ok(json!({
+            "audio_zones": audio_zones,
+            "count": audio_zones.len(),
+            "total": total,
+            "truncated": audio_zones.len() < total
+        }))
+    }
+
+    /// Fetch live state for a list of UUIDs and return a mapping from UUID to state value.
+    pub(super) async fn fetch_live_states(
+        client: &Arc<dyn LoxoneClient>,
+        uuids: &[String],
+    ) -> std::collections::HashMap<String, Value> {
+        if uuids.is_empty() {
+            return std::collections::HashMap::new();
+        }
+        match client.get_device_states(uuids).await {
+            Ok(states) => states,
+            Err(e) => {
+                warn!("Failed to fetch live device states: {e}");
+                std::collections::HashMap::new()
+            }
+        }
+    }
+}
+
+/// All MCP tools defined in a single impl block
+#[mcp_tools]
+impl LoxoneMcpServer {
+    // ========================================================================
+    // LIGHTING TOOLS
+    // ========================================================================
+
+    /// Control lights in a room, by device, or system-wide
+    ///
+    /// Unified lighting control with scope-based targeting. Supports:
+    /// - scope: "device" (single light), "room" (all lights in room), "system" (all lights)
+    /// - action: "on", "off", "dim", "bright"
+    /// - brightness: 0-100 for dimming (optional)
+    pub async fn control_lights(
+        &self,
+        scope: String,
+        target: Option<String>,
+        action: String,
+        brightness: Option<u8>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        // Normalize action (multi-language support)
+        let normalized_action = match action.to_lowercase().as_str() {
+            "on" | "ein" | "an" | "einschalten" => "on",
+            "off" | "aus" | "ab" | "ausschalten" => "off",
+            "dim" | "dimmen" => "dim",
+            "bright" | "hell" => "bright",
+            _ => {
+                return Err(format!(
+                    "Invalid action '{action}'. Supported: on, off, dim, bright"
+                ));
+            }
+        };
+
+        // Validate brightness
+        if let Some(level) = brightness
+            && level > 100
+        {
+            return Err("Brightness must be between 0-100".to_string());
+        }
+
+        // Level from the normalized action + brightness; plain on/off otherwise
+        let level = match (normalized_action, brightness) {
+            (_, Some(level)) => Some(level),
+            ("dim", None) => Some(25),     // default dim level
+            ("bright", None) => Some(100), // full brightness
+            _ => None,
+        };
+        let command = match level {
+            Some(level) => level.to_string(),
+            None => normalized_action.to_string(),
+        };
+        let build =
+            |control: &LoxoneControl| Self::light_command(control, normalized_action, level);
+
+        let model = self.control_model().await?;
+
+        match scope.to_lowercase().as_str() {
+            "device" => {
+                let target_id = target
+                    .as_deref()
+                    .ok_or_else(|| "target is required when scope is 'device'".to_string())?;
+                let control = self.resolve_control(&model, target_id)?;
+                let command = build(control).map_err(|e| e.to_string())?;
+                let response = self.send_control_command(control, &command).await?;
+                Ok(json!({
+                    "scope": "device",
+                    "target": target_id,
+                    "uuid": control.uuid,
+                    "action": normalized_action,
+                    "brightness": brightness,
+                    "command_sent": command,
+                    "status": "executed",
+                    "miniserver_response": response.value
+                }))
+            }
+            "room" => {
+                let room_name = target.as_deref().ok_or_else(|| {
+                    "target (room name) is required when scope is 'room'".to_string()
+                })?;
+                let room_uuid = self.resolve_room(&model, room_name)?;
+                let controls = model.of_type_in_room(room_uuid, ControlType::is_lighting);
+                if controls.is_empty() {
+                    return Err(format!("No lights found in room '{room_name}'"));
+                }
+                let results = self.send_to_controls(&controls, build).await?;
+                Ok(json!({
+                    "scope": "room",
+                    "target": room_name,
+                    "action": normalized_action,
+                    "brightness": brightness,
+                    "command_sent": command,
+                    "devices_affected": results.len(),
+                    "results": results
+                }))
+            }
+            "system" => {
+                let controls = model.of_type(ControlType::is_lighting);
+                if controls.is_empty() {
+                    return Err("No lights found in the system".to_string());
+                }
+                let results = self.send_to_controls(&controls, build).await?;
+                Ok(json!({
+                    "scope": "system",
+                    "action": normalized_action,
+                    "brightness": brightness,
+                    "command_sent": command,
+                    "devices_affected": results.len(),
+                    "results": results
+                }))
+            }
+            _ => Err(format!(
+                "Invalid scope '{scope}'. Use: device, room, system"
+            )),
+        }
+    }
+
+    /// Get the current state of all lights
+    ///
+    /// Returns a list of all lighting devices with their current state,
+    /// brightness level, and room location.
+    pub async fn get_lights_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let mut controls = model.of_type(ControlType::is_lighting);
+        let total = self.limit_listing(&mut controls);
+        let lights = self.describe_controls(&model, &controls, "state").await?;
+
+        Ok(json!({
+            "lights": lights,
+            "count": lights.len(),
+            "total": total,
+            "truncated": lights.len() < total
+        }))
+    }
+
+    // ========================================================================
+    // CLIMATE TOOLS
+    // ========================================================================
+
+    /// Control room temperature settings
+    ///
+    /// Set target temperature for a room or zone. Supports heating and cooling modes.
+    pub async fn set_temperature(
+        &self,
+        room: String,
+        temperature: f64,
         mode: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        if !(5.0..=35.0).contains(&temperature) {
-            return Err("Temperature must be between 5°C and 35°C".to_string());
+        if !(5.0..=35.0).contains(&temperature) {
+            return Err("Temperature must be between 5°C and 35°C".to_string());
+        }
+
+        let mode = mode.unwrap_or_else(|| "auto".to_string());
+        if !["heat", "cool", "auto", "off"].contains(&mode.as_str()) {
+            return Err(format!("Invalid mode '{mode}'. Use: heat, cool, auto, off"));
+        }
+
+        let model = self.control_model().await?;
+
+        // Try to find the thermostat: first by direct UUID/name, then by room
+        let targets = self.climate_targets(&model, &room)?;
+
+        let results = self
+            .send_to_controls(&targets, |control| match control.control_type {
+                ControlType::IRoomControllerV2 => {
+                    control.build_command("setComfortTemperature", &[&temperature])
+                }
+                // Index 1 is the comfort temperature of the legacy controller
+                _ => control.build_command("settemp", &[&1, &temperature]),
+            })
+            .await?;
+
+        // Also send mode command if not "auto" (the default)
+        if mode != "auto" {
+            for control in &targets {
+                let mode_command = match (&control.control_type, mode.as_str()) {
+                    (ControlType::IRoomControllerV2, "heat") => "setOperatingMode/1",
+                    (ControlType::IRoomControllerV2, "cool") => "setOperatingMode/2",
+                    (ControlType::IRoomControllerV2, _) => {
+                        warn!("{} has no 'off' operating mode", control.name);
+                        continue;
+                    }
+                    (_, "heat") => "setmode/1",
+                    (_, "cool") => "setmode/2",
+                    _ => "setmode/0",
+                };
+                if let Err(e) = self.send_control_command(control, mode_command).await {
+                    warn!("Failed to set mode on {}: {e}", control.uuid);
+                }
+            }
+        }
+
+        Ok(json!({
+            "room": room,
+            "target_temperature": temperature,
+            "mode": mode,
+            "controllers_affected": results.len(),
+            "results": results
+        }))
+    }
+
+    /// Get current climate status for all rooms
+    ///
+    /// Reports each room controller's live state with its temperatures, comfort
+    /// and eco settings, operating and active mode, open-window detection and
+    /// active overrides, plus whether the building is heating or cooling.
+    /// Optionally limited to one `room` (or controller name).
+    pub async fn get_climate_status(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        Ok(self.climate_status(room.as_deref()).await?)
+    }
+
+    /// Set the operating mode of room controllers
+    ///
+    /// `mode`: auto, auto_heating (heat), auto_cooling (cool), manual,
+    /// manual_heating or manual_cooling. `room` is a room or controller name.
+    pub async fn set_climate_mode(
+        &self,
+        room: String,
+        mode: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let mode_id = climate::operating_mode_id(&mode)?;
+        let model = self.control_model().await?;
+        let targets = self.climate_v2_targets(&model, &room)?;
+        let results = self
+            .send_to_controls(&targets, |control| {
+                control.build_command("setOperatingMode", &[&mode_id])
+            })
+            .await?;
+
+        Ok(json!({
+            "room": room,
+            "operating_mode": climate::OPERATING_MODES[mode_id],
+            "controllers_affected": results.len(),
+            "results": results
+        }))
+    }
+
+    /// Set comfort temperatures of room controllers
+    ///
+    /// `heating` and `cooling` are the comfort temperatures in °C, `tolerance`
+    /// the allowed deviation before heating or cooling starts (0.5-3°C).
+    /// Eco temperatures follow from these and the absent offsets.
+    pub async fn set_comfort_temperatures(
+        &self,
+        room: String,
+        heating: Option<f64>,
+        cooling: Option<f64>,
+        tolerance: Option<f64>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let settings: Vec<(&str, f64)> = [
+            ("setComfortTemperature", heating),
+            ("setComfortTemperatureCool", cooling),
+            ("setComfortTolerance", tolerance),
+        ]
+        .into_iter()
+        .filter_map(|(command, value)| Some((command, value?)))
+        .collect();
+        if settings.is_empty() {
+            return Err("Give at least one of heating, cooling or tolerance".to_string());
         }
 
-        let mode = mode.unwrap_or_else(|| "auto".to_string());
-        if !["heat", "cool", "auto", "off"].contains(&mode.as_str()) {
-            return Err(format!("Invalid mode '{mode}'. Use: heat, cool, auto, off"));
+        let model = self.control_model().await?;
+        let targets = self.climate_v2_targets(&model, &room)?;
+        let mut results = Vec::new();
+        for (command, value) in settings {
+            results.extend(
+                self.send_to_controls(&targets, |control| {
+                    control.build_command(command, &[&value])
+                })
+                .await?,
+            );
         }
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+        Ok(json!({
+            "room": room,
+            "heating": heating,
+            "cooling": cooling,
+            "tolerance": tolerance,
+            "results": results
+        }))
+    }
+
+    /// Override the schedule of room controllers for a while
+    ///
+    /// `mode`: eco, comfort, building_protection or manual; `stop` ends the
+    /// active override. The override lasts `duration_minutes` (default 60);
+    /// `temperature` optionally sets the target for its duration.
+    pub async fn set_climate_override(
+        &self,
+        room: String,
+        mode: String,
+        duration_minutes: Option<u32>,
+        temperature: Option<f64>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-        let climate_types = &["IRoomController", "Intelligent Room Controller"];
+        let model = self.control_model().await?;
+        let targets = self.climate_v2_targets(&model, &room)?;
 
-        // Try to find the thermostat: first by direct UUID/name, then by room
-        let thermostat = Self::find_control_by_id_or_name(&structure, &room);
-        let targets: Vec<(&String, &Value)> = if let Some(target) = thermostat {
-            // Check it's actually a climate controller
-            let control_type = target.1.get("type").and_then(|v| v.as_str()).unwrap_or("");
-            if climate_types.contains(&control_type) {
-                vec![target]
-            } else {
-                // Not a climate controller, search by room
-                Self::find_climate_in_room(&structure, &room, climate_types)?
+        if matches!(mode.to_lowercase().as_str(), "stop" | "end" | "cancel") {
+            let results = self
+                .send_to_controls(&targets, |control| {
+                    control.build_command("stopOverride", &[])
+                })
+                .await?;
+            return Ok(json!({
+                "room": room,
+                "override": "stopped",
+                "results": results
+            }));
+        }
+
+        let mode_id = climate::temperature_mode_id(&mode)?;
+        let duration = duration_minutes.unwrap_or(60).clamp(1, 7 * 24 * 60);
+        let until = climate::override_until(duration);
+        let results = self
+            .send_to_controls(&targets, |control| match temperature {
+                Some(temperature) => {
+                    control.build_command("override", &[&mode_id, &until, &temperature])
+                }
+                None => control.build_command("override", &[&mode_id, &until]),
+            })
+            .await?;
+
+        Ok(json!({
+            "room": room,
+            "mode": climate::TEMPERATURE_MODES[mode_id],
+            "duration_minutes": duration,
+            "until": chrono::DateTime::from_timestamp(
+                until + crate::client::binary_protocol::LOXONE_EPOCH_UNIX,
+                0
+            )
+            .map(|time| time.to_rfc3339()),
+            "temperature": temperature,
+            "results": results
+        }))
+    }
+
+    /// Get the weekly heating/cooling schedule of room controllers
+    ///
+    /// Lists the timer entries per day with start, end and temperature mode.
+    /// Schedules pushed over the WebSocket connection are used as they arrive;
+    /// until then they are read from the Miniserver.
+    pub async fn get_climate_schedule(
+        &self,
+        room: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let targets = self.climate_v2_targets(&model, &room)?;
+
+        let mut schedules = Vec::new();
+        for control in targets {
+            let Some(timer) = climate::schedule_timer(control) else {
+                schedules.push(json!({
+                    "uuid": control.uuid,
+                    "name": control.name,
+                    "error": "Controller has no schedule"
+                }));
+                continue;
+            };
+            let event = match timer.state_uuid(climate::SCHEDULE_STATE) {
+                Some(uuid) => self
+                    .state_value(uuid)
+                    .await
+                    .and_then(climate::daytimer_event),
+                None => None,
+            };
+            schedules.push(match event {
+                Some(event) => json!({
+                    "uuid": control.uuid,
+                    "name": control.name,
+                    "schedule_uuid": timer.uuid,
+                    "entries": climate::schedule_entries(&event, model.operating_modes())
+                }),
+                None => json!({
+                    "uuid": control.uuid,
+                    "name": control.name,
+                    "schedule_uuid": timer.uuid,
+                    "entries": [],
+                    "message": "The Miniserver reported no schedule for this controller"
+                }),
+            });
+        }
+
+        Ok(json!({
+            "room": room,
+            "days": model.operating_modes(),
+            "schedules": schedules
+        }))
+    }
+
+    /// Replace the weekly schedule of a room controller
+    ///
+    /// `entries` like "Monday 06:00-22:00 comfort" or "Sonntag 08:00-23:00 eco":
+    /// a day (calendar mode name or ID), a time range and optionally a
+    /// temperature mode (default comfort) and `on_presence`. Replaces all
+    /// entries; times outside the entries use the eco temperature.
+    pub async fn set_climate_schedule(
+        &self,
+        room: String,
+        entries: Vec<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let targets = self.climate_v2_targets(&model, &room)?;
+        let [control] = targets.as_slice() else {
+            let names: Vec<&str> = targets.iter().map(|c| c.name.as_str()).collect();
+            return Err(format!(
+                "'{room}' matches several controllers ({}); name one",
+                names.join(", ")
+            ));
+        };
+        let timer = climate::schedule_timer(control)
+            .ok_or_else(|| format!("{} has no schedule", control.name))?;
+
+        let parsed = entries
+            .iter()
+            .map(|entry| climate::parse_schedule_entry(entry, model.operating_modes()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let command = climate::schedule_command(&parsed);
+        let response = self.send_control_command(timer, &command).await?;
+
+        let event = DaytimerEvent {
+            uuid: timer.uuid.clone(),
+            default_value: 0.0,
+            entries: parsed,
+        };
+        Ok(json!({
+            "uuid": control.uuid,
+            "name": control.name,
+            "schedule_uuid": timer.uuid,
+            "entries": climate::schedule_entries(&event, model.operating_modes()),
+            "command_sent": command,
+            "miniserver_response": response.value
+        }))
+    }
+
+    // ========================================================================
+    // BLINDS/ROLLADEN TOOLS
+    // ========================================================================
+
+    /// Control blinds/rolladen position
+    ///
+    /// Set blind position (0=fully open, 100=fully closed) or use actions like up/down/stop.
+    pub async fn control_blinds(
+        &self,
+        target: String,
+        action: Option<String>,
+        position: Option<u8>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        // Determine command based on action or position
+        let name = if position.is_some() {
+            "ManualPosition"
+        } else if let Some(ref act) = action {
+            match act.to_lowercase().as_str() {
+                "up" | "open" | "auf" => "FullUp",
+                "down" | "close" | "ab" | "zu" => "FullDown",
+                "stop" | "halt" => "stop",
+                "shade" | "schatten" => "shade",
+                _ => {
+                    return Err(format!(
+                        "Invalid action '{act}'. Use: up, down, stop, shade"
+                    ));
+                }
+            }
+        } else {
+            return Err("Either action or position must be provided".to_string());
+        };
+
+        // Target can be a UUID or a device name
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, &target)?;
+        let command = match position {
+            Some(pos) => control.build_command(name, &[&pos]),
+            None => control.build_command(name, &[]),
+        }
+        .map_err(|e| e.to_string())?;
+        let response = self.send_control_command(control, &command).await?;
+
+        Ok(json!({
+            "target": target,
+            "uuid": control.uuid,
+            "action": action,
+            "position": position,
+            "command_sent": command,
+            "status": "executed",
+            "miniserver_response": response.value
+        }))
+    }
+
+    /// Get status of all blinds/rolladen
+    pub async fn get_blinds_status(&self) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let mut controls = model.of_type(ControlType::is_blind);
+        let total = self.limit_listing(&mut controls);
+        let blinds = self.describe_controls(&model, &controls, "state").await?;
+
+        Ok(json!({
+            "blinds": blinds,
+            "count": blinds.len(),
+            "total": total,
+            "truncated": blinds.len() < total
+        }))
+    }
+
+    // ========================================================================
+    // DISCOVERY TOOLS
+    // ========================================================================
+
+    /// List all rooms in the Loxone system
+    pub async fn list_rooms(&self) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let mut rooms: Vec<_> = model.rooms().iter().collect();
+        rooms.sort_by(|a, b| a.1.cmp(b.1));
+        let rooms: Vec<_> = rooms
+            .into_iter()
+            .map(|(uuid, name)| {
+                json!({
+                    "uuid": uuid,
+                    "name": name,
+                    "device_count": model.filter(|c| c.room.as_ref() == Some(uuid)).len()
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "rooms": rooms,
+            "count": rooms.len()
+        }))
+    }
+
+    /// List all devices in a specific room or system-wide
+    pub async fn list_devices(
+        &self,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        Ok(self.device_inventory(room.as_deref()).await?)
+    }
+
+    /// Get detailed information about a specific device
+    pub async fn get_device_info(
+        &self,
+        device_id: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let control = model
+            .find(&device_id)
+            .ok_or_else(|| format!("Device '{device_id}' not found"))?;
+
+        let commands = control
+            .commands()
+            .map(|specs| specs.iter().map(CommandSpec::usage).collect::<Vec<_>>());
+
+        Ok(json!({
+            "uuid": control.uuid,
+            "room_name": control.room.as_deref().and_then(|room| model.room_name(room)),
+            "control": control,
+            "commands": commands
+        }))
+    }
+
+    /// Send a raw command to a device
+    ///
+    /// The command is checked against the device type's command catalog
+    /// (e.g. `ManualPosition/40` for a blind, `changeTo/2` for a light
+    /// controller) before it is sent.
+    pub async fn send_device_command(
+        &self,
+        device: String,
+        command: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, &device)?;
+        let response = self.send_control_command(control, &command).await?;
+
+        Ok(json!({
+            "device": device,
+            "uuid": control.uuid,
+            "type": control.type_name(),
+            "command_sent": command,
+            "status": "executed",
+            "miniserver_response": response.value
+        }))
+    }
+
+    // ========================================================================
+    // SYSTEM TOOLS
+    // ========================================================================
+
+    /// Get server status and health information
+    pub async fn get_server_status(&self) -> std::result::Result<serde_json::Value, String> {
+        Ok(self.server_status())
+    }
+
+    // ========================================================================
+    // AUDIO TOOLS
+    // ========================================================================
+
+    /// Control an audio zone (play, pause, stop, next, previous)
+    ///
+    /// Manages playback in audio zones. Actions: play, pause, stop, next, previous, mute, unmute
+    pub async fn control_audio_zone(
+        &self,
+        zone: String,
+        action: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let normalized_action = match action.to_lowercase().as_str() {
+            "play" | "abspielen" | "start" => "play",
+            "pause" | "pausieren" => "pause",
+            "stop" | "stopp" | "anhalten" => "stop",
+            "next" | "weiter" | "nächster" => "next",
+            "previous" | "zurück" | "vorheriger" => "previous",
+            "mute" | "stumm" => "mute",
+            "unmute" | "laut" => "unmute",
+            _ => {
+                return Err(format!(
+                    "Invalid action '{action}'. Use: play, pause, stop, next, previous, mute, unmute"
+                ));
             }
-        } else {
-            Self::find_climate_in_room(&structure, &room, climate_types)?
         };
 
-        if targets.is_empty() {
-            return Err(format!("No climate controller found for room '{room}'"));
-        }
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, &zone)?;
 
-        let command = format!("settemp/{temperature}");
-        let mut results = Vec::new();
-        for (uuid, control) in &targets {
-            let name = control
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown");
-            match client.send_command(uuid, &command).await {
-                Ok(response) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "status": "executed",
-                        "miniserver_response": response.value
-                    }));
-                }
-                Err(e) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "status": "error",
-                        "error": format!("{e}")
-                    }));
-                }
-            }
-        }
+        // Map normalized actions to Loxone audio commands
+        let command = match (&control.control_type, normalized_action) {
+            (ControlType::AudioZoneV2, "next") => "next",
+            (ControlType::AudioZoneV2, "previous") => "prev",
+            (_, "next") => "queueplus",
+            (_, "previous") => "queueminus",
+            _ => normalized_action,
+        };
 
-        // Also send mode command if not "auto" (the default)
-        if mode != "auto" {
-            let mode_command = match mode.as_str() {
-                "heat" => "setmode/1",
-                "cool" => "setmode/2",
-                "off" => "setmode/0",
-                _ => "setmode/3", // auto
-            };
-            for (uuid, _) in &targets {
-                if let Err(e) = client.send_command(uuid, mode_command).await {
-                    warn!("Failed to set mode on {uuid}: {e}");
-                }
-            }
-        }
+        let response = self.send_control_command(control, command).await?;
 
         Ok(json!({
-            "room": room,
-            "target_temperature": temperature,
-            "mode": mode,
+            "zone": zone,
+            "action": normalized_action,
             "command_sent": command,
-            "controllers_affected": results.len(),
-            "results": results
+            "status": "executed",
+            "miniserver_response": response.value
         }))
     }
 
-    /// Get current climate status for all rooms
-    pub async fn get_climate_status(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Set volume for an audio zone
+    ///
+    /// Set volume level (0-100) for a specific audio zone
+    pub async fn set_audio_volume(
+        &self,
+        zone: String,
+        volume: u8,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut climate_uuids = Vec::new();
-        let mut climate_info = Vec::new();
+        if volume > 100 {
+            return Err("Volume must be between 0-100".to_string());
+        }
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, &zone)?;
+        let command = control
+            .build_command("volume", &[&volume])
+            .map_err(|e| e.to_string())?;
+        let response = self.send_control_command(control, &command).await?;
 
-            if matches!(
-                control_type,
-                "IRoomController" | "Intelligent Room Controller"
-            ) {
-                climate_uuids.push(uuid.clone());
-                climate_info.push((uuid.clone(), control.clone()));
-            }
-        }
+        Ok(json!({
+            "zone": zone,
+            "volume": volume,
+            "command_sent": command,
+            "status": "executed",
+            "miniserver_response": response.value
+        }))
+    }
 
-        let live_states = Self::fetch_live_states(client, &climate_uuids).await;
+    /// Get status of audio zones
+    ///
+    /// Every zone (or those of `zone`, a zone or room name) with its playback
+    /// state, volume, shuffle and repeat, the current track (title, artist,
+    /// album, cover URL) and the members of its sync group.
+    pub async fn get_audio_status(
+        &self,
+        zone: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        Ok(self.audio_status(zone.as_deref()).await?)
+    }
 
-        let climate_data: Vec<Value> = climate_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+    /// List the favorites of an audio zone
+    ///
+    /// Returns the room favorites of the zone and the global favorites of the
+    /// Audio Server, each with the ID and name to pass to `play_audio_favorite`.
+    pub async fn list_audio_favorites(
+        &self,
+        zone: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "room": room,
-                    "state": state
-                })
-            })
-            .collect();
+        let model = self.control_model().await?;
+        let control = self.audio_zone(&model, &zone)?;
+        let room_favorites = self.read_audio_list(control, "getroomfavs").await?;
+        let global_favorites = self.read_audio_list(control, "getfavorites").await?;
 
         Ok(json!({
-            "climate_controllers": climate_data,
-            "count": climate_data.len()
+            "zone": control.name,
+            "uuid": control.uuid,
+            "room_favorites": room_favorites,
+            "global_favorites": global_favorites
         }))
     }
 
-    // ========================================================================
-    // BLINDS/ROLLADEN TOOLS
-    // ========================================================================
-
-    /// Control blinds/rolladen position
+    /// Play a favorite in an audio zone
     ///
-    /// Set blind position (0=fully open, 100=fully closed) or use actions like up/down/stop.
-    pub async fn control_blinds(
+    /// `favorite` is a favorite ID or name; room favorites are used unless
+    /// `global` is true.
+    pub async fn play_audio_favorite(
         &self,
-        target: String,
-        action: Option<String>,
-        position: Option<u8>,
+        zone: String,
+        favorite: String,
+        global: Option<bool>,
     ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        // Determine command based on action or position
-        let command = if let Some(pos) = position {
-            if pos > 100 {
-                return Err("Position must be between 0-100".to_string());
-            }
-            format!("ManualPosition/{pos}")
-        } else if let Some(ref act) = action {
-            match act.to_lowercase().as_str() {
-                "up" | "open" | "auf" => "FullUp".to_string(),
-                "down" | "close" | "ab" | "zu" => "FullDown".to_string(),
-                "stop" | "halt" => "Stop".to_string(),
-                "shade" | "schatten" => "Shade".to_string(),
-                _ => {
-                    return Err(format!(
-                        "Invalid action '{act}'. Use: up, down, stop, shade"
-                    ));
-                }
-            }
+        let global = global.unwrap_or(false);
+        let (list_command, play_command) = if global {
+            ("getfavorites", "playFav")
         } else {
-            return Err("Either action or position must be provided".to_string());
+            ("getroomfavs", "playZoneFav")
         };
-
-        let client = self.get_client()?;
-
-        // Target can be a UUID or a device name; send command directly
-        let response = client
-            .send_command(&target, &command)
-            .await
-            .map_err(|e| format!("Failed to send blinds command to {target}: {e}"))?;
+        let model = self.control_model().await?;
+        let control = self.audio_zone(&model, &zone)?;
+        let favorite_id = match favorite.trim().parse::<i64>() {
+            Ok(id) => id,
+            Err(_) => {
+                let favorites = self.read_audio_list(control, list_command).await?;
+                audio::find_favorite(&favorites, &favorite)?
+            }
+        };
+        let command = control
+            .build_command(play_command, &[&favorite_id])
+            .map_err(|e| e.to_string())?;
+        let response = self.send_control_command(control, &command).await?;
 
         Ok(json!({
-            "target": target,
-            "action": action,
-            "position": position,
+            "zone": control.name,
+            "favorite": favorite,
+            "favorite_id": favorite_id,
+            "scope": if global { "global" } else { "room" },
             "command_sent": command,
             "status": "executed",
             "miniserver_response": response.value
         }))
     }
 
-    /// Get status of all blinds/rolladen
-    pub async fn get_blinds_status(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Get the play queue of an audio zone
+    ///
+    /// Returns up to `limit` (default 25, at most 100) tracks from position
+    /// `start` (default 0).
+    pub async fn get_audio_queue(
+        &self,
+        zone: String,
+        start: Option<u32>,
+        limit: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+        let start = start.unwrap_or(0);
+        let limit = limit.unwrap_or(25).clamp(1, 100);
+        let model = self.control_model().await?;
+        let control = self.audio_zone(&model, &zone)?;
+        let command = control
+            .build_command("getqueue", &[&start, &limit])
+            .map_err(|e| e.to_string())?;
+        let queue = self.read_audio_list(control, &command).await?;
+
+        Ok(json!({
+            "zone": control.name,
+            "start": start,
+            "count": queue.len(),
+            "queue": queue
+        }))
+    }
 
-        let mut blind_uuids = Vec::new();
-        let mut blind_info = Vec::new();
+    /// Set shuffle and repeat of an audio zone
+    ///
+    /// `repeat`: off, queue (all) or track (one).
+    pub async fn set_audio_playback_mode(
+        &self,
+        zone: String,
+        shuffle: Option<bool>,
+        repeat: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        if shuffle.is_none() && repeat.is_none() {
+            return Err("Provide shuffle and/or repeat".to_string());
+        }
+        let repeat_id = repeat.as_deref().map(audio::repeat_mode_id).transpose()?;
+        let model = self.control_model().await?;
+        let control = self.audio_zone(&model, &zone)?;
 
-            if matches!(control_type, "Jalousie" | "Blinds" | "Rolladen") {
-                blind_uuids.push(uuid.clone());
-                blind_info.push((uuid.clone(), control.clone()));
-            }
+        let mut commands = Vec::new();
+        if let Some(shuffle) = shuffle {
+            commands.push(control.build_command("shuffle", &[&u8::from(shuffle)]));
         }
+        if let Some(repeat_id) = repeat_id {
+            commands.push(control.build_command("repeat", &[&repeat_id]));
+        }
+        let sent = self.send_commands(control, commands).await?;
 
-        let live_states = Self::fetch_live_states(client, &blind_uuids).await;
+        Ok(json!({
+            "zone": control.name,
+            "shuffle": shuffle,
+            "repeat": repeat_id.map(|id| audio::REPEAT_MODES[id]),
+            "commands_sent": sent,
+            "status": "executed"
+        }))
+    }
 
-        let blinds: Vec<Value> = blind_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+    /// Play audio zones in sync
+    ///
+    /// The first zone leads: the others join its sync group and play what it
+    /// plays.
+    pub async fn group_audio_zones(
+        &self,
+        zones: Vec<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "room": room,
-                    "state": state
-                })
-            })
+        if zones.len() < 2 {
+            return Err("A sync group needs at least two zones".to_string());
+        }
+        let model = self.control_model().await?;
+        let controls = zones
+            .iter()
+            .map(|zone| self.audio_zone(&model, zone))
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        let leader = controls[0];
+        let leader_id = audio::player_id(leader)
+            .ok_or_else(|| format!("{} has no player ID to group with", leader.name))?;
+        let members: Vec<&LoxoneControl> = controls[1..]
+            .iter()
+            .copied()
+            .filter(|control| control.uuid != leader.uuid)
             .collect();
+        let results = self
+            .send_to_controls(&members, |control| {
+                control.build_command("syncWith", &[&leader_id])
+            })
+            .await?;
 
         Ok(json!({
-            "blinds": blinds,
-            "count": blinds.len()
+            "leader": leader.name,
+            "player_id": leader_id,
+            "members": members.iter().map(|control| &control.name).collect::<Vec<_>>(),
+            "results": results
         }))
     }
 
-    // ========================================================================
-    // DISCOVERY TOOLS
-    // ========================================================================
-
-    /// List all rooms in the Loxone system
-    pub async fn list_rooms(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Remove audio zones from their sync groups
+    pub async fn ungroup_audio_zones(
+        &self,
+        zones: Vec<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let rooms: Vec<_> = structure
-            .rooms
+        let model = self.control_model().await?;
+        let controls = zones
             .iter()
-            .map(|(uuid, room)| {
-                json!({
-                    "uuid": uuid,
-                    "name": room.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
-                    "type": room.get("type").and_then(|v| v.as_str()).unwrap_or("Room")
-                })
-            })
-            .collect();
+            .map(|zone| self.audio_zone(&model, zone))
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        let results = self
+            .send_to_controls(&controls, |control| control.build_command("unsync", &[]))
+            .await?;
 
-        Ok(json!({
-            "rooms": rooms,
-            "count": rooms.len()
-        }))
+        Ok(json!({ "results": results }))
     }
 
-    /// List all devices in a specific room or system-wide
-    pub async fn list_devices(
+    /// Play an announcement on audio zones
+    ///
+    /// `announcement`: doorbell, alarm, fire_alarm or tts, which speaks `text`
+    /// (at `volume` 0-100, if given). Without `zones` the central audio zone
+    /// announces everywhere, or every zone if there is none.
+    pub async fn audio_announcement(
         &self,
-        room: Option<String>,
+        announcement: String,
+        zones: Option<Vec<String>>,
+        text: Option<String>,
+        volume: Option<u8>,
     ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+        let command = audio::announcement_command(&announcement)?;
+        let text = match (command, text.as_deref().map(str::trim)) {
+            ("tts", Some(text)) if !text.is_empty() => Some(urlencoding::encode(text).into_owned()),
+            ("tts", _) => return Err("Text-to-speech needs a text".to_string()),
+            _ => None,
+        };
+        if volume.is_some_and(|volume| volume > 100) {
+            return Err("Volume must be between 0-100".to_string());
+        }
 
-        let devices: Vec<_> = structure
-            .controls
-            .iter()
-            .filter(|(_, control)| {
-                if let Some(ref room_filter) = room {
-                    control
-                        .get("room")
-                        .and_then(|v| v.as_str())
-                        .map(|r| r.to_lowercase().contains(&room_filter.to_lowercase()))
-                        .unwrap_or(false)
+        let model = self.control_model().await?;
+        let targets = match zones.filter(|zones| !zones.is_empty()) {
+            Some(zones) => {
+                let mut targets = Vec::new();
+                for zone in &zones {
+                    targets.extend(self.audio_targets(&model, zone)?);
+                }
+                let mut seen = std::collections::HashSet::new();
+                targets.retain(|control| seen.insert(&control.uuid));
+                targets
+            }
+            None => {
+                let central = model.filter(|c| c.control_type == ControlType::CentralAudioZone);
+                if central.is_empty() {
+                    model.filter(|c| c.control_type == ControlType::AudioZoneV2)
                 } else {
-                    true
+                    central
                 }
+            }
+        };
+        if targets.is_empty() {
+            return Err("No audio zones to announce on".to_string());
+        }
+
+        let results = self
+            .send_to_controls(&targets, |control| match (&text, volume) {
+                (Some(text), Some(volume)) => control.build_command(command, &[text, &volume]),
+                (Some(text), None) => control.build_command(command, &[text]),
+                (None, _) => control.build_command(command, &[]),
             })
-            .map(|(uuid, control)| {
-                json!({
-                    "uuid": uuid,
-                    "name": control.get("name").and_then(|v| v.as_str()).unwrap_or("Unknown"),
-                    "type": control.get("type").and_then(|v| v.as_str()).unwrap_or("Unknown"),
-                    "room": control.get("room").and_then(|v| v.as_str()).unwrap_or("Unknown"),
-                    "category": control.get("cat").and_then(|v| v.as_str()).unwrap_or("Unknown")
-                })
-            })
-            .collect();
+            .await?;
 
         Ok(json!({
-            "devices": devices,
-            "count": devices.len(),
-            "filter": room
+            "announcement": command,
+            "zones": targets.len(),
+            "results": results
         }))
     }
 
-    /// Get detailed information about a specific device
-    pub async fn get_device_info(
-        &self,
-        device_id: String,
-    ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    // ========================================================================
+    // SENSOR TOOLS
+    // ========================================================================
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        // Find device by UUID or name
-        let device = structure.controls.iter().find(|(uuid, control)| {
-            uuid.to_string() == device_id
-                || control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .map(|n| n.to_lowercase().contains(&device_id.to_lowercase()))
-                    .unwrap_or(false)
-        });
-
-        match device {
-            Some((uuid, control)) => Ok(json!({
-                "uuid": uuid,
-                "control": control
-            })),
-            None => Err(format!("Device '{device_id}' not found")),
-        }
+    /// Get all sensor readings
+    ///
+    /// Returns current values from all sensors (temperature, humidity, motion, etc.)
+    pub async fn get_sensor_readings(&self) -> std::result::Result<serde_json::Value, String> {
+        Ok(self
+            .device_listing("sensors", "value", |control| {
+                control.control_type.is_sensor()
+            })
+            .await?)
+    }
+
+    /// Get door and window sensor status
+    ///
+    /// Returns open/closed state of all door and window sensors
+    pub async fn get_door_window_status(&self) -> std::result::Result<serde_json::Value, String> {
+        Ok(self
+            .device_listing("door_window_sensors", "state", Self::is_door_window)
+            .await?)
+    }
+
+    /// Get motion detector status
+    pub async fn get_motion_status(&self) -> std::result::Result<serde_json::Value, String> {
+        Ok(self
+            .device_listing("motion_sensors", "state", |control| {
+                control.control_type.is_presence()
+            })
+            .await?)
     }
 
     // ========================================================================
-    // SYSTEM TOOLS
+    // WEATHER TOOLS
     // ========================================================================
 
-    /// Get server status and health information
-    pub async fn get_server_status(&self) -> std::result::Result<serde_json::Value, String> {
-        let connected = self.context.is_some() && self.client.is_some();
+    /// Get current weather data
+    ///
+    /// Returns weather station readings (temperature, humidity, wind, rain)
+    pub async fn get_weather(&self) -> std::result::Result<serde_json::Value, String> {
+        Ok(self
+            .device_listing("weather_devices", "state", |control| {
+                control.control_type.is_weather()
+            })
+            .await?)
+    }
 
-        Ok(json!({
-            "connected": connected,
-            "version": env!("CARGO_PKG_VERSION"),
-            "name": "Loxone MCP Server"
-        }))
+    // ========================================================================
+    // ENERGY TOOLS
+    // ========================================================================
+
+    /// Get energy consumption data
+    ///
+    /// Returns current power usage and energy meters
+    pub async fn get_energy_status(&self) -> std::result::Result<serde_json::Value, String> {
+        Ok(self
+            .device_listing("energy_devices", "state", |control| {
+                control.control_type.is_energy()
+            })
+            .await?)
     }
 
-    // ========================================================================
-    // AUDIO TOOLS
-    // ========================================================================
-
-    /// Control an audio zone (play, pause, stop, next, previous)
+    /// Control EV charging
     ///
-    /// Manages playback in audio zones. Actions: play, pause, stop, next, previous, mute, unmute
-    pub async fn control_audio_zone(
+    /// Start, stop, or set charging limits for electric vehicle chargers
+    pub async fn control_ev_charging(
         &self,
-        zone: String,
+        charger: String,
         action: String,
+        limit_kwh: Option<f64>,
     ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
         let normalized_action = match action.to_lowercase().as_str() {
-            "play" | "abspielen" | "start" => "play",
+            "start" | "laden" => "start",
+            "stop" | "stoppen" => "stop",
             "pause" | "pausieren" => "pause",
-            "stop" | "stopp" | "anhalten" => "stop",
-            "next" | "weiter" | "nächster" => "next",
-            "previous" | "zurück" | "vorheriger" => "previous",
-            "mute" | "stumm" => "mute",
-            "unmute" | "laut" => "unmute",
             _ => {
                 return Err(format!(
-                    "Invalid action '{action}'. Use: play, pause, stop, next, previous, mute, unmute"
+                    "Invalid action '{action}'. Use: start, stop, pause"
                 ));
             }
         };
 
-        let client = self.get_client()?;
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, &charger)?;
 
-        // Map normalized actions to Loxone audio commands
-        let command = match normalized_action {
-            "play" => "play",
-            "pause" => "pause",
-            "stop" => "stop",
-            "next" => "queueplus",
-            "previous" => "queueminus",
-            "mute" => "mute",
-            "unmute" => "unmute",
-            _ => normalized_action,
+        // Map actions to Loxone commands; Wallbox2 switches via allow/{flag}
+        let command = match (&control.control_type, normalized_action) {
+            (ControlType::Wallbox2, "start") => "allow/1",
+            (ControlType::Wallbox2, _) => "allow/0",
+            (_, "start") => "on",
+            _ => "off",
         };
 
-        let response = client
-            .send_command(&zone, command)
-            .await
-            .map_err(|e| format!("Failed to control audio zone {zone}: {e}"))?;
+        let response = self.send_control_command(control, command).await?;
+
+        // If a limit was specified, try to send it as well
+        let limit_response = if let Some(limit) = limit_kwh {
+            let limit_cmd = control.build_command("setlimit", &[&limit]);
+            let sent = match limit_cmd {
+                Ok(limit_cmd) => self.send_control_command(control, &limit_cmd).await,
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(resp) => Some(resp.value),
+                Err(e) => {
+                    warn!("Failed to set charging limit on {charger}: {e}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
 
         Ok(json!({
-            "zone": zone,
+            "charger": charger,
             "action": normalized_action,
             "command_sent": command,
+            "limit_kwh": limit_kwh,
             "status": "executed",
-            "miniserver_response": response.value
+            "miniserver_response": response.value,
+            "limit_response": limit_response
         }))
     }
 
-    /// Set volume for an audio zone
+    // ========================================================================
+    // SECURITY TOOLS
+    // ========================================================================
+
+    /// Get security system status
     ///
-    /// Set volume level (0-100) for a specific audio zone
-    pub async fn set_audio_volume(
+    /// Returns alarm system state, door locks, and security sensors
+    pub async fn get_security_status(&self) -> std::result::Result<serde_json::Value, String> {
+        Ok(self
+            .device_listing("security_devices", "state", |control| {
+                control.control_type.is_security()
+            })
+            .await?)
+    }
+
+    /// Arm or disarm security system
+    ///
+    /// Set security system mode: arm_away, arm_home, disarm
+    pub async fn set_security_mode(
         &self,
-        zone: String,
-        volume: u8,
+        mode: String,
+        code: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        if volume > 100 {
-            return Err("Volume must be between 0-100".to_string());
+        let normalized_mode = match mode.to_lowercase().as_str() {
+            "arm" | "arm_away" | "scharf" | "abwesend" => "arm_away",
+            "arm_home" | "arm_stay" | "zuhause" => "arm_home",
+            "disarm" | "unscharf" | "aus" => "disarm",
+            _ => {
+                return Err(format!(
+                    "Invalid mode '{mode}'. Use: arm_away, arm_home, disarm"
+                ));
+            }
+        };
+
+        let model = self.control_model().await?;
+
+        // Find alarm/security controls
+        let security_controls = model.of_type(ControlType::is_alarm);
+
+        if security_controls.is_empty() {
+            return Err("No security/alarm devices found in the system".to_string());
         }
 
-        let client = self.get_client()?;
-        let command = format!("volume/{volume}");
-        let response = client
-            .send_command(&zone, &command)
-            .await
-            .map_err(|e| format!("Failed to set volume on zone {zone}: {e}"))?;
+        // Arm with motion detection when away, without it when at home. The
+        // Miniserver checks the user's permissions, not a code in the command.
+        let command = match normalized_mode {
+            "arm_away" => "on/1",
+            "arm_home" => "on/0",
+            _ => "off",
+        };
+
+        let results = self
+            .send_to_controls(&security_controls, |control| {
+                match (&control.control_type, command) {
+                    // Central alarms only know plain on/off
+                    (ControlType::CentralAlarm, "off") => Ok("off".to_string()),
+                    (ControlType::CentralAlarm, _) => control.build_command("on", &[]),
+                    _ => {
+                        control.validate_command(command)?;
+                        Ok(command.to_string())
+                    }
+                }
+            })
+            .await?;
 
         Ok(json!({
-            "zone": zone,
-            "volume": volume,
+            "mode": normalized_mode,
+            "code_provided": code.is_some(),
+            "note": code
+                .as_ref()
+                .map(|_| "Codes are not sent; the Miniserver authorizes the connected user"),
             "command_sent": command,
-            "status": "executed",
-            "miniserver_response": response.value
+            "devices_affected": results.len(),
+            "results": results
         }))
     }
 
-    /// Get status of all audio zones
-    pub async fn get_audio_status(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Control door lock
+    ///
+    /// Lock or unlock a smart door lock
+    pub async fn control_door_lock(
+        &self,
+        lock: String,
+        action: String,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut audio_uuids = Vec::new();
-        let mut audio_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-            if control_type.contains("Audio") || control_type == "MediaController" {
-                audio_uuids.push(uuid.clone());
-                audio_info.push((uuid.clone(), control.clone()));
-            }
-        }
-
-        let live_states = Self::fetch_live_states(client, &audio_uuids).await;
+        let normalized_action = match action.to_lowercase().as_str() {
+            "lock" | "abschließen" | "zu" => "lock",
+            "unlock" | "aufschließen" | "auf" => "unlock",
+            _ => return Err(format!("Invalid action '{action}'. Use: lock, unlock")),
+        };
 
-        let audio_zones: Vec<Value> = audio_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+        let command = match normalized_action {
+            "lock" => "on",
+            _ => "off",
+        };
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "state": state
-                })
-            })
-            .collect();
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, &lock)?;
+        let response = self.send_control_command(control, command).await?;
 
         Ok(json!({
-            "audio_zones": audio_zones,
-            "count": audio_zones.len()
+            "lock": lock,
+            "action": normalized_action,
+            "command_sent": command,
+            "status": "executed",
+            "miniserver_response": response.value
         }))
     }
 
     // ========================================================================
-    // SENSOR TOOLS
+    // CAMERA TOOLS
     // ========================================================================
 
-    /// Get all sensor readings
+    /// Get camera/intercom status
     ///
-    /// Returns current values from all sensors (temperature, humidity, motion, etc.)
-    pub async fn get_sensor_readings(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Returns list of cameras and video intercoms
+    pub async fn get_camera_status(&self) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut sensor_uuids = Vec::new();
-        let mut sensor_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-            // Match sensor types
-            if matches!(
-                control_type,
-                "InfoOnlyAnalog"
-                    | "InfoOnlyDigital"
-                    | "PresenceDetector"
-                    | "MotionSensor"
-                    | "SmokeAlarm"
-                    | "Meter"
-                    | "Sensor"
-            ) {
-                sensor_uuids.push(uuid.clone());
-                sensor_info.push((uuid.clone(), control.clone()));
-            }
-        }
+        let model = self.control_model().await?;
+        let mut controls = model.of_type(ControlType::is_camera);
+        let total = self.limit_listing(&mut controls);
+        let cameras = self.describe_controls(&model, &controls, "state").await?;
+
+        Ok(json!({
+            "cameras": cameras,
+            "count": cameras.len(),
+            "total": total,
+            "truncated": cameras.len() < total
+        }))
+    }
 
-        let live_states = Self::fetch_live_states(client, &sensor_uuids).await;
+    /// Take a snapshot from a camera or video intercom
+    ///
+    /// Returns the current picture as image content.
+    pub async fn get_camera_snapshot(
+        &self,
+        camera: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-        let sensors: Vec<Value> = sensor_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, &camera)?;
+        if !control.control_type.is_camera() {
+            return Err(format!(
+                "'{camera}' is a {}, not a camera",
+                control.type_name()
+            ));
+        }
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "value": state
-                })
-            })
-            .collect();
+        let image = self
+            .get_client()?
+            .fetch_camera_image(&control.uuid, None)
+            .await
+            .map_err(|e| format!("Failed to fetch snapshot from {}: {e}", control.name))?;
 
         Ok(json!({
-            "sensors": sensors,
-            "count": sensors.len()
+            "camera": camera,
+            "uuid": control.uuid,
+            "name": control.name,
+            "room_name": control.room.as_deref().and_then(|room| model.room_name(room)),
+            "image": media::image_json(&image)
         }))
     }
 
-    /// Get door and window sensor status
+    // ========================================================================
+    // INTERCOM TOOLS
+    // ========================================================================
+
+    /// Answer or control intercom
     ///
-    /// Returns open/closed state of all door and window sensors
-    pub async fn get_door_window_status(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Answer calls, open doors, or control intercom features
+    pub async fn control_intercom(
+        &self,
+        intercom: String,
+        action: String,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut dw_uuids = Vec::new();
-        let mut dw_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-            let name = control
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_lowercase();
-
-            // Match door/window sensors
-            if control_type == "InfoOnlyDigital"
-                && (name.contains("door")
-                    || name.contains("window")
-                    || name.contains("tür")
-                    || name.contains("fenster"))
-            {
-                dw_uuids.push(uuid.clone());
-                dw_info.push((uuid.clone(), control.clone()));
+        let normalized_action = match action.to_lowercase().as_str() {
+            "answer" | "annehmen" | "abheben" => "answer",
+            "hangup" | "auflegen" | "beenden" => "hangup",
+            "open" | "öffnen" | "tür" => "open_door",
+            "talk" | "sprechen" => "talk",
+            "mute" | "stumm" => "mute",
+            _ => {
+                return Err(format!(
+                    "Invalid action '{action}'. Use: answer, hangup, open, talk, mute"
+                ));
             }
-        }
-
-        let live_states = Self::fetch_live_states(client, &dw_uuids).await;
+        };
 
-        let door_windows: Vec<Value> = dw_info
-            .iter()
-            .map(|(uuid, control)| {
-                let display_name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+        // Map intercom actions to Loxone commands
+        let command = match normalized_action {
+            "open_door" => "open",
+            _ => normalized_action,
+        };
 
-                json!({
-                    "uuid": uuid,
-                    "name": display_name,
-                    "room": room,
-                    "state": state
-                })
-            })
-            .collect();
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, &intercom)?;
+        let response = self.send_control_command(control, command).await?;
 
         Ok(json!({
-            "door_window_sensors": door_windows,
-            "count": door_windows.len()
+            "intercom": intercom,
+            "action": normalized_action,
+            "command_sent": command,
+            "status": "executed",
+            "miniserver_response": response.value
         }))
     }
 
-    /// Get motion detector status
-    pub async fn get_motion_status(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Get intercom call history
+    ///
+    /// Returns the last bell events of each intercom (newest first) with the
+    /// picture the Miniserver stored for every ring. Filter by intercom name or
+    /// UUID; `limit` caps the events per intercom (default 5).
+    pub async fn get_intercom_history(
+        &self,
+        intercom: Option<String>,
+        limit: Option<u32>,
+        include_images: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
+        let limit = limit.unwrap_or(5).clamp(1, 50) as usize;
+        let include_images = include_images.unwrap_or(true);
         let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut motion_uuids = Vec::new();
-        let mut motion_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let model = self.control_model().await?;
 
-            if matches!(control_type, "PresenceDetector" | "MotionSensor") {
-                motion_uuids.push(uuid.clone());
-                motion_info.push((uuid.clone(), control.clone()));
+        let intercoms = match intercom {
+            Some(ref identifier) => {
+                let control = self.resolve_control(&model, identifier)?;
+                if !control.control_type.is_camera() {
+                    return Err(format!(
+                        "'{identifier}' is a {}, not an intercom",
+                        control.type_name()
+                    ));
+                }
+                vec![control]
             }
-        }
-
-        let live_states = Self::fetch_live_states(client, &motion_uuids).await;
+            None => model.of_type(ControlType::is_camera),
+        };
 
-        let motion_sensors: Vec<Value> = motion_info
+        // Bell events are a text state: `|` separated timestamps
+        let state_uuids: Vec<String> = intercoms
             .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
-
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "room": room,
-                    "state": state
-                })
-            })
+            .filter_map(|control| control.state_uuid("lastBellEvents"))
+            .map(str::to_string)
             .collect();
+        let states = if state_uuids.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            client
+                .get_state_values(&state_uuids)
+                .await
+                .map_err(|e| format!("Failed to read bell events: {e}"))?
+        };
+
+        let mut history = Vec::new();
+        for control in &intercoms {
+            let raw = control
+                .state_uuid("lastBellEvents")
+                .and_then(|uuid| states.get(uuid))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+
+            let mut events = Vec::new();
+            for timestamp in media::parse_bell_events(raw).into_iter().take(limit) {
+                let mut event = json!({
+                    "timestamp": timestamp,
+                    "time": media::bell_event_time(&timestamp).map(|time| time.to_string())
+                });
+                if include_images {
+                    match client
+                        .fetch_camera_image(&control.uuid, Some(&timestamp))
+                        .await
+                    {
+                        Ok(image) => event["image"] = media::image_json(&image),
+                        Err(e) => event["image_error"] = json!(e.to_string()),
+                    }
+                }
+                events.push(event);
+            }
+
+            history.push(json!({
+                "uuid": control.uuid,
+                "name": control.name,
+                "type": control.type_name(),
+                "room_name": control.room.as_deref().and_then(|room| model.room_name(room)),
+                "last_bell": events.first().and_then(|event| event.get("time").cloned()),
+                "events": events
+            }));
+        }
 
         Ok(json!({
-            "motion_sensors": motion_sensors,
-            "count": motion_sensors.len()
+            "intercoms": history,
+            "count": history.len()
         }))
     }
 
     // ========================================================================
-    // WEATHER TOOLS
+    // SCENE/MOOD TOOLS
     // ========================================================================
 
-    /// Get current weather data
+    /// Activate a scene or mood
     ///
-    /// Returns weather station readings (temperature, humidity, wind, rain)
-    pub async fn get_weather(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Trigger predefined scenes (moods) for rooms or the whole house
+    pub async fn activate_scene(
+        &self,
+        scene: String,
+        room: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        let mut weather_uuids = Vec::new();
-        let mut weather_info = Vec::new();
-
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let model = self.control_model().await?;
 
-            if control_type.contains("Weather") {
-                weather_uuids.push(uuid.clone());
-                weather_info.push((uuid.clone(), control.clone()));
-            }
+        // If scene looks like a UUID, switch that controller on directly
+        if scene.contains('-') && scene.len() > 30 {
+            let control = self.resolve_control(&model, &scene)?;
+            let response = self.send_control_command(control, "on").await?;
+            return Ok(json!({
+                "scene": scene,
+                "room": room,
+                "command_sent": "on",
+                "status": "activated",
+                "miniserver_response": response.value
+            }));
         }
 
-        let live_states = Self::fetch_live_states(client, &weather_uuids).await;
+        // Search for matching scene controllers
+        let controllers = match room {
+            Some(ref room_name) => {
+                self.find_controls_in_room(&model, room_name, ControlType::has_moods)?
+            }
+            None => model.of_type(ControlType::has_moods),
+        };
 
-        let weather_devices: Vec<Value> = weather_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+        if controllers.is_empty() {
+            return Err(format!(
+                "No scene controllers found{}",
+                room.as_ref()
+                    .map(|r| format!(" in room '{r}'"))
+                    .unwrap_or_default()
+            ));
+        }
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "state": state
-                })
+        // Match the scene to a mood by name or ID; with an unknown mood list
+        // an ID is sent as is
+        let values = self
+            .read_state_values(&controllers, moods::MOOD_STATES)
+            .await?;
+        let results = self
+            .send_to_controls(&controllers, |control| {
+                let mood_id = moods::find_mood(&moods::mood_list(control, &values), &scene)
+                    .map_err(crate::error::LoxoneError::invalid_input)?;
+                control.build_command("changeTo", &[&mood_id])
             })
-            .collect();
+            .await?;
 
         Ok(json!({
-            "weather_devices": weather_devices,
-            "count": weather_devices.len()
+            "scene": scene,
+            "room": room,
+            "controllers_affected": results.len(),
+            "results": results
         }))
     }
 
-    // ========================================================================
-    // ENERGY TOOLS
-    // ========================================================================
-
-    /// Get energy consumption data
+    /// List available scenes
     ///
-    /// Returns current power usage and energy meters
-    pub async fn get_energy_status(&self) -> std::result::Result<serde_json::Value, String> {
+    /// Lists the moods of every light controller with their IDs and whether
+    /// they are active or favorites, plus the active and favorite moods in order.
+    pub async fn list_scenes(&self) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+        let model = self.control_model().await?;
+        let controllers = model.of_type(ControlType::has_moods);
+        let values = self
+            .read_state_values(&controllers, moods::MOOD_STATES)
+            .await?;
+        let scenes: Vec<_> = controllers
+            .iter()
+            .map(|control| {
+                let room = control.room.as_deref().unwrap_or("Unknown");
+                let mut scene = moods::moods_status(control, &values);
+                scene["uuid"] = json!(control.uuid);
+                scene["name"] = json!(control.name);
+                scene["room"] = json!(room);
+                scene["room_name"] = json!(model.room_name(room));
+                scene
+            })
+            .collect();
 
-        let mut energy_uuids = Vec::new();
-        let mut energy_info = Vec::new();
+        Ok(json!({
+            "scene_controllers": scenes,
+            "count": scenes.len()
+        }))
+    }
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    /// Mix moods of a light controller
+    ///
+    /// Adds moods to and removes moods from the active mix without switching
+    /// the other active moods off. Moods are given by name or ID;
+    /// `controller` is a light controller or room name.
+    pub async fn mix_moods(
+        &self,
+        controller: String,
+        add: Option<Vec<String>>,
+        remove: Option<Vec<String>>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-            if matches!(control_type, "Meter" | "EnergyManager" | "EnergyMonitor")
-                || control_type.contains("Energy")
-            {
-                energy_uuids.push(uuid.clone());
-                energy_info.push((uuid.clone(), control.clone()));
+        let add = add.unwrap_or_default();
+        let remove = remove.unwrap_or_default();
+        if add.is_empty() && remove.is_empty() {
+            return Err("Provide moods to add and/or remove".to_string());
+        }
+        let model = self.control_model().await?;
+        let control = self.mood_controller(&model, &controller)?;
+        let mood_list = self.controller_moods(control).await?;
+
+        let mut commands = Vec::new();
+        for (command, moods) in [("addMood", &add), ("removeMood", &remove)] {
+            for mood in moods {
+                let mood_id = moods::find_mood(&mood_list, mood)?;
+                commands.push(control.build_command(command, &[&mood_id]));
             }
         }
-
-        let live_states = Self::fetch_live_states(client, &energy_uuids).await;
-
-        let energy_devices: Vec<Value> = energy_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
-
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "state": state
-                })
-            })
-            .collect();
+        let sent = self.send_commands(control, commands).await?;
 
         Ok(json!({
-            "energy_devices": energy_devices,
-            "count": energy_devices.len()
+            "controller": control.name,
+            "added": add,
+            "removed": remove,
+            "commands_sent": sent,
+            "status": "executed"
         }))
     }
 
-    /// Control EV charging
+    /// Save the current output levels of a light controller as a mood
     ///
-    /// Start, stop, or set charging limits for electric vehicle chargers
-    pub async fn control_ev_charging(
+    /// Overwrites the mood with this exact name (or ID), or creates a new
+    /// mood with the name. Built-in moods cannot be changed.
+    pub async fn learn_mood(
         &self,
-        charger: String,
-        action: String,
-        limit_kwh: Option<f64>,
+        controller: String,
+        mood: String,
     ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let normalized_action = match action.to_lowercase().as_str() {
-            "start" | "laden" => "start",
-            "stop" | "stoppen" => "stop",
-            "pause" | "pausieren" => "pause",
-            _ => {
+        let model = self.control_model().await?;
+        let control = self.mood_controller(&model, &controller)?;
+        let mood_list = self.controller_moods(control).await?;
+        let existing = match mood.trim().parse::<i64>() {
+            Ok(id) => Some(
+                mood_list
+                    .iter()
+                    .find(|known| known.id == id)
+                    .ok_or_else(|| format!("No mood with ID {id}; give a name for a new mood"))?,
+            ),
+            Err(_) => moods::find_named(&mood_list, &mood),
+        };
+        let (mood_id, name, created) = match existing {
+            Some(known) if known.is_static => {
                 return Err(format!(
-                    "Invalid action '{action}'. Use: start, stop, pause"
+                    "'{}' is a built-in mood and cannot be changed",
+                    known.name
                 ));
             }
+            Some(known) => (known.id, known.name.clone(), false),
+            None if mood.trim().is_empty() => return Err("Give a name for the mood".to_string()),
+            None => (
+                moods::next_mood_id(&mood_list),
+                mood.trim().to_string(),
+                true,
+            ),
         };
+        let command = control
+            .build_command("learn", &[&mood_id, &urlencoding::encode(&name)])
+            .map_err(|e| e.to_string())?;
+        let response = self.send_control_command(control, &command).await?;
 
-        let client = self.get_client()?;
-
-        // Map actions to Loxone commands
-        let command = match normalized_action {
-            "start" => "on".to_string(),
-            "stop" => "off".to_string(),
-            "pause" => "off".to_string(),
-            _ => "off".to_string(),
-        };
+        Ok(json!({
+            "controller": control.name,
+            "mood": name,
+            "mood_id": mood_id,
+            "created": created,
+            "command_sent": command,
+            "status": "executed",
+            "miniserver_response": response.value
+        }))
+    }
 
-        let response = client
-            .send_command(&charger, &command)
-            .await
-            .map_err(|e| format!("Failed to control EV charger {charger}: {e}"))?;
+    /// Delete a mood of a light controller
+    ///
+    /// The mood is given by its exact name or ID. Built-in moods cannot be deleted.
+    pub async fn delete_mood(
+        &self,
+        controller: String,
+        mood: String,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-        // If a limit was specified, try to send it as well
-        let limit_response = if let Some(limit) = limit_kwh {
-            let limit_cmd = format!("setlimit/{limit}");
-            match client.send_command(&charger, &limit_cmd).await {
-                Ok(resp) => Some(resp.value),
-                Err(e) => {
-                    warn!("Failed to set charging limit on {charger}: {e}");
-                    None
-                }
-            }
-        } else {
-            None
-        };
+        let model = self.control_model().await?;
+        let control = self.mood_controller(&model, &controller)?;
+        let mood_list = self.controller_moods(control).await?;
+        let known = match mood.trim().parse::<i64>() {
+            Ok(id) => mood_list.iter().find(|known| known.id == id),
+            Err(_) => moods::find_named(&mood_list, &mood),
+        }
+        .ok_or_else(|| format!("No mood named exactly '{mood}' on {}", control.name))?;
+        if known.is_static {
+            return Err(format!(
+                "'{}' is a built-in mood and cannot be deleted",
+                known.name
+            ));
+        }
+        let command = control
+            .build_command("delete", &[&known.id])
+            .map_err(|e| e.to_string())?;
+        let response = self.send_control_command(control, &command).await?;
 
         Ok(json!({
-            "charger": charger,
-            "action": normalized_action,
+            "controller": control.name,
+            "mood": known.name,
+            "mood_id": known.id,
             "command_sent": command,
-            "limit_kwh": limit_kwh,
             "status": "executed",
-            "miniserver_response": response.value,
-            "limit_response": limit_response
+            "miniserver_response": response.value
         }))
     }
 
-    // ========================================================================
-    // SECURITY TOOLS
-    // ========================================================================
-
-    /// Get security system status
+    /// Add a mood to or remove it from the favorites of a light controller
     ///
-    /// Returns alarm system state, door locks, and security sensors
-    pub async fn get_security_status(&self) -> std::result::Result<serde_json::Value, String> {
+    /// `favorite` defaults to true. `position` (0 = first) moves the mood
+    /// within the favorites, or within the other moods when it is not a favorite.
+    pub async fn set_favorite_mood(
+        &self,
+        controller: String,
+        mood: String,
+        favorite: Option<bool>,
+        position: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+        let favorite = favorite.unwrap_or(true);
+        let model = self.control_model().await?;
+        let control = self.mood_controller(&model, &controller)?;
+        let mood_list = self.controller_moods(control).await?;
+        let mood_id = moods::find_mood(&mood_list, &mood)?;
 
-        let mut security_uuids = Vec::new();
-        let mut security_info = Vec::new();
+        let (toggle, move_command) = if favorite {
+            ("addToFavoriteMood", "moveFavoriteMood")
+        } else {
+            ("removeFromFavoriteMood", "moveAdditionalMood")
+        };
+        let mut commands = vec![control.build_command(toggle, &[&mood_id])];
+        if let Some(position) = position {
+            commands.push(control.build_command(move_command, &[&mood_id, &position]));
+        }
+        let sent = self.send_commands(control, commands).await?;
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        Ok(json!({
+            "controller": control.name,
+            "mood_id": mood_id,
+            "favorite": favorite,
+            "position": position,
+            "commands_sent": sent,
+            "status": "executed"
+        }))
+    }
 
-            if matches!(
-                control_type,
-                "Alarm" | "SmokeAlarm" | "Gate" | "DoorLock" | "AccessControl"
-            ) || control_type.contains("Security")
-            {
-                security_uuids.push(uuid.clone());
-                security_info.push((uuid.clone(), control.clone()));
-            }
-        }
+    /// Set brightness and white tone of individual light outputs
+    ///
+    /// `target` is an output, a light controller or a room; controllers are
+    /// expanded to their circuits and `output` narrows them down by name.
+    /// `brightness` is 0-100, `white` a tone (warm, soft, neutral, cool,
+    /// daylight) or a Kelvin value for tunable-white (`ColorPickerV2`)
+    /// outputs, e.g. white "warm" at brightness 40.
+    pub async fn set_light_output(
+        &self,
+        target: String,
+        output: Option<String>,
+        brightness: Option<u8>,
+        white: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-        let live_states = Self::fetch_live_states(client, &security_uuids).await;
+        if brightness.is_some_and(|level| level > 100) {
+            return Err("Brightness must be between 0-100".to_string());
+        }
+        let kelvin = white.as_deref().map(lighting::parse_white).transpose()?;
+        if brightness.is_none() && kelvin.is_none() {
+            return Err("Provide a brightness and/or a white tone".to_string());
+        }
 
-        let security_devices: Vec<Value> = security_info
-            .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
+        let model = self.control_model().await?;
+        let outputs: Vec<&LoxoneControl> = self
+            .light_output_targets(&model, &target, output.as_deref())?
+            .into_iter()
+            .map(|(output, _)| output)
+            .collect();
 
-                json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "state": state
-                })
+        let colors = self.current_colors(&outputs).await?;
+        let results = self
+            .send_to_controls(&outputs, |control| {
+                let current = colors.get(&control.uuid).copied();
+                lighting::output_command(control, brightness, kelvin, current)
             })
+            .await?;
+
+        Ok(json!({
+            "target": target,
+            "brightness": brightness,
+            "kelvin": kelvin,
+            "outputs_affected": results.len(),
+            "results": results
+        }))
+    }
+
+    /// Set the colour of colour light outputs
+    ///
+    /// `color` is a hex value (#ff8800), rgb(r,g,b), hsv(h,s,v), a colour
+    /// name (red, orange, blue, ...), a white tone (warm white, neutral
+    /// white, ...) or a Kelvin value (3000K). `target` is an output, a light
+    /// controller or a room; only its colour outputs (`ColorPicker`,
+    /// `ColorPickerV2`) are changed, optionally narrowed down by `output`
+    /// name. `brightness` 0-100 overrides the colour's own brightness.
+    pub async fn set_light_color(
+        &self,
+        target: String,
+        color: String,
+        output: Option<String>,
+        brightness: Option<u8>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        if brightness.is_some_and(|level| level > 100) {
+            return Err("Brightness must be between 0-100".to_string());
+        }
+        let mut light_color = lighting::parse_color(&color)?;
+        if let Some(level) = brightness {
+            light_color = light_color.with_brightness(level);
+        }
+
+        let model = self.control_model().await?;
+        let outputs: Vec<&LoxoneControl> = self
+            .light_output_targets(&model, &target, output.as_deref())?
+            .into_iter()
+            .map(|(output, _)| output)
+            .filter(|output| lighting::is_color_output(output))
             .collect();
+        if outputs.is_empty() {
+            return Err(format!(
+                "No colour outputs found for '{target}'; use set_light_output for dimmers and switches"
+            ));
+        }
+        let results = self
+            .send_to_controls(&outputs, |control| light_color.command(control))
+            .await?;
 
         Ok(json!({
-            "security_devices": security_devices,
-            "count": security_devices.len()
+            "target": target,
+            "color": light_color.describe(),
+            "outputs_affected": results.len(),
+            "results": results
         }))
     }
 
-    /// Arm or disarm security system
+    /// List colour light outputs and their current colours
     ///
-    /// Set security system mode: arm_away, arm_home, disarm
-    pub async fn set_security_mode(
+    /// Finds the colour outputs (`ColorPicker`, `ColorPickerV2`, including
+    /// the circuits of light controllers) of a `target` output, controller or
+    /// room, or of the whole house. The colour is null until the Miniserver
+    /// has reported it.
+    pub async fn get_light_colors(
         &self,
-        mode: String,
-        code: Option<String>,
+        target: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let normalized_mode = match mode.to_lowercase().as_str() {
-            "arm" | "arm_away" | "scharf" | "abwesend" => "arm_away",
-            "arm_home" | "arm_stay" | "zuhause" => "arm_home",
-            "disarm" | "unscharf" | "aus" => "disarm",
-            _ => {
-                return Err(format!(
-                    "Invalid mode '{mode}'. Use: arm_away, arm_home, disarm"
-                ));
-            }
+        let model = self.control_model().await?;
+        let outputs: Vec<_> = match target {
+            Some(ref target) => self.light_output_targets(&model, target, None)?,
+            None => model
+                .of_type(ControlType::is_lighting)
+                .into_iter()
+                .flat_map(lighting::outputs_with_controller)
+                .collect(),
         };
+        let outputs: Vec<_> = outputs
+            .into_iter()
+            .filter(|(output, _)| lighting::is_color_output(output))
+            .collect();
+        let controls: Vec<&LoxoneControl> = outputs.iter().map(|(output, _)| *output).collect();
+        let colors = self.current_colors(&controls).await?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
-            .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
-
-        // Find alarm/security controls
-        let security_controls: Vec<(&String, &Value)> = structure
-            .controls
+        let entries: Vec<Value> = outputs
             .iter()
-            .filter(|(_, control)| {
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                matches!(control_type, "Alarm" | "AccessControl")
-                    || control_type.contains("Security")
+            .map(|(output, controller)| {
+                let room = controller.unwrap_or(output).room.as_deref();
+                json!({
+                    "uuid": output.uuid,
+                    "name": output.name,
+                    "type": output.type_name(),
+                    "picker_type": output.detail_str("pickerType"),
+                    "controller": controller.map(|c| &c.name),
+                    "room_name": room.and_then(|room| model.room_name(room)),
+                    "color": colors.get(&output.uuid).map(lighting::LightColor::describe)
+                })
             })
             .collect();
 
-        if security_controls.is_empty() {
-            return Err("No security/alarm devices found in the system".to_string());
-        }
+        Ok(json!({
+            "outputs": entries,
+            "count": entries.len()
+        }))
+    }
 
-        // Build command - include code if provided
-        let command = match (normalized_mode, &code) {
-            ("arm_away", Some(c)) => format!("on/{c}"),
-            ("arm_away", None) => "on".to_string(),
-            ("arm_home", Some(c)) => format!("on/{c}"),
-            ("arm_home", None) => "on".to_string(),
-            ("disarm", Some(c)) => format!("off/{c}"),
-            ("disarm", None) => "off".to_string(),
-            _ => "off".to_string(),
+    // ========================================================================
+    // BULK TOOLS
+    // ========================================================================
+
+    /// Apply one action to every device matching a selector
+    ///
+    /// Select devices by `rooms`, `categories`, `types` (control types like
+    /// "Jalousie" or the groups lights, blinds, climate, audio) and a `name`
+    /// pattern (`*`/`?` wildcards, otherwise a substring); all given criteria
+    /// must match. `action` is a catalog command such as "on", "off",
+    /// "FullDown" or a value, with optional `parameters` (e.g. action
+    /// "ManualPosition" with parameters ["40"]). Without `execute` only the
+    /// plan of every UUID and command is returned; with `execute: true` the
+    /// commands are sent in parallel and the outcome of each device is reported.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn bulk_action(
+        &self,
+        action: String,
+        rooms: Option<Vec<String>>,
+        categories: Option<Vec<String>>,
+        types: Option<Vec<String>>,
+        name: Option<String>,
+        parameters: Option<Vec<String>>,
+        execute: Option<bool>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
+
+        let selector = DeviceSelector {
+            rooms: rooms.unwrap_or_default(),
+            categories: categories.unwrap_or_default(),
+            types: types.unwrap_or_default(),
+            name,
         };
+        let parameters = parameters.unwrap_or_default();
+        let model = self.control_model().await?;
+        let controls = selector
+            .select(&model, &self.names)
+            .map_err(|e| e.to_string())?;
+        if controls.is_empty() {
+            return Err("No devices match the selection".to_string());
+        }
+        let plan = plan_action(&model, &controls, &action, &parameters);
+        let planned = plan.iter().filter(|entry| entry.command.is_some()).count();
 
-        let mut results = Vec::new();
-        for (uuid, control) in &security_controls {
-            let name = control
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown");
-            match client.send_command(uuid, &command).await {
-                Ok(response) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "status": "executed",
-                        "miniserver_response": response.value
-                    }));
-                }
-                Err(e) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "status": "error",
-                        "error": format!("{e}")
-                    }));
-                }
-            }
+        if !execute.unwrap_or(false) {
+            return Ok(json!({
+                "action": action,
+                "parameters": parameters,
+                "dry_run": true,
+                "devices_selected": plan.len(),
+                "commands_planned": planned,
+                "plan": plan
+            }));
+        }
+
+        if planned == 0 {
+            return Err(format!(
+                "'{action}' applies to none of the {} selected devices",
+                plan.len()
+            ));
+        }
+        let max_devices = self.tool_config().max_devices_per_query;
+        if planned > max_devices {
+            return Err(format!(
+                "{planned} devices selected, more than the limit of {max_devices}; narrow the selection"
+            ));
         }
 
+        let commands: Vec<(&LoxoneControl, String)> = controls
+            .iter()
+            .zip(&plan)
+            .filter_map(|(control, entry)| Some((*control, entry.command.clone()?)))
+            .collect();
+        let mut outcomes = self.send_parallel(&commands).await?.into_iter();
+
+        let results: Vec<Value> = plan
+            .iter()
+            .map(|entry| {
+                let outcome = match (&entry.command, &entry.skipped) {
+                    (Some(_), _) => match outcomes.next() {
+                        Some(Ok(response)) => json!({
+                            "status": "executed",
+                            "miniserver_response": response.value
+                        }),
+                        Some(Err(e)) => json!({"status": "error", "error": e.to_string()}),
+                        None => json!({"status": "error", "error": "No response"}),
+                    },
+                    (None, reason) => json!({"status": "skipped", "reason": reason}),
+                };
+                let mut result = json!(entry);
+                if let (Some(result), Some(outcome)) = (result.as_object_mut(), outcome.as_object())
+                {
+                    result.remove("skipped");
+                    result.extend(outcome.clone());
+                }
+                result
+            })
+            .collect();
+        let succeeded = results
+            .iter()
+            .filter(|result| result["status"] == "executed")
+            .count();
+
         Ok(json!({
-            "mode": normalized_mode,
-            "code_provided": code.is_some(),
-            "command_sent": command,
-            "devices_affected": results.len(),
+            "action": action,
+            "parameters": parameters,
+            "dry_run": false,
+            "devices_selected": plan.len(),
+            "commands_sent": commands.len(),
+            "succeeded": succeeded,
+            "failed": commands.len() - succeeded,
             "results": results
         }))
     }
 
-    /// Control door lock
+    /// Get the history of sensor values
     ///
-    /// Lock or unlock a smart door lock
-    pub async fn control_door_lock(
+    /// Answers questions like "what was the living room temperature last
+    /// night". Select series by `device` (UUID or name), `room` and
+    /// `sensor_type` (temperature, humidity, power, energy, brightness,
+    /// contact, motion, switch, level, position). `since`/`until` are RFC 3339
+    /// times or durations before now like "12h" (default: the last 24 hours).
+    /// `resolution` (raw, 1m, 1h, 1d) is picked from the time span if omitted.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_sensor_history(
         &self,
-        lock: String,
-        action: String,
+        device: Option<String>,
+        room: Option<String>,
+        sensor_type: Option<String>,
+        state: Option<String>,
+        since: Option<String>,
+        until: Option<String>,
+        resolution: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
-
-        let normalized_action = match action.to_lowercase().as_str() {
-            "lock" | "abschließen" | "zu" => "lock",
-            "unlock" | "aufschließen" | "auf" => "unlock",
-            _ => return Err(format!("Invalid action '{action}'. Use: lock, unlock")),
-        };
+        let history = self
+            .history
+            .as_ref()
+            .ok_or_else(|| "Sensor history is disabled (history.enabled)".to_string())?;
+
+        let from = crate::utils::parse_time(since.as_deref().unwrap_or("24h"))
+            .map_err(|e| e.to_string())?;
+        let to = until
+            .as_deref()
+            .map(crate::utils::parse_time)
+            .transpose()
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(chrono::Utc::now);
+        if from > to {
+            return Err(format!("'since' ({from}) is after 'until' ({to})"));
+        }
 
-        let client = self.get_client()?;
-        let command = match normalized_action {
-            "lock" => "on",
-            "unlock" => "off",
-            _ => "off",
+        let query = HistoryQuery {
+            device,
+            room,
+            kind: sensor_type
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(|e: crate::error::LoxoneError| e.to_string())?,
+            state,
+            from,
+            to,
+            resolution: resolution
+                .as_deref()
+                .map(str::parse)
+                .transpose()
+                .map_err(|e: crate::error::LoxoneError| e.to_string())?,
         };
-
-        let response = client
-            .send_command(&lock, command)
+        let results = history
+            .query(&query)
             .await
-            .map_err(|e| format!("Failed to control door lock {lock}: {e}"))?;
+            .map_err(|e| format!("Failed to read sensor history: {e}"))?;
 
+        let series: Vec<_> = results
+            .iter()
+            .map(|series| {
+                let mut value = json!(series);
+                value["summary"] = json!(series.summary());
+                value
+            })
+            .collect();
         Ok(json!({
-            "lock": lock,
-            "action": normalized_action,
-            "command_sent": command,
-            "status": "executed",
-            "miniserver_response": response.value
+            "from": from,
+            "to": to,
+            "resolution": query.resolution(),
+            "series": series,
+            "count": series.len()
         }))
     }
 
-    // ========================================================================
-    // CAMERA TOOLS
-    // ========================================================================
-
-    /// Get camera/intercom status
+    /// List controls with statistics recorded by the Miniserver
     ///
-    /// Returns list of cameras and video intercoms
-    pub async fn get_camera_status(&self) -> std::result::Result<serde_json::Value, String> {
+    /// The Miniserver keeps monthly statistics for controls that have them
+    /// enabled in Loxone Config, often going back years. Returns each
+    /// control's recorded outputs, frequency and available months. `device`
+    /// filters by control UUID or name.
+    pub async fn list_statistics(
+        &self,
+        device: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
+        let model = self.control_model().await?;
+        let files = self
+            .get_client()?
+            .list_statistics()
             .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+            .map_err(|e| format!("Failed to list statistics: {e}"))?;
 
-        let mut camera_uuids = Vec::new();
-        let mut camera_info = Vec::new();
+        let controls: Vec<Value> = Self::statistic_controls(&model, device.as_deref())
+            .into_iter()
+            .map(|(control, config)| {
+                let months: Vec<String> = files
+                    .iter()
+                    .filter(|file| file.control_uuid == control.uuid)
+                    .map(|file| file.month.to_string())
+                    .collect();
+                json!({
+                    "uuid": control.uuid,
+                    "name": control.name,
+                    "type": control.type_name(),
+                    "room": control.room.as_deref().and_then(|room| model.room_name(room)),
+                    "frequency": config.frequency_label(),
+                    "outputs": config.outputs.iter().map(|output| json!({
+                        "id": output.id,
+                        "name": output.name,
+                        "unit": output.format.as_deref().and_then(unit_from_format),
+                    })).collect::<Vec<_>>(),
+                    "months": months,
+                })
+            })
+            .collect();
+        Ok(json!({
+            "controls": controls,
+            "count": controls.len()
+        }))
+    }
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    /// Get the statistics the Miniserver recorded for a control
+    ///
+    /// Reads the Miniserver's own monthly statistics, so meter readings and
+    /// temperatures are available for as far back as they were recorded.
+    /// `device` is a control UUID or name, `output` an output name or ID
+    /// (default: all outputs). `since`/`until` are RFC 3339 times or durations
+    /// before now like "90d" (default: the last 30 days). `resolution` (raw,
+    /// 1m, 1h, 1d) is picked from the time span if omitted.
+    pub async fn get_statistics(
+        &self,
+        device: String,
+        output: Option<String>,
+        since: Option<String>,
+        until: Option<String>,
+        resolution: Option<String>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        self.ensure_connected()?;
 
-            if matches!(control_type, "Intercom" | "Camera" | "Doorbell")
-                || control_type.contains("Camera")
-            {
-                camera_uuids.push(uuid.clone());
-                camera_info.push((uuid.clone(), control.clone()));
-            }
+        let from = crate::utils::parse_time(since.as_deref().unwrap_or("30d"))
+            .map_err(|e| e.to_string())?;
+        let to = until
+            .as_deref()
+            .map(crate::utils::parse_time)
+            .transpose()
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(chrono::Utc::now);
+        if from > to {
+            return Err(format!("'since' ({from}) is after 'until' ({to})"));
         }
+        let resolution = match resolution.as_deref() {
+            Some(resolution) => resolution
+                .parse()
+                .map_err(|e: crate::error::LoxoneError| e.to_string())?,
+            None => Resolution::for_span(to - from),
+        };
 
-        let live_states = Self::fetch_live_states(client, &camera_uuids).await;
+        let model = self.control_model().await?;
+        let (control, config) = match Self::statistic_controls(&model, Some(&device)).as_slice() {
+            [] => return Err(format!("No control with statistics matches '{device}'")),
+            [found] => *found,
+            several => {
+                let names: Vec<&str> = several.iter().map(|(c, _)| c.name.as_str()).collect();
+                return Err(format!(
+                    "'{device}' matches several controls with statistics: {}",
+                    names.join(", ")
+                ));
+            }
+        };
+        let outputs: Vec<_> = match output.as_deref() {
+            Some(query) => vec![config.output(query).ok_or_else(|| {
+                let names: Vec<&str> = config.outputs.iter().map(|o| o.name.as_str()).collect();
+                format!(
+                    "Output '{query}' not found for {}. Available: {}",
+                    control.name,
+                    names.join(", ")
+                )
+            })?],
+            None => config.outputs.iter().collect(),
+        };
 
-        let cameras: Vec<Value> = camera_info
+        let entries = load_statistics(
+            self.get_client()?.as_ref(),
+            &control.uuid,
+            config.outputs.len(),
+            from,
+            to,
+        )
+        .await
+        .map_err(|e| format!("Failed to read statistics of {}: {e}", control.name))?;
+
+        let series: Vec<Value> = outputs
             .iter()
-            .map(|(uuid, control)| {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                let state = live_states.get(uuid).cloned().unwrap_or(Value::Null);
-
+            .map(|output| {
+                let index = config
+                    .outputs
+                    .iter()
+                    .position(|o| o.id == output.id)
+                    .unwrap_or_default();
+                let mut points = rollup(
+                    resolution,
+                    entries.iter().filter_map(|entry| {
+                        entry
+                            .values
+                            .get(index)
+                            .map(|value| (entry.timestamp, *value))
+                    }),
+                );
+                points.truncate(MAX_POINTS);
                 json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "type": control_type,
-                    "room": room,
-                    "state": state
+                    "output": output.name,
+                    "id": output.id,
+                    "unit": output.format.as_deref().and_then(unit_from_format),
+                    "summary": summarize(&points),
+                    "points": points,
                 })
             })
             .collect();
-
         Ok(json!({
-            "cameras": cameras,
-            "count": cameras.len()
+            "uuid": control.uuid,
+            "name": control.name,
+            "from": from,
+            "to": to,
+            "resolution": resolution,
+            "entries": entries.len(),
+            "series": series,
         }))
     }
 
     // ========================================================================
-    // INTERCOM TOOLS
+    // NATURAL LANGUAGE TOOLS
     // ========================================================================
 
-    /// Answer or control intercom
+    /// Control the home with a natural-language request
     ///
-    /// Answer calls, open doors, or control intercom features
-    pub async fn control_intercom(
+    /// The request (e.g. "dim the kitchen lights to 30% and close the blinds")
+    /// is answered by an LLM: the connected client's model through MCP
+    /// sampling, or a provider configured for the server (Ollama, OpenAI or
+    /// Anthropic); `provider` picks one of "mcp", "ollama", "openai" or
+    /// "anthropic". The answer is parsed into device commands and checked
+    /// against the safety rules. By default this is a dry run that returns the
+    /// plan; with `dry_run: false` the commands are sent, except those that
+    /// need approval.
+    pub async fn execute_natural_language(
         &self,
-        intercom: String,
-        action: String,
+        request: String,
+        dry_run: Option<bool>,
+        provider: Option<String>,
     ) -> std::result::Result<serde_json::Value, String> {
         self.ensure_connected()?;
 
-        let normalized_action = match action.to_lowercase().as_str() {
-            "answer" | "annehmen" | "abheben" => "answer",
-            "hangup" | "auflegen" | "beenden" => "hangup",
-            "open" | "öffnen" | "tür" => "open_door",
-            "talk" | "sprechen" => "talk",
-            "mute" | "stumm" => "mute",
-            _ => {
-                return Err(format!(
-                    "Invalid action '{action}'. Use: answer, hangup, open, talk, mute"
-                ));
-            }
-        };
+        if request.trim().is_empty() {
+            return Err("Request must not be empty".to_string());
+        }
+        self.run_natural_language(&request, dry_run.unwrap_or(true), provider.as_deref())
+            .await
+            .map_err(|e| e.to_string())
+    }
 
-        let client = self.get_client()?;
+    // ========================================================================
+    // AUDIT TOOLS
+    // ========================================================================
 
-        // Map intercom actions to Loxone commands
-        let command = match normalized_action {
-            "answer" => "answer",
-            "hangup" => "hangup",
-            "open_door" => "open",
-            "talk" => "talk",
-            "mute" => "mute",
-            _ => normalized_action,
+    /// Get the audit log of device commands
+    ///
+    /// Returns the commands sent to the Miniserver (newest first) with the
+    /// tool and client that sent them, the response code and the latency.
+    /// `since` is an RFC 3339 time or a duration like "24h"; `device` matches
+    /// a control UUID or part of its name; `limit` defaults to 100.
+    pub async fn get_audit_log(
+        &self,
+        since: Option<String>,
+        tool: Option<String>,
+        device: Option<String>,
+        client: Option<String>,
+        limit: Option<u32>,
+    ) -> std::result::Result<serde_json::Value, String> {
+        let audit = self
+            .audit
+            .as_ref()
+            .ok_or_else(|| "Audit log is disabled (server.audit.enabled)".to_string())?;
+
+        let filter = AuditFilter {
+            since: since
+                .as_deref()
+                .map(crate::utils::parse_time)
+                .transpose()
+                .map_err(|e| e.to_string())?,
+            tool,
+            device,
+            client,
+            limit: limit.map(|limit| limit.clamp(1, 1000) as usize),
         };
-
-        let response = client
-            .send_command(&intercom, command)
+        let entries = audit
+            .query(&filter)
             .await
-            .map_err(|e| format!("Failed to control intercom {intercom}: {e}"))?;
+            .map_err(|e| format!("Failed to read audit log: {e}"))?;
 
         Ok(json!({
-            "intercom": intercom,
-            "action": normalized_action,
-            "command_sent": command,
-            "status": "executed",
-            "miniserver_response": response.value
+            "entries": entries,
+            "count": entries.len()
         }))
     }
 
-    /// Get intercom call history
-    pub async fn get_intercom_history(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    // ========================================================================
+    // RESOURCES
+    // ========================================================================
+    //
+    // One method per `ResourceManager` catalog entry; content is produced by
+    // the `ResourceHandler` implementation in `resource_handlers.rs`.
+
+    /// List of all rooms with device counts and information
+    #[mcp_resource(uri_template = "loxone://rooms")]
+    pub async fn all_rooms(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://rooms").await
+    }
 
-        Ok(json!({
-            "history": [],
-            "message": "Call history requires active connection to Loxone"
-        }))
+    /// All devices in a specific room
+    #[mcp_resource(uri_template = "loxone://rooms/{roomName}/devices")]
+    pub async fn room_devices(&self, room_name: String) -> std::result::Result<Value, String> {
+        self.read_catalog_resource(&format!("loxone://rooms/{room_name}/devices"))
+            .await
     }
 
-    // ========================================================================
-    // SCENE/MOOD TOOLS
-    // ========================================================================
+    /// Complete overview of a room including devices and statistics
+    #[mcp_resource(uri_template = "loxone://rooms/{roomName}/overview")]
+    pub async fn room_overview(&self, room_name: String) -> std::result::Result<Value, String> {
+        self.read_catalog_resource(&format!("loxone://rooms/{room_name}/overview"))
+            .await
+    }
 
-    /// Activate a scene or mood
-    ///
-    /// Trigger predefined scenes (moods) for rooms or the whole house
-    pub async fn activate_scene(
+    /// Complete list of all devices in the system
+    #[mcp_resource(uri_template = "loxone://devices/all")]
+    pub async fn all_devices(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://devices/all").await
+    }
+
+    /// All devices of a specific type
+    #[mcp_resource(uri_template = "loxone://devices/type/{deviceType}")]
+    pub async fn devices_by_type(&self, device_type: String) -> std::result::Result<Value, String> {
+        self.read_catalog_resource(&format!("loxone://devices/type/{device_type}"))
+            .await
+    }
+
+    /// All devices in a specific category
+    #[mcp_resource(uri_template = "loxone://devices/category/{category}")]
+    pub async fn devices_by_category(
         &self,
-        scene: String,
-        room: Option<String>,
-    ) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+        category: String,
+    ) -> std::result::Result<Value, String> {
+        self.read_catalog_resource(&format!("loxone://devices/category/{category}"))
+            .await
+    }
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
+    /// Overall system status and health information
+    #[mcp_resource(uri_template = "loxone://system/status")]
+    pub async fn system_status(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://system/status").await
+    }
+
+    /// Available system capabilities and features
+    #[mcp_resource(uri_template = "loxone://system/capabilities")]
+    pub async fn system_capabilities(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://system/capabilities")
             .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+    }
 
-        let scene_types = &["LightController", "MoodSwitch"];
+    /// Overview of all device categories with counts and examples
+    #[mcp_resource(uri_template = "loxone://system/categories")]
+    pub async fn system_categories(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://system/categories")
+            .await
+    }
 
-        // If scene looks like a UUID, send command directly
-        if scene.contains('-') && scene.len() > 30 {
-            let command = format!("changeTo/{scene}");
-            let response = client
-                .send_command(&scene, "on")
-                .await
-                .map_err(|e| format!("Failed to activate scene {scene}: {e}"))?;
-            return Ok(json!({
-                "scene": scene,
-                "room": room,
-                "command_sent": command,
-                "status": "activated",
-                "miniserver_response": response.value
-            }));
-        }
+    /// All audio zones and their current status
+    #[mcp_resource(uri_template = "loxone://audio/zones")]
+    pub async fn audio_zones(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://audio/zones").await
+    }
 
-        // Search for matching scene controllers
-        let controllers: Vec<(&String, &Value)> = if let Some(ref room_name) = room {
-            if let Some(room_uuid) = Self::resolve_room_uuid(&structure, room_name) {
-                Self::find_controls_by_type_in_room(&structure, &room_uuid, scene_types)
-            } else {
-                // Try matching by name
-                structure
-                    .controls
-                    .iter()
-                    .filter(|(_, control)| {
-                        let ct = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                        let name = control
-                            .get("name")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("")
-                            .to_lowercase();
-                        scene_types.contains(&ct) && name.contains(&room_name.to_lowercase())
-                    })
-                    .collect()
-            }
-        } else {
-            Self::find_controls_by_type(&structure, scene_types)
-        };
+    /// Available audio sources and their status
+    #[mcp_resource(uri_template = "loxone://audio/sources")]
+    pub async fn audio_sources(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://audio/sources").await
+    }
 
-        if controllers.is_empty() {
-            return Err(format!(
-                "No scene controllers found{}",
-                room.as_ref()
-                    .map(|r| format!(" in room '{r}'"))
-                    .unwrap_or_default()
-            ));
-        }
+    /// All door and window sensors with current state
+    #[mcp_resource(uri_template = "loxone://sensors/door-window")]
+    pub async fn door_window_sensors(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://sensors/door-window")
+            .await
+    }
 
-        // Try to match the scene name to a mood ID, or use the scene value directly
-        let scene_lower = scene.to_lowercase();
-        let mut results = Vec::new();
-        for (uuid, control) in &controllers {
-            let name = control
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("Unknown");
-
-            // Check if there are moods defined that match the scene name
-            let mood_id = if let Some(moods) = control.get("moods") {
-                if let Some(moods_obj) = moods.as_object() {
-                    moods_obj
-                        .iter()
-                        .find(|(_, v)| {
-                            v.as_str()
-                                .map(|s| s.to_lowercase().contains(&scene_lower))
-                                .unwrap_or(false)
-                        })
-                        .map(|(id, _)| id.clone())
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+    /// All temperature sensors and their current readings
+    #[mcp_resource(uri_template = "loxone://sensors/temperature")]
+    pub async fn temperature_sensors(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://sensors/temperature")
+            .await
+    }
 
-            let command = if let Some(ref id) = mood_id {
-                format!("changeTo/{id}")
-            } else {
-                // Try the scene string as a direct command (could be a mood number)
-                format!("changeTo/{scene}")
-            };
+    /// Dynamically discovered sensors with metadata
+    #[mcp_resource(uri_template = "loxone://sensors/discovered")]
+    pub async fn discovered_sensors(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://sensors/discovered")
+            .await
+    }
 
-            match client.send_command(uuid, &command).await {
-                Ok(response) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "command_sent": command,
-                        "mood_id": mood_id,
-                        "status": "activated",
-                        "miniserver_response": response.value
-                    }));
-                }
-                Err(e) => {
-                    results.push(json!({
-                        "uuid": uuid,
-                        "name": name,
-                        "command_sent": command,
-                        "status": "error",
-                        "error": format!("{e}")
-                    }));
-                }
-            }
-        }
+    /// All motion and presence sensors with current state
+    #[mcp_resource(uri_template = "loxone://sensors/motion")]
+    pub async fn motion_sensors(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://sensors/motion").await
+    }
 
-        Ok(json!({
-            "scene": scene,
-            "room": room,
-            "controllers_affected": results.len(),
-            "results": results
-        }))
+    /// All air quality sensors including CO2, VOC, humidity, and particulate matter
+    #[mcp_resource(uri_template = "loxone://sensors/air-quality")]
+    pub async fn air_quality_sensors(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://sensors/air-quality")
+            .await
     }
 
-    /// List available scenes
-    pub async fn list_scenes(&self) -> std::result::Result<serde_json::Value, String> {
-        self.ensure_connected()?;
+    /// All presence and occupancy detectors with room-level occupancy analytics
+    #[mcp_resource(uri_template = "loxone://sensors/presence")]
+    pub async fn presence_detectors(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://sensors/presence")
+            .await
+    }
 
-        let client = self.get_client()?;
-        let structure = client
-            .get_structure()
+    /// All weather station sensors including temperature, wind, rain, pressure, humidity, and solar radiation
+    #[mcp_resource(uri_template = "loxone://sensors/weather-station")]
+    pub async fn weather_station_sensors(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://sensors/weather-station")
+            .await
+    }
+
+    /// Current weather data from all weather sensors
+    #[mcp_resource(uri_template = "loxone://weather/current")]
+    pub async fn current_weather(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://weather/current").await
+    }
+
+    /// Outdoor environmental conditions with comfort assessment
+    #[mcp_resource(uri_template = "loxone://weather/outdoor-conditions")]
+    pub async fn outdoor_conditions(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://weather/outdoor-conditions")
             .await
-            .map_err(|e| format!("Failed to get structure: {e}"))?;
+    }
 
-        let mut scenes = Vec::new();
+    /// Multi-day weather forecast data
+    #[mcp_resource(uri_template = "loxone://weather/forecast-daily")]
+    pub async fn daily_weather_forecast(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://weather/forecast-daily")
+            .await
+    }
 
-        for (uuid, control) in &structure.controls {
-            let control_type = control.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    /// Hourly weather forecast data
+    #[mcp_resource(uri_template = "loxone://weather/forecast-hourly")]
+    pub async fn hourly_weather_forecast(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://weather/forecast-hourly")
+            .await
+    }
 
-            if matches!(control_type, "LightController" | "MoodSwitch") {
-                let name = control
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                let room = control
-                    .get("room")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
+    /// Current security system status and alarm states
+    #[mcp_resource(uri_template = "loxone://security/status")]
+    pub async fn security_system_status(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://security/status").await
+    }
 
-                // Extract moods if available
-                let moods = control.get("moods").cloned().unwrap_or(json!([]));
+    /// All security zones and their current states
+    #[mcp_resource(uri_template = "loxone://security/zones")]
+    pub async fn security_zones(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://security/zones").await
+    }
 
-                scenes.push(json!({
-                    "uuid": uuid,
-                    "name": name,
-                    "room": room,
-                    "moods": moods
-                }));
-            }
-        }
+    /// Current energy consumption and usage metrics
+    #[mcp_resource(uri_template = "loxone://energy/consumption")]
+    pub async fn energy_consumption(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://energy/consumption")
+            .await
+    }
 
-        Ok(json!({
-            "scene_controllers": scenes,
-            "count": scenes.len()
-        }))
+    /// All energy meters and their current readings
+    #[mcp_resource(uri_template = "loxone://energy/meters")]
+    pub async fn energy_meters(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://energy/meters").await
+    }
+
+    /// Historical energy usage data and trends
+    #[mcp_resource(uri_template = "loxone://energy/usage-history")]
+    pub async fn energy_usage_history(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://energy/usage-history")
+            .await
+    }
+
+    /// Overview of the climate control system
+    #[mcp_resource(uri_template = "loxone://climate/overview")]
+    pub async fn climate_overview(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://climate/overview")
+            .await
+    }
+
+    /// Climate data for a specific room
+    #[mcp_resource(uri_template = "loxone://climate/rooms/{roomName}")]
+    pub async fn room_climate(&self, room_name: String) -> std::result::Result<Value, String> {
+        self.read_catalog_resource(&format!("loxone://climate/rooms/{room_name}"))
+            .await
+    }
+
+    /// All temperature sensor readings
+    #[mcp_resource(uri_template = "loxone://climate/sensors")]
+    pub async fn climate_sensors(&self) -> std::result::Result<Value, String> {
+        self.read_catalog_resource("loxone://climate/sensors").await
     }
 }
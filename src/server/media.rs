@@ -0,0 +1,160 @@
+//! Image content for intercom and camera tools
+//!
+//! Tools return images inline as `{"image": {"mime_type", "data"}}` objects with
+//! base64 data. [`with_image_content`] moves them out of the JSON text into MCP
+//! image content, so assistants receive the picture rather than a base64 blob.
+
+use crate::client::LoxoneImage;
+use base64::Engine;
+use chrono::NaiveDateTime;
+use pulseengine_mcp_protocol::{CallToolResult, Content};
+use serde_json::{Value, json};
+
+/// Timestamp format of intercom bell events (`YYYYMMDDhhmmss`)
+const BELL_EVENT_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// Parse the `lastBellEvents` state of an intercom, newest first
+///
+/// The Miniserver reports the timestamps as a `|` separated list; anything
+/// that is not a valid timestamp is skipped.
+pub fn parse_bell_events(value: &str) -> Vec<String> {
+    let mut events: Vec<String> = value
+        .split(['|', ','])
+        .map(str::trim)
+        .filter(|ts| bell_event_time(ts).is_some())
+        .map(str::to_string)
+        .collect();
+    events.sort_unstable_by(|a, b| b.cmp(a));
+    events.dedup();
+    events
+}
+
+/// Local time of a bell event timestamp
+pub fn bell_event_time(timestamp: &str) -> Option<NaiveDateTime> {
+    if timestamp.len() != 14 {
+        return None;
+    }
+    NaiveDateTime::parse_from_str(timestamp, BELL_EVENT_FORMAT).ok()
+}
+
+/// JSON payload of an image, picked up by [`with_image_content`]
+pub fn image_json(image: &LoxoneImage) -> Value {
+    json!({
+        "mime_type": image.mime_type,
+        "size_bytes": image.data.len(),
+        "data": base64::engine::general_purpose::STANDARD.encode(&image.data)
+    })
+}
+
+/// Move inline images of a tool result into MCP image content
+///
+/// The base64 data is removed from the JSON text and structured content;
+/// each image is replaced by a `content_index` pointing at its image content.
+pub fn with_image_content(mut result: CallToolResult) -> CallToolResult {
+    let Some(mut structured) = result.structured_content.take() else {
+        return result;
+    };
+
+    let mut images = Vec::new();
+    extract_images(&mut structured, &mut images);
+    if images.is_empty() {
+        result.structured_content = Some(structured);
+        return result;
+    }
+
+    let text = serde_json::to_string(&structured).unwrap_or_default();
+    result.content = std::iter::once(Content::text(text)).chain(images).collect();
+    result.structured_content = Some(structured);
+    result
+}
+
+fn extract_images(value: &mut Value, images: &mut Vec<Content>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Object(image)) = map.get_mut("image")
+                && let (Some(Value::String(data)), Some(mime_type)) = (
+                    image.remove("data"),
+                    image.get("mime_type").and_then(Value::as_str),
+                )
+            {
+                images.push(Content::image(data, mime_type));
+                // Content 0 is the JSON text
+                image.insert("content_index".to_string(), json!(images.len()));
+            }
+            for (key, child) in map.iter_mut() {
+                if key != "image" {
+                    extract_images(child, images);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                extract_images(item, images);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_result(value: Value) -> CallToolResult {
+        CallToolResult {
+            content: vec![Content::text(value.to_string())],
+            is_error: Some(false),
+            structured_content: Some(value),
+            _meta: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_bell_events() {
+        let events = parse_bell_events("20241018101500|20241019073000||garbage|20241018101500");
+        assert_eq!(events, vec!["20241019073000", "20241018101500"]);
+        assert_eq!(
+            bell_event_time(&events[0]).unwrap().to_string(),
+            "2024-10-19 07:30:00"
+        );
+        assert!(parse_bell_events("").is_empty());
+        assert!(bell_event_time("20241340000000").is_none());
+    }
+
+    #[test]
+    fn test_images_become_image_content() {
+        let image = LoxoneImage {
+            data: vec![0xff, 0xd8, 0xff],
+            mime_type: "image/jpeg".to_string(),
+        };
+        let result = with_image_content(tool_result(json!({
+            "events": [
+                { "timestamp": "20241019073000", "image": image_json(&image) },
+                { "timestamp": "20241018101500", "image_error": "not found" }
+            ]
+        })));
+
+        assert_eq!(result.content.len(), 2);
+        let Content::Image {
+            data, mime_type, ..
+        } = &result.content[1]
+        else {
+            panic!("expected image content");
+        };
+        assert_eq!(data, "/9j/");
+        assert_eq!(mime_type, "image/jpeg");
+
+        let structured = result.structured_content.unwrap();
+        let payload = &structured["events"][0]["image"];
+        assert!(payload.get("data").is_none());
+        assert_eq!(payload["content_index"], 1);
+        assert_eq!(payload["size_bytes"], 3);
+    }
+
+    #[test]
+    fn test_results_without_images_are_unchanged() {
+        let result = with_image_content(tool_result(json!({ "count": 0 })));
+        assert_eq!(result.content.len(), 1);
+        assert_eq!(result.structured_content.unwrap()["count"], 0);
+    }
+}
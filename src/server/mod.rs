@@ -2,11 +2,23 @@
 //!
 //! This module contains the macro-based MCP server and supporting components.
 
+pub mod audio;
+pub mod bulk;
+pub mod climate;
+pub mod consent;
+pub mod context_builders;
 pub mod framework_backend;
 pub mod health_check;
+pub mod lighting;
 pub mod loxone_batch_executor;
 pub mod macro_backend;
+pub mod media;
 pub mod models;
+pub mod moods;
+pub mod natural_language;
+
+/// MCP prompts rendered with live context
+pub mod prompts;
 pub mod rate_limiter;
 pub mod request_coalescing;
 pub mod request_context;
@@ -14,12 +26,21 @@ pub mod resource_monitor;
 pub mod response_cache;
 pub mod schema_validation;
 
+/// Stdio transport that can send requests to the client
+pub mod stdio_transport;
+
 // Legacy MCP Resources enabled for weather storage integration
 pub mod resources;
 
+/// `loxone://` resource handlers served by the macro-based server
+pub mod resource_handlers;
+
 /// Real-time resource subscription system for MCP
 pub mod subscription;
 
+/// Serving with live `resources/subscribe` notifications
+pub mod subscription_runtime;
+
 pub use framework_backend::*;
 pub use models::*;
 pub use request_context::*;
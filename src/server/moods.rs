@@ -0,0 +1,268 @@
+//! Light controller moods: mood list, active mix and favorites
+//!
+//! A `LightControllerV2` reports its moods as JSON text states: `moodList`
+//! holds every mood with its ID and name, while `activeMoods`,
+//! `favoriteMoods` and `additionalMoods` hold ordered mood IDs. Legacy light
+//! controllers list their moods in the structure file instead. These helpers
+//! read those values and resolve the mood names the mood tools accept.
+
+use super::climate::state_value;
+use crate::client::LoxoneControl;
+use crate::services::name_resolution::canonical;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Light controller states reported by the mood tools
+pub const MOOD_STATES: &[&str] = &[
+    "moodList",
+    "activeMoods",
+    "favoriteMoods",
+    "additionalMoods",
+];
+
+/// Built-in mood switching every output on
+pub const ALL_ON_MOOD: i64 = 777;
+/// Built-in mood switching every output off
+pub const ALL_OFF_MOOD: i64 = 778;
+
+/// Mood of a light controller
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LiveMood {
+    /// Mood ID used by `changeTo/{id}` and the mix commands
+    pub id: i64,
+    /// Display name
+    pub name: String,
+    /// Built-in moods can be neither learned nor deleted
+    #[serde(rename = "static")]
+    pub is_static: bool,
+}
+
+/// Moods of a controller: the `moodList` state, else the structure's moods
+pub fn mood_list(control: &LoxoneControl, values: &HashMap<String, Value>) -> Vec<LiveMood> {
+    let listed = state_value(control, "moodList", values)
+        .map(|value| parse_mood_list(&value))
+        .unwrap_or_default();
+    if !listed.is_empty() {
+        return listed;
+    }
+    control
+        .moods
+        .iter()
+        .filter_map(|mood| {
+            let id = mood.id.parse().ok()?;
+            Some(LiveMood {
+                id,
+                name: mood.name.clone(),
+                is_static: is_builtin(id),
+            })
+        })
+        .collect()
+}
+
+/// Parse a `moodList` value (a JSON array, or JSON text holding one)
+pub fn parse_mood_list(value: &Value) -> Vec<LiveMood> {
+    let moods = match value {
+        Value::String(text) => match serde_json::from_str::<Value>(text) {
+            Ok(Value::Array(moods)) => moods,
+            _ => return Vec::new(),
+        },
+        Value::Array(moods) => moods.clone(),
+        _ => return Vec::new(),
+    };
+    moods
+        .iter()
+        .filter_map(|mood| {
+            let id = id_value(mood.get("id")?)?;
+            Some(LiveMood {
+                id,
+                name: mood.get("name")?.as_str()?.to_string(),
+                is_static: mood
+                    .get("static")
+                    .and_then(Value::as_bool)
+                    .unwrap_or_else(|| is_builtin(id)),
+            })
+        })
+        .collect()
+}
+
+/// Mood IDs of an ID list state (`activeMoods`, `favoriteMoods`, ...)
+pub fn mood_ids(control: &LoxoneControl, name: &str, values: &HashMap<String, Value>) -> Vec<i64> {
+    match state_value(control, name, values) {
+        Some(Value::String(text)) => match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Array(ids)) => ids.iter().filter_map(id_value).collect(),
+            _ => Vec::new(),
+        },
+        Some(Value::Array(ids)) => ids.iter().filter_map(id_value).collect(),
+        // A single active mood arrives as a plain number
+        Some(value) => id_value(&value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+fn id_value(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(id) => id.as_f64().map(|id| id as i64),
+        Value::String(id) => id.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn is_builtin(id: i64) -> bool {
+    matches!(id, ALL_ON_MOOD | ALL_OFF_MOOD)
+}
+
+/// Moods of a controller with their active and favorite flags
+pub fn moods_status(control: &LoxoneControl, values: &HashMap<String, Value>) -> Value {
+    let moods = mood_list(control, values);
+    let active = mood_ids(control, "activeMoods", values);
+    let favorites = mood_ids(control, "favoriteMoods", values);
+    let name = |id: &i64| {
+        moods
+            .iter()
+            .find(|mood| mood.id == *id)
+            .map_or_else(|| id.to_string(), |mood| mood.name.clone())
+    };
+
+    json!({
+        "moods": moods
+            .iter()
+            .map(|mood| json!({
+                "id": mood.id,
+                "name": mood.name,
+                "static": mood.is_static,
+                "active": active.contains(&mood.id),
+                "favorite": favorites.contains(&mood.id),
+            }))
+            .collect::<Vec<_>>(),
+        "active_moods": active.iter().map(name).collect::<Vec<_>>(),
+        "favorite_moods": favorites.iter().map(name).collect::<Vec<_>>(),
+    })
+}
+
+/// ID of a mood given by ID or by name
+///
+/// Names match after normalization, exactly first, then as a substring. An
+/// ID is accepted as is when the mood list is unknown.
+pub fn find_mood(moods: &[LiveMood], mood: &str) -> Result<i64, String> {
+    let found = match mood.trim().parse::<i64>() {
+        Ok(id) if moods.is_empty() => return Ok(id),
+        Ok(id) => moods.iter().find(|known| known.id == id),
+        Err(_) => find_named(moods, mood).or_else(|| {
+            let wanted = canonical(mood);
+            moods
+                .iter()
+                .find(|known| canonical(&known.name).contains(&wanted))
+        }),
+    };
+    found.map(|mood| mood.id).ok_or_else(|| {
+        let names: Vec<&str> = moods.iter().map(|mood| mood.name.as_str()).collect();
+        format!(
+            "No mood '{mood}'. Moods: {}",
+            if names.is_empty() {
+                "none known yet".to_string()
+            } else {
+                names.join(", ")
+            }
+        )
+    })
+}
+
+/// The mood named exactly `name` (after normalization)
+pub fn find_named<'a>(moods: &'a [LiveMood], name: &str) -> Option<&'a LiveMood> {
+    let wanted = canonical(name);
+    moods.iter().find(|mood| canonical(&mood.name) == wanted)
+}
+
+/// Lowest mood ID not in use, for learning a new mood
+pub fn next_mood_id(moods: &[LiveMood]) -> i64 {
+    (1..)
+        .find(|id| !is_builtin(*id) && moods.iter().all(|mood| mood.id != *id))
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ControlModel, LoxoneStructure};
+
+    fn model() -> ControlModel {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {},
+            "cats": {},
+            "controls": {
+                "lc-1": {
+                    "name": "Kitchen Lights",
+                    "type": "LightControllerV2",
+                    "states": {
+                        "moodList": "s-list", "activeMoods": "s-active",
+                        "favoriteMoods": "s-favorites"
+                    }
+                },
+                "legacy-1": {
+                    "name": "Hall Lights",
+                    "type": "LightController",
+                    "moods": { "778": "Off", "2": "Reading", "1": "Bright" }
+                }
+            }
+        }))
+        .unwrap();
+        ControlModel::from_structure(&structure)
+    }
+
+    #[test]
+    fn test_mood_states() {
+        let model = model();
+        let kitchen = model.get("lc-1").unwrap();
+        let values: HashMap<String, Value> = [
+            (
+                "s-list",
+                json!(r#"[{"name":"Cooking","id":1,"static":false},{"name":"Dinner","id":2},{"name":"Off","id":778,"static":true}]"#),
+            ),
+            ("s-active", json!({ "LL": { "value": "[2]" } })),
+            ("s-favorites", json!("[2, 1]")),
+        ]
+        .into_iter()
+        .map(|(uuid, value)| (uuid.to_string(), value))
+        .collect();
+
+        let moods = mood_list(kitchen, &values);
+        assert_eq!(moods.len(), 3);
+        assert!(moods[2].is_static);
+        let status = moods_status(kitchen, &values);
+        assert_eq!(status["active_moods"], json!(["Dinner"]));
+        assert_eq!(status["favorite_moods"], json!(["Dinner", "Cooking"]));
+        assert_eq!(status["moods"][1]["active"], true);
+        assert_eq!(status["moods"][0]["favorite"], true);
+
+        // Legacy controllers fall back to the structure's moods
+        let legacy = mood_list(model.get("legacy-1").unwrap(), &HashMap::new());
+        let ids: Vec<i64> = legacy.iter().map(|mood| mood.id).collect();
+        assert_eq!(ids, [1, 2, 778]);
+        assert!(legacy[2].is_static);
+    }
+
+    #[test]
+    fn test_find_mood_and_next_id() {
+        let moods = parse_mood_list(&json!([
+            { "name": "Cooking", "id": 1 },
+            { "name": "Dinner", "id": 3 },
+            { "name": "Dinner Party", "id": 2 },
+            { "name": "Bright", "id": 777 }
+        ]));
+        assert_eq!(find_mood(&moods, "dinner").unwrap(), 3);
+        assert_eq!(find_mood(&moods, "party").unwrap(), 2);
+        assert_eq!(find_mood(&moods, "777").unwrap(), 777);
+        assert!(find_mood(&moods, "12").is_err());
+        assert!(
+            find_mood(&moods, "Movie")
+                .unwrap_err()
+                .contains("Cooking, Dinner, Dinner Party, Bright")
+        );
+        assert_eq!(find_mood(&[], "12").unwrap(), 12);
+        assert!(find_named(&moods, "Din").is_none());
+        assert_eq!(next_mood_id(&moods), 4);
+        assert_eq!(next_mood_id(&[]), 1);
+    }
+}
@@ -0,0 +1,172 @@
+//! Natural-language device control through the sampling pipeline
+//!
+//! A request goes to an LLM (see [`crate::sampling::providers`]), the answer is
+//! parsed into device commands, checked against the executor's safety rules and,
+//! unless it is a dry run, sent to the Miniserver. The server is the
+//! [`CommandSink`]: names resolve with the fuzzy name resolver and every command
+//! is built for the control type, validated against the command catalog and
+//! audited like any other tool call.
+
+use crate::client::{ClientContext, ControlType, LoxoneControl};
+use crate::error::{LoxoneError, Result};
+use crate::sampling::config::ProviderFactoryConfig;
+use crate::sampling::executor::{CommandSink, ExecutionContext};
+use crate::sampling::providers::sampling_client;
+use crate::sampling::service::{SamplingService, SamplingServiceConfig};
+use crate::server::macro_backend::LoxoneMcpServer;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+#[async_trait]
+impl CommandSink for LoxoneMcpServer {
+    async fn resolve_device(&self, device: &str, room: Option<&str>) -> Result<String> {
+        let model = self.control_model().await?;
+        let resolved = match (self.resolve_control(&model, device), room) {
+            (Ok(control), _) => Ok(control),
+            // "Ceiling Light" in "Kitchen" may only be unique together with the room
+            (Err(_), Some(room)) => self.resolve_control(&model, &format!("{room} {device}")),
+            (Err(e), None) => Err(e),
+        };
+        resolved.map(|control| control.uuid.clone())
+    }
+
+    async fn send_action(
+        &self,
+        device_uuid: &str,
+        action: &str,
+        value: Option<&str>,
+    ) -> Result<String> {
+        let model = self.control_model().await?;
+        let control = self.resolve_control(&model, device_uuid)?;
+        let command = action_command(control, action, value)?;
+        self.send_control_command(control, &command).await?;
+        Ok(format!("Sent '{command}' to {}", control.name))
+    }
+}
+
+/// Loxone command for a parsed action on a control
+fn action_command(control: &LoxoneControl, action: &str, value: Option<&str>) -> Result<String> {
+    let number = |what: &str| {
+        value
+            .and_then(|value| value.parse::<f64>().ok())
+            .ok_or_else(|| LoxoneError::invalid_input(format!("'{action}' needs a {what}")))
+    };
+    match action {
+        "on" | "off" => LoxoneMcpServer::light_command(control, action, None),
+        "dim" | "brighten" => {
+            let level = number("brightness")?.clamp(0.0, 100.0) as u8;
+            LoxoneMcpServer::light_command(control, "on", Some(level))
+        }
+        "up" => Ok("FullUp".to_string()),
+        "down" => Ok("FullDown".to_string()),
+        "stop" | "play" | "pause" => Ok(action.to_string()),
+        "volume" => control.build_command("volume", &[&(number("volume")? as u8)]),
+        "set_temperature" => {
+            let temperature = number("temperature")?;
+            match control.control_type {
+                ControlType::IRoomControllerV2 => {
+                    control.build_command("setComfortTemperature", &[&temperature])
+                }
+                // Index 1 is the comfort temperature of the legacy controller
+                _ => control.build_command("settemp", &[&1, &temperature]),
+            }
+        }
+        other => Err(LoxoneError::invalid_input(format!(
+            "Unsupported action '{other}'"
+        ))),
+    }
+}
+
+impl LoxoneMcpServer {
+    /// Plan (and unless `dry_run`, execute) a natural-language request
+    pub(super) async fn run_natural_language(
+        &self,
+        request: &str,
+        dry_run: bool,
+        provider: Option<&str>,
+    ) -> Result<Value> {
+        let client = sampling_client(&ProviderFactoryConfig::from_env(), provider)?;
+        let client_context = self
+            .context()
+            .cloned()
+            .unwrap_or_else(|| Arc::new(ClientContext::new()));
+        let service =
+            SamplingService::new(client, client_context, SamplingServiceConfig::default())
+                .with_command_sink(Arc::new(self.clone()));
+
+        let context = ExecutionContext {
+            user_id: "mcp".to_string(),
+            dry_run,
+            ..ExecutionContext::default()
+        };
+        let result = service
+            .process_automation_request(request.to_string(), context)
+            .await?;
+
+        let plan = &result.parsed_response;
+        let execution = &result.execution_result;
+        Ok(json!({
+            "request": request,
+            "dry_run": dry_run,
+            "plan": {
+                "analysis": plan.analysis,
+                "confidence": plan.confidence,
+                "commands": plan.commands,
+                "recommendations": plan.recommendations,
+            },
+            "results": execution.results,
+            "approval_required": execution.approval_required,
+            "success_count": execution.success_count,
+            "failure_count": execution.failure_count,
+            "llm_response": result.llm_response,
+            "metrics": result.metrics,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ControlModel, LoxoneStructure};
+
+    fn model() -> ControlModel {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {},
+            "cats": {},
+            "controls": {
+                "dimmer": { "name": "Kitchen Dimmer", "type": "Dimmer" },
+                "switch": { "name": "Hall Light", "type": "Switch" },
+                "blind": { "name": "Kitchen Blind", "type": "Jalousie" },
+                "heating": { "name": "Kitchen Heating", "type": "IRoomControllerV2" },
+                "audio": { "name": "Kitchen Audio", "type": "AudioZoneV2" }
+            }
+        }))
+        .unwrap();
+        ControlModel::from_structure(&structure)
+    }
+
+    fn command(model: &ControlModel, uuid: &str, action: &str, value: Option<&str>) -> String {
+        let control = model.controls().find(|c| c.uuid == uuid).unwrap();
+        action_command(control, action, value).unwrap()
+    }
+
+    #[test]
+    fn test_actions_map_to_control_commands() {
+        let model = model();
+        assert_eq!(command(&model, "dimmer", "dim", Some("30")), "30");
+        assert_eq!(command(&model, "switch", "brighten", Some("80")), "on");
+        assert_eq!(command(&model, "switch", "off", None), "off");
+        assert_eq!(command(&model, "blind", "down", None), "FullDown");
+        assert_eq!(
+            command(&model, "heating", "set_temperature", Some("21.5")),
+            "setComfortTemperature/21.5"
+        );
+        assert_eq!(command(&model, "audio", "volume", Some("35")), "volume/35");
+
+        let audio = model.controls().find(|c| c.uuid == "audio").unwrap();
+        assert!(action_command(audio, "volume", None).is_err());
+        assert!(action_command(audio, "teleport", None).is_err());
+    }
+}
@@ -0,0 +1,423 @@
+//! MCP prompts for common home-automation intents
+//!
+//! Each prompt is rendered on request with live context from [`ContextBuilder`]:
+//! the instruction is followed by the aggregated [`LlmContext`] as an embedded
+//! `loxone://context/{prompt}` resource, so the client's model plans against the
+//! current state of the home rather than a static description.
+//!
+//! `cozy_atmosphere` and `prepare_for_event` mirror the sampling flows in
+//! [`crate::sampling`], for clients that run the model themselves.
+
+use crate::error::{LoxoneError, Result};
+use crate::server::context_builders::{ContextBuilder, ContextType, LlmContext, TemporalContext};
+use crate::server::macro_backend::LoxoneMcpServer;
+use pulseengine_mcp_protocol::{
+    GetPromptResult, Prompt, PromptArgument, PromptMessage, PromptMessageRole,
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+pub const EVENING_ROUTINE: &str = "evening_routine";
+pub const ENERGY_AUDIT: &str = "energy_audit";
+pub const LEAVING_HOME: &str = "leaving_home";
+pub const COZY_ATMOSPHERE: &str = "cozy_atmosphere";
+pub const PREPARE_FOR_EVENT: &str = "prepare_for_event";
+
+/// Prompt argument as (name, description, required)
+type ArgumentSpec = (&'static str, &'static str, bool);
+
+struct PromptSpec {
+    name: &'static str,
+    description: &'static str,
+    arguments: &'static [ArgumentSpec],
+}
+
+const PROMPTS: &[PromptSpec] = &[
+    PromptSpec {
+        name: EVENING_ROUTINE,
+        description: "Plan an evening routine for a room: lights, blinds and temperature",
+        arguments: &[(
+            "room",
+            "Room name, e.g. 'Living Room' or 'Wohnzimmer'",
+            true,
+        )],
+    },
+    PromptSpec {
+        name: ENERGY_AUDIT,
+        description: "Audit energy use and recommend savings from live consumption and device data",
+        arguments: &[(
+            "focus",
+            "Area to focus on: lighting, climate or overall (default)",
+            false,
+        )],
+    },
+    PromptSpec {
+        name: LEAVING_HOME,
+        description: "Checklist before leaving the house: lights, doors and windows, alarm, climate",
+        arguments: &[(
+            "duration",
+            "How long the house stays empty, e.g. '2 hours' or 'a week'",
+            false,
+        )],
+    },
+    PromptSpec {
+        name: COZY_ATMOSPHERE,
+        description: "Suggest lighting, temperature and blind settings for a cozy atmosphere",
+        arguments: &[
+            (
+                "time_of_day",
+                "Morning, afternoon, evening or night (default: now)",
+                false,
+            ),
+            ("weather", "Weather outside, e.g. 'rainy' or 'cold'", false),
+            (
+                "mood",
+                "Desired mood: relaxing (default), romantic, energizing, peaceful",
+                false,
+            ),
+        ],
+    },
+    PromptSpec {
+        name: PREPARE_FOR_EVENT,
+        description: "Prepare the home for an event such as a party, movie night or dinner",
+        arguments: &[
+            (
+                "event_type",
+                "Type of event: party, movie_night, dinner, work_meeting, reading",
+                true,
+            ),
+            ("room", "Primary room for the event", false),
+            ("duration", "Expected duration of the event", false),
+            ("guest_count", "Number of guests expected", false),
+        ],
+    },
+];
+
+/// All prompts offered by the server
+pub fn list() -> Vec<Prompt> {
+    PROMPTS
+        .iter()
+        .map(|spec| Prompt {
+            name: spec.name.to_string(),
+            title: None,
+            description: Some(spec.description.to_string()),
+            arguments: Some(
+                spec.arguments
+                    .iter()
+                    .map(|(name, description, required)| PromptArgument {
+                        name: name.to_string(),
+                        description: Some(description.to_string()),
+                        required: Some(*required),
+                    })
+                    .collect(),
+            ),
+            icons: None,
+        })
+        .collect()
+}
+
+impl LoxoneMcpServer {
+    /// Render a prompt with live context from the Miniserver
+    pub async fn render_prompt(
+        &self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<GetPromptResult> {
+        let spec = PROMPTS
+            .iter()
+            .find(|spec| spec.name == name)
+            .ok_or_else(|| LoxoneError::not_found(format!("Unknown prompt: {name}")))?;
+        let argument = |key: &str| {
+            arguments
+                .get(key)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        if let Some((missing, _, _)) = spec
+            .arguments
+            .iter()
+            .find(|(key, _, required)| *required && argument(key).is_none())
+        {
+            return Err(LoxoneError::invalid_input(format!(
+                "Prompt '{name}' requires the '{missing}' argument"
+            )));
+        }
+
+        let temporal = TemporalContext::default();
+        let now = format!("{} on {}", temporal.time_of_day, temporal.day_of_week);
+        let (instruction, context_type, resources) = match name {
+            EVENING_ROUTINE => {
+                let room = self
+                    .prompt_room(argument("room").unwrap_or_default())
+                    .await?;
+                (
+                    format!(
+                        "Plan an evening routine for the {room}. It is {now}. Dim the lights to a \
+                         relaxing level, close the blinds for privacy and choose a comfortable \
+                         temperature for the rest of the evening. Base every step on the live \
+                         device states in the attached context, list the tool calls you would make \
+                         (e.g. `control_light`, `control_blind`, `set_temperature`) and ask before \
+                         changing anything."
+                    ),
+                    ContextType::Comfort,
+                    room_resources(&room, &["devices"], &["climate/rooms"]),
+                )
+            }
+            ENERGY_AUDIT => {
+                let focus = argument("focus").unwrap_or("overall");
+                (
+                    format!(
+                        "Audit the energy use of this home with a focus on {focus}. Using the \
+                         consumption, meter and device data in the attached context, point out \
+                         devices using energy without need, rooms lit or heated while nobody is \
+                         there, and schedule or setpoint changes that would save energy. Rank the \
+                         recommendations by expected savings and name the tools that would apply \
+                         them."
+                    ),
+                    ContextType::Energy,
+                    Vec::new(),
+                )
+            }
+            LEAVING_HOME => {
+                let away = argument("duration")
+                    .map(|duration| format!(" for {duration}"))
+                    .unwrap_or_default();
+                (
+                    format!(
+                        "I'm leaving the house{away}. Go through a leaving-the-house checklist with \
+                         the attached context: lights still on, open doors and windows, the alarm \
+                         state, climate setpoints that should drop to eco or building protection, \
+                         and blinds. Report each item as OK or needing action, and propose the \
+                         tool calls for the items that need action, as a `bulk_action` dry run \
+                         where several devices are affected."
+                    ),
+                    ContextType::Security,
+                    vec![
+                        "loxone://security/status".to_string(),
+                        "loxone://climate/overview".to_string(),
+                        "loxone://devices/category/blinds".to_string(),
+                    ],
+                )
+            }
+            COZY_ATMOSPHERE => {
+                let time_of_day = argument("time_of_day").unwrap_or(&temporal.time_of_day);
+                let weather = argument("weather")
+                    .map(|weather| format!(" and the weather is {weather}"))
+                    .unwrap_or_default();
+                let mood = argument("mood").unwrap_or("relaxing");
+                (
+                    format!(
+                        "I want to make my home cozy. It's {time_of_day}{weather}. I'm looking for \
+                         a {mood} atmosphere. Please analyze the current state in the attached \
+                         context and suggest optimal settings for lighting, temperature, and \
+                         blinds."
+                    ),
+                    ContextType::Comfort,
+                    vec![
+                        "loxone://devices/category/lighting".to_string(),
+                        "loxone://weather/current".to_string(),
+                    ],
+                )
+            }
+            PREPARE_FOR_EVENT => {
+                let event_type = argument("event_type").unwrap_or_default();
+                let mut description = format!("I'm preparing for a {event_type}");
+                let mut resources = Vec::new();
+                if let Some(room) = argument("room") {
+                    let room = self.prompt_room(room).await?;
+                    description.push_str(&format!(" in the {room}"));
+                    resources = room_resources(&room, &["devices"], &[]);
+                }
+                if let Some(duration) = argument("duration") {
+                    description.push_str(&format!(" lasting {duration}"));
+                }
+                if let Some(guest_count) = argument("guest_count") {
+                    description.push_str(&format!(" with {guest_count} guests"));
+                }
+                (
+                    format!(
+                        "{description}. Please suggest the optimal home automation settings based \
+                         on the attached context."
+                    ),
+                    ContextType::Entertainment,
+                    resources,
+                )
+            }
+            _ => unreachable!("prompt specs and renderers are kept in sync"),
+        };
+
+        let context = ContextBuilder::new(self, context_type)
+            .with_resources(resources.iter().map(String::as_str).collect())
+            .with_metadata("prompt", json!(name))
+            .with_metadata("arguments", json!(arguments))
+            .with_temporal_context(temporal)
+            .build()
+            .await?;
+
+        prompt_result(spec, instruction, &context)
+    }
+
+    /// Canonical name of a room given in a prompt argument
+    async fn prompt_room(&self, room: &str) -> Result<String> {
+        let model = self.control_model().await?;
+        let uuid = self.resolve_room(&model, room)?;
+        Ok(model.room_name(uuid).unwrap_or(room).to_string())
+    }
+}
+
+/// `loxone://rooms/{room}/...` and `loxone://.../{room}` resources for a room
+fn room_resources(room: &str, suffixes: &[&str], prefixes: &[&str]) -> Vec<String> {
+    let room = urlencoding::encode(room);
+    suffixes
+        .iter()
+        .map(|suffix| format!("loxone://rooms/{room}/{suffix}"))
+        .chain(
+            prefixes
+                .iter()
+                .map(|prefix| format!("loxone://{prefix}/{room}")),
+        )
+        .collect()
+}
+
+fn prompt_result(
+    spec: &PromptSpec,
+    instruction: String,
+    context: &LlmContext,
+) -> Result<GetPromptResult> {
+    let text = format!("{instruction}\n\nHome overview: {}", context.summary());
+    let embedded = serde_json::to_string_pretty(&context.to_json()?)?;
+
+    Ok(GetPromptResult {
+        description: Some(spec.description.to_string()),
+        messages: vec![
+            PromptMessage::new_text(PromptMessageRole::User, text),
+            PromptMessage::new_resource(
+                PromptMessageRole::User,
+                format!("loxone://context/{}", spec.name),
+                Some("application/json".to_string()),
+                Some(embedded),
+            ),
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ClientContext, LoxoneClient, LoxoneStructure};
+    use crate::config::ServerConfig;
+    use crate::mock::MockLoxoneClient;
+    use crate::services::{SensorTypeRegistry, UnifiedValueResolver};
+    use pulseengine_mcp_protocol::PromptMessageContent;
+    use std::sync::Arc;
+
+    fn test_server() -> LoxoneMcpServer {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {
+                "room-1": { "name": "Living Room", "type": 1 },
+                "room-2": { "name": "Kitchen", "type": 2 }
+            },
+            "controls": {
+                "light-1": { "name": "Ceiling Light", "type": "Dimmer", "room": "room-1" },
+                "blind-1": { "name": "Window Blind", "type": "Jalousie", "room": "room-1" },
+                "climate-1": { "name": "Living Climate", "type": "IRoomControllerV2", "room": "room-1" }
+            },
+            "cats": {}
+        }))
+        .unwrap();
+        let client: Arc<dyn LoxoneClient> =
+            Arc::new(MockLoxoneClient::new().with_structure(structure));
+        let value_resolver = Arc::new(UnifiedValueResolver::new(
+            client.clone(),
+            Arc::new(SensorTypeRegistry::new()),
+        ));
+        LoxoneMcpServer::with_context(
+            client,
+            Arc::new(ClientContext::new()),
+            value_resolver,
+            None,
+            ServerConfig::default(),
+        )
+    }
+
+    fn arguments(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_prompts_are_listed_with_arguments() {
+        let prompts = list();
+        let names: Vec<&str> = prompts.iter().map(|prompt| prompt.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                EVENING_ROUTINE,
+                ENERGY_AUDIT,
+                LEAVING_HOME,
+                COZY_ATMOSPHERE,
+                PREPARE_FOR_EVENT
+            ]
+        );
+
+        let evening = &prompts[0].arguments.as_ref().unwrap()[0];
+        assert_eq!(evening.name, "room");
+        assert_eq!(evening.required, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_evening_routine_embeds_room_context() {
+        let server = test_server();
+        let result = server
+            .render_prompt(EVENING_ROUTINE, &arguments(&[("room", "wohnzimmer")]))
+            .await
+            .unwrap();
+
+        let PromptMessageContent::Text { text } = &result.messages[0].content else {
+            panic!("expected the instruction first");
+        };
+        assert!(text.starts_with("Plan an evening routine for the Living Room."));
+
+        let PromptMessageContent::Resource { resource } = &result.messages[1].content else {
+            panic!("expected the embedded context second");
+        };
+        assert_eq!(resource.uri, "loxone://context/evening_routine");
+        let context: serde_json::Value =
+            serde_json::from_str(resource.text.as_deref().unwrap()).unwrap();
+        assert_eq!(context["context_type"], "comfort");
+        assert_eq!(
+            context["resources"]["rooms_Living Room_devices"]["count"],
+            3
+        );
+        assert_eq!(context["metadata"]["arguments"]["room"], "wohnzimmer");
+        assert!(context["metadata"]["temporal_context"]["time_of_day"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_arguments_are_checked() {
+        let server = test_server();
+
+        let missing = server
+            .render_prompt(EVENING_ROUTINE, &HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(missing.to_string().contains("'room'"));
+
+        let unknown_room = server
+            .render_prompt(EVENING_ROUTINE, &arguments(&[("room", "Garage")]))
+            .await;
+        assert!(matches!(unknown_room, Err(LoxoneError::NotFound(_))));
+
+        let unknown = server.render_prompt("make_coffee", &HashMap::new()).await;
+        assert!(matches!(unknown, Err(LoxoneError::NotFound(_))));
+
+        let audit = server
+            .render_prompt(ENERGY_AUDIT, &HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(audit.messages.len(), 2);
+    }
+}
@@ -0,0 +1,867 @@
+//! Resource handlers for the `loxone://` resource catalog
+//!
+//! Implements [`ResourceHandler`] for [`LoxoneMcpServer`] so that every URI
+//! registered by [`ResourceManager`] can be read via MCP `resources/read`.
+//! Content is built from the structure file and the same live-state lookups
+//! the tools use; where a read-only tool already returns the right shape the
+//! resource calls the typed helper behind that tool, so error kinds survive.
+
+use crate::client::binary_protocol::{LOXONE_EPOCH_UNIX, WeatherEvent};
+use crate::client::{ControlType, LoxoneControl};
+use crate::error::{LoxoneError, Result};
+use crate::server::macro_backend::LoxoneMcpServer;
+use crate::server::resources::{
+    ResourceContent, ResourceContext, ResourceHandler, ResourceManager, ResourceMetadata,
+};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Name keywords identifying temperature sensors among generic analog inputs
+const TEMPERATURE_KEYWORDS: &[&str] = &["temp", "temperatur"];
+
+/// Name keywords identifying air quality sensors
+const AIR_QUALITY_KEYWORDS: &[&str] = &[
+    "co2",
+    "voc",
+    "humidity",
+    "feuchte",
+    "pm2",
+    "pm10",
+    "air quality",
+    "luftqualität",
+];
+
+/// Name keywords identifying weather station sensors
+const WEATHER_STATION_KEYWORDS: &[&str] = &[
+    "wind",
+    "rain",
+    "regen",
+    "pressure",
+    "luftdruck",
+    "solar",
+    "brightness",
+    "helligkeit",
+    "lux",
+];
+
+/// Name keywords identifying outdoor sensors
+const OUTDOOR_KEYWORDS: &[&str] = &["outdoor", "outside", "außen", "aussen"];
+
+/// Control types exposed by `loxone://devices/category/{category}`
+fn category_control_types(category: &str) -> Option<&'static [&'static str]> {
+    let types: &'static [&'static str] = match category {
+        "lighting" => &[
+            "Switch",
+            "Dimmer",
+            "LightController",
+            "LightControllerV2",
+            "CentralLightController",
+            "ColorPicker",
+            "ColorPickerV2",
+        ],
+        "blinds" => &["Jalousie", "CentralJalousie", "Blinds", "Rolladen"],
+        "climate" => &[
+            "IRoomController",
+            "IRoomControllerV2",
+            "Intelligent Room Controller",
+            "ClimateController",
+        ],
+        "security" => &["Alarm", "SmokeAlarm", "Gate", "DoorLock", "AccessControl"],
+        "audio" => &[
+            "AudioZone",
+            "AudioZoneV2",
+            "CentralAudioZone",
+            "MediaController",
+        ],
+        "sensors" => &[
+            "InfoOnlyAnalog",
+            "InfoOnlyDigital",
+            "PresenceDetector",
+            "MotionSensor",
+            "SmokeAlarm",
+        ],
+        "energy" => &[
+            "Meter",
+            "EnergyManager",
+            "EnergyManager2",
+            "EnergyMonitor",
+            "Fronius",
+        ],
+        "irrigation" => &["Irrigation"],
+        "ventilation" => &["Ventilation"],
+        "access" => &["NfcCodeTouch", "Intercom", "IntercomV2"],
+        _ => return None,
+    };
+    Some(types)
+}
+
+/// Fetch a required path parameter extracted by [`ResourceManager::parse_uri`]
+fn path_param<'a>(context: &'a ResourceContext, name: &str) -> Result<&'a str> {
+    context
+        .params
+        .path_params
+        .get(name)
+        .map(String::as_str)
+        .ok_or_else(|| LoxoneError::invalid_input(format!("Missing URI parameter '{name}'")))
+}
+
+fn name_matches(control: &LoxoneControl, keywords: &[&str]) -> bool {
+    let name = control.name.to_lowercase();
+    keywords.iter().any(|keyword| name.contains(keyword))
+}
+
+/// Convert seconds since the Loxone epoch to an RFC 3339 timestamp
+fn loxone_timestamp(seconds: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(LOXONE_EPOCH_UNIX + seconds, 0).map(|t| t.to_rfc3339())
+}
+
+/// Collapse hourly weather entries into per-day minimum/maximum summaries
+fn daily_forecast(events: &[WeatherEvent]) -> Vec<Value> {
+    let mut days: BTreeMap<i64, Vec<&crate::client::binary_protocol::WeatherEntry>> =
+        BTreeMap::new();
+    for entry in events.iter().flat_map(|event| &event.entries) {
+        days.entry(i64::from(entry.timestamp).div_euclid(86_400))
+            .or_default()
+            .push(entry);
+    }
+
+    days.into_iter()
+        .map(|(day, entries)| {
+            let min = |f: fn(&crate::client::binary_protocol::WeatherEntry) -> f64| {
+                entries.iter().map(|e| f(e)).fold(f64::INFINITY, f64::min)
+            };
+            let max = |f: fn(&crate::client::binary_protocol::WeatherEntry) -> f64| {
+                entries.iter().map(|e| f(e)).fold(f64::NEG_INFINITY, f64::max)
+            };
+            json!({
+                "date": loxone_timestamp(day * 86_400)
+                    .and_then(|t| t.get(..10).map(str::to_string)),
+                "temperature_min": min(|e| e.temperature),
+                "temperature_max": max(|e| e.temperature),
+                "precipitation_total": entries.iter().map(|e| e.precipitation).sum::<f64>(),
+                "wind_speed_max": max(|e| e.wind_speed),
+                "relative_humidity_avg": entries.iter().map(|e| f64::from(e.relative_humidity)).sum::<f64>()
+                    / entries.len() as f64,
+                "hours": entries.len()
+            })
+        })
+        .collect()
+}
+
+impl LoxoneMcpServer {
+    /// Read a catalog resource by concrete URI and return its JSON payload
+    pub(crate) async fn catalog_resource(&self, uri: &str) -> Result<Value> {
+        let context = self.resource_manager().parse_uri(uri)?;
+        let content = ResourceHandler::read_resource(self, context).await?;
+        Ok(content.data)
+    }
+
+    /// [`Self::catalog_resource`] for the `#[mcp_resource]` methods, which
+    /// report errors as text like tools
+    pub(crate) async fn read_catalog_resource(
+        &self,
+        uri: &str,
+    ) -> std::result::Result<Value, String> {
+        Ok(self.catalog_resource(uri).await?)
+    }
+
+    async fn controls_resource<F>(&self, key: &str, filter: F) -> Result<Value>
+    where
+        F: Fn(&LoxoneControl) -> bool,
+    {
+        let model = self.control_model().await?;
+        let controls = model.filter(filter);
+        let described = self.describe_controls(&model, &controls, "state").await?;
+        Ok(json!({
+            key: described,
+            "count": described.len()
+        }))
+    }
+
+    async fn rooms_resource(&self) -> Result<Value> {
+        let model = self.control_model().await?;
+        let mut device_counts: HashMap<&str, usize> = HashMap::new();
+        for control in model.controls() {
+            *device_counts
+                .entry(control.room.as_deref().unwrap_or_default())
+                .or_default() += 1;
+        }
+
+        let mut rooms: Vec<Value> = model
+            .rooms()
+            .iter()
+            .map(|(uuid, name)| {
+                json!({
+                    "uuid": uuid,
+                    "name": name,
+                    "device_count": device_counts.get(uuid.as_str()).copied().unwrap_or(0)
+                })
+            })
+            .collect();
+        rooms.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+        Ok(json!({
+            "rooms": rooms,
+            "count": rooms.len()
+        }))
+    }
+
+    async fn room_devices_resource(&self, room: &str) -> Result<Value> {
+        let model = self.control_model().await?;
+        let room_uuid = self.resolve_room(&model, room)?;
+        let controls = model.filter(|control| control.room.as_deref() == Some(room_uuid));
+        let devices = self.describe_controls(&model, &controls, "state").await?;
+
+        Ok(json!({
+            "room": model.room_name(room_uuid),
+            "room_uuid": room_uuid,
+            "devices": devices,
+            "count": devices.len()
+        }))
+    }
+
+    async fn room_overview_resource(&self, room: &str) -> Result<Value> {
+        let mut overview = self.room_devices_resource(room).await?;
+        let mut by_type: BTreeMap<String, usize> = BTreeMap::new();
+        if let Some(devices) = overview["devices"].as_array() {
+            for device in devices {
+                *by_type
+                    .entry(device["type"].as_str().unwrap_or("Unknown").to_string())
+                    .or_default() += 1;
+            }
+        }
+        overview["device_types"] = json!(by_type);
+        Ok(overview)
+    }
+
+    async fn devices_by_category_resource(&self, category: &str) -> Result<Value> {
+        let types = category_control_types(category).ok_or_else(|| {
+            LoxoneError::not_found(format!("Unknown device category '{category}'"))
+        })?;
+        let mut data = self
+            .controls_resource("devices", |control| types.contains(&control.type_name()))
+            .await?;
+        data["category"] = json!(category);
+        Ok(data)
+    }
+
+    async fn devices_by_type_resource(&self, device_type: &str) -> Result<Value> {
+        let mut data = self
+            .controls_resource("devices", |control| {
+                control.type_name().eq_ignore_ascii_case(device_type)
+            })
+            .await?;
+        data["type"] = json!(device_type);
+        Ok(data)
+    }
+
+    async fn system_status_resource(&self) -> Result<Value> {
+        let mut status = self.server_status();
+        if let Some(client) = self.client() {
+            status["miniserver_reachable"] = json!(client.health_check().await.unwrap_or(false));
+            if let Ok(model) = self.control_model().await {
+                status["structure_last_modified"] = json!(model.last_modified());
+                status["room_count"] = json!(model.rooms().len());
+                status["device_count"] = json!(model.len());
+            }
+        }
+        Ok(status)
+    }
+
+    async fn system_categories_resource(&self) -> Result<Value> {
+        let model = self.control_model().await?;
+        let mut categories = serde_json::Map::new();
+        for category in [
+            "lighting",
+            "blinds",
+            "climate",
+            "security",
+            "audio",
+            "sensors",
+            "energy",
+            "irrigation",
+            "ventilation",
+            "access",
+        ] {
+            let types = category_control_types(category).unwrap_or_default();
+            let mut examples: Vec<&str> = model
+                .controls()
+                .filter(|control| types.contains(&control.type_name()))
+                .map(|control| control.name.as_str())
+                .collect();
+            let count = examples.len();
+            examples.sort_unstable();
+            examples.truncate(3);
+            categories.insert(
+                category.to_string(),
+                json!({
+                    "count": count,
+                    "control_types": types,
+                    "examples": examples
+                }),
+            );
+        }
+        Ok(json!({ "categories": categories }))
+    }
+
+    async fn system_capabilities_resource(&self) -> Result<Value> {
+        let model = self.control_model().await?;
+        let count = |category: &str| {
+            let types = category_control_types(category).unwrap_or_default();
+            model
+                .controls()
+                .filter(|control| types.contains(&control.type_name()))
+                .count()
+        };
+        let has_weather = model
+            .controls()
+            .any(|control| control.control_type.is_weather());
+
+        Ok(json!({
+            "has_lighting": count("lighting") > 0,
+            "has_blinds": count("blinds") > 0,
+            "has_climate": count("climate") > 0,
+            "has_security": count("security") > 0,
+            "has_audio": count("audio") > 0,
+            "has_sensors": count("sensors") > 0,
+            "has_energy": count("energy") > 0,
+            "has_weather": has_weather,
+            "light_count": count("lighting"),
+            "blind_count": count("blinds"),
+            "climate_count": count("climate"),
+            "sensor_count": count("sensors")
+        }))
+    }
+
+    async fn audio_sources_resource(&self) -> Result<Value> {
+        let model = self.control_model().await?;
+        let sources: Vec<Value> = model
+            .of_type(ControlType::is_audio)
+            .into_iter()
+            .map(|control| {
+                json!({
+                    "zone_uuid": control.uuid,
+                    "zone_name": control.name,
+                    "sources": control.detail("sources").cloned().unwrap_or_else(|| json!([]))
+                })
+            })
+            .collect();
+        Ok(json!({
+            "zones": sources,
+            "count": sources.len()
+        }))
+    }
+
+    async fn presence_resource(&self) -> Result<Value> {
+        let mut data = self
+            .controls_resource("presence_detectors", |control| {
+                control.control_type.is_presence()
+            })
+            .await?;
+
+        let mut occupancy: BTreeMap<String, bool> = BTreeMap::new();
+        if let Some(detectors) = data["presence_detectors"].as_array() {
+            for detector in detectors {
+                let room = detector["room_name"]
+                    .as_str()
+                    .unwrap_or("Unknown")
+                    .to_string();
+                let active = match &detector["state"] {
+                    Value::Bool(b) => *b,
+                    Value::Number(n) => n.as_f64().unwrap_or(0.0) > 0.0,
+                    Value::Object(states) => states
+                        .get("active")
+                        .and_then(|v| v.as_f64())
+                        .is_some_and(|v| v > 0.0),
+                    _ => false,
+                };
+                *occupancy.entry(room).or_default() |= active;
+            }
+        }
+        data["occupied_rooms"] = json!(
+            occupancy
+                .iter()
+                .filter(|(_, occupied)| **occupied)
+                .map(|(room, _)| room)
+                .collect::<Vec<_>>()
+        );
+        data["room_occupancy"] = json!(occupancy);
+        Ok(data)
+    }
+
+    async fn temperature_sensors_resource(&self) -> Result<Value> {
+        self.controls_resource("temperature_sensors", |control| {
+            control.control_type.is_room_controller()
+                || (control.control_type == ControlType::InfoOnlyAnalog
+                    && name_matches(control, TEMPERATURE_KEYWORDS))
+        })
+        .await
+    }
+
+    async fn outdoor_conditions_resource(&self) -> Result<Value> {
+        let mut data = self
+            .controls_resource("outdoor_sensors", |control| {
+                control.control_type.is_weather()
+                    || (control.control_type == ControlType::InfoOnlyAnalog
+                        && name_matches(control, OUTDOOR_KEYWORDS))
+            })
+            .await?;
+        data["forecast_now"] = self
+            .weather_events()
+            .await
+            .iter()
+            .flat_map(|event| &event.entries)
+            .min_by_key(|entry| entry.timestamp)
+            .map(|entry| json!(entry))
+            .unwrap_or(Value::Null);
+        Ok(data)
+    }
+
+    /// Weather forecasts pushed over the WebSocket weather event table
+    async fn weather_events(&self) -> Vec<WeatherEvent> {
+        match self.context() {
+            Some(context) => context.weather_events().await,
+            None => Vec::new(),
+        }
+    }
+
+    async fn forecast_resource(&self, hourly: bool) -> Result<Value> {
+        let events = self.weather_events().await;
+        if events.is_empty() {
+            return Ok(json!({
+                "forecast": [],
+                "message": "No weather forecast received yet; forecasts are pushed by the Loxone weather service over the WebSocket connection"
+            }));
+        }
+
+        let forecast: Vec<Value> = if hourly {
+            let mut entries: Vec<_> = events.iter().flat_map(|event| &event.entries).collect();
+            entries.sort_by_key(|entry| entry.timestamp);
+            entries
+                .into_iter()
+                .map(|entry| {
+                    let mut value = json!(entry);
+                    value["time"] = json!(loxone_timestamp(i64::from(entry.timestamp)));
+                    value
+                })
+                .collect()
+        } else {
+            daily_forecast(&events)
+        };
+
+        Ok(json!({
+            "last_update": events
+                .iter()
+                .map(|event| event.last_update)
+                .max()
+                .and_then(|t| loxone_timestamp(i64::from(t))),
+            "count": forecast.len(),
+            "forecast": forecast
+        }))
+    }
+
+    async fn security_zones_resource(&self) -> Result<Value> {
+        let mut data = self
+            .controls_resource("zones", |control| {
+                matches!(
+                    control.control_type,
+                    ControlType::Alarm | ControlType::SmokeAlarm
+                )
+            })
+            .await?;
+        data["armed_zones"] = json!(
+            data["zones"]
+                .as_array()
+                .map(|zones| {
+                    zones
+                        .iter()
+                        .filter(|zone| {
+                            zone["state"].get("armed").and_then(|v| v.as_f64()) == Some(1.0)
+                        })
+                        .count()
+                })
+                .unwrap_or(0)
+        );
+        Ok(data)
+    }
+
+    /// Daily totals of the last 30 days from the Miniserver statistics of
+    /// every energy control that records them
+    async fn energy_usage_history_resource(&self) -> Result<Value> {
+        let model = self.control_model().await?;
+        let mut history = Vec::new();
+        for (control, _) in Self::statistic_controls(&model, None)
+            .into_iter()
+            .filter(|(control, _)| control.control_type.is_energy())
+        {
+            let statistics = self
+                .get_statistics(
+                    control.uuid.clone(),
+                    None,
+                    Some("30d".to_string()),
+                    None,
+                    Some("1d".to_string()),
+                )
+                .await;
+            history.push(statistics.unwrap_or_else(|error| {
+                json!({
+                    "uuid": control.uuid,
+                    "name": control.name,
+                    "error": error
+                })
+            }));
+        }
+
+        let mut data = json!({
+            "history": history,
+            "count": history.len()
+        });
+        if history.is_empty() {
+            data["message"] = json!(
+                "No energy meter records statistics; use list_statistics and get_statistics for other controls"
+            );
+        }
+        Ok(data)
+    }
+
+    async fn room_climate_resource(&self, room: &str) -> Result<Value> {
+        let model = self.control_model().await?;
+        let controllers = self.find_controls_in_room(&model, room, |control_type| {
+            control_type.is_room_controller() || *control_type == ControlType::ClimateController
+        })?;
+        if controllers.is_empty() {
+            return Err(LoxoneError::not_found(format!(
+                "No climate controllers found for room '{room}'"
+            )));
+        }
+        let controllers = self
+            .describe_controls(&model, &controllers, "state")
+            .await?;
+        Ok(json!({
+            "room": room,
+            "controllers": controllers,
+            "count": controllers.len()
+        }))
+    }
+}
+
+impl ResourceHandler for LoxoneMcpServer {
+    async fn read_resource(&self, context: ResourceContext) -> Result<ResourceContent> {
+        let path = context
+            .uri
+            .strip_prefix("loxone://")
+            .unwrap_or(&context.uri);
+        let path = path.split('?').next().unwrap_or(path);
+        let segments: Vec<&str> = path.split('/').collect();
+
+        let data = match segments.as_slice() {
+            ["rooms"] => self.rooms_resource().await?,
+            ["rooms", _, "devices"] => {
+                self.room_devices_resource(path_param(&context, "roomName")?)
+                    .await?
+            }
+            ["rooms", _, "overview"] => {
+                self.room_overview_resource(path_param(&context, "roomName")?)
+                    .await?
+            }
+            ["devices", "all"] => self.device_inventory(None).await?,
+            ["devices", "type", _] => {
+                self.devices_by_type_resource(path_param(&context, "deviceType")?)
+                    .await?
+            }
+            ["devices", "category", _] => {
+                self.devices_by_category_resource(path_param(&context, "category")?)
+                    .await?
+            }
+            ["system", "status"] => self.system_status_resource().await?,
+            ["system", "capabilities"] => self.system_capabilities_resource().await?,
+            ["system", "categories"] => self.system_categories_resource().await?,
+            ["audio", "zones"] => self.audio_status(None).await?,
+            ["audio", "sources"] => self.audio_sources_resource().await?,
+            ["sensors", "door-window"] => {
+                self.device_listing("door_window_sensors", "state", Self::is_door_window)
+                    .await?
+            }
+            ["sensors", "temperature"] | ["climate", "sensors"] => {
+                self.temperature_sensors_resource().await?
+            }
+            ["sensors", "discovered"] => {
+                self.device_listing("sensors", "value", |control| {
+                    control.control_type.is_sensor()
+                })
+                .await?
+            }
+            ["sensors", "motion"] => {
+                self.device_listing("motion_sensors", "state", |control| {
+                    control.control_type.is_presence()
+                })
+                .await?
+            }
+            ["sensors", "air-quality"] => {
+                self.controls_resource("air_quality_sensors", |control| {
+                    control.control_type == ControlType::InfoOnlyAnalog
+                        && name_matches(control, AIR_QUALITY_KEYWORDS)
+                })
+                .await?
+            }
+            ["sensors", "presence"] => self.presence_resource().await?,
+            ["sensors", "weather-station"] => {
+                self.controls_resource("weather_station_sensors", |control| {
+                    control.control_type.is_weather()
+                        || (control.control_type == ControlType::InfoOnlyAnalog
+                            && name_matches(control, WEATHER_STATION_KEYWORDS))
+                })
+                .await?
+            }
+            ["weather", "current"] => {
+                self.device_listing("weather_devices", "state", |control| {
+                    control.control_type.is_weather()
+                })
+                .await?
+            }
+            ["weather", "outdoor-conditions"] => self.outdoor_conditions_resource().await?,
+            ["weather", "forecast-daily"] => self.forecast_resource(false).await?,
+            ["weather", "forecast-hourly"] => self.forecast_resource(true).await?,
+            ["security", "status"] => {
+                self.device_listing("security_devices", "state", |control| {
+                    control.control_type.is_security()
+                })
+                .await?
+            }
+            ["security", "zones"] => self.security_zones_resource().await?,
+            ["energy", "consumption"] => {
+                self.device_listing("energy_devices", "state", |control| {
+                    control.control_type.is_energy()
+                })
+                .await?
+            }
+            ["energy", "meters"] => {
+                self.controls_resource("meters", |control| {
+                    control.control_type == ControlType::Meter
+                })
+                .await?
+            }
+            ["energy", "usage-history"] => self.energy_usage_history_resource().await?,
+            ["climate", "overview"] => self.climate_status(None).await?,
+            ["climate", "rooms", _] => {
+                self.room_climate_resource(path_param(&context, "roomName")?)
+                    .await?
+            }
+            _ => {
+                return Err(LoxoneError::not_found(format!(
+                    "No handler for resource '{}'",
+                    context.uri
+                )));
+            }
+        };
+
+        let body = data.to_string();
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        body.hash(&mut hasher);
+
+        Ok(ResourceContent {
+            metadata: ResourceMetadata {
+                content_type: "application/json".to_string(),
+                last_modified: context.timestamp,
+                etag: format!("\"{:016x}\"", hasher.finish()),
+                cache_ttl: ResourceManager::get_resource_cache_ttl(&context.uri),
+                size: body.len(),
+            },
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ClientContext, LoxoneClient, LoxoneStructure};
+    use crate::config::ServerConfig;
+    use crate::mock::MockLoxoneClient;
+    use crate::services::{SensorTypeRegistry, UnifiedValueResolver};
+    use pulseengine_mcp_protocol::ReadResourceRequestParam;
+    use pulseengine_mcp_server::McpResourcesProvider;
+    use std::sync::Arc;
+
+    fn test_structure() -> LoxoneStructure {
+        serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {
+                "room-1": { "name": "Living Room", "type": 1 },
+                "room-2": { "name": "Kitchen", "type": 2 }
+            },
+            "controls": {
+                "light-1": { "name": "Ceiling Light", "type": "Dimmer", "room": "room-1" },
+                "blind-1": { "name": "Window Blind", "type": "Jalousie", "room": "room-1" },
+                "temp-1": { "name": "Kitchen Temperature", "type": "InfoOnlyAnalog", "room": "room-2" },
+                "climate-1": { "name": "Kitchen Climate", "type": "IRoomControllerV2", "room": "room-2" }
+            },
+            "cats": {}
+        }))
+        .unwrap()
+    }
+
+    fn test_server() -> LoxoneMcpServer {
+        let client: Arc<dyn LoxoneClient> =
+            Arc::new(MockLoxoneClient::new().with_structure(test_structure()));
+        let value_resolver = Arc::new(UnifiedValueResolver::new(
+            client.clone(),
+            Arc::new(SensorTypeRegistry::new()),
+        ));
+        LoxoneMcpServer::with_context(
+            client,
+            Arc::new(ClientContext::new()),
+            value_resolver,
+            None,
+            ServerConfig::default(),
+        )
+    }
+
+    async fn read_json(server: &LoxoneMcpServer, uri: &str) -> Value {
+        let result = server
+            .read_resource_impl(ReadResourceRequestParam {
+                uri: uri.to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.contents[0].uri, uri);
+        serde_json::from_str(result.contents[0].text.as_deref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_catalog_is_exposed() {
+        let server = LoxoneMcpServer::default();
+        let exposed: Vec<String> = server
+            .get_available_resources()
+            .into_iter()
+            .map(|r| r.uri)
+            .collect();
+
+        let manager = ResourceManager::new();
+        for resource in manager.list_resources() {
+            assert!(
+                exposed.contains(&resource.uri),
+                "{} is not exposed",
+                resource.uri
+            );
+        }
+        assert_eq!(exposed.len(), manager.list_resources().len());
+    }
+
+    #[tokio::test]
+    async fn test_read_rooms() {
+        let data = read_json(&test_server(), "loxone://rooms").await;
+        assert_eq!(data["count"], 2);
+        assert_eq!(data["rooms"][0]["name"], "Kitchen");
+        assert_eq!(data["rooms"][1]["device_count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_templated_room_devices() {
+        let data = read_json(&test_server(), "loxone://rooms/Living%20Room/devices").await;
+        assert_eq!(data["room"], "Living Room");
+        assert_eq!(data["count"], 2);
+        assert_eq!(data["devices"][0]["name"], "Ceiling Light");
+        assert_eq!(data["devices"][0]["room_name"], "Living Room");
+    }
+
+    #[tokio::test]
+    async fn test_read_devices_by_category_and_climate() {
+        let server = test_server();
+
+        let lighting = read_json(&server, "loxone://devices/category/lighting").await;
+        assert_eq!(lighting["count"], 1);
+        assert_eq!(lighting["devices"][0]["uuid"], "light-1");
+
+        let climate = read_json(&server, "loxone://climate/rooms/Kitchen").await;
+        assert_eq!(climate["controllers"][0]["uuid"], "climate-1");
+
+        let sensors = read_json(&server, "loxone://sensors/temperature").await;
+        assert_eq!(sensors["count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_control_model_is_cached_in_context() {
+        let server = test_server();
+        assert!(server.context().unwrap().control_model().await.is_none());
+
+        let climate = server.get_climate_status(None).await.unwrap();
+        assert_eq!(climate["count"], 1);
+        assert_eq!(climate["climate_controllers"][0]["room_name"], "Kitchen");
+
+        let model = server.context().unwrap().control_model().await.unwrap();
+        assert_eq!(model.len(), 4);
+        assert_eq!(
+            model.get("climate-1").unwrap().control_type,
+            ControlType::IRoomControllerV2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resource_errors_keep_their_kind() {
+        let server = test_server();
+
+        let unknown_room = server
+            .catalog_resource("loxone://rooms/Attic/devices")
+            .await
+            .unwrap_err();
+        assert!(matches!(unknown_room, LoxoneError::NotFound(_)));
+
+        let no_controller = server
+            .catalog_resource("loxone://climate/rooms/Attic")
+            .await
+            .unwrap_err();
+        assert!(matches!(no_controller, LoxoneError::NotFound(_)));
+
+        let offline = LoxoneMcpServer::default()
+            .catalog_resource("loxone://audio/zones")
+            .await
+            .unwrap_err();
+        assert!(matches!(offline, LoxoneError::Connection(_)));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_room_is_an_error() {
+        let result = test_server()
+            .read_resource_impl(ReadResourceRequestParam {
+                uri: "loxone://rooms/Attic/devices".to_string(),
+            })
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forecast_from_pushed_weather_table() {
+        let server = test_server();
+        let entry =
+            |timestamp: i32, temperature: f64| crate::client::binary_protocol::WeatherEntry {
+                timestamp,
+                weather_type: 1,
+                wind_direction: 0,
+                solar_radiation: 0,
+                relative_humidity: 50,
+                temperature,
+                perceived_temperature: temperature,
+                dew_point: 0.0,
+                precipitation: 0.5,
+                wind_speed: 10.0,
+                barometric_pressure: 1013.0,
+            };
+        let event = WeatherEvent {
+            uuid: "weather-1".to_string(),
+            last_update: 86_400,
+            entries: vec![entry(90_000, 5.0), entry(93_600, 8.0), entry(180_000, 3.0)],
+        };
+        server.context().unwrap().record_weather_event(&event).await;
+
+        let daily = read_json(&server, "loxone://weather/forecast-daily").await;
+        assert_eq!(daily["count"], 2);
+        assert_eq!(daily["forecast"][0]["date"], "2009-01-02");
+        assert_eq!(daily["forecast"][0]["temperature_max"], 8.0);
+        assert_eq!(daily["forecast"][0]["precipitation_total"], 1.0);
+
+        let hourly = read_json(&server, "loxone://weather/forecast-hourly").await;
+        assert_eq!(hourly["count"], 3);
+    }
+}
@@ -8,7 +8,10 @@
 //!
 //! Available resource URIs:
 //! - `loxone://rooms` - All rooms list
+//! - `loxone://rooms/{roomName}/devices` - All devices in a room
+//! - `loxone://rooms/{roomName}/overview` - Room overview with device statistics
 //! - `loxone://devices/all` - All devices
+//! - `loxone://devices/type/{deviceType}` - All devices of a Loxone control type
 //! - `loxone://devices/category/blinds` - All blinds/rolladen with current positions
 //! - `loxone://devices/category/lighting` - All lighting devices with states
 //! - `loxone://devices/category/climate` - All climate devices and sensors
@@ -33,8 +36,12 @@
 //! - `loxone://energy/consumption` - Energy consumption data
 //! - `loxone://energy/meters` - Energy meters
 //! - `loxone://energy/usage-history` - Historical energy usage
+//! - `loxone://climate/overview` - Climate system overview
+//! - `loxone://climate/rooms/{roomName}` - Climate data for a room
+//! - `loxone://climate/sensors` - Temperature sensor readings
 //!
-//! Note: For room-specific or device-type-specific queries, use the appropriate tools instead.
+//! Templated URIs are read with concrete, percent-encoded values, e.g.
+//! `loxone://rooms/Living%20Room/devices`.
 
 use crate::error::{LoxoneError, Result};
 use serde::{Deserialize, Serialize};
@@ -493,7 +500,9 @@ impl ResourceManager {
             LoxoneResource {
                 uri: "loxone://energy/usage-history".to_string(),
                 name: "Energy Usage History".to_string(),
-                description: "Historical energy usage data and trends".to_string(),
+                description:
+                    "Daily energy meter totals of the last 30 days from Miniserver statistics"
+                        .to_string(),
                 mime_type: Some("application/json".to_string()),
             },
             ResourceCategory::Energy,
@@ -502,20 +511,10 @@ impl ResourceManager {
         // Additional resources for tools that were converted from read-only tools
 
         // Room-specific resources
-        // Note: These use templated URIs - handler must parse {room} parameter
-        self.register_resource(
-            LoxoneResource {
-                uri: "loxone://rooms/{room}/devices".to_string(),
-                name: "Room Devices".to_string(),
-                description: "All devices in a specific room".to_string(),
-                mime_type: Some("application/json".to_string()),
-            },
-            ResourceCategory::Rooms,
-        );
-
+        // Note: These use templated URIs - handler must parse {roomName} parameter
         self.register_resource(
             LoxoneResource {
-                uri: "loxone://rooms/{room}/overview".to_string(),
+                uri: "loxone://rooms/{roomName}/overview".to_string(),
                 name: "Room Overview".to_string(),
                 description: "Complete overview of a room including devices and statistics"
                     .to_string(),
@@ -537,7 +536,7 @@ impl ResourceManager {
 
         self.register_resource(
             LoxoneResource {
-                uri: "loxone://climate/rooms/{room}".to_string(),
+                uri: "loxone://climate/rooms/{roomName}".to_string(),
                 name: "Room Climate".to_string(),
                 description: "Climate data for a specific room".to_string(),
                 mime_type: Some("application/json".to_string()),
@@ -721,6 +720,7 @@ impl ResourceManager {
             let category = path_parts[0];
             let valid_categories = [
                 "rooms", "devices", "system", "audio", "sensors", "weather", "security", "energy",
+                "climate",
             ];
             if !valid_categories.contains(&category) {
                 return Err(LoxoneError::invalid_input(format!(
@@ -1037,7 +1037,10 @@ impl ResourceManager {
             if template_part.starts_with('{') && template_part.ends_with('}') {
                 // Extract parameter name
                 let param_name = &template_part[1..template_part.len() - 1];
-                params.insert(param_name.to_string(), actual_part.to_string());
+                let value = urlencoding::decode(actual_part)
+                    .map(|decoded| decoded.into_owned())
+                    .unwrap_or_else(|_| actual_part.to_string());
+                params.insert(param_name.to_string(), value);
                 has_params = true;
             } else if template_part != actual_part {
                 // Parts don't match and it's not a parameter
@@ -0,0 +1,487 @@
+//! Stdio transport that can ask the client
+//!
+//! The framework's stdio transport handles each line before reading the next,
+//! so a tool waiting for the answer to an `elicitation/create` or
+//! `sampling/createMessage` request never receives it and stdio tools run
+//! without a way to reach the client. [`StdioTransport`] keeps reading stdin
+//! while requests are handled: requests go to a worker in arrival order and
+//! responses complete the server request waiting for them. Responses, server
+//! requests and notifications share one locked stdout, a line per write.
+
+use async_trait::async_trait;
+use pulseengine_mcp_server::transport::{RequestHandler, TransportError};
+use pulseengine_mcp_server::{Transport, protocol::Error as ProtocolError};
+use pulseengine_mcp_transport::batch::{JsonRpcMessage, create_error_response, process_batch};
+use pulseengine_mcp_transport::validation::extract_id_from_malformed;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error, info, warn};
+
+/// Stdout shared by responses, server requests and notifications, one line per write
+type LineWriter = Arc<tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+
+/// Senders of server requests waiting for the client, by request id
+type PendingRequests = Arc<Mutex<HashMap<String, oneshot::Sender<Value>>>>;
+
+/// Newline-delimited JSON-RPC over stdin/stdout with server-initiated requests
+///
+/// Clones share the same stdout, pending requests and running state, so a
+/// clone can be handed to the server handler while the original is started.
+#[derive(Clone)]
+pub struct StdioTransport {
+    writer: LineWriter,
+    pending: PendingRequests,
+    running: Arc<AtomicBool>,
+}
+
+impl StdioTransport {
+    /// Transport on the process stdin/stdout
+    pub fn new() -> Self {
+        Self::with_writer(Box::new(tokio::io::stdout()))
+    }
+
+    /// Transport writing to an arbitrary writer
+    pub fn with_writer(writer: Box<dyn AsyncWrite + Send + Unpin>) -> Self {
+        Self {
+            writer: Arc::new(tokio::sync::Mutex::new(writer)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Write one message as a line
+    async fn write_line(&self, message: &impl Serialize) -> Result<(), TransportError> {
+        let mut line = serde_json::to_vec(message)
+            .map_err(|e| TransportError::Protocol(format!("Failed to serialize message: {e}")))?;
+        line.push(b'\n');
+
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(&line)
+            .await
+            .map_err(|e| TransportError::Connection(format!("Failed to write to stdout: {e}")))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| TransportError::Connection(format!("Failed to flush stdout: {e}")))
+    }
+
+    /// Read messages until EOF, handling requests in order
+    async fn serve<R>(&self, reader: R, handler: RequestHandler) -> Result<(), TransportError>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        let (queue, mut requests) = mpsc::unbounded_channel::<String>();
+        let worker = {
+            let transport = self.clone();
+            tokio::spawn(async move {
+                while let Some(line) = requests.recv().await {
+                    transport.handle_line(&line, &handler).await;
+                }
+            })
+        };
+
+        let mut lines = reader.lines();
+        let read = loop {
+            if !self.running.load(Ordering::Relaxed) {
+                break Ok(());
+            }
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    let line = line.trim();
+                    if line.is_empty() || self.complete_request(line) {
+                        continue;
+                    }
+                    // The worker only stops once the queue is dropped
+                    let _ = queue.send(line.to_string());
+                }
+                Ok(None) => {
+                    debug!("EOF reached, stopping stdio transport");
+                    break Ok(());
+                }
+                Err(e) => {
+                    break Err(TransportError::Connection(format!("Stdin read error: {e}")));
+                }
+            }
+        };
+
+        // Nobody is left to answer; fail the requests still waiting
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+        drop(queue);
+        if let Err(e) = worker.await {
+            warn!("Stdio request worker failed: {}", e);
+        }
+        read
+    }
+
+    /// Route a client response to the server request waiting for it
+    ///
+    /// Returns `false` for requests and notifications, which the handler takes.
+    fn complete_request(&self, line: &str) -> bool {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            return false;
+        };
+        if message.get("method").is_some()
+            || (message.get("result").is_none() && message.get("error").is_none())
+        {
+            return false;
+        }
+
+        let id = match message.get("id") {
+            Some(Value::String(id)) => id.clone(),
+            Some(Value::Number(id)) => id.to_string(),
+            _ => return false,
+        };
+        let result = match message.get("error") {
+            Some(error) => json!({ "error": error }),
+            None => message.get("result").cloned().unwrap_or(Value::Null),
+        };
+
+        let sender = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&id);
+        match sender {
+            Some(sender) => {
+                let _ = sender.send(result);
+                debug!("Routed response for request {}", id);
+            }
+            None => warn!("Received response for unknown request {}", id),
+        }
+        true
+    }
+
+    /// Handle a request, notification or batch and write the response
+    async fn handle_line(&self, line: &str, handler: &RequestHandler) {
+        debug!("Processing message: {}", line);
+
+        let response = match JsonRpcMessage::parse(line) {
+            Ok(message) => match process_batch(message, handler).await {
+                Ok(Some(response)) => self.write_line(&response_value(response)).await,
+                Ok(None) => Ok(()),
+                Err(e) => {
+                    let error = ProtocolError::invalid_request(format!("Invalid JSON-RPC: {e}"));
+                    self.write_line(&create_error_response(
+                        error,
+                        extract_id_from_malformed(line),
+                    ))
+                    .await
+                }
+            },
+            Err(e) => {
+                let error = ProtocolError::parse_error(format!("Invalid JSON: {e}"));
+                self.write_line(&create_error_response(
+                    error,
+                    extract_id_from_malformed(line),
+                ))
+                .await
+            }
+        };
+        if let Err(e) = response {
+            error!("Failed to send response: {}", e);
+        }
+    }
+}
+
+impl Default for StdioTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// JSON value of a single or batch response
+fn response_value(message: JsonRpcMessage) -> Value {
+    match message {
+        JsonRpcMessage::Single(value) => value,
+        JsonRpcMessage::Batch(values) => Value::Array(values),
+    }
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn start(&mut self, handler: RequestHandler) -> Result<(), TransportError> {
+        info!("Starting stdio transport");
+        self.running.store(true, Ordering::Relaxed);
+        let served = self
+            .serve(BufReader::new(tokio::io::stdin()), handler)
+            .await;
+        info!("Stdio transport stopped");
+        served
+    }
+
+    async fn stop(&mut self) -> Result<(), TransportError> {
+        self.running.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<(), TransportError> {
+        if self.running.load(Ordering::Relaxed) {
+            Ok(())
+        } else {
+            Err(TransportError::Connection(
+                "Transport not running".to_string(),
+            ))
+        }
+    }
+
+    fn supports_bidirectional(&self) -> bool {
+        true
+    }
+
+    async fn send_notification(
+        &self,
+        _session_id: Option<&str>,
+        method: &str,
+        params: Value,
+    ) -> Result<(), TransportError> {
+        self.write_line(&json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }))
+        .await
+    }
+
+    async fn send_request(
+        &self,
+        _session_id: Option<&str>,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, TransportError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let Some(response) = self.register_pending_request(&id) else {
+            return Err(TransportError::ChannelClosed);
+        };
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        if let Err(e) = self.write_line(&request).await {
+            self.pending
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(timeout, response).await {
+            Ok(Ok(result)) => match result.get("error") {
+                Some(error) => Err(TransportError::Protocol(format!(
+                    "Client rejected {method}: {error}"
+                ))),
+                None => Ok(result),
+            },
+            Ok(Err(_)) => Err(TransportError::ChannelClosed),
+            Err(_) => {
+                self.pending
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .remove(&id);
+                Err(TransportError::Timeout)
+            }
+        }
+    }
+
+    fn register_pending_request(&self, request_id: &str) -> Option<oneshot::Receiver<Value>> {
+        let (sender, receiver) = oneshot::channel();
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(request_id.to_string(), sender);
+        Some(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulseengine_mcp_server::protocol::{Request, Response};
+
+    /// Writer whose output the test can read back
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl AsyncWrite for Captured {
+        fn poll_write(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            std::task::Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    impl Captured {
+        fn lines(&self) -> Vec<Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    /// Handler that asks the client before answering `tools/call`
+    fn asking_handler(transport: StdioTransport) -> RequestHandler {
+        Box::new(move |request: Request| {
+            let transport = transport.clone();
+            Box::pin(async move {
+                let answer = transport
+                    .send_request(
+                        None,
+                        "elicitation/create",
+                        json!({ "message": "Unlock?" }),
+                        Duration::from_secs(5),
+                    )
+                    .await
+                    .unwrap();
+                Response {
+                    jsonrpc: "2.0".to_string(),
+                    id: request.id,
+                    result: Some(answer),
+                    error: None,
+                }
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn test_client_answers_reach_the_waiting_request() {
+        let output = Captured::default();
+        let transport = StdioTransport::with_writer(Box::new(output.clone()));
+        transport.running.store(true, Ordering::Relaxed);
+
+        let (mut client, server) = tokio::io::duplex(4096);
+        let serving = tokio::spawn({
+            let transport = transport.clone();
+            let handler = asking_handler(transport.clone());
+            async move { transport.serve(BufReader::new(server), handler).await }
+        });
+
+        client
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"tools/call\",\"params\":{}}\n")
+            .await
+            .unwrap();
+
+        // The tool's question goes out while the call is still running
+        let question = loop {
+            if let Some(line) = output.lines().into_iter().next() {
+                break line;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(question["method"], "elicitation/create");
+        let answer = json!({
+            "jsonrpc": "2.0",
+            "id": question["id"],
+            "result": { "action": "accept", "content": { "approve": true } }
+        });
+        client
+            .write_all(format!("{answer}\n").as_bytes())
+            .await
+            .unwrap();
+        drop(client);
+
+        serving.await.unwrap().unwrap();
+        let lines = output.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["id"], 1);
+        assert_eq!(lines[1]["result"]["action"], "accept");
+    }
+
+    #[tokio::test]
+    async fn test_notifications_share_the_response_writer() {
+        let output = Captured::default();
+        let transport = StdioTransport::with_writer(Box::new(output.clone()));
+
+        let sending = (0..20).map(|n| {
+            let transport = transport.clone();
+            async move {
+                transport
+                    .send_notification(
+                        None,
+                        "notifications/resources/updated",
+                        json!({ "uri": format!("loxone://rooms/{n}/devices") }),
+                    )
+                    .await
+            }
+        });
+        for sent in futures::future::join_all(sending).await {
+            sent.unwrap();
+        }
+
+        // Every frame is a whole line, even when written concurrently
+        let lines = output.lines();
+        assert_eq!(lines.len(), 20);
+        assert!(lines.iter().all(|line| {
+            line["jsonrpc"] == "2.0"
+                && line["method"] == "notifications/resources/updated"
+                && line.get("id").is_none()
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_fail_the_request() {
+        let transport = StdioTransport::with_writer(Box::new(Captured::default()));
+        let waiting = tokio::spawn({
+            let transport = transport.clone();
+            async move {
+                transport
+                    .send_request(
+                        None,
+                        "sampling/createMessage",
+                        json!({}),
+                        Duration::from_secs(5),
+                    )
+                    .await
+            }
+        });
+
+        let id = loop {
+            let pending = transport.pending.lock().unwrap().keys().next().cloned();
+            if let Some(id) = pending {
+                break id;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        let error = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": "Method not found" }
+        });
+        assert!(transport.complete_request(&error.to_string()));
+
+        assert!(matches!(
+            waiting.await.unwrap(),
+            Err(TransportError::Protocol(_))
+        ));
+    }
+}
@@ -1,20 +1,100 @@
 //! Resource Change Detector
 //!
-//! Monitors Loxone WebSocket streams for state changes and maps them to MCP resource URIs.
-//! Detects device state changes, sensor updates, and system events to trigger notifications.
+//! Watches subscribed MCP resources for changes. Subscribed resources are re-read
+//! and compared against their last snapshot whenever the Loxone WebSocket pushes a
+//! state change; without pushes (HTTP-only clients, dropped WebSocket) they are
+//! polled on a fixed interval instead.
+//!
+//! A push only re-reads the resources whose last snapshot mentions the pushed
+//! state or its control. Resources that mention no UUIDs at all are re-read on
+//! every push, since nothing tells which states they are built from.
 
+use super::manager::ResourceSubscriptionManager;
 use super::types::{ChangeDetectorStats, ResourceChange, ResourceChangeType, SubscriptionEvent};
 use crate::client::LoxoneClient;
+use crate::client::binary_protocol::parse_uuid;
 use crate::error::{LoxoneError, Result};
+use async_trait::async_trait;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{RwLock, broadcast};
-use tokio::time::{interval, timeout};
+use tokio::time::{MissedTickBehavior, interval, timeout};
 use tracing::{debug, info, warn};
 
-/// Detects resource changes from Loxone WebSocket events
+/// Default interval for polling subscribed resources when no pushes arrive
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Timeout for reading a single resource snapshot
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Produces the current content of a resource for change comparison
+#[async_trait]
+pub trait ResourceSnapshotSource: Send + Sync {
+    /// Read the current content of a resource URI
+    async fn snapshot(&self, resource_uri: &str) -> Result<Value>;
+
+    /// UUIDs a resource may mention when the given state changes
+    ///
+    /// Defaults to the state UUID itself; sources that know the structure add
+    /// the controls owning the state.
+    async fn affected_uuids(&self, state_uuid: &str) -> Vec<String> {
+        vec![state_uuid.to_string()]
+    }
+}
+
+/// Shared source slot, set once the server is wired up
+type SharedSource = Arc<RwLock<Option<Arc<dyn ResourceSnapshotSource>>>>;
+
+/// Loxone UUIDs mentioned by a resource snapshot, per resource URI
+type ResourceUuids = Arc<RwLock<HashMap<String, HashSet<String>>>>;
+
+/// Collect every Loxone UUID appearing as a key or string value
+fn mentioned_uuids(value: &Value, uuids: &mut HashSet<String>) {
+    match value {
+        Value::String(text) if parse_uuid(text).is_ok() => {
+            uuids.insert(text.clone());
+        }
+        Value::Array(items) => items.iter().for_each(|item| mentioned_uuids(item, uuids)),
+        Value::Object(entries) => {
+            for (key, item) in entries {
+                if parse_uuid(key).is_ok() {
+                    uuids.insert(key.clone());
+                }
+                mentioned_uuids(item, uuids);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Classify a resource URI into the change type reported to clients
+fn change_type_for_uri(resource_uri: &str) -> ResourceChangeType {
+    let path = resource_uri.trim_start_matches("loxone://");
+    match path.split('/').next().unwrap_or_default() {
+        "sensors" | "climate" => ResourceChangeType::SensorValue,
+        "weather" => ResourceChangeType::Weather,
+        "security" => ResourceChangeType::Security,
+        "energy" => ResourceChangeType::Energy,
+        "audio" => ResourceChangeType::AudioZone,
+        "system" => ResourceChangeType::SystemStatus,
+        "rooms" if path == "rooms" => ResourceChangeType::RoomConfig,
+        _ => ResourceChangeType::DeviceState,
+    }
+}
+
+/// Wait for the next pushed state change, or forever when there is no push feed
+async fn next_push(
+    state_changes: &mut Option<broadcast::Receiver<String>>,
+) -> std::result::Result<String, broadcast::error::RecvError> {
+    match state_changes {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Detects changes to subscribed resources
 pub struct ResourceChangeDetector {
     /// Loxone client for WebSocket monitoring
     #[allow(dead_code)]
@@ -26,9 +106,21 @@ pub struct ResourceChangeDetector {
     /// Cache mapping Loxone UUIDs to resource URIs
     uuid_to_resource_cache: Arc<RwLock<HashMap<String, String>>>,
 
-    /// Last known values for change detection
+    /// Last known content per resource URI
     last_values: Arc<RwLock<HashMap<String, Value>>>,
 
+    /// UUIDs mentioned by the last known content per resource URI
+    resource_uuids: ResourceUuids,
+
+    /// Reads resource content for comparison
+    source: SharedSource,
+
+    /// Pushed state changes from the Loxone WebSocket, taken by the monitor task
+    state_changes: Arc<RwLock<Option<broadcast::Receiver<String>>>>,
+
+    /// Polling interval used while no state changes are pushed
+    poll_interval: Duration,
+
     /// Statistics for monitoring
     stats: Arc<RwLock<ChangeDetectorStats>>,
 
@@ -45,6 +137,14 @@ pub struct ResourceChangeDetector {
 impl ResourceChangeDetector {
     /// Create a new change detector
     pub async fn new(event_sender: broadcast::Sender<SubscriptionEvent>) -> Result<Self> {
+        Self::with_poll_interval(event_sender, DEFAULT_POLL_INTERVAL).await
+    }
+
+    /// Create a new change detector with a custom polling interval
+    pub async fn with_poll_interval(
+        event_sender: broadcast::Sender<SubscriptionEvent>,
+        poll_interval: Duration,
+    ) -> Result<Self> {
         debug!("🔍 Initializing resource change detector");
 
         Ok(Self {
@@ -52,6 +152,10 @@ impl ResourceChangeDetector {
             event_sender,
             uuid_to_resource_cache: Arc::new(RwLock::new(HashMap::new())),
             last_values: Arc::new(RwLock::new(HashMap::new())),
+            resource_uuids: Arc::new(RwLock::new(HashMap::new())),
+            source: Arc::new(RwLock::new(None)),
+            state_changes: Arc::new(RwLock::new(None)),
+            poll_interval,
             stats: Arc::new(RwLock::new(ChangeDetectorStats::default())),
             should_stop: Arc::new(RwLock::new(false)),
             debounce_duration: Duration::from_millis(500), // 500ms debounce
@@ -73,8 +177,69 @@ impl ResourceChangeDetector {
         Ok(())
     }
 
-    /// Start monitoring for changes
-    pub async fn start_monitoring(&self) -> Result<()> {
+    /// Attach the resource source and, when available, the WebSocket push feed
+    pub async fn set_source(
+        &self,
+        source: Arc<dyn ResourceSnapshotSource>,
+        state_changes: Option<broadcast::Receiver<String>>,
+    ) {
+        info!(
+            "🔌 Change detection source attached ({})",
+            if state_changes.is_some() {
+                "push with polling fallback"
+            } else {
+                "polling"
+            }
+        );
+        *self.source.write().await = Some(source);
+        *self.state_changes.write().await = state_changes;
+    }
+
+    /// Record the current content of a newly subscribed resource as baseline
+    pub async fn track_resource(&self, resource_uri: &str) {
+        let Some(source) = self.source.read().await.clone() else {
+            return;
+        };
+        match timeout(SNAPSHOT_TIMEOUT, source.snapshot(resource_uri)).await {
+            Ok(Ok(value)) => {
+                let mut uuids = HashSet::new();
+                mentioned_uuids(&value, &mut uuids);
+                self.resource_uuids
+                    .write()
+                    .await
+                    .insert(resource_uri.to_string(), uuids);
+                self.last_values
+                    .write()
+                    .await
+                    .insert(resource_uri.to_string(), value);
+            }
+            Ok(Err(e)) => debug!("No baseline for {}: {}", resource_uri, e),
+            Err(_) => debug!("Timeout reading baseline for {}", resource_uri),
+        }
+    }
+
+    /// Re-read the given resources and emit a change event for every one that differs
+    ///
+    /// Returns the number of changed resources.
+    pub async fn detect_changes(&self, resource_uris: &HashSet<String>) -> Result<usize> {
+        Self::forget_unmonitored(resource_uris, &self.last_values, &self.resource_uuids).await;
+        Self::scan_resources(
+            resource_uris,
+            &self.source,
+            &self.last_values,
+            &self.resource_uuids,
+            &self.last_change_times,
+            &self.event_sender,
+            &self.stats,
+        )
+        .await
+    }
+
+    /// Start monitoring subscribed resources for changes
+    pub async fn start_monitoring(
+        &self,
+        subscription_manager: Arc<ResourceSubscriptionManager>,
+    ) -> Result<()> {
         debug!("🚀 Starting resource change monitoring");
 
         // Reset stop flag
@@ -83,34 +248,85 @@ impl ResourceChangeDetector {
             *should_stop = false;
         }
 
-        // Start periodic structure refresh
-        let _uuid_cache = self.uuid_to_resource_cache.clone();
+        let mut state_changes = self.state_changes.write().await.take();
+        let source = self.source.clone();
+        let last_values = self.last_values.clone();
+        let resource_uuids = self.resource_uuids.clone();
+        let last_change_times = self.last_change_times.clone();
+        let event_sender = self.event_sender.clone();
         let stats = self.stats.clone();
         let should_stop = self.should_stop.clone();
+        let poll_interval = self.poll_interval;
+        let debounce_duration = self.debounce_duration;
 
         tokio::spawn(async move {
-            let mut refresh_interval = interval(Duration::from_secs(300)); // 5 minutes
+            // Pushes are coalesced to one scan per debounce period
+            let mut check_interval = interval(debounce_duration);
+            check_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut pushed_states = HashSet::new();
+            let mut last_push: Option<Instant> = None;
+            let mut last_scan = Instant::now();
 
             loop {
-                refresh_interval.tick().await;
-
-                if *should_stop.read().await {
-                    break;
-                }
-
-                // Refresh UUID mapping periodically
-                debug!("🔄 Refreshing UUID to resource mapping");
-                // Note: In a real implementation, we'd refresh the mapping here
-                // For now, we'll just update stats
-                {
-                    let mut detector_stats = stats.write().await;
-                    detector_stats.mapping_cache_hits += 1;
+                tokio::select! {
+                    push = next_push(&mut state_changes) => match push {
+                        Ok(state_uuid) => {
+                            pushed_states.insert(Some(state_uuid));
+                            last_push = Some(Instant::now());
+                            stats.write().await.websocket_events_processed += 1;
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            // Missed pushes could have touched any resource
+                            pushed_states.insert(None);
+                            last_push = Some(Instant::now());
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            info!("State push feed closed, falling back to polling");
+                            state_changes = None;
+                        }
+                    },
+                    _ = check_interval.tick() => {
+                        if *should_stop.read().await {
+                            break;
+                        }
+
+                        let pushes_quiet =
+                            last_push.is_none_or(|at| at.elapsed() >= poll_interval);
+                        let poll_due = pushes_quiet && last_scan.elapsed() >= poll_interval;
+                        if pushed_states.is_empty() && !poll_due {
+                            continue;
+                        }
+                        last_scan = Instant::now();
+
+                        let monitored = subscription_manager.get_monitored_resources().await;
+                        Self::forget_unmonitored(&monitored, &last_values, &resource_uuids).await;
+                        let pushed = std::mem::take(&mut pushed_states);
+                        let resource_uris = if poll_due || pushed.contains(&None) {
+                            monitored.clone()
+                        } else {
+                            let pushed: Vec<String> = pushed.into_iter().flatten().collect();
+                            Self::affected_resources(&monitored, &pushed, &source, &resource_uuids)
+                                .await
+                        };
+                        if let Err(e) = Self::scan_resources(
+                            &resource_uris,
+                            &source,
+                            &last_values,
+                            &resource_uuids,
+                            &last_change_times,
+                            &event_sender,
+                            &stats,
+                        )
+                        .await
+                        {
+                            warn!("Error scanning subscribed resources: {}", e);
+                        }
+                    }
                 }
             }
-        });
 
-        // Start WebSocket event monitoring
-        self.monitor_websocket_events().await?;
+            info!("🔍 Resource change monitoring stopped");
+        });
 
         Ok(())
     }
@@ -125,102 +341,118 @@ impl ResourceChangeDetector {
         Ok(())
     }
 
-    /// Monitor WebSocket events for changes
-    async fn monitor_websocket_events(&self) -> Result<()> {
-        debug!("👂 Starting WebSocket event monitoring");
-
-        // In a real implementation, this would:
-        // 1. Connect to Loxone WebSocket
-        // 2. Listen for state update events
-        // 3. Parse and map events to resource changes
-        // 4. Apply debouncing
-        // 5. Send notifications
+    /// Subscribed resources that may change with the pushed states
+    ///
+    /// Resources without a snapshot yet, or whose snapshot mentions no UUID,
+    /// are always included.
+    async fn affected_resources(
+        resource_uris: &HashSet<String>,
+        pushed_states: &[String],
+        source: &SharedSource,
+        resource_uuids: &ResourceUuids,
+    ) -> HashSet<String> {
+        let Some(source) = source.read().await.clone() else {
+            return resource_uris.clone();
+        };
+        let mut affected = HashSet::new();
+        for state_uuid in pushed_states {
+            affected.extend(source.affected_uuids(state_uuid).await);
+        }
 
-        // For now, we'll simulate monitoring with a periodic check
-        let event_sender = self.event_sender.clone();
-        let uuid_cache = self.uuid_to_resource_cache.clone();
-        let last_values = self.last_values.clone();
-        let stats = self.stats.clone();
-        let should_stop = self.should_stop.clone();
-        let debounce_duration = self.debounce_duration;
-        let last_change_times = self.last_change_times.clone();
+        let resource_uuids = resource_uuids.read().await;
+        resource_uris
+            .iter()
+            .filter(|uri| match resource_uuids.get(*uri) {
+                Some(uuids) if !uuids.is_empty() => !uuids.is_disjoint(&affected),
+                _ => true,
+            })
+            .cloned()
+            .collect()
+    }
 
-        tokio::spawn(async move {
-            let mut check_interval = interval(Duration::from_secs(1));
+    /// Forget snapshots of resources nobody is subscribed to anymore
+    async fn forget_unmonitored(
+        monitored: &HashSet<String>,
+        last_values: &Arc<RwLock<HashMap<String, Value>>>,
+        resource_uuids: &ResourceUuids,
+    ) {
+        last_values
+            .write()
+            .await
+            .retain(|uri, _| monitored.contains(uri));
+        resource_uuids
+            .write()
+            .await
+            .retain(|uri, _| monitored.contains(uri));
+    }
 
-            loop {
-                check_interval.tick().await;
+    /// Compare the current content of resources against their last snapshot
+    async fn scan_resources(
+        resource_uris: &HashSet<String>,
+        source: &SharedSource,
+        last_values: &Arc<RwLock<HashMap<String, Value>>>,
+        resource_uuids: &ResourceUuids,
+        last_change_times: &Arc<RwLock<HashMap<String, SystemTime>>>,
+        event_sender: &broadcast::Sender<SubscriptionEvent>,
+        stats: &Arc<RwLock<ChangeDetectorStats>>,
+    ) -> Result<usize> {
+        let Some(source) = source.read().await.clone() else {
+            return Ok(0);
+        };
 
-                if *should_stop.read().await {
-                    break;
+        let mut changed = 0;
+        for resource_uri in resource_uris {
+            let current = match timeout(SNAPSHOT_TIMEOUT, source.snapshot(resource_uri)).await {
+                Ok(Ok(value)) => value,
+                Ok(Err(e)) => {
+                    debug!("Failed to read {}: {}", resource_uri, e);
+                    continue;
                 }
+                Err(_) => {
+                    warn!("Timeout reading {}", resource_uri);
+                    continue;
+                }
+            };
 
-                // Simulate WebSocket event processing
-                // In real implementation, this would be event-driven
-                if let Err(e) = Self::process_simulated_events(
-                    &event_sender,
-                    &uuid_cache,
-                    &last_values,
-                    &stats,
-                    debounce_duration,
-                    &last_change_times,
-                )
+            let mut uuids = HashSet::new();
+            mentioned_uuids(&current, &mut uuids);
+            resource_uuids
+                .write()
                 .await
-                {
-                    warn!("Error processing WebSocket events: {}", e);
-                }
+                .insert(resource_uri.clone(), uuids);
+            let previous = last_values
+                .write()
+                .await
+                .insert(resource_uri.clone(), current.clone());
+            // The first snapshot only establishes the baseline
+            let Some(previous) = previous else {
+                continue;
+            };
+            if previous == current {
+                continue;
             }
-        });
-
-        Ok(())
-    }
-
-    /// Process simulated WebSocket events (replace with real implementation)
-    async fn process_simulated_events(
-        event_sender: &broadcast::Sender<SubscriptionEvent>,
-        _uuid_cache: &Arc<RwLock<HashMap<String, String>>>,
-        _last_values: &Arc<RwLock<HashMap<String, Value>>>,
-        stats: &Arc<RwLock<ChangeDetectorStats>>,
-        _debounce_duration: Duration,
-        _last_change_times: &Arc<RwLock<HashMap<String, SystemTime>>>,
-    ) -> Result<()> {
-        // Simulate occasional events for testing
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        // Simple pseudo-random number generation without external dependencies
-        let mut hasher = DefaultHasher::new();
-        std::time::SystemTime::now().hash(&mut hasher);
-        let pseudo_random = (hasher.finish() % 1000) as f64 / 1000.0;
 
-        if pseudo_random < 0.001 {
-            // 0.1% chance per check
             let change = ResourceChange {
-                resource_uri: "loxone://devices/all".to_string(),
-                change_type: ResourceChangeType::DeviceState,
+                resource_uri: resource_uri.clone(),
+                change_type: change_type_for_uri(resource_uri),
                 timestamp: SystemTime::now(),
-                previous_value: Some(serde_json::json!({"state": "off"})),
-                new_value: serde_json::json!({"state": "on"}),
-                loxone_uuid: Some("simulated-device-123".to_string()),
-                metadata: {
-                    let mut map = HashMap::new();
-                    map.insert("simulation".to_string(), serde_json::json!(true));
-                    map
-                },
+                previous_value: Some(previous),
+                new_value: current,
+                loxone_uuid: None,
+                metadata: HashMap::new(),
             };
-
-            // Send resource change event
+            last_change_times
+                .write()
+                .await
+                .insert(resource_uri.clone(), change.timestamp);
             let _ = event_sender.send(SubscriptionEvent::ResourceChanged { change });
-
-            // Update stats
-            {
-                let mut detector_stats = stats.write().await;
-                detector_stats.changes_detected += 1;
-                detector_stats.websocket_events_processed += 1;
-            }
+            changed += 1;
         }
 
-        Ok(())
+        if changed > 0 {
+            stats.write().await.changes_detected += changed as u64;
+        }
+        Ok(changed)
     }
 
     /// Build mapping from Loxone UUIDs to resource URIs
@@ -356,6 +588,24 @@ impl ResourceChangeDetector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::server::subscription::types::{ClientInfo, ClientTransport};
+
+    /// Snapshot source serving values from a shared map
+    #[derive(Default)]
+    struct MapSource(RwLock<HashMap<String, Value>>, RwLock<Vec<String>>);
+
+    #[async_trait]
+    impl ResourceSnapshotSource for MapSource {
+        async fn snapshot(&self, resource_uri: &str) -> Result<Value> {
+            self.1.write().await.push(resource_uri.to_string());
+            self.0
+                .read()
+                .await
+                .get(resource_uri)
+                .cloned()
+                .ok_or_else(|| LoxoneError::not_found(resource_uri))
+        }
+    }
 
     #[tokio::test]
     async fn test_detector_creation() {
@@ -405,4 +655,147 @@ mod tests {
         // Should not be debounced anymore
         assert!(!detector.should_debounce(resource_uri).await);
     }
+
+    #[tokio::test]
+    async fn test_detect_changes_against_baseline() {
+        let (sender, mut events) = broadcast::channel(100);
+        let detector = ResourceChangeDetector::new(sender).await.unwrap();
+        let source = Arc::new(MapSource::default());
+        let uri = "loxone://sensors/door-window".to_string();
+        source
+            .0
+            .write()
+            .await
+            .insert(uri.clone(), serde_json::json!({"open": 0}));
+        detector.set_source(source.clone(), None).await;
+
+        detector.track_resource(&uri).await;
+        let uris = HashSet::from([uri.clone()]);
+        assert_eq!(detector.detect_changes(&uris).await.unwrap(), 0);
+
+        source
+            .0
+            .write()
+            .await
+            .insert(uri.clone(), serde_json::json!({"open": 1}));
+        assert_eq!(detector.detect_changes(&uris).await.unwrap(), 1);
+
+        match events.recv().await.unwrap() {
+            SubscriptionEvent::ResourceChanged { change } => {
+                assert_eq!(change.resource_uri, uri);
+                assert_eq!(change.change_type, ResourceChangeType::SensorValue);
+                assert_eq!(change.previous_value, Some(serde_json::json!({"open": 0})));
+                assert_eq!(change.new_value, serde_json::json!({"open": 1}));
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+        assert_eq!(detector.get_statistics().await.changes_detected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_polling_without_push_feed() {
+        let (sender, mut events) = broadcast::channel(100);
+        let detector =
+            ResourceChangeDetector::with_poll_interval(sender, Duration::from_millis(100))
+                .await
+                .unwrap();
+        let source = Arc::new(MapSource::default());
+        let uri = "loxone://system/status".to_string();
+        source
+            .0
+            .write()
+            .await
+            .insert(uri.clone(), serde_json::json!({"connected": true}));
+        detector.set_source(source.clone(), None).await;
+
+        let manager = Arc::new(ResourceSubscriptionManager::new());
+        let client = ClientInfo {
+            id: "stdio".to_string(),
+            transport: ClientTransport::Stdio,
+            capabilities: vec!["resources".to_string()],
+            connected_at: SystemTime::now(),
+        };
+        manager
+            .add_subscription(client, uri.clone(), None)
+            .await
+            .unwrap();
+        detector.track_resource(&uri).await;
+        detector.start_monitoring(manager).await.unwrap();
+
+        source
+            .0
+            .write()
+            .await
+            .insert(uri.clone(), serde_json::json!({"connected": false}));
+
+        let event = timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("polling did not detect the change")
+            .unwrap();
+        assert!(matches!(
+            event,
+            SubscriptionEvent::ResourceChanged { change } if change.resource_uri == uri
+        ));
+        detector.stop_monitoring().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_push_rereads_only_affected_resources() {
+        const LIGHT: &str = "1c8f8a16-0300-0001-ffff000000000000";
+        const BLINDS: &str = "1c8f8a16-0300-0003-ffff000000000000";
+
+        let (sender, mut events) = broadcast::channel(100);
+        let detector =
+            ResourceChangeDetector::with_poll_interval(sender, Duration::from_secs(3600))
+                .await
+                .unwrap();
+        let source = Arc::new(MapSource::default());
+        let light_uri = "loxone://devices/category/lighting".to_string();
+        let blinds_uri = "loxone://devices/category/blinds".to_string();
+        for (uri, uuid) in [(&light_uri, LIGHT), (&blinds_uri, BLINDS)] {
+            source
+                .0
+                .write()
+                .await
+                .insert(uri.clone(), serde_json::json!([{"uuid": uuid, "value": 0}]));
+        }
+        let (pushes, state_changes) = broadcast::channel(16);
+        detector
+            .set_source(source.clone(), Some(state_changes))
+            .await;
+
+        let manager = Arc::new(ResourceSubscriptionManager::new());
+        let client = ClientInfo {
+            id: "stdio".to_string(),
+            transport: ClientTransport::Stdio,
+            capabilities: vec!["resources".to_string()],
+            connected_at: SystemTime::now(),
+        };
+        for uri in [&light_uri, &blinds_uri] {
+            manager
+                .add_subscription(client.clone(), uri.clone(), None)
+                .await
+                .unwrap();
+            detector.track_resource(uri).await;
+        }
+        detector.start_monitoring(manager).await.unwrap();
+        source.1.write().await.clear();
+
+        source.0.write().await.insert(
+            light_uri.clone(),
+            serde_json::json!([{"uuid": LIGHT, "value": 1}]),
+        );
+        pushes.send(LIGHT.to_string()).unwrap();
+
+        let event = timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("push did not trigger a re-read")
+            .unwrap();
+        assert!(matches!(
+            event,
+            SubscriptionEvent::ResourceChanged { change } if change.resource_uri == light_uri
+        ));
+        assert_eq!(*source.1.read().await, vec![light_uri]);
+        detector.stop_monitoring().await.unwrap();
+    }
 }
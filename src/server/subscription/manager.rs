@@ -66,13 +66,12 @@ impl ResourceSubscriptionManager {
             last_notification: None,
         };
 
-        // Add to client subscriptions
+        // Add to client subscriptions, replacing a repeated subscription
         {
             let mut client_subs = self.client_subscriptions.write().await;
-            client_subs
-                .entry(client_id.clone())
-                .or_insert_with(Vec::new)
-                .push(subscription);
+            let subscriptions = client_subs.entry(client_id.clone()).or_default();
+            subscriptions.retain(|sub| sub.resource_uri != resource_uri);
+            subscriptions.push(subscription);
         }
 
         // Add to resource subscribers index
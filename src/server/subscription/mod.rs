@@ -7,11 +7,13 @@
 pub mod detector;
 pub mod dispatcher;
 pub mod manager;
+pub mod sink;
 pub mod types;
 
-pub use detector::ResourceChangeDetector;
+pub use detector::{ResourceChangeDetector, ResourceSnapshotSource};
 pub use dispatcher::NotificationDispatcher;
 pub use manager::ResourceSubscriptionManager;
+pub use sink::{NotificationSink, TransportNotificationSink};
 pub use types::{
     ClientInfo, ClientSubscription, NotificationTarget, ResourceChange, SubscriptionEvent,
     SubscriptionFilter,
@@ -19,7 +21,8 @@ pub use types::{
 
 use crate::error::Result;
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::time::Duration;
+use tokio::sync::{RwLock, broadcast};
 use tracing::{debug, info, warn};
 
 /// Central subscription coordinator that manages the entire subscription lifecycle
@@ -27,7 +30,7 @@ pub struct SubscriptionCoordinator {
     /// Manages client subscriptions and resource mappings
     subscription_manager: Arc<ResourceSubscriptionManager>,
 
-    /// Detects changes from Loxone WebSocket streams
+    /// Detects changes to subscribed resources
     change_detector: Arc<ResourceChangeDetector>,
 
     /// Dispatches notifications to subscribed clients
@@ -35,11 +38,19 @@ pub struct SubscriptionCoordinator {
 
     /// Broadcast channel for system-wide events
     system_events: broadcast::Sender<SubscriptionEvent>,
+
+    /// Structure reloads, forwarded as list-changed notifications once started
+    structure_changes: RwLock<Option<broadcast::Receiver<String>>>,
 }
 
 impl SubscriptionCoordinator {
     /// Create new subscription coordinator with all components
     pub async fn new() -> Result<Self> {
+        Self::with_poll_interval(detector::DEFAULT_POLL_INTERVAL).await
+    }
+
+    /// Create a coordinator that polls resources at the given interval without pushes
+    pub async fn with_poll_interval(poll_interval: Duration) -> Result<Self> {
         debug!("🔄 Initializing resource subscription system...");
 
         // Create broadcast channel for system events
@@ -47,7 +58,10 @@ impl SubscriptionCoordinator {
 
         // Initialize core components
         let subscription_manager = Arc::new(ResourceSubscriptionManager::new());
-        let change_detector = Arc::new(ResourceChangeDetector::new(system_events.clone()).await?);
+        let change_detector = Arc::new(
+            ResourceChangeDetector::with_poll_interval(system_events.clone(), poll_interval)
+                .await?,
+        );
         let notification_dispatcher =
             Arc::new(NotificationDispatcher::new(system_events.subscribe()));
 
@@ -58,20 +72,59 @@ impl SubscriptionCoordinator {
             change_detector,
             notification_dispatcher,
             system_events,
+            structure_changes: RwLock::new(None),
         })
     }
 
+    /// Attach the resource source for change detection
+    ///
+    /// `state_changes` is the WebSocket push feed; without it resources are polled.
+    pub async fn attach_source(
+        &self,
+        source: Arc<dyn ResourceSnapshotSource>,
+        state_changes: Option<broadcast::Receiver<String>>,
+    ) {
+        self.change_detector.set_source(source, state_changes).await;
+    }
+
+    /// Attach the sink notifications are delivered through
+    pub async fn attach_sink(&self, sink: Arc<dyn NotificationSink>) {
+        self.notification_dispatcher.set_sink(sink).await;
+    }
+
+    /// Attach the structure reload feed of the client context
+    ///
+    /// Every reload tells clients to refresh their tool and resource lists.
+    pub async fn attach_structure_changes(&self, structure_changes: broadcast::Receiver<String>) {
+        *self.structure_changes.write().await = Some(structure_changes);
+    }
+
+    /// Tell every client that the tool and resource lists changed
+    pub async fn notify_list_changed(&self) -> Result<()> {
+        Self::broadcast_list_changed(&self.notification_dispatcher).await
+    }
+
+    async fn broadcast_list_changed(dispatcher: &NotificationDispatcher) -> Result<()> {
+        for method in [
+            "notifications/tools/list_changed",
+            "notifications/resources/list_changed",
+        ] {
+            dispatcher.broadcast(method, serde_json::json!({})).await?;
+        }
+        Ok(())
+    }
+
     /// Start the subscription system background tasks
     pub async fn start(&self) -> Result<()> {
         debug!("🚀 Starting subscription system background tasks...");
 
         // Start change detection monitoring
         let change_detector = self.change_detector.clone();
-        let _subscription_manager = self.subscription_manager.clone();
+        let subscription_manager = self.subscription_manager.clone();
         let system_events = self.system_events.clone();
 
         tokio::spawn(async move {
-            if let Err(e) = change_detector.start_monitoring().await {
+            if let Err(e) = change_detector.start_monitoring(subscription_manager).await {
                 warn!("Change detector error: {}", e);
                 let _ = system_events.send(SubscriptionEvent::SystemError {
                     error: e.to_string(),
@@ -93,6 +146,33 @@ impl SubscriptionCoordinator {
             }
         });
 
+        // Forward structure reloads until shutdown
+        if let Some(mut structure_changes) = self.structure_changes.write().await.take() {
+            let dispatcher = self.notification_dispatcher.clone();
+            let mut system_events = self.system_events.subscribe();
+
+            tokio::spawn(async move {
+                loop {
+                    tokio::select! {
+                        change = structure_changes.recv() => match change {
+                            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                                info!("🔄 Structure changed, notifying clients");
+                                if let Err(e) = Self::broadcast_list_changed(&dispatcher).await {
+                                    warn!("Failed to send list changed notifications: {}", e);
+                                }
+                            }
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        },
+                        event = system_events.recv() => match event {
+                            Ok(SubscriptionEvent::SystemShutdown)
+                            | Err(broadcast::error::RecvError::Closed) => break,
+                            _ => {}
+                        },
+                    }
+                }
+            });
+        }
+
         debug!("✅ Subscription system started successfully");
         Ok(())
     }
@@ -116,6 +196,9 @@ impl SubscriptionCoordinator {
             .add_subscription(client_info, resource_uri.clone(), filter)
             .await?;
 
+        // Snapshot the resource so the next change is reported against it
+        self.change_detector.track_resource(&resource_uri).await;
+
         // Notify system of new subscription
         let _ = self
             .system_events
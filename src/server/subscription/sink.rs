@@ -0,0 +1,83 @@
+//! Notification Sinks
+//!
+//! Delivery endpoints for `notifications/resources/updated`. The dispatcher hands
+//! every notification to the sink attached for the transport the server runs on.
+//! Notifications without a subscription, such as `notifications/tools/list_changed`,
+//! are broadcast to every connected client.
+
+use super::types::{ClientInfo, ClientTransport, ResourceChangeNotification};
+use crate::error::{LoxoneError, Result};
+use async_trait::async_trait;
+use pulseengine_mcp_transport::Transport;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Connection id used when a notification should reach every open session
+pub const BROADCAST_CONNECTION: &str = "*";
+
+/// Delivers resource change notifications to MCP clients
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    /// Deliver a notification to the given client
+    async fn deliver(
+        &self,
+        client: &ClientInfo,
+        notification: &ResourceChangeNotification,
+    ) -> Result<()>;
+
+    /// Send a notification to every connected client
+    async fn broadcast(&self, method: &str, params: serde_json::Value) -> Result<()>;
+}
+
+/// Sends notifications through the running transport (stdout, or streamable HTTP SSE streams)
+pub struct TransportNotificationSink {
+    transport: Arc<dyn Transport>,
+}
+
+impl TransportNotificationSink {
+    /// Create a sink on top of a running transport
+    pub fn new(transport: Arc<dyn Transport>) -> Self {
+        Self { transport }
+    }
+}
+
+#[async_trait]
+impl NotificationSink for TransportNotificationSink {
+    async fn deliver(
+        &self,
+        client: &ClientInfo,
+        notification: &ResourceChangeNotification,
+    ) -> Result<()> {
+        let session_id = match &client.transport {
+            ClientTransport::HttpSse { connection_id }
+            | ClientTransport::WebSocket { connection_id }
+                if connection_id != BROADCAST_CONNECTION =>
+            {
+                Some(connection_id.as_str())
+            }
+            _ => None,
+        };
+
+        self.transport
+            .send_notification(
+                session_id,
+                &notification.method,
+                serde_json::to_value(&notification.params)?,
+            )
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Transport notification failed: {e}")))?;
+
+        debug!("📡 Transport notification sent to {}", client.id);
+        Ok(())
+    }
+
+    async fn broadcast(&self, method: &str, params: serde_json::Value) -> Result<()> {
+        self.transport
+            .send_notification(None, method, params)
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Transport notification failed: {e}")))?;
+
+        debug!("📡 Transport notification {} broadcast", method);
+        Ok(())
+    }
+}
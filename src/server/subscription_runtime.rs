@@ -0,0 +1,996 @@
+//! Subscription-aware serving for the macro-based server
+//!
+//! The `#[mcp_server]` backend accepts `resources/subscribe` as a no-op and keeps
+//! its transport private to `McpServer`, so nothing can push notifications after
+//! a request has been answered. [`LoxoneMcpService`] wraps [`LoxoneMcpServer`] and
+//! routes subscribe/unsubscribe through a [`SubscriptionCoordinator`];
+//! [`LoxoneMcpRuntime`] serves it while keeping a handle on the transport so
+//! `notifications/resources/updated` reaches stdio and streamable HTTP clients.
+//!
+//! Structure reloads of the client context are announced with
+//! `notifications/tools/list_changed` and `notifications/resources/list_changed`.
+//!
+//! Streamable HTTP subscriptions are tracked per session, so updates only reach
+//! the SSE stream of the session that subscribed.
+
+use crate::audit::AuditCaller;
+use crate::error::{LoxoneError, Result};
+use crate::server::macro_backend::LoxoneMcpServer;
+use crate::server::media;
+use crate::server::prompts;
+use crate::server::stdio_transport::StdioTransport;
+use crate::server::subscription::sink::BROADCAST_CONNECTION;
+use crate::server::subscription::types::ClientTransport;
+use crate::server::subscription::{
+    ClientInfo, ResourceSnapshotSource, SubscriptionCoordinator, TransportNotificationSink,
+};
+use async_trait::async_trait;
+use pulseengine_mcp_server::auth::AuthenticationManager;
+use pulseengine_mcp_server::security::SecurityMiddleware;
+use pulseengine_mcp_server::transport::{RequestHandler, TransportError};
+use pulseengine_mcp_server::{
+    CommonMcpError, GenericServerHandler, McpBackend, MiddlewareStack, ServerConfig, Transport,
+    TransportConfig, protocol::*,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+#[async_trait]
+impl ResourceSnapshotSource for LoxoneMcpServer {
+    async fn snapshot(&self, resource_uri: &str) -> Result<Value> {
+        self.catalog_resource(resource_uri).await
+    }
+
+    async fn affected_uuids(&self, state_uuid: &str) -> Vec<String> {
+        let mut uuids = vec![state_uuid.to_string()];
+        let Some(context) = self.context() else {
+            return uuids;
+        };
+        let Some(state) = context.resolve_state(state_uuid).await else {
+            return uuids;
+        };
+        // Sub-controls (e.g. daytimers) are listed under their parent control
+        if let Some(model) = context.control_model().await
+            && let Some(parent) = model.controls().find(|control| {
+                control
+                    .flatten()
+                    .iter()
+                    .any(|sub| sub.uuid == state.control_uuid)
+            })
+            && parent.uuid != state.control_uuid
+        {
+            uuids.push(parent.uuid.clone());
+        }
+        uuids.push(state.control_uuid);
+        uuids
+    }
+}
+
+/// [`LoxoneMcpServer`] with live resource subscriptions and configured tool gating
+#[derive(Clone)]
+pub struct LoxoneMcpService {
+    server: LoxoneMcpServer,
+    subscriptions: Arc<SubscriptionCoordinator>,
+    client: ClientInfo,
+    /// `clientInfo` names announced at `initialize`, by client id
+    client_names: Arc<std::sync::RwLock<HashMap<String, String>>>,
+}
+
+impl LoxoneMcpService {
+    /// Wrap a server for the given transport
+    pub async fn new(server: LoxoneMcpServer, transport: ClientTransport) -> Result<Self> {
+        let subscriptions = Arc::new(SubscriptionCoordinator::new().await?);
+        Self::with_coordinator(server, transport, subscriptions).await
+    }
+
+    /// Wrap a server using an existing subscription coordinator
+    pub async fn with_coordinator(
+        server: LoxoneMcpServer,
+        transport: ClientTransport,
+        subscriptions: Arc<SubscriptionCoordinator>,
+    ) -> Result<Self> {
+        // Pushed state values land in the shared context; without one we poll
+        let state_changes = server
+            .context()
+            .map(|context| context.subscribe_state_changes());
+        subscriptions
+            .attach_source(Arc::new(server.clone()), state_changes)
+            .await;
+        if let Some(context) = server.context() {
+            subscriptions
+                .attach_structure_changes(context.subscribe_structure_changes())
+                .await;
+        }
+
+        let id = match &transport {
+            ClientTransport::Stdio => "stdio",
+            ClientTransport::HttpSse { .. } => "http",
+            ClientTransport::WebSocket { .. } => "websocket",
+        };
+
+        Ok(Self {
+            server,
+            subscriptions,
+            client: ClientInfo {
+                id: id.to_string(),
+                transport,
+                capabilities: vec!["resources".to_string()],
+                connected_at: SystemTime::now(),
+            },
+            client_names: Arc::new(std::sync::RwLock::new(HashMap::new())),
+        })
+    }
+
+    /// The wrapped server
+    pub fn server(&self) -> &LoxoneMcpServer {
+        &self.server
+    }
+
+    /// The coordinator tracking subscriptions for this service
+    pub fn subscriptions(&self) -> &Arc<SubscriptionCoordinator> {
+        &self.subscriptions
+    }
+
+    /// Client of the request being handled
+    ///
+    /// HTTP requests carry the session they belong to; every session is a client
+    /// of its own whose notifications go to that session's SSE stream.
+    fn request_client(&self) -> ClientInfo {
+        let Some(session) = pulseengine_mcp_transport::try_current_session_id() else {
+            return self.client.clone();
+        };
+        let transport = match &self.client.transport {
+            ClientTransport::HttpSse { .. } => ClientTransport::HttpSse {
+                connection_id: session.clone(),
+            },
+            ClientTransport::WebSocket { .. } => ClientTransport::WebSocket {
+                connection_id: session.clone(),
+            },
+            ClientTransport::Stdio => ClientTransport::Stdio,
+        };
+        ClientInfo {
+            id: format!("{}:{session}", self.client.id),
+            transport,
+            ..self.client.clone()
+        }
+    }
+
+    /// Remember the `clientInfo` name an `initialize` request announces
+    pub fn record_initialize(&self, params: &Value) {
+        let Some(name) = params.pointer("/clientInfo/name").and_then(Value::as_str) else {
+            return;
+        };
+        self.client_names
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(self.request_client().id, name.to_string());
+    }
+
+    /// Client identity recorded with audited commands
+    ///
+    /// The `clientInfo` name from `initialize`, followed by the transport and
+    /// session the request arrived on. The name is self-reported; no API key
+    /// is checked.
+    pub fn audit_client(&self) -> String {
+        let id = self.request_client().id;
+        let names = self
+            .client_names
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match names.get(&id) {
+            Some(name) => format!("{name} ({id})"),
+            None => id,
+        }
+    }
+}
+
+#[async_trait]
+impl McpBackend for LoxoneMcpService {
+    type Error = CommonMcpError;
+    type Config = ();
+
+    async fn initialize(_config: Self::Config) -> std::result::Result<Self, Self::Error> {
+        Self::new(LoxoneMcpServer::default(), ClientTransport::Stdio)
+            .await
+            .map_err(|e| CommonMcpError::Setup(e.to_string()))
+    }
+
+    fn get_server_info(&self) -> ServerInfo {
+        let mut info = self.server.get_server_info();
+        if let Some(resources) = info.capabilities.resources.as_mut() {
+            resources.subscribe = Some(true);
+            resources.list_changed = Some(true);
+        }
+        if let Some(tools) = info.capabilities.tools.as_mut() {
+            tools.list_changed = Some(true);
+        }
+        info
+    }
+
+    async fn health_check(&self) -> std::result::Result<(), Self::Error> {
+        self.server.health_check().await
+    }
+
+    async fn list_tools(
+        &self,
+        request: PaginatedRequestParam,
+    ) -> std::result::Result<ListToolsResult, Self::Error> {
+        let mut result = self.server.list_tools(request).await?;
+        let tools = self.server.tool_config();
+        result
+            .tools
+            .retain(|tool| tools.is_tool_enabled(&tool.name));
+        Ok(result)
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+    ) -> std::result::Result<CallToolResult, Self::Error> {
+        if !self.server.tool_config().is_tool_enabled(&request.name) {
+            return Err(CommonMcpError::InvalidParams(format!(
+                "Tool '{}' is disabled by configuration",
+                request.name
+            )));
+        }
+        // Commands sent by the tool are audited with the tool and its caller
+        let caller = AuditCaller {
+            tool: Some(request.name.clone()),
+            client: Some(self.audit_client()),
+        };
+        caller
+            .scope(self.server.call_tool(request))
+            .await
+            .map(media::with_image_content)
+    }
+
+    async fn list_resources(
+        &self,
+        request: PaginatedRequestParam,
+    ) -> std::result::Result<ListResourcesResult, Self::Error> {
+        self.server.list_resources(request).await
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+    ) -> std::result::Result<ReadResourceResult, Self::Error> {
+        self.server.read_resource(request).await
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: PaginatedRequestParam,
+    ) -> std::result::Result<ListPromptsResult, Self::Error> {
+        Ok(ListPromptsResult {
+            prompts: prompts::list(),
+            next_cursor: None,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+    ) -> std::result::Result<GetPromptResult, Self::Error> {
+        let arguments = request.arguments.unwrap_or_default();
+        self.server
+            .render_prompt(&request.name, &arguments)
+            .await
+            .map_err(|e| match e {
+                LoxoneError::NotFound(_) | LoxoneError::InvalidInput(_) => {
+                    CommonMcpError::InvalidParams(e.to_string())
+                }
+                _ => CommonMcpError::Internal(e.to_string()),
+            })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+    ) -> std::result::Result<(), Self::Error> {
+        self.server
+            .resource_manager()
+            .parse_uri(&request.uri)
+            .map_err(|e| CommonMcpError::InvalidParams(e.to_string()))?;
+
+        self.subscriptions
+            .subscribe_client(self.request_client(), request.uri, None)
+            .await
+            .map_err(|e| CommonMcpError::Internal(e.to_string()))
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+    ) -> std::result::Result<(), Self::Error> {
+        self.subscriptions
+            .unsubscribe_client(self.request_client().id, Some(request.uri))
+            .await
+            .map_err(|e| CommonMcpError::Internal(e.to_string()))
+    }
+
+    async fn on_startup(&self) -> std::result::Result<(), Self::Error> {
+        self.subscriptions
+            .start()
+            .await
+            .map_err(|e| CommonMcpError::Setup(e.to_string()))
+    }
+
+    async fn on_shutdown(&self) -> std::result::Result<(), Self::Error> {
+        self.subscriptions
+            .shutdown()
+            .await
+            .map_err(|e| CommonMcpError::Internal(e.to_string()))?;
+
+        if let Some(client) = self.server.client()
+            && let Err(e) = client.shutdown().await
+        {
+            warn!("Failed to release Miniserver session: {}", e);
+        }
+        Ok(())
+    }
+}
+
+/// Shared view of the running transport for the handler and notification sink
+struct SharedTransport {
+    transport: Arc<RwLock<Box<dyn Transport>>>,
+}
+
+#[async_trait]
+impl Transport for SharedTransport {
+    async fn start(&mut self, _handler: RequestHandler) -> std::result::Result<(), TransportError> {
+        Err(TransportError::NotSupported(
+            "Cannot start transport through shared handle".to_string(),
+        ))
+    }
+
+    async fn stop(&mut self) -> std::result::Result<(), TransportError> {
+        Err(TransportError::NotSupported(
+            "Cannot stop transport through shared handle".to_string(),
+        ))
+    }
+
+    async fn health_check(&self) -> std::result::Result<(), TransportError> {
+        self.transport.read().await.health_check().await
+    }
+
+    fn supports_bidirectional(&self) -> bool {
+        self.transport
+            .try_read()
+            .map(|transport| transport.supports_bidirectional())
+            .unwrap_or(false)
+    }
+
+    async fn send_notification(
+        &self,
+        session_id: Option<&str>,
+        method: &str,
+        params: Value,
+    ) -> std::result::Result<(), TransportError> {
+        self.transport
+            .read()
+            .await
+            .send_notification(session_id, method, params)
+            .await
+    }
+
+    async fn send_request(
+        &self,
+        session_id: Option<&str>,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> std::result::Result<Value, TransportError> {
+        self.transport
+            .read()
+            .await
+            .send_request(session_id, method, params, timeout)
+            .await
+    }
+
+    fn register_pending_request(
+        &self,
+        request_id: &str,
+    ) -> Option<tokio::sync::oneshot::Receiver<Value>> {
+        self.transport
+            .try_read()
+            .ok()
+            .and_then(|transport| transport.register_pending_request(request_id))
+    }
+}
+
+/// Runs a [`LoxoneMcpService`] on stdio or streamable HTTP
+pub struct LoxoneMcpRuntime {
+    service: LoxoneMcpService,
+    handler: GenericServerHandler<LoxoneMcpService>,
+    transport: Arc<RwLock<Box<dyn Transport>>>,
+    /// Handle tools and notifications use while the transport runs
+    shared: Arc<dyn Transport>,
+    transport_config: TransportConfig,
+}
+
+impl LoxoneMcpRuntime {
+    /// Serve over stdio
+    pub async fn stdio(server: LoxoneMcpServer) -> Result<Self> {
+        Self::build(server, TransportConfig::Stdio, ClientTransport::Stdio).await
+    }
+
+    /// Serve over streamable HTTP (`/mcp` with SSE notifications)
+    pub async fn streamable_http(server: LoxoneMcpServer, port: u16) -> Result<Self> {
+        Self::build(
+            server,
+            TransportConfig::StreamableHttp { port, host: None },
+            ClientTransport::HttpSse {
+                connection_id: BROADCAST_CONNECTION.to_string(),
+            },
+        )
+        .await
+    }
+
+    async fn build(
+        server: LoxoneMcpServer,
+        transport_config: TransportConfig,
+        client_transport: ClientTransport,
+    ) -> Result<Self> {
+        let service = LoxoneMcpService::new(server, client_transport).await?;
+        let (transport, shared): (_, Option<Arc<dyn Transport>>) = match transport_config {
+            // Holds stdin while running, so tools get a clone to reach the client
+            TransportConfig::Stdio => {
+                let stdio = StdioTransport::new();
+                (
+                    Box::new(stdio.clone()) as Box<dyn Transport>,
+                    Some(Arc::new(stdio)),
+                )
+            }
+            _ => (
+                pulseengine_mcp_server::transport::create_transport(transport_config.clone())
+                    .map_err(|e| {
+                        LoxoneError::connection(format!("Failed to create transport: {e}"))
+                    })?,
+                None,
+            ),
+        };
+        let transport = Arc::new(RwLock::new(transport));
+        let shared = shared.unwrap_or_else(|| {
+            Arc::new(SharedTransport {
+                transport: transport.clone(),
+            })
+        });
+
+        // Same middleware as the macro-generated servers: default security, no
+        // auth. Clients are not authenticated, so audit entries name them by
+        // their self-reported `clientInfo` (see `audit_client`).
+        let auth_manager = Arc::new(AuthenticationManager::new_disabled());
+        let middleware = MiddlewareStack::new()
+            .with_security(SecurityMiddleware::new(
+                ServerConfig::default().security_config,
+            ))
+            .with_auth(auth_manager.clone());
+        let handler =
+            GenericServerHandler::new(Arc::new(service.clone()), auth_manager, middleware);
+
+        Ok(Self {
+            service,
+            handler,
+            transport,
+            shared,
+            transport_config,
+        })
+    }
+
+    /// The service being served
+    pub fn service(&self) -> &LoxoneMcpService {
+        &self.service
+    }
+
+    /// Run until stdin closes (stdio) or Ctrl+C (HTTP)
+    pub async fn run(&mut self) -> Result<()> {
+        self.service
+            .on_startup()
+            .await
+            .map_err(|e| LoxoneError::internal(e.to_string()))?;
+
+        let handler = self.handler.clone();
+        let service = self.service.clone();
+        let request_handler: RequestHandler = Box::new(move |request| {
+            let handler = handler.clone();
+            let service = service.clone();
+            Box::pin(async move {
+                // The framework does not pass `initialize` on to the backend
+                if request.method == "initialize" {
+                    service.record_initialize(&request.params);
+                }
+                match handler.handle_request(request).await {
+                    Ok(response) => response,
+                    Err(error) => Response {
+                        jsonrpc: "2.0".to_string(),
+                        id: None,
+                        result: None,
+                        error: Some(error.into()),
+                    },
+                }
+            })
+        });
+
+        // Tools ask the client for approvals and sampling through the transport
+        self.handler.set_transport(self.shared.clone());
+
+        let served = match self.transport_config {
+            TransportConfig::Stdio => {
+                // The stdio transport reads stdin until EOF while holding the
+                // transport; notifications go through the shared handle, which
+                // writes to the same locked stdout as responses
+                self.service
+                    .subscriptions()
+                    .attach_sink(Arc::new(TransportNotificationSink::new(
+                        self.shared.clone(),
+                    )))
+                    .await;
+                self.transport.write().await.start(request_handler).await
+            }
+            _ => {
+                let started = self.transport.write().await.start(request_handler).await;
+                if started.is_ok() {
+                    self.service
+                        .subscriptions()
+                        .attach_sink(Arc::new(TransportNotificationSink::new(
+                            self.shared.clone(),
+                        )))
+                        .await;
+                    info!("✅ Resource subscriptions enabled");
+                    if let Err(e) = tokio::signal::ctrl_c().await {
+                        warn!("Failed to listen for Ctrl+C: {}", e);
+                    }
+                    info!("Shutdown signal received");
+                }
+                started
+            }
+        };
+
+        if let Err(e) = self.transport.write().await.stop().await {
+            warn!("Failed to stop transport: {}", e);
+        }
+        self.service
+            .on_shutdown()
+            .await
+            .map_err(|e| LoxoneError::internal(e.to_string()))?;
+
+        served.map_err(|e| LoxoneError::connection(format!("Transport error: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::binary_protocol::{WeatherEntry, WeatherEvent};
+    use crate::client::{ClientContext, LoxoneClient};
+    use crate::config::ServerConfig as LoxoneServerConfig;
+    use crate::mock::MockLoxoneClient;
+    use crate::server::subscription::NotificationSink;
+    use crate::server::subscription::types::ResourceChangeNotification;
+    use crate::services::{SensorTypeRegistry, UnifiedValueResolver};
+    use tokio::sync::mpsc;
+
+    /// Collects delivered notifications for assertions
+    struct ChannelSink(mpsc::UnboundedSender<ResourceChangeNotification>);
+
+    #[async_trait]
+    impl NotificationSink for ChannelSink {
+        async fn deliver(
+            &self,
+            _client: &ClientInfo,
+            notification: &ResourceChangeNotification,
+        ) -> Result<()> {
+            self.0
+                .send(notification.clone())
+                .map_err(|e| LoxoneError::connection(e.to_string()))
+        }
+
+        async fn broadcast(&self, _method: &str, _params: Value) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Collects the methods of broadcast notifications
+    struct BroadcastSink(mpsc::UnboundedSender<String>);
+
+    #[async_trait]
+    impl NotificationSink for BroadcastSink {
+        async fn deliver(
+            &self,
+            _client: &ClientInfo,
+            _notification: &ResourceChangeNotification,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn broadcast(&self, method: &str, _params: Value) -> Result<()> {
+            self.0
+                .send(method.to_string())
+                .map_err(|e| LoxoneError::connection(e.to_string()))
+        }
+    }
+
+    /// Records the session every notification is sent to
+    struct RecordingTransport(mpsc::UnboundedSender<Option<String>>);
+
+    #[async_trait]
+    impl Transport for RecordingTransport {
+        async fn start(
+            &mut self,
+            _handler: RequestHandler,
+        ) -> std::result::Result<(), TransportError> {
+            Ok(())
+        }
+
+        async fn stop(&mut self) -> std::result::Result<(), TransportError> {
+            Ok(())
+        }
+
+        async fn health_check(&self) -> std::result::Result<(), TransportError> {
+            Ok(())
+        }
+
+        async fn send_notification(
+            &self,
+            session_id: Option<&str>,
+            _method: &str,
+            _params: Value,
+        ) -> std::result::Result<(), TransportError> {
+            self.0
+                .send(session_id.map(str::to_string))
+                .map_err(|e| TransportError::Connection(e.to_string()))
+        }
+    }
+
+    fn test_server() -> LoxoneMcpServer {
+        let structure = serde_json::from_value(serde_json::json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": { "room-1": { "name": "Living Room", "type": 1 } },
+            "controls": {},
+            "cats": {}
+        }))
+        .unwrap();
+        let client: Arc<dyn LoxoneClient> =
+            Arc::new(MockLoxoneClient::new().with_structure(structure));
+        let value_resolver = Arc::new(UnifiedValueResolver::new(
+            client.clone(),
+            Arc::new(SensorTypeRegistry::new()),
+        ));
+        LoxoneMcpServer::with_context(
+            client,
+            Arc::new(ClientContext::new()),
+            value_resolver,
+            None,
+            LoxoneServerConfig::default(),
+        )
+    }
+
+    /// Push a one-entry forecast the way the WebSocket client records it
+    async fn push_weather(context: &ClientContext, temperature: f64) {
+        let event = WeatherEvent {
+            uuid: "weather-1".to_string(),
+            last_update: 86_400,
+            entries: vec![WeatherEntry {
+                timestamp: 90_000,
+                weather_type: 1,
+                wind_direction: 0,
+                solar_radiation: 0,
+                relative_humidity: 50,
+                temperature,
+                perceived_temperature: temperature,
+                dew_point: 0.0,
+                precipitation: 0.0,
+                wind_speed: 10.0,
+                barometric_pressure: 1013.0,
+            }],
+        };
+        context.record_weather_event(&event).await;
+        context
+            .record_state_value(&event.uuid, serde_json::to_value(&event).unwrap())
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_capability_is_advertised() {
+        let service = LoxoneMcpService::new(test_server(), ClientTransport::Stdio)
+            .await
+            .unwrap();
+        let capabilities = service.get_server_info().capabilities;
+        let resources = capabilities.resources.unwrap();
+        assert_eq!(resources.subscribe, Some(true));
+        assert_eq!(resources.list_changed, Some(true));
+        assert_eq!(capabilities.tools.unwrap().list_changed, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_structure_reload_notifies_list_changed() {
+        let server = test_server();
+        let context = server.context().unwrap().clone();
+        let service = LoxoneMcpService::new(server, ClientTransport::Stdio)
+            .await
+            .unwrap();
+        let (sender, mut notifications) = mpsc::unbounded_channel();
+        service
+            .subscriptions()
+            .attach_sink(Arc::new(BroadcastSink(sender)))
+            .await;
+        service.on_startup().await.unwrap();
+
+        let structure = |last_modified: &str| {
+            serde_json::from_value(serde_json::json!({
+                "lastModified": last_modified,
+                "rooms": {},
+                "controls": {},
+                "cats": {}
+            }))
+            .unwrap()
+        };
+        // The initial load is not a change
+        context
+            .update_structure(structure("2024-01-01 00:00:00"))
+            .await
+            .unwrap();
+        context
+            .update_structure(structure("2024-02-01 00:00:00"))
+            .await
+            .unwrap();
+
+        let mut methods = Vec::new();
+        for _ in 0..2 {
+            let method = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+                .await
+                .expect("no notification after structure reload")
+                .unwrap();
+            methods.push(method);
+        }
+        assert_eq!(
+            methods,
+            [
+                "notifications/tools/list_changed",
+                "notifications/resources/list_changed"
+            ]
+        );
+        let next = tokio::time::timeout(Duration::from_millis(500), notifications.recv()).await;
+        assert!(next.is_err());
+
+        service.on_shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_rejects_unknown_resources() {
+        let service = LoxoneMcpService::new(test_server(), ClientTransport::Stdio)
+            .await
+            .unwrap();
+        let result = service
+            .subscribe(SubscribeRequestParam {
+                uri: "loxone://nowhere".to_string(),
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            service
+                .subscriptions()
+                .get_statistics()
+                .await
+                .total_subscriptions,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pushed_state_change_notifies_subscriber() {
+        let server = test_server();
+        let context = server.context().unwrap().clone();
+        let service = LoxoneMcpService::new(server, ClientTransport::Stdio)
+            .await
+            .unwrap();
+        let (sender, mut notifications) = mpsc::unbounded_channel();
+        service
+            .subscriptions()
+            .attach_sink(Arc::new(ChannelSink(sender)))
+            .await;
+        service.on_startup().await.unwrap();
+
+        let uri = "loxone://weather/forecast-hourly";
+        service
+            .subscribe(SubscribeRequestParam {
+                uri: uri.to_string(),
+            })
+            .await
+            .unwrap();
+
+        push_weather(&context, 7.5).await;
+
+        let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+            .await
+            .expect("no notification after state push")
+            .unwrap();
+        assert_eq!(notification.method, "notifications/resources/updated");
+        assert_eq!(notification.params.uri, uri);
+        assert_eq!(
+            notification.params.data.unwrap()["forecast"][0]["temperature"],
+            7.5
+        );
+
+        // No further notifications once unsubscribed
+        service
+            .unsubscribe(UnsubscribeRequestParam {
+                uri: uri.to_string(),
+            })
+            .await
+            .unwrap();
+        push_weather(&context, 9.0).await;
+        let next = tokio::time::timeout(Duration::from_millis(1500), notifications.recv()).await;
+        assert!(next.is_err());
+
+        service.on_shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tools_are_hidden_and_rejected() {
+        let mut config = LoxoneServerConfig::default();
+        config.mcp.tools.enable_weather = false;
+        let server = test_server().with_config(config);
+        let service = LoxoneMcpService::new(server, ClientTransport::Stdio)
+            .await
+            .unwrap();
+
+        let tools = service
+            .list_tools(PaginatedRequestParam { cursor: None })
+            .await
+            .unwrap()
+            .tools;
+        assert!(!tools.iter().any(|tool| tool.name == "get_weather"));
+        assert!(tools.iter().any(|tool| tool.name == "list_rooms"));
+
+        let result = service
+            .call_tool(CallToolRequestParam {
+                name: "get_weather".to_string(),
+                arguments: None,
+            })
+            .await;
+        assert!(matches!(result, Err(CommonMcpError::InvalidParams(_))));
+    }
+
+    #[tokio::test]
+    async fn test_every_tool_belongs_to_a_tool_group() {
+        let server = test_server();
+        let tools = server.tool_config();
+        let names: Vec<String> = server
+            .list_tools(PaginatedRequestParam { cursor: None })
+            .await
+            .unwrap()
+            .tools
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect();
+
+        let ungated: Vec<&String> = names
+            .iter()
+            .filter(|name| !tools.is_tool_enabled(name))
+            .collect();
+        assert!(ungated.is_empty(), "tools without a group: {ungated:?}");
+    }
+
+    #[tokio::test]
+    async fn test_prompts_are_listed_and_rendered() {
+        let service = LoxoneMcpService::new(test_server(), ClientTransport::Stdio)
+            .await
+            .unwrap();
+
+        let prompts = service
+            .list_prompts(PaginatedRequestParam { cursor: None })
+            .await
+            .unwrap()
+            .prompts;
+        assert!(prompts.iter().any(|prompt| prompt.name == "leaving_home"));
+
+        let result = service
+            .get_prompt(GetPromptRequestParam {
+                name: "leaving_home".to_string(),
+                arguments: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.messages.len(), 2);
+
+        let result = service
+            .get_prompt(GetPromptRequestParam {
+                name: "evening_routine".to_string(),
+                arguments: None,
+            })
+            .await;
+        assert!(matches!(result, Err(CommonMcpError::InvalidParams(_))));
+    }
+
+    #[tokio::test]
+    async fn test_http_subscriptions_are_tracked_per_session() {
+        let server = test_server();
+        let context = server.context().unwrap().clone();
+        let service = LoxoneMcpService::new(
+            server,
+            ClientTransport::HttpSse {
+                connection_id: BROADCAST_CONNECTION.to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        let (sender, mut sessions) = mpsc::unbounded_channel();
+        service
+            .subscriptions()
+            .attach_sink(Arc::new(TransportNotificationSink::new(Arc::new(
+                RecordingTransport(sender),
+            ))))
+            .await;
+        service.on_startup().await.unwrap();
+
+        let uri = "loxone://weather/forecast-hourly";
+        for session in ["session-a", "session-b"] {
+            pulseengine_mcp_transport::with_session(
+                session.to_string(),
+                service.subscribe(SubscribeRequestParam {
+                    uri: uri.to_string(),
+                }),
+            )
+            .await
+            .unwrap();
+        }
+        pulseengine_mcp_transport::with_session(
+            "session-a".to_string(),
+            service.unsubscribe(UnsubscribeRequestParam {
+                uri: uri.to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        push_weather(&context, 7.5).await;
+
+        let session = tokio::time::timeout(Duration::from_secs(5), sessions.recv())
+            .await
+            .expect("no notification after state push")
+            .unwrap();
+        assert_eq!(session.as_deref(), Some("session-b"));
+        let next = tokio::time::timeout(Duration::from_millis(1500), sessions.recv()).await;
+        assert!(next.is_err());
+
+        service.on_shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_audit_client_names_the_initialized_client() {
+        let service = LoxoneMcpService::new(
+            test_server(),
+            ClientTransport::HttpSse {
+                connection_id: BROADCAST_CONNECTION.to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        pulseengine_mcp_transport::with_session("session-a".to_string(), async {
+            service.record_initialize(&serde_json::json!({
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": { "name": "claude-desktop", "version": "1.0" }
+            }));
+        })
+        .await;
+
+        let client = pulseengine_mcp_transport::with_session("session-a".to_string(), async {
+            service.audit_client()
+        })
+        .await;
+        assert_eq!(client, "claude-desktop (http:session-a)");
+        let other = pulseengine_mcp_transport::with_session("session-b".to_string(), async {
+            service.audit_client()
+        })
+        .await;
+        assert_eq!(other, "http:session-b");
+    }
+}
@@ -5,6 +5,7 @@
 
 pub mod cache_manager;
 pub mod connection_pool;
+pub mod name_resolution;
 pub mod sensor_logger;
 pub mod sensor_registry;
 pub mod state_manager;
@@ -12,6 +13,7 @@ pub mod unified_models;
 pub mod value_parsers;
 pub mod value_resolution;
 
+pub use name_resolution::{Candidate, NameResolver, Resolution};
 pub use sensor_logger::SensorStateLogger;
 pub use sensor_registry::{SensorInventory, SensorType, SensorTypeRegistry};
 pub use state_manager::{
@@ -0,0 +1,539 @@
+//! Name resolution for rooms and devices
+//!
+//! Users refer to rooms and devices the way they speak, in German or English,
+//! with or without umlauts and often with typos: "Bad", "Kueche Licht",
+//! "living room lights". [`NameResolver`] scores every room or control against
+//! such a query and either picks a clear winner or returns the candidates, so
+//! tools ask back instead of acting on an arbitrary match.
+//!
+//! Scoring compares normalized names (lowercase, umlauts and `ß` folded,
+//! punctuation removed) and their English translations: exact matches first,
+//! then all query words present, word prefixes, substrings and finally
+//! edit-distance similarity. Controls are also matched together with their
+//! room name, so "Küche Licht" finds the light named "Licht" in the kitchen.
+//!
+//! A translation never singles out one of several names the query matches as
+//! spelled: "Bad" is the German word for the bathroom, but it is also a prefix
+//! of both "Badezimmer" and "Badzimmer OG", so both are returned.
+
+use crate::client::{ControlModel, LoxoneControl};
+use crate::error::{LoxoneError, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Scores of the match kinds, best first
+const EXACT: f64 = 1.0;
+const TRANSLATED_EXACT: f64 = 0.95;
+const ALL_WORDS: f64 = 0.9;
+const WORD_PREFIXES: f64 = 0.8;
+const SUBSTRING: f64 = 0.7;
+const FUZZY: f64 = 0.6;
+
+/// Word similarity needed for an edit-distance match
+const MIN_SIMILARITY: f64 = 0.75;
+
+/// Lowest score that still counts as a candidate: the weakest fuzzy match
+const MIN_SCORE: f64 = FUZZY * MIN_SIMILARITY;
+
+/// Candidates within this distance of the best score make a query ambiguous
+const AMBIGUITY_MARGIN: f64 = 0.1;
+
+/// Most candidates reported for an ambiguous query
+const MAX_CANDIDATES: usize = 10;
+
+/// German words and English synonyms with their canonical English form
+const TRANSLATIONS: &[(&str, &str)] = &[
+    ("bad", "bathroom"),
+    ("badezimmer", "bathroom"),
+    ("bath", "bathroom"),
+    ("kueche", "kitchen"),
+    ("wohnzimmer", "living room"),
+    ("lounge", "living room"),
+    ("schlafzimmer", "bedroom"),
+    ("kinderzimmer", "kids room"),
+    ("esszimmer", "dining room"),
+    ("arbeitszimmer", "office"),
+    ("buero", "office"),
+    ("gaestezimmer", "guest room"),
+    ("flur", "hallway"),
+    ("diele", "hallway"),
+    ("gang", "hallway"),
+    ("hall", "hallway"),
+    ("corridor", "hallway"),
+    ("eingang", "entrance"),
+    ("keller", "basement"),
+    ("dachboden", "attic"),
+    ("garten", "garden"),
+    ("terrasse", "terrace"),
+    ("og", "upper floor"),
+    ("obergeschoss", "upper floor"),
+    ("eg", "ground floor"),
+    ("erdgeschoss", "ground floor"),
+    ("licht", "light"),
+    ("lichter", "light"),
+    ("lights", "light"),
+    ("beleuchtung", "light"),
+    ("leuchte", "light"),
+    ("lampe", "lamp"),
+    ("deckenlicht", "ceiling light"),
+    ("deckenleuchte", "ceiling light"),
+    ("stehlampe", "floor lamp"),
+    ("jalousie", "blinds"),
+    ("jalousien", "blinds"),
+    ("rollladen", "blinds"),
+    ("rolladen", "blinds"),
+    ("raffstore", "blinds"),
+    ("blind", "blinds"),
+    ("shutters", "blinds"),
+    ("beschattung", "shading"),
+    ("heizung", "heating"),
+    ("klima", "climate"),
+    ("temperatur", "temperature"),
+    ("tuer", "door"),
+    ("haustuer", "front door"),
+    ("fenster", "window"),
+    ("tor", "gate"),
+    ("garagentor", "garage gate"),
+    ("steckdose", "outlet"),
+    ("musik", "music"),
+    ("montag", "monday"),
+    ("dienstag", "tuesday"),
+    ("mittwoch", "wednesday"),
+    ("donnerstag", "thursday"),
+    ("freitag", "friday"),
+    ("samstag", "saturday"),
+    ("sonntag", "sunday"),
+    ("feiertag", "holiday"),
+    ("urlaub", "vacation"),
+];
+
+/// Match of a query against a room or control
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candidate {
+    /// UUID of the room or control
+    pub uuid: String,
+    /// Display name
+    pub name: String,
+    /// Room name of a control
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    /// Match quality from 0 to 1
+    pub score: f64,
+}
+
+/// Outcome of resolving a name
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution<T> {
+    /// A single clear match
+    Found(T),
+    /// Several similarly good matches, best first
+    Ambiguous(Vec<Candidate>),
+    /// Nothing matched
+    NotFound,
+}
+
+impl<T> Resolution<T> {
+    /// The match, if there is a clear one
+    pub fn found(self) -> Option<T> {
+        match self {
+            Self::Found(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The match, or an error listing candidates or reporting the miss
+    pub fn into_result(self, kind: &str, query: &str) -> Result<T> {
+        match self {
+            Self::Found(value) => Ok(value),
+            Self::Ambiguous(candidates) => Err(LoxoneError::invalid_input(ambiguity_message(
+                kind,
+                query,
+                &candidates,
+            ))),
+            Self::NotFound => Err(LoxoneError::not_found(format!(
+                "{kind} '{query}' not found"
+            ))),
+        }
+    }
+}
+
+/// Describe the candidates of an ambiguous query
+pub fn ambiguity_message(kind: &str, query: &str, candidates: &[Candidate]) -> String {
+    let list: Vec<String> = candidates
+        .iter()
+        .map(|candidate| match &candidate.room {
+            Some(room) => format!("{} in {room} ({})", candidate.name, candidate.uuid),
+            None => format!("{} ({})", candidate.name, candidate.uuid),
+        })
+        .collect();
+    format!(
+        "{kind} '{query}' is ambiguous. Candidates: {}. Use a UUID or a more specific name.",
+        list.join("; ")
+    )
+}
+
+/// Fold a name for comparison: lowercase, umlauts spelled out, no punctuation
+pub fn normalize(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    for c in name.to_lowercase().chars() {
+        match c {
+            'ä' => folded.push_str("ae"),
+            'ö' => folded.push_str("oe"),
+            'ü' => folded.push_str("ue"),
+            'ß' => folded.push_str("ss"),
+            c if c.is_alphanumeric() => folded.push(c),
+            _ => folded.push(' '),
+        }
+    }
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Normalized English form of a name, for comparing German and English names
+pub fn canonical(name: &str) -> String {
+    translate(&normalize(name))
+}
+
+/// Normalized name with every known word replaced by its English form
+fn translate(normalized: &str) -> String {
+    normalized
+        .split(' ')
+        .map(|word| {
+            TRANSLATIONS
+                .iter()
+                .find(|(from, _)| *from == word)
+                .map_or(word, |(_, to)| to)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Levenshtein distance in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Similarity of two words from 0 to 1
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+/// Score a normalized query against a normalized name
+fn score_normalized(query: &str, name: &str, exact: f64) -> f64 {
+    if query.is_empty() || name.is_empty() {
+        return 0.0;
+    }
+    if query == name {
+        return exact;
+    }
+    let words: Vec<&str> = name.split(' ').collect();
+    let query_words: Vec<&str> = query.split(' ').collect();
+    if query_words.iter().all(|q| words.contains(q)) {
+        return ALL_WORDS;
+    }
+    if query_words
+        .iter()
+        .all(|q| words.iter().any(|w| w.starts_with(q)))
+    {
+        return WORD_PREFIXES;
+    }
+    if name.contains(query) {
+        return SUBSTRING;
+    }
+    let similarities: Vec<f64> = query_words
+        .iter()
+        .map(|q| words.iter().map(|w| similarity(q, w)).fold(0.0, f64::max))
+        .collect();
+    if similarities.iter().all(|s| *s >= MIN_SIMILARITY) {
+        let average = similarities.iter().sum::<f64>() / similarities.len() as f64;
+        return FUZZY * average;
+    }
+    0.0
+}
+
+/// Score of a query against a name
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Score {
+    /// Comparing the names as spelled
+    spelled: f64,
+    /// Best of spelled and translated
+    overall: f64,
+}
+
+impl Score {
+    fn max(self, other: Self) -> Self {
+        Self {
+            spelled: self.spelled.max(other.spelled),
+            overall: self.overall.max(other.overall),
+        }
+    }
+}
+
+/// Score a query against a name, as spelled and translated
+fn score(query: &str, name: &str) -> Score {
+    let (query, name) = (normalize(query), normalize(name));
+    let spelled = score_normalized(&query, &name, EXACT);
+    Score {
+        spelled,
+        overall: spelled.max(score_normalized(
+            &translate(&query),
+            &translate(&name),
+            TRANSLATED_EXACT,
+        )),
+    }
+}
+
+/// Candidate with the score it got as spelled
+struct Scored {
+    candidate: Candidate,
+    spelled: f64,
+}
+
+impl Scored {
+    fn new(uuid: &str, name: &str, room: Option<&str>, score: Score) -> Self {
+        Self {
+            candidate: Candidate {
+                uuid: uuid.to_string(),
+                name: name.to_string(),
+                room: room.map(str::to_string),
+                score: score.overall,
+            },
+            spelled: score.spelled,
+        }
+    }
+}
+
+/// Resolves room and device names, including user-defined aliases
+#[derive(Debug, Clone, Default)]
+pub struct NameResolver {
+    /// Normalized alias to the name or UUID it stands for
+    aliases: HashMap<String, String>,
+}
+
+impl NameResolver {
+    /// Create a resolver with aliases mapping names to room or device names or UUIDs
+    pub fn new(aliases: &HashMap<String, String>) -> Self {
+        Self {
+            aliases: aliases
+                .iter()
+                .map(|(alias, target)| (normalize(alias), target.clone()))
+                .collect(),
+        }
+    }
+
+    /// The query with a matching alias replaced by its target
+    fn expand<'a>(&'a self, query: &'a str) -> &'a str {
+        self.aliases
+            .get(&normalize(query))
+            .map_or(query, String::as_str)
+    }
+
+    /// Resolve a room name or UUID to the room UUID
+    pub fn resolve_room<'a>(&self, model: &'a ControlModel, query: &str) -> Resolution<&'a str> {
+        let query = self.expand(query);
+        if let Some((uuid, _)) = model.rooms().get_key_value(query) {
+            return Resolution::Found(uuid.as_str());
+        }
+        let candidates = model
+            .rooms()
+            .iter()
+            .map(|(uuid, name)| Scored::new(uuid, name, None, score(query, name)))
+            .collect();
+        decide(candidates, |uuid| {
+            model
+                .rooms()
+                .get_key_value(uuid)
+                .map(|(uuid, _)| uuid.as_str())
+        })
+    }
+
+    /// Resolve a device name or UUID, optionally prefixed or followed by its room
+    pub fn resolve_control<'a>(
+        &self,
+        model: &'a ControlModel,
+        query: &str,
+    ) -> Resolution<&'a LoxoneControl> {
+        let query = self.expand(query);
+        if let Some(control) = model.get(query) {
+            return Resolution::Found(control);
+        }
+        let candidates = model
+            .controls()
+            .map(|control| {
+                let room = control
+                    .room
+                    .as_deref()
+                    .and_then(|room| model.room_name(room));
+                let by_name = score(query, &control.name);
+                // "Küche Licht" and "Licht Küche" both name the kitchen light
+                let by_room = room.map_or(Score::default(), |room| {
+                    score(query, &format!("{room} {}", control.name))
+                        .max(score(query, &format!("{} {room}", control.name)))
+                });
+                Scored::new(&control.uuid, &control.name, room, by_name.max(by_room))
+            })
+            .collect();
+        decide(candidates, |uuid| model.get(uuid))
+    }
+}
+
+/// Pick the clear winner of the scored candidates, if there is one
+///
+/// When the best match only won through translation, candidates that match
+/// the query as spelled within [`AMBIGUITY_MARGIN`] of it make the query
+/// ambiguous. Otherwise a single best exact match wins, and the best match
+/// wins when no other candidate comes within [`AMBIGUITY_MARGIN`] of it.
+fn decide<T>(mut scored: Vec<Scored>, lookup: impl Fn(&str) -> Option<T>) -> Resolution<T> {
+    scored.retain(|s| s.candidate.score >= MIN_SCORE);
+    scored.sort_by(|a, b| {
+        b.candidate
+            .score
+            .total_cmp(&a.candidate.score)
+            .then_with(|| a.candidate.name.cmp(&b.candidate.name))
+            .then_with(|| a.candidate.uuid.cmp(&b.candidate.uuid))
+    });
+    let Some(best) = scored.first() else {
+        return Resolution::NotFound;
+    };
+
+    let translated_win = best.spelled >= MIN_SCORE && best.spelled < best.candidate.score;
+    let spelled_rival = |s: &Scored| {
+        translated_win && s.spelled >= MIN_SCORE && best.spelled - s.spelled < AMBIGUITY_MARGIN
+    };
+    let close = |s: &Scored| best.candidate.score - s.candidate.score < AMBIGUITY_MARGIN;
+    let rivals = scored[1..]
+        .iter()
+        .filter(|s| spelled_rival(s) && !close(s))
+        .count();
+
+    let contenders: Vec<Candidate> = scored
+        .iter()
+        .filter(|s| close(s) || spelled_rival(s))
+        .map(|s| s.candidate.clone())
+        .collect();
+    let exact = contenders
+        .iter()
+        .take_while(|candidate| candidate.score >= TRANSLATED_EXACT)
+        .count();
+    if contenders.len() == 1 || (exact == 1 && rivals == 0) {
+        return lookup(&best.candidate.uuid).map_or(Resolution::NotFound, Resolution::Found);
+    }
+    let mut candidates = contenders;
+    candidates.truncate(MAX_CANDIDATES);
+    Resolution::Ambiguous(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::LoxoneStructure;
+    use serde_json::json;
+
+    fn model() -> ControlModel {
+        let structure: LoxoneStructure = serde_json::from_value(json!({
+            "lastModified": "2024-01-01 00:00:00",
+            "rooms": {
+                "room-bath": { "name": "Badezimmer" },
+                "room-bath-og": { "name": "Badzimmer OG" },
+                "room-kitchen": { "name": "Küche" },
+                "room-living": { "name": "Wohnzimmer" },
+                "room-office": { "name": "Arbeitszimmer" }
+            },
+            "cats": {},
+            "controls": {
+                "light-kitchen": { "name": "Licht", "type": "Switch", "room": "room-kitchen" },
+                "light-bath": { "name": "Licht", "type": "Switch", "room": "room-bath" },
+                "blinds-living": { "name": "Jalousie Süd", "type": "Jalousie", "room": "room-living" },
+                "lamp-living": { "name": "Stehlampe", "type": "Switch", "room": "room-living" }
+            }
+        }))
+        .unwrap();
+        ControlModel::from_structure(&structure)
+    }
+
+    #[test]
+    fn test_normalize_and_translate() {
+        assert_eq!(normalize("Küche/Eßzimmer  OG"), "kueche esszimmer og");
+        assert_eq!(
+            translate(&normalize("Wohnzimmer Licht")),
+            "living room light"
+        );
+        assert_eq!(edit_distance("kitchen", "kichten"), 2);
+        assert!(score("Kuche", "Küche").overall >= MIN_SCORE);
+        assert_eq!(score("living room", "Wohnzimmer").overall, TRANSLATED_EXACT);
+    }
+
+    #[test]
+    fn test_rooms_resolve_deterministically() {
+        let model = model();
+        let resolver = NameResolver::default();
+        let room = |query| resolver.resolve_room(&model, query);
+
+        assert_eq!(room("bathroom"), Resolution::Found("room-bath"));
+        assert_eq!(room("kitchen"), Resolution::Found("room-kitchen"));
+        assert_eq!(room("kueche"), Resolution::Found("room-kitchen"));
+        assert_eq!(room("Wohnzimmr"), Resolution::Found("room-living"));
+        assert_eq!(room("room-office"), Resolution::Found("room-office"));
+        assert_eq!(room("Garage"), Resolution::NotFound);
+
+        let Resolution::Ambiguous(candidates) = room("zimmer") else {
+            panic!("'zimmer' should be ambiguous");
+        };
+        assert!(candidates.len() >= 2);
+        assert!(candidates.iter().any(|c| c.uuid == "room-bath-og"));
+
+        // "Bad" translates to the bathroom, but prefixes both bathrooms as spelled
+        let Resolution::Ambiguous(candidates) = room("Bad") else {
+            panic!("'Bad' should be ambiguous");
+        };
+        let uuids: Vec<_> = candidates.iter().map(|c| c.uuid.as_str()).collect();
+        assert_eq!(uuids, ["room-bath", "room-bath-og"]);
+    }
+
+    #[test]
+    fn test_controls_resolve_with_rooms_and_aliases() {
+        let model = model();
+        let resolver = NameResolver::new(&HashMap::from([(
+            "Leselampe".to_string(),
+            "Stehlampe".to_string(),
+        )]));
+        let control = |query| {
+            resolver
+                .resolve_control(&model, query)
+                .found()
+                .map(|control| control.uuid.as_str())
+        };
+
+        assert_eq!(control("Küche Licht"), Some("light-kitchen"));
+        assert_eq!(control("kitchen light"), Some("light-kitchen"));
+        assert_eq!(control("Licht Bad"), Some("light-bath"));
+        assert_eq!(control("blinds"), Some("blinds-living"));
+        assert_eq!(control("floor lamp"), Some("lamp-living"));
+        assert_eq!(control("leselampe"), Some("lamp-living"));
+
+        let Resolution::Ambiguous(candidates) = resolver.resolve_control(&model, "Licht") else {
+            panic!("'Licht' exists in two rooms");
+        };
+        let rooms: Vec<_> = candidates
+            .iter()
+            .filter_map(|c| c.room.as_deref())
+            .collect();
+        assert_eq!(rooms, ["Badezimmer", "Küche"]);
+        let message = ambiguity_message("Device", "Licht", &candidates);
+        assert!(message.contains("light-kitchen"), "{message}");
+    }
+}
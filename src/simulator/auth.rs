@@ -0,0 +1,231 @@
+//! Simulated Miniserver authentication
+//!
+//! Implements the token handshake the way the Miniserver verifies it:
+//! `getkey2` hands out a key and user salt, `getjwt` expects
+//! `HMAC(key, "{user}:{SHA256(password:salt)}")`, and `refreshjwt`,
+//! `killtoken` and `authwithtoken` expect `HMAC(key, token)`. Session keys of
+//! the command encryption are RSA encrypted with the key from `getPublicKey`.
+
+use crate::client::binary_protocol::LOXONE_EPOCH_UNIX;
+use crate::error::{LoxoneError, Result};
+use crate::security::encryption::CommandCipher;
+use base64::Engine;
+use openssl::hash::{MessageDigest, hash};
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::{Padding, Rsa};
+use openssl::sign::Signer;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Token issued by the simulator
+#[derive(Debug, Clone)]
+struct IssuedToken {
+    username: String,
+    /// Expiry in seconds since the Loxone epoch
+    valid_until: i64,
+    rights: i64,
+}
+
+/// Credentials, keys and issued tokens of the simulated Miniserver
+pub struct SimulatedAuth {
+    username: String,
+    password: String,
+    /// Hex key handed out by `getkey2` and `getkey`
+    key: String,
+    /// Hex salt of the configured user
+    salt: String,
+    token_lifetime: Duration,
+    /// RSA key pair of `getPublicKey`, generated on first use
+    rsa: Option<Rsa<Private>>,
+    tokens: HashMap<String, IssuedToken>,
+}
+
+impl SimulatedAuth {
+    /// Create the authentication state for a single user
+    pub fn new(username: &str, password: &str, token_lifetime: Duration) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+            key: hex::encode(rand::random::<[u8; 32]>()),
+            salt: hex::encode(rand::random::<[u8; 16]>()),
+            token_lifetime,
+            rsa: None,
+            tokens: HashMap::new(),
+        }
+    }
+
+    /// Check HTTP basic credentials
+    pub fn check_password(&self, username: &str, password: &str) -> bool {
+        username == self.username && password == self.password
+    }
+
+    /// Check an `autht` token for the given user
+    pub fn check_token(&self, token: &str, username: &str) -> bool {
+        self.tokens
+            .get(token)
+            .is_some_and(|issued| issued.username == username && issued.valid_until > now())
+    }
+
+    /// Number of tokens that have not been killed
+    pub fn active_tokens(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// PEM public key served by `jdev/sys/getPublicKey`, generated on first use
+    pub fn public_key(&mut self) -> Result<String> {
+        let pem = self
+            .rsa()?
+            .public_key_to_pem()
+            .map_err(|e| LoxoneError::crypto(format!("Failed to encode public key: {e}")))?;
+        String::from_utf8(pem)
+            .map_err(|e| LoxoneError::crypto(format!("Invalid PEM encoding: {e}")))
+    }
+
+    /// Decrypt the base64 session key of `jdev/sys/keyexchange` or `?sk=`
+    pub fn session_cipher(&mut self, session_key: &str) -> Result<CommandCipher> {
+        let encrypted = base64::engine::general_purpose::STANDARD
+            .decode(session_key.trim())
+            .map_err(|e| LoxoneError::crypto(format!("Invalid session key encoding: {e}")))?;
+        let rsa = self.rsa()?;
+        let mut decrypted = vec![0; rsa.size() as usize];
+        let len = rsa
+            .private_decrypt(&encrypted, &mut decrypted, Padding::PKCS1)
+            .map_err(|e| LoxoneError::crypto(format!("Failed to decrypt session key: {e}")))?;
+        let session_key = String::from_utf8_lossy(&decrypted[..len]);
+        CommandCipher::from_session_key(&session_key)
+            .map_err(|e| LoxoneError::crypto(e.to_string()))
+    }
+
+    fn rsa(&mut self) -> Result<&Rsa<Private>> {
+        if self.rsa.is_none() {
+            let rsa = Rsa::generate(2048)
+                .map_err(|e| LoxoneError::crypto(format!("Failed to generate RSA key: {e}")))?;
+            self.rsa = Some(rsa);
+        }
+        self.rsa
+            .as_ref()
+            .ok_or_else(|| LoxoneError::crypto("RSA key unavailable"))
+    }
+
+    /// Response to `jdev/sys/getkey2/{user}`
+    pub fn getkey2(&self) -> Value {
+        json!({
+            "key": self.key,
+            "salt": self.salt,
+            "hashAlg": "SHA256"
+        })
+    }
+
+    /// Response to `jdev/sys/getkey`
+    pub fn getkey(&self) -> Value {
+        json!(self.key)
+    }
+
+    /// Verify a `getjwt` hash and issue a token
+    pub fn issue_token(&mut self, hash: &str, username: &str, permission: &str) -> Result<Value> {
+        if username != self.username {
+            return Err(LoxoneError::authentication(format!(
+                "Unknown user: {username}"
+            )));
+        }
+
+        let password_hash = hex::encode_upper(sha256(
+            format!("{}:{}", self.password, self.salt).as_bytes(),
+        )?);
+        let expected = self.hmac(format!("{username}:{password_hash}").as_bytes())?;
+        if !expected.eq_ignore_ascii_case(hash) {
+            return Err(LoxoneError::authentication("Invalid credentials hash"));
+        }
+
+        let rights = permission.parse::<i64>().unwrap_or(4);
+        Ok(self.insert_token(username, rights))
+    }
+
+    /// Verify a `refreshjwt` hash and replace the token with a new one
+    pub fn refresh_token(&mut self, hash: &str, username: &str) -> Result<Value> {
+        let token = self.find_token(hash, username)?;
+        let issued = self
+            .tokens
+            .remove(&token)
+            .ok_or_else(|| LoxoneError::authentication("Unknown token"))?;
+        Ok(self.insert_token(username, issued.rights))
+    }
+
+    /// Verify a `killtoken` hash and revoke the token
+    pub fn kill_token(&mut self, hash: &str, username: &str) -> Result<()> {
+        let token = self.find_token(hash, username)?;
+        self.tokens.remove(&token);
+        Ok(())
+    }
+
+    /// Verify an `authwithtoken` hash
+    pub fn auth_with_token(&self, hash: &str, username: &str) -> Result<Value> {
+        let token = self.find_token(hash, username)?;
+        let issued = &self.tokens[&token];
+        Ok(json!({
+            "validUntil": issued.valid_until,
+            "tokenRights": issued.rights,
+            "unsecurePass": false
+        }))
+    }
+
+    fn insert_token(&mut self, username: &str, rights: i64) -> Value {
+        let token = hex::encode(rand::random::<[u8; 24]>());
+        let valid_until = now() + self.token_lifetime.as_secs() as i64;
+        self.tokens.insert(
+            token.clone(),
+            IssuedToken {
+                username: username.to_string(),
+                valid_until,
+                rights,
+            },
+        );
+        json!({
+            "token": token,
+            "validUntil": valid_until,
+            "tokenRights": rights,
+            "unsecurePass": false,
+            "key": self.key
+        })
+    }
+
+    fn find_token(&self, hash: &str, username: &str) -> Result<String> {
+        self.tokens
+            .iter()
+            .filter(|(_, issued)| issued.username == username && issued.valid_until > now())
+            .find(|(token, _)| {
+                self.hmac(token.as_bytes())
+                    .is_ok_and(|expected| expected.eq_ignore_ascii_case(hash))
+            })
+            .map(|(token, _)| token.clone())
+            .ok_or_else(|| LoxoneError::authentication("Invalid token hash"))
+    }
+
+    fn hmac(&self, data: &[u8]) -> Result<String> {
+        let key =
+            hex::decode(&self.key).map_err(|e| LoxoneError::crypto(format!("Invalid key: {e}")))?;
+        let key = PKey::hmac(&key)
+            .map_err(|e| LoxoneError::crypto(format!("Failed to create HMAC key: {e}")))?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &key)
+            .map_err(|e| LoxoneError::crypto(format!("Failed to create signer: {e}")))?;
+        signer
+            .update(data)
+            .map_err(|e| LoxoneError::crypto(format!("Failed to update signer: {e}")))?;
+        let mac = signer
+            .sign_to_vec()
+            .map_err(|e| LoxoneError::crypto(format!("Failed to sign: {e}")))?;
+        Ok(hex::encode(mac))
+    }
+}
+
+fn sha256(data: &[u8]) -> Result<Vec<u8>> {
+    hash(MessageDigest::sha256(), data)
+        .map(|digest| digest.to_vec())
+        .map_err(|e| LoxoneError::crypto(format!("Failed to hash: {e}")))
+}
+
+/// Current time in seconds since the Loxone epoch
+fn now() -> i64 {
+    chrono::Utc::now().timestamp() - LOXONE_EPOCH_UNIX
+}
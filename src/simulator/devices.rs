@@ -0,0 +1,733 @@
+//! Simulated control states and physics
+//!
+//! Every control in the structure fixture is mapped onto a small behaviour
+//! model. Commands change states immediately (switches, dimmers) or set a
+//! target the physics tick moves towards (blinds, room temperatures).
+
+use crate::client::binary_protocol::ValueEvent;
+use crate::error::{LoxoneError, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Physical constants of the simulated home
+#[derive(Debug, Clone)]
+pub struct PhysicsSettings {
+    /// Time a blind needs to travel from fully up to fully down
+    pub blind_travel_time: Duration,
+    /// Room temperature change towards the target, in °C per minute
+    pub temperature_rate: f64,
+    /// Initial actual temperature of every room controller
+    pub initial_temperature: f64,
+    /// Initial target temperature of every room controller
+    pub initial_target_temperature: f64,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            blind_travel_time: Duration::from_secs(20),
+            temperature_rate: 0.1,
+            initial_temperature: 20.5,
+            initial_target_temperature: 21.5,
+        }
+    }
+}
+
+/// Behaviour model of a control type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlKind {
+    /// On/off outputs (`Switch`, `Pushbutton`, `TimedSwitch`)
+    Switch,
+    /// Dimmable outputs (`Dimmer`, `EIBDimmer`)
+    Dimmer,
+    /// Blinds and shutters (`Jalousie`)
+    Jalousie,
+    /// Room temperature controllers (`IRoomController`, `IRoomControllerV2`)
+    RoomController,
+    /// Audio Server zones (`AudioZoneV2`)
+    AudioZone,
+    /// Read-only inputs (`InfoOnlyAnalog`, `InfoOnlyDigital`)
+    Sensor,
+    /// Everything else: simple value store on the first state
+    Generic,
+}
+
+impl ControlKind {
+    /// Map a Loxone control type onto its behaviour model
+    pub fn from_control_type(control_type: &str) -> Self {
+        match control_type {
+            "Switch" | "Pushbutton" | "TimedSwitch" => Self::Switch,
+            "Dimmer" | "EIBDimmer" => Self::Dimmer,
+            "Jalousie" => Self::Jalousie,
+            "IRoomController" | "IRoomControllerV2" => Self::RoomController,
+            "AudioZoneV2" => Self::AudioZone,
+            "InfoOnlyAnalog" | "InfoOnlyDigital" => Self::Sensor,
+            _ => Self::Generic,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SimulatedControl {
+    kind: ControlKind,
+    /// State name -> state UUID
+    states: HashMap<String, String>,
+    /// Primary state reported for `jdev/sps/io/{uuid}/state`
+    primary_state: Option<String>,
+    /// Blind target position (0 = up, 1 = down) while moving
+    target_position: Option<f64>,
+    /// Dimmer level restored by `on`
+    last_level: f64,
+}
+
+/// State of every simulated control
+#[derive(Debug, Clone)]
+pub struct SimulatedHome {
+    controls: HashMap<String, SimulatedControl>,
+    /// State UUID -> current value
+    values: HashMap<String, f64>,
+    settings: PhysicsSettings,
+}
+
+impl SimulatedHome {
+    /// Build the simulated home from a `LoxAPP3.json` structure
+    pub fn from_structure(structure: &Value, settings: PhysicsSettings) -> Self {
+        let mut home = Self {
+            controls: HashMap::new(),
+            values: HashMap::new(),
+            settings,
+        };
+
+        if let Some(controls) = structure.get("controls").and_then(Value::as_object) {
+            for (uuid, control) in controls {
+                home.add_control(uuid, control);
+            }
+        }
+        if let Some(global_states) = structure.get("globalStates").and_then(Value::as_object) {
+            for state_uuid in global_states.values().filter_map(Value::as_str) {
+                home.values.insert(state_uuid.to_string(), 0.0);
+            }
+        }
+
+        home
+    }
+
+    fn add_control(&mut self, uuid: &str, control: &Value) {
+        let control_type = control.get("type").and_then(Value::as_str).unwrap_or("");
+        let kind = ControlKind::from_control_type(control_type);
+        let states: HashMap<String, String> = control
+            .get("states")
+            .and_then(Value::as_object)
+            .map(|states| {
+                states
+                    .iter()
+                    .filter_map(|(name, state)| Some((name.clone(), state.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let primary_state = match kind {
+            ControlKind::Switch => Some("active"),
+            ControlKind::Dimmer | ControlKind::Jalousie => Some("position"),
+            ControlKind::RoomController => Some("tempActual"),
+            ControlKind::AudioZone => Some("playState"),
+            ControlKind::Sensor | ControlKind::Generic => ["value", "active", "position"]
+                .into_iter()
+                .find(|name| states.contains_key(*name)),
+        }
+        .filter(|name| states.contains_key(*name))
+        .map(str::to_string)
+        .or_else(|| {
+            let mut names: Vec<&String> = states.keys().collect();
+            names.sort();
+            names.first().map(|name| (*name).clone())
+        });
+
+        for (name, state_uuid) in &states {
+            let initial = match (kind, name.as_str()) {
+                (ControlKind::Dimmer, "max") => 100.0,
+                (ControlKind::Dimmer, "step") => 1.0,
+                (ControlKind::Jalousie, "autoAllowed") => 1.0,
+                (ControlKind::RoomController, "tempActual") => self.settings.initial_temperature,
+                (ControlKind::RoomController, "tempTarget" | "comfortTemperature") => {
+                    self.settings.initial_target_temperature
+                }
+                (ControlKind::AudioZone, "volume") => 30.0,
+                (ControlKind::AudioZone, "volumeStep") => 5.0,
+                _ => 0.0,
+            };
+            self.values.insert(state_uuid.clone(), initial);
+        }
+
+        self.controls.insert(
+            uuid.to_string(),
+            SimulatedControl {
+                kind,
+                states,
+                primary_state,
+                target_position: None,
+                last_level: 100.0,
+            },
+        );
+
+        // Sub-controls (e.g. light circuits of a LightControllerV2) are
+        // addressable controls of their own
+        if let Some(sub_controls) = control.get("subControls").and_then(Value::as_object) {
+            for (sub_uuid, sub_control) in sub_controls {
+                self.add_control(sub_uuid, sub_control);
+            }
+        }
+    }
+
+    /// Number of simulated controls, including sub-controls
+    pub fn control_count(&self) -> usize {
+        self.controls.len()
+    }
+
+    /// Current value of a state UUID
+    pub fn value(&self, state_uuid: &str) -> Option<f64> {
+        self.values.get(state_uuid).copied()
+    }
+
+    /// Current value of a named control state
+    pub fn control_value(&self, control_uuid: &str, state: &str) -> Option<f64> {
+        let state_uuid = self.controls.get(control_uuid)?.states.get(state)?;
+        self.value(state_uuid)
+    }
+
+    /// Value reported for `jdev/sps/io/{uuid}` without a command
+    ///
+    /// Accepts both control UUIDs (primary state) and state UUIDs.
+    pub fn current_value(&self, uuid: &str) -> Option<f64> {
+        if let Some(value) = self.value(uuid) {
+            return Some(value);
+        }
+        let control = self.controls.get(uuid)?;
+        control
+            .primary_state
+            .as_ref()
+            .and_then(|state| control.states.get(state))
+            .and_then(|state_uuid| self.value(state_uuid))
+    }
+
+    /// Snapshot of every state value, as sent after `enablebinstatusupdate`
+    pub fn all_values(&self) -> Vec<ValueEvent> {
+        let mut events: Vec<ValueEvent> = self
+            .values
+            .iter()
+            .map(|(uuid, value)| ValueEvent {
+                uuid: uuid.clone(),
+                value: *value,
+            })
+            .collect();
+        events.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+        events
+    }
+
+    /// Overwrite a state value directly (e.g. a sensor reading)
+    pub fn set_value(&mut self, state_uuid: &str, value: f64) -> Result<Vec<ValueEvent>> {
+        if !self.values.contains_key(state_uuid) {
+            return Err(LoxoneError::not_found(format!(
+                "Unknown state UUID: {state_uuid}"
+            )));
+        }
+        let mut changes = Vec::new();
+        self.store(state_uuid, value, &mut changes);
+        Ok(changes)
+    }
+
+    /// Apply a `jdev/sps/io/{uuid}/{command}` command
+    ///
+    /// Returns the control's primary value after the command together with
+    /// the state changes it caused.
+    pub fn apply_command(&mut self, uuid: &str, command: &str) -> Result<(f64, Vec<ValueEvent>)> {
+        let Some(control) = self.controls.get(uuid).cloned() else {
+            if self.values.contains_key(uuid) && matches!(command, "" | "state") {
+                return Ok((self.values[uuid], Vec::new()));
+            }
+            return Err(LoxoneError::not_found(format!(
+                "Unknown control UUID: {uuid}"
+            )));
+        };
+
+        let mut changes = Vec::new();
+        if !matches!(command, "" | "state") {
+            let (name, argument) = match command.split_once('/') {
+                Some((name, argument)) => (name, Some(argument)),
+                None => (command, None),
+            };
+            match control.kind {
+                ControlKind::Switch => self.switch_command(&control, name, &mut changes)?,
+                ControlKind::Dimmer => self.dimmer_command(uuid, &control, name, &mut changes)?,
+                ControlKind::Jalousie => {
+                    self.jalousie_command(uuid, &control, name, argument, &mut changes)?
+                }
+                ControlKind::RoomController => {
+                    self.room_controller_command(&control, name, argument, &mut changes)?
+                }
+                ControlKind::AudioZone => {
+                    self.audio_zone_command(&control, name, argument, &mut changes)?
+                }
+                ControlKind::Sensor => {
+                    return Err(LoxoneError::invalid_input(format!(
+                        "Control {uuid} is read-only"
+                    )));
+                }
+                ControlKind::Generic => self.generic_command(&control, name, &mut changes),
+            }
+        }
+
+        let value = self.current_value(uuid).unwrap_or_default();
+        Ok((value, changes))
+    }
+
+    /// Advance the physics by `elapsed` simulated time
+    pub fn tick(&mut self, elapsed: Duration) -> Vec<ValueEvent> {
+        let mut changes = Vec::new();
+        let seconds = elapsed.as_secs_f64();
+        let travel = self
+            .settings
+            .blind_travel_time
+            .as_secs_f64()
+            .max(f64::EPSILON);
+        let temperature_step = self.settings.temperature_rate * seconds / 60.0;
+
+        let uuids: Vec<String> = self.controls.keys().cloned().collect();
+        for uuid in uuids {
+            let control = self.controls[&uuid].clone();
+            match control.kind {
+                ControlKind::Jalousie => {
+                    let Some(target) = control.target_position else {
+                        continue;
+                    };
+                    let current = self.state(&control, "position");
+                    let next = approach(current, target, seconds / travel);
+                    self.set_state(&control, "position", next, &mut changes);
+                    if (next - target).abs() < f64::EPSILON {
+                        self.stop_blind(&uuid, &control, &mut changes);
+                    }
+                }
+                ControlKind::RoomController => {
+                    let target = self.state(&control, "tempTarget");
+                    let current = self.state(&control, "tempActual");
+                    let next = approach(current, target, temperature_step);
+                    self.set_state(&control, "tempActual", round(next, 100.0), &mut changes);
+                }
+                _ => {}
+            }
+        }
+
+        changes
+    }
+
+    fn switch_command(
+        &mut self,
+        control: &SimulatedControl,
+        name: &str,
+        changes: &mut Vec<ValueEvent>,
+    ) -> Result<()> {
+        let active = match name {
+            "on" => 1.0,
+            "off" => 0.0,
+            "pulse" => 1.0 - self.state(control, "active"),
+            other => bool_value(other)?,
+        };
+        self.set_state(control, "active", active, changes);
+        Ok(())
+    }
+
+    fn dimmer_command(
+        &mut self,
+        uuid: &str,
+        control: &SimulatedControl,
+        name: &str,
+        changes: &mut Vec<ValueEvent>,
+    ) -> Result<()> {
+        let min = self.state(control, "min");
+        let max = self.state(control, "max").max(min);
+        let step = self.state(control, "step").max(1.0);
+        let current = self.state(control, "position");
+
+        let level = match name {
+            "on" => control.last_level,
+            "off" => 0.0,
+            "plus" => current + step,
+            "minus" => current - step,
+            other => other.parse::<f64>().map_err(|_| {
+                LoxoneError::invalid_input(format!("Invalid dimmer command: {other}"))
+            })?,
+        }
+        .clamp(min, max);
+
+        if level > 0.0
+            && let Some(control) = self.controls.get_mut(uuid)
+        {
+            control.last_level = level;
+        }
+        self.set_state(control, "position", level, changes);
+        Ok(())
+    }
+
+    fn jalousie_command(
+        &mut self,
+        uuid: &str,
+        control: &SimulatedControl,
+        name: &str,
+        argument: Option<&str>,
+        changes: &mut Vec<ValueEvent>,
+    ) -> Result<()> {
+        let target = match name {
+            "up" | "FullUp" => Some(0.0),
+            "down" | "FullDown" | "shade" | "Shade" => Some(1.0),
+            "UpOff" | "DownOff" | "stop" | "Stop" => None,
+            "ManualPosition" => Some(percent_argument(name, argument)?),
+            "ManualLamelle" => {
+                let shade = percent_argument(name, argument)?;
+                self.set_state(control, "shadePosition", shade, changes);
+                return Ok(());
+            }
+            other => {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Invalid jalousie command: {other}"
+                )));
+            }
+        };
+
+        let Some(target) = target else {
+            self.stop_blind(uuid, control, changes);
+            return Ok(());
+        };
+
+        let current = self.state(control, "position");
+        self.set_state(control, "up", f64::from(target < current), changes);
+        self.set_state(control, "down", f64::from(target > current), changes);
+        if let Some(control) = self.controls.get_mut(uuid) {
+            control.target_position = Some(target);
+        }
+        Ok(())
+    }
+
+    fn stop_blind(
+        &mut self,
+        uuid: &str,
+        control: &SimulatedControl,
+        changes: &mut Vec<ValueEvent>,
+    ) {
+        self.set_state(control, "up", 0.0, changes);
+        self.set_state(control, "down", 0.0, changes);
+        if let Some(control) = self.controls.get_mut(uuid) {
+            control.target_position = None;
+        }
+    }
+
+    fn room_controller_command(
+        &mut self,
+        control: &SimulatedControl,
+        name: &str,
+        argument: Option<&str>,
+        changes: &mut Vec<ValueEvent>,
+    ) -> Result<()> {
+        // The value is the last argument (`settemp/{index}/{temperature}`)
+        let value = || {
+            argument
+                .and_then(|argument| argument.rsplit('/').next())
+                .and_then(|argument| argument.parse::<f64>().ok())
+                .ok_or_else(|| {
+                    LoxoneError::invalid_input(format!("Command {name} needs a numeric argument"))
+                })
+        };
+
+        match name {
+            "settemp" | "setManualTemperature" => {
+                self.set_state(control, "tempTarget", value()?, changes);
+            }
+            "setComfortTemperature" => {
+                let comfort = value()?;
+                self.set_state(control, "comfortTemperature", comfort, changes);
+                self.set_state(control, "tempTarget", comfort, changes);
+            }
+            "setComfortTemperatureCool" => {
+                self.set_state(control, "comfortTemperatureCool", value()?, changes);
+            }
+            "setComfortTolerance" => {
+                self.set_state(control, "comfortTolerance", value()?, changes);
+            }
+            "setmode" | "setOperatingMode" => {
+                self.set_state(control, "operatingMode", value()?, changes);
+            }
+            // `override/{mode}/{until}[/{temperature}]`
+            "override" => {
+                let args: Vec<f64> = argument
+                    .unwrap_or_default()
+                    .split('/')
+                    .filter_map(|arg| arg.parse().ok())
+                    .collect();
+                let (Some(&mode), Some(_until)) = (args.first(), args.get(1)) else {
+                    return Err(LoxoneError::invalid_input(
+                        "Command override needs a mode and an end time",
+                    ));
+                };
+                self.set_state(control, "activeMode", mode, changes);
+                if let Some(&temperature) = args.get(2) {
+                    self.set_state(control, "tempTarget", temperature, changes);
+                }
+            }
+            "stopOverride" => {
+                let comfort = self.state(control, "comfortTemperature");
+                self.set_state(control, "activeMode", 1.0, changes);
+                self.set_state(control, "tempTarget", comfort, changes);
+            }
+            other => {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Invalid room controller command: {other}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn audio_zone_command(
+        &mut self,
+        control: &SimulatedControl,
+        name: &str,
+        argument: Option<&str>,
+        changes: &mut Vec<ValueEvent>,
+    ) -> Result<()> {
+        let value = || {
+            argument
+                .and_then(|argument| argument.parse::<f64>().ok())
+                .ok_or_else(|| {
+                    LoxoneError::invalid_input(format!("Command {name} needs a numeric argument"))
+                })
+        };
+        let step = self.state(control, "volumeStep").max(1.0);
+        let volume = self.state(control, "volume");
+
+        match name {
+            "on" => self.set_state(control, "power", 1.0, changes),
+            "off" => {
+                self.set_state(control, "power", 0.0, changes);
+                self.set_state(control, "playState", 0.0, changes);
+            }
+            "play" | "resume" => {
+                self.set_state(control, "power", 1.0, changes);
+                self.set_state(control, "playState", 2.0, changes);
+            }
+            "pause" => self.set_state(control, "playState", 1.0, changes),
+            "stop" => self.set_state(control, "playState", 0.0, changes),
+            "volume" => {
+                self.set_state(control, "volume", value()?.clamp(0.0, 100.0), changes);
+            }
+            "volUp" | "volup" => {
+                self.set_state(control, "volume", (volume + step).min(100.0), changes);
+            }
+            "volDown" | "voldown" => {
+                self.set_state(control, "volume", (volume - step).max(0.0), changes);
+            }
+            "shuffle" => self.set_state(control, "shuffle", f64::from(value()? != 0.0), changes),
+            "repeat" => self.set_state(control, "repeat", value()?, changes),
+            "source" | "playZoneFav" | "playFav" => {
+                self.set_state(control, "source", value()?, changes);
+                self.set_state(control, "power", 1.0, changes);
+                self.set_state(control, "playState", 2.0, changes);
+            }
+            // Numeric states only hold one player: the zone followed in the group
+            "syncWith" => self.set_state(control, "syncedZones", value()?, changes),
+            "unsync" => self.set_state(control, "syncedZones", 0.0, changes),
+            // Announcements, favorites and queue reads are accepted without effect
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn generic_command(
+        &mut self,
+        control: &SimulatedControl,
+        name: &str,
+        changes: &mut Vec<ValueEvent>,
+    ) {
+        let Some(primary) = control.primary_state.clone() else {
+            return;
+        };
+        let value = match name {
+            "on" => 1.0,
+            "off" => 0.0,
+            "pulse" => 1.0 - self.state(control, &primary),
+            other => match other.parse::<f64>() {
+                Ok(value) => value,
+                // Commands we do not model are accepted without effect
+                Err(_) => return,
+            },
+        };
+        self.set_state(control, &primary, value, changes);
+    }
+
+    fn state(&self, control: &SimulatedControl, name: &str) -> f64 {
+        control
+            .states
+            .get(name)
+            .and_then(|uuid| self.value(uuid))
+            .unwrap_or_default()
+    }
+
+    fn set_state(
+        &mut self,
+        control: &SimulatedControl,
+        name: &str,
+        value: f64,
+        changes: &mut Vec<ValueEvent>,
+    ) {
+        if let Some(state_uuid) = control.states.get(name) {
+            self.store(&state_uuid.clone(), value, changes);
+        }
+    }
+
+    fn store(&mut self, state_uuid: &str, value: f64, changes: &mut Vec<ValueEvent>) {
+        let previous = self.values.insert(state_uuid.to_string(), value);
+        if previous != Some(value) {
+            changes.push(ValueEvent {
+                uuid: state_uuid.to_string(),
+                value,
+            });
+        }
+    }
+}
+
+/// Move `current` towards `target` by at most `step`
+fn approach(current: f64, target: f64, step: f64) -> f64 {
+    if (target - current).abs() <= step {
+        target
+    } else if target > current {
+        current + step
+    } else {
+        current - step
+    }
+}
+
+fn round(value: f64, factor: f64) -> f64 {
+    (value * factor).round() / factor
+}
+
+fn bool_value(command: &str) -> Result<f64> {
+    command
+        .parse::<f64>()
+        .map(|value| f64::from(value != 0.0))
+        .map_err(|_| LoxoneError::invalid_input(format!("Invalid switch command: {command}")))
+}
+
+fn percent_argument(name: &str, argument: Option<&str>) -> Result<f64> {
+    argument
+        .and_then(|argument| argument.parse::<f64>().ok())
+        .map(|percent| (percent / 100.0).clamp(0.0, 1.0))
+        .ok_or_else(|| LoxoneError::invalid_input(format!("{name} needs a percentage")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DIMMER: &str = "0b734138-037d-034e-ffff000000000001";
+    const BLIND: &str = "0b734138-037d-034e-ffff000000000002";
+    const CLIMATE: &str = "0b734138-037d-034e-ffff000000000003";
+    const SENSOR: &str = "0b734138-037d-034e-ffff000000000004";
+
+    fn home() -> SimulatedHome {
+        let structure = json!({
+            "controls": {
+                DIMMER: {
+                    "type": "Dimmer",
+                    "states": {
+                        "position": "0b734138-037d-034e-ffff000000000011",
+                        "min": "0b734138-037d-034e-ffff000000000012",
+                        "max": "0b734138-037d-034e-ffff000000000013",
+                        "step": "0b734138-037d-034e-ffff000000000014"
+                    }
+                },
+                BLIND: {
+                    "type": "Jalousie",
+                    "states": {
+                        "up": "0b734138-037d-034e-ffff000000000021",
+                        "down": "0b734138-037d-034e-ffff000000000022",
+                        "position": "0b734138-037d-034e-ffff000000000023"
+                    }
+                },
+                CLIMATE: {
+                    "type": "IRoomControllerV2",
+                    "states": {
+                        "tempActual": "0b734138-037d-034e-ffff000000000031",
+                        "tempTarget": "0b734138-037d-034e-ffff000000000032"
+                    }
+                },
+                SENSOR: {
+                    "type": "InfoOnlyAnalog",
+                    "states": { "value": "0b734138-037d-034e-ffff000000000041" }
+                }
+            }
+        });
+        SimulatedHome::from_structure(&structure, PhysicsSettings::default())
+    }
+
+    #[test]
+    fn test_dimmer_restores_last_level() {
+        let mut home = home();
+
+        let (value, changes) = home.apply_command(DIMMER, "40").unwrap();
+        assert_eq!(value, 40.0);
+        assert_eq!(changes.len(), 1);
+
+        home.apply_command(DIMMER, "off").unwrap();
+        assert_eq!(home.current_value(DIMMER), Some(0.0));
+        assert_eq!(home.apply_command(DIMMER, "on").unwrap().0, 40.0);
+        assert_eq!(home.apply_command(DIMMER, "250").unwrap().0, 100.0);
+        assert!(home.apply_command(DIMMER, "bright").is_err());
+    }
+
+    #[test]
+    fn test_blind_moves_over_time() {
+        let mut home = home();
+
+        home.apply_command(BLIND, "FullDown").unwrap();
+        assert_eq!(home.control_value(BLIND, "down"), Some(1.0));
+        assert_eq!(home.current_value(BLIND), Some(0.0));
+
+        home.tick(Duration::from_secs(10));
+        assert_eq!(home.current_value(BLIND), Some(0.5));
+
+        home.tick(Duration::from_secs(15));
+        assert_eq!(home.current_value(BLIND), Some(1.0));
+        assert_eq!(home.control_value(BLIND, "down"), Some(0.0));
+
+        home.apply_command(BLIND, "ManualPosition/25").unwrap();
+        assert_eq!(home.control_value(BLIND, "up"), Some(1.0));
+        home.tick(Duration::from_secs(60));
+        assert_eq!(home.current_value(BLIND), Some(0.25));
+    }
+
+    #[test]
+    fn test_room_temperature_drifts_to_target() {
+        let mut home = home();
+
+        home.apply_command(CLIMATE, "setManualTemperature/22.5")
+            .unwrap();
+        let changes = home.tick(Duration::from_secs(60));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(home.current_value(CLIMATE), Some(20.6));
+
+        home.tick(Duration::from_secs(3600));
+        assert_eq!(home.current_value(CLIMATE), Some(22.5));
+        assert!(home.tick(Duration::from_secs(60)).is_empty());
+    }
+
+    #[test]
+    fn test_sensors_are_read_only() {
+        let mut home = home();
+
+        assert!(home.apply_command(SENSOR, "on").is_err());
+        home.set_value("0b734138-037d-034e-ffff000000000041", 3.5)
+            .unwrap();
+        assert_eq!(home.apply_command(SENSOR, "state").unwrap().0, 3.5);
+        assert!(home.apply_command("unknown", "on").is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Local Miniserver simulator for offline development and CI
+//!
+//! Serves a structure fixture the way a Miniserver does: `/data/LoxAPP3.json`,
+//! `jdev/sps/io/{uuid}/{cmd}`, the `getkey2`/`getjwt` token handshake, the
+//! `/stats` statistics files and the `/ws/rfc6455` WebSocket with binary value,
+//! text and daytimer event tables. Commands mutate the
+//! simulated state and a physics tick moves blinds and room temperatures
+//! towards their targets, so clients observe realistic state streams.
+//!
+//! Unlike a real Miniserver, failed commands are answered with the matching
+//! HTTP status in addition to the `Code` field of the `LL` response.
+
+pub mod auth;
+pub mod devices;
+mod server;
+mod statistics;
+
+pub use devices::{ControlKind, PhysicsSettings, SimulatedHome};
+
+use crate::client::binary_protocol::{
+    DaytimerEvent, EventTable, TextEvent, ValueEvent, parse_uuid,
+};
+use crate::client::statistics::StatisticEntry;
+use crate::error::{LoxoneError, Result};
+use auth::SimulatedAuth;
+use chrono::Utc;
+use serde_json::Value;
+use statistics::StatisticsRecorder;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, broadcast};
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+/// Structure fixture used when no `--structure` file is given
+pub const DEFAULT_STRUCTURE: &str = include_str!("LoxAPP3.json");
+
+/// Firmware version reported by `jdev/cfg/apiversion`
+pub const SIMULATED_FIRMWARE_VERSION: &str = "14.5.12.7";
+
+/// Simulator configuration
+#[derive(Debug, Clone)]
+pub struct SimulatorConfig {
+    /// Username accepted by basic and token authentication
+    pub username: String,
+    /// Password of [`SimulatorConfig::username`]
+    pub password: String,
+    /// Interval of the physics tick
+    pub tick_interval: Duration,
+    /// Simulated seconds per wall-clock second
+    pub time_scale: f64,
+    /// Lifetime of tokens issued by `getjwt`
+    pub token_lifetime: Duration,
+    /// Physical constants of the simulated home
+    pub physics: PhysicsSettings,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            username: "admin".to_string(),
+            password: "admin".to_string(),
+            tick_interval: Duration::from_millis(250),
+            time_scale: 1.0,
+            token_lifetime: Duration::from_secs(30 * 24 * 3600),
+            physics: PhysicsSettings::default(),
+        }
+    }
+}
+
+/// Served `LoxAPP3.json` and its version
+struct StructureFile {
+    contents: String,
+    last_modified: String,
+}
+
+impl StructureFile {
+    /// Validate a structure file, returning it with the parsed JSON
+    fn parse(contents: &str) -> Result<(Self, Value)> {
+        let parsed: Value = serde_json::from_str(contents)?;
+        if !parsed.get("controls").is_some_and(Value::is_object) {
+            return Err(LoxoneError::config(
+                "Structure fixture has no controls object",
+            ));
+        }
+        let file = Self {
+            contents: contents.to_string(),
+            last_modified: parsed["lastModified"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        Ok((file, parsed))
+    }
+}
+
+/// Shared simulator state
+struct SimulatorState {
+    config: SimulatorConfig,
+    structure: std::sync::RwLock<StructureFile>,
+    serial: String,
+    home: Mutex<SimulatedHome>,
+    auth: Mutex<SimulatedAuth>,
+    statistics: std::sync::Mutex<StatisticsRecorder>,
+    /// Text states (e.g. mood lists) by state UUID
+    texts: Mutex<HashMap<String, TextEvent>>,
+    /// Daytimer schedules by state UUID
+    daytimers: Mutex<HashMap<String, DaytimerEvent>>,
+    events: broadcast::Sender<EventTable>,
+}
+
+/// Simulated Loxone Miniserver
+#[derive(Clone)]
+pub struct MiniserverSimulator {
+    state: Arc<SimulatorState>,
+}
+
+impl MiniserverSimulator {
+    /// Create a simulator for a `LoxAPP3.json` structure
+    pub fn new(config: SimulatorConfig, structure: &str) -> Result<Self> {
+        let (structure, parsed) = StructureFile::parse(structure)?;
+        let home = SimulatedHome::from_structure(&parsed, config.physics.clone());
+        let statistics =
+            StatisticsRecorder::from_structure(&parsed, |uuid| home.current_value(uuid));
+        let auth = SimulatedAuth::new(&config.username, &config.password, config.token_lifetime);
+        let (events, _) = broadcast::channel(256);
+
+        Ok(Self {
+            state: Arc::new(SimulatorState {
+                serial: parsed["msInfo"]["serialNr"]
+                    .as_str()
+                    .unwrap_or("504F94FFFE000000")
+                    .to_string(),
+                structure: std::sync::RwLock::new(structure),
+                home: Mutex::new(home),
+                auth: Mutex::new(auth),
+                statistics: std::sync::Mutex::new(statistics),
+                texts: Mutex::new(HashMap::new()),
+                daytimers: Mutex::new(HashMap::new()),
+                events,
+                config,
+            }),
+        })
+    }
+
+    /// Create a simulator for the bundled structure fixture
+    pub fn with_default_structure(config: SimulatorConfig) -> Result<Self> {
+        Self::new(config, DEFAULT_STRUCTURE)
+    }
+
+    /// Create a simulator for a structure file on disk
+    pub fn from_structure_file(config: SimulatorConfig, path: &Path) -> Result<Self> {
+        let structure = std::fs::read_to_string(path).map_err(|e| {
+            LoxoneError::config(format!("Failed to read structure {}: {e}", path.display()))
+        })?;
+        Self::new(config, &structure)
+    }
+
+    /// Number of simulated controls
+    pub async fn control_count(&self) -> usize {
+        self.state.home.lock().await.control_count()
+    }
+
+    /// Current value of a state or control UUID
+    pub async fn value(&self, uuid: &str) -> Option<f64> {
+        self.state.home.lock().await.current_value(uuid)
+    }
+
+    /// Overwrite a state value and notify WebSocket clients
+    pub async fn set_value(&self, state_uuid: &str, value: f64) -> Result<()> {
+        let changes = self.state.home.lock().await.set_value(state_uuid, value)?;
+        self.publish(changes);
+        Ok(())
+    }
+
+    /// Overwrite a text state and notify WebSocket clients
+    pub async fn set_text(&self, state_uuid: &str, text: &str) -> Result<()> {
+        parse_uuid(state_uuid)?;
+        let event = TextEvent {
+            uuid: state_uuid.to_string(),
+            icon_uuid: "00000000-0000-0000-0000000000000000".to_string(),
+            text: text.to_string(),
+        };
+        self.state
+            .texts
+            .lock()
+            .await
+            .insert(state_uuid.to_string(), event.clone());
+        let _ = self.state.events.send(EventTable::Texts(vec![event]));
+        Ok(())
+    }
+
+    /// Overwrite a daytimer schedule and notify WebSocket clients
+    pub async fn set_daytimer(&self, event: DaytimerEvent) -> Result<()> {
+        parse_uuid(&event.uuid)?;
+        self.state
+            .daytimers
+            .lock()
+            .await
+            .insert(event.uuid.clone(), event.clone());
+        let _ = self.state.events.send(EventTable::Daytimers(vec![event]));
+        Ok(())
+    }
+
+    /// Serve a new structure file, as after saving a program in Loxone Config
+    ///
+    /// Only the served `LoxAPP3.json` and its `lastModified` version change;
+    /// the simulated controls keep their states and physics.
+    pub fn replace_structure(&self, structure: &str) -> Result<()> {
+        let (structure, _) = StructureFile::parse(structure)?;
+        *self
+            .state
+            .structure
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = structure;
+        Ok(())
+    }
+
+    /// Add a recorded entry to the statistics of a control
+    pub fn add_statistic(&self, control_uuid: &str, entry: StatisticEntry) -> Result<()> {
+        self.statistics().insert(control_uuid, entry)
+    }
+
+    /// Number of issued tokens that have not been killed
+    pub async fn active_tokens(&self) -> usize {
+        self.state.auth.lock().await.active_tokens()
+    }
+
+    /// Advance the physics by `elapsed` simulated time
+    pub async fn advance(&self, elapsed: Duration) {
+        let changes = self.state.home.lock().await.tick(elapsed);
+        self.publish(changes);
+    }
+
+    /// Serve on an already bound listener until the task is dropped
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        let ticker = self.spawn_ticker();
+        let result = axum::serve(listener, server::router(self.clone()))
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Simulator server failed: {e}")));
+        ticker.abort();
+        result
+    }
+
+    /// Bind to `addr` and serve in the background
+    pub async fn spawn(self, addr: SocketAddr) -> Result<SimulatorHandle> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| LoxoneError::connection(format!("Failed to bind {addr}: {e}")))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| LoxoneError::connection(format!("Failed to read local address: {e}")))?;
+        info!("🏠 Miniserver simulator listening on http://{local_addr}");
+
+        let task = tokio::spawn(self.serve(listener));
+        Ok(SimulatorHandle { local_addr, task })
+    }
+
+    fn spawn_ticker(&self) -> JoinHandle<()> {
+        let simulator = self.clone();
+        let tick_interval = self.state.config.tick_interval;
+        let time_scale = self.state.config.time_scale;
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick_interval);
+            let mut last_tick = Instant::now();
+            loop {
+                interval.tick().await;
+                let elapsed = last_tick.elapsed().mul_f64(time_scale);
+                last_tick = Instant::now();
+                simulator.advance(elapsed).await;
+            }
+        })
+    }
+
+    fn structure(&self) -> std::sync::RwLockReadGuard<'_, StructureFile> {
+        self.state
+            .structure
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn statistics(&self) -> std::sync::MutexGuard<'_, StatisticsRecorder> {
+        self.state
+            .statistics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn publish(&self, changes: Vec<ValueEvent>) {
+        if changes.is_empty() {
+            return;
+        }
+        debug!("Simulator: {} state changes", changes.len());
+        self.statistics().record_changes(&changes, Utc::now());
+        // No receivers simply means no WebSocket client enabled status updates
+        let _ = self.state.events.send(EventTable::Values(changes));
+    }
+}
+
+/// Handle of a simulator serving in the background; aborts it on drop
+pub struct SimulatorHandle {
+    local_addr: SocketAddr,
+    task: JoinHandle<Result<()>>,
+}
+
+impl SimulatorHandle {
+    /// Address the simulator is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// HTTP base URL of the simulator
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.local_addr)
+    }
+
+    /// Stop serving
+    pub fn shutdown(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for SimulatorHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,530 @@
+//! HTTP and WebSocket front end of the Miniserver simulator
+//!
+//! HTTP requests and WebSocket text commands share one dispatcher, so every
+//! command behaves the same on both transports.
+
+use super::{MiniserverSimulator, SIMULATED_FIRMWARE_VERSION};
+use crate::client::binary_protocol::{
+    EventTable, MessageHeader, MessageIdentifier, encode_daytimer_events, encode_text_events,
+    encode_value_events, parse_uuid,
+};
+use crate::client::statistics::StatisticMonth;
+use crate::error::LoxoneError;
+use crate::security::encryption::{CommandCipher, SaltedCommand};
+use axum::{
+    Router,
+    body::Body,
+    extract::{
+        Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::{HeaderMap, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+/// Authentication and streaming state of one client
+#[derive(Debug, Default)]
+struct Session {
+    authorized: bool,
+    binary_status: bool,
+    /// Command encryption session from `keyexchange` (WebSocket) or `?sk=` (HTTP)
+    cipher: Option<CommandCipher>,
+}
+
+/// Result of a dispatched command
+enum Reply {
+    /// `{"LL": {...}}` response
+    Json {
+        control: String,
+        code: u16,
+        value: Value,
+    },
+    /// Raw file contents (the structure file)
+    File(String),
+    /// HTML page (the statistics listing)
+    Html(String),
+    /// Binary file (statistics data)
+    Binary(Vec<u8>),
+    /// Header-only keep-alive response
+    Keepalive,
+    /// `fenc` response: the JSON body, encrypted and base64 encoded
+    Encrypted(String),
+}
+
+impl Reply {
+    fn ok(control: &str, value: Value) -> Self {
+        Self::Json {
+            control: control.to_string(),
+            code: 200,
+            value,
+        }
+    }
+
+    fn error(control: &str, code: u16, message: impl Into<String>) -> Self {
+        Self::Json {
+            control: control.to_string(),
+            code,
+            value: Value::String(message.into()),
+        }
+    }
+
+    fn from_error(control: &str, error: &LoxoneError) -> Self {
+        let code = match error {
+            LoxoneError::Authentication(_) => 401,
+            LoxoneError::NotFound(_) => 404,
+            LoxoneError::InvalidInput(_) => 400,
+            _ => 500,
+        };
+        Self::error(control, code, error.to_string())
+    }
+
+    fn body(control: &str, code: u16, value: &Value) -> String {
+        json!({
+            "LL": {
+                "control": control,
+                "value": value,
+                "Code": code.to_string()
+            }
+        })
+        .to_string()
+    }
+}
+
+pub(super) fn router(simulator: MiniserverSimulator) -> Router {
+    Router::new()
+        .route("/ws/rfc6455", get(websocket))
+        .fallback(get(http_command))
+        .with_state(simulator)
+}
+
+async fn http_command(
+    State(simulator): State<MiniserverSimulator>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Response {
+    let cipher = match query.get("sk") {
+        Some(session_key) => simulator
+            .state
+            .auth
+            .lock()
+            .await
+            .session_cipher(session_key)
+            .ok(),
+        None => None,
+    };
+    let mut session = Session {
+        authorized: simulator.authorize(&headers, &query).await,
+        binary_status: false,
+        cipher,
+    };
+
+    match simulator.execute(uri.path(), &mut session).await {
+        Reply::Json {
+            control,
+            code,
+            value,
+        } => {
+            let status = StatusCode::from_u16(code).unwrap_or(StatusCode::OK);
+            (
+                status,
+                [(header::CONTENT_TYPE, "application/json")],
+                Reply::body(&control, code, &value),
+            )
+                .into_response()
+        }
+        Reply::File(contents) => (
+            [(header::CONTENT_TYPE, "application/json")],
+            Body::from(contents),
+        )
+            .into_response(),
+        Reply::Html(page) => ([(header::CONTENT_TYPE, "text/html")], page).into_response(),
+        Reply::Binary(data) => (
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            Body::from(data),
+        )
+            .into_response(),
+        Reply::Keepalive => StatusCode::OK.into_response(),
+        Reply::Encrypted(body) => ([(header::CONTENT_TYPE, "text/plain")], body).into_response(),
+    }
+}
+
+async fn websocket(
+    State(simulator): State<MiniserverSimulator>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let has_credentials = query.contains_key("autht") || query.contains_key("password");
+    let authorized = simulator.authorize(&headers, &query).await;
+    if has_credentials && !authorized {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    upgrade.on_upgrade(move |socket| async move {
+        let session = Session {
+            authorized,
+            ..Session::default()
+        };
+        simulator.run_websocket(socket, session).await;
+    })
+}
+
+impl MiniserverSimulator {
+    /// Check basic credentials or an `autht` token
+    async fn authorize(&self, headers: &HeaderMap, query: &HashMap<String, String>) -> bool {
+        let auth = self.state.auth.lock().await;
+
+        if let (Some(token), Some(user)) = (query.get("autht"), query.get("user")) {
+            return auth.check_token(token, user);
+        }
+        if let (Some(user), Some(password)) = (query.get("user"), query.get("password")) {
+            return auth.check_password(user, password);
+        }
+
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|encoded| {
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded)
+                    .ok()
+            })
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .and_then(|decoded| {
+                decoded
+                    .split_once(':')
+                    .map(|(user, password)| auth.check_password(user, password))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Dispatch a Miniserver command such as `jdev/sps/io/{uuid}/on`
+    async fn execute(&self, command: &str, session: &mut Session) -> Reply {
+        let command = command.trim_start_matches('/');
+        let command = command.split_once('?').map_or(command, |(path, _)| path);
+        let control = command.to_string();
+        let segments: Vec<String> = command
+            .split('/')
+            .map(|segment| {
+                urlencoding::decode(segment)
+                    .map(|decoded| decoded.into_owned())
+                    .unwrap_or_else(|_| segment.to_string())
+            })
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        debug!("Simulator command: {command}");
+
+        // Handshake commands that work without authentication
+        match segments.as_slice() {
+            ["jdev", "cfg", "apiversion" | "version"] => {
+                return Reply::ok(&control, json!(SIMULATED_FIRMWARE_VERSION));
+            }
+            ["jdev", "cfg", "api"] => {
+                return Reply::ok(
+                    &control,
+                    json!(format!(
+                        "{{'snr': '{}', 'version':'{SIMULATED_FIRMWARE_VERSION}'}}",
+                        self.state.serial
+                    )),
+                );
+            }
+            ["jdev", "sys", "getPublicKey"] => {
+                return match self.state.auth.lock().await.public_key() {
+                    Ok(pem) => Reply::ok(&control, json!(pem)),
+                    Err(e) => Reply::from_error(&control, &e),
+                };
+            }
+            ["jdev", "sys", "getkey2", _user] => {
+                return Reply::ok(&control, self.state.auth.lock().await.getkey2());
+            }
+            ["jdev", "sys", "getkey"] => {
+                return Reply::ok(&control, self.state.auth.lock().await.getkey());
+            }
+            ["jdev", "sys", "getjwt", hash, user, permission, ..] => {
+                let result = self
+                    .state
+                    .auth
+                    .lock()
+                    .await
+                    .issue_token(hash, user, permission);
+                return match result {
+                    Ok(token) => {
+                        session.authorized = true;
+                        Reply::ok(&control, token)
+                    }
+                    Err(e) => Reply::from_error(&control, &e),
+                };
+            }
+            ["jdev", "sys", "refreshjwt", hash, user] => {
+                return match self.state.auth.lock().await.refresh_token(hash, user) {
+                    Ok(token) => Reply::ok(&control, token),
+                    Err(e) => Reply::from_error(&control, &e),
+                };
+            }
+            ["jdev", "sys", "killtoken", hash, user] => {
+                return match self.state.auth.lock().await.kill_token(hash, user) {
+                    Ok(()) => Reply::ok(&control, json!("")),
+                    Err(e) => Reply::from_error(&control, &e),
+                };
+            }
+            ["authwithtoken", hash, user] | ["jdev", "sys", "authwithtoken", hash, user] => {
+                return match self.state.auth.lock().await.auth_with_token(hash, user) {
+                    Ok(info) => {
+                        session.authorized = true;
+                        Reply::ok(&control, info)
+                    }
+                    Err(e) => Reply::from_error(&control, &e),
+                };
+            }
+            ["jdev", "sys", "keyexchange", session_key] => {
+                return match self.state.auth.lock().await.session_cipher(session_key) {
+                    Ok(cipher) => {
+                        session.cipher = Some(cipher);
+                        Reply::ok(&control, json!(""))
+                    }
+                    Err(e) => Reply::from_error(&control, &e),
+                };
+            }
+            ["jdev", "sys", endpoint @ ("enc" | "fenc"), encrypted] => {
+                let encrypt_response = *endpoint == "fenc";
+                return self
+                    .execute_encrypted(&control, encrypted, encrypt_response, session)
+                    .await;
+            }
+            ["keepalive"] => return Reply::Keepalive,
+            _ => {}
+        }
+
+        if !session.authorized {
+            return Reply::error(&control, 401, "Unauthorized");
+        }
+
+        match segments.as_slice() {
+            ["data", "LoxAPP3.json"] => Reply::File(self.structure().contents.clone()),
+            ["jdev", "sps", "LoxAPPversion3"] => {
+                Reply::ok(&control, json!(self.structure().last_modified))
+            }
+            ["jdev", "sps", "enablebinstatusupdate"] => {
+                session.binary_status = true;
+                Reply::ok(&control, json!("1"))
+            }
+            ["jdev", "sps", "io", uuid, rest @ ..] => {
+                let command = rest.join("/");
+                // Text and daytimer states are read like value states
+                if matches!(command.as_str(), "" | "state")
+                    && let Some(value) = self.event_state(uuid).await
+                {
+                    return Reply::ok(&control, value);
+                }
+                let result = self.state.home.lock().await.apply_command(uuid, &command);
+                match result {
+                    Ok((value, changes)) => {
+                        self.publish(changes);
+                        Reply::ok(&control, json!(value))
+                    }
+                    Err(e) => Reply::from_error(&control, &e),
+                }
+            }
+            ["stats"] | ["stats", ""] => Reply::Html(self.statistics().listing()),
+            ["binstatisticdata", uuid, month] => match StatisticMonth::parse(month) {
+                Some(month) => match self.statistics().file(uuid, month) {
+                    Ok(data) => Reply::Binary(data),
+                    Err(e) => Reply::from_error(&control, &e),
+                },
+                None => Reply::error(&control, 400, format!("Invalid month: {month}")),
+            },
+            _ => Reply::error(&control, 404, format!("Unknown command: {command}")),
+        }
+    }
+
+    /// Decrypt and run a `jdev/sys/enc/` or `jdev/sys/fenc/` command
+    ///
+    /// Token credentials travel inside the encrypted command as its query.
+    async fn execute_encrypted(
+        &self,
+        control: &str,
+        encrypted: &str,
+        encrypt_response: bool,
+        session: &mut Session,
+    ) -> Reply {
+        let Some(cipher) = session.cipher.clone() else {
+            return Reply::error(control, 400, "No session key exchanged");
+        };
+        let plaintext = match cipher.decrypt_text(encrypted) {
+            Ok(plaintext) => plaintext,
+            Err(e) => return Reply::error(control, 400, format!("Invalid encrypted command: {e}")),
+        };
+        let Some(salted) = SaltedCommand::parse(&plaintext) else {
+            return Reply::error(control, 400, "Encrypted command is not salted");
+        };
+
+        let (command, query) = salted
+            .command
+            .split_once('?')
+            .unwrap_or((salted.command, ""));
+        let query: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        if !query.is_empty() && self.authorize(&HeaderMap::new(), &query).await {
+            session.authorized = true;
+        }
+
+        match Box::pin(self.execute(command, session)).await {
+            Reply::Json {
+                control,
+                code,
+                value,
+            } if encrypt_response => {
+                Reply::Encrypted(cipher.encrypt_text(&Reply::body(&control, code, &value)))
+            }
+            reply => reply,
+        }
+    }
+
+    /// Current text or daytimer state of a state UUID, if it is one
+    async fn event_state(&self, uuid: &str) -> Option<Value> {
+        if let Some(event) = self.state.texts.lock().await.get(uuid) {
+            return Some(json!(event.text));
+        }
+        let daytimers = self.state.daytimers.lock().await;
+        daytimers
+            .get(uuid)
+            .and_then(|event| serde_json::to_value(event).ok())
+    }
+
+    /// Serve one WebSocket client until it disconnects
+    async fn run_websocket(&self, socket: WebSocket, mut session: Session) {
+        let (mut sender, mut receiver) = socket.split();
+        let mut events = self.state.events.subscribe();
+
+        loop {
+            tokio::select! {
+                message = receiver.next() => {
+                    let text = match message {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Close(_))) | None => break,
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => {
+                            debug!("Simulator WebSocket error: {e}");
+                            break;
+                        }
+                    };
+
+                    let streaming = session.binary_status;
+                    let reply = self.execute(&text, &mut session).await;
+                    let mut frames = reply_frames(reply);
+                    if session.binary_status && !streaming {
+                        // Status updates start with the full state tables
+                        let values = self.state.home.lock().await.all_values();
+                        frames.extend(event_table_frames(EventTable::Values(values)));
+                        let texts = self.state.texts.lock().await.values().cloned().collect();
+                        frames.extend(event_table_frames(EventTable::Texts(texts)));
+                        let daytimers =
+                            self.state.daytimers.lock().await.values().cloned().collect();
+                        frames.extend(event_table_frames(EventTable::Daytimers(daytimers)));
+                    }
+                    if send_all(&mut sender, frames).await.is_err() {
+                        break;
+                    }
+                }
+                changes = events.recv(), if session.binary_status => {
+                    let changes = match changes {
+                        Ok(changes) => changes,
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("Simulator WebSocket client lagged, {skipped} updates dropped");
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+                    if send_all(&mut sender, event_table_frames(changes)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn send_all<S>(sender: &mut S, frames: Vec<Message>) -> Result<(), axum::Error>
+where
+    S: SinkExt<Message, Error = axum::Error> + Unpin,
+{
+    for frame in frames {
+        sender.send(frame).await?;
+    }
+    Ok(())
+}
+
+/// Header frame followed by the payload frame(s) of a reply
+fn reply_frames(reply: Reply) -> Vec<Message> {
+    match reply {
+        Reply::Json {
+            control,
+            code,
+            value,
+        } => {
+            let body = Reply::body(&control, code, &value);
+            vec![
+                header_frame(MessageIdentifier::Text, body.len()),
+                Message::Text(body),
+            ]
+        }
+        Reply::File(contents) => vec![
+            header_frame(MessageIdentifier::BinaryFile, contents.len()),
+            Message::Binary(contents.into_bytes()),
+        ],
+        Reply::Html(page) => vec![
+            header_frame(MessageIdentifier::BinaryFile, page.len()),
+            Message::Binary(page.into_bytes()),
+        ],
+        Reply::Binary(data) => vec![
+            header_frame(MessageIdentifier::BinaryFile, data.len()),
+            Message::Binary(data),
+        ],
+        Reply::Keepalive => vec![header_frame(MessageIdentifier::Keepalive, 0)],
+        Reply::Encrypted(body) => vec![
+            header_frame(MessageIdentifier::Text, body.len()),
+            Message::Text(body),
+        ],
+    }
+}
+
+fn event_table_frames(table: EventTable) -> Vec<Message> {
+    let (identifier, payload) = match table {
+        EventTable::Values(mut values) => {
+            // Fixtures may contain UUIDs that have no binary form; those are skipped
+            values.retain(|event| parse_uuid(&event.uuid).is_ok());
+            (MessageIdentifier::ValueStates, encode_value_events(&values))
+        }
+        EventTable::Texts(texts) => (MessageIdentifier::TextStates, encode_text_events(&texts)),
+        EventTable::Daytimers(daytimers) => (
+            MessageIdentifier::DaytimerStates,
+            encode_daytimer_events(&daytimers),
+        ),
+        EventTable::Weather(_) => return Vec::new(),
+    };
+    match payload {
+        Ok(payload) if !payload.is_empty() => vec![
+            header_frame(identifier, payload.len()),
+            Message::Binary(payload),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+fn header_frame(identifier: MessageIdentifier, length: usize) -> Message {
+    Message::Binary(
+        MessageHeader::exact(identifier, length as u32)
+            .encode()
+            .to_vec(),
+    )
+}
@@ -0,0 +1,286 @@
+//! Statistics recording of the simulated Miniserver
+//!
+//! Controls with a `statistic` object in the structure record their outputs
+//! whenever one of the output states changes, at most once per configured
+//! interval. Unlike a real Miniserver, averaging frequencies store the current
+//! value instead of the average over the interval.
+
+use crate::client::LoxoneControl;
+use crate::client::binary_protocol::ValueEvent;
+use crate::client::statistics::{
+    StatisticConfig, StatisticEntry, StatisticMonth, encode_statistics,
+};
+use crate::error::{LoxoneError, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Control with statistics enabled
+#[derive(Debug)]
+struct RecordedControl {
+    config: StatisticConfig,
+    entries: Vec<StatisticEntry>,
+}
+
+/// Statistics of all controls, recorded from value events
+#[derive(Debug, Default)]
+pub(super) struct StatisticsRecorder {
+    controls: HashMap<String, RecordedControl>,
+    /// Output state UUID to the controls recording it
+    outputs: HashMap<String, Vec<String>>,
+    /// Latest value of every output state
+    values: HashMap<String, f64>,
+}
+
+impl StatisticsRecorder {
+    /// Recorder for the controls of a structure with statistics enabled
+    pub(super) fn from_structure(structure: &Value, initial: impl Fn(&str) -> Option<f64>) -> Self {
+        let mut recorder = Self::default();
+        let Some(controls) = structure.get("controls").and_then(Value::as_object) else {
+            return recorder;
+        };
+        for (uuid, control) in controls {
+            let Some(control) = LoxoneControl::from_value(uuid, control) else {
+                continue;
+            };
+            for control in control.flatten() {
+                let Some(config) = control.statistic.clone() else {
+                    continue;
+                };
+                for state_uuid in config.outputs.iter().filter_map(|o| o.uuid.as_ref()) {
+                    recorder
+                        .outputs
+                        .entry(state_uuid.clone())
+                        .or_default()
+                        .push(control.uuid.clone());
+                    if let Some(value) = initial(state_uuid) {
+                        recorder.values.insert(state_uuid.clone(), value);
+                    }
+                }
+                recorder.controls.insert(
+                    control.uuid.clone(),
+                    RecordedControl {
+                        config,
+                        entries: Vec::new(),
+                    },
+                );
+            }
+        }
+        recorder
+    }
+
+    /// Record the controls whose outputs changed
+    pub(super) fn record_changes(&mut self, changes: &[ValueEvent], now: DateTime<Utc>) {
+        let mut changed = Vec::new();
+        for event in changes {
+            if let Some(controls) = self.outputs.get(&event.uuid) {
+                self.values.insert(event.uuid.clone(), event.value);
+                changed.extend(controls.iter().cloned());
+            }
+        }
+        changed.sort();
+        changed.dedup();
+
+        for control_uuid in changed {
+            let Some(control) = self.controls.get_mut(&control_uuid) else {
+                continue;
+            };
+            let interval = chrono::Duration::seconds(i64::from(control.config.interval_seconds()));
+            if control
+                .entries
+                .last()
+                .is_some_and(|last| now - last.timestamp < interval)
+            {
+                continue;
+            }
+            let values = control
+                .config
+                .outputs
+                .iter()
+                .map(|output| {
+                    output
+                        .uuid
+                        .as_ref()
+                        .and_then(|uuid| self.values.get(uuid))
+                        .copied()
+                        .unwrap_or_default()
+                })
+                .collect();
+            control.entries.push(StatisticEntry {
+                timestamp: now,
+                values,
+            });
+        }
+    }
+
+    /// Add an entry to a control's statistics, keeping them in time order
+    pub(super) fn insert(&mut self, control_uuid: &str, entry: StatisticEntry) -> Result<()> {
+        let control = self.controls.get_mut(control_uuid).ok_or_else(|| {
+            LoxoneError::not_found(format!("No statistics for control {control_uuid}"))
+        })?;
+        if entry.values.len() != control.config.outputs.len() {
+            return Err(LoxoneError::invalid_input(format!(
+                "Control {control_uuid} records {} outputs, got {} values",
+                control.config.outputs.len(),
+                entry.values.len()
+            )));
+        }
+        let index = control
+            .entries
+            .partition_point(|existing| existing.timestamp <= entry.timestamp);
+        control.entries.insert(index, entry);
+        Ok(())
+    }
+
+    /// `/stats` page linking every recorded control and month
+    pub(super) fn listing(&self) -> String {
+        let mut files: Vec<String> = self
+            .controls
+            .iter()
+            .flat_map(|(uuid, control)| {
+                let mut months: Vec<StatisticMonth> = control
+                    .entries
+                    .iter()
+                    .map(|entry| StatisticMonth::of(entry.timestamp))
+                    .collect();
+                months.dedup();
+                months
+                    .into_iter()
+                    .map(move |month| format!("{uuid}.{month}"))
+            })
+            .collect();
+        files.sort();
+
+        let links: String = files
+            .iter()
+            .map(|file| format!("<a href=\"{file}\">{file}</a><br>\n"))
+            .collect();
+        format!("<html><head><title>Statistics</title></head><body>\n{links}</body></html>\n")
+    }
+
+    /// Binary statistics of a control for one month
+    pub(super) fn file(&self, control_uuid: &str, month: StatisticMonth) -> Result<Vec<u8>> {
+        let entries: Vec<StatisticEntry> = self
+            .controls
+            .get(control_uuid)
+            .map(|control| {
+                control
+                    .entries
+                    .iter()
+                    .filter(|entry| StatisticMonth::of(entry.timestamp) == month)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        if entries.is_empty() {
+            return Err(LoxoneError::not_found(format!(
+                "No statistics of {control_uuid} for {month}"
+            )));
+        }
+        encode_statistics(control_uuid, &entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::statistics::parse_statistics;
+    use serde_json::json;
+
+    const CONTROL: &str = "1c8f8a16-0300-0004-ffff000000000000";
+    const ACTUAL: &str = "1c8f8a16-0300-0004-ffff000000000001";
+    const TARGET: &str = "1c8f8a16-0300-0004-ffff000000000002";
+
+    fn recorder() -> StatisticsRecorder {
+        let structure = json!({
+            "controls": {
+                CONTROL: {
+                    "name": "Climate",
+                    "type": "IRoomControllerV2",
+                    "states": {"tempActual": ACTUAL, "tempTarget": TARGET},
+                    "statistic": {
+                        "frequency": 1,
+                        "outputs": [
+                            {"id": 0, "name": "Actual", "uuid": ACTUAL},
+                            {"id": 1, "name": "Target", "uuid": TARGET}
+                        ]
+                    }
+                }
+            }
+        });
+        StatisticsRecorder::from_structure(&structure, |uuid| (uuid == TARGET).then_some(22.0))
+    }
+
+    fn event(uuid: &str, value: f64) -> ValueEvent {
+        ValueEvent {
+            uuid: uuid.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_records_changes_once_per_interval() {
+        let mut recorder = recorder();
+        let start: DateTime<Utc> = "2025-01-31T23:59:00Z".parse().unwrap();
+
+        recorder.record_changes(&[event(ACTUAL, 20.5)], start);
+        recorder.record_changes(
+            &[event(ACTUAL, 20.6)],
+            start + chrono::Duration::seconds(30),
+        );
+        recorder.record_changes(
+            &[event("other", 1.0)],
+            start + chrono::Duration::seconds(90),
+        );
+        recorder.record_changes(
+            &[event(ACTUAL, 20.7)],
+            start + chrono::Duration::seconds(90),
+        );
+
+        let january = StatisticMonth::of(start);
+        let entries = parse_statistics(&recorder.file(CONTROL, january).unwrap(), 2).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].values, [20.5, 22.0]);
+
+        let february = january.next();
+        let entries = parse_statistics(&recorder.file(CONTROL, february).unwrap(), 2).unwrap();
+        assert_eq!(entries[0].values, [20.7, 22.0]);
+
+        let listing = recorder.listing();
+        assert!(listing.contains(&format!("{CONTROL}.202501")));
+        assert!(listing.contains(&format!("{CONTROL}.202502")));
+        assert!(recorder.file(CONTROL, february.next()).is_err());
+    }
+
+    #[test]
+    fn test_insert_keeps_order() {
+        let mut recorder = recorder();
+        let at = |minute| StatisticEntry {
+            timestamp: "2025-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+                + chrono::Duration::minutes(minute),
+            values: vec![minute as f64, 22.0],
+        };
+        recorder.insert(CONTROL, at(10)).unwrap();
+        recorder.insert(CONTROL, at(5)).unwrap();
+        assert!(recorder.insert("unknown", at(1)).is_err());
+        assert!(
+            recorder
+                .insert(
+                    CONTROL,
+                    StatisticEntry {
+                        values: vec![1.0],
+                        ..at(1)
+                    }
+                )
+                .is_err()
+        );
+
+        let month = StatisticMonth {
+            year: 2025,
+            month: 3,
+        };
+        let entries = parse_statistics(&recorder.file(CONTROL, month).unwrap(), 2).unwrap();
+        let minutes: Vec<f64> = entries.iter().map(|entry| entry.values[0]).collect();
+        assert_eq!(minutes, [5.0, 10.0]);
+    }
+}
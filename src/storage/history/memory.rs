@@ -0,0 +1,108 @@
+//! In-memory sensor history backend
+//!
+//! Points are lost on restart; used for tests and offline servers.
+
+use super::{HistoryBackend, HistoryPoint, Resolution, SeriesInfo};
+use crate::error::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock;
+
+/// Points of one series at one resolution, by timestamp
+type Points = BTreeMap<DateTime<Utc>, HistoryPoint>;
+
+/// Sensor history kept in memory
+#[derive(Debug, Default)]
+pub struct MemoryHistoryBackend {
+    series: RwLock<HashMap<String, SeriesInfo>>,
+    points: RwLock<HashMap<(String, Resolution), Points>>,
+}
+
+impl MemoryHistoryBackend {
+    /// Empty backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl HistoryBackend for MemoryHistoryBackend {
+    async fn upsert_series(&self, series: &SeriesInfo) -> Result<()> {
+        self.series
+            .write()
+            .await
+            .insert(series.id.clone(), series.clone());
+        Ok(())
+    }
+
+    async fn series(&self) -> Result<Vec<SeriesInfo>> {
+        Ok(self.series.read().await.values().cloned().collect())
+    }
+
+    async fn merge_point(
+        &self,
+        series_id: &str,
+        resolution: Resolution,
+        point: &HistoryPoint,
+    ) -> Result<()> {
+        self.points
+            .write()
+            .await
+            .entry((series_id.to_string(), resolution))
+            .or_default()
+            .entry(point.timestamp)
+            .and_modify(|existing| existing.merge(point))
+            .or_insert_with(|| point.clone());
+        Ok(())
+    }
+
+    async fn points(
+        &self,
+        series_id: &str,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<HistoryPoint>> {
+        if from > to {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .points
+            .read()
+            .await
+            .get(&(series_id.to_string(), resolution))
+            .map(|points| {
+                points
+                    .range(from..=to)
+                    .take(limit)
+                    .map(|(_, point)| point.clone())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn purge_before(&self, resolution: Resolution, cutoff: DateTime<Utc>) -> Result<usize> {
+        let mut removed = 0;
+        for ((_, points_resolution), points) in self.points.write().await.iter_mut() {
+            if *points_resolution != resolution {
+                continue;
+            }
+            let kept = points.split_off(&cutoff);
+            removed += points.len();
+            *points = kept;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_backend() {
+        super::super::tests::check_backend(&MemoryHistoryBackend::new()).await;
+    }
+}
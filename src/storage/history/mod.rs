@@ -0,0 +1,1136 @@
+//! Sensor history: time series of numeric and boolean device states
+//!
+//! Every numeric or boolean state the Miniserver reports (temperatures,
+//! humidity, power, contacts, motion, levels) is stored as a series per
+//! control state. Samples are kept raw and rolled up into 1-minute, 1-hour
+//! and 1-day buckets (count, min, max, average, last), each with its own
+//! retention. Series carry the control name, room and [`SensorKind`] so they
+//! can be queried by UUID, room or sensor type.
+//!
+//! Storage backends:
+//! - In-memory (tests, offline servers)
+//! - SQLite database (with "turso" feature)
+//!
+//! Values reach the history through the state changes of the shared
+//! [`ClientContext`]; [`poll_states`] feeds the context for clients that do
+//! not push state changes.
+
+pub mod memory;
+#[cfg(feature = "turso")]
+pub mod sqlite;
+
+pub use memory::MemoryHistoryBackend;
+#[cfg(feature = "turso")]
+pub use sqlite::SqliteHistoryBackend;
+
+use crate::client::{
+    ClientContext, ControlModel, ControlType, LoxoneClient, LoxoneControl, StateRef,
+};
+use crate::config::{HistoryBackendKind, HistoryConfig};
+use crate::error::{LoxoneError, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// How often retention is enforced while recording
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Maximum number of points returned per series
+pub const MAX_POINTS: usize = 2000;
+
+/// Granularity of stored points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    /// Individual samples
+    Raw,
+    /// 1-minute rollups
+    Minute,
+    /// 1-hour rollups
+    Hour,
+    /// 1-day rollups (UTC days)
+    Day,
+}
+
+impl Resolution {
+    /// All resolutions, finest first
+    pub const ALL: [Self; 4] = [Self::Raw, Self::Minute, Self::Hour, Self::Day];
+
+    /// Name used in storage and tool arguments
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::Minute => "minute",
+            Self::Hour => "hour",
+            Self::Day => "day",
+        }
+    }
+
+    /// Bucket length in seconds
+    pub fn bucket_seconds(self) -> i64 {
+        match self {
+            Self::Raw => 1,
+            Self::Minute => 60,
+            Self::Hour => 3600,
+            Self::Day => 86400,
+        }
+    }
+
+    /// Start of the bucket containing `timestamp`
+    pub fn bucket_start(self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let seconds = timestamp.timestamp();
+        let start = seconds - seconds.rem_euclid(self.bucket_seconds());
+        Utc.timestamp_opt(start, 0).single().unwrap_or(timestamp)
+    }
+
+    /// Coarsest resolution that still gives enough points for a time span
+    pub fn for_span(span: chrono::Duration) -> Self {
+        if span <= chrono::Duration::hours(2) {
+            Self::Raw
+        } else if span <= chrono::Duration::days(2) {
+            Self::Minute
+        } else if span <= chrono::Duration::days(60) {
+            Self::Hour
+        } else {
+            Self::Day
+        }
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = LoxoneError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "raw" => Ok(Self::Raw),
+            "minute" | "1m" => Ok(Self::Minute),
+            "hour" | "1h" => Ok(Self::Hour),
+            "day" | "1d" => Ok(Self::Day),
+            _ => Err(LoxoneError::invalid_input(format!(
+                "Invalid resolution '{s}'. Supported: raw, 1m, 1h, 1d"
+            ))),
+        }
+    }
+}
+
+/// What a series measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    Temperature,
+    Humidity,
+    Power,
+    Energy,
+    Brightness,
+    /// Door and window contacts
+    Contact,
+    Motion,
+    /// On/off switches
+    Switch,
+    /// Dimmer levels
+    Level,
+    /// Blind positions
+    Position,
+    Other,
+}
+
+impl SensorKind {
+    /// Name used in storage and tool arguments
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Temperature => "temperature",
+            Self::Humidity => "humidity",
+            Self::Power => "power",
+            Self::Energy => "energy",
+            Self::Brightness => "brightness",
+            Self::Contact => "contact",
+            Self::Motion => "motion",
+            Self::Switch => "switch",
+            Self::Level => "level",
+            Self::Position => "position",
+            Self::Other => "other",
+        }
+    }
+
+    /// Classify a control state by control type, state name and value format
+    pub fn classify(control: &LoxoneControl, state: &str) -> Self {
+        let state_lower = state.to_lowercase();
+        let name = control.name.to_lowercase();
+        let unit = control.format().map(str::to_lowercase).unwrap_or_default();
+
+        if state_lower.starts_with("temp") || state_lower.contains("temperature") {
+            return Self::Temperature;
+        }
+        match &control.control_type {
+            control_type if control_type.is_presence() => {
+                if state_lower == "active" {
+                    Self::Motion
+                } else {
+                    Self::Other
+                }
+            }
+            ControlType::InfoOnlyDigital if state_lower == "active" => Self::Contact,
+            ControlType::Meter => match state_lower.as_str() {
+                "actual" => Self::Power,
+                "total" => Self::Energy,
+                _ => Self::Other,
+            },
+            ControlType::Jalousie if state_lower == "position" => Self::Position,
+            ControlType::Dimmer | ControlType::EIBDimmer if state_lower == "position" => {
+                Self::Level
+            }
+            ControlType::Switch if state_lower == "active" => Self::Switch,
+            ControlType::InfoOnlyAnalog | ControlType::Other(_) if state_lower == "value" => {
+                if unit.contains('°') {
+                    Self::Temperature
+                } else if name.contains("humid") || name.contains("feucht") {
+                    Self::Humidity
+                } else if unit.contains("kwh") {
+                    Self::Energy
+                } else if unit.contains("kw") || unit.ends_with('w') {
+                    Self::Power
+                } else if unit.contains("lx") || name.contains("lux") || name.contains("hellig") {
+                    Self::Brightness
+                } else {
+                    Self::Other
+                }
+            }
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for SensorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SensorKind {
+    type Err = LoxoneError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let kind = match s.to_lowercase().as_str() {
+            "temperature" | "temp" => Self::Temperature,
+            "humidity" => Self::Humidity,
+            "power" => Self::Power,
+            "energy" => Self::Energy,
+            "brightness" | "lux" => Self::Brightness,
+            "contact" | "door" | "window" => Self::Contact,
+            "motion" | "presence" => Self::Motion,
+            "switch" => Self::Switch,
+            "level" | "dimmer" => Self::Level,
+            "position" | "blind" => Self::Position,
+            "other" => Self::Other,
+            _ => {
+                return Err(LoxoneError::invalid_input(format!(
+                    "Invalid sensor type '{s}'. Supported: temperature, humidity, power, \
+                     energy, brightness, contact, motion, switch, level, position, other"
+                )));
+            }
+        };
+        Ok(kind)
+    }
+}
+
+/// One control state recorded over time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeriesInfo {
+    /// `{control_uuid}/{state}`
+    pub id: String,
+    /// Control (or sub-control) owning the state
+    pub control_uuid: String,
+    /// Control name when last recorded
+    pub control_name: String,
+    /// State name, e.g. `tempActual` or `value`
+    pub state: String,
+    /// Room name
+    pub room: Option<String>,
+    /// What the state measures
+    pub kind: SensorKind,
+    /// Unit taken from the control's value format
+    pub unit: Option<String>,
+}
+
+impl SeriesInfo {
+    /// Series for a state of the control model; `None` for unknown controls
+    pub fn for_state(model: &ControlModel, state_ref: &StateRef) -> Option<Self> {
+        let (control, parent) = find_control(model, &state_ref.control_uuid)?;
+        let room = control
+            .room
+            .as_deref()
+            .or(parent.and_then(|parent| parent.room.as_deref()))
+            .and_then(|room| model.room_name(room))
+            .map(str::to_string);
+        Some(Self {
+            id: format!("{}/{}", control.uuid, state_ref.state_name),
+            control_uuid: control.uuid.clone(),
+            control_name: control.name.clone(),
+            state: state_ref.state_name.clone(),
+            room,
+            kind: SensorKind::classify(control, &state_ref.state_name),
+            unit: control.format().and_then(unit_from_format),
+        })
+    }
+}
+
+/// Control by UUID, searching sub-controls too, with its top-level parent
+fn find_control<'a>(
+    model: &'a ControlModel,
+    uuid: &str,
+) -> Option<(&'a LoxoneControl, Option<&'a LoxoneControl>)> {
+    if let Some(control) = model.get(uuid) {
+        return Some((control, None));
+    }
+    model.controls().find_map(|parent| {
+        parent
+            .flatten()
+            .into_iter()
+            .find(|control| control.uuid == uuid)
+            .map(|control| (control, Some(parent)))
+    })
+}
+
+/// Unit of a Loxone value format: `%.1f°` is `°`, `%.0f%%` is `%`
+pub(crate) fn unit_from_format(format: &str) -> Option<String> {
+    let mut unit = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            unit.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'%') {
+            chars.next();
+            unit.push('%');
+            continue;
+        }
+        // Skip a conversion like `%.1f`, `%d` or `%s`
+        for c in chars.by_ref() {
+            if c.is_ascii_alphabetic() {
+                break;
+            }
+        }
+    }
+    let unit = unit.trim();
+    (!unit.is_empty()).then(|| unit.to_string())
+}
+
+/// Numeric value of a state: numbers, booleans (1/0) and numeric text
+pub fn numeric_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::Bool(flag) => Some(if *flag { 1.0 } else { 0.0 }),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Aggregate of the samples in a bucket; a single sample for raw points
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryPoint {
+    /// Sample time, or start of the bucket
+    pub timestamp: DateTime<Utc>,
+    /// Number of samples
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    /// Latest sample in the bucket
+    pub last: f64,
+}
+
+impl HistoryPoint {
+    /// Point for a single sample
+    pub fn sample(timestamp: DateTime<Utc>, value: f64) -> Self {
+        Self {
+            timestamp,
+            count: 1,
+            min: value,
+            max: value,
+            avg: value,
+            last: value,
+        }
+    }
+
+    /// Add the samples of a later point
+    pub fn merge(&mut self, later: &HistoryPoint) {
+        let count = self.count + later.count;
+        self.avg = (self.avg * self.count as f64 + later.avg * later.count as f64) / count as f64;
+        self.count = count;
+        self.min = self.min.min(later.min);
+        self.max = self.max.max(later.max);
+        self.last = later.last;
+    }
+}
+
+/// Roll samples up into points of a resolution, oldest first
+pub fn rollup(
+    resolution: Resolution,
+    samples: impl IntoIterator<Item = (DateTime<Utc>, f64)>,
+) -> Vec<HistoryPoint> {
+    let mut samples: Vec<_> = samples.into_iter().collect();
+    samples.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut points: BTreeMap<DateTime<Utc>, HistoryPoint> = BTreeMap::new();
+    for (timestamp, value) in samples {
+        let bucket = resolution.bucket_start(timestamp);
+        let sample = HistoryPoint::sample(bucket, value);
+        match points.get_mut(&bucket) {
+            Some(point) => point.merge(&sample),
+            None => {
+                points.insert(bucket, sample);
+            }
+        }
+    }
+    points.into_values().collect()
+}
+
+/// Storage for series and their points
+#[async_trait]
+pub trait HistoryBackend: Send + Sync {
+    /// Create or update a series
+    async fn upsert_series(&self, series: &SeriesInfo) -> Result<()>;
+
+    /// All known series
+    async fn series(&self) -> Result<Vec<SeriesInfo>>;
+
+    /// Merge a point into the point with the same timestamp, or add it
+    async fn merge_point(
+        &self,
+        series_id: &str,
+        resolution: Resolution,
+        point: &HistoryPoint,
+    ) -> Result<()>;
+
+    /// Points of a series in `[from, to]`, oldest first, at most `limit`
+    async fn points(
+        &self,
+        series_id: &str,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<HistoryPoint>>;
+
+    /// Remove points older than `cutoff`; returns how many were removed
+    async fn purge_before(&self, resolution: Resolution, cutoff: DateTime<Utc>) -> Result<usize>;
+}
+
+/// Selection of series and time range
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryQuery {
+    /// Control UUID, series ID, or part of the control name (case-insensitive)
+    pub device: Option<String>,
+    /// Part of the room name (case-insensitive)
+    pub room: Option<String>,
+    /// Only series of this kind
+    pub kind: Option<SensorKind>,
+    /// Only this state name
+    pub state: Option<String>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Picked from the time span if not given
+    pub resolution: Option<Resolution>,
+}
+
+impl HistoryQuery {
+    /// Whether a series is selected by this query
+    pub fn matches(&self, series: &SeriesInfo) -> bool {
+        let contains = |text: &str, part: &str| text.to_lowercase().contains(&part.to_lowercase());
+        self.device.as_ref().is_none_or(|device| {
+            series.control_uuid == *device
+                || series.id == *device
+                || contains(&series.control_name, device)
+        }) && self.room.as_ref().is_none_or(|room| {
+            series
+                .room
+                .as_deref()
+                .is_some_and(|series_room| contains(series_room, room))
+        }) && self.kind.is_none_or(|kind| series.kind == kind)
+            && self
+                .state
+                .as_ref()
+                .is_none_or(|state| series.state.eq_ignore_ascii_case(state))
+    }
+
+    /// Resolution used for this query
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+            .unwrap_or_else(|| Resolution::for_span(self.to - self.from))
+    }
+}
+
+/// Points of one series
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeriesHistory {
+    #[serde(flatten)]
+    pub series: SeriesInfo,
+    pub resolution: Resolution,
+    pub points: Vec<HistoryPoint>,
+}
+
+impl SeriesHistory {
+    /// Aggregate over all points, stamped with the first point's time
+    pub fn summary(&self) -> Option<HistoryPoint> {
+        summarize(&self.points)
+    }
+}
+
+/// Aggregate of points in time order, stamped with the first point's time
+pub fn summarize(points: &[HistoryPoint]) -> Option<HistoryPoint> {
+    let (first, rest) = points.split_first()?;
+    let mut summary = first.clone();
+    for point in rest {
+        summary.merge(point);
+    }
+    Some(summary)
+}
+
+/// Sensor history with rollups and retention on top of a backend
+#[derive(Clone)]
+pub struct SensorHistory {
+    backend: Arc<dyn HistoryBackend>,
+    retention: Vec<(Resolution, Duration)>,
+    /// Series written to the backend, to skip unchanged upserts
+    known: Arc<Mutex<HashMap<String, SeriesInfo>>>,
+    last_purge: Arc<Mutex<Option<Instant>>>,
+}
+
+impl SensorHistory {
+    /// History on a backend; points are purged per resolution after `retention`
+    pub fn new(backend: Arc<dyn HistoryBackend>, retention: Vec<(Resolution, Duration)>) -> Self {
+        Self {
+            backend,
+            retention,
+            known: Arc::new(Mutex::new(HashMap::new())),
+            last_purge: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// In-memory history without retention
+    pub fn memory() -> Self {
+        Self::new(Arc::new(MemoryHistoryBackend::new()), Vec::new())
+    }
+
+    /// Open the configured history; `None` if disabled
+    pub async fn open(config: &HistoryConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let backend: Arc<dyn HistoryBackend> = match config.backend {
+            HistoryBackendKind::Memory => Arc::new(MemoryHistoryBackend::new()),
+            #[cfg(feature = "turso")]
+            HistoryBackendKind::Sqlite => {
+                let path = match &config.path {
+                    Some(path) => path.clone(),
+                    None => default_path()?,
+                };
+                info!("Sensor history database: {}", path.display());
+                Arc::new(SqliteHistoryBackend::open(&path).await?)
+            }
+            #[cfg(not(feature = "turso"))]
+            HistoryBackendKind::Sqlite => {
+                return Err(LoxoneError::config(
+                    "SQLite sensor history requires the 'turso' feature",
+                ));
+            }
+        };
+
+        let history = Self::new(
+            backend,
+            vec![
+                (Resolution::Raw, config.raw_retention),
+                (Resolution::Minute, config.minute_retention),
+                (Resolution::Hour, config.hour_retention),
+                (Resolution::Day, config.day_retention),
+            ],
+        );
+        history.enforce_retention().await?;
+        Ok(Some(history))
+    }
+
+    /// Record a sample as a raw point and in every rollup
+    pub async fn record(
+        &self,
+        series: &SeriesInfo,
+        timestamp: DateTime<Utc>,
+        value: f64,
+    ) -> Result<()> {
+        let changed = self
+            .known
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&series.id)
+            != Some(series);
+        if changed {
+            self.backend.upsert_series(series).await?;
+            self.known
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .insert(series.id.clone(), series.clone());
+        }
+
+        for resolution in Resolution::ALL {
+            let point = HistoryPoint::sample(resolution.bucket_start(timestamp), value);
+            self.backend
+                .merge_point(&series.id, resolution, &point)
+                .await?;
+        }
+
+        let purge_due = self
+            .last_purge
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .is_none_or(|at| at.elapsed() >= PURGE_INTERVAL);
+        if purge_due && let Err(e) = self.enforce_retention().await {
+            warn!("Failed to purge sensor history: {}", e);
+        }
+        Ok(())
+    }
+
+    /// All known series
+    pub async fn series(&self) -> Result<Vec<SeriesInfo>> {
+        self.backend.series().await
+    }
+
+    /// Points of every series selected by the query, sorted by room and name
+    pub async fn query(&self, query: &HistoryQuery) -> Result<Vec<SeriesHistory>> {
+        let resolution = query.resolution();
+        let mut series: Vec<_> = self
+            .backend
+            .series()
+            .await?
+            .into_iter()
+            .filter(|series| query.matches(series))
+            .collect();
+        series.sort_by(|a, b| {
+            (&a.room, &a.control_name, &a.state).cmp(&(&b.room, &b.control_name, &b.state))
+        });
+
+        let mut results = Vec::with_capacity(series.len());
+        for series in series {
+            let points = self
+                .backend
+                .points(&series.id, resolution, query.from, query.to, MAX_POINTS)
+                .await?;
+            results.push(SeriesHistory {
+                series,
+                resolution,
+                points,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Remove points past the retention of their resolution
+    pub async fn enforce_retention(&self) -> Result<usize> {
+        *self
+            .last_purge
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now());
+
+        let mut removed = 0;
+        for (resolution, retention) in &self.retention {
+            let retention = chrono::Duration::from_std(*retention)
+                .map_err(|e| LoxoneError::config(format!("Invalid history retention: {e}")))?;
+            removed += self
+                .backend
+                .purge_before(*resolution, Utc::now() - retention)
+                .await?;
+        }
+        if removed > 0 {
+            info!("Purged {} sensor history points past retention", removed);
+        }
+        Ok(removed)
+    }
+
+    /// Record every numeric state change of the context in the background
+    pub fn spawn_recorder(&self, context: Arc<ClientContext>) -> JoinHandle<()> {
+        let history = self.clone();
+        let mut changes = context.subscribe_state_changes();
+        tokio::spawn(async move {
+            loop {
+                let state_uuid = match changes.recv().await {
+                    Ok(state_uuid) => state_uuid,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Sensor history missed {} state changes", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if let Err(e) = history.record_state(&context, &state_uuid).await {
+                    warn!("Failed to record state {}: {}", state_uuid, e);
+                }
+            }
+        })
+    }
+
+    /// Record the current value of a state UUID of the context
+    async fn record_state(&self, context: &ClientContext, state_uuid: &str) -> Result<()> {
+        let Some(value) = context
+            .get_state_value(state_uuid)
+            .await
+            .as_ref()
+            .and_then(numeric_value)
+        else {
+            return Ok(());
+        };
+        let (Some(state_ref), Some(model)) = (
+            context.resolve_state(state_uuid).await,
+            context.control_model().await,
+        ) else {
+            return Ok(());
+        };
+        let Some(series) = SeriesInfo::for_state(&model, &state_ref) else {
+            return Ok(());
+        };
+        self.record(&series, Utc::now(), value).await
+    }
+}
+
+/// Poll sensor states into the context for clients that do not push them
+///
+/// Every `interval`, the states of all controls with a known [`SensorKind`]
+/// are read and stored with [`ClientContext::record_state_value`], which
+/// notifies the recorder of changed values.
+pub fn poll_states(
+    client: Arc<dyn LoxoneClient>,
+    context: Arc<ClientContext>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let model = match context.control_model().await {
+                Some(model) => model,
+                None => match client.get_structure().await {
+                    Ok(structure) => {
+                        if let Err(e) = context.update_structure(structure).await {
+                            warn!("Failed to load structure for sensor history: {}", e);
+                            continue;
+                        }
+                        match context.control_model().await {
+                            Some(model) => model,
+                            None => continue,
+                        }
+                    }
+                    Err(e) => {
+                        debug!("Structure not available for sensor history: {}", e);
+                        continue;
+                    }
+                },
+            };
+
+            let state_uuids = tracked_states(&model);
+            if state_uuids.is_empty() {
+                continue;
+            }
+            match client.get_state_values(&state_uuids).await {
+                Ok(values) => {
+                    for (state_uuid, value) in values {
+                        // Direct reads may come back in the `LL` response envelope
+                        let value = value.pointer("/LL/value").cloned().unwrap_or(value);
+                        context.record_state_value(&state_uuid, value).await;
+                    }
+                }
+                Err(e) => warn!("Failed to poll sensor states: {}", e),
+            }
+        }
+    })
+}
+
+/// State UUIDs of all control states with a known sensor kind
+fn tracked_states(model: &ControlModel) -> Vec<String> {
+    let mut uuids: Vec<String> = model
+        .controls()
+        .flat_map(LoxoneControl::flatten)
+        .flat_map(|control| {
+            control
+                .states
+                .iter()
+                .filter(|(state, _)| SensorKind::classify(control, state) != SensorKind::Other)
+                .map(|(_, uuid)| uuid.clone())
+        })
+        .collect();
+    uuids.sort();
+    uuids.dedup();
+    uuids
+}
+
+/// `history.db` in the user config directory
+#[cfg(feature = "turso")]
+fn default_path() -> Result<PathBuf> {
+    Ok(crate::config::master_key::config_dir()
+        .map_err(|e| LoxoneError::config(e.to_string()))?
+        .join("history.db"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(super) fn series(name: &str, room: &str, kind: SensorKind) -> SeriesInfo {
+        let uuid = format!("uuid-{}", name.to_lowercase().replace(' ', "-"));
+        SeriesInfo {
+            id: format!("{uuid}/value"),
+            control_uuid: uuid,
+            control_name: name.to_string(),
+            state: "value".to_string(),
+            room: Some(room.to_string()),
+            kind,
+            unit: None,
+        }
+    }
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 15, hour, minute, second)
+            .unwrap()
+    }
+
+    /// Shared contract of all backends
+    pub(super) async fn check_backend(backend: &dyn HistoryBackend) {
+        let living = series("Living Temperature", "Living Room", SensorKind::Temperature);
+        backend.upsert_series(&living).await.unwrap();
+        let renamed = SeriesInfo {
+            control_name: "Living Room Temperature".to_string(),
+            ..living.clone()
+        };
+        backend.upsert_series(&renamed).await.unwrap();
+        assert_eq!(backend.series().await.unwrap(), vec![renamed]);
+
+        for (minute, value) in [(0, 20.0), (0, 22.0), (1, 21.0), (5, 19.0)] {
+            let point = HistoryPoint::sample(at(22, minute, 0), value);
+            backend
+                .merge_point(&living.id, Resolution::Minute, &point)
+                .await
+                .unwrap();
+        }
+
+        let points = backend
+            .points(
+                &living.id,
+                Resolution::Minute,
+                at(22, 0, 0),
+                at(22, 1, 0),
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].count, 2);
+        assert_eq!(
+            (points[0].min, points[0].max, points[0].avg),
+            (20.0, 22.0, 21.0)
+        );
+        assert_eq!(points[0].last, 22.0);
+        assert_eq!(points[1].timestamp, at(22, 1, 0));
+
+        let limited = backend
+            .points(&living.id, Resolution::Minute, at(0, 0, 0), at(23, 0, 0), 1)
+            .await
+            .unwrap();
+        assert_eq!(limited.len(), 1);
+        assert!(
+            backend
+                .points(&living.id, Resolution::Hour, at(0, 0, 0), at(23, 0, 0), 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let removed = backend
+            .purge_before(Resolution::Minute, at(22, 2, 0))
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        let left = backend
+            .points(
+                &living.id,
+                Resolution::Minute,
+                at(0, 0, 0),
+                at(23, 0, 0),
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].last, 19.0);
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        let time = at(22, 47, 31);
+        assert_eq!(Resolution::Raw.bucket_start(time), time);
+        assert_eq!(Resolution::Minute.bucket_start(time), at(22, 47, 0));
+        assert_eq!(Resolution::Hour.bucket_start(time), at(22, 0, 0));
+        assert_eq!(Resolution::Day.bucket_start(time), at(0, 0, 0));
+
+        assert_eq!(
+            Resolution::for_span(chrono::Duration::hours(10)),
+            Resolution::Minute
+        );
+        assert_eq!("1h".parse::<Resolution>().unwrap(), Resolution::Hour);
+        assert!("weekly".parse::<Resolution>().is_err());
+    }
+
+    #[test]
+    fn test_rollup() {
+        let points = rollup(
+            Resolution::Hour,
+            [
+                (at(22, 47, 0), 21.0),
+                (at(21, 5, 0), 19.0),
+                (at(22, 10, 0), 20.0),
+            ],
+        );
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].timestamp, at(21, 0, 0));
+        assert_eq!(points[1].count, 2);
+        assert_eq!(points[1].avg, 20.5);
+        assert_eq!(points[1].last, 21.0);
+    }
+
+    #[test]
+    fn test_classify_states() {
+        let control = |control_type: &str, name: &str, format: Option<&str>| {
+            let mut value = json!({
+                "name": name,
+                "type": control_type,
+                "uuidAction": "c-1",
+                "states": {}
+            });
+            if let Some(format) = format {
+                value["details"] = json!({ "format": format });
+            }
+            LoxoneControl::from_value("c-1", &value).unwrap()
+        };
+
+        let climate = control("IRoomControllerV2", "Climate", Some("%.1f°"));
+        assert_eq!(
+            SensorKind::classify(&climate, "tempActual"),
+            SensorKind::Temperature
+        );
+        assert_eq!(
+            SensorKind::classify(&climate, "operatingMode"),
+            SensorKind::Other
+        );
+
+        let analog =
+            |name, format| SensorKind::classify(&control("InfoOnlyAnalog", name, format), "value");
+        assert_eq!(analog("Outside", Some("%.1f°")), SensorKind::Temperature);
+        assert_eq!(
+            analog("Bath Humidity", Some("%.0f%%")),
+            SensorKind::Humidity
+        );
+        assert_eq!(analog("PV", Some("%.2fkW")), SensorKind::Power);
+        assert_eq!(analog("Grid", Some("%.1fkWh")), SensorKind::Energy);
+        assert_eq!(analog("Counter", None), SensorKind::Other);
+
+        let contact = control("InfoOnlyDigital", "Front Door", None);
+        assert_eq!(
+            SensorKind::classify(&contact, "active"),
+            SensorKind::Contact
+        );
+        let motion = control("PresenceDetector", "Hall Motion", None);
+        assert_eq!(SensorKind::classify(&motion, "active"), SensorKind::Motion);
+        let dimmer = control("Dimmer", "Ceiling", Some("%.0f%%"));
+        assert_eq!(SensorKind::classify(&dimmer, "position"), SensorKind::Level);
+        assert_eq!(SensorKind::classify(&dimmer, "step"), SensorKind::Other);
+
+        assert_eq!("door".parse::<SensorKind>().unwrap(), SensorKind::Contact);
+        assert!("rainfall".parse::<SensorKind>().is_err());
+    }
+
+    #[test]
+    fn test_units_and_values() {
+        assert_eq!(unit_from_format("%.1f°").as_deref(), Some("°"));
+        assert_eq!(unit_from_format("%.0f%%").as_deref(), Some("%"));
+        assert_eq!(unit_from_format("%.2f kW").as_deref(), Some("kW"));
+        assert_eq!(unit_from_format("%d"), None);
+
+        assert_eq!(numeric_value(&json!(21.5)), Some(21.5));
+        assert_eq!(numeric_value(&json!(true)), Some(1.0));
+        assert_eq!(numeric_value(&json!(" 3.5 ")), Some(3.5));
+        assert_eq!(numeric_value(&json!("open")), None);
+        assert_eq!(numeric_value(&json!({"temperature": 7.5})), None);
+    }
+
+    #[tokio::test]
+    async fn test_record_rolls_up_and_queries() {
+        let history = SensorHistory::memory();
+        let living = series("Living Temperature", "Living Room", SensorKind::Temperature);
+        let kitchen = series("Kitchen Temperature", "Kitchen", SensorKind::Temperature);
+        let door = series("Front Door", "Hallway", SensorKind::Contact);
+
+        for (minute, value) in [(0, 20.0), (20, 21.0), (40, 22.0), (70, 23.0)] {
+            let time = at(22, 0, 0) + chrono::Duration::minutes(minute);
+            history.record(&living, time, value).await.unwrap();
+        }
+        history.record(&kitchen, at(22, 5, 0), 19.0).await.unwrap();
+        history.record(&door, at(22, 6, 0), 1.0).await.unwrap();
+
+        let query = HistoryQuery {
+            device: None,
+            room: Some("living".to_string()),
+            kind: Some(SensorKind::Temperature),
+            state: None,
+            from: at(18, 0, 0),
+            to: at(23, 59, 0),
+            resolution: Some(Resolution::Hour),
+        };
+        let results = history.query(&query).await.unwrap();
+        assert_eq!(results.len(), 1);
+        let points = &results[0].points;
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].count, 3);
+        assert_eq!(points[0].avg, 21.0);
+        assert_eq!(points[1].last, 23.0);
+
+        let raw = HistoryQuery {
+            resolution: None,
+            from: at(22, 0, 0),
+            to: at(23, 0, 0),
+            ..query.clone()
+        };
+        assert_eq!(raw.resolution(), Resolution::Raw);
+        assert_eq!(history.query(&raw).await.unwrap()[0].points.len(), 3);
+
+        let temperatures = HistoryQuery {
+            room: None,
+            ..query.clone()
+        };
+        let names: Vec<_> = history
+            .query(&temperatures)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|series| series.series.control_name)
+            .collect();
+        assert_eq!(names, ["Kitchen Temperature", "Living Temperature"]);
+
+        let by_uuid = HistoryQuery {
+            device: Some(door.control_uuid.clone()),
+            room: None,
+            kind: None,
+            ..query
+        };
+        assert_eq!(history.query(&by_uuid).await.unwrap()[0].series, door);
+    }
+
+    #[tokio::test]
+    async fn test_retention_per_resolution() {
+        let backend = Arc::new(MemoryHistoryBackend::new());
+        let history = SensorHistory::new(
+            backend,
+            vec![
+                (Resolution::Raw, Duration::from_secs(3600)),
+                (Resolution::Day, Duration::from_secs(30 * 86400)),
+            ],
+        );
+        let living = series("Living Temperature", "Living Room", SensorKind::Temperature);
+        history
+            .record(&living, Utc::now() - chrono::Duration::hours(3), 20.0)
+            .await
+            .unwrap();
+        history.enforce_retention().await.unwrap();
+
+        let query = HistoryQuery {
+            device: None,
+            room: None,
+            kind: None,
+            state: None,
+            from: Utc::now() - chrono::Duration::days(2),
+            to: Utc::now(),
+            resolution: Some(Resolution::Raw),
+        };
+        assert!(history.query(&query).await.unwrap()[0].points.is_empty());
+        let daily = HistoryQuery {
+            resolution: Some(Resolution::Day),
+            ..query
+        };
+        assert_eq!(history.query(&daily).await.unwrap()[0].points.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_recorder_follows_state_changes() {
+        let context = Arc::new(ClientContext::new());
+        let structure = serde_json::from_value(json!({
+            "lastModified": "2025-01-01 00:00:00",
+            "rooms": { "room-1": { "name": "Kitchen" } },
+            "cats": {},
+            "controls": {
+                "temp-1": {
+                    "name": "Kitchen Temperature",
+                    "type": "InfoOnlyAnalog",
+                    "uuidAction": "temp-1",
+                    "room": "room-1",
+                    "details": { "format": "%.1f°" },
+                    "states": { "value": "temp-1-value" }
+                }
+            }
+        }))
+        .unwrap();
+        context.update_structure(structure).await.unwrap();
+
+        let history = SensorHistory::memory();
+        let recorder = history.spawn_recorder(context.clone());
+        context
+            .record_state_value("temp-1-value", json!(21.5))
+            .await;
+        context.record_state_value("unknown", json!(1.0)).await;
+        context
+            .record_state_value("temp-1-value", json!(22.0))
+            .await;
+
+        let query = HistoryQuery {
+            device: Some("kitchen".to_string()),
+            room: None,
+            kind: None,
+            state: None,
+            from: Utc::now() - chrono::Duration::minutes(5),
+            to: Utc::now() + chrono::Duration::minutes(1),
+            resolution: Some(Resolution::Minute),
+        };
+        let points = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(series) = history.query(&query).await.unwrap().pop()
+                    && series
+                        .points
+                        .first()
+                        .is_some_and(|point| point.last == 22.0)
+                {
+                    return series;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(points.series.kind, SensorKind::Temperature);
+        assert_eq!(points.series.room.as_deref(), Some("Kitchen"));
+        assert_eq!(points.series.unit.as_deref(), Some("°"));
+        assert_eq!(history.series().await.unwrap().len(), 1);
+        recorder.abort();
+    }
+}
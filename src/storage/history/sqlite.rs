@@ -0,0 +1,304 @@
+//! SQLite sensor history backend
+//!
+//! Series metadata lives in `sensor_series`, points of all resolutions in
+//! `sensor_points` keyed by series, resolution and Unix timestamp. Rollups are
+//! merged in place with an upsert.
+
+use super::{HistoryBackend, HistoryPoint, Resolution, SensorKind, SeriesInfo};
+use crate::error::{LoxoneError, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use libsql::{Connection, Database, Row};
+use std::path::Path;
+use tokio::sync::Mutex;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sensor_series (
+        id TEXT PRIMARY KEY,
+        control_uuid TEXT NOT NULL,
+        control_name TEXT NOT NULL,
+        state TEXT NOT NULL,
+        room TEXT,
+        kind TEXT NOT NULL,
+        unit TEXT
+    );
+    CREATE TABLE IF NOT EXISTS sensor_points (
+        series_id TEXT NOT NULL,
+        resolution TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        count INTEGER NOT NULL,
+        min_value REAL NOT NULL,
+        max_value REAL NOT NULL,
+        avg_value REAL NOT NULL,
+        last_value REAL NOT NULL,
+        PRIMARY KEY (series_id, resolution, timestamp)
+    );
+    CREATE INDEX IF NOT EXISTS sensor_points_age ON sensor_points (resolution, timestamp);
+";
+
+/// Sensor history in a SQLite database
+pub struct SqliteHistoryBackend {
+    /// Keeps the database open for the connection
+    _database: Database,
+    connection: Mutex<Connection>,
+}
+
+impl SqliteHistoryBackend {
+    /// Open or create the database at `path`
+    pub async fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        Self::connect(path).await
+    }
+
+    /// Database that lives as long as the backend
+    pub async fn in_memory() -> Result<Self> {
+        Self::connect(Path::new(":memory:")).await
+    }
+
+    async fn connect(path: &Path) -> Result<Self> {
+        let database = libsql::Builder::new_local(path)
+            .build()
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to open history database: {e}")))?;
+        let connection = database
+            .connect()
+            .map_err(|e| LoxoneError::database(format!("Failed to open history database: {e}")))?;
+        connection
+            .execute_batch(SCHEMA)
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to create history tables: {e}")))?;
+        Ok(Self {
+            _database: database,
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl std::fmt::Debug for SqliteHistoryBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteHistoryBackend")
+            .finish_non_exhaustive()
+    }
+}
+
+fn series_from_row(row: &Row) -> std::result::Result<SeriesInfo, String> {
+    let kind: String = row.get(5).map_err(|e| e.to_string())?;
+    Ok(SeriesInfo {
+        id: row.get(0).map_err(|e| e.to_string())?,
+        control_uuid: row.get(1).map_err(|e| e.to_string())?,
+        control_name: row.get(2).map_err(|e| e.to_string())?,
+        state: row.get(3).map_err(|e| e.to_string())?,
+        room: row.get(4).map_err(|e| e.to_string())?,
+        kind: kind.parse::<SensorKind>().map_err(|e| e.to_string())?,
+        unit: row.get(6).map_err(|e| e.to_string())?,
+    })
+}
+
+fn point_from_row(row: &Row) -> std::result::Result<HistoryPoint, String> {
+    let timestamp: i64 = row.get(0).map_err(|e| e.to_string())?;
+    let count: i64 = row.get(1).map_err(|e| e.to_string())?;
+    Ok(HistoryPoint {
+        timestamp: Utc
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .ok_or_else(|| format!("invalid timestamp {timestamp}"))?,
+        count: count.max(0) as u64,
+        min: row.get(2).map_err(|e| e.to_string())?,
+        max: row.get(3).map_err(|e| e.to_string())?,
+        avg: row.get(4).map_err(|e| e.to_string())?,
+        last: row.get(5).map_err(|e| e.to_string())?,
+    })
+}
+
+#[async_trait]
+impl HistoryBackend for SqliteHistoryBackend {
+    async fn upsert_series(&self, series: &SeriesInfo) -> Result<()> {
+        self.connection
+            .lock()
+            .await
+            .execute(
+                "INSERT INTO sensor_series (id, control_uuid, control_name, state, room, kind, unit)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(id) DO UPDATE SET control_name = excluded.control_name,
+                     room = excluded.room, kind = excluded.kind, unit = excluded.unit",
+                libsql::params![
+                    series.id.as_str(),
+                    series.control_uuid.as_str(),
+                    series.control_name.as_str(),
+                    series.state.as_str(),
+                    series.room.as_deref(),
+                    series.kind.as_str(),
+                    series.unit.as_deref()
+                ],
+            )
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to write sensor series: {e}")))?;
+        Ok(())
+    }
+
+    async fn series(&self) -> Result<Vec<SeriesInfo>> {
+        let connection = self.connection.lock().await;
+        let mut rows = connection
+            .query(
+                "SELECT id, control_uuid, control_name, state, room, kind, unit FROM sensor_series",
+                (),
+            )
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to read sensor series: {e}")))?;
+
+        let mut series = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to read sensor series: {e}")))?
+        {
+            series.push(
+                series_from_row(&row)
+                    .map_err(|e| LoxoneError::database(format!("Invalid sensor series: {e}")))?,
+            );
+        }
+        Ok(series)
+    }
+
+    async fn merge_point(
+        &self,
+        series_id: &str,
+        resolution: Resolution,
+        point: &HistoryPoint,
+    ) -> Result<()> {
+        self.connection
+            .lock()
+            .await
+            .execute(
+                "INSERT INTO sensor_points (series_id, resolution, timestamp, count,
+                     min_value, max_value, avg_value, last_value)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT(series_id, resolution, timestamp) DO UPDATE SET
+                     avg_value = (avg_value * count + excluded.avg_value * excluded.count)
+                         / (count + excluded.count),
+                     count = count + excluded.count,
+                     min_value = min(min_value, excluded.min_value),
+                     max_value = max(max_value, excluded.max_value),
+                     last_value = excluded.last_value",
+                libsql::params![
+                    series_id,
+                    resolution.as_str(),
+                    point.timestamp.timestamp(),
+                    i64::try_from(point.count).unwrap_or(i64::MAX),
+                    point.min,
+                    point.max,
+                    point.avg,
+                    point.last
+                ],
+            )
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to write sensor point: {e}")))?;
+        Ok(())
+    }
+
+    async fn points(
+        &self,
+        series_id: &str,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<HistoryPoint>> {
+        let connection = self.connection.lock().await;
+        let mut rows = connection
+            .query(
+                "SELECT timestamp, count, min_value, max_value, avg_value, last_value
+                 FROM sensor_points
+                 WHERE series_id = ?1 AND resolution = ?2 AND timestamp >= ?3 AND timestamp <= ?4
+                 ORDER BY timestamp LIMIT ?5",
+                libsql::params![
+                    series_id,
+                    resolution.as_str(),
+                    from.timestamp(),
+                    to.timestamp(),
+                    i64::try_from(limit).unwrap_or(i64::MAX)
+                ],
+            )
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to read sensor points: {e}")))?;
+
+        let mut points = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to read sensor points: {e}")))?
+        {
+            points.push(
+                point_from_row(&row)
+                    .map_err(|e| LoxoneError::database(format!("Invalid sensor point: {e}")))?,
+            );
+        }
+        Ok(points)
+    }
+
+    async fn purge_before(&self, resolution: Resolution, cutoff: DateTime<Utc>) -> Result<usize> {
+        let removed = self
+            .connection
+            .lock()
+            .await
+            .execute(
+                "DELETE FROM sensor_points WHERE resolution = ?1 AND timestamp < ?2",
+                libsql::params![resolution.as_str(), cutoff.timestamp()],
+            )
+            .await
+            .map_err(|e| LoxoneError::database(format!("Failed to purge sensor history: {e}")))?;
+        Ok(removed as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::history::{HistoryQuery, SensorHistory};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sqlite_backend() {
+        let backend = SqliteHistoryBackend::in_memory().await.unwrap();
+        super::super::tests::check_backend(&backend).await;
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_history_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+        let series = super::super::tests::series("Outside", "Garden", SensorKind::Temperature);
+        let now = Utc::now();
+
+        let history = SensorHistory::new(
+            Arc::new(SqliteHistoryBackend::open(&path).await.unwrap()),
+            Vec::new(),
+        );
+        history.record(&series, now, 4.5).await.unwrap();
+        history.record(&series, now, 5.5).await.unwrap();
+        drop(history);
+
+        let reopened = SensorHistory::new(
+            Arc::new(SqliteHistoryBackend::open(&path).await.unwrap()),
+            Vec::new(),
+        );
+        let query = HistoryQuery {
+            device: None,
+            room: Some("garden".to_string()),
+            kind: Some(SensorKind::Temperature),
+            state: None,
+            from: now - chrono::Duration::days(1),
+            to: now,
+            resolution: Some(Resolution::Day),
+        };
+        let results = reopened.query(&query).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].series, series);
+        assert_eq!(results[0].points[0].count, 2);
+        assert_eq!(results[0].points[0].avg, 5.0);
+    }
+}
@@ -2,13 +2,14 @@
 //!
 //! This module provides storage implementations for:
 //! - Weather data from WebSocket streams
-//! - Device state history
+//! - Device state history (sensor time series with rollups)
 //! - System metrics and analytics
 //!
 //! Available implementations:
 //! - Simple in-memory storage (default)
 //! - Turso database storage (with "turso" feature)
 
+pub mod history;
 pub mod simple_storage;
 
 #[cfg(feature = "turso")]
@@ -104,15 +104,39 @@ impl SimpleWeatherStorage {
         // Determine parameter name if not provided
         let param_name = parameter_name.unwrap_or("weather_value");
 
+        self.store_device_weather(
+            &device_uuid,
+            param_name,
+            value,
+            timestamp,
+            unit,
+            quality_score,
+        )
+        .await
+    }
+
+    /// Store weather data for a known device UUID
+    ///
+    /// A point with the same timestamp replaces the stored one, so repeated
+    /// forecasts for the same hour do not pile up.
+    pub async fn store_device_weather(
+        &self,
+        device_uuid: &str,
+        parameter_name: &str,
+        value: f64,
+        timestamp: u32,
+        unit: Option<&str>,
+        quality_score: Option<f64>,
+    ) -> Result<()> {
         debug!(
             "Storing weather data: device={}, param={}, value={:.2}, timestamp={}",
-            device_uuid, param_name, value, timestamp
+            device_uuid, parameter_name, value, timestamp
         );
 
         // Create data point
         let data_point = SimpleWeatherDataPoint {
-            device_uuid: device_uuid.clone(),
-            parameter_name: param_name.to_string(),
+            device_uuid: device_uuid.to_string(),
+            parameter_name: parameter_name.to_string(),
             value,
             unit: unit.map(|s| s.to_string()),
             timestamp,
@@ -121,11 +145,18 @@ impl SimpleWeatherStorage {
 
         // Store in memory
         let mut data = self.data.write().await;
-        let device_data = data.entry(device_uuid).or_insert_with(HashMap::new);
+        let device_data = data
+            .entry(device_uuid.to_string())
+            .or_insert_with(HashMap::new);
         let param_data = device_data
-            .entry(param_name.to_string())
+            .entry(parameter_name.to_string())
             .or_insert_with(Vec::new);
 
+        if let Some(existing) = param_data.iter_mut().find(|p| p.timestamp == timestamp) {
+            *existing = data_point;
+            return Ok(());
+        }
+
         // Add new data point
         param_data.push(data_point);
 
@@ -1,8 +1,10 @@
 //! Utility modules for common functionality
 
 pub mod error_helpers;
+pub mod time;
 
 // Re-export commonly used helpers
 pub use error_helpers::{
     parse_socket_addr_safe, parse_with_context, safe_header_pair, safe_mutex_lock,
 };
+pub use time::parse_time;
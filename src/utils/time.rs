@@ -0,0 +1,36 @@
+//! Time parsing for tool arguments
+
+use crate::error::{LoxoneError, Result};
+use chrono::{DateTime, Utc};
+
+/// Point in time given as RFC 3339 or as a duration before now ("24h")
+pub fn parse_time(time: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let ago = humantime_serde::re::humantime::parse_duration(time).map_err(|_| {
+        LoxoneError::invalid_input(format!(
+            "Invalid time '{time}': use RFC 3339 or a duration like '24h'"
+        ))
+    })?;
+    let ago = chrono::Duration::from_std(ago)
+        .map_err(|e| LoxoneError::invalid_input(format!("Invalid time '{time}': {e}")))?;
+    Ok(Utc::now() - ago)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        let exact = parse_time("2025-03-01T12:00:00+01:00").unwrap();
+        assert_eq!(exact.to_rfc3339(), "2025-03-01T11:00:00+00:00");
+
+        let day_ago = parse_time("24h").unwrap();
+        let expected = Utc::now() - chrono::Duration::hours(24);
+        assert!((day_ago - expected).num_seconds().abs() < 5);
+
+        assert!(parse_time("yesterday-ish").is_err());
+    }
+}
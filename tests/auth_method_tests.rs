@@ -1 +1,122 @@
+//! Tests for client backend selection via `LoxoneConfig.auth_method`
+//!
+//! Uses the WireMock Miniserver to verify that the configured authentication
+//! method is honoured and that token authentication falls back to basic
+//! authentication on firmware that cannot do it.
 
+use loxone_mcp_rust::client::client_factory::connect_configured_client;
+use loxone_mcp_rust::config::{AuthMethod, LoxoneConfig, credentials::LoxoneCredentials};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::{
+    Mock, ResponseTemplate,
+    matchers::{method, path_regex},
+};
+
+mod common;
+use common::MockLoxoneServer;
+
+fn config_for(server: &MockLoxoneServer, auth_method: AuthMethod) -> LoxoneConfig {
+    LoxoneConfig {
+        url: server.base_url.parse().unwrap(),
+        username: "admin".to_string(),
+        timeout: Duration::from_secs(5),
+        auth_method,
+        ..Default::default()
+    }
+}
+
+fn credentials() -> LoxoneCredentials {
+    LoxoneCredentials {
+        username: "admin".to_string(),
+        password: "secret".to_string(),
+        api_key: None,
+        #[cfg(feature = "crypto-openssl")]
+        public_key: None,
+    }
+}
+
+async fn mock_firmware_version(server: &MockLoxoneServer, version: &str) {
+    server
+        .add_mock(
+            Mock::given(method("GET"))
+                .and(path_regex(r"/jdev/cfg/apiversion$"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "LL": {
+                        "control": "dev/cfg/apiversion",
+                        "value": version,
+                        "Code": "200"
+                    }
+                }))),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn test_basic_auth_is_honoured() {
+    let server = MockLoxoneServer::start().await;
+
+    let (client, negotiated) = connect_configured_client(
+        &config_for(&server, AuthMethod::Basic),
+        &credentials(),
+        Arc::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(negotiated, AuthMethod::Basic);
+    assert!(client.is_connected().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_token_auth_falls_back_on_gen1_firmware() {
+    let server = MockLoxoneServer::start().await;
+    mock_firmware_version(&server, "8.3.3.21").await;
+
+    let (client, negotiated) = connect_configured_client(
+        &config_for(&server, AuthMethod::Token),
+        &credentials(),
+        Arc::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(negotiated, AuthMethod::Basic);
+    assert!(client.is_connected().await.unwrap());
+}
+
+#[cfg(feature = "crypto-openssl")]
+#[tokio::test]
+async fn test_token_auth_falls_back_when_key_exchange_fails() {
+    // Gen 2 firmware that advertises the key endpoint but serves no public key
+    let server = MockLoxoneServer::start().await;
+    mock_firmware_version(&server, "12.0.2.24").await;
+
+    let (client, negotiated) = connect_configured_client(
+        &config_for(&server, AuthMethod::Token),
+        &credentials(),
+        Arc::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(negotiated, AuthMethod::Basic);
+    assert!(client.is_connected().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_unreachable_miniserver_fails() {
+    let config = LoxoneConfig {
+        url: "http://127.0.0.1:9".parse().unwrap(),
+        timeout: Duration::from_secs(1),
+        auth_method: AuthMethod::Basic,
+        ..Default::default()
+    };
+
+    assert!(
+        connect_configured_client(&config, &credentials(), Arc::default())
+            .await
+            .is_err()
+    );
+}
@@ -24,6 +24,9 @@ pub fn test_loxone_config(#[default("http://localhost:8080")] mock_url: &str
         #[cfg(feature = "websocket")]
         websocket: Default::default(),
         auth_method: loxone_mcp_rust::config::AuthMethod::Basic,
+        credential_id: None,
+        command_encryption: Default::default(),
+        reuse_token: false,
     }
 }
 
@@ -1,10 +1,13 @@
 //! Tests for MCP consent flow functionality
 
+use loxone_mcp_rust::error::{LoxoneError, Result};
 use loxone_mcp_rust::mcp_consent::{
-    ConsentConfig, ConsentDecision, ConsentManager, ConsentRequest, ConsentResponse, OperationType,
-    SensitivityLevel,
+    ConsentApprover, ConsentConfig, ConsentDecision, ConsentManager, ConsentRequest,
+    ConsentResponse, DecisionMethod, OperationType, SensitivityLevel,
 };
 use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, SystemTime};
 use tokio::time::timeout;
 
@@ -28,6 +31,60 @@ fn create_test_config() -> ConsentConfig {
     }
 }
 
+/// Approver answering every request the same way
+struct FixedApprover {
+    answer: Option<bool>,
+    remember: bool,
+    calls: AtomicUsize,
+}
+
+impl FixedApprover {
+    fn new(answer: Option<bool>) -> Arc<Self> {
+        Arc::new(Self {
+            answer,
+            remember: false,
+            calls: AtomicUsize::new(0),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ConsentApprover for FixedApprover {
+    async fn request_approval(
+        &self,
+        request: &ConsentRequest,
+        _timeout: Duration,
+    ) -> Result<Option<ConsentResponse>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(self.answer.map(|approved| ConsentResponse {
+            apply_to_similar: self.remember,
+            user_id: Some("tester".to_string()),
+            ..ConsentResponse::new(request.id, approved)
+        }))
+    }
+}
+
+/// Approver whose channel is broken
+struct FailingApprover;
+
+#[async_trait::async_trait]
+impl ConsentApprover for FailingApprover {
+    async fn request_approval(
+        &self,
+        _request: &ConsentRequest,
+        _timeout: Duration,
+    ) -> Result<Option<ConsentResponse>> {
+        Err(LoxoneError::connection("webhook unreachable"))
+    }
+}
+
+fn unlock_operation() -> OperationType {
+    OperationType::SecurityControl {
+        action: "unlock".to_string(),
+        scope: "Front Door".to_string(),
+    }
+}
+
 #[tokio::test]
 async fn test_consent_config_creation() {
     let config = ConsentConfig::default();
@@ -436,3 +493,165 @@ async fn test_timeout_behavior() {
     let consent_result = result.unwrap();
     assert!(consent_result.is_ok());
 }
+
+#[tokio::test]
+async fn test_approver_decides_and_is_audited() {
+    let approver = FixedApprover::new(Some(true));
+    let manager = ConsentManager::with_config(create_test_config()).with_approver(approver.clone());
+
+    let decision = manager
+        .request_consent(unlock_operation(), "test".to_string())
+        .await
+        .unwrap();
+    assert!(matches!(decision, ConsentDecision::Approved));
+
+    let history = manager.decision_history().await;
+    assert_eq!(history.len(), 1);
+    assert!(matches!(
+        history[0].decision_method,
+        DecisionMethod::UserDecision
+    ));
+    assert_eq!(history[0].response.user_id.as_deref(), Some("tester"));
+    assert_eq!(manager.get_statistics().await.pending_requests, 0);
+
+    // Approvals are not remembered unless asked for
+    let denying = FixedApprover::new(Some(false));
+    let manager = ConsentManager::with_config(create_test_config()).with_approver(denying.clone());
+    for _ in 0..2 {
+        let decision = manager
+            .request_consent(unlock_operation(), "test".to_string())
+            .await
+            .unwrap();
+        assert!(matches!(decision, ConsentDecision::Denied { .. }));
+    }
+    assert_eq!(denying.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_remembered_approval_is_cached() {
+    let approver = Arc::new(FixedApprover {
+        answer: Some(true),
+        remember: true,
+        calls: AtomicUsize::new(0),
+    });
+    let manager = ConsentManager::with_config(create_test_config()).with_approver(approver.clone());
+
+    for _ in 0..2 {
+        let decision = manager
+            .request_consent(unlock_operation(), "test".to_string())
+            .await
+            .unwrap();
+        assert!(matches!(decision, ConsentDecision::Approved));
+    }
+    assert_eq!(approver.calls.load(Ordering::SeqCst), 1);
+
+    let history = manager.decision_history().await;
+    assert_eq!(history.len(), 2);
+    assert!(matches!(
+        history[1].decision_method,
+        DecisionMethod::CachedConsent
+    ));
+}
+
+#[tokio::test]
+async fn test_remembered_unlock_is_limited_to_door_and_client() {
+    let approver = Arc::new(FixedApprover {
+        answer: Some(true),
+        remember: true,
+        calls: AtomicUsize::new(0),
+    });
+    let manager = ConsentManager::with_config(create_test_config()).with_approver(approver.clone());
+    let unlock = |door: &str| OperationType::SecurityControl {
+        action: "unlock".to_string(),
+        scope: door.to_string(),
+    };
+    let client_a = "mcp_tool:unlock_door (claude-desktop (stdio))";
+
+    for _ in 0..2 {
+        manager
+            .request_consent(unlock("Garden Shed"), client_a.to_string())
+            .await
+            .unwrap();
+    }
+    assert_eq!(approver.calls.load(Ordering::SeqCst), 1);
+
+    // Another door still asks
+    manager
+        .request_consent(unlock("Front Door"), client_a.to_string())
+        .await
+        .unwrap();
+    assert_eq!(approver.calls.load(Ordering::SeqCst), 2);
+
+    // So does another client for the remembered door
+    manager
+        .request_consent(
+            unlock("Garden Shed"),
+            "mcp_tool:unlock_door (other-client (http:session-b))".to_string(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(approver.calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_unanswered_request_times_out() {
+    let config = ConsentConfig {
+        default_timeout: Duration::from_millis(100),
+        ..create_test_config()
+    };
+    let approver = FixedApprover::new(None);
+    let manager = ConsentManager::with_config(config).with_approver(approver);
+
+    let decision = manager
+        .request_consent(unlock_operation(), "test".to_string())
+        .await
+        .unwrap();
+    assert!(matches!(decision, ConsentDecision::TimedOut));
+
+    let stats = manager.get_statistics().await;
+    assert_eq!(stats.pending_requests, 0);
+    assert_eq!(stats.timed_out_count, 1);
+}
+
+#[tokio::test]
+async fn test_failing_approver_denies() {
+    let manager =
+        ConsentManager::with_config(create_test_config()).with_approver(Arc::new(FailingApprover));
+
+    let decision = manager
+        .request_consent(unlock_operation(), "test".to_string())
+        .await
+        .unwrap();
+    match decision {
+        ConsentDecision::Denied { reason } => assert!(reason.contains("webhook unreachable")),
+        other => panic!("Expected denial, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_channel_response_resolves_request() {
+    let mut manager = ConsentManager::with_config(create_test_config());
+    let (mut request_rx, response_tx) = manager.setup_channels().await;
+
+    tokio::spawn(async move {
+        while let Some(request) = request_rx.recv().await {
+            let response = ConsentResponse {
+                reason: Some("Not now".to_string()),
+                ..ConsentResponse::new(request.id, false)
+            };
+            response_tx.send(response).unwrap();
+        }
+    });
+
+    let decision = timeout(
+        Duration::from_secs(2),
+        manager.request_consent(unlock_operation(), "test".to_string()),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    match decision {
+        ConsentDecision::Denied { reason } => assert_eq!(reason, "Not now"),
+        other => panic!("Expected denial, got {other:?}"),
+    }
+}
@@ -106,6 +106,9 @@ fn build_config_and_credentials(
             keepalive_interval: Duration::from_secs(30),
         },
         auth_method: AuthMethod::Basic,
+        credential_id: None,
+        command_encryption: Default::default(),
+        reuse_token: false,
     };
 
     let credentials = create_credentials(user.to_string(), password.to_string());
@@ -734,7 +737,7 @@ async fn test_live_mcp_tool_execution() {
     }
 
     println!("\n=== MCP Tool: get_climate_status ===");
-    match mcp_server.get_climate_status().await {
+    match mcp_server.get_climate_status(None).await {
         Ok(result) => {
             let count = result.get("count").and_then(|v| v.as_u64()).unwrap_or(0);
             println!("  Climate controllers found: {count}");
@@ -0,0 +1,1883 @@
+//! End-to-end tests against the local Miniserver simulator
+//!
+//! Drives the simulator with the real HTTP, token and WebSocket clients to
+//! verify the structure, command, handshake and event stream endpoints.
+
+#![cfg(all(
+    feature = "http-server",
+    feature = "crypto-openssl",
+    feature = "websocket"
+))]
+
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use loxone_mcp_rust::audit::{AuditCaller, AuditLog};
+use loxone_mcp_rust::client::binary_protocol::{
+    BinaryMessage, BinaryMessageDecoder, DaytimerEntry, DaytimerEvent, EventTable, ValueEvent,
+};
+use loxone_mcp_rust::client::client_factory::connect_configured_client;
+use loxone_mcp_rust::client::statistics::{StatisticEntry, StatisticMonth, parse_statistics};
+use loxone_mcp_rust::client::websocket_client::{EventFilter, LoxoneWebSocketClient, StateUpdate};
+use loxone_mcp_rust::client::{ClientContext, LoxoneClient, LoxoneHttpClient, TokenHttpClient};
+use loxone_mcp_rust::config::{
+    AuthMethod, CommandEncryption, LoxoneConfig, ServerConfig, credentials::LoxoneCredentials,
+};
+use loxone_mcp_rust::sampling::executor::{CommandExecutor, ExecutionContext};
+use loxone_mcp_rust::sampling::response_parser::DeviceCommand;
+use loxone_mcp_rust::server::macro_backend::LoxoneMcpServer;
+use loxone_mcp_rust::server::subscription::types::{ClientTransport, ResourceChangeNotification};
+use loxone_mcp_rust::server::subscription::{ClientInfo, NotificationSink};
+use loxone_mcp_rust::server::subscription_runtime::LoxoneMcpService;
+use loxone_mcp_rust::services::{SensorTypeRegistry, UnifiedValueResolver};
+use loxone_mcp_rust::simulator::{
+    DEFAULT_STRUCTURE, MiniserverSimulator, SimulatorConfig, SimulatorHandle,
+};
+use loxone_mcp_rust::storage::history::{SensorHistory, poll_states};
+use pulseengine_mcp_server::McpBackend;
+use pulseengine_mcp_server::protocol::SubscribeRequestParam;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+const CEILING_LIGHT: &str = "1c8f8a16-0300-0001-ffff000000000000";
+const CEILING_LIGHT_POSITION: &str = "1c8f8a16-0300-0001-ffff000000000001";
+const FLOOR_LAMP: &str = "1c8f8a16-0300-0002-ffff000000000000";
+const FLOOR_LAMP_ACTIVE: &str = "1c8f8a16-0300-0002-ffff000000000001";
+const LIVING_ROOM_BLINDS: &str = "1c8f8a16-0300-0003-ffff000000000000";
+const LIVING_ROOM_CLIMATE: &str = "1c8f8a16-0300-0004-ffff000000000000";
+const LIVING_ROOM_TARGET: &str = "1c8f8a16-0300-0004-ffff000000000002";
+const LIVING_ROOM_OPERATING_MODE: &str = "1c8f8a16-0300-0004-ffff000000000004";
+const LIVING_ROOM_ACTIVE_MODE: &str = "1c8f8a16-0300-0004-ffff000000000005";
+const LIVING_ROOM_COMFORT_COOL: &str = "1c8f8a16-0300-0004-ffff000000000006";
+const LIVING_ROOM_OPEN_WINDOW: &str = "1c8f8a16-0300-0004-ffff00000000000c";
+const LIVING_ROOM_SCHEDULE: &str = "1c8f8a16-0300-0004-ffff000000000020";
+const LIVING_ROOM_SCHEDULE_ENTRIES: &str = "1c8f8a16-0300-0004-ffff000000000021";
+const KITCHEN_LIGHT: &str = "1c8f8a16-0300-0005-ffff000000000000";
+const KITCHEN_TEMPERATURE: &str = "1c8f8a16-0300-0007-ffff000000000000";
+const KITCHEN_TEMPERATURE_VALUE: &str = "1c8f8a16-0300-0007-ffff000000000001";
+const HOUSE_METER_TOTAL: &str = "1c8f8a16-0300-0011-ffff000000000002";
+const BEDROOM_AUDIO_PLAY_STATE: &str = "1c8f8a16-0300-000b-ffff000000000002";
+const BEDROOM_AUDIO_SHUFFLE: &str = "1c8f8a16-0300-000b-ffff000000000005";
+const BEDROOM_AUDIO_REPEAT: &str = "1c8f8a16-0300-000b-ffff000000000006";
+const BEDROOM_AUDIO_SOURCE: &str = "1c8f8a16-0300-000b-ffff000000000007";
+const HALLWAY_AUDIO_SYNCED: &str = "1c8f8a16-0300-000c-ffff00000000000c";
+const HALLWAY_MOOD_LIST: &str = "1c8f8a16-0300-000e-ffff000000000001";
+const HALLWAY_ACTIVE_MOODS: &str = "1c8f8a16-0300-000e-ffff000000000002";
+const HALLWAY_FAVORITE_MOODS: &str = "1c8f8a16-0300-000e-ffff000000000003";
+const HALLWAY_SPOTS_POSITION: &str = "1c8f8a16-0300-000e-ffff000000000011";
+const HALLWAY_LED_COLOR: &str = "1c8f8a16-0300-000e-ffff000000000021";
+const FRONT_DOOR_LOCK_ACTIVE: &str = "1c8f8a16-0300-000f-ffff000000000001";
+const HOUSE_ALARM_ARMED: &str = "1c8f8a16-0300-0010-ffff000000000001";
+
+async fn start_simulator(config: SimulatorConfig) -> (MiniserverSimulator, SimulatorHandle) {
+    let simulator = MiniserverSimulator::with_default_structure(config).unwrap();
+    let handle = simulator
+        .clone()
+        .spawn("127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    (simulator, handle)
+}
+
+fn config_for(handle: &SimulatorHandle, auth_method: AuthMethod) -> LoxoneConfig {
+    LoxoneConfig {
+        url: handle.base_url().parse().unwrap(),
+        username: "admin".to_string(),
+        timeout: Duration::from_secs(5),
+        max_retries: 1,
+        auth_method,
+        ..Default::default()
+    }
+}
+
+fn credentials(password: &str) -> LoxoneCredentials {
+    LoxoneCredentials {
+        username: "admin".to_string(),
+        password: password.to_string(),
+        api_key: None,
+        public_key: None,
+    }
+}
+
+#[tokio::test]
+async fn test_basic_client_loads_structure_and_controls_devices() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+
+    let structure = client.get_structure().await.unwrap();
+    // The simulator addresses sub-controls (e.g. climate schedules) as controls too
+    let sub_controls: usize = structure
+        .controls
+        .values()
+        .filter_map(|control| control.get("subControls")?.as_object())
+        .map(|sub_controls| sub_controls.len())
+        .sum();
+    assert_eq!(
+        structure.controls.len() + sub_controls,
+        simulator.control_count().await
+    );
+    assert_eq!(structure.rooms.len(), 4);
+
+    let response = client.send_command(CEILING_LIGHT, "60").await.unwrap();
+    assert_eq!(response.value["LL"]["value"], 60.0);
+    assert_eq!(simulator.value(CEILING_LIGHT_POSITION).await, Some(60.0));
+
+    client.send_command(CEILING_LIGHT, "off").await.unwrap();
+    let response = client.send_command(CEILING_LIGHT, "on").await.unwrap();
+    assert_eq!(response.value["LL"]["value"], 60.0);
+}
+
+#[tokio::test]
+async fn test_tools_validate_commands_before_sending() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+
+    let result = server
+        .send_device_command("Ceiling Light".to_string(), "40".to_string())
+        .await
+        .unwrap();
+    assert_eq!(result["command_sent"], "40");
+    assert_eq!(simulator.value(CEILING_LIGHT_POSITION).await, Some(40.0));
+
+    // Out of range and unknown commands never reach the Miniserver
+    let error = server
+        .send_device_command("Ceiling Light".to_string(), "150".to_string())
+        .await
+        .unwrap_err();
+    assert!(error.contains("Valid commands"), "{error}");
+    assert!(
+        server
+            .send_device_command(LIVING_ROOM_BLINDS.to_string(), "open".to_string())
+            .await
+            .is_err()
+    );
+    assert_eq!(simulator.value(CEILING_LIGHT_POSITION).await, Some(40.0));
+
+    let result = server
+        .control_blinds("Living Room Blinds".to_string(), None, Some(30))
+        .await
+        .unwrap();
+    assert_eq!(result["command_sent"], "ManualPosition/30");
+
+    let info = server
+        .get_device_info(LIVING_ROOM_BLINDS.to_string())
+        .await
+        .unwrap();
+    assert!(
+        info["commands"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("ManualPosition/{position:0-100}"))
+    );
+}
+
+#[tokio::test]
+async fn test_bulk_action_plans_before_executing() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    simulator
+        .set_value(CEILING_LIGHT_POSITION, 60.0)
+        .await
+        .unwrap();
+    simulator.set_value(FLOOR_LAMP_ACTIVE, 1.0).await.unwrap();
+
+    let mut client =
+        TokenHttpClient::new(config_for(&handle, AuthMethod::Token), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+
+    // The dry run lists every selected device without sending anything
+    let plan = server
+        .bulk_action(
+            "off".to_string(),
+            Some(vec!["Living Room".to_string()]),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(plan["dry_run"], true);
+    assert_eq!(plan["devices_selected"], 4);
+    assert_eq!(plan["commands_planned"], 2);
+    let skipped: Vec<&str> = plan["plan"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|entry| entry.get("skipped").is_some())
+        .map(|entry| entry["uuid"].as_str().unwrap())
+        .collect();
+    assert_eq!(skipped.len(), 2);
+    assert!(skipped.contains(&LIVING_ROOM_BLINDS));
+    assert_eq!(simulator.value(CEILING_LIGHT_POSITION).await, Some(60.0));
+
+    let result = server
+        .bulk_action(
+            "off".to_string(),
+            Some(vec!["Living Room".to_string()]),
+            None,
+            Some(vec!["lights".to_string()]),
+            None,
+            None,
+            Some(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["commands_sent"], 2);
+    assert_eq!(result["succeeded"], 2);
+    let uuids: Vec<&str> = result["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["uuid"].as_str().unwrap())
+        .collect();
+    assert!(uuids.contains(&CEILING_LIGHT));
+    assert_eq!(simulator.value(CEILING_LIGHT_POSITION).await, Some(0.0));
+    assert_eq!(simulator.value(FLOOR_LAMP_ACTIVE).await, Some(0.0));
+
+    // Blinds take a position, validated against the catalog
+    let result = server
+        .bulk_action(
+            "ManualPosition".to_string(),
+            None,
+            Some(vec!["Shading".to_string()]),
+            None,
+            Some("*blinds".to_string()),
+            Some(vec!["40".to_string()]),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["commands_planned"], 2);
+    assert_eq!(result["plan"][0]["command"], "ManualPosition/40");
+    assert!(
+        server
+            .bulk_action(
+                "ManualPosition".to_string(),
+                None,
+                Some(vec!["Shading".to_string()]),
+                None,
+                None,
+                Some(vec!["140".to_string()]),
+                Some(true),
+            )
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_natural_language_commands_reach_the_miniserver() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    simulator
+        .set_value(CEILING_LIGHT_POSITION, 60.0)
+        .await
+        .unwrap();
+
+    let mut client =
+        TokenHttpClient::new(config_for(&handle, AuthMethod::Token), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+    // The server resolves the parsed names and sends the commands
+    let executor = CommandExecutor::new(Arc::new(ClientContext::new())).with_sink(Arc::new(server));
+    let command = |device: &str, action: &str, value: Option<&str>| DeviceCommand {
+        device: device.to_string(),
+        action: action.to_string(),
+        value: value.map(str::to_string),
+        room: Some("Living Room".to_string()),
+        confidence: 0.9,
+    };
+    let commands = vec![
+        command("ceiling light", "dim", Some("40")),
+        command("Living Room Blinds", "down", None),
+        command("Living Room Climate", "set_temperature", Some("22")),
+    ];
+
+    let plan = executor
+        .execute_command_batch(
+            commands.clone(),
+            ExecutionContext {
+                dry_run: true,
+                ..ExecutionContext::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(plan.success_count, 3);
+    assert_eq!(plan.results[0].device_uuid.as_deref(), Some(CEILING_LIGHT));
+    assert_eq!(simulator.value(CEILING_LIGHT_POSITION).await, Some(60.0));
+
+    let result = executor
+        .execute_command_batch(commands, ExecutionContext::default())
+        .await
+        .unwrap();
+    assert_eq!(result.success_count, 3, "{:?}", result.results);
+    assert_eq!(
+        result.results[1].message,
+        "Sent 'FullDown' to Living Room Blinds"
+    );
+    assert_eq!(simulator.value(CEILING_LIGHT_POSITION).await, Some(40.0));
+}
+
+#[tokio::test]
+async fn test_device_names_resolve_fuzzily() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let mut client =
+        TokenHttpClient::new(config_for(&handle, AuthMethod::Token), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let mut config = ServerConfig::default();
+    config
+        .mcp
+        .aliases
+        .insert("Leselampe".to_string(), "Floor Lamp".to_string());
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        config,
+    );
+
+    // German names with umlauts find the English-named devices and rooms
+    let result = server
+        .control_lights(
+            "device".to_string(),
+            Some("Küche Licht".to_string()),
+            "on".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["uuid"], KITCHEN_LIGHT);
+    let result = server
+        .control_lights(
+            "room".to_string(),
+            Some("Wohnzimmer".to_string()),
+            "off".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["devices_affected"], 2);
+    let result = server
+        .control_lights(
+            "device".to_string(),
+            Some("leselampe".to_string()),
+            "on".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["uuid"], FLOOR_LAMP);
+    assert_eq!(simulator.value(FLOOR_LAMP_ACTIVE).await, Some(1.0));
+
+    // An ambiguous name lists the candidates instead of picking one
+    let error = server
+        .control_lights(
+            "device".to_string(),
+            Some("Licht".to_string()),
+            "off".to_string(),
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(error.contains("ambiguous"), "{error}");
+    assert!(error.contains(CEILING_LIGHT), "{error}");
+    assert!(error.contains(KITCHEN_LIGHT), "{error}");
+    assert_eq!(simulator.value(FLOOR_LAMP_ACTIVE).await, Some(1.0));
+}
+
+#[tokio::test]
+async fn test_room_controller_modes_overrides_and_schedule() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    simulator
+        .set_value(LIVING_ROOM_OPEN_WINDOW, 1.0)
+        .await
+        .unwrap();
+
+    // Daytimer schedules are pushed over the WebSocket connection
+    let context = Arc::new(ClientContext::new());
+    let (client, _) = connect_configured_client(
+        &config_for(&handle, AuthMethod::WebSocket),
+        &credentials("admin"),
+        context.clone(),
+    )
+    .await
+    .unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::from(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        context.clone(),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+
+    server
+        .set_climate_mode("Living Room".to_string(), "manual_cooling".to_string())
+        .await
+        .unwrap();
+    assert_eq!(simulator.value(LIVING_ROOM_OPERATING_MODE).await, Some(5.0));
+    assert!(
+        server
+            .set_climate_mode("Living Room".to_string(), "turbo".to_string())
+            .await
+            .is_err()
+    );
+
+    let result = server
+        .set_comfort_temperatures("Living Room".to_string(), None, Some(24.5), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        result["results"][0]["command_sent"],
+        "setComfortTemperatureCool/24.5"
+    );
+    assert_eq!(simulator.value(LIVING_ROOM_COMFORT_COOL).await, Some(24.5));
+
+    // A timed override switches the active mode and target until it ends
+    let result = server
+        .set_climate_override(
+            "Living Room".to_string(),
+            "comfort".to_string(),
+            Some(90),
+            Some(23.0),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["duration_minutes"], 90);
+    assert!(
+        result["results"][0]["command_sent"]
+            .as_str()
+            .unwrap()
+            .starts_with("override/1/")
+    );
+    assert_eq!(simulator.value(LIVING_ROOM_ACTIVE_MODE).await, Some(1.0));
+    assert_eq!(simulator.value(LIVING_ROOM_TARGET).await, Some(23.0));
+    server
+        .set_climate_override("Living Room".to_string(), "stop".to_string(), None, None)
+        .await
+        .unwrap();
+    assert_eq!(simulator.value(LIVING_ROOM_TARGET).await, Some(21.5));
+
+    let status = server
+        .get_climate_status(Some("Living Room".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(status["count"], 1);
+    let climate = &status["climate_controllers"][0]["climate"];
+    assert_eq!(climate["operating_mode"], "manual_cooling");
+    assert_eq!(climate["active_mode"], "comfort");
+    assert_eq!(climate["comfort"]["cooling"], 24.5);
+    assert_eq!(climate["open_window"], true);
+    assert_eq!(climate["schedule_uuid"], LIVING_ROOM_SCHEDULE);
+
+    // Schedules are written to the daytimer sub-control
+    let result = server
+        .set_climate_schedule(
+            "Living Room".to_string(),
+            vec![
+                "Montag 06:00-22:00".to_string(),
+                "Saturday 08:00-23:30 eco".to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["schedule_uuid"], LIVING_ROOM_SCHEDULE);
+    assert_eq!(
+        result["command_sent"],
+        "set/2/3;360;1320;0;1/8;480;1410;0;0"
+    );
+    assert!(
+        server
+            .set_climate_schedule(
+                "Living Room".to_string(),
+                vec!["Funday 06:00-22:00".to_string()]
+            )
+            .await
+            .is_err()
+    );
+
+    // ... and read back from the pushed daytimer event
+    let schedule = server
+        .get_climate_schedule("Living Room".to_string())
+        .await
+        .unwrap();
+    assert!(schedule["schedules"][0]["message"].is_string());
+    let event = DaytimerEvent {
+        uuid: LIVING_ROOM_SCHEDULE_ENTRIES.to_string(),
+        default_value: 0.0,
+        entries: vec![DaytimerEntry {
+            mode: 3,
+            from: 360,
+            to: 1320,
+            need_activate: false,
+            value: 1.0,
+        }],
+    };
+    simulator.set_daytimer(event.clone()).await.unwrap();
+    wait_for_state(
+        &context,
+        LIVING_ROOM_SCHEDULE_ENTRIES,
+        serde_json::to_value(&event).unwrap(),
+    )
+    .await;
+    let schedule = server
+        .get_climate_schedule("Wohnzimmer".to_string())
+        .await
+        .unwrap();
+    let entry = &schedule["schedules"][0]["entries"][0];
+    assert_eq!(entry["day"], "Monday");
+    assert_eq!(entry["from"], "06:00");
+    assert_eq!(entry["to"], "22:00");
+    assert_eq!(entry["mode"], "comfort");
+    assert_eq!(schedule["days"]["9"], "Sunday");
+
+    // Without pushes the schedule is read from the Miniserver
+    let mut client =
+        TokenHttpClient::new(config_for(&handle, AuthMethod::Token), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+    let schedule = server
+        .get_climate_schedule("Living Room".to_string())
+        .await
+        .unwrap();
+    assert_eq!(schedule["schedules"][0]["entries"][0]["from"], "06:00");
+}
+
+#[tokio::test]
+async fn test_audio_zone_favorites_groups_and_announcements() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let mut client =
+        TokenHttpClient::new(config_for(&handle, AuthMethod::Token), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+
+    let result = server
+        .play_audio_favorite("Bedroom Audio".to_string(), "3".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(result["command_sent"], "playZoneFav/3");
+    assert_eq!(simulator.value(BEDROOM_AUDIO_SOURCE).await, Some(3.0));
+    assert_eq!(simulator.value(BEDROOM_AUDIO_PLAY_STATE).await, Some(2.0));
+    let error = server
+        .play_audio_favorite("Bedroom Audio".to_string(), "Jazz".to_string(), None)
+        .await
+        .unwrap_err();
+    assert!(error.contains("No favorite named 'Jazz'"), "{error}");
+
+    server
+        .set_audio_playback_mode(
+            "Bedroom Audio".to_string(),
+            Some(true),
+            Some("all".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(simulator.value(BEDROOM_AUDIO_SHUFFLE).await, Some(1.0));
+    assert_eq!(simulator.value(BEDROOM_AUDIO_REPEAT).await, Some(1.0));
+
+    let result = server
+        .group_audio_zones(vec![
+            "Bedroom Audio".to_string(),
+            "Hallway Audio".to_string(),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(result["player_id"], 1);
+    assert_eq!(result["results"][0]["command_sent"], "syncWith/1");
+    assert_eq!(simulator.value(HALLWAY_AUDIO_SYNCED).await, Some(1.0));
+
+    let status = server
+        .get_audio_status(Some("Hallway Audio".to_string()))
+        .await
+        .unwrap();
+    let audio = &status["audio_zones"][0]["audio"];
+    assert_eq!(audio["player_id"], 2);
+    assert_eq!(
+        audio["group"]["members"],
+        serde_json::json!(["Bedroom Audio"])
+    );
+
+    server
+        .ungroup_audio_zones(vec!["Hallway Audio".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(simulator.value(HALLWAY_AUDIO_SYNCED).await, Some(0.0));
+
+    // Without zones, announcements go to the central audio zone
+    let result = server
+        .audio_announcement(
+            "tts".to_string(),
+            None,
+            Some("Dinner is ready".to_string()),
+            Some(40),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["zones"], 1);
+    assert_eq!(
+        result["results"][0]["command_sent"],
+        "tts/Dinner%20is%20ready/40"
+    );
+    let result = server
+        .audio_announcement(
+            "doorbell".to_string(),
+            Some(vec!["Bedroom".to_string(), "Hallway".to_string()]),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["zones"], 2);
+    assert!(
+        server
+            .audio_announcement("tts".to_string(), None, None, None)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_light_controller_moods_and_outputs() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let context = Arc::new(ClientContext::new());
+    let (client, _) = connect_configured_client(
+        &config_for(&handle, AuthMethod::WebSocket),
+        &credentials("admin"),
+        context.clone(),
+    )
+    .await
+    .unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::from(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        context.clone(),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+
+    // Mood lists are text states, pushed over the WebSocket connection
+    let mood_list = r#"[{"name":"Evening","id":1,"static":false},{"name":"Night Walk","id":2,"static":false},{"name":"Bright","id":777,"static":true},{"name":"Off","id":778,"static":true}]"#;
+    for (uuid, text) in [
+        (HALLWAY_MOOD_LIST, mood_list),
+        (HALLWAY_ACTIVE_MOODS, "[1]"),
+        (HALLWAY_FAVORITE_MOODS, "[2]"),
+    ] {
+        simulator.set_text(uuid, text).await.unwrap();
+        wait_for_state(&context, uuid, serde_json::json!(text)).await;
+    }
+
+    let scenes = server.list_scenes().await.unwrap();
+    let hallway = scenes["scene_controllers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|scene| scene["name"] == "Hallway Lights")
+        .unwrap();
+    assert_eq!(hallway["moods"].as_array().unwrap().len(), 4);
+    assert_eq!(hallway["active_moods"], serde_json::json!(["Evening"]));
+    assert_eq!(hallway["favorite_moods"], serde_json::json!(["Night Walk"]));
+
+    let result = server
+        .activate_scene("night".to_string(), Some("Hallway".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(result["results"][0]["command_sent"], "changeTo/2");
+
+    let result = server
+        .mix_moods(
+            "Hallway Lights".to_string(),
+            Some(vec!["Evening".to_string()]),
+            Some(vec!["Night Walk".to_string()]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        result["commands_sent"],
+        serde_json::json!(["addMood/1", "removeMood/2"])
+    );
+    assert!(
+        server
+            .mix_moods("Hallway".to_string(), Some(vec!["Party".to_string()]), None)
+            .await
+            .unwrap_err()
+            .contains("Evening, Night Walk")
+    );
+
+    // Learning overwrites a mood of the same name or creates a new one
+    let result = server
+        .learn_mood("Hallway".to_string(), "Movie Night".to_string())
+        .await
+        .unwrap();
+    assert_eq!(result["created"], true);
+    assert_eq!(result["command_sent"], "learn/3/Movie%20Night");
+    let result = server
+        .learn_mood("Hallway".to_string(), "evening".to_string())
+        .await
+        .unwrap();
+    assert_eq!(result["created"], false);
+    assert_eq!(result["command_sent"], "learn/1/Evening");
+    assert!(
+        server
+            .learn_mood("Hallway".to_string(), "Bright".to_string())
+            .await
+            .is_err()
+    );
+
+    assert!(
+        server
+            .delete_mood("Hallway".to_string(), "Off".to_string())
+            .await
+            .is_err()
+    );
+    let result = server
+        .delete_mood("Hallway".to_string(), "Night Walk".to_string())
+        .await
+        .unwrap();
+    assert_eq!(result["command_sent"], "delete/2");
+
+    let result = server
+        .set_favorite_mood("Hallway".to_string(), "Evening".to_string(), None, Some(0))
+        .await
+        .unwrap();
+    assert_eq!(
+        result["commands_sent"],
+        serde_json::json!(["addToFavoriteMood/1", "moveFavoriteMood/1/0"])
+    );
+
+    // "Make the hallway warm white at 40%"
+    let result = server
+        .set_light_output(
+            "Hallway".to_string(),
+            None,
+            Some(40),
+            Some("warm white".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["outputs_affected"], 2);
+    let sent: Vec<&str> = result["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["command_sent"].as_str().unwrap())
+        .collect();
+    assert!(sent.contains(&"temp(40,2700)"), "{sent:?}");
+    assert!(sent.contains(&"40"), "{sent:?}");
+    assert_eq!(simulator.value(HALLWAY_SPOTS_POSITION).await, Some(40.0));
+
+    // Colour outputs keep their colour when only the brightness changes
+    simulator
+        .set_text(HALLWAY_LED_COLOR, "hsv(240,100,90)")
+        .await
+        .unwrap();
+    wait_for_state(
+        &context,
+        HALLWAY_LED_COLOR,
+        serde_json::json!("hsv(240,100,90)"),
+    )
+    .await;
+    let result = server
+        .set_light_output(
+            "Hallway Lights".to_string(),
+            Some("LED".to_string()),
+            Some(20),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["outputs_affected"], 1);
+    assert_eq!(result["results"][0]["command_sent"], "hsv(240,100,20)");
+}
+
+#[tokio::test]
+async fn test_light_colors_of_controller_outputs() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let context = Arc::new(ClientContext::new());
+    let (client, _) = connect_configured_client(
+        &config_for(&handle, AuthMethod::WebSocket),
+        &credentials("admin"),
+        context.clone(),
+    )
+    .await
+    .unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::from(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        context.clone(),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+
+    // Only the colour picker circuit of the light controller takes colours
+    let colors = server.get_light_colors(None).await.unwrap();
+    assert_eq!(colors["count"], 1);
+    let strip = &colors["outputs"][0];
+    assert_eq!(strip["name"], "Hallway LED Strip");
+    assert_eq!(strip["controller"], "Hallway Lights");
+    assert_eq!(strip["picker_type"], "Lumitech");
+    assert_eq!(strip["room_name"], "Hallway");
+    assert!(strip["color"].is_null());
+
+    let result = server
+        .set_light_color("Hallway".to_string(), "#ff8800".to_string(), None, Some(60))
+        .await
+        .unwrap();
+    assert_eq!(result["outputs_affected"], 1);
+    assert_eq!(result["results"][0]["command_sent"], "hsv(32,100,60)");
+    let result = server
+        .set_light_color(
+            "Hallway Lights".to_string(),
+            "3000K".to_string(),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result["results"][0]["command_sent"], "temp(100,3000)");
+    assert!(
+        server
+            .set_light_color("Kitchen".to_string(), "blue".to_string(), None, None)
+            .await
+            .unwrap_err()
+            .contains("No colour outputs")
+    );
+    assert!(
+        server
+            .set_light_color("Hallway".to_string(), "mauve".to_string(), None, None)
+            .await
+            .is_err()
+    );
+
+    // Colours are read back from the picker's colour state
+    simulator
+        .set_text(HALLWAY_LED_COLOR, "temp(45,2700)")
+        .await
+        .unwrap();
+    wait_for_state(
+        &context,
+        HALLWAY_LED_COLOR,
+        serde_json::json!("temp(45,2700)"),
+    )
+    .await;
+    let colors = server
+        .get_light_colors(Some("Hallway".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        colors["outputs"][0]["color"],
+        serde_json::json!({ "mode": "white", "kelvin": 2700, "brightness": 45 })
+    );
+}
+
+#[tokio::test]
+async fn test_commands_are_audited() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+    assert!(
+        server
+            .get_audit_log(None, None, None, None, None)
+            .await
+            .is_err()
+    );
+    let server = server.with_audit_log(AuditLog::memory());
+
+    let caller = AuditCaller {
+        tool: Some("send_device_command".to_string()),
+        client: Some("http:session-1".to_string()),
+    };
+    caller
+        .scope(server.send_device_command("Ceiling Light".to_string(), "40".to_string()))
+        .await
+        .unwrap();
+    server
+        .control_lights(
+            "device".to_string(),
+            Some("Floor Lamp".to_string()),
+            "on".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    // Rejected commands never reach the Miniserver but are audited
+    server
+        .send_device_command("Ceiling Light".to_string(), "150".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(simulator.value(FLOOR_LAMP_ACTIVE).await, Some(1.0));
+
+    let log = server
+        .get_audit_log(None, None, None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(log["count"], 3);
+    assert_eq!(log["entries"][0]["command"], "150");
+    assert!(log["entries"][0]["response_code"].is_null());
+    assert!(
+        log["entries"][0]["error"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid command")
+    );
+    assert_eq!(log["entries"][1]["device_name"], "Floor Lamp");
+    assert_eq!(log["entries"][1]["command"], "on");
+    assert_eq!(log["entries"][1]["response_code"], 200);
+
+    let ceiling = server
+        .get_audit_log(
+            Some("1h".to_string()),
+            None,
+            Some("ceiling".to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(ceiling["count"], 2);
+    let entry = &ceiling["entries"][1];
+    assert_eq!(entry["device_uuid"], CEILING_LIGHT);
+    assert_eq!(entry["command"], "40");
+    assert_eq!(entry["tool"], "send_device_command");
+    assert_eq!(entry["client"], "http:session-1");
+    assert!(entry["latency_ms"].is_u64());
+
+    let error = server
+        .get_audit_log(Some("last tuesday".to_string()), None, None, None, None)
+        .await
+        .unwrap_err();
+    assert!(error.contains("Invalid time"), "{error}");
+}
+
+#[tokio::test]
+async fn test_sensor_history_from_polled_states() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let context = Arc::new(ClientContext::new());
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+
+    let history = SensorHistory::memory();
+    let recorder = history.spawn_recorder(context.clone());
+    let poller = poll_states(client.clone(), context.clone(), Duration::from_millis(50));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        context,
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    )
+    .with_sensor_history(history);
+
+    simulator
+        .set_value(KITCHEN_TEMPERATURE_VALUE, 21.5)
+        .await
+        .unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let result = server
+                .get_sensor_history(
+                    None,
+                    Some("kitchen".to_string()),
+                    Some("temperature".to_string()),
+                    None,
+                    Some("1h".to_string()),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            if result["series"][0]["summary"]["last"] == 21.5 {
+                return result;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap();
+    poller.abort();
+    recorder.abort();
+
+    assert_eq!(result["count"], 1);
+    assert_eq!(result["resolution"], "raw");
+    let series = &result["series"][0];
+    assert_eq!(series["control_name"], "Kitchen Temperature");
+    assert_eq!(series["room"], "Kitchen");
+    assert_eq!(series["kind"], "temperature");
+    assert_eq!(series["unit"], "°");
+
+    let error = server
+        .get_sensor_history(
+            None,
+            None,
+            Some("rainfall".to_string()),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(error.contains("Invalid sensor type"), "{error}");
+}
+
+#[tokio::test]
+async fn test_miniserver_statistics() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let last_year = Utc::now() - chrono::Duration::days(400);
+    for (minutes, value) in [(0, 18.0), (30, 19.0)] {
+        simulator
+            .add_statistic(
+                KITCHEN_TEMPERATURE,
+                StatisticEntry {
+                    timestamp: last_year + chrono::Duration::minutes(minutes),
+                    values: vec![value],
+                },
+            )
+            .unwrap();
+    }
+    simulator
+        .set_value(KITCHEN_TEMPERATURE_VALUE, 21.5)
+        .await
+        .unwrap();
+    simulator
+        .set_value(HOUSE_METER_TOTAL, 1234.5)
+        .await
+        .unwrap();
+
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+
+    // Client API: listing and the binary files
+    let files = client.list_statistics().await.unwrap();
+    let kitchen_files: Vec<_> = files
+        .iter()
+        .filter(|f| f.control_uuid == KITCHEN_TEMPERATURE)
+        .collect();
+    assert_eq!(kitchen_files.len(), 2);
+    let data = client
+        .fetch_statistics(KITCHEN_TEMPERATURE, StatisticMonth::of(Utc::now()))
+        .await
+        .unwrap();
+    let entries = parse_statistics(&data, 1).unwrap();
+    assert_eq!(entries.last().unwrap().values, [21.5]);
+
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+
+    let listed = server.list_statistics(None).await.unwrap();
+    let names: Vec<_> = listed["controls"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|control| control["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        ["House Meter", "Kitchen Temperature", "Living Room Climate"]
+    );
+    assert_eq!(listed["controls"][1]["outputs"][0]["unit"], "°");
+    assert_eq!(listed["controls"][1]["months"].as_array().unwrap().len(), 2);
+
+    let recent = server
+        .get_statistics(
+            "Kitchen Temperature".to_string(),
+            None,
+            Some("1h".to_string()),
+            None,
+            Some("raw".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(recent["entries"], 1);
+    assert_eq!(recent["series"][0]["points"][0]["last"], 21.5);
+
+    let yearly = server
+        .get_statistics(
+            "kitchen temp".to_string(),
+            Some("temperature".to_string()),
+            Some("500d".to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(yearly["resolution"], "day");
+    assert_eq!(yearly["entries"], 3);
+    assert_eq!(yearly["series"][0]["summary"]["min"], 18.0);
+    assert_eq!(yearly["series"][0]["summary"]["max"], 21.5);
+
+    // Meter statistics back the energy usage history resource
+    let usage = server.energy_usage_history().await.unwrap();
+    assert_eq!(usage["count"], 1);
+    assert_eq!(usage["history"][0]["name"], "House Meter");
+    assert_eq!(usage["history"][0]["resolution"], "day");
+    assert_eq!(usage["history"][0]["entries"], 1);
+    assert_eq!(
+        usage["history"][0]["series"][0]["points"][0]["last"],
+        1234.5
+    );
+
+    let error = server
+        .get_statistics("Kitchen Light".to_string(), None, None, None, None)
+        .await
+        .unwrap_err();
+    assert!(error.contains("No control with statistics"), "{error}");
+    let error = server
+        .get_statistics(
+            "Living Room Climate".to_string(),
+            Some("humidity".to_string()),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        error.contains("Actual temperature, Target temperature"),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn test_structure_reloads_when_version_changes() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let mut client =
+        TokenHttpClient::new(config_for(&handle, AuthMethod::Token), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let context = ClientContext::new();
+
+    // The first check loads the structure, later ones only compare versions
+    let version = client.get_structure_version().await.unwrap();
+    assert!(context.refresh_structure(&client).await.unwrap());
+    assert_eq!(context.structure_version().await, Some(version));
+    assert!(!context.refresh_structure(&client).await.unwrap());
+
+    let mut changes = context.subscribe_structure_changes();
+    let mut structure: serde_json::Value = serde_json::from_str(DEFAULT_STRUCTURE).unwrap();
+    structure["lastModified"] = serde_json::json!("2030-01-01 00:00:00");
+    structure["controls"][KITCHEN_TEMPERATURE]["name"] = serde_json::json!("Kitchen Sensor");
+    simulator.replace_structure(&structure.to_string()).unwrap();
+
+    assert!(context.refresh_structure(&client).await.unwrap());
+    assert_eq!(changes.try_recv().unwrap(), "2030-01-01 00:00:00");
+    let model = context.control_model().await.unwrap();
+    assert_eq!(model.last_modified(), "2030-01-01 00:00:00");
+    assert_eq!(
+        model.get(KITCHEN_TEMPERATURE).unwrap().name,
+        "Kitchen Sensor"
+    );
+    assert!(!context.refresh_structure(&client).await.unwrap());
+    assert!(changes.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_door_unlock_needs_approval() {
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let webhook = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(serde_json::json!({
+            "operation": {"security_control": {"action": "unlock", "scope": "Front Door Lock"}}
+        })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({"approved": false})),
+        )
+        .up_to_n_times(1)
+        .mount(&webhook)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({"approved": true, "user": "owner"})),
+        )
+        .mount(&webhook)
+        .await;
+
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let mut config = ServerConfig::default();
+    config.mcp.consent.webhook_url = Some(webhook.uri().parse().unwrap());
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        config,
+    );
+    simulator
+        .set_value(FRONT_DOOR_LOCK_ACTIVE, 1.0)
+        .await
+        .unwrap();
+
+    let error = server
+        .control_door_lock("Front Door Lock".to_string(), "unlock".to_string())
+        .await
+        .unwrap_err();
+    assert!(error.contains("Consent denied"), "{error}");
+    assert_eq!(simulator.value(FRONT_DOOR_LOCK_ACTIVE).await, Some(1.0));
+
+    let result = server
+        .control_door_lock("Front Door Lock".to_string(), "unlock".to_string())
+        .await
+        .unwrap();
+    assert_eq!(result["status"], "executed");
+    assert_eq!(simulator.value(FRONT_DOOR_LOCK_ACTIVE).await, Some(0.0));
+
+    // Locking is not sensitive and never asks
+    server
+        .control_door_lock("Front Door Lock".to_string(), "lock".to_string())
+        .await
+        .unwrap();
+    assert_eq!(webhook.received_requests().await.unwrap().len(), 2);
+
+    let history = server.consent_manager().decision_history().await;
+    assert_eq!(history.len(), 2);
+    assert!(!history[0].response.approved);
+    assert_eq!(history[1].response.user_id.as_deref(), Some("owner"));
+}
+
+#[tokio::test]
+async fn test_device_commands_cannot_skip_consent() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::new(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    // Neither elicitation nor a webhook: nothing can approve
+    let server = LoxoneMcpServer::with_context(
+        client,
+        Arc::new(ClientContext::new()),
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    )
+    .with_audit_log(AuditLog::memory());
+    simulator.set_value(HOUSE_ALARM_ARMED, 1.0).await.unwrap();
+    simulator
+        .set_value(FRONT_DOOR_LOCK_ACTIVE, 1.0)
+        .await
+        .unwrap();
+
+    let error = server
+        .send_device_command("House Alarm".to_string(), "off".to_string())
+        .await
+        .unwrap_err();
+    assert!(error.contains("Consent denied"), "{error}");
+    assert!(error.contains("No approval channel"), "{error}");
+    assert!(
+        server
+            .send_device_command("Front Door Lock".to_string(), "off".to_string())
+            .await
+            .unwrap_err()
+            .contains("Consent denied")
+    );
+    assert!(
+        server
+            .set_security_mode("disarm".to_string(), None)
+            .await
+            .unwrap_err()
+            .contains("Consent denied")
+    );
+    assert_eq!(simulator.value(HOUSE_ALARM_ARMED).await, Some(1.0));
+    assert_eq!(simulator.value(FRONT_DOOR_LOCK_ACTIVE).await, Some(1.0));
+
+    // Refused attempts are on record even though nothing was sent
+    let denied = server
+        .get_audit_log(None, None, Some("House Alarm".to_string()), None, None)
+        .await
+        .unwrap();
+    assert_eq!(denied["count"], 2);
+    for entry in denied["entries"].as_array().unwrap() {
+        assert_eq!(entry["command"], "off");
+        assert!(entry["response_code"].is_null());
+        assert!(
+            entry["error"].as_str().unwrap().contains("Consent denied"),
+            "{entry}"
+        );
+    }
+
+    // Arming and locking are not sensitive
+    server
+        .send_device_command("House Alarm".to_string(), "on/1".to_string())
+        .await
+        .unwrap();
+    server
+        .control_door_lock("Front Door Lock".to_string(), "lock".to_string())
+        .await
+        .unwrap();
+    assert_eq!(server.consent_manager().decision_history().await.len(), 3);
+}
+
+#[tokio::test]
+async fn test_wrong_password_is_rejected() {
+    let (_simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("wrong"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+    assert!(client.get_structure().await.unwrap_err().is_auth_error());
+    assert!(
+        TokenHttpClient::with_token_cache(
+            config_for(&handle, AuthMethod::Token),
+            credentials("wrong"),
+            None
+        )
+        .await
+        .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_token_handshake_and_kill_on_shutdown() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let (client, negotiated) = connect_configured_client(
+        &config_for(&handle, AuthMethod::Token),
+        &credentials("admin"),
+        Arc::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(negotiated, AuthMethod::Token);
+    assert_eq!(simulator.active_tokens().await, 1);
+
+    client
+        .send_command(LIVING_ROOM_CLIMATE, "setManualTemperature/23")
+        .await
+        .unwrap();
+
+    client.shutdown().await.unwrap();
+    assert_eq!(simulator.active_tokens().await, 0);
+}
+
+#[tokio::test]
+async fn test_encrypted_commands() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    for (mode, level) in [
+        (CommandEncryption::Request, 30),
+        (CommandEncryption::Full, 70),
+    ] {
+        let config = LoxoneConfig {
+            command_encryption: mode,
+            ..config_for(&handle, AuthMethod::Token)
+        };
+        let (client, _) = connect_configured_client(&config, &credentials("admin"), Arc::default())
+            .await
+            .unwrap();
+
+        let response = client
+            .send_command(CEILING_LIGHT, &level.to_string())
+            .await
+            .unwrap();
+        assert_eq!(response.value["LL"]["value"], f64::from(level), "{mode:?}");
+        assert_eq!(
+            simulator.value(CEILING_LIGHT_POSITION).await,
+            Some(f64::from(level))
+        );
+        client.shutdown().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_blinds_move_over_time() {
+    let config = SimulatorConfig {
+        tick_interval: Duration::from_millis(20),
+        time_scale: 40.0,
+        ..Default::default()
+    };
+    let (simulator, handle) = start_simulator(config).await;
+    let mut client =
+        LoxoneHttpClient::new(config_for(&handle, AuthMethod::Basic), credentials("admin"))
+            .await
+            .unwrap();
+    client.connect().await.unwrap();
+
+    client
+        .send_command(LIVING_ROOM_BLINDS, "FullDown")
+        .await
+        .unwrap();
+
+    // 20 s of travel at 40x take half a second
+    let mut position = 0.0;
+    for _ in 0..100 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        position = simulator.value(LIVING_ROOM_BLINDS).await.unwrap();
+        if position >= 1.0 {
+            break;
+        }
+    }
+    assert_eq!(position, 1.0);
+}
+
+/// Read frames until the next value event table is complete
+async fn next_value_table(
+    socket: &mut WsStream,
+    decoder: &mut BinaryMessageDecoder,
+) -> Vec<ValueEvent> {
+    loop {
+        let frame = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        match frame {
+            Message::Text(_) => {
+                decoder.feed_text();
+            }
+            Message::Binary(data) => {
+                if let Some(BinaryMessage::EventTable(EventTable::Values(events))) =
+                    decoder.feed_binary(&data).unwrap()
+                {
+                    return events;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_streams_binary_value_events() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let url = format!(
+        "ws://{}/ws/rfc6455?user=admin&password=admin",
+        handle.local_addr()
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    socket
+        .send(Message::Text("jdev/sps/enablebinstatusupdate".to_string()))
+        .await
+        .unwrap();
+
+    let mut decoder = BinaryMessageDecoder::new();
+
+    // The initial table carries every state
+    let initial = next_value_table(&mut socket, &mut decoder).await;
+    assert!(
+        initial
+            .iter()
+            .any(|event| event.uuid == CEILING_LIGHT_POSITION && event.value == 0.0)
+    );
+
+    simulator
+        .set_value(CEILING_LIGHT_POSITION, 75.0)
+        .await
+        .unwrap();
+    let update = next_value_table(&mut socket, &mut decoder).await;
+    assert_eq!(update.len(), 1);
+    assert_eq!(update[0].uuid, CEILING_LIGHT_POSITION);
+    assert_eq!(update[0].value, 75.0);
+}
+
+#[tokio::test]
+async fn test_websocket_rejects_wrong_password() {
+    let (_simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let url = format!(
+        "ws://{}/ws/rfc6455?user=admin&password=wrong",
+        handle.local_addr()
+    );
+    assert!(tokio_tungstenite::connect_async(url).await.is_err());
+}
+
+/// Wait for a state update of the given state UUID
+async fn next_update_for(
+    updates: &mut tokio::sync::mpsc::UnboundedReceiver<StateUpdate>,
+    uuid: &str,
+    value: f64,
+) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while let Some(update) = updates.recv().await {
+            if update.uuid == uuid && update.value == value {
+                return;
+            }
+        }
+        panic!("update channel closed");
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_websocket_client_reuses_http_token() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let config = config_for(&handle, AuthMethod::Token);
+
+    let mut http_client =
+        TokenHttpClient::with_token_cache(config.clone(), credentials("admin"), None)
+            .await
+            .unwrap();
+    http_client.connect().await.unwrap();
+    let http_client: Arc<dyn LoxoneClient> = Arc::new(http_client);
+
+    let mut client = LoxoneWebSocketClient::new_with_http_client(
+        config,
+        credentials("admin"),
+        http_client.clone(),
+    )
+    .await
+    .unwrap();
+    client.connect().await.unwrap();
+    // authwithtoken with the HTTP client's JWT instead of a second token
+    assert_eq!(simulator.active_tokens().await, 1);
+
+    let mut updates = client.subscribe_with_filter(EventFilter::match_all()).await;
+    http_client.send_command(CEILING_LIGHT, "40").await.unwrap();
+    next_update_for(&mut updates, CEILING_LIGHT_POSITION, 40.0).await;
+}
+
+#[tokio::test]
+async fn test_websocket_client_acquires_token_over_socket() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let mut client = LoxoneWebSocketClient::new(
+        config_for(&handle, AuthMethod::WebSocket),
+        credentials("admin"),
+    )
+    .await
+    .unwrap();
+    client.connect().await.unwrap();
+    assert_eq!(simulator.active_tokens().await, 1);
+
+    let mut updates = client.subscribe_with_filter(EventFilter::match_all()).await;
+    client.send_command(CEILING_LIGHT, "55").await.unwrap();
+    // Without a structure the update carries the state UUID
+    next_update_for(&mut updates, CEILING_LIGHT_POSITION, 55.0).await;
+
+    let mut client = LoxoneWebSocketClient::new(
+        config_for(&handle, AuthMethod::WebSocket),
+        credentials("wrong"),
+    )
+    .await
+    .unwrap();
+    assert!(client.connect().await.unwrap_err().is_auth_error());
+}
+
+/// Wait until a pushed state value reaches the context
+async fn wait_for_state(context: &ClientContext, uuid: &str, value: serde_json::Value) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while context.get_state_value(uuid).await.as_ref() != Some(&value) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{uuid} never became {value}"));
+}
+
+#[tokio::test]
+async fn test_websocket_pushes_reach_server_context() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let context = Arc::new(ClientContext::new());
+    let (client, negotiated) = connect_configured_client(
+        &config_for(&handle, AuthMethod::WebSocket),
+        &credentials("admin"),
+        context.clone(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(negotiated, AuthMethod::WebSocket);
+    let client: Arc<dyn LoxoneClient> = Arc::from(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        context,
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+
+    simulator
+        .set_value(KITCHEN_TEMPERATURE_VALUE, 23.5)
+        .await
+        .unwrap();
+    wait_for_state(
+        server.context().unwrap(),
+        KITCHEN_TEMPERATURE_VALUE,
+        serde_json::json!(23.5),
+    )
+    .await;
+}
+
+/// Collects delivered resource notifications
+struct ChannelSink(tokio::sync::mpsc::UnboundedSender<ResourceChangeNotification>);
+
+#[async_trait::async_trait]
+impl NotificationSink for ChannelSink {
+    async fn deliver(
+        &self,
+        _client: &ClientInfo,
+        notification: &ResourceChangeNotification,
+    ) -> loxone_mcp_rust::Result<()> {
+        let _ = self.0.send(notification.clone());
+        Ok(())
+    }
+
+    async fn broadcast(
+        &self,
+        _method: &str,
+        _params: serde_json::Value,
+    ) -> loxone_mcp_rust::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_pushes_notify_resource_subscribers() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+
+    let context = Arc::new(ClientContext::new());
+    let (client, _) = connect_configured_client(
+        &config_for(&handle, AuthMethod::WebSocket),
+        &credentials("admin"),
+        context.clone(),
+    )
+    .await
+    .unwrap();
+    let client: Arc<dyn LoxoneClient> = Arc::from(client);
+    let value_resolver = Arc::new(UnifiedValueResolver::new(
+        client.clone(),
+        Arc::new(SensorTypeRegistry::new()),
+    ));
+    let server = LoxoneMcpServer::with_context(
+        client,
+        context,
+        value_resolver,
+        None,
+        ServerConfig::default(),
+    );
+    let service = LoxoneMcpService::new(server, ClientTransport::Stdio)
+        .await
+        .unwrap();
+    let (sender, mut notifications) = tokio::sync::mpsc::unbounded_channel();
+    service
+        .subscriptions()
+        .attach_sink(Arc::new(ChannelSink(sender)))
+        .await;
+    service.on_startup().await.unwrap();
+
+    let uri = "loxone://sensors/temperature";
+    service
+        .subscribe(SubscribeRequestParam {
+            uri: uri.to_string(),
+        })
+        .await
+        .unwrap();
+
+    simulator
+        .set_value(KITCHEN_TEMPERATURE_VALUE, 25.5)
+        .await
+        .unwrap();
+    let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+        .await
+        .expect("no notification after the simulator pushed a change")
+        .unwrap();
+    assert_eq!(notification.method, "notifications/resources/updated");
+    assert_eq!(notification.params.uri, uri);
+
+    service.on_shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_websocket_keepalive_keeps_session_open() {
+    let (_simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let mut config = config_for(&handle, AuthMethod::WebSocket);
+    config.websocket.keepalive_interval = Duration::from_millis(100);
+
+    let mut client = LoxoneWebSocketClient::new(config, credentials("admin"))
+        .await
+        .unwrap();
+    client.connect().await.unwrap();
+    let initial = client.get_stats().await.messages_received;
+
+    tokio::time::sleep(Duration::from_millis(450)).await;
+    let stats = client.get_stats().await;
+    assert!(client.is_connected().await.unwrap());
+    assert_eq!(stats.reconnection_attempts, 0);
+    // Every keepalive is answered with a keep-alive header
+    assert!(stats.messages_received >= initial + 3);
+}
+
+#[tokio::test]
+async fn test_websocket_handshake_with_encryption() {
+    let (simulator, handle) = start_simulator(SimulatorConfig::default()).await;
+    let config = LoxoneConfig {
+        command_encryption: CommandEncryption::Full,
+        ..config_for(&handle, AuthMethod::WebSocket)
+    };
+
+    let mut client = LoxoneWebSocketClient::new(config, credentials("admin"))
+        .await
+        .unwrap();
+    client.connect().await.unwrap();
+    assert_eq!(simulator.active_tokens().await, 1);
+
+    let mut updates = client.subscribe_with_filter(EventFilter::match_all()).await;
+    client.send_command(CEILING_LIGHT, "25").await.unwrap();
+    next_update_for(&mut updates, CEILING_LIGHT_POSITION, 25.0).await;
+}
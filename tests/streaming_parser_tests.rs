@@ -20,6 +20,9 @@ fn create_test_config() -> (LoxoneConfig, LoxoneCredentials) {
         #[cfg(feature = "websocket")]
         websocket: Default::default(),
         auth_method: AuthMethod::Basic,
+        credential_id: None,
+        command_encryption: Default::default(),
+        reuse_token: false,
     };
 
     let credentials = LoxoneCredentials {
@@ -0,0 +1,292 @@
+//! JWT token lifecycle tests against a stub Miniserver
+//!
+//! Covers token caching across restarts (`reuse_token`), `refreshjwt` for
+//! tokens close to expiry and `killtoken` on clean shutdown.
+
+#![cfg(feature = "crypto-openssl")]
+
+use loxone_mcp_rust::client::auth::{AuthToken, CachedToken};
+use loxone_mcp_rust::client::binary_protocol::LOXONE_EPOCH_UNIX;
+use loxone_mcp_rust::client::token_cache::TokenCache;
+use loxone_mcp_rust::client::{LoxoneClient, TokenHttpClient};
+use loxone_mcp_rust::config::{AuthMethod, LoxoneConfig, credentials::LoxoneCredentials};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::sign::Signer;
+use serde_json::json;
+use std::time::Duration;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, path_regex},
+};
+
+/// One-time key served by `getkey`
+const TOKEN_KEY: &str = "4c6f786f6e652d6b6579";
+
+const CREDENTIAL_ID: &str = "home";
+
+/// `validUntil` (Loxone epoch) for a token expiring `seconds` from now
+fn valid_until_in(seconds: i64) -> i64 {
+    chrono::Utc::now().timestamp() - LOXONE_EPOCH_UNIX + seconds
+}
+
+/// HMAC-SHA256 of a token with [`TOKEN_KEY`], as the Miniserver expects it
+fn token_hash(token: &str) -> String {
+    let key = PKey::hmac(&hex::decode(TOKEN_KEY).unwrap()).unwrap();
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).unwrap();
+    signer.update(token.as_bytes()).unwrap();
+    hex::encode(signer.sign_to_vec().unwrap())
+}
+
+fn ll(value: serde_json::Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "LL": { "control": "", "value": value, "Code": "200" }
+    }))
+}
+
+/// Stub Miniserver speaking the token handshake; `getjwt` issues `jwt-1`
+async fn stub_miniserver(expected_logins: u64) -> MockServer {
+    let server = MockServer::start().await;
+    let public_key = Rsa::generate(2048).unwrap().public_key_to_pem().unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/jdev/sys/getPublicKey"))
+        .respond_with(ll(json!(String::from_utf8(public_key).unwrap())))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/jdev/sys/getkey2/admin"))
+        .respond_with(ll(json!({
+            "key": "41424344",
+            "salt": "73616c74",
+            "hashAlg": "SHA256"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/jdev/sys/getjwt/"))
+        .respond_with(ll(json!({
+            "token": "jwt-1",
+            "validUntil": valid_until_in(30 * 24 * 3600),
+            "tokenRights": 4,
+            "unsecurePass": false
+        })))
+        .expect(expected_logins)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/jdev/sys/getkey"))
+        .respond_with(ll(json!(TOKEN_KEY)))
+        .mount(&server)
+        .await;
+
+    server
+}
+
+fn config_for(server: &MockServer) -> LoxoneConfig {
+    LoxoneConfig {
+        url: server.uri().parse().unwrap(),
+        username: "admin".to_string(),
+        timeout: Duration::from_secs(5),
+        auth_method: AuthMethod::Token,
+        credential_id: Some(CREDENTIAL_ID.to_string()),
+        ..Default::default()
+    }
+}
+
+fn credentials() -> LoxoneCredentials {
+    LoxoneCredentials {
+        username: "admin".to_string(),
+        password: "secret".to_string(),
+        api_key: None,
+        public_key: None,
+    }
+}
+
+fn cached(token: &str, valid_until: i64) -> CachedToken {
+    CachedToken {
+        username: "admin".to_string(),
+        hash_alg: "SHA256".to_string(),
+        token: AuthToken {
+            token: token.to_string(),
+            key: String::new(),
+            salt: String::new(),
+            valid_until,
+            token_rights: 4,
+            unsecure_pass: false,
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_token_is_cached_and_reused_across_restarts() {
+    let server = stub_miniserver(1).await;
+    let dir = tempfile::tempdir().unwrap();
+    let cache = || Some(TokenCache::new(dir.path(), [9u8; 32]));
+    let config = || LoxoneConfig {
+        reuse_token: true,
+        ..config_for(&server)
+    };
+
+    let first = TokenHttpClient::with_token_cache(config(), credentials(), cache())
+        .await
+        .unwrap();
+    first.shutdown().await.unwrap();
+    drop(first);
+
+    let stored = TokenCache::new(dir.path(), [9u8; 32])
+        .load(CREDENTIAL_ID)
+        .unwrap();
+    assert_eq!(stored.token.token, "jwt-1");
+
+    // The restart must not run the handshake again
+    let second = TokenHttpClient::with_token_cache(config(), credentials(), cache())
+        .await
+        .unwrap();
+    assert!(
+        second
+            .get_auth_params()
+            .await
+            .unwrap()
+            .contains("autht=jwt-1")
+    );
+
+    server.verify().await;
+}
+
+#[tokio::test]
+async fn test_expiring_cached_token_is_refreshed() {
+    let server = stub_miniserver(0).await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/jdev/sys/refreshjwt/{}/admin",
+            token_hash("jwt-old")
+        )))
+        .respond_with(ll(json!({
+            "token": "jwt-2",
+            "validUntil": valid_until_in(30 * 24 * 3600),
+            "tokenRights": 4,
+            "unsecurePass": false
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let cache = TokenCache::new(dir.path(), [9u8; 32]);
+    cache
+        .store(CREDENTIAL_ID, &cached("jwt-old", valid_until_in(600)))
+        .unwrap();
+
+    let client = TokenHttpClient::with_token_cache(config_for(&server), credentials(), Some(cache))
+        .await
+        .unwrap();
+    assert!(
+        client
+            .get_auth_params()
+            .await
+            .unwrap()
+            .contains("autht=jwt-2")
+    );
+
+    let stored = TokenCache::new(dir.path(), [9u8; 32])
+        .load(CREDENTIAL_ID)
+        .unwrap();
+    assert_eq!(stored.token.token, "jwt-2");
+
+    server.verify().await;
+}
+
+#[tokio::test]
+async fn test_uncached_token_is_killed_on_shutdown() {
+    let server = stub_miniserver(1).await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/jdev/sys/killtoken/{}/admin",
+            token_hash("jwt-1")
+        )))
+        .respond_with(ll(json!("")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config = LoxoneConfig {
+        credential_id: None,
+        ..config_for(&server)
+    };
+    let client = TokenHttpClient::with_token_cache(config, credentials(), None)
+        .await
+        .unwrap();
+    client.shutdown().await.unwrap();
+
+    server.verify().await;
+}
+
+#[tokio::test]
+async fn test_cached_token_is_killed_and_removed_on_shutdown() {
+    let server = stub_miniserver(1).await;
+    Mock::given(method("GET"))
+        .and(path(format!(
+            "/jdev/sys/killtoken/{}/admin",
+            token_hash("jwt-1")
+        )))
+        .respond_with(ll(json!("")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let client = TokenHttpClient::with_token_cache(
+        config_for(&server),
+        credentials(),
+        Some(TokenCache::new(dir.path(), [9u8; 32])),
+    )
+    .await
+    .unwrap();
+    assert!(
+        TokenCache::new(dir.path(), [9u8; 32])
+            .load(CREDENTIAL_ID)
+            .is_some()
+    );
+    client.shutdown().await.unwrap();
+
+    assert!(
+        TokenCache::new(dir.path(), [9u8; 32])
+            .load(CREDENTIAL_ID)
+            .is_none()
+    );
+    server.verify().await;
+}
+
+#[tokio::test]
+async fn test_reused_token_survives_shutdown() {
+    let server = stub_miniserver(1).await;
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/jdev/sys/killtoken/"))
+        .respond_with(ll(json!("")))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let config = LoxoneConfig {
+        reuse_token: true,
+        ..config_for(&server)
+    };
+    let client = TokenHttpClient::with_token_cache(
+        config,
+        credentials(),
+        Some(TokenCache::new(dir.path(), [9u8; 32])),
+    )
+    .await
+    .unwrap();
+    client.shutdown().await.unwrap();
+
+    assert!(
+        TokenCache::new(dir.path(), [9u8; 32])
+            .load(CREDENTIAL_ID)
+            .is_some()
+    );
+    server.verify().await;
+}
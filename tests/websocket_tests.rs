@@ -305,4 +305,154 @@ mod websocket_integration_tests {
 
         // Backend successfully initialized with state update mock
     }
+
+    #[tokio::test]
+    async fn test_event_table_feeds_state_updates_and_context() {
+        use loxone_mcp_rust::client::binary_protocol::{BinaryMessage, BinaryMessageDecoder};
+        use loxone_mcp_rust::client::websocket_client::{LoxoneEventType, LoxoneWebSocketClient};
+        use loxone_mcp_rust::client::{ClientContext, LoxoneStructure};
+        use std::collections::HashMap;
+
+        let structure: LoxoneStructure = serde_json::from_value(serde_json::json!({
+            "lastModified": "2025-01-01 00:00:00",
+            "controls": {
+                "0b734138-037d-034e-ffff403fb0c34b9e": {
+                    "name": "Living Room Light",
+                    "type": "Dimmer",
+                    "room": "room-1",
+                    "states": { "position": "1234abcd-0001-0002-0102030405060708" }
+                }
+            },
+            "rooms": { "room-1": { "name": "Living Room" } },
+            "cats": {},
+            "global_states": HashMap::<String, serde_json::Value>::new()
+        }))
+        .unwrap();
+        let context = ClientContext::new();
+        context.update_structure(structure).await.unwrap();
+
+        // Header frame followed by a single value event (state UUID + 42.5)
+        let mut decoder = BinaryMessageDecoder::new();
+        let header = [0x03, 0x02, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00];
+        assert!(decoder.feed_binary(&header).unwrap().is_none());
+        let payload = [
+            0xcd, 0xab, 0x34, 0x12, 0x01, 0x00, 0x02, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+            0x07, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x45, 0x40,
+        ];
+        let Some(BinaryMessage::EventTable(table)) = decoder.feed_binary(&payload).unwrap() else {
+            panic!("expected event table");
+        };
+
+        let updates = LoxoneWebSocketClient::event_table_to_state_updates(table, &context).await;
+        assert_eq!(updates.len(), 1);
+        let update = &updates[0];
+        assert_eq!(update.uuid, "0b734138-037d-034e-ffff403fb0c34b9e");
+        assert_eq!(update.state, "position");
+        assert_eq!(update.value, serde_json::json!(42.5));
+        assert_eq!(update.event_type, LoxoneEventType::State);
+        assert_eq!(update.room.as_deref(), Some("Living Room"));
+        assert_eq!(update.device_name.as_deref(), Some("Living Room Light"));
+        assert!(update.previous_value.is_none());
+
+        assert_eq!(
+            context
+                .get_state_value("1234abcd-0001-0002-0102030405060708")
+                .await,
+            Some(serde_json::json!(42.5))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_weather_events_feed_weather_storage() {
+        use loxone_mcp_rust::client::ClientContext;
+        use loxone_mcp_rust::client::binary_protocol::{WeatherEntry, WeatherEvent};
+        use loxone_mcp_rust::client::websocket_client::LoxoneWebSocketClient;
+        use loxone_mcp_rust::storage::{WeatherStorage, WeatherStorageConfig};
+
+        let storage = WeatherStorage::new(WeatherStorageConfig::default())
+            .await
+            .unwrap();
+        let entry = |timestamp, temperature| WeatherEntry {
+            timestamp,
+            weather_type: 1,
+            wind_direction: 180,
+            solar_radiation: 300,
+            relative_humidity: 55,
+            temperature,
+            perceived_temperature: temperature - 1.0,
+            dew_point: 8.0,
+            precipitation: 0.0,
+            wind_speed: 12.0,
+            barometric_pressure: 1013.0,
+        };
+        let event = WeatherEvent {
+            uuid: "weather-state".to_string(),
+            last_update: 0,
+            entries: vec![entry(3600, 18.5), entry(7200, 19.5)],
+        };
+
+        let context = ClientContext::new();
+        LoxoneWebSocketClient::store_weather_events(
+            std::slice::from_ref(&event),
+            &context,
+            &storage,
+        )
+        .await;
+        // The same forecast arriving again replaces the stored points
+        LoxoneWebSocketClient::store_weather_events(&[event], &context, &storage).await;
+
+        let temperature = storage
+            .get_weather_parameter("weather-state", "temperature", 10)
+            .await
+            .unwrap();
+        assert_eq!(temperature.len(), 2);
+        assert_eq!(temperature[0].value, 19.5);
+        assert_eq!(temperature[0].timestamp, 1_230_768_000 + 7200);
+        assert_eq!(temperature[0].unit.as_deref(), Some("°C"));
+
+        let humidity = storage
+            .get_weather_parameter("weather-state", "relative_humidity", 10)
+            .await
+            .unwrap();
+        assert_eq!(humidity[0].value, 55.0);
+    }
+
+    #[tokio::test]
+    async fn test_weather_events_are_kept_decoded() {
+        use loxone_mcp_rust::client::ClientContext;
+        use loxone_mcp_rust::client::binary_protocol::{EventTable, WeatherEntry, WeatherEvent};
+        use loxone_mcp_rust::client::websocket_client::LoxoneWebSocketClient;
+
+        let event = WeatherEvent {
+            uuid: "weather-state".to_string(),
+            last_update: 0,
+            entries: vec![WeatherEntry {
+                timestamp: 3600,
+                weather_type: 1,
+                wind_direction: 180,
+                solar_radiation: 300,
+                relative_humidity: 55,
+                temperature: 18.5,
+                perceived_temperature: 17.5,
+                dew_point: 8.0,
+                precipitation: 0.0,
+                wind_speed: 12.0,
+                barometric_pressure: 1013.0,
+            }],
+        };
+
+        let context = ClientContext::new();
+        let updates = LoxoneWebSocketClient::event_table_to_state_updates(
+            EventTable::Weather(vec![event]),
+            &context,
+        )
+        .await;
+        assert_eq!(updates.len(), 1);
+
+        let events = context.weather_events().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].uuid, "weather-state");
+        assert_eq!(events[0].entries[0].temperature, 18.5);
+        assert!(context.get_state_value("weather-state").await.is_some());
+    }
 }